@@ -0,0 +1,33 @@
+//! The parts of the Melee Stream Tool that don't need the desktop shell:
+//! the start.gg bracket simulator, Slippi replay parsing, the overlay data
+//! model and the tag/code folding every player match goes through.
+//!
+//! The desktop app re-exports these modules under their old paths
+//! (`startgg_sim`, `types`, `replay`, `config`), so other tools, e.g. a web
+//! dashboard for TOs, see the same types the app serves on `/state.json`.
+//!
+//! Replay ordering by start time is here; the replay index itself stays in
+//! the app crate for now, since it is built around the overlay's replay
+//! cache and the configured spectate folder. So do the bracket providers
+//! (start.gg fetching, the linked bracket, picking the sim or the live
+//! bracket), which read the app's config and token. Moving them needs a
+//! config type the core can own first.
+//!
+//! ```
+//! use melee_stream_core::text::{normalize_tag_key, player_key};
+//!
+//! assert_eq!(normalize_tag_key("ＴＳＭ｜Ｌｅｆｆｅｎ"), "leffen");
+//! assert_eq!(player_key("Mang0", Some("mang#0")), "MANG#0");
+//! ```
+
+/// Overlay payload types: one [`overlay::OverlayState`] per setup, plus the
+/// set result snapshots the overlay history records.
+pub mod overlay;
+/// Game Start parsing, live frame decoding, replay ordering and the
+/// character/costume tables.
+pub mod replay;
+/// A deterministic double-elimination bracket that answers like start.gg,
+/// used for rehearsals and tests.
+pub mod startgg_sim;
+/// NFKC folding and the normalized keys players are matched by.
+pub mod text;
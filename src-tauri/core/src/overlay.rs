@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// One player as a setup's overlay shows them, merged from the replay,
+/// start.gg and the player's saved profile.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerState {
+    /// "left" or "right" on screen
+    pub side: String,
+    /// Controller port, 1 to 4, when known from a replay
+    pub port: Option<u8>,
+    pub tag: String,
+    /// ASCII spelling of `tag` for fonts without CJK glyphs; unset when the
+    /// tag is already ASCII
+    #[serde(default)]
+    pub tag_romaji: Option<String>,
+    pub sponsor: Option<String>,
+    /// Social handle shown under the tag, e.g. "@leffen"
+    pub handle: Option<String>,
+    /// Character name, e.g. "Fox"
+    pub character: String,
+    /// Costume name, e.g. "Default" or "Green"
+    pub character_color: String,
+    /// External character ID from the Game Start block
+    pub character_id: Option<u8>,
+    pub costume_id: Option<u8>,
+    /// From the asset pack, when it has art for the character and costume
+    pub portrait_url: Option<String>,
+    pub stock_icon_url: Option<String>,
+    /// Games won in the current set
+    pub score: u32,
+    /// ISO 3166-1 alpha-2
+    pub country_code: Option<String>,
+    /// Slippi connect code, e.g. "MANG#0"
+    pub connect_code: Option<String>,
+    /// Slippi ranked standing, once it's been fetched
+    pub rank: Option<PlayerRank>,
+    #[serde(default)]
+    pub socials: Option<PlayerSocials>,
+    #[serde(default)]
+    pub connection: Option<ConnectionQuality>,
+    /// Stocks left in the game being played; unset between games
+    #[serde(default)]
+    pub stocks: Option<u8>,
+    /// Damage in the game being played
+    #[serde(default)]
+    pub percent: Option<f32>,
+}
+
+/// A player's netplay connection as a ping widget would show it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionQuality {
+    pub ping_ms: u32,
+    pub jitter_ms: u32,
+    pub packet_loss_pct: f32,
+    pub grade: ConnectionGrade,
+    /// Where the sample came from, e.g. "simulated"
+    pub source: String,
+    pub sampled_at_ms: u64,
+}
+
+/// How a connection feels to play on, see [`ConnectionGrade::from_sample`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ConnectionGrade {
+    Good,
+    Fair,
+    Poor,
+}
+
+impl ConnectionGrade {
+    /// Rough netplay thresholds: past ~60ms or any steady loss players
+    /// start to feel it, past ~120ms or 5% loss the game stutters.
+    pub fn from_sample(ping_ms: u32, jitter_ms: u32, packet_loss_pct: f32) -> Self {
+        let effective = ping_ms + jitter_ms;
+        if effective > 120 || packet_loss_pct >= 5.0 {
+            ConnectionGrade::Poor
+        } else if effective > 60 || packet_loss_pct > 0.0 {
+            ConnectionGrade::Fair
+        } else {
+            ConnectionGrade::Good
+        }
+    }
+}
+
+/// A player's social accounts, as usernames without the leading @.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlayerSocials {
+    pub twitter: Option<String>,
+    pub discord: Option<String>,
+    pub twitch: Option<String>,
+}
+
+/// A player's Slippi ranked standing.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRank {
+    pub connect_code: String,
+    pub display_name: Option<String>,
+    /// Slippi's ordinal rating
+    pub rating: f64,
+    /// Rank tier the rating falls in, e.g. "Platinum 2"
+    pub rank: String,
+    pub wins: u32,
+    pub losses: u32,
+    /// Set only for the top players of a season
+    pub global_placement: Option<u32>,
+    pub regional_placement: Option<u32>,
+    pub continent: Option<String>,
+    pub fetched_at_ms: u64,
+}
+
+/// One commentator on the commentary lower third.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CommentaryState {
+    pub name: String,
+    /// Shown under the name, e.g. "@TophSSBM"
+    pub handle: Option<String>,
+    /// Whether they're on the mic right now; unset when nobody tracks it
+    pub active: Option<bool>,
+    /// Without the leading @
+    #[serde(default)]
+    pub twitter: Option<String>,
+    #[serde(default)]
+    pub pronouns: Option<String>,
+}
+
+/// The set and game a setup is showing.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchMeta {
+    pub tournament: Option<String>,
+    /// e.g. "Winners Round 1"
+    pub round: String,
+    pub best_of: u8,
+    /// 1-based game of the set being played
+    pub game_number: Option<u32>,
+    /// The start.gg (or simulated) set, when the setup is on one
+    pub set_id: Option<u64>,
+    /// "pending", "inProgress" or "completed"
+    pub set_state: Option<String>,
+    /// Stage name from [`crate::replay::map_stage`]
+    pub stage: Option<String>,
+    pub stage_image_url: Option<String>,
+    /// Free text from the operator
+    pub notes: Option<String>,
+    /// The setup's set is over and it's showing the break screen
+    #[serde(default)]
+    pub on_break: bool,
+}
+
+/// Everything one setup's overlay draws.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct OverlayState {
+    /// The player on the left; `p2` is on the right
+    pub p1: PlayerState,
+    pub p2: PlayerState,
+    pub meta: MatchMeta,
+    pub commentators: Vec<CommentaryState>,
+}
+
+/// The body of `/overlay/state.json`: every setup's overlay.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AllSetupsState {
+    /// Setup 1 first
+    pub setups: Vec<OverlayState>,
+    /// Zone for any wall-clock time the overlay shows
+    #[serde(default)]
+    pub timezone: Option<TimeZoneInfo>,
+}
+
+/// One side of a finished set.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetResultPlayer {
+    pub tag: String,
+    pub connect_code: Option<String>,
+    /// Games won
+    pub score: u32,
+    /// Characters they played, in order, with one entry per switch
+    pub characters: Vec<String>,
+}
+
+/// A set as it ended on a setup's overlay, kept for the overlay history and
+/// result cards.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetResultSnapshot {
+    pub setup_id: u32,
+    pub set_id: u64,
+    pub tournament: Option<String>,
+    pub round: String,
+    pub best_of: u8,
+    pub p1: SetResultPlayer,
+    pub p2: SetResultPlayer,
+    /// The winner's side, "left" or "right"; unset on a tied score
+    pub winner: Option<String>,
+    pub started_at_ms: Option<u64>,
+    pub completed_at_ms: Option<u64>,
+    /// The two times above in venue time (RFC 3339)
+    pub started_at_local: Option<String>,
+    pub completed_at_local: Option<String>,
+    pub duration_ms: Option<u64>,
+    /// Links to the set's replays once they're uploaded
+    pub replay_urls: Vec<String>,
+}
+
+/// The zone timestamps are shown in, sent alongside them so overlays and
+/// exports don't have to guess.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeZoneInfo {
+    /// IANA name, or "Local" when no venue timezone is configured
+    pub name: String,
+    /// Offset from UTC at the time the info was built
+    pub utc_offset_minutes: i32,
+    /// e.g. "EST"; the numeric offset where the zone has no abbreviation
+    pub abbreviation: String,
+    /// False when the configured zone is unknown and the machine's zone is
+    /// used instead
+    pub valid: bool,
+}
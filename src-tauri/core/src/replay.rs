@@ -0,0 +1,595 @@
+use crate::text::{ascii_tag, decode_netplay_name};
+use peppi::{game::Port, io::slippi};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// One player from a replay's Game Start block.
+#[derive(Debug, Clone)]
+pub struct ParsedPlayerInfo {
+    pub port: u8,
+    pub tag: Option<String>,
+    /// ASCII spelling of a non-ASCII tag, see [`ascii_tag`].
+    pub tag_romaji: Option<String>,
+    pub code: Option<String>,
+    pub character: Option<String>,
+    pub color: Option<String>,
+    pub character_id: Option<u8>,
+    pub costume_id: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedGameInfo {
+    pub players: Vec<ParsedPlayerInfo>,
+    /// External stage ID from the Game Start block
+    pub stage_id: u16,
+    /// Name from [`map_stage`]; None off the tournament stages
+    pub stage: Option<String>,
+}
+
+/// Parsed Game Start, keyed to the file's mtime so a cache can tell when
+/// the replay was rewritten.
+#[derive(Debug, Clone)]
+pub struct ParsedReplay {
+    pub info: ParsedGameInfo,
+    pub modified: SystemTime,
+}
+
+/// Connect codes found by pattern-matching raw replay bytes. Only sees
+/// ASCII codes, and can pick up text that merely looks like one; prefer
+/// the netplay block from [`parse_game_start`] when the replay has it.
+pub fn extract_connect_codes(bytes: &[u8]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'#' {
+            let mut start = i;
+            while start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
+                start -= 1;
+            }
+            let mut end = i + 1;
+            while end < bytes.len() && bytes[end].is_ascii_digit() {
+                end += 1;
+            }
+            let left_len = i - start;
+            let right_len = end.saturating_sub(i + 1);
+            if (2..=12).contains(&left_len) && (3..=4).contains(&right_len) {
+                let left = &bytes[start..i];
+                let right = &bytes[i + 1..end];
+                if left.iter().all(|b| b.is_ascii_alphanumeric()) && right.iter().all(|b| b.is_ascii_digit()) {
+                    let code = format!(
+                        "{}#{}",
+                        String::from_utf8_lossy(left),
+                        String::from_utf8_lossy(right)
+                    );
+                    out.push(code);
+                }
+            }
+            i = end;
+        } else {
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Fallback tag for a player known only by connect code: "MANG#0" -> "MANG".
+pub fn tag_from_code(code: &str) -> String {
+    code.split('#').next().unwrap_or(code).to_string()
+}
+
+/// Character name for an external ID, as used in the Game Start block.
+pub fn map_character(id: u8) -> Option<&'static str> {
+    match id {
+        0x00 => Some("Captain Falcon"),
+        0x01 => Some("Donkey Kong"),
+        0x02 => Some("Fox"),
+        0x03 => Some("Mr Game & Watch"),
+        0x04 => Some("Kirby"),
+        0x05 => Some("Bowser"),
+        0x06 => Some("Link"),
+        0x07 => Some("Luigi"),
+        0x08 => Some("Mario"),
+        0x09 => Some("Marth"),
+        0x0A => Some("Mewtwo"),
+        0x0B => Some("Ness"),
+        0x0C => Some("Peach"),
+        0x0D => Some("Pikachu"),
+        0x0E => Some("Ice Climbers"),
+        0x0F => Some("Jigglypuff"),
+        0x10 => Some("Samus"),
+        0x11 => Some("Yoshi"),
+        0x12 => Some("Zelda"),
+        0x13 => Some("Sheik"),
+        0x14 => Some("Falco"),
+        0x15 => Some("Young Link"),
+        0x16 => Some("Dr Mario"),
+        0x17 => Some("Roy"),
+        0x18 => Some("Pichu"),
+        0x19 => Some("Ganondorf"),
+        _ => None,
+    }
+}
+
+/// Maps an in-game (internal) character ID, as used in frame data, to a name.
+/// Unlike the external IDs in the game start block, the Ice Climbers are two
+/// separate characters here (Popo leads, Nana follows) and Sheik/Zelda show
+/// whichever form the player is currently in.
+pub fn map_internal_character(id: u8) -> Option<&'static str> {
+    match id {
+        0x00 => Some("Mario"),
+        0x01 => Some("Fox"),
+        0x02 => Some("Captain Falcon"),
+        0x03 => Some("Donkey Kong"),
+        0x04 => Some("Kirby"),
+        0x05 => Some("Bowser"),
+        0x06 => Some("Link"),
+        0x07 => Some("Sheik"),
+        0x08 => Some("Ness"),
+        0x09 => Some("Peach"),
+        0x0A => Some("Popo"),
+        0x0B => Some("Nana"),
+        0x0C => Some("Pikachu"),
+        0x0D => Some("Samus"),
+        0x0E => Some("Yoshi"),
+        0x0F => Some("Jigglypuff"),
+        0x10 => Some("Mewtwo"),
+        0x11 => Some("Luigi"),
+        0x12 => Some("Marth"),
+        0x13 => Some("Zelda"),
+        0x14 => Some("Young Link"),
+        0x15 => Some("Dr Mario"),
+        0x16 => Some("Falco"),
+        0x17 => Some("Pichu"),
+        0x18 => Some("Mr Game & Watch"),
+        0x19 => Some("Ganondorf"),
+        0x1A => Some("Roy"),
+        _ => None,
+    }
+}
+
+/// Maps an external stage ID from the game start block to the stage's name
+/// as used for stage images. Only the stages played in tournaments.
+pub fn map_stage(id: u16) -> Option<&'static str> {
+    match id {
+        2 => Some("Fountain of Dreams"),
+        3 => Some("Pokemon Stadium"),
+        8 => Some("Yoshi's Story"),
+        28 => Some("Dream Land N64"),
+        31 => Some("Battlefield"),
+        32 => Some("Final Destination"),
+        _ => None,
+    }
+}
+
+/// Costume names in in-game costume ID order. Names match the files in
+/// overlay/resources/characters/{portraits,stock_icons}. Popo and Nana share
+/// the Ice Climbers table (the costume is picked for the pair), and Sheik and
+/// Zelda share one table so a mid-game transform keeps the same color.
+pub fn costume_names(char_name: &str) -> &'static [&'static str] {
+    match char_name {
+        "Captain Falcon" => &["Default", "Black", "Red", "White", "Green", "Blue"],
+        "Donkey Kong" => &["Default", "Purple", "Red", "Blue", "Green"],
+        "Fox" | "Falco" => &["Default", "Red", "Blue", "Green"],
+        "Mr Game & Watch" => &["Default", "Red", "Blue", "Green"],
+        "Kirby" => &["Default", "Yellow", "Blue", "Red", "Green", "White"],
+        "Bowser" => &["Default", "Red", "Blue", "Black"],
+        "Link" => &["Default", "Red", "Blue", "Black", "White"],
+        "Luigi" => &["Default", "White", "Blue", "Pink"],
+        "Mario" => &["Default", "Yellow", "Brown", "Blue", "Green"],
+        "Marth" => &["Default", "Red", "Green", "Black", "White"],
+        "Mewtwo" => &["Default", "Yellow", "Blue", "Green"],
+        "Ness" => &["Default", "Yellow", "Blue", "Green"],
+        "Peach" => &["Default", "Yellow", "White", "Blue", "Green"],
+        "Pikachu" => &["Default", "Red", "Blue", "Green"],
+        "Ice Climbers" | "Popo" | "Nana" => &["Default", "Green", "Orange", "Red"],
+        "Jigglypuff" => &["Default", "Red", "Blue", "Green", "Yellow"],
+        "Samus" => &["Default", "Pink", "Brown", "Green", "Purple"],
+        "Yoshi" => &["Default", "Red", "Blue", "Yellow", "Pink", "Cyan"],
+        "Zelda" | "Sheik" => &["Default", "Red", "Blue", "Green", "Purple"],
+        "Young Link" => &["Default", "Red", "Blue", "White", "Black"],
+        "Dr Mario" => &["Default", "Red", "Blue", "Green", "Black"],
+        "Roy" => &["Default", "Red", "Blue", "Green", "Yellow"],
+        "Pichu" => &["Default", "Red", "Blue", "Green"],
+        "Ganondorf" => &["Default", "Red", "Blue", "Green", "Purple"],
+        _ => &["Default"],
+    }
+}
+
+pub fn map_color(char_name: &str, costume: u8) -> &'static str {
+    costume_names(char_name)
+        .get(costume as usize)
+        .copied()
+        .unwrap_or("Default")
+}
+
+/// Players and stage from a replay's Game Start block, without reading frames. None
+/// if the file isn't a readable replay or names no known characters.
+pub fn parse_game_start(path: &Path) -> Option<ParsedGameInfo> {
+    let file = fs::File::open(path).ok()?;
+    let mut reader = BufReader::new(file);
+    if slippi::de::parse_header(&mut reader, None).is_err() {
+        return None;
+    }
+
+    let mut opts = slippi::de::Opts::default();
+    opts.skip_frames = true;
+    let state = slippi::de::parse_start(&mut reader, Some(&opts)).ok()?;
+    let start = state.start();
+    let mut players = Vec::new();
+
+    for pl in start.players.iter() {
+        let name = match map_character(pl.character) {
+            Some(n) => n.to_string(),
+            None => continue,
+        };
+        let color = map_color(&name, pl.costume).to_string();
+        let netplay = pl.netplay.as_ref().map(|n| (decode_netplay_name(&n.name.0), n.code.0.clone()));
+        let tag = netplay
+            .as_ref()
+            .map(|(n, _)| n.clone())
+            .or_else(|| pl.name_tag.as_ref().map(|s| s.0.clone()));
+        let code = netplay.as_ref().map(|(_, c)| c.clone());
+        let tag_romaji = tag.as_deref().and_then(|t| ascii_tag(t, code.as_deref()));
+        let port = match pl.port {
+            Port::P1 => 1,
+            Port::P2 => 2,
+            Port::P3 => 3,
+            Port::P4 => 4,
+        };
+
+        players.push(ParsedPlayerInfo {
+            port,
+            tag,
+            tag_romaji,
+            code,
+            character: Some(name),
+            color: Some(color),
+            character_id: Some(pl.character),
+            costume_id: Some(pl.costume),
+        });
+    }
+
+    if players.is_empty() {
+        return None;
+    }
+    Some(ParsedGameInfo { players, stage_id: start.stage, stage: map_stage(start.stage).map(str::to_string) })
+}
+
+/// Where the raw event stream starts: `{U\x03raw[$U#l` and the 4-byte
+/// length, which stays 0 until the game is over.
+pub const RAW_EVENTS_OFFSET: u64 = 15;
+const EVENT_PAYLOADS: u8 = 0x35;
+const GAME_START: u8 = 0x36;
+const POST_FRAME_UPDATE: u8 = 0x38;
+const GAME_END: u8 = 0x39;
+const FRAME_BOOKEND: u8 = 0x3C;
+/// Post-frame updates are at least this long, command byte included, in
+/// every replay version that records stocks.
+const POST_FRAME_MIN_LEN: usize = 0x22;
+
+/// A player's latest post-frame update.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LivePlayerFrame {
+    pub frame: i32,
+    pub stocks: u8,
+    pub percent: f32,
+}
+
+/// Frame data decoded so far from a replay that's still being written.
+///
+/// peppi's parser reads whole events from a reader and can't pick up again
+/// partway through one, so a growing file is read here straight from its
+/// raw event stream: each call to [`LiveReplay::feed`] takes the bytes
+/// appended since `offset` and decodes the events that are complete. The
+/// overlay HUD reads players' stocks and percent from it, and real-time
+/// playback how far the game has got.
+#[derive(Debug, Clone)]
+pub struct LiveReplay {
+    /// Bytes of the file decoded so far
+    pub offset: u64,
+    /// Set by the Game Start event; Dolphin can't open a replay before it
+    pub started: bool,
+    /// Set by the Game End event, or when the stream can't be read further
+    pub ended: bool,
+    /// The stream had an event it never declared, so nothing after it
+    /// could be read
+    pub unreadable: bool,
+    /// Newest frame with a post-frame update or bookend
+    pub latest_frame: Option<i32>,
+    /// File length at the last [`LiveReplay::read_from`], decoded or not
+    seen: u64,
+    payload_sizes: HashMap<u8, usize>,
+    /// Indexed by port - 1
+    players: [Option<LivePlayerFrame>; 4],
+}
+
+impl Default for LiveReplay {
+    fn default() -> Self {
+        Self {
+            offset: RAW_EVENTS_OFFSET,
+            started: false,
+            ended: false,
+            unreadable: false,
+            latest_frame: None,
+            seen: 0,
+            payload_sizes: HashMap::new(),
+            players: [None; 4],
+        }
+    }
+}
+
+impl LiveReplay {
+    /// The latest frame of the player on `port` (1-4).
+    pub fn player(&self, port: u8) -> Option<LivePlayerFrame> {
+        let idx = usize::from(port).checked_sub(1)?;
+        self.players.get(idx).copied().flatten()
+    }
+
+    /// Decode whatever has been appended to `path` since the last read.
+    /// Starts over when the file got shorter, e.g. a replay rewritten from
+    /// the start. Returns whether the file grew, even if only by part of an
+    /// event.
+    pub fn read_from(&mut self, path: &Path) -> std::io::Result<bool> {
+        let mut file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        if len < self.seen {
+            *self = LiveReplay::default();
+        }
+        let grew = len > self.seen;
+        self.seen = len;
+        if self.ended || len <= self.offset {
+            return Ok(grew);
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut bytes)?;
+        self.feed(&bytes);
+        Ok(grew)
+    }
+
+    /// Decode the complete events in `bytes`, which follow on from `offset`.
+    /// A trailing partial event is left for the next call.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let mut pos = 0;
+        while !self.ended && pos < bytes.len() {
+            let command = bytes[pos];
+            let body_len = if self.payload_sizes.is_empty() {
+                if command != EVENT_PAYLOADS {
+                    self.ended = true;
+                    self.unreadable = true;
+                    break;
+                }
+                match bytes.get(pos + 1) {
+                    Some(len) => usize::from(*len),
+                    None => break,
+                }
+            } else {
+                match self.payload_sizes.get(&command) {
+                    Some(len) => *len,
+                    None => {
+                        // Not a command the replay declared; nothing after
+                        // this can be framed.
+                        self.ended = true;
+                        self.unreadable = true;
+                        break;
+                    }
+                }
+            };
+            let Some(event) = bytes.get(pos..pos + 1 + body_len) else {
+                break;
+            };
+            match command {
+                EVENT_PAYLOADS if self.payload_sizes.is_empty() => {
+                    for entry in event[2..].chunks_exact(3) {
+                        self.payload_sizes.insert(entry[0], usize::from(u16::from_be_bytes([entry[1], entry[2]])));
+                    }
+                }
+                GAME_START => self.started = true,
+                POST_FRAME_UPDATE => {
+                    self.frame_seen(event);
+                    self.post_frame(event);
+                }
+                FRAME_BOOKEND => self.frame_seen(event),
+                GAME_END => self.ended = true,
+                _ => {}
+            }
+            pos += event.len();
+        }
+        self.offset += pos as u64;
+    }
+
+    fn frame_seen(&mut self, event: &[u8]) {
+        if let Some(frame) = event.get(1..5) {
+            let frame = i32::from_be_bytes([frame[0], frame[1], frame[2], frame[3]]);
+            self.latest_frame = Some(self.latest_frame.map_or(frame, |latest| latest.max(frame)));
+        }
+    }
+
+    fn post_frame(&mut self, event: &[u8]) {
+        // Nana's updates would overwrite Popo's; the HUD follows the leader.
+        if event.len() < POST_FRAME_MIN_LEN || event[0x6] != 0 {
+            return;
+        }
+        let Some(slot) = self.players.get_mut(usize::from(event[0x5])) else {
+            return;
+        };
+        *slot = Some(LivePlayerFrame {
+            frame: i32::from_be_bytes([event[0x1], event[0x2], event[0x3], event[0x4]]),
+            stocks: event[0x21],
+            percent: f32::from_be_bytes([event[0x16], event[0x17], event[0x18], event[0x19]]),
+        });
+    }
+}
+
+/// Epoch values below this are seconds: 1e11 seconds is the year 5138,
+/// while 1e11 ms is still 1973.
+const SECONDS_CUTOFF: i64 = 100_000_000_000;
+
+/// Epoch milliseconds from a timestamp in either seconds or milliseconds.
+pub fn normalize_timestamp_ms(value: i64) -> i64 {
+    if value.abs() < SECONDS_CUTOFF {
+        value.saturating_mul(1000)
+    } else {
+        value
+    }
+}
+
+/// A replay metadata timestamp: RFC 3339, a naive UTC date-time, or epoch
+/// seconds or milliseconds.
+pub fn parse_metadata_timestamp_ms(value: &Value) -> Option<i64> {
+    match value {
+        Value::String(raw) => {
+            if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
+                return Some(parsed.timestamp_millis());
+            }
+            if let Ok(parsed) = raw.parse::<i64>() {
+                return Some(normalize_timestamp_ms(parsed));
+            }
+            for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
+                if let Ok(parsed) = NaiveDateTime::parse_from_str(raw, fmt) {
+                    return Some(DateTime::<Utc>::from_naive_utc_and_offset(parsed, Utc).timestamp_millis());
+                }
+            }
+            None
+        }
+        Value::Number(num) => num.as_i64().map(normalize_timestamp_ms),
+        _ => None,
+    }
+}
+
+/// When a replay's game started, from its metadata.
+pub fn replay_metadata_timestamp_ms(path: &Path) -> Option<i64> {
+    let file = fs::File::open(path).ok()?;
+    let mut opts = slippi::de::Opts::default();
+    opts.skip_frames = true;
+    let game = slippi::de::read(file, Some(&opts)).ok()?;
+    let metadata = game.metadata?;
+    for key in ["startAt", "playedOn", "startTime", "date"] {
+        if let Some(value) = metadata.get(key) {
+            if let Some(timestamp) = parse_metadata_timestamp_ms(value) {
+                return Some(timestamp);
+            }
+        }
+    }
+    None
+}
+
+pub fn replay_modified_timestamp_ms(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let duration = modified.duration_since(UNIX_EPOCH).ok()?;
+    Some(duration.as_millis() as i64)
+}
+
+/// Replays in the order they were played: by metadata start time, then
+/// file mtime; files with neither keep their place at the end.
+pub fn sort_replay_paths_by_start_time(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut entries: Vec<(i64, usize, PathBuf)> = paths
+        .into_iter()
+        .enumerate()
+        .map(|(idx, path)| {
+            let key = replay_metadata_timestamp_ms(&path)
+                .or_else(|| replay_modified_timestamp_ms(&path))
+                .unwrap_or(i64::MAX);
+            (key, idx, path)
+        })
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
+    entries.into_iter().map(|(_, _, path)| path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post_frame(frame: i32, player: u8, follower: bool, stocks: u8, percent: f32) -> Vec<u8> {
+        let mut event = vec![0u8; POST_FRAME_MIN_LEN];
+        event[0] = POST_FRAME_UPDATE;
+        event[0x1..0x5].copy_from_slice(&frame.to_be_bytes());
+        event[0x5] = player;
+        event[0x6] = u8::from(follower);
+        event[0x16..0x1A].copy_from_slice(&percent.to_be_bytes());
+        event[0x21] = stocks;
+        event
+    }
+
+    #[test]
+    fn live_replay_decodes_across_partial_reads() {
+        let post_len = (POST_FRAME_MIN_LEN - 1) as u8;
+        let mut bytes = vec![EVENT_PAYLOADS, 7, POST_FRAME_UPDATE, 0, post_len, GAME_END, 0, 1];
+        bytes.extend(post_frame(100, 0, false, 4, 12.5));
+        bytes.extend(post_frame(100, 1, false, 3, 40.0));
+        bytes.extend(post_frame(100, 1, true, 3, 99.0));
+        bytes.extend(post_frame(101, 0, false, 4, 27.0));
+
+        let mut live = LiveReplay::default();
+        let split = bytes.len() - 10;
+        live.feed(&bytes[..split]);
+        assert_eq!(live.player(1).map(|p| p.percent), Some(12.5));
+        assert_eq!(live.player(2).map(|p| (p.stocks, p.percent)), Some((3, 40.0)));
+        assert_eq!(live.offset, RAW_EVENTS_OFFSET + (bytes.len() - POST_FRAME_MIN_LEN) as u64);
+
+        let read = (live.offset - RAW_EVENTS_OFFSET) as usize;
+        live.feed(&bytes[read..]);
+        assert_eq!(live.player(1), Some(LivePlayerFrame { frame: 101, stocks: 4, percent: 27.0 }));
+        assert!(!live.ended);
+        live.feed(&[GAME_END, 2]);
+        assert!(live.ended);
+        assert_eq!(live.player(3), None);
+    }
+
+    #[test]
+    fn live_replay_tracks_the_game_from_a_growing_file() {
+        let post_len = (POST_FRAME_MIN_LEN - 1) as u8;
+        let mut bytes = b"{U\x03raw[$U#l\0\0\0\0".to_vec();
+        bytes.extend([EVENT_PAYLOADS, 13, GAME_START, 0, 4, POST_FRAME_UPDATE, 0, post_len]);
+        bytes.extend([FRAME_BOOKEND, 0, 8, GAME_END, 0, 1]);
+        bytes.extend([GAME_START, 0, 0, 0, 0]);
+        let dir = std::env::temp_dir().join(format!("live-replay-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Game.slp");
+        fs::write(&path, &bytes).unwrap();
+
+        let mut live = LiveReplay::default();
+        assert!(live.read_from(&path).unwrap());
+        assert!(live.started);
+        assert_eq!(live.latest_frame, None);
+        assert!(!live.read_from(&path).unwrap());
+
+        bytes.extend(post_frame(-123, 0, false, 4, 0.0));
+        bytes.push(FRAME_BOOKEND);
+        bytes.extend((-122i32).to_be_bytes());
+        bytes.extend([0, 0, 0, 0, GAME_END]);
+        fs::write(&path, &bytes).unwrap();
+        assert!(live.read_from(&path).unwrap());
+        assert_eq!(live.latest_frame, Some(-122));
+        assert_eq!(live.player(1).map(|p| p.frame), Some(-123));
+        assert!(!live.ended);
+
+        bytes.push(2);
+        fs::write(&path, &bytes).unwrap();
+        assert!(live.read_from(&path).unwrap());
+        assert!(live.ended && !live.unreadable);
+
+        // A replay written again from the start is read from the top.
+        fs::write(&path, &bytes[..34]).unwrap();
+        live.read_from(&path).unwrap();
+        assert!(live.started && !live.ended);
+        assert_eq!(live.latest_frame, None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn undeclared_events_make_the_stream_unreadable() {
+        let mut live = LiveReplay::default();
+        live.feed(&[EVENT_PAYLOADS, 4, GAME_START, 0, 4, 0x99, 1, 2]);
+        assert!(live.ended && live.unreadable);
+        assert_eq!(live.offset, RAW_EVENTS_OFFSET + 5);
+    }
+}
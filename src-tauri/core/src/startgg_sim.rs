@@ -1,10 +1,12 @@
+use crate::text::fold_unicode;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::collections::{HashMap, HashSet};
+use specta::Type;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimEventConfig {
   pub id: String,
@@ -12,7 +14,7 @@ pub struct StartggSimEventConfig {
   pub slug: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimPhaseConfig {
   pub id: String,
@@ -20,7 +22,7 @@ pub struct StartggSimPhaseConfig {
   pub best_of: u8,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimEntrantConfig {
   pub id: u32,
@@ -29,7 +31,7 @@ pub struct StartggSimEntrantConfig {
   pub seed: Option<u32>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase", default)]
 pub struct StartggSimSimulationConfig {
   pub time_scale: f64,
@@ -55,35 +57,35 @@ impl Default for StartggSimSimulationConfig {
   }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceScore {
   pub value: Option<i32>,
   pub label: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceStats {
   #[serde(default)]
   pub score: Option<StartggReferenceScore>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceStanding {
   #[serde(default)]
   pub stats: Option<StartggReferenceStats>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceEntrant {
   pub id: Option<u32>,
   pub name: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceSlot {
   #[serde(default)]
@@ -98,7 +100,7 @@ pub struct StartggReferenceSlot {
   pub prereq_placement: Option<u32>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+#[derive(Clone, Debug, Serialize, Deserialize, Default, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggReferenceSet {
   pub id: Option<u64>,
@@ -110,7 +112,7 @@ pub struct StartggReferenceSet {
   pub slots: Vec<StartggReferenceSlot>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimConfig {
   pub event: StartggSimEventConfig,
@@ -123,7 +125,7 @@ pub struct StartggSimConfig {
   pub reference_sets: Vec<StartggReferenceSet>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimEntrant {
   pub id: u32,
@@ -132,7 +134,7 @@ pub struct StartggSimEntrant {
   pub slippi_code: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimSlot {
   pub entrant_id: Option<u32>,
@@ -146,10 +148,34 @@ pub struct StartggSimSlot {
   pub source_label: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// Set ids for start.gg's non-numeric `preview_...` ids start here: above
+/// any id start.gg assigns, and below 2^53 so they survive a trip through JS.
+pub const PREVIEW_SET_ID_BASE: u64 = 1 << 52;
+
+/// The id a non-numeric start.gg set id is known by: an FNV-1a hash of it,
+/// at or above `PREVIEW_SET_ID_BASE`. startggAdapter.ts hashes the same way.
+pub fn preview_set_id(raw: &str) -> u64 {
+  let hash = raw
+    .bytes()
+    .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3));
+  PREVIEW_SET_ID_BASE | (hash & (PREVIEW_SET_ID_BASE - 1))
+}
+
+pub fn is_preview_set_id(id: u64) -> bool {
+  id >= PREVIEW_SET_ID_BASE
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimSet {
   pub id: u64,
+  /// start.gg's id when it isn't a number, as for sets of a phase that
+  /// hasn't started; `id` is then `preview_set_id` of it
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub startgg_id: Option<String>,
+  /// The preview id this set went by before its phase started
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub preview_id: Option<u64>,
   pub phase_id: String,
   pub phase_name: String,
   pub round: i32,
@@ -163,7 +189,7 @@ pub struct StartggSimSet {
   pub slots: Vec<StartggSimSlot>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSimState {
   pub event: StartggSimEventConfig,
@@ -173,6 +199,228 @@ pub struct StartggSimState {
   pub started_at_ms: u64,
   pub now_ms: u64,
   pub reference_tournament_link: Option<String>,
+  /// Bumped whenever any set changes; 0 for live start.gg state
+  pub version: u64,
+  /// True when nothing changed after `since_ms`; sets and entrants are empty
+  #[serde(skip_serializing_if = "std::ops::Not::not")]
+  pub not_modified: bool,
+  /// True while the sim is still loading; everything else is empty
+  #[serde(skip_serializing_if = "std::ops::Not::not")]
+  pub warming: bool,
+}
+
+impl StartggSimState {
+  /// Placeholder for polls that arrive while the sim loads in the background.
+  pub fn warming(now_ms: u64) -> Self {
+    StartggSimState {
+      event: StartggSimEventConfig {
+        id: String::new(),
+        name: "Loading bracket".to_string(),
+        slug: String::new(),
+      },
+      phases: Vec::new(),
+      entrants: Vec::new(),
+      sets: Vec::new(),
+      started_at_ms: now_ms,
+      now_ms,
+      reference_tournament_link: None,
+      version: 0,
+      not_modified: false,
+      warming: true,
+    }
+  }
+
+  /// A poll's answer from a published full state: with `since_ms`, only
+  /// the sets updated after it and no entrants, or `not_modified` when
+  /// nothing was. Only the sets returned are cloned.
+  pub fn since(&self, since_ms: Option<u64>) -> StartggSimState {
+    let Some(since) = since_ms.filter(|since| *since > 0) else {
+      return self.clone();
+    };
+    let last_changed_ms = self.sets.iter().map(|set| set.updated_at_ms).max().unwrap_or(0);
+    let not_modified = since >= last_changed_ms;
+    let sets = if not_modified {
+      Vec::new()
+    } else {
+      self.sets.iter().filter(|set| set.updated_at_ms > since).cloned().collect()
+    };
+    StartggSimState {
+      event: self.event.clone(),
+      phases: self.phases.clone(),
+      entrants: Vec::new(),
+      sets,
+      started_at_ms: self.started_at_ms,
+      now_ms: self.now_ms,
+      reference_tournament_link: self.reference_tournament_link.clone(),
+      version: self.version,
+      not_modified,
+      warming: self.warming,
+    }
+  }
+
+  /// The state in start.gg's GraphQL response shape, limited to `filter`;
+  /// the sets pageInfo describes the filtered total and the requested page.
+  pub fn to_raw(&self, filter: Option<&StartggSimSetFilter>) -> Value {
+    let Some(filter) = filter else {
+      return startgg_state_to_raw(self, self.now_ms);
+    };
+    let mut state = self.clone();
+    let total = filter.apply(&mut state.sets);
+    let mut raw = startgg_state_to_raw(&state, state.now_ms);
+    if let Some(page_info) = raw.pointer_mut("/data/event/sets/pageInfo") {
+      *page_info = filter.page_info(total);
+    }
+    raw
+  }
+}
+
+/// What a set's slot is filled from, comparable between a preview set and
+/// the real one: the entrant, a bye, or another set's winner or loser.
+/// None while the source set is a preview that hasn't been matched yet.
+fn slot_origin(slot: &StartggSimSlot, matched: &HashMap<u64, u64>) -> Option<String> {
+  if let Some(entrant_id) = slot.entrant_id {
+    return Some(format!("entrant:{entrant_id}"));
+  }
+  let source_type = slot.source_type.as_deref()?;
+  match slot.source_set_id {
+    Some(id) if is_preview_set_id(id) => matched.get(&id).map(|real| format!("{source_type}:{real}")),
+    Some(id) => Some(format!("{source_type}:{id}")),
+    None => Some(source_type.to_string()),
+  }
+}
+
+fn set_origin(set: &StartggSimSet, matched: &HashMap<u64, u64>) -> Option<(String, i32, Vec<String>)> {
+  let mut slots = set
+    .slots
+    .iter()
+    .map(|slot| slot_origin(slot, matched))
+    .collect::<Option<Vec<_>>>()?;
+  slots.sort();
+  Some((set.phase_id.clone(), set.round, slots))
+}
+
+/// Record on each set of `next` the preview id it had in `previous`, the
+/// last state fetched, once start.gg has replaced its preview sets with
+/// real ones. Sets match on phase, round and what fills their slots, so
+/// later rounds match once the sets feeding them have.
+pub fn reconcile_preview_sets(previous: &StartggSimState, next: &mut StartggSimState) {
+  let carried = previous
+    .sets
+    .iter()
+    .filter_map(|set| Some((set.id, set.preview_id?)))
+    .collect::<HashMap<_, _>>();
+  for set in next.sets.iter_mut().filter(|set| set.preview_id.is_none()) {
+    set.preview_id = carried.get(&set.id).copied();
+  }
+
+  let next_ids = next.sets.iter().map(|set| set.id).collect::<HashSet<_>>();
+  let mut previews = previous
+    .sets
+    .iter()
+    .filter(|set| set.startgg_id.is_some() && !next_ids.contains(&set.id))
+    .collect::<Vec<_>>();
+  let mut matched: HashMap<u64, u64> = HashMap::new();
+  loop {
+    let mut candidates: HashMap<(String, i32, Vec<String>), Vec<u64>> = HashMap::new();
+    for set in next.sets.iter().filter(|set| set.startgg_id.is_none() && set.preview_id.is_none()) {
+      if matched.values().any(|real| *real == set.id) {
+        continue;
+      }
+      if let Some(origin) = set_origin(set, &matched) {
+        candidates.entry(origin).or_default().push(set.id);
+      }
+    }
+    let before = matched.len();
+    previews.retain(|preview| {
+      // Only an unambiguous match counts, and each real set is taken once.
+      let real = set_origin(preview, &matched)
+        .filter(|origin| candidates.get(origin).is_some_and(|ids| ids.len() == 1))
+        .and_then(|origin| candidates.remove(&origin))
+        .map(|ids| ids[0]);
+      match real {
+        Some(real) => {
+          matched.insert(preview.id, real);
+          false
+        }
+        None => true,
+      }
+    });
+    if matched.len() == before || previews.is_empty() {
+      break;
+    }
+  }
+
+  let by_real = matched.into_iter().map(|(preview, real)| (real, preview)).collect::<HashMap<_, _>>();
+  for set in next.sets.iter_mut() {
+    if let Some(preview) = by_real.get(&set.id) {
+      set.preview_id = Some(*preview);
+    }
+  }
+}
+
+/// Optional set filters for state polls. Every field narrows the result;
+/// `limit`/`offset` page through whatever matched.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StartggSimSetFilter {
+  /// Set states to keep ("pending", "inProgress", "completed", "skipped").
+  /// "callable" matches pending sets with both entrants known.
+  pub states: Option<Vec<String>>,
+  pub phase_id: Option<String>,
+  pub min_round: Option<i32>,
+  pub max_round: Option<i32>,
+  pub entrant_id: Option<u32>,
+  pub limit: Option<usize>,
+  pub offset: Option<usize>,
+}
+
+impl StartggSimSetFilter {
+  pub fn matches(&self, set: &StartggSimSet) -> bool {
+    if let Some(states) = self.states.as_ref() {
+      let callable = set.state == "pending" && set.slots.iter().all(|slot| slot.entrant_id.is_some());
+      let state_match = states
+        .iter()
+        .any(|state| *state == set.state || (state == "callable" && callable));
+      if !state_match {
+        return false;
+      }
+    }
+    if self.phase_id.as_ref().is_some_and(|phase_id| *phase_id != set.phase_id) {
+      return false;
+    }
+    if self.min_round.is_some_and(|min| set.round < min) {
+      return false;
+    }
+    if self.max_round.is_some_and(|max| set.round > max) {
+      return false;
+    }
+    if let Some(entrant_id) = self.entrant_id {
+      if !set.slots.iter().any(|slot| slot.entrant_id == Some(entrant_id)) {
+        return false;
+      }
+    }
+    true
+  }
+
+  /// Keep the matching page of `sets`. Returns how many sets matched
+  /// before paging.
+  pub fn apply(&self, sets: &mut Vec<StartggSimSet>) -> usize {
+    sets.retain(|set| self.matches(set));
+    let total = sets.len();
+    let offset = self.offset.unwrap_or(0).min(total);
+    sets.drain(..offset);
+    if let Some(limit) = self.limit {
+      sets.truncate(limit);
+    }
+    total
+  }
+
+  fn page_info(&self, total: usize) -> Value {
+    let per_page = self.limit.filter(|limit| *limit > 0).unwrap_or(total.max(1));
+    let page = self.offset.unwrap_or(0) / per_page + 1;
+    let total_pages = total.div_ceil(per_page).max(1);
+    json!({ "total": total, "totalPages": total_pages, "page": page, "perPage": per_page })
+  }
 }
 
 // ── Persistence types ───────────────────────────────────────────────────
@@ -268,6 +516,41 @@ struct SimSet {
   end_at_ms: Option<u64>,
 }
 
+/// Everything in a SimSet that shows up in its snapshot and can change
+/// after the bracket is built.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SetCacheKey {
+  state: SimSetState,
+  started_at_ms: Option<u64>,
+  completed_at_ms: Option<u64>,
+  updated_at_ms: u64,
+  winner_slot: Option<usize>,
+  entrant_ids: [Option<u32>; 2],
+  scores: [Option<u8>; 2],
+  results: [Option<SlotResult>; 2],
+}
+
+impl SetCacheKey {
+  fn of(set: &SimSet) -> Self {
+    SetCacheKey {
+      state: set.state,
+      started_at_ms: set.started_at_ms,
+      completed_at_ms: set.completed_at_ms,
+      updated_at_ms: set.updated_at_ms,
+      winner_slot: set.winner_slot,
+      entrant_ids: [set.slots[0].entrant_id, set.slots[1].entrant_id],
+      scores: [set.slots[0].score, set.slots[1].score],
+      results: [set.slots[0].result, set.slots[1].result],
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+struct CachedSet {
+  key: SetCacheKey,
+  set: StartggSimSet,
+}
+
 #[derive(Clone, Copy, Debug)]
 enum SlotResolution {
   Ready(u32),
@@ -322,13 +605,15 @@ struct ReferenceOutcome {
   dq_slot: Option<usize>,
 }
 
+/// Xorshift generator; the same seed replays the same bracket. Also used
+/// by the app for repeatable spoofed latency.
 #[derive(Clone, Debug)]
-struct SimRng {
+pub struct SimRng {
   state: u64,
 }
 
 impl SimRng {
-  fn new(seed: u64) -> Self {
+  pub fn new(seed: u64) -> Self {
     let mut state = seed;
     if state == 0 {
       state = 0x9E37_79B9_7F4A_7C15;
@@ -336,7 +621,7 @@ impl SimRng {
     SimRng { state }
   }
 
-  fn next_u64(&mut self) -> u64 {
+  pub fn next_u64(&mut self) -> u64 {
     let mut x = self.state;
     x ^= x << 13;
     x ^= x >> 7;
@@ -365,6 +650,12 @@ pub struct StartggSim {
   entrants_by_id: HashMap<u32, SimEntrant>,
   sets: Vec<SimSet>,
   set_index: HashMap<u64, usize>,
+  /// For each set index, the sets whose slots or conditions read its result
+  dependents: Vec<Vec<usize>>,
+  /// Snapshot of each set, rebuilt only when its SetCacheKey changes
+  snapshot_cache: Vec<Option<CachedSet>>,
+  version: u64,
+  last_changed_ms: u64,
   started_at_ms: u64,
   rng: SimRng,
 }
@@ -394,6 +685,8 @@ impl StartggSim {
       build_reference_sets(&entrants, &config.phases[0], &config.reference_sets)?
     };
 
+    let dependents = build_dependents(&sets, &set_index);
+    let snapshot_cache = vec![None; sets.len()];
     let sim_seed = config.simulation.seed;
     Ok(StartggSim {
       config,
@@ -401,6 +694,10 @@ impl StartggSim {
       entrants_by_id,
       sets,
       set_index,
+      dependents,
+      snapshot_cache,
+      version: 0,
+      last_changed_ms: 0,
       started_at_ms: now_ms,
       rng: SimRng::new(sim_seed),
     })
@@ -410,20 +707,52 @@ impl StartggSim {
     !self.config.reference_sets.is_empty()
   }
 
+  pub fn set_scores(&self, set_id: u64) -> Option<[u8; 2]> {
+    let set = self.get_set(set_id)?;
+    Some([
+      set.slots[0].score.unwrap_or(0),
+      set.slots[1].score.unwrap_or(0),
+    ])
+  }
+
   pub fn state(&mut self, now_ms: u64) -> StartggSimState {
     self.state_since(now_ms, None)
   }
 
-  pub fn state_since(&mut self, now_ms: u64, since_ms: Option<u64>) -> StartggSimState {
+  /// Advance the sim to `now_ms` and return its version, without building a
+  /// snapshot: the version only moves when a set changed, so callers can
+  /// skip `state` until it does.
+  pub fn tick(&mut self, now_ms: u64) -> u64 {
     self.advance(now_ms);
-    let mut snapshot = self.snapshot(now_ms);
-    if let Some(since) = since_ms {
-      if since > 0 {
-        snapshot.sets.retain(|set| set.updated_at_ms > since);
-        snapshot.entrants = Vec::new();
+    self.refresh_snapshot_cache();
+    self.version
+  }
+
+  fn state_since(&mut self, now_ms: u64, since_ms: Option<u64>) -> StartggSimState {
+    self.tick(now_ms);
+    let since = since_ms.filter(|since| *since > 0);
+    let mut snapshot = self.snapshot(now_ms, since);
+    snapshot.not_modified = since.is_some_and(|since| since >= self.last_changed_ms);
+    snapshot
+  }
+
+  /// Rebuild cached snapshots for sets that changed since the last poll and
+  /// bump the version if any did.
+  fn refresh_snapshot_cache(&mut self) {
+    let mut changed = false;
+    for idx in 0..self.sets.len() {
+      let key = SetCacheKey::of(&self.sets[idx]);
+      if matches!(&self.snapshot_cache[idx], Some(cached) if cached.key == key) {
+        continue;
       }
+      let set = self.snapshot_set(&self.sets[idx]);
+      self.last_changed_ms = self.last_changed_ms.max(key.updated_at_ms);
+      self.snapshot_cache[idx] = Some(CachedSet { key, set });
+      changed = true;
+    }
+    if changed {
+      self.version += 1;
     }
-    snapshot
   }
 
   pub fn raw_response(&mut self, now_ms: u64, since_ms: Option<u64>) -> Value {
@@ -449,40 +778,25 @@ impl StartggSim {
       }
     }
 
-    let mut safety = 0;
-    loop {
-      safety += 1;
-      if safety > 1000 {
-        break;
+    // Every pending set is checked once; a set that leaves Pending here
+    // (bye, skip) queues the sets that read its result.
+    let mut queue = (0..self.sets.len())
+      .filter(|idx| self.sets[*idx].state == SimSetState::Pending)
+      .collect::<VecDeque<_>>();
+    while let Some(idx) = queue.pop_front() {
+      if self.sets[idx].state != SimSetState::Pending {
+        continue;
       }
-      let mut progressed = false;
-
-      for idx in 0..self.sets.len() {
-        let state = self.sets[idx].state;
-        if state != SimSetState::Pending {
-          continue;
-        }
-        if self.apply_condition(idx, now_ms) {
-          progressed = true;
-          continue;
-        }
-
+      if !self.apply_condition(idx, now_ms) {
         let (res_a, res_b) = {
           let set = &self.sets[idx];
           (self.resolve_slot(set.slots[0].source), self.resolve_slot(set.slots[1].source))
         };
-
-        if self.apply_resolutions(idx, res_a, res_b, now_ms) {
-          progressed = true;
-        }
-
-        if self.auto_advance_if_bye(idx, res_a, res_b, now_ms) {
-          progressed = true;
-        }
+        self.apply_resolutions(idx, res_a, res_b, now_ms);
+        self.auto_advance_if_bye(idx, res_a, res_b, now_ms);
       }
-
-      if !progressed {
-        break;
+      if self.sets[idx].state != SimSetState::Pending {
+        queue.extend(self.dependents[idx].iter().copied());
       }
     }
 
@@ -890,6 +1204,8 @@ impl StartggSim {
 
     let config = self.config.clone();
     let mut next = StartggSim::new(config, now_ms)?;
+    // Keep the version monotonic for pollers across the rebuild.
+    next.version = self.version;
     next.advance(now_ms);
 
     for outcome in outcomes {
@@ -1212,27 +1528,13 @@ impl StartggSim {
   }
 
   fn collect_dependent_sets(&self, root_id: u64) -> HashSet<u64> {
-    let mut dependents: HashMap<u64, Vec<u64>> = HashMap::new();
-    for set in &self.sets {
-      for slot in &set.slots {
-        match slot.source {
-          SlotSource::Winner(source_id) | SlotSource::Loser(source_id) => {
-            dependents.entry(source_id).or_default().push(set.id);
-          }
-          _ => {}
-        }
-      }
-    }
-
     let mut affected = HashSet::new();
-    let mut stack = vec![root_id];
+    let mut stack = self.set_index.get(&root_id).copied().into_iter().collect::<Vec<_>>();
     while let Some(current) = stack.pop() {
-      if !affected.insert(current) {
+      if !affected.insert(self.sets[current].id) {
         continue;
       }
-      if let Some(children) = dependents.get(&current) {
-        stack.extend(children.iter().copied());
-      }
+      stack.extend(self.dependents[current].iter().copied());
     }
     affected
   }
@@ -1348,10 +1650,14 @@ impl StartggSim {
     self.set_index.get(&set_id).and_then(|idx| self.sets.get(*idx))
   }
 
-  fn snapshot(&self, now_ms: u64) -> StartggSimState {
+  /// Assemble state from the snapshot cache; call refresh_snapshot_cache
+  /// first. With `since_ms` only sets updated after it are included and
+  /// entrants are left out.
+  fn snapshot(&self, now_ms: u64, since_ms: Option<u64>) -> StartggSimState {
     let entrants = self
       .entrants
       .iter()
+      .filter(|_| since_ms.is_none())
       .cloned()
       .map(|e| StartggSimEntrant {
         id: e.id,
@@ -1361,77 +1667,11 @@ impl StartggSim {
       })
       .collect::<Vec<_>>();
     let sets = self
-      .sets
+      .snapshot_cache
       .iter()
-      .map(|set| {
-        let slots = set
-          .slots
-          .iter()
-          .map(|slot| {
-            let entrant = slot.entrant_id.and_then(|id| self.entrants_by_id.get(&id));
-            let (source_type, source_set_id, source_label) = match slot.source {
-              SlotSource::Winner(set_id) => {
-                let label = self
-                  .get_set(set_id)
-                  .map(|set| set.round_label.clone())
-                  .unwrap_or_else(|| format!("Set {set_id}"));
-                (
-                  Some("winner".to_string()),
-                  Some(set_id),
-                  Some(format!("Winner of {label}")),
-                )
-              }
-              SlotSource::Loser(set_id) => {
-                let label = self
-                  .get_set(set_id)
-                  .map(|set| set.round_label.clone())
-                  .unwrap_or_else(|| format!("Set {set_id}"));
-                (
-                  Some("loser".to_string()),
-                  Some(set_id),
-                  Some(format!("Loser of {label}")),
-                )
-              }
-              SlotSource::Empty => (Some("empty".to_string()), None, Some("TBD".to_string())),
-              SlotSource::Entrant(_) => (None, None, None),
-            };
-            StartggSimSlot {
-              entrant_id: slot.entrant_id,
-              entrant_name: entrant.map(|e| e.name.clone()),
-              slippi_code: entrant.map(|e| e.slippi_code.clone()),
-              seed: entrant.map(|e| e.seed),
-              score: slot.score,
-              result: slot.result.map(|r| match r {
-                SlotResult::Win => "win".to_string(),
-                SlotResult::Loss => "loss".to_string(),
-                SlotResult::Dq => "dq".to_string(),
-              }),
-              source_type,
-              source_set_id,
-              source_label,
-            }
-          })
-          .collect();
-        StartggSimSet {
-          id: set.id,
-          phase_id: set.phase_id.clone(),
-          phase_name: self.config.phases[0].name.clone(),
-          round: set.round,
-          round_label: set.round_label.clone(),
-          best_of: set.best_of,
-          state: match set.state {
-            SimSetState::Pending => "pending".to_string(),
-            SimSetState::InProgress => "inProgress".to_string(),
-            SimSetState::Completed => "completed".to_string(),
-            SimSetState::Skipped => "skipped".to_string(),
-          },
-          started_at_ms: set.started_at_ms,
-          completed_at_ms: set.completed_at_ms,
-          updated_at_ms: set.updated_at_ms,
-          winner_id: set_winner_id(set),
-          slots,
-        }
-      })
+      .flatten()
+      .filter(|cached| since_ms.map_or(true, |since| cached.key.updated_at_ms > since))
+      .map(|cached| cached.set.clone())
       .collect::<Vec<_>>();
 
     StartggSimState {
@@ -1442,6 +1682,85 @@ impl StartggSim {
       started_at_ms: self.started_at_ms,
       now_ms,
       reference_tournament_link: self.config.reference_tournament_link.clone(),
+      version: self.version,
+      not_modified: false,
+      warming: false,
+    }
+  }
+
+  pub fn set_snapshot(&self, set_id: u64) -> Option<StartggSimSet> {
+    self.get_set(set_id).map(|set| self.snapshot_set(set))
+  }
+
+  fn snapshot_set(&self, set: &SimSet) -> StartggSimSet {
+    let slots = set
+      .slots
+      .iter()
+      .map(|slot| {
+        let entrant = slot.entrant_id.and_then(|id| self.entrants_by_id.get(&id));
+        let (source_type, source_set_id, source_label) = match slot.source {
+          SlotSource::Winner(set_id) => {
+            let label = self
+              .get_set(set_id)
+              .map(|set| set.round_label.clone())
+              .unwrap_or_else(|| format!("Set {set_id}"));
+            (
+              Some("winner".to_string()),
+              Some(set_id),
+              Some(format!("Winner of {label}")),
+            )
+          }
+          SlotSource::Loser(set_id) => {
+            let label = self
+              .get_set(set_id)
+              .map(|set| set.round_label.clone())
+              .unwrap_or_else(|| format!("Set {set_id}"));
+            (
+              Some("loser".to_string()),
+              Some(set_id),
+              Some(format!("Loser of {label}")),
+            )
+          }
+          SlotSource::Empty => (Some("empty".to_string()), None, Some("TBD".to_string())),
+          SlotSource::Entrant(_) => (None, None, None),
+        };
+        StartggSimSlot {
+          entrant_id: slot.entrant_id,
+          entrant_name: entrant.map(|e| e.name.clone()),
+          slippi_code: entrant.map(|e| e.slippi_code.clone()),
+          seed: entrant.map(|e| e.seed),
+          score: slot.score,
+          result: slot.result.map(|r| match r {
+            SlotResult::Win => "win".to_string(),
+            SlotResult::Loss => "loss".to_string(),
+            SlotResult::Dq => "dq".to_string(),
+          }),
+          source_type,
+          source_set_id,
+          source_label,
+        }
+      })
+      .collect();
+    StartggSimSet {
+      id: set.id,
+      startgg_id: None,
+      preview_id: None,
+      phase_id: set.phase_id.clone(),
+      phase_name: self.config.phases[0].name.clone(),
+      round: set.round,
+      round_label: set.round_label.clone(),
+      best_of: set.best_of,
+      state: match set.state {
+        SimSetState::Pending => "pending".to_string(),
+        SimSetState::InProgress => "inProgress".to_string(),
+        SimSetState::Completed => "completed".to_string(),
+        SimSetState::Skipped => "skipped".to_string(),
+      },
+      started_at_ms: set.started_at_ms,
+      completed_at_ms: set.completed_at_ms,
+      updated_at_ms: set.updated_at_ms,
+      winner_id: set_winner_id(set),
+      slots,
     }
   }
 
@@ -1625,10 +1944,12 @@ fn startgg_state_to_raw(state: &StartggSimState, now_ms: u64) -> Value {
     .map(|phase| json!({ "id": phase.id, "name": phase.name }))
     .collect::<Vec<_>>();
 
+  let slugs = unique_slugs(state.entrants.iter().map(|entrant| (entrant.id, entrant.name.as_str())));
   let entrants = state
     .entrants
     .iter()
-    .map(|entrant| {
+    .zip(&slugs)
+    .map(|(entrant, slug)| {
       let user_id = entrant.id + 10_000;
       let participant_id = entrant.id + 20_000;
       json!({
@@ -1642,7 +1963,7 @@ fn startgg_state_to_raw(state: &StartggSimState, now_ms: u64) -> Value {
           "connectedAccounts": { "slippi": { "value": entrant.slippi_code } },
           "user": {
             "id": user_id,
-            "slug": slugify(&entrant.name),
+            "slug": slug,
             "authorizations": [
               { "type": "SLIPPI", "externalUsername": entrant.slippi_code }
             ]
@@ -1652,6 +1973,14 @@ fn startgg_state_to_raw(state: &StartggSimState, now_ms: u64) -> Value {
     })
     .collect::<Vec<_>>();
 
+  // Preview sets go out under start.gg's own string ids, as start.gg
+  // sends them.
+  let raw_ids: HashMap<u64, Value> = state
+    .sets
+    .iter()
+    .filter_map(|set| Some((set.id, Value::from(set.startgg_id.clone()?))))
+    .collect();
+  let raw_id = |id: u64| raw_ids.get(&id).cloned().unwrap_or_else(|| json!(id));
   let sets = state
     .sets
     .iter()
@@ -1687,13 +2016,14 @@ fn startgg_state_to_raw(state: &StartggSimState, now_ms: u64) -> Value {
               }
             },
             "sourceType": slot.source_type,
-            "sourceSetId": slot.source_set_id,
+            "sourceSetId": slot.source_set_id.map(raw_id),
             "sourceLabel": slot.source_label
           })
         })
         .collect::<Vec<_>>();
       json!({
-        "id": set.id,
+        "id": raw_id(set.id),
+        "previewId": set.preview_id,
         "round": set.round,
         "fullRoundText": full_round_text,
         "state": state_code(&set.state),
@@ -1731,7 +2061,10 @@ fn startgg_state_to_raw(state: &StartggSimState, now_ms: u64) -> Value {
     "extensions": {
       "nowMs": now_ms,
       "startedAtMs": state.started_at_ms,
-      "eventLink": state.reference_tournament_link
+      "eventLink": state.reference_tournament_link,
+      "version": state.version,
+      "notModified": state.not_modified,
+      "warming": state.warming
     }
   })
 }
@@ -1786,13 +2119,14 @@ fn to_seconds(ms: Option<u64>) -> Option<u64> {
   ms.map(|value| value / 1000)
 }
 
+/// Unicode-aware: letters and digits in any script are kept (after NFKC
+/// folding), so Japanese and Korean tags don't collapse to an empty slug.
 fn slugify(name: &str) -> String {
   let mut out = String::new();
   let mut last_dash = false;
-  for ch in name.chars() {
-    let lower = ch.to_ascii_lowercase();
-    if lower.is_ascii_alphanumeric() {
-      out.push(lower);
+  for ch in fold_unicode(name).chars() {
+    if ch.is_alphanumeric() {
+      out.push(ch);
       last_dash = false;
     } else if !last_dash {
       out.push('-');
@@ -1802,6 +2136,52 @@ fn slugify(name: &str) -> String {
   out.trim_matches('-').to_string()
 }
 
+/// One slug per entrant, in order. Identical tags get `-2`, `-3`, ...
+/// suffixes and tags with no usable characters fall back to the entrant id.
+fn unique_slugs<'a>(entrants: impl Iterator<Item = (u32, &'a str)>) -> Vec<String> {
+  let mut used = HashSet::new();
+  entrants
+    .map(|(id, name)| {
+      let base = slugify(name);
+      let base = if base.is_empty() { format!("entrant-{id}") } else { base };
+      let mut slug = base.clone();
+      let mut suffix = 2;
+      while !used.insert(slug.clone()) {
+        slug = format!("{base}-{suffix}");
+        suffix += 1;
+      }
+      slug
+    })
+    .collect()
+}
+
+/// Reverse edges of the bracket: for each set index, the indices of the sets
+/// fed by its winner or loser, plus a grand final reset gated on it.
+fn build_dependents(sets: &[SimSet], set_index: &HashMap<u64, usize>) -> Vec<Vec<usize>> {
+  let mut dependents = vec![Vec::new(); sets.len()];
+  for (idx, set) in sets.iter().enumerate() {
+    let mut sources = set
+      .slots
+      .iter()
+      .filter_map(|slot| match slot.source {
+        SlotSource::Winner(source_id) | SlotSource::Loser(source_id) => Some(source_id),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    if let Some(SimSetCondition::GrandFinalReset { gf1_id, .. }) = set.condition {
+      sources.push(gf1_id);
+    }
+    sources.sort_unstable();
+    sources.dedup();
+    for source_id in sources {
+      if let Some(source) = set_index.get(&source_id).copied() {
+        dependents[source].push(idx);
+      }
+    }
+  }
+  dependents
+}
+
 fn apply_slot_resolution(slot: &mut SimSlot, resolution: SlotResolution) -> bool {
   match resolution {
     SlotResolution::Ready(id) => {
@@ -2309,6 +2689,53 @@ fn next_power_of_two(n: usize) -> usize {
   value
 }
 
+/// One first-round pairing under a seeding. A missing entrant is a bye.
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedMatchup {
+  pub high_seed: u32,
+  pub low_seed: u32,
+  pub high: Option<StartggSimEntrant>,
+  pub low: Option<StartggSimEntrant>,
+}
+
+/// First-round pairings of a standard bracket over `entrants` by seed,
+/// in bracket order: 1 v 8, 4 v 5, 2 v 7, 3 v 6 for eight.
+pub fn first_round_matchups(entrants: &[StartggSimEntrant]) -> Vec<SeedMatchup> {
+  let mut ranked = entrants.to_vec();
+  ranked.sort_by_key(|e| (e.seed, e.id));
+  let positions = seed_positions(next_power_of_two(ranked.len()) as u32);
+  positions
+    .chunks(2)
+    .filter_map(|pair| {
+      let (high_seed, low_seed) = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+      let at = |seed: u32| ranked.get(seed as usize - 1).cloned();
+      let high = at(high_seed);
+      let low = at(low_seed);
+      (high.is_some() || low.is_some()).then_some(SeedMatchup { high_seed, low_seed, high, low })
+    })
+    .collect()
+}
+
+/// Trade the seeds of two entrants.
+pub fn swap_entrant_seeds(entrants: &mut [StartggSimEntrant], a_id: u32, b_id: u32) -> Result<(), String> {
+  if a_id == b_id {
+    return Err("Pick two different entrants to swap.".to_string());
+  }
+  let find = |entrants: &[StartggSimEntrant], id: u32| {
+    entrants
+      .iter()
+      .position(|e| e.id == id)
+      .ok_or_else(|| format!("Entrant {id} is not in this bracket."))
+  };
+  let a = find(entrants, a_id)?;
+  let b = find(entrants, b_id)?;
+  let seed = entrants[a].seed;
+  entrants[a].seed = entrants[b].seed;
+  entrants[b].seed = seed;
+  Ok(())
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -2457,6 +2884,89 @@ mod tests {
     assert!(none.sets.len() < all.sets.len() || none.sets.is_empty());
   }
 
+  #[test]
+  fn state_since_reports_not_modified() {
+    let mut sim = make_sim(4);
+    let all = sim.state(1000);
+    let idle = sim.state_since(1500, Some(1000));
+    assert!(idle.not_modified);
+    assert!(idle.sets.is_empty());
+    assert_eq!(idle.version, all.version);
+
+    let ready = sim.ready_set_ids()[0];
+    sim.force_winner(ready, 0, 2000).unwrap();
+    let changed = sim.state_since(2000, Some(1000));
+    assert!(!changed.not_modified);
+    assert!(changed.version > all.version);
+    assert!(changed.sets.iter().any(|s| s.id == ready && s.state == "completed"));
+  }
+
+  #[test]
+  fn snapshot_since_matches_state_since() {
+    let mut sim = make_sim(4);
+    let full = sim.state(1000);
+    let idle = full.since(Some(1000));
+    assert!(idle.not_modified);
+    assert!(idle.sets.is_empty() && idle.entrants.is_empty());
+
+    let ready = sim.ready_set_ids()[0];
+    sim.force_winner(ready, 0, 2000).unwrap();
+    let full = sim.state(2000);
+    let changed = full.since(Some(1000));
+    assert_eq!(
+      changed.sets.iter().map(|s| s.id).collect::<Vec<_>>(),
+      sim.state_since(2000, Some(1000)).sets.iter().map(|s| s.id).collect::<Vec<_>>()
+    );
+    assert!(!changed.not_modified);
+    assert!(changed.entrants.is_empty());
+    assert_eq!(full.since(None).sets.len(), full.sets.len());
+  }
+
+  #[test]
+  fn tick_only_moves_the_version_when_a_set_changes() {
+    let mut sim = make_sim(4);
+    let version = sim.tick(1000);
+    assert_eq!(sim.tick(1500), version);
+    assert_eq!(sim.state(1500).version, version);
+
+    let ready = sim.ready_set_ids()[0];
+    sim.force_winner(ready, 0, 2000).unwrap();
+    assert!(sim.tick(2000) > version);
+  }
+
+  #[test]
+  fn set_filter_states_rounds_and_paging() {
+    let mut sim = make_sim(8);
+    let all = sim.state(1000);
+    let callable = StartggSimSetFilter {
+      states: Some(vec!["callable".to_string()]),
+      ..Default::default()
+    };
+    let filtered = |filter: &StartggSimSetFilter| {
+      let mut sets = all.sets.clone();
+      filter.apply(&mut sets);
+      sets
+    };
+    let sets = filtered(&callable);
+    assert_eq!(sets.len(), 4);
+    assert!(sets.iter().all(|s| s.round == 1));
+
+    let losers = StartggSimSetFilter { max_round: Some(-1), ..Default::default() };
+    let expected = all.sets.iter().filter(|s| s.round < 0).count();
+    assert_eq!(filtered(&losers).len(), expected);
+
+    let entrant = StartggSimSetFilter { entrant_id: Some(1), ..Default::default() };
+    assert_eq!(filtered(&entrant).len(), 1);
+
+    let paged = StartggSimSetFilter { limit: Some(3), offset: Some(3), ..Default::default() };
+    let raw = all.to_raw(Some(&paged));
+    let nodes = raw["data"]["event"]["sets"]["nodes"].as_array().unwrap();
+    assert_eq!(nodes.len(), 3);
+    assert_eq!(nodes[0]["id"], all.sets[3].id);
+    assert_eq!(raw["data"]["event"]["sets"]["pageInfo"]["total"], all.sets.len());
+    assert_eq!(raw["data"]["event"]["sets"]["pageInfo"]["page"], 2);
+  }
+
   // ── advance_set ──────────────────────────────────────────────────────
 
   #[test]
@@ -2561,6 +3071,51 @@ mod tests {
     assert_eq!(pending_count, 0, "no sets should be pending after complete_all_sets");
   }
 
+  /// A 512-entrant bracket with its first 64 sets decided at 2000.
+  fn large_sim() -> StartggSim {
+    let mut sim = make_sim(512);
+    assert_eq!(sim.state(1000).sets.len(), 1023);
+    for set_id in sim.ready_set_ids().into_iter().take(64) {
+      sim.force_winner(set_id, 0, 2000).unwrap();
+    }
+    sim.state(2000);
+    sim
+  }
+
+  #[test]
+  fn large_bracket_polls_only_return_changed_sets() {
+    let mut sim = large_sim();
+    assert!(sim.state_since(3000, Some(2500)).sets.is_empty());
+    let changed = sim.state_since(3000, Some(1500));
+    assert!(changed.sets.len() >= 64 && changed.sets.len() < 1023);
+    assert!(changed.sets.iter().all(|s| s.updated_at_ms > 1500));
+
+    sim.complete_all_sets(4000).expect("complete_all_sets should succeed");
+    let state = sim.state(4000);
+    assert!(state.sets.iter().all(|s| s.state == "completed" || s.state == "skipped"));
+  }
+
+  /// Wall-clock budget for polling a large bracket; machine dependent, so
+  /// run it by hand with `cargo test --release -- --ignored`.
+  #[test]
+  #[ignore]
+  fn large_bracket_state_since_stays_fast() {
+    let mut sim = large_sim();
+    let polls = 50u32;
+    let started = std::time::Instant::now();
+    for i in 0..polls {
+      sim.state_since(3000 + i as u64, Some(2500));
+    }
+    let per_poll = started.elapsed() / polls;
+    // Debug builds are roughly an order of magnitude slower.
+    let budget = if cfg!(debug_assertions) {
+      std::time::Duration::from_millis(30)
+    } else {
+      std::time::Duration::from_millis(3)
+    };
+    assert!(per_poll < budget, "state_since took {per_poll:?} per poll at 1023 sets");
+  }
+
   // ── reset_set_and_dependents ─────────────────────────────────────────
 
   #[test]
@@ -2597,6 +3152,31 @@ mod tests {
     assert_eq!(next_power_of_two(9), 16);
   }
 
+  #[test]
+  fn swapped_seeds_change_first_round_matchups() {
+    let mut entrants = (1..=6)
+      .map(|i| StartggSimEntrant { id: i * 10, name: format!("P{i}"), seed: i, slippi_code: String::new() })
+      .collect::<Vec<_>>();
+    let pairs = |entrants: &[StartggSimEntrant]| {
+      first_round_matchups(entrants)
+        .iter()
+        .map(|m| (m.high.as_ref().map(|e| e.id), m.low.as_ref().map(|e| e.id)))
+        .collect::<Vec<_>>()
+    };
+    // Seeds 1 and 2 get byes against the empty 7 and 8 slots.
+    assert_eq!(
+      pairs(&entrants),
+      vec![(Some(10), None), (Some(40), Some(50)), (Some(20), None), (Some(30), Some(60))]
+    );
+    swap_entrant_seeds(&mut entrants, 20, 50).unwrap();
+    assert_eq!(
+      pairs(&entrants),
+      vec![(Some(10), None), (Some(40), Some(20)), (Some(50), None), (Some(30), Some(60))]
+    );
+    assert!(swap_entrant_seeds(&mut entrants, 20, 20).is_err());
+    assert!(swap_entrant_seeds(&mut entrants, 20, 99).is_err());
+  }
+
   #[test]
   fn seed_positions_correct_for_4() {
     let positions = seed_positions(4);
@@ -2729,4 +3309,67 @@ mod tests {
     fs::remove_file(&config_path).ok();
     fs::remove_file(&state_path).ok();
   }
+
+  #[test]
+  fn raw_response_slugs_are_unique_and_unicode() {
+    let mut config = make_config(4);
+    config.entrants[0].name = "あcola".to_string();
+    config.entrants[1].name = "Zain".to_string();
+    config.entrants[2].name = "Zain".to_string();
+    config.entrants[3].name = "!!!".to_string();
+    let mut sim = StartggSim::new(config, 0).unwrap();
+    let raw = sim.raw_response(0, None);
+    let nodes = raw["data"]["event"]["entrants"]["nodes"].as_array().unwrap();
+    let slugs = nodes
+      .iter()
+      .map(|node| node.pointer("/participants/0/user/slug").and_then(|s| s.as_str()).unwrap_or_default())
+      .collect::<Vec<_>>();
+    assert_eq!(slugs, vec!["あcola", "zain", "zain-2", "entrant-4"]);
+    assert_eq!(nodes[0]["name"], "あcola");
+  }
+
+  #[test]
+  fn preview_sets_reconcile_with_real_ids() {
+    let real = make_sim(8).state(1000);
+    // The same bracket as start.gg shows it before the phase starts.
+    let mut preview = real.clone();
+    let ids = preview
+      .sets
+      .iter()
+      .enumerate()
+      .map(|(idx, set)| {
+        let raw = format!("preview_9001_{}_{idx}", set.round);
+        (set.id, (preview_set_id(&raw), raw))
+      })
+      .collect::<HashMap<_, _>>();
+    for set in preview.sets.iter_mut() {
+      let (id, raw) = ids[&set.id].clone();
+      set.id = id;
+      set.startgg_id = Some(raw);
+      for slot in set.slots.iter_mut() {
+        slot.source_set_id = slot.source_set_id.map(|source| ids[&source].0);
+      }
+    }
+    assert!(preview.sets.iter().all(|set| is_preview_set_id(set.id)));
+    let raw = preview.to_raw(None);
+    assert_eq!(raw["data"]["event"]["sets"]["nodes"][0]["id"], json!(preview.sets[0].startgg_id));
+
+    let mut next = real.clone();
+    reconcile_preview_sets(&preview, &mut next);
+    for set in &next.sets {
+      assert_eq!(set.preview_id, Some(ids[&set.id].0), "set {}", set.id);
+    }
+    // Kept on later fetches, once the previews themselves are gone.
+    let mut later = real.clone();
+    reconcile_preview_sets(&next, &mut later);
+    assert_eq!(later.sets[0].preview_id, next.sets[0].preview_id);
+  }
+
+  #[test]
+  fn tag_keys_fold_full_width_forms() {
+    use crate::text::{normalize_broadcast_key, normalize_tag_key};
+    assert_eq!(normalize_tag_key("ＴＳＭ｜Ｌｅｆｆｅｎ"), "leffen");
+    assert_eq!(normalize_tag_key("あcola＃123"), "あcola");
+    assert_eq!(normalize_broadcast_key(" ＭＡＮＧ＃０ "), "mang#0");
+  }
 }
@@ -0,0 +1,201 @@
+use encoding_rs::SHIFT_JIS;
+use unicode_normalization::UnicodeNormalization;
+
+/// NFKC-normalize and lowercase, so full-width and half-width forms (common
+/// in JP/KR tags) and composed/decomposed hangul compare equal.
+pub fn fold_unicode(raw: &str) -> String {
+    raw.nfkc().flat_map(char::to_lowercase).collect()
+}
+
+pub fn normalize_broadcast_key(raw: &str) -> String {
+    fold_unicode(raw.trim()).trim().to_string()
+}
+
+pub fn normalize_tag_key(raw: &str) -> String {
+    // Fold first so full-width '｜' and '＃' split like their ASCII forms.
+    let folded = fold_unicode(raw);
+    let trimmed = strip_sponsor_tag(&folded).trim();
+    if trimmed.is_empty() {
+        return String::new();
+    }
+    let without_code = trimmed.split('#').next().unwrap_or(trimmed);
+    without_code.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Identity for a player across events: connect code when there is one,
+/// otherwise the folded tag.
+pub fn player_key(name: &str, code: Option<&str>) -> String {
+    match code.map(str::trim).filter(|code| !code.is_empty()) {
+        Some(code) => code.to_ascii_uppercase(),
+        None => format!("tag:{}", normalize_tag_key(name)),
+    }
+}
+
+pub fn strip_sponsor_tag(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    if let Some(idx) = trimmed.find('|') {
+        trimmed[idx + 1..].trim()
+    } else {
+        trimmed
+    }
+}
+
+/// Whether re-decoded text reads as Japanese: some kana or full-width
+/// letters, or at least two kanji. One stray kanji is more likely a Latin-1
+/// tag like "Zéro" whose bytes happen to pair up.
+fn reads_as_japanese(decoded: &str) -> bool {
+    let kana = decoded.chars().any(|c| matches!(c, '\u{3040}'..='\u{30FF}' | '\u{FF01}'..='\u{FF5E}'));
+    let kanji = decoded.chars().filter(|c| matches!(c, '\u{4E00}'..='\u{9FFF}')).count();
+    kana || kanji >= 2
+}
+
+/// Netplay display name as text. Names are Shift-JIS on the wire; when one
+/// arrives as its bytes widened to Latin-1 chars, decode those bytes again.
+/// Kept as is unless that yields Japanese text without errors, so accented
+/// European tags pass through.
+pub fn decode_netplay_name(raw: &str) -> String {
+    let trimmed = raw.trim_end_matches('\0');
+    if trimmed.is_ascii() || trimmed.chars().any(|c| (c as u32) > 0xFF) {
+        return trimmed.to_string();
+    }
+    let bytes = trimmed.chars().map(|c| c as u8).collect::<Vec<_>>();
+    let (decoded, had_errors) = SHIFT_JIS.decode_without_bom_handling(&bytes);
+    if had_errors || !reads_as_japanese(&decoded) {
+        return trimmed.to_string();
+    }
+    decoded.into_owned()
+}
+
+/// Hiragana with a Hepburn spelling, in the order of `ROMAJI`.
+const KANA: &str = "あいうえおかきくけこがぎぐげごさしすせそざじずぜぞたちつてとだぢづでど\
+    なにぬねのはひふへほばびぶべぼぱぴぷぺぽまみむめもやゆよらりるれろわゐゑをんゔぁぃぅぇぉゎ";
+const ROMAJI: &[&str] = &[
+    "a", "i", "u", "e", "o", "ka", "ki", "ku", "ke", "ko", "ga", "gi", "gu", "ge", "go", "sa", "shi", "su", "se", "so",
+    "za", "ji", "zu", "ze", "zo", "ta", "chi", "tsu", "te", "to", "da", "ji", "zu", "de", "do", "na", "ni", "nu", "ne",
+    "no", "ha", "hi", "fu", "he", "ho", "ba", "bi", "bu", "be", "bo", "pa", "pi", "pu", "pe", "po", "ma", "mi", "mu",
+    "me", "mo", "ya", "yu", "yo", "ra", "ri", "ru", "re", "ro", "wa", "wi", "we", "wo", "n", "vu", "a", "i", "u", "e",
+    "o", "wa",
+];
+
+/// Hepburn for one hiragana; None for anything else.
+fn kana_romaji(c: char) -> Option<&'static str> {
+    let idx = KANA.chars().position(|kana| kana == c)?;
+    ROMAJI.get(idx).copied()
+}
+
+/// Katakana to the matching hiragana, so one table covers both.
+fn to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(c as u32 - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+/// ASCII spelling of a tag: full-width letters folded, kana in Hepburn.
+/// None when something is left that has no ASCII form, e.g. kanji.
+pub fn romanize(raw: &str) -> Option<String> {
+    let folded = raw.nfkc().map(to_hiragana).collect::<Vec<_>>();
+    let mut out = String::new();
+    // っ doubles the next consonant.
+    let mut double_next = false;
+    let mut i = 0;
+    while i < folded.len() {
+        let c = folded[i];
+        i += 1;
+        let mut syllable = match c {
+            'っ' => {
+                double_next = true;
+                continue;
+            }
+            'ー' => {
+                if let Some(vowel) = out.chars().last().filter(|c| "aiueo".contains(*c)) {
+                    out.push(vowel);
+                }
+                continue;
+            }
+            '・' => " ".to_string(),
+            c if c.is_ascii() => c.to_string(),
+            c => kana_romaji(c)?.to_string(),
+        };
+        // きゃ -> kya, しゃ -> sha.
+        let small_y = folded.get(i).and_then(|next| match next {
+            'ゃ' => Some('a'),
+            'ゅ' => Some('u'),
+            'ょ' => Some('o'),
+            _ => None,
+        });
+        if let Some(vowel) = small_y.filter(|_| syllable.len() > 1 && syllable.ends_with('i')) {
+            syllable.pop();
+            if !(syllable.ends_with("sh") || syllable.ends_with("ch") || syllable.ends_with('j')) {
+                syllable.push('y');
+            }
+            syllable.push(vowel);
+            i += 1;
+        }
+        if std::mem::take(&mut double_next) {
+            match syllable.chars().next() {
+                Some('c') => out.push('t'),
+                Some(first) if first.is_ascii_alphabetic() && !"aiueon".contains(first) => out.push(first),
+                _ => {}
+            }
+        }
+        out.push_str(&syllable);
+    }
+    let out = out.trim().to_string();
+    (out.is_ascii() && !out.is_empty()).then_some(out)
+}
+
+/// ASCII fallback for a display tag, for fonts without CJK glyphs: the
+/// romanized tag, else the connect code's prefix. None when the tag is
+/// already plain ASCII.
+pub fn ascii_tag(tag: &str, code: Option<&str>) -> Option<String> {
+    if tag.is_ascii() {
+        return None;
+    }
+    romanize(tag).or_else(|| {
+        let code = code?.trim();
+        let prefix = code.split('#').next().unwrap_or(code);
+        (!prefix.is_empty() && prefix.is_ascii()).then(|| prefix.to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widened_shift_jis_names_are_decoded() {
+        // "マング" as Shift-JIS bytes widened to Latin-1 chars.
+        let (bytes, _, _) = SHIFT_JIS.encode("マング");
+        let widened = bytes.iter().map(|b| *b as char).collect::<String>();
+        assert_eq!(decode_netplay_name(&widened), "マング");
+    }
+
+    #[test]
+    fn names_that_are_already_text_pass_through() {
+        assert_eq!(decode_netplay_name("マング\0\0"), "マング");
+        assert_eq!(decode_netplay_name("Mang0"), "Mang0");
+        assert_eq!(decode_netplay_name("Zéro"), "Zéro");
+    }
+
+    #[test]
+    fn kana_romanize_in_hepburn() {
+        assert_eq!(romanize("マング").as_deref(), Some("mangu"));
+        assert_eq!(romanize("しゅーと").as_deref(), Some("shuuto"));
+        assert_eq!(romanize("きゃっち").as_deref(), Some("kyatchi"));
+    }
+
+    #[test]
+    fn full_width_letters_fold_and_kanji_do_not_romanize() {
+        assert_eq!(romanize("ＡＭＳＡ").as_deref(), Some("AMSA"));
+        assert_eq!(romanize("侍"), None);
+    }
+
+    #[test]
+    fn ascii_tags_fall_back_to_the_code_prefix() {
+        assert_eq!(ascii_tag("Mang0", Some("MANG#0")), None);
+        assert_eq!(ascii_tag("あまさ", None).as_deref(), Some("amasa"));
+        assert_eq!(ascii_tag("侍", Some("SAMU#123")).as_deref(), Some("SAMU"));
+        assert_eq!(ascii_tag("侍", None), None);
+    }
+}
@@ -0,0 +1,66 @@
+// Shared helpers for the default scene pack. Polls /state.json like the
+// main overlay and hands each payload to the page's render function.
+(function () {
+  function stripSponsorTag(tag) {
+    if (!tag) return "";
+    const trimmed = String(tag).trim();
+    const pipeIndex = trimmed.indexOf("|");
+    return pipeIndex === -1 ? trimmed : trimmed.slice(pipeIndex + 1).trim();
+  }
+
+  function setupIndexFromQuery() {
+    const params = new URLSearchParams(window.location.search);
+    const val = Number(params.get("setup"));
+    if (Number.isFinite(val) && val >= 1 && val <= 16) return val - 1;
+    return 0;
+  }
+
+  // ?tags=ascii shows each player's ASCII tag (romaji or connect code) for
+  // fonts without Japanese glyphs; otherwise the tag as entered.
+  function displayTag(player) {
+    const ascii = new URLSearchParams(window.location.search).get("tags") === "ascii";
+    return stripSponsorTag(ascii && player?.tagRomaji ? player.tagRomaji : player?.tag);
+  }
+
+  function poll(render, intervalMs) {
+    async function load() {
+      try {
+        const r = await fetch(`/state.json?ts=${Date.now()}`, { cache: "no-store" });
+        if (!r.ok) throw new Error(r.statusText);
+        render(await r.json());
+      } catch (e) {
+        console.warn("[scene] state load error:", e);
+      }
+    }
+    load();
+    setInterval(load, intervalMs || 500);
+  }
+
+  // Messages pushed on the overlay socket for `topics` (comma-separated),
+  // reconnecting after the app restarts. Each message is { topic, payload }.
+  function subscribe(topics, onMessage) {
+    function connect() {
+      const ws = new WebSocket(`ws://${window.location.host}/ws?topics=${encodeURIComponent(topics)}`);
+      ws.onmessage = (event) => {
+        try {
+          const msg = JSON.parse(event.data);
+          onMessage(msg.payload, msg.topic);
+        } catch (e) {
+          console.warn("[scene] socket message error:", e);
+        }
+      };
+      ws.onclose = () => setTimeout(connect, 2000);
+    }
+    connect();
+  }
+
+  function formatRemaining(ms) {
+    const total = Math.ceil(ms / 1000);
+    const hours = Math.floor(total / 3600);
+    const minutes = Math.floor((total % 3600) / 60);
+    const seconds = String(total % 60).padStart(2, "0");
+    return hours ? `${hours}:${String(minutes).padStart(2, "0")}:${seconds}` : `${minutes}:${seconds}`;
+  }
+
+  window.scene = { stripSponsorTag, displayTag, setupIndexFromQuery, poll, subscribe, formatRemaining };
+})();
@@ -0,0 +1,268 @@
+use crate::bracket_config::{read_replay_map, write_replay_map};
+use crate::config::*;
+use crate::jobs::Job;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::{
+    fs,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+
+/// Hex sha256 of a file, streamed so large replays aren't loaded at once.
+pub fn sha256_file(path: &Path) -> Result<String, String> {
+    let file = fs::File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buf).map_err(|e| format!("read {}: {e}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveIssue {
+    pub set_id: u64,
+    pub path: String,
+    /// "missing", "corrupt", "unreadable" or "unhashed"
+    pub kind: String,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveVerification {
+    pub config_path: String,
+    pub files_checked: usize,
+    pub files_ok: usize,
+    pub issues: Vec<ArchiveIssue>,
+    /// True only when every recorded replay exists and matches its checksum
+    pub safe_to_delete_originals: bool,
+}
+
+/// Re-hash every replay in an event's bracket config and compare against the
+/// checksums recorded when the replays were archived.
+pub fn verify_archive_at(config_path: &Path, job: Option<&Job>) -> Result<ArchiveVerification, String> {
+    let replay_map = read_replay_map(config_path)?
+        .ok_or_else(|| "referenceReplayMap missing from bracket config.".to_string())?;
+    let total = replay_map.sets.iter().map(|set| set.replays.len()).sum::<usize>();
+    let mut done = 0;
+    let mut files_checked = 0;
+    let mut files_ok = 0;
+    let mut issues = Vec::new();
+    for set in &replay_map.sets {
+        for replay in &set.replays {
+            if let Some(job) = job {
+                job.step(done, total, format!("Checking {}", replay.path))?;
+            }
+            done += 1;
+            let Some(path) = replay_map.resolve_replay_path(&replay.path) else {
+                continue;
+            };
+            files_checked += 1;
+            let issue = |kind: &str, actual: Option<String>, message: Option<String>| ArchiveIssue {
+                set_id: set.id,
+                path: path.display().to_string(),
+                kind: kind.to_string(),
+                expected: replay.sha256.clone(),
+                actual,
+                message,
+            };
+            if !path.is_file() {
+                issues.push(issue("missing", None, None));
+                continue;
+            }
+            let actual = match sha256_file(&path) {
+                Ok(hash) => hash,
+                Err(err) => {
+                    issues.push(issue("unreadable", None, Some(err)));
+                    continue;
+                }
+            };
+            match replay.sha256.as_deref() {
+                Some(expected) if expected.eq_ignore_ascii_case(&actual) => files_ok += 1,
+                Some(_) => issues.push(issue("corrupt", Some(actual), None)),
+                None => issues.push(issue(
+                    "unhashed",
+                    Some(actual),
+                    Some("No checksum was recorded; re-archive the set to add one.".to_string()),
+                )),
+            }
+        }
+    }
+    Ok(ArchiveVerification {
+        config_path: config_path.display().to_string(),
+        files_checked,
+        files_ok,
+        safe_to_delete_originals: issues.is_empty() && files_checked > 0,
+        issues,
+    })
+}
+
+/// Record checksums for archived replays that don't have one yet. Returns
+/// how many were added.
+pub fn add_missing_checksums(config_path: &Path, job: Option<&Job>) -> Result<usize, String> {
+    let mut replay_map = read_replay_map(config_path)?
+        .ok_or_else(|| "referenceReplayMap missing from bracket config.".to_string())?;
+    let mut added = 0;
+    let mut done = 0;
+    let resolver = replay_map.clone();
+    let total = resolver.sets.iter().flat_map(|set| &set.replays).filter(|replay| replay.sha256.is_none()).count();
+    for set in &mut replay_map.sets {
+        for replay in &mut set.replays {
+            if replay.sha256.is_some() {
+                continue;
+            }
+            if let Some(job) = job {
+                job.step(done, total, format!("Hashing {}", replay.path))?;
+            }
+            done += 1;
+            let Some(path) = resolver.resolve_replay_path(&replay.path).filter(|path| path.is_file()) else {
+                continue;
+            };
+            replay.sha256 = Some(sha256_file(&path)?);
+            added += 1;
+        }
+    }
+    if added > 0 {
+        write_replay_map(config_path, &replay_map)?;
+    }
+    Ok(added)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+fn event_config_path(event: Option<String>) -> Result<PathBuf, String> {
+    let path = match event.as_deref().map(str::trim).filter(|raw| !raw.is_empty()) {
+        Some(raw) => resolve_startgg_sim_config_path(raw),
+        None => startgg_sim_config_path(),
+    };
+    if !path.is_file() {
+        return Err(format!("Bracket config not found at {}", path.display()));
+    }
+    Ok(path)
+}
+
+/// Check an event's archived replays before the spectate originals are
+/// deleted or the archive goes to cold storage. `event` is the bracket
+/// config path; the current one is used when omitted.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn verify_archive(event: Option<String>, app: AppHandle) -> Result<ArchiveVerification, String> {
+    let config_path = event_config_path(event)?;
+    let job = Job::start(&app, "archiveVerify", "Verify archived replays", true);
+    let result = verify_archive_at(&config_path, Some(&job));
+    job.finish(result, |report| format!("{} of {} replays OK.", report.files_ok, report.files_checked))
+}
+
+/// Hash replays archived before checksums were recorded.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn backfill_archive_checksums(event: Option<String>, app: AppHandle) -> Result<usize, String> {
+    let config_path = event_config_path(event)?;
+    let job = Job::start(&app, "archiveChecksums", "Hash archived replays", true);
+    let result = add_missing_checksums(&config_path, Some(&job));
+    job.finish(result, |added| format!("Added {added} checksum(s)."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temp folder holding `files`, and a bracket config whose replay map
+    /// lists `replays` in one set.
+    fn archive(name: &str, files: &[(&str, &str)], replays: serde_json::Value) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("archive_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        let config = dir.join("bracket.json");
+        let body = serde_json::json!({
+            "referenceReplayMap": {
+                "replaysDir": dir.to_string_lossy(),
+                "sets": [{ "id": 1, "replays": replays }]
+            }
+        });
+        fs::write(&config, body.to_string()).unwrap();
+        (dir, config)
+    }
+
+    fn hash_of(contents: &str) -> String {
+        Sha256::digest(contents.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn issue_kinds(config: &Path) -> Vec<String> {
+        verify_archive_at(config, None).unwrap().issues.into_iter().map(|issue| issue.kind).collect()
+    }
+
+    #[test]
+    fn files_hash_like_their_contents() {
+        let (dir, _) = archive("hash", &[("game.slp", "game one")], serde_json::json!([]));
+        assert_eq!(sha256_file(&dir.join("game.slp")).unwrap(), hash_of("game one"));
+        assert!(sha256_file(&dir.join("gone.slp")).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn matching_checksums_are_safe_to_delete() {
+        let replays = serde_json::json!([{ "path": "good.slp", "sha256": hash_of("game one").to_uppercase() }]);
+        let (dir, config) = archive("good", &[("good.slp", "game one")], replays);
+        let report = verify_archive_at(&config, None).unwrap();
+        assert_eq!((report.files_checked, report.files_ok), (1, 1));
+        assert!(report.issues.is_empty());
+        assert!(report.safe_to_delete_originals);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn changed_replays_are_corrupt() {
+        let replays = serde_json::json!([{ "path": "bad.slp", "sha256": hash_of("game two") }]);
+        let (dir, config) = archive("corrupt", &[("bad.slp", "game two, truncated")], replays);
+        assert_eq!(issue_kinds(&config), vec!["corrupt"]);
+        assert!(!verify_archive_at(&config, None).unwrap().safe_to_delete_originals);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn deleted_replays_are_missing() {
+        let replays = serde_json::json!([{ "path": "gone.slp", "sha256": hash_of("game one") }]);
+        let (dir, config) = archive("missing", &[], replays);
+        assert_eq!(issue_kinds(&config), vec!["missing"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn an_empty_archive_is_not_safe_to_delete() {
+        let (dir, config) = archive("empty", &[], serde_json::json!([]));
+        assert!(!verify_archive_at(&config, None).unwrap().safe_to_delete_originals);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unhashed_replays_are_flagged_until_backfilled() {
+        let replays = serde_json::json!([{ "path": "loose.slp" }, { "path": "gone.slp" }]);
+        let (dir, config) = archive("unhashed", &[("loose.slp", "game three")], replays);
+        assert_eq!(issue_kinds(&config), vec!["unhashed", "missing"]);
+        // Only replays that are still there get a checksum.
+        assert_eq!(add_missing_checksums(&config, None).unwrap(), 1);
+        assert_eq!(issue_kinds(&config), vec!["missing"]);
+        assert_eq!(add_missing_checksums(&config, None).unwrap(), 0);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
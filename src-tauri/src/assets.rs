@@ -0,0 +1,295 @@
+use crate::config::*;
+use crate::replay::{costume_names, map_character};
+use crate::types::{AppConfig, AllSetupsState, AssetPackReport, PlayerState};
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Stages the validator expects an image for.
+pub const LEGAL_STAGES: [&str; 6] = [
+    "Battlefield",
+    "Final Destination",
+    "Dream Land N64",
+    "Yoshi's Story",
+    "Fountain of Dreams",
+    "Pokemon Stadium",
+];
+
+/// Where a resolved asset was found; each root is served under its own prefix.
+struct AssetRoot {
+    dir: PathBuf,
+    url_prefix: &'static str,
+}
+
+// ── Pack lookup ─────────────────────────────────────────────────────────
+
+pub fn asset_pack_dir(config: &AppConfig) -> Option<PathBuf> {
+    let trimmed = config.asset_pack_path.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let dir = resolve_repo_path(trimmed);
+    dir.is_dir().then_some(dir)
+}
+
+/// Configured pack first, bundled overlay resources second.
+fn asset_roots(config: &AppConfig, bundled: &Path) -> Vec<AssetRoot> {
+    let mut roots = Vec::new();
+    if let Some(dir) = asset_pack_dir(config) {
+        roots.push(AssetRoot { dir, url_prefix: "/assets" });
+    }
+    roots.push(AssetRoot {
+        dir: bundled.to_path_buf(),
+        url_prefix: "/resources",
+    });
+    roots
+}
+
+/// Folder name used for a character in the pack. Popo and Nana both live
+/// under Ice Climbers.
+fn character_dir_name(character: &str) -> &str {
+    match character {
+        "Popo" | "Nana" => "Ice Climbers",
+        other => other,
+    }
+}
+
+fn portrait_rel(character: &str, color: &str) -> String {
+    format!("characters/portraits/{}/{}.png", character_dir_name(character), color)
+}
+
+/// Where a costume's stock icon may be, in lookup order. Sheik's icons sit
+/// in Zelda's folder as `Sheik <Color>.png`, as they do in the bundled
+/// resources; a pack with its own Sheik folder works too.
+fn stock_icon_rels(character: &str, color: &str) -> Vec<String> {
+    let mut rels = Vec::new();
+    if character == "Sheik" {
+        rels.push(format!("characters/stock_icons/Zelda/Sheik {color}.png"));
+    }
+    rels.push(format!("characters/stock_icons/{}/{}.png", character_dir_name(character), color));
+    rels
+}
+
+/// Stock icons to try for a player: their costume, the default costume,
+/// then the wireframe.
+fn stock_icon_candidates(character: &str, color: &str) -> Vec<String> {
+    let mut candidates = stock_icon_rels(character, color);
+    candidates.extend(stock_icon_rels(character, "Default"));
+    candidates.push("characters/stock_icons/Wireframe.png".to_string());
+    candidates
+}
+
+fn stage_rel(stage: &str) -> String {
+    format!("stages/{}.png", stage)
+}
+
+/// Return the URL of the first candidate that exists in any root, trying
+/// every root for one candidate before moving to the next.
+fn first_existing(roots: &[AssetRoot], candidates: &[String]) -> Option<String> {
+    for rel in candidates {
+        for root in roots {
+            if root.dir.join(rel).is_file() {
+                return Some(format!("{}/{}", root.url_prefix, encode_url_path(rel)));
+            }
+        }
+    }
+    None
+}
+
+fn encode_url_path(rel: &str) -> String {
+    let mut out = String::with_capacity(rel.len());
+    for byte in rel.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+// ── Overlay resolution ──────────────────────────────────────────────────
+
+fn resolve_player_assets(player: &mut PlayerState, roots: &[AssetRoot]) {
+    let character = player.character.trim();
+    if character.is_empty() {
+        return;
+    }
+    let color = player.character_color.trim();
+    let color = if color.is_empty() { "Default" } else { color };
+    player.portrait_url = first_existing(
+        roots,
+        &[portrait_rel(character, color), portrait_rel(character, "Default")],
+    );
+    player.stock_icon_url = first_existing(roots, &stock_icon_candidates(character, color));
+}
+
+/// Fill in portrait, stock icon and stage image URLs for every setup.
+pub fn apply_overlay_assets(state: &mut AllSetupsState, config: &AppConfig, bundled: &Path) {
+    let roots = asset_roots(config, bundled);
+    for setup in state.setups.iter_mut() {
+        resolve_player_assets(&mut setup.p1, &roots);
+        resolve_player_assets(&mut setup.p2, &roots);
+        setup.meta.stage_image_url = setup
+            .meta
+            .stage
+            .as_deref()
+            .map(str::trim)
+            .filter(|stage| !stage.is_empty())
+            .and_then(|stage| first_existing(&roots, &[stage_rel(stage)]));
+    }
+}
+
+/// Map a request path under `/assets` to a file inside the configured pack.
+/// Rejects anything that would escape the pack directory.
+fn pack_file_path(config: &AppConfig, rel: &str) -> Option<PathBuf> {
+    let dir = asset_pack_dir(config)?;
+    let rel = Path::new(rel);
+    if !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
+    }
+    let path = dir.join(rel);
+    path.is_file().then_some(path)
+}
+
+fn asset_content_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .as_deref()
+    {
+        Some("png") => "image/png",
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("webp") => "image/webp",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+// ── Validation ──────────────────────────────────────────────────────────
+
+/// Check a pack for every character/costume portrait and stock icon plus the
+/// legal stage images. Missing files aren't fatal (lookups fall back to the
+/// bundled resources), but they're reported so operators can fix the pack.
+pub fn validate_asset_pack_dir(dir: &Path) -> AssetPackReport {
+    // Each file may be in any of its places; a missing one is reported by
+    // the first.
+    let mut expected = Vec::new();
+    for id in 0u8..=0x19 {
+        let Some(character) = map_character(id) else {
+            continue;
+        };
+        for color in costume_names(character) {
+            expected.push(vec![portrait_rel(character, color)]);
+            expected.push(stock_icon_rels(character, color));
+        }
+    }
+    for stage in LEGAL_STAGES {
+        expected.push(vec![stage_rel(stage)]);
+    }
+    let missing = expected
+        .iter()
+        .filter(|rels| !rels.iter().any(|rel| dir.join(rel).is_file()))
+        .map(|rels| rels[0].clone())
+        .collect::<Vec<_>>();
+    AssetPackReport {
+        root: dir.to_string_lossy().to_string(),
+        checked: expected.len(),
+        missing,
+    }
+}
+
+/// Validate the configured pack at startup and log what's missing.
+pub fn log_asset_pack_warnings() {
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+    if config.asset_pack_path.trim().is_empty() {
+        return;
+    }
+    let Some(dir) = asset_pack_dir(&config) else {
+        tracing::warn!("Asset pack directory {} not found — using bundled resources", config.asset_pack_path);
+        return;
+    };
+    let report = validate_asset_pack_dir(&dir);
+    if !report.missing.is_empty() {
+        tracing::warn!(
+            "Asset pack {} is missing {} of {} files (first: {})",
+            report.root,
+            report.missing.len(),
+            report.checked,
+            report.missing[0]
+        );
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn validate_asset_pack(path: Option<String>) -> Result<AssetPackReport, String> {
+    let dir = match path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(raw) => resolve_repo_path(raw),
+        None => {
+            let config = cached_config()?;
+            asset_pack_dir(&config).ok_or_else(|| "No asset pack directory configured.".to_string())?
+        }
+    };
+    if !dir.is_dir() {
+        return Err(format!("Asset pack not found at {}", dir.display()));
+    }
+    Ok(validate_asset_pack_dir(&dir))
+}
+
+pub fn read_pack_file(config: &AppConfig, rel: &str) -> Option<(Vec<u8>, &'static str)> {
+    let path = pack_file_path(config, rel)?;
+    let bytes = fs::read(&path).ok()?;
+    Some((bytes, asset_content_type(&path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundled_roots() -> Vec<AssetRoot> {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../overlay/resources");
+        vec![AssetRoot { dir, url_prefix: "/resources" }]
+    }
+
+    #[test]
+    fn every_character_resolves_to_its_own_bundled_art() {
+        let roots = bundled_roots();
+        let characters = (0u8..=0x19).filter_map(map_character).chain(["Popo", "Nana"]);
+        for character in characters {
+            for color in costume_names(character) {
+                let portrait = first_existing(&roots, &[portrait_rel(character, color)]);
+                assert!(portrait.is_some(), "no portrait for {character} {color}");
+                let icon = first_existing(&roots, &stock_icon_candidates(character, color)).unwrap();
+                assert!(!icon.ends_with("Wireframe.png"), "{character} {color} fell back to {icon}");
+                if *color != "Default" {
+                    assert!(!icon.ends_with("Default.png"), "{character} {color} fell back to {icon}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sheik_icons_are_found_in_zeldas_folder() {
+        let roots = bundled_roots();
+        let icon = first_existing(&roots, &stock_icon_candidates("Sheik", "Purple"));
+        assert_eq!(icon.as_deref(), Some("/resources/characters/stock_icons/Zelda/Sheik%20Purple.png"));
+        let zelda = first_existing(&roots, &stock_icon_candidates("Zelda", "Purple"));
+        assert_eq!(zelda.as_deref(), Some("/resources/characters/stock_icons/Zelda/Purple.png"));
+        let climbers = first_existing(&roots, &stock_icon_candidates("Nana", "Red"));
+        assert_eq!(climbers.as_deref(), Some("/resources/characters/stock_icons/Ice%20Climbers/Red.png"));
+    }
+
+    #[test]
+    fn the_bundled_art_passes_validation_except_for_stages() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../overlay/resources");
+        let report = validate_asset_pack_dir(&dir);
+        let missing = report.missing.iter().filter(|rel| !rel.starts_with("stages/")).collect::<Vec<_>>();
+        assert!(missing.is_empty(), "{missing:?}");
+        assert_eq!(report.missing.len(), LEGAL_STAGES.len());
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::{
+    cell::RefCell,
+    fs::{self, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Commands invoked from this machine's UI.
+pub const AUDIT_ORIGIN_LOCAL: &str = "local";
+const DEFAULT_QUERY_LIMIT: usize = 500;
+
+/// Serializes appends so concurrent commands never interleave lines.
+static AUDIT_WRITE: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    /// Origin of the request this thread is serving; None means the local UI.
+    static ORIGIN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// One state-changing operator action. The log is append-only JSON lines, so
+/// post-event disputes can be settled from what was actually clicked.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub at_ms: u64,
+    /// "local" for the desktop UI, or "remote:<token name>"
+    pub origin: String,
+    pub action: String,
+    pub params: Value,
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AuditQuery {
+    pub action: Option<String>,
+    pub origin: Option<String>,
+    pub since_ms: Option<u64>,
+    pub until_ms: Option<u64>,
+    pub set_id: Option<u64>,
+    pub setup_id: Option<u32>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        if self.action.as_deref().is_some_and(|action| action != entry.action) {
+            return false;
+        }
+        if self.origin.as_deref().is_some_and(|origin| !entry.origin.starts_with(origin)) {
+            return false;
+        }
+        if self.since_ms.is_some_and(|since| entry.at_ms < since) {
+            return false;
+        }
+        if self.until_ms.is_some_and(|until| entry.at_ms > until) {
+            return false;
+        }
+        if let Some(set_id) = self.set_id {
+            if entry.params.get("setId").and_then(|v| v.as_u64()) != Some(set_id) {
+                return false;
+            }
+        }
+        if let Some(setup_id) = self.setup_id {
+            if entry.params.get("setupId").and_then(|v| v.as_u64()) != Some(u64::from(setup_id)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn audit_log_path() -> PathBuf {
+    repo_root().join("logs").join("operator_audit.jsonl")
+}
+
+/// Move the log into `dir` when an event is archived; the next entry
+/// starts a fresh one.
+pub fn archive_audit_log(dir: &Path) -> Result<(), String> {
+    let _guard = AUDIT_WRITE.lock().unwrap_or_else(|e| e.into_inner());
+    archive_file(&audit_log_path(), dir)
+}
+
+/// Undo `archive_audit_log`.
+pub fn restore_audit_log(dir: &Path) -> Result<(), String> {
+    let _guard = AUDIT_WRITE.lock().unwrap_or_else(|e| e.into_inner());
+    restore_archived_file(&audit_log_path(), dir)
+}
+
+/// Append an entry. Failures are logged, never returned: a full disk
+/// shouldn't stop the operator from reporting a set.
+pub fn record_action(origin: &str, action: &str, params: Value, error: Option<&str>) {
+    let entry = AuditEntry {
+        at_ms: now_ms(),
+        origin: origin.to_string(),
+        action: action.to_string(),
+        params,
+        ok: error.is_none(),
+        error: error.map(str::to_string),
+    };
+    let Ok(line) = serde_json::to_string(&entry) else {
+        return;
+    };
+    let _guard = AUDIT_WRITE.lock().unwrap_or_else(|e| e.into_inner());
+    let path = audit_log_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write audit log {}: {}", path.display(), e);
+    }
+}
+
+/// Run `f` with everything it audits recorded under `origin`, e.g. a remote
+/// API request running the same code paths as the desktop UI.
+pub fn with_origin<T>(origin: &str, f: impl FnOnce() -> T) -> T {
+    let previous = ORIGIN.with(|cell| cell.replace(Some(origin.to_string())));
+    let result = f();
+    ORIGIN.with(|cell| *cell.borrow_mut() = previous);
+    result
+}
+
+/// Record the outcome of a command, under the origin set by `with_origin`
+/// or as local.
+pub fn record_local<T>(action: &str, params: Value, result: &Result<T, String>) {
+    let error = result.as_ref().err().map(String::as_str);
+    ORIGIN.with(|cell| match cell.borrow().as_deref() {
+        Some(origin) => record_action(origin, action, params, error),
+        None => record_action(AUDIT_ORIGIN_LOCAL, action, params, error),
+    });
+}
+
+/// Run a local command body and record its outcome.
+pub fn audited<T>(action: &str, params: Value, f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let result = f();
+    record_local(action, params, &result);
+    result
+}
+
+pub fn read_audit_log(query: &AuditQuery) -> Result<Vec<AuditEntry>, String> {
+    let path = audit_log_path();
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let file = fs::File::open(&path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| format!("read {}: {e}", path.display()))?;
+        // A torn last line (crash mid-write) shouldn't hide the rest.
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) else {
+            continue;
+        };
+        if query.matches(&entry) {
+            entries.push(entry);
+        }
+    }
+    entries.reverse();
+    entries.truncate(query.limit.unwrap_or(DEFAULT_QUERY_LIMIT));
+    Ok(entries)
+}
+
+/// Operator actions, newest first.
+#[tauri::command]
+#[specta::specta]
+pub fn query_audit_log(query: Option<AuditQuery>) -> Result<Vec<AuditEntry>, String> {
+    read_audit_log(&query.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn entry(at_ms: u64, origin: &str, action: &str, params: Value) -> AuditEntry {
+        AuditEntry {
+            at_ms,
+            origin: origin.to_string(),
+            action: action.to_string(),
+            params,
+            ok: true,
+            error: None,
+        }
+    }
+
+    fn report() -> AuditEntry {
+        entry(2_000, AUDIT_ORIGIN_LOCAL, "forceWinner", json!({ "setId": 42, "reporter": "manual" }))
+    }
+
+    fn assign() -> AuditEntry {
+        entry(3_000, "remote:desk", "assignStream", json!({ "setupId": 2 }))
+    }
+
+    #[test]
+    fn an_empty_query_matches_everything() {
+        assert!(AuditQuery::default().matches(&report()));
+        assert!(AuditQuery::default().matches(&assign()));
+    }
+
+    #[test]
+    fn query_filters_by_action() {
+        let query = AuditQuery { action: Some("assignStream".to_string()), ..Default::default() };
+        assert!(query.matches(&assign()));
+        assert!(!query.matches(&report()));
+    }
+
+    #[test]
+    fn query_filters_by_origin_prefix() {
+        let query = AuditQuery { origin: Some("remote".to_string()), ..Default::default() };
+        assert!(query.matches(&assign()));
+        assert!(!query.matches(&report()));
+    }
+
+    #[test]
+    fn query_filters_by_time_window() {
+        let query = AuditQuery { since_ms: Some(2_500), until_ms: Some(3_000), ..Default::default() };
+        assert!(query.matches(&assign()));
+        assert!(!query.matches(&report()));
+    }
+
+    #[test]
+    fn query_filters_by_set_and_setup() {
+        let by_set = AuditQuery { set_id: Some(42), ..Default::default() };
+        assert!(by_set.matches(&report()));
+        assert!(!by_set.matches(&assign()));
+        let by_setup = AuditQuery { setup_id: Some(2), ..Default::default() };
+        assert!(by_setup.matches(&assign()));
+        assert!(!by_setup.matches(&report()));
+    }
+
+    #[test]
+    fn origin_applies_only_inside_with_origin() {
+        let origin = || ORIGIN.with(|cell| cell.borrow().clone());
+        assert_eq!(with_origin("remote:desk", origin).as_deref(), Some("remote:desk"));
+        assert_eq!(origin(), None);
+    }
+}
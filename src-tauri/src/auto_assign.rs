@@ -0,0 +1,303 @@
+use crate::audit_log::{audited, with_origin};
+use crate::auto_stop::leave_break;
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::friendlies::{assigned_set, set_decided};
+use crate::idle_mode::exit_idle;
+use crate::mirror::{end_mirror, mirror_links};
+use crate::run_of_show::current_bracket_state;
+use crate::slippi::{assign_stream, scan_slippi_streams_inner};
+use crate::startgg_sim::{StartggSimSet, StartggSimState};
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{cmp::Reverse, collections::HashSet, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with an `AutoAssignEvent` each time a live set's stream is put
+/// on a setup.
+pub const AUTO_ASSIGN_EVENT: &str = "setup-auto-assigned";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoAssignEvent {
+    pub setup_id: u32,
+    pub set_id: u64,
+    pub stream_id: String,
+    pub round: String,
+    pub at_ms: u64,
+}
+
+fn is_grand_final(set: &StartggSimSet) -> bool {
+    let label = set.round_label.trim().to_lowercase();
+    label.contains("grand") || label.starts_with("gf")
+}
+
+fn best_seed(set: &StartggSimSet) -> u32 {
+    set.slots.iter().filter_map(|slot| slot.seed).min().unwrap_or(u32::MAX)
+}
+
+/// Live sets with both players known, most important first.
+fn live_sets(state: &StartggSimState, priority: AutoAssignPriority) -> Vec<StartggSimSet> {
+    let mut sets = state
+        .sets
+        .iter()
+        .filter(|set| set.state == "inProgress" && set.slots.iter().all(|slot| slot.entrant_id.is_some()))
+        .cloned()
+        .collect::<Vec<_>>();
+    match priority {
+        AutoAssignPriority::RoundImportance => {
+            sets.sort_by_key(|set| (Reverse(is_grand_final(set)), Reverse(set.round.unsigned_abs()), best_seed(set)))
+        }
+        AutoAssignPriority::Seed => sets.sort_by_key(|set| (best_seed(set), set.started_at_ms.unwrap_or(u64::MAX))),
+        AutoAssignPriority::StartedFirst => {
+            sets.sort_by_key(|set| (set.started_at_ms.unwrap_or(u64::MAX), best_seed(set)))
+        }
+    }
+    sets
+}
+
+/// A scanned stream broadcast by one of the set's players.
+fn stream_for_set<'a>(set: &StartggSimSet, streams: &'a [SlippiStream]) -> Option<&'a SlippiStream> {
+    let codes = set
+        .slots
+        .iter()
+        .filter_map(|slot| slot.slippi_code.as_deref())
+        .map(normalize_broadcast_key)
+        .filter(|code| !code.is_empty())
+        .collect::<Vec<_>>();
+    streams.iter().find(|stream| {
+        stream
+            .p1_code
+            .as_deref()
+            .map(normalize_broadcast_key)
+            .is_some_and(|code| codes.contains(&code))
+    })
+}
+
+/// Free setups, lowest id first, and the live sets no setup is showing, in
+/// priority order. A setup is free when it has no stream or its set is
+/// over; mirrors and console stations are never free.
+fn plan(
+    setups: &[Setup],
+    bracket: &StartggSimState,
+    priority: AutoAssignPriority,
+    mirrors: &HashSet<u32>,
+) -> (Vec<u32>, Vec<StartggSimSet>) {
+    let mut shown = HashSet::new();
+    let mut free = Vec::new();
+    for setup in setups.iter().filter(|setup| !mirrors.contains(&setup.id)) {
+        let set = setup
+            .assigned_stream
+            .as_ref()
+            .and_then(|stream| assigned_set(setup.id, stream, Some(bracket)));
+        // Console stations show whatever set is called to them, which only
+        // the operator knows; they're never free.
+        let console = setup.capture_source_id.is_some();
+        match (&setup.assigned_stream, set) {
+            (None, _) if !console => free.push(setup.id),
+            (Some(_), Some(set)) if set_decided(&set) => {
+                if !console {
+                    free.push(setup.id);
+                }
+            }
+            (Some(_), Some(set)) => {
+                shown.insert(set.id);
+            }
+            // Put there by hand for something off-bracket; leave it be.
+            _ => {}
+        }
+    }
+    free.sort_unstable();
+    let sets = live_sets(bracket, priority)
+        .into_iter()
+        .filter(|set| !shown.contains(&set.id))
+        .collect();
+    (free, sets)
+}
+
+fn assign_live_sets(app: &AppHandle, config: &AppConfig, attempted: &mut HashSet<u64>) {
+    let store = app.state::<SharedSetupStore>().inner().clone();
+    let test_state = app.state::<SharedTestState>().inner().clone();
+    let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+    let Some(bracket) = current_bracket_state(&test_state, &live_startgg) else {
+        return;
+    };
+    let setups = store.lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+    let mirrors = mirror_links().iter().map(|link| link.mirror_id).collect::<HashSet<_>>();
+    let (free, sets) = plan(&setups, &bracket, config.auto_assign_priority, &mirrors);
+    let sets = sets.into_iter().filter(|set| !attempted.contains(&set.id)).collect::<Vec<_>>();
+    if free.is_empty() || sets.is_empty() {
+        return;
+    }
+    let replay_cache = app.state::<SharedOverlayCache>().inner().clone();
+    let streams = match scan_slippi_streams_inner(app, &test_state, &replay_cache, &store) {
+        Ok(streams) => streams,
+        Err(err) => {
+            tracing::warn!("Auto-assign couldn't scan streams: {}", err);
+            return;
+        }
+    };
+    let taken = setups
+        .iter()
+        .filter_map(|setup| setup.assigned_stream.as_ref().map(|stream| stream.id.clone()))
+        .collect::<HashSet<_>>();
+    let streams = streams.into_iter().filter(|stream| !taken.contains(&stream.id)).collect::<Vec<_>>();
+
+    let mut free = free.into_iter();
+    for set in sets {
+        // The players may not be broadcasting yet; try again next time.
+        let Some(stream) = stream_for_set(&set, &streams) else {
+            continue;
+        };
+        let Some(setup_id) = free.next() else {
+            break;
+        };
+        attempted.insert(set.id);
+        let params = json!({ "setupId": setup_id, "setId": set.id, "streamId": stream.id });
+        let result = with_origin("auto", || {
+            audited("autoAssignStream", params, || {
+                assign_stream(setup_id, stream.clone(), Some(true), &store, &test_state)
+            })
+        });
+        match result {
+            Ok(result) => {
+                exit_idle(&format!("set {} went live", set.id));
+                leave_break(setup_id);
+                end_mirror(setup_id);
+                if let Some(warning) = result.warning {
+                    tracing::warn!("Auto-assign setup {}: {}", setup_id, warning);
+                }
+                tracing::info!("Set {} ({}) is live; streaming it on setup {}", set.id, set.round_label, setup_id);
+                let event = AutoAssignEvent {
+                    setup_id,
+                    set_id: set.id,
+                    stream_id: stream.id.clone(),
+                    round: set.round_label.clone(),
+                    at_ms: now_ms(),
+                };
+                let _ = app.emit(AUTO_ASSIGN_EVENT, &event);
+            }
+            Err(err) => tracing::warn!("Auto-assigning set {} to setup {}: {}", set.id, setup_id, err),
+        }
+    }
+}
+
+/// Put each set that goes live on a free setup, most important sets first,
+/// while `auto_assign_enabled` is on. Each set is tried once; after that
+/// it's the operator's.
+pub fn spawn_auto_assign_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut attempted: HashSet<u64> = HashSet::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let Ok(config) = cached_config() else {
+                continue;
+            };
+            if !config.auto_assign_enabled || !feature_enabled(FeatureFlag::AutoSpectate) {
+                continue;
+            }
+            assign_live_sets(&app, &config, &mut attempted);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::StartggSimSlot;
+
+    fn set(id: u64, round: i32, label: &str, state: &str, seeds: [u32; 2]) -> StartggSimSet {
+        let slot = |seed: u32| StartggSimSlot {
+            entrant_id: Some(seed),
+            entrant_name: Some(format!("P{seed}")),
+            slippi_code: Some(format!("P{seed}#000")),
+            seed: Some(seed),
+            ..Default::default()
+        };
+        StartggSimSet {
+            id,
+            phase_id: "phase".to_string(),
+            phase_name: "Bracket".to_string(),
+            round,
+            round_label: label.to_string(),
+            best_of: 3,
+            state: state.to_string(),
+            started_at_ms: Some(id),
+            slots: seeds.into_iter().map(slot).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn stream(code: &str) -> SlippiStream {
+        SlippiStream {
+            id: format!("codes:{code}"),
+            p1_code: Some(code.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn bracket() -> StartggSimState {
+        let mut bracket = StartggSimState::warming(0);
+        bracket.warming = false;
+        bracket.sets = vec![
+            set(1, 1, "Winners Round 1", "inProgress", [1, 8]),
+            set(2, -3, "Losers Round 3", "inProgress", [6, 7]),
+            set(3, 0, "Grand Finals", "inProgress", [2, 3]),
+            set(4, 2, "Winners Round 2", "pending", [4, 5]),
+        ];
+        bracket
+    }
+
+    /// Setup 1 already shows set 1.
+    fn setups() -> Vec<Setup> {
+        let mut setups = SetupStore::bootstrap_from_existing().setups;
+        setups[0].assigned_stream = Some(stream("P1#000"));
+        setups
+    }
+
+    fn ids(sets: Vec<StartggSimSet>) -> Vec<u64> {
+        sets.iter().map(|set| set.id).collect()
+    }
+
+    #[test]
+    fn round_importance_puts_grand_finals_then_later_rounds_first() {
+        assert_eq!(ids(live_sets(&bracket(), AutoAssignPriority::RoundImportance)), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn seed_priority_puts_the_best_seed_first() {
+        assert_eq!(ids(live_sets(&bracket(), AutoAssignPriority::Seed)), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn started_first_priority_goes_by_start_time() {
+        assert_eq!(ids(live_sets(&bracket(), AutoAssignPriority::StartedFirst)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn setups_showing_a_live_set_and_mirrors_are_not_free() {
+        let (free, sets) = plan(&setups(), &bracket(), AutoAssignPriority::RoundImportance, &HashSet::from([3]));
+        assert_eq!(free, vec![2]);
+        assert_eq!(ids(sets), vec![3, 2]);
+    }
+
+    #[test]
+    fn a_set_gets_the_stream_of_one_of_its_players() {
+        let bracket = bracket();
+        let streams = vec![stream("P9#000"), stream("p3#000")];
+        assert_eq!(stream_for_set(&bracket.sets[2], &streams).map(|s| s.id.as_str()), Some("codes:p3#000"));
+        assert!(stream_for_set(&bracket.sets[1], &streams).is_none());
+    }
+
+    #[test]
+    fn console_stations_are_never_handed_a_stream() {
+        let mut setups = setups();
+        setups[1].capture_source_id = Some("elgato-1".to_string());
+        let (free, _) = plan(&setups, &bracket(), AutoAssignPriority::RoundImportance, &HashSet::from([3]));
+        assert!(free.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::friendlies::{assigned_set, set_decided};
+use crate::run_of_show::current_bracket_state;
+use crate::slippi::clear_assignment;
+use crate::types::*;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Setups showing the break screen, with the set that sent them there.
+static ON_BREAK: Mutex<Vec<(u32, u64)>> = Mutex::new(Vec::new());
+
+/// Payload of `setup-auto-stopped`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoStopEvent {
+    pub setup_id: u32,
+    pub set_id: u64,
+    pub action: AutoStopAction,
+    pub at_ms: u64,
+}
+
+/// Whether a setup whose set was decided at `decided_at_ms` has waited out
+/// its grace period.
+fn stop_due(policy: &AutoStopPolicy, decided_at_ms: u64, now_ms: u64) -> bool {
+    policy.action != AutoStopAction::Off && now_ms.saturating_sub(decided_at_ms) >= policy.grace_secs.saturating_mul(1000)
+}
+
+fn break_set(setup_id: u32) -> Option<u64> {
+    let on_break = ON_BREAK.lock().unwrap_or_else(|e| e.into_inner());
+    on_break.iter().find(|(id, _)| *id == setup_id).map(|(_, set_id)| *set_id)
+}
+
+/// Take a setup off the break screen, e.g. once a new stream is assigned.
+pub fn leave_break(setup_id: u32) {
+    ON_BREAK.lock().unwrap_or_else(|e| e.into_inner()).retain(|(id, _)| *id != setup_id);
+}
+
+/// Flag the overlays of setups on break so scenes show the break screen.
+pub fn apply_break_screens(state: &mut AllSetupsState) {
+    let on_break = ON_BREAK.lock().unwrap_or_else(|e| e.into_inner());
+    for (setup_id, _) in on_break.iter() {
+        let idx = (*setup_id as usize).checked_sub(1);
+        if let Some(overlay) = idx.and_then(|idx| state.setups.get_mut(idx)) {
+            overlay.meta.on_break = true;
+        }
+    }
+}
+
+fn stop_setup(store: &SharedSetupStore, setup_id: u32, set_id: u64, action: AutoStopAction) -> Result<(), String> {
+    match action {
+        AutoStopAction::Off => Ok(()),
+        AutoStopAction::ClearAssignment => clear_assignment(setup_id, Some(true), store).map(|_| ()),
+        AutoStopAction::BreakScene => {
+            leave_break(setup_id);
+            ON_BREAK.lock().unwrap_or_else(|e| e.into_inner()).push((setup_id, set_id));
+            Ok(())
+        }
+    }
+}
+
+/// Apply each setup's auto-stop policy once its set is decided and the
+/// grace period is up, and emit `setup-auto-stopped`. A setup on break
+/// comes back when its players' next set shows up.
+pub fn spawn_auto_stop_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        // When each (setup, set) was first seen decided but not reported.
+        let mut first_decided: HashMap<(u32, u64), u64> = HashMap::new();
+        let mut handled: HashSet<(u32, u64)> = HashSet::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if !feature_enabled(FeatureFlag::AutoStop) {
+                continue;
+            }
+            let store = app.state::<SharedSetupStore>().inner().clone();
+            let setups = store.lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+            let watching = setups
+                .iter()
+                .any(|setup| setup.auto_stop.action != AutoStopAction::Off || break_set(setup.id).is_some());
+            if !watching {
+                continue;
+            }
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let bracket = current_bracket_state(&test_state, &live_startgg);
+            let now = now_ms();
+            for setup in setups {
+                let Some(stream) = setup.assigned_stream.as_ref() else {
+                    continue;
+                };
+                let Some(set) = assigned_set(setup.id, stream, bracket.as_ref()) else {
+                    continue;
+                };
+                if !set_decided(&set) {
+                    if break_set(setup.id).is_some_and(|break_id| break_id != set.id) {
+                        leave_break(setup.id);
+                    }
+                    continue;
+                }
+                let key = (setup.id, set.id);
+                if handled.contains(&key) {
+                    continue;
+                }
+                let seen_at = *first_decided.entry(key).or_insert(now);
+                if !stop_due(&setup.auto_stop, set.completed_at_ms.unwrap_or(seen_at), now) {
+                    continue;
+                }
+                handled.insert(key);
+                match stop_setup(&store, setup.id, set.id, setup.auto_stop.action) {
+                    Ok(()) => {
+                        tracing::info!("Setup {}: set {} is over ({:?})", setup.id, set.id, setup.auto_stop.action);
+                        let event = AutoStopEvent {
+                            setup_id: setup.id,
+                            set_id: set.id,
+                            action: setup.auto_stop.action,
+                            at_ms: now,
+                        };
+                        let _ = app.emit("setup-auto-stopped", &event);
+                    }
+                    Err(err) => tracing::warn!("Setup {}: auto-stop failed: {}", setup.id, err),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn break_after(grace_secs: u64) -> AutoStopPolicy {
+        AutoStopPolicy { action: AutoStopAction::BreakScene, grace_secs }
+    }
+
+    #[test]
+    fn stops_once_the_grace_period_is_up() {
+        assert!(!stop_due(&break_after(30), 10_000, 39_999));
+        assert!(stop_due(&break_after(30), 10_000, 40_000));
+        assert!(stop_due(&break_after(0), 10_000, 10_000));
+    }
+
+    #[test]
+    fn a_completion_time_from_another_clock_does_not_underflow() {
+        assert!(!stop_due(&break_after(30), 50_000, 40_000));
+    }
+
+    #[test]
+    fn off_never_stops() {
+        assert!(!stop_due(&AutoStopPolicy::default(), 0, u64::MAX));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::archive::ArchiveVerification;
+use crate::audit_log::{AuditEntry, AuditQuery};
+use crate::auto_assign::AutoAssignEvent;
+use crate::auto_stop::AutoStopEvent;
+use crate::bracket_config::{BracketConfigFile, BracketMigrationReport};
+use crate::broadcast_watch::BroadcastEnded;
+use crate::capabilities::Capabilities;
+use crate::capture_sources::CaptureSource;
+use crate::capture_windows::CaptureArrangement;
+use crate::checklist::{Checklist, ChecklistRun};
+use crate::commentators::CommentatorBoard;
+use crate::config::*;
+use crate::config_transaction::ConfigChange;
+use crate::connect_codes::ReplayCodes;
+use crate::countdowns::{CountdownBoardState, CountdownRequest, CountdownUpdate};
+use crate::crowd_prompts::{CrowdPromptState, PromptRequest};
+use crate::entrant_audit::EntrantAuditReport;
+use crate::entrant_commands::SetupWithSeed;
+use crate::event_templates::{CurrentEvent, EventTemplate, NewEventReport};
+use crate::exhibition::ExhibitionSet;
+use crate::feature_flags::FeatureFlagStatus;
+use crate::forecast::BracketForecast;
+use crate::frame_export::SetAnalysisExport;
+use crate::friendlies::GameClassification;
+use crate::fun_facts::FunFact;
+use crate::idle_mode::SubsystemStatus;
+use crate::jobs::JobProgress;
+use crate::launcher_watchdog::LauncherAlert;
+use crate::linked_bracket::BracketLinkStatus;
+use crate::mirror::{MirrorLink, MirrorStopped};
+use crate::network::NetworkStatus;
+use crate::obs::{ObsSceneList, ObsSetupBinding, ObsStatus};
+use crate::playback::{PlaybackSession, PreparedSetPlayback};
+use crate::process_health::SetupProcessStatus;
+use crate::player_db::PlayerProfile;
+use crate::ratings::{PlayerRating, RatedSet};
+use crate::ready_check::SetReadyStatus;
+use crate::remote_api::{NewRemoteToken, RemoteApiStatus, RemoteTokenInfo};
+use crate::replay_frames::ReplayFrames;
+use crate::replay_upload::SetReplayManifest;
+use crate::reseed::ReseedReport;
+use crate::result_card::ResultCardState;
+use crate::run_of_show::{RunOfShow, ScheduleTimeline};
+use crate::score_tracker::SetupScore;
+use crate::session_stats::SessionSummary;
+use crate::setup_meta::SetupMetadata;
+use crate::set_codes::QuickAssignResult;
+use crate::slippi_login::SlippiLoginStatus;
+use crate::stage_strikes::SetStageStrikes;
+use crate::startgg_report::{SetResultProposal, StartggReportResult};
+use crate::startgg_sim::{StartggSimConfig, StartggSimSetFilter};
+use crate::startgg_webhook::StartggWebhookStatus;
+use crate::types::*;
+use crate::specta_builder;
+use specta_typescript::{BigIntExportBehavior, Typescript};
+use std::path::{Path, PathBuf};
+use tauri_specta::Builder;
+
+const HEADER: &str = "\
+// Generated from the Rust commands and payload types; do not edit.
+// Regenerate with `npm run bindings` after changing a command or event payload.";
+
+/// Every event the backend emits and the payload type it carries.
+const EVENTS: &[(&str, &str)] = &[
+    ("app-capabilities", "Capabilities"),
+    ("bracket-forecast", "BracketForecast"),
+    ("config-changed", "ConfigChange"),
+    ("feature-flag-changed", "FeatureFlagStatus"),
+    ("game-detected", "GameClassification"),
+    ("idle-mode", "SubsystemStatus"),
+    ("job-progress", "JobProgress"),
+    ("mirror-stopped", "MirrorStopped"),
+    ("network-status", "NetworkStatus"),
+    ("playback-error", "{ setupId: number; message: string }"),
+    ("playback-frame-pacing", "FramePacingStatus"),
+    ("playback-telemetry", "PlaybackBufferStatus"),
+    ("run-of-show-behind", "ScheduleTimeline"),
+    ("score-discrepancy", "ScoreReconciliation"),
+    ("session-summary", "SessionSummary"),
+    ("setup-auto-assigned", "AutoAssignEvent"),
+    ("setup-auto-stopped", "AutoStopEvent"),
+    ("setup-deleting-live", "SetupDeleteWarning"),
+    ("setup-process-status", "SetupProcessStatus"),
+    ("setup-score-updated", "SetupScore"),
+    ("shutdown-complete", "ShutdownReport"),
+    ("shutdown-progress", "StartupStep"),
+    ("slippi-broadcast-ended", "BroadcastEnded"),
+    ("slippi-launcher-alert", "LauncherAlert"),
+    ("slippi-needs-login", "SlippiLoginStatus"),
+    ("spoof-replay-progress", "unknown"),
+    ("startgg-result-proposed", "SetResultProposal"),
+    ("startup-complete", "StartupReport"),
+    ("startup-progress", "StartupStep"),
+];
+
+/// Default output: the frontend's types folder.
+pub fn default_bindings_path() -> PathBuf {
+    repo_root().join("src").join("types").join("bindings.ts")
+}
+
+/// Register every command and event payload type by name; event payloads
+/// aren't in any command signature. Registering a type pulls in the types
+/// it refers to, so only the outermost ones are listed.
+fn with_payload_types(builder: Builder<tauri::Wry>) -> Builder<tauri::Wry> {
+    builder
+        // Setups and streams
+        .typ::<Setup>()
+        .typ::<SetupMetadata>()
+        .typ::<SetupsPayload>()
+        .typ::<SetupWithSeed>()
+        .typ::<SetupDeleteWarning>()
+        .typ::<AssignStreamResult>()
+        .typ::<QuickAssignResult>()
+        .typ::<SetReadyStatus>()
+        .typ::<SlippiWindowInfo>()
+        .typ::<CaptureArrangement>()
+        .typ::<CaptureSource>()
+        .typ::<MirrorLink>()
+        .typ::<MirrorStopped>()
+        .typ::<ExhibitionSet>()
+        .typ::<SetStageStrikes>()
+        .typ::<GameClassification>()
+        .typ::<BroadcastEnded>()
+        .typ::<SetupProcessStatus>()
+        .typ::<SetupScore>()
+        .typ::<ObsSetupBinding>()
+        // Overlay
+        .typ::<AllSetupsState>()
+        .typ::<CommentatorBoard>()
+        .typ::<SetResultSnapshot>()
+        .typ::<CrowdPromptState>()
+        .typ::<PromptRequest>()
+        .typ::<CountdownBoardState>()
+        .typ::<CountdownRequest>()
+        .typ::<CountdownUpdate>()
+        .typ::<ResultCardState>()
+        .typ::<FunFact>()
+        .typ::<PlayerProfile>()
+        // Bracket
+        .typ::<StartggLiveSnapshot>()
+        .typ::<StartggSimConfig>()
+        .typ::<StartggSimSetFilter>()
+        .typ::<StartggWebhookStatus>()
+        .typ::<BracketConfigInfo>()
+        .typ::<BracketConfigFile>()
+        .typ::<BracketMigrationReport>()
+        .typ::<BracketPersistenceStatus>()
+        .typ::<BracketLinkStatus>()
+        .typ::<BracketForecast>()
+        .typ::<PendingReport>()
+        .typ::<ScoreReconciliation>()
+        .typ::<SetResultProposal>()
+        .typ::<StartggReportResult>()
+        .typ::<ReseedReport>()
+        .typ::<EntrantAuditReport>()
+        .typ::<RunOfShow>()
+        .typ::<ScheduleTimeline>()
+        .typ::<UnifiedEntrant>()
+        .typ::<PlayerRating>()
+        .typ::<RatedSet>()
+        // Replays and playback
+        .typ::<PlaybackSession>()
+        .typ::<PreparedSetPlayback>()
+        .typ::<FramePacingStatus>()
+        .typ::<ReplayFrames>()
+        .typ::<SetReplayManifest>()
+        .typ::<ReplayCodes>()
+        .typ::<SetAnalysisExport>()
+        .typ::<ArchiveVerification>()
+        .typ::<SpoofReplayResult>()
+        // App
+        .typ::<AppConfig>()
+        .typ::<ConfigChange>()
+        .typ::<Checklist>()
+        .typ::<ChecklistRun>()
+        .typ::<FeatureFlagStatus>()
+        .typ::<ObsStatus>()
+        .typ::<ObsSceneList>()
+        .typ::<RemoteApiStatus>()
+        .typ::<RemoteTokenInfo>()
+        .typ::<RemoteToken>()
+        .typ::<NewRemoteToken>()
+        .typ::<AssetPackReport>()
+        .typ::<Capabilities>()
+        .typ::<StartupReport>()
+        .typ::<ShutdownReport>()
+        .typ::<EventTemplate>()
+        .typ::<CurrentEvent>()
+        .typ::<NewEventReport>()
+        .typ::<SubsystemStatus>()
+        .typ::<NetworkStatus>()
+        .typ::<JobProgress>()
+        .typ::<LauncherAlert>()
+        .typ::<SlippiLoginStatus>()
+        .typ::<AutoAssignEvent>()
+        .typ::<AutoStopEvent>()
+        .typ::<SessionSummary>()
+        .typ::<AuditEntry>()
+        .typ::<AuditQuery>()
+        .typ::<CdpTarget>()
+        .typ::<TestPlayerProfile>()
+}
+
+/// TypeScript for every command (a typed `commands` wrapper around
+/// `invoke`) and payload type, plus an `AppEvents` map from event name to
+/// payload for typing `listen` calls.
+pub fn bindings_source() -> Result<String, String> {
+    // serde_json writes u64 as a plain number, not a BigInt.
+    let exporter = Typescript::default()
+        .bigint(BigIntExportBehavior::Number)
+        .header(HEADER);
+    let mut source = with_payload_types(specta_builder())
+        .export_str(exporter)
+        .map_err(|e| format!("export bindings: {e}"))?;
+    source.push_str("\nexport type AppEvents = {\n");
+    for (event, payload) in EVENTS {
+        source.push_str(&format!("  \"{event}\": {payload};\n"));
+    }
+    source.push_str("};\n");
+    Ok(source)
+}
+
+/// Write the bindings to `path`, or the frontend's types folder.
+pub fn write_bindings(path: Option<&Path>) -> Result<PathBuf, String> {
+    let path = path.map(Path::to_path_buf).unwrap_or_else(default_bindings_path);
+    let source = bindings_source()?;
+    write_file_atomic(&path, source.as_bytes()).map_err(|e| format!("write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Dev builds only: regenerate the frontend's TypeScript bindings.
+#[tauri::command]
+#[specta::specta]
+pub fn generate_bindings(path: Option<String>) -> Result<String, String> {
+    if !cfg!(debug_assertions) {
+        return Err("Bindings can only be generated from a dev build.".to_string());
+    }
+    let path = path.map(|raw| resolve_repo_path(&raw));
+    write_bindings(path.as_deref()).map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn bindings_cover_every_command_and_event_payload() {
+        let source = bindings_source().unwrap();
+        assert!(source.contains("export type SlippiStream = "));
+        assert!(source.contains("export type StartggSimSet = "));
+        assert!(source.contains("async assignStreamToSetup("));
+        assert!(source.contains("async startggSimRawCompleteBracket("));
+        for (event, payload) in EVENTS {
+            let named = payload.chars().next().is_some_and(|c| c.is_ascii_uppercase());
+            if named {
+                assert!(source.contains(&format!("export type {payload} = ")), "{event}: {payload}");
+            }
+        }
+        // u64 ids come through as numbers.
+        assert!(!source.contains("bigint"));
+    }
+
+    #[test]
+    fn committed_bindings_are_up_to_date() {
+        let committed = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("..")
+            .join("src")
+            .join("types")
+            .join("bindings.ts");
+        let committed = fs::read_to_string(&committed).unwrap_or_default();
+        assert!(
+            committed == bindings_source().unwrap(),
+            "src/types/bindings.ts is stale; regenerate it with `npm run bindings`."
+        );
+    }
+}
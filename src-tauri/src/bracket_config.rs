@@ -0,0 +1,695 @@
+use crate::config::{resolve_repo_path, resolve_startgg_sim_config_path, write_file_atomic};
+use crate::slp_scan::resolve_zip_member;
+use crate::startgg_sim::StartggSimConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Current layout of bracket config files. Files without `schemaVersion`
+/// predate it and may use snake_case keys or bare replay path strings;
+/// `migrate_bracket_config` rewrites them.
+pub const BRACKET_CONFIG_SCHEMA_VERSION: u32 = 1;
+
+// ── Replay map ──────────────────────────────────────────────────────────
+//
+// `referenceReplayMap` ties bracket sets to the replay files recorded for
+// them, so test mode can replay a whole set.
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketReplaySlot {
+    #[serde(default)]
+    pub slippi_code: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketReplayEntry {
+    /// Absolute, or relative to `replaysDir` (the repo root when unset)
+    #[serde(default)]
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub slots: Vec<BracketReplaySlot>,
+    /// Hex sha256 of the file when it was archived, checked by `verify_archive`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketReplaySet {
+    pub id: u64,
+    #[serde(default)]
+    pub replays: Vec<BracketReplayEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketReplayMap {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replays_dir: Option<String>,
+    #[serde(default)]
+    pub sets: Vec<BracketReplaySet>,
+}
+
+impl BracketReplayMap {
+    pub fn set(&self, set_id: u64) -> Option<&BracketReplaySet> {
+        self.sets.iter().find(|set| set.id == set_id)
+    }
+
+    /// Resolve a recorded replay path against `replaysDir`, or the repo root
+    /// when unset. None for empty paths.
+    pub fn resolve_replay_path(&self, raw: &str) -> Option<PathBuf> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return None;
+        }
+        let path = PathBuf::from(raw);
+        let path = if path.is_absolute() {
+            path
+        } else if let Some(base) = self.replays_dir.as_deref() {
+            resolve_repo_path(base).join(&path)
+        } else {
+            resolve_repo_path(raw)
+        };
+        // `pack.zip/game1.slp` reads game1.slp out of the zip.
+        Some(resolve_zip_member(&path))
+    }
+
+    /// Replay paths for `set` in game order, resolved and deduplicated.
+    pub fn replay_paths(&self, set: &BracketReplaySet) -> Vec<PathBuf> {
+        let mut seen = HashSet::new();
+        let mut out = Vec::new();
+        for replay in &set.replays {
+            let Some(path) = self.resolve_replay_path(&replay.path) else {
+                continue;
+            };
+            if seen.insert(path.clone()) {
+                out.push(path);
+            }
+        }
+        out
+    }
+
+    /// Replace the replays recorded for `set_id`, adding the set if needed.
+    pub fn set_replays(&mut self, set_id: u64, replays: Vec<BracketReplayEntry>) {
+        match self.sets.iter_mut().find(|set| set.id == set_id) {
+            Some(set) => set.replays = replays,
+            None => self.sets.push(BracketReplaySet { id: set_id, replays }),
+        }
+    }
+}
+
+/// The replay map on its own, so it can be read without the rest of the
+/// config having to parse.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReplayMapSection {
+    #[serde(default)]
+    reference_replay_map: Option<BracketReplayMap>,
+}
+
+/// Like `ReplayMapSection`, with the sets left as JSON to be read one by one.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReplayMapSection {
+    #[serde(default)]
+    reference_replay_map: Option<RawReplayMap>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawReplayMap {
+    #[serde(default)]
+    replays_dir: Option<String>,
+    #[serde(default)]
+    sets: Vec<Value>,
+}
+
+/// Read `referenceReplayMap` from the bracket config at `path`. A set that
+/// doesn't parse is skipped with a warning instead of hiding every other
+/// set; `validate_bracket_config` says what's wrong with it.
+pub fn read_replay_map(path: &Path) -> Result<Option<BracketReplayMap>, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("read bracket config {}: {e}", path.display()))?;
+    let section: RawReplayMapSection = serde_json::from_str(&data)
+        .map_err(|e| format!("parse bracket config {}: {e}", path.display()))?;
+    Ok(section.reference_replay_map.map(|raw| {
+        let sets = raw
+            .sets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, set)| match BracketReplaySet::deserialize(set) {
+                Ok(set) => Some(set),
+                Err(e) => {
+                    tracing::warn!("Skipping referenceReplayMap.sets[{}] in {}: {}", idx, path.display(), e);
+                    None
+                }
+            })
+            .collect();
+        BracketReplayMap { replays_dir: raw.replays_dir, sets }
+    }))
+}
+
+/// Replace `referenceReplayMap` in the bracket config at `path`, leaving
+/// every other key as it was. Sets `read_replay_map` skipped are written
+/// back untouched, so saving the map doesn't lose them.
+pub fn write_replay_map(path: &Path, replay_map: &BracketReplayMap) -> Result<(), String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("read bracket config {}: {e}", path.display()))?;
+    let mut value: Value =
+        serde_json::from_str(&data).map_err(|e| format!("parse bracket config {}: {e}", path.display()))?;
+    let root = value
+        .as_object_mut()
+        .ok_or_else(|| "Bracket config must be a JSON object.".to_string())?;
+    let unreadable = root
+        .get("referenceReplayMap")
+        .and_then(|map| map.get("sets"))
+        .and_then(Value::as_array)
+        .map(|sets| {
+            sets.iter()
+                .filter(|set| BracketReplaySet::deserialize(*set).is_err())
+                .cloned()
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut map_value = serde_json::to_value(replay_map).map_err(|e| e.to_string())?;
+    if let Some(sets) = map_value.get_mut("sets").and_then(Value::as_array_mut) {
+        sets.extend(unreadable);
+    }
+    root.insert("referenceReplayMap".to_string(), map_value);
+    write_bracket_value(path, &value)
+}
+
+/// Set the `seed` of each entrant in `seeds` (entrant id to seed) in the
+/// bracket config at `path`, leaving every other key as it was.
+pub fn write_entrant_seeds(path: &Path, seeds: &HashMap<u32, u32>) -> Result<(), String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("read bracket config {}: {e}", path.display()))?;
+    let mut value: Value =
+        serde_json::from_str(&data).map_err(|e| format!("parse bracket config {}: {e}", path.display()))?;
+    let entrants = value
+        .get_mut("entrants")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| "Bracket config has no entrants list.".to_string())?;
+    for entrant in entrants.iter_mut() {
+        let id = entrant.get("id").and_then(Value::as_u64).and_then(|id| u32::try_from(id).ok());
+        if let Some(seed) = id.and_then(|id| seeds.get(&id)) {
+            if let Some(entrant) = entrant.as_object_mut() {
+                entrant.insert("seed".to_string(), Value::from(*seed));
+            }
+        }
+    }
+    write_bracket_value(path, &value)
+}
+
+fn write_bracket_value(path: &Path, value: &Value) -> Result<(), String> {
+    let payload = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    write_file_atomic(path, payload.as_bytes()).map_err(|e| format!("write bracket config {}: {e}", path.display()))
+}
+
+// ── Full config ─────────────────────────────────────────────────────────
+
+/// Everything a bracket config file holds: the sim config plus the replay
+/// map and schema version that only the file cares about.
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketConfigFile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+    #[serde(flatten)]
+    pub sim: StartggSimConfig,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_replay_map: Option<BracketReplayMap>,
+}
+
+// ── Validation ──────────────────────────────────────────────────────────
+
+#[derive(Clone, Debug, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketConfigIssue {
+    /// Path of the offending field, e.g. `entrants[3].id`; empty when the
+    /// parser couldn't tell
+    pub field: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl BracketConfigIssue {
+    fn at(field: impl Into<String>, message: impl Into<String>) -> Self {
+        BracketConfigIssue {
+            field: field.into(),
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    fn from_parse(section: &str, err: serde_json::Error) -> Self {
+        // serde_json appends the position to its message; it's kept in
+        // line/column instead.
+        let message = err.to_string();
+        let position = format!(" at line {} column {}", err.line(), err.column());
+        BracketConfigIssue {
+            field: section.to_string(),
+            line: Some(err.line()),
+            column: Some(err.column()),
+            message: message.strip_suffix(&position).unwrap_or(&message).to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for BracketConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, "line {line}, column {column}: ")?;
+        }
+        if !self.field.is_empty() {
+            write!(f, "{}: ", self.field)?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Check bracket config JSON against the schema. Parse errors carry line
+/// and column; checks on parsed values carry the field path.
+pub fn validate_bracket_config_str(text: &str) -> Vec<BracketConfigIssue> {
+    let value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(err) => return vec![BracketConfigIssue::from_parse("", err)],
+    };
+    if !value.is_object() {
+        return vec![BracketConfigIssue::at("", "Bracket config must be a JSON object.")];
+    }
+
+    let mut issues = Vec::new();
+    if let Some(version) = value.get("schemaVersion") {
+        match version.as_u64() {
+            Some(version) if version as u32 == BRACKET_CONFIG_SCHEMA_VERSION => {}
+            Some(version) if version as u32 > BRACKET_CONFIG_SCHEMA_VERSION => issues.push(BracketConfigIssue::at(
+                "schemaVersion",
+                format!("Written by a newer version (schema {version}); this build reads schema {BRACKET_CONFIG_SCHEMA_VERSION}."),
+            )),
+            _ => issues.push(BracketConfigIssue::at(
+                "schemaVersion",
+                "Old or invalid schema version; run migrate_bracket_config.",
+            )),
+        }
+    }
+
+    // Parse each section straight from the text so serde errors keep
+    // their line numbers.
+    let sim = match serde_json::from_str::<StartggSimConfig>(text) {
+        Ok(sim) => Some(sim),
+        Err(err) => {
+            issues.push(BracketConfigIssue::from_parse("", err));
+            None
+        }
+    };
+    let replay_map = match serde_json::from_str::<ReplayMapSection>(text) {
+        Ok(section) => section.reference_replay_map,
+        Err(err) => {
+            issues.push(BracketConfigIssue::from_parse("referenceReplayMap", err));
+            None
+        }
+    };
+
+    if let Some(sim) = sim.as_ref() {
+        check_sim_config(sim, &mut issues);
+    }
+    if let Some(replay_map) = replay_map.as_ref() {
+        check_replay_map(replay_map, sim.as_ref(), &mut issues);
+    }
+    issues
+}
+
+fn check_sim_config(sim: &StartggSimConfig, issues: &mut Vec<BracketConfigIssue>) {
+    if sim.phases.is_empty() {
+        issues.push(BracketConfigIssue::at("phases", "At least one phase is required."));
+    }
+    for (idx, phase) in sim.phases.iter().enumerate() {
+        if phase.best_of == 0 || phase.best_of % 2 == 0 {
+            issues.push(BracketConfigIssue::at(
+                format!("phases[{idx}].bestOf"),
+                format!("Best-of must be an odd number, got {}.", phase.best_of),
+            ));
+        }
+    }
+
+    if sim.entrants.len() < 2 {
+        issues.push(BracketConfigIssue::at("entrants", "At least two entrants are required."));
+    }
+    let mut ids = HashSet::new();
+    for (idx, entrant) in sim.entrants.iter().enumerate() {
+        if !ids.insert(entrant.id) {
+            issues.push(BracketConfigIssue::at(
+                format!("entrants[{idx}].id"),
+                format!("Entrant id {} is used more than once.", entrant.id),
+            ));
+        }
+        if entrant.name.trim().is_empty() {
+            issues.push(BracketConfigIssue::at(format!("entrants[{idx}].name"), "Name is empty."));
+        }
+    }
+
+    let simulation = &sim.simulation;
+    if simulation.min_set_duration_sec > simulation.max_set_duration_sec {
+        issues.push(BracketConfigIssue::at(
+            "simulation.minSetDurationSec",
+            "Minimum set duration is longer than the maximum.",
+        ));
+    }
+    if simulation.time_scale <= 0.0 {
+        issues.push(BracketConfigIssue::at("simulation.timeScale", "Time scale must be positive."));
+    }
+}
+
+fn check_replay_map(replay_map: &BracketReplayMap, sim: Option<&StartggSimConfig>, issues: &mut Vec<BracketConfigIssue>) {
+    // Generated brackets number their own sets, so set ids can only be
+    // checked against reference sets.
+    let reference_ids = sim
+        .filter(|sim| !sim.reference_sets.is_empty())
+        .map(|sim| sim.reference_sets.iter().filter_map(|set| set.id).collect::<HashSet<_>>());
+    let mut seen = HashSet::new();
+    for (idx, set) in replay_map.sets.iter().enumerate() {
+        let field = format!("referenceReplayMap.sets[{idx}]");
+        if !seen.insert(set.id) {
+            issues.push(BracketConfigIssue::at(
+                format!("{field}.id"),
+                format!("Set {} is listed more than once.", set.id),
+            ));
+        }
+        if let Some(reference_ids) = reference_ids.as_ref() {
+            if !reference_ids.contains(&set.id) {
+                issues.push(BracketConfigIssue::at(
+                    format!("{field}.id"),
+                    format!("Set {} is not one of the reference sets.", set.id),
+                ));
+            }
+        }
+        for (replay_idx, replay) in set.replays.iter().enumerate() {
+            if replay.path.trim().is_empty() {
+                issues.push(BracketConfigIssue::at(
+                    format!("{field}.replays[{replay_idx}].path"),
+                    "Replay path is empty.",
+                ));
+            }
+            if let Some(hash) = replay.sha256.as_deref() {
+                if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    issues.push(BracketConfigIssue::at(
+                        format!("{field}.replays[{replay_idx}].sha256"),
+                        "Checksum must be 64 hex characters.",
+                    ));
+                }
+            }
+        }
+    }
+}
+
+// ── Migration ───────────────────────────────────────────────────────────
+
+fn camel_case(key: &str) -> String {
+    let mut out = String::with_capacity(key.len());
+    let mut upper = false;
+    for ch in key.chars() {
+        if ch == '_' && out.chars().last().is_some_and(|last| last.is_ascii_alphanumeric()) {
+            upper = true;
+        } else if upper {
+            out.extend(ch.to_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+fn camel_case_keys(value: &mut Value, path: &str, changes: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            let old = std::mem::take(map);
+            let existing = old.keys().cloned().collect::<HashSet<_>>();
+            for (key, mut child) in old {
+                let next = camel_case(&key);
+                let child_path = if path.is_empty() { next.clone() } else { format!("{path}.{next}") };
+                camel_case_keys(&mut child, &child_path, changes);
+                if next != key {
+                    // An existing camelCase key wins over its snake_case twin.
+                    if existing.contains(&next) || map.contains_key(&next) {
+                        changes.push(format!("Dropped {key} in favour of {child_path}."));
+                        continue;
+                    }
+                    changes.push(format!("Renamed {key} to {child_path}."));
+                }
+                map.insert(next, child);
+            }
+        }
+        Value::Array(items) => {
+            for (idx, item) in items.iter_mut().enumerate() {
+                camel_case_keys(item, &format!("{path}[{idx}]"), changes);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Bring an older bracket config up to the current schema in place.
+/// Returns a description of each change; empty when it was already current.
+pub fn migrate_bracket_config_value(value: &mut Value) -> Result<Vec<String>, String> {
+    if !value.is_object() {
+        return Err("Bracket config must be a JSON object.".to_string());
+    }
+    let mut changes = Vec::new();
+    camel_case_keys(value, "", &mut changes);
+
+    if let Some(sets) = value
+        .pointer_mut("/referenceReplayMap/sets")
+        .and_then(|sets| sets.as_array_mut())
+    {
+        for (idx, set) in sets.iter_mut().enumerate() {
+            if let Some(id) = set.get("id").and_then(|id| id.as_str()).and_then(|id| id.trim().parse::<u64>().ok()) {
+                set["id"] = Value::from(id);
+                changes.push(format!("Converted referenceReplayMap.sets[{idx}].id to a number."));
+            }
+            let Some(replays) = set.get_mut("replays").and_then(|replays| replays.as_array_mut()) else {
+                continue;
+            };
+            for (replay_idx, replay) in replays.iter_mut().enumerate() {
+                if let Some(path) = replay.as_str() {
+                    *replay = serde_json::json!({ "path": path });
+                    changes.push(format!(
+                        "Wrapped referenceReplayMap.sets[{idx}].replays[{replay_idx}] in a path entry."
+                    ));
+                }
+            }
+        }
+    }
+
+    let version = value.get("schemaVersion").and_then(|v| v.as_u64());
+    if version.map_or(true, |version| (version as u32) < BRACKET_CONFIG_SCHEMA_VERSION) {
+        value["schemaVersion"] = Value::from(BRACKET_CONFIG_SCHEMA_VERSION);
+        changes.push(format!("Set schemaVersion to {BRACKET_CONFIG_SCHEMA_VERSION}."));
+    }
+    Ok(changes)
+}
+
+#[derive(Clone, Debug, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketMigrationReport {
+    pub path: String,
+    /// Copy of the file before it was rewritten; None when nothing changed
+    pub backup_path: Option<String>,
+    pub changes: Vec<String>,
+    /// Problems still present after migrating
+    pub issues: Vec<BracketConfigIssue>,
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+fn existing_bracket_config(config_path: &str) -> Result<PathBuf, String> {
+    let resolved = resolve_startgg_sim_config_path(config_path);
+    if resolved.is_file() {
+        Ok(resolved)
+    } else {
+        Err(format!("Bracket config not found at {}", resolved.display()))
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn validate_bracket_config(config_path: String) -> Result<Vec<BracketConfigIssue>, String> {
+    let resolved = existing_bracket_config(&config_path)?;
+    let data =
+        fs::read_to_string(&resolved).map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
+    Ok(validate_bracket_config_str(&data))
+}
+
+/// Rewrite an older bracket config in the current schema, keeping a `.bak`
+/// copy of the original next to it.
+#[tauri::command]
+#[specta::specta]
+pub fn migrate_bracket_config(config_path: String) -> Result<BracketMigrationReport, String> {
+    let resolved = existing_bracket_config(&config_path)?;
+    let data =
+        fs::read_to_string(&resolved).map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
+    let mut value: Value =
+        serde_json::from_str(&data).map_err(|e| format!("parse bracket config {}: {e}", resolved.display()))?;
+    let changes = migrate_bracket_config_value(&mut value)?;
+
+    let mut backup_path = None;
+    if !changes.is_empty() {
+        let backup = resolved.with_extension("json.bak");
+        fs::copy(&resolved, &backup).map_err(|e| format!("back up {}: {e}", resolved.display()))?;
+        write_bracket_value(&resolved, &value)?;
+        backup_path = Some(backup.to_string_lossy().to_string());
+    }
+    let payload = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+    Ok(BracketMigrationReport {
+        path: resolved.to_string_lossy().to_string(),
+        backup_path,
+        changes,
+        issues: validate_bracket_config_str(&payload),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn sample_config() -> Value {
+        json!({
+            "schemaVersion": 1,
+            "event": { "id": "e1", "name": "Weekly", "slug": "weekly" },
+            "phases": [{ "id": "p1", "name": "Bracket", "bestOf": 3 }],
+            "entrants": [
+                { "id": 1, "name": "Alpha", "slippiCode": "ALPH#1", "seed": 1 },
+                { "id": 2, "name": "Beta", "slippiCode": "BETA#2", "seed": 2 }
+            ],
+            "simulation": {
+                "timeScale": 2.0,
+                "minSetDurationSec": 60,
+                "maxSetDurationSec": 120,
+                "maxConcurrentSets": 1,
+                "seed": 7,
+                "allowGrandFinalsReset": false,
+                "manualMode": true
+            },
+            "referenceTournamentLink": null,
+            "referenceReplayMap": {
+                "replaysDir": "replays",
+                "sets": [{
+                    "id": 11,
+                    "replays": [{ "path": "game1.slp", "slots": [{ "slippiCode": "ALPH#1" }] }]
+                }]
+            }
+        })
+    }
+
+    #[test]
+    fn config_round_trips_through_serde() {
+        let original = sample_config();
+        let parsed: BracketConfigFile = serde_json::from_value(original.clone()).unwrap();
+        assert_eq!(parsed.schema_version, Some(1));
+        assert_eq!(parsed.sim.entrants.len(), 2);
+        let replay_map = parsed.reference_replay_map.as_ref().unwrap();
+        assert_eq!(replay_map.set(11).unwrap().replays[0].path, "game1.slp");
+
+        let written = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(written, original);
+        let reparsed: BracketConfigFile = serde_json::from_value(written.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&reparsed).unwrap(), written);
+    }
+
+    #[test]
+    fn valid_config_has_no_issues() {
+        let text = serde_json::to_string_pretty(&sample_config()).unwrap();
+        assert_eq!(validate_bracket_config_str(&text), Vec::new());
+    }
+
+    #[test]
+    fn type_errors_report_line_numbers() {
+        let mut config = sample_config();
+        config["entrants"][1]["id"] = json!("two");
+        let text = serde_json::to_string_pretty(&config).unwrap();
+        let issues = validate_bracket_config_str(&text);
+        assert_eq!(issues.len(), 1);
+        let expected_line = text.lines().position(|line| line.contains("\"two\"")).unwrap() + 1;
+        assert_eq!(issues[0].line, Some(expected_line));
+    }
+
+    #[test]
+    fn semantic_errors_name_the_field() {
+        let mut config = sample_config();
+        config["entrants"][1]["id"] = json!(1);
+        config["phases"][0]["bestOf"] = json!(2);
+        let text = serde_json::to_string(&config).unwrap();
+        let fields = validate_bracket_config_str(&text)
+            .into_iter()
+            .map(|issue| issue.field)
+            .collect::<Vec<_>>();
+        assert_eq!(fields, vec!["phases[0].bestOf", "entrants[1].id"]);
+    }
+
+    #[test]
+    fn migrate_upgrades_old_layout() {
+        let mut old = json!({
+            "event": { "id": "e1", "name": "Weekly", "slug": "weekly" },
+            "phases": [{ "id": "p1", "name": "Bracket", "best_of": 3 }],
+            "entrants": [
+                { "id": 1, "name": "Alpha", "slippi_code": "ALPH#1", "seed": 1 },
+                { "id": 2, "name": "Beta", "slippi_code": "BETA#2", "seed": 2 }
+            ],
+            "simulation": { "manual_mode": true },
+            "reference_tournament_link": null,
+            "referenceReplayMap": { "sets": [{ "id": "11", "replays": ["game1.slp"] }] }
+        });
+        let changes = migrate_bracket_config_value(&mut old).unwrap();
+        assert!(!changes.is_empty());
+        assert_eq!(old["phases"][0]["bestOf"], 3);
+        assert_eq!(old["entrants"][0]["slippiCode"], "ALPH#1");
+        assert_eq!(old["referenceReplayMap"]["sets"][0]["id"], 11);
+        assert_eq!(old["referenceReplayMap"]["sets"][0]["replays"][0]["path"], "game1.slp");
+        assert_eq!(old["schemaVersion"], BRACKET_CONFIG_SCHEMA_VERSION);
+
+        let text = serde_json::to_string(&old).unwrap();
+        assert_eq!(validate_bracket_config_str(&text), Vec::new());
+        assert!(migrate_bracket_config_value(&mut old).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_bad_replay_set_is_skipped_and_kept_on_write() {
+        let mut config = sample_config();
+        config["referenceReplayMap"]["sets"] = json!([
+            { "id": "eleven", "replays": [] },
+            { "id": 12, "replays": [{ "path": "game2.slp" }] },
+        ]);
+        let dir = std::env::temp_dir().join(format!("bracket-replay-map-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("bracket.json");
+        fs::write(&path, config.to_string()).unwrap();
+
+        let mut replay_map = read_replay_map(&path).unwrap().unwrap();
+        assert_eq!(replay_map.replays_dir.as_deref(), Some("replays"));
+        assert_eq!(replay_map.sets.iter().map(|set| set.id).collect::<Vec<_>>(), vec![12]);
+
+        replay_map.set_replays(13, vec![BracketReplayEntry { path: "game3.slp".to_string(), ..Default::default() }]);
+        write_replay_map(&path, &replay_map).unwrap();
+        let written: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        let ids = written["referenceReplayMap"]["sets"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|set| set["id"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![json!(12), json!(13), json!("eleven")]);
+        assert_eq!(read_replay_map(&path).unwrap().unwrap().sets.len(), 2);
+
+        fs::write(&path, "{ not json").unwrap();
+        assert!(read_replay_map(&path).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::capabilities::require_slippi_launcher;
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::idle_mode::is_idle;
+use crate::slippi::{scrape_slippi_via_cdp, slippi_devtools_port};
+use crate::stream_identity::stream_id_codes;
+use crate::test_mode::mock_streams_enabled;
+use crate::types::*;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with a `BroadcastEnded` when a setup's assigned broadcast stops,
+/// which usually means the set is over.
+pub const BROADCAST_ENDED_EVENT: &str = "slippi-broadcast-ended";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+/// Scans in a row without the card before the broadcast counts as gone;
+/// cards drop out for a moment while the list refreshes.
+const MISSES_BEFORE_GONE: u32 = 2;
+
+const ENDED_TOKENS: &[&str] = &["ended", "offline", "disconnected", "no longer"];
+const LIVE_TOKENS: &[&str] = &["live", "broadcasting", "in game", "in lobby"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum BroadcastEndReason {
+    /// The launcher marked the broadcast ended.
+    Ended,
+    /// The card left the spectate list.
+    Gone,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastEnded {
+    pub setup_id: u32,
+    pub stream_id: String,
+    pub label: String,
+    pub reason: BroadcastEndReason,
+    /// Viewers on the last scan that still saw the broadcast.
+    pub last_viewer_count: Option<u32>,
+    pub at_ms: u64,
+}
+
+/// Viewer count from a card's text lines, e.g. "12 viewers" or "Viewers: 3".
+pub fn viewer_count_from_lines(lines: &[String]) -> Option<u32> {
+    lines.iter().find_map(|line| {
+        let lower = line.to_lowercase();
+        if !["viewer", "watching", "spectator"].iter().any(|word| lower.contains(word)) {
+            return None;
+        }
+        let digits = lower
+            .split(|c: char| !c.is_ascii_digit() && c != ',')
+            .find(|part| part.chars().any(|c| c.is_ascii_digit()))?;
+        digits.replace(',', "").parse().ok()
+    })
+}
+
+/// Live or ended from a card's text lines; None when it says neither.
+pub fn broadcast_status_from_lines(lines: &[String]) -> Option<BroadcastStatus> {
+    let lower = lines.iter().map(|line| line.to_lowercase()).collect::<Vec<_>>();
+    let has = |tokens: &[&str]| lower.iter().any(|line| tokens.iter().any(|token| line.contains(token)));
+    if has(ENDED_TOKENS) {
+        Some(BroadcastStatus::Ended)
+    } else if has(LIVE_TOKENS) {
+        Some(BroadcastStatus::Live)
+    } else {
+        None
+    }
+}
+
+fn stream_label(stream: &SlippiStream) -> String {
+    match (stream.p1_tag.as_deref(), stream.p1_code.as_deref()) {
+        (Some(tag), Some(code)) => format!("{tag} ({code})"),
+        (Some(name), None) | (None, Some(name)) => name.to_string(),
+        (None, None) => stream.id.clone(),
+    }
+}
+
+/// The card on the spectate list for an assigned stream: same id, or the
+/// same connect codes.
+fn find_card<'a>(assigned: &SlippiStream, cards: &'a [SlippiStream]) -> Option<&'a SlippiStream> {
+    cards.iter().find(|card| card.id == assigned.id).or_else(|| {
+        let codes = stream_id_codes(&assigned.id);
+        if codes.is_empty() {
+            return None;
+        }
+        cards.iter().find(|card| stream_id_codes(&card.id) == codes)
+    })
+}
+
+/// Assigned broadcasts as seen from one scan to the next.
+#[derive(Debug, Default)]
+struct BroadcastTracker {
+    /// Misses in a row, by (setup, stream id).
+    misses: HashMap<(u32, String), u32>,
+    /// Viewers last seen, by (setup, stream id).
+    viewers: HashMap<(u32, String), u32>,
+    /// Already reported, so each end is reported once per assignment.
+    reported: HashSet<(u32, String)>,
+}
+
+impl BroadcastTracker {
+    /// Record a scan for one setup's assignment. Some once, when the
+    /// broadcast has ended.
+    fn observe(&mut self, setup_id: u32, stream_id: &str, card: Option<&SlippiStream>) -> Option<BroadcastEndReason> {
+        let key = (setup_id, stream_id.to_string());
+        if self.reported.contains(&key) {
+            return None;
+        }
+        let reason = match card {
+            Some(card) => {
+                self.misses.remove(&key);
+                if let Some(count) = card.viewer_count {
+                    self.viewers.insert(key.clone(), count);
+                }
+                (card.broadcast_status == Some(BroadcastStatus::Ended)).then_some(BroadcastEndReason::Ended)
+            }
+            None => {
+                let misses = self.misses.entry(key.clone()).or_insert(0);
+                *misses += 1;
+                (*misses >= MISSES_BEFORE_GONE).then_some(BroadcastEndReason::Gone)
+            }
+        };
+        if reason.is_some() {
+            self.misses.remove(&key);
+            self.reported.insert(key);
+        }
+        reason
+    }
+
+    /// Forget assignments that no longer exist.
+    fn retain(&mut self, assigned: &HashSet<(u32, String)>) {
+        self.misses.retain(|key, _| assigned.contains(key));
+        self.viewers.retain(|key, _| assigned.contains(key));
+        self.reported.retain(|key| assigned.contains(key));
+    }
+}
+
+/// Copy the latest viewer count and status onto assigned streams.
+fn update_assigned(store: &SharedSetupStore, cards: &[SlippiStream]) {
+    let mut guard = store.lock().unwrap_or_else(|e| e.into_inner());
+    for setup in guard.setups.iter_mut() {
+        let Some(assigned) = setup.assigned_stream.as_mut() else {
+            continue;
+        };
+        if let Some(card) = find_card(assigned, cards) {
+            assigned.viewer_count = card.viewer_count;
+            assigned.broadcast_status = card.broadcast_status;
+        }
+    }
+}
+
+/// Scan the spectate list while setups are assigned, keep their viewer
+/// counts current and emit `slippi-broadcast-ended` when one stops.
+pub fn spawn_broadcast_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut tracker = BroadcastTracker::default();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if is_idle()
+                || mock_streams_enabled()
+                || test_flags().fake_streams
+                || !feature_enabled(FeatureFlag::BroadcastMonitor)
+            {
+                continue;
+            }
+            let store = app.state::<SharedSetupStore>().inner().clone();
+            let assigned = store
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .setups
+                .iter()
+                .filter_map(|setup| Some((setup.id, setup.assigned_stream.clone()?)))
+                .collect::<Vec<_>>();
+            tracker.retain(&assigned.iter().map(|(id, stream)| (*id, stream.id.clone())).collect());
+            if assigned.is_empty() || require_slippi_launcher("Watching").is_err() {
+                continue;
+            }
+            // A failed or empty scan (e.g. the launcher is on another tab)
+            // says nothing about the broadcasts.
+            let cards = match scrape_slippi_via_cdp(slippi_devtools_port()) {
+                Ok(cards) if !cards.is_empty() => cards,
+                Ok(_) => continue,
+                Err(err) => {
+                    tracing::debug!("Broadcast scan failed: {}", err);
+                    continue;
+                }
+            };
+            update_assigned(&store, &cards);
+            for (setup_id, stream) in &assigned {
+                let Some(reason) = tracker.observe(*setup_id, &stream.id, find_card(stream, &cards)) else {
+                    continue;
+                };
+                let event = BroadcastEnded {
+                    setup_id: *setup_id,
+                    stream_id: stream.id.clone(),
+                    label: stream_label(stream),
+                    reason,
+                    last_viewer_count: tracker.viewers.get(&(*setup_id, stream.id.clone())).copied(),
+                    at_ms: now_ms(),
+                };
+                tracing::info!("Setup {}: broadcast {} ended ({:?})", setup_id, event.label, reason);
+                let _ = app.emit(BROADCAST_ENDED_EVENT, &event);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|line| line.to_string()).collect()
+    }
+
+    fn card(status: Option<BroadcastStatus>, viewers: Option<u32>) -> SlippiStream {
+        SlippiStream {
+            id: "codes:AAAA#1".to_string(),
+            viewer_count: viewers,
+            broadcast_status: status,
+            ..Default::default()
+        }
+    }
+
+    const STREAM: &str = "codes:AAAA#1";
+
+    #[test]
+    fn viewer_counts_are_read_from_card_text() {
+        assert_eq!(viewer_count_from_lines(&lines(&["Mango", "MANG#0", "1,204 viewers"])), Some(1204));
+        assert_eq!(viewer_count_from_lines(&lines(&["Viewers: 3"])), Some(3));
+        assert_eq!(viewer_count_from_lines(&lines(&["Mango", "MANG#0"])), None);
+    }
+
+    #[test]
+    fn ended_wins_over_live_and_other_text_says_nothing() {
+        assert_eq!(broadcast_status_from_lines(&lines(&["Live", "In game"])), Some(BroadcastStatus::Live));
+        assert_eq!(broadcast_status_from_lines(&lines(&["Broadcast ended"])), Some(BroadcastStatus::Ended));
+        assert_eq!(broadcast_status_from_lines(&lines(&["Live", "Disconnected"])), Some(BroadcastStatus::Ended));
+        assert_eq!(broadcast_status_from_lines(&lines(&["Mango"])), None);
+    }
+
+    #[test]
+    fn one_missed_scan_is_a_refresh_and_two_are_gone() {
+        let mut tracker = BroadcastTracker::default();
+        let live = card(Some(BroadcastStatus::Live), Some(40));
+        assert_eq!(tracker.observe(1, STREAM, Some(&live)), None);
+        assert_eq!(tracker.observe(1, STREAM, None), None);
+        assert_eq!(tracker.observe(1, STREAM, Some(&live)), None);
+        assert_eq!(tracker.observe(1, STREAM, None), None);
+        assert_eq!(tracker.observe(1, STREAM, None), Some(BroadcastEndReason::Gone));
+        assert_eq!(tracker.viewers.get(&(1, STREAM.to_string())), Some(&40));
+    }
+
+    #[test]
+    fn an_ended_card_is_reported_once() {
+        let mut tracker = BroadcastTracker::default();
+        let ended = card(Some(BroadcastStatus::Ended), None);
+        assert_eq!(tracker.observe(2, STREAM, Some(&ended)), Some(BroadcastEndReason::Ended));
+        assert_eq!(tracker.observe(2, STREAM, Some(&ended)), None);
+        assert_eq!(tracker.observe(2, STREAM, None), None);
+        assert_eq!(tracker.observe(2, STREAM, None), None);
+    }
+
+    #[test]
+    fn reassigning_a_stream_starts_over() {
+        let mut tracker = BroadcastTracker::default();
+        let ended = card(Some(BroadcastStatus::Ended), None);
+        tracker.observe(2, STREAM, Some(&ended));
+        tracker.retain(&HashSet::new());
+        assert_eq!(tracker.observe(2, STREAM, Some(&ended)), Some(BroadcastEndReason::Ended));
+    }
+
+    #[test]
+    fn assigned_streams_find_their_own_card() {
+        let mut assigned = card(None, None);
+        assigned.id = "codes:AAAA#1|BBBB#2".to_string();
+        let mut moved = card(None, Some(7));
+        moved.id = "codes:AAAA#1|BBBB#2".to_string();
+        let cards = [card(None, None), moved];
+        assert_eq!(find_card(&assigned, &cards).and_then(|card| card.viewer_count), Some(7));
+        let mut unlisted = card(None, None);
+        unlisted.id = "card-4".to_string();
+        assert!(find_card(&unlisted, &cards).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+use crate::config::*;
+use crate::dolphin::{dolphin_config, slippi_appimage_path};
+use crate::slippi::slippi_x11_connect;
+use crate::windowing::{WaylandBackend, WindowBackend};
+use serde::Serialize;
+use specta::Type;
+use std::{
+    env,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+/// Starts every error from a window feature this session can't offer, so
+/// the UI can tell "not available here" from "tried and failed".
+pub const UNSUPPORTED_SESSION: &str = "Unsupported on this session";
+/// How long a display probe is reused by the window-feature guards.
+const DISPLAY_PROBE_TTL: Duration = Duration::from_secs(10);
+
+/// What this machine can do, from the binaries and folders it has.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AppMode {
+    /// Dolphin and the ISO are present: launching, spectating and playback
+    Full,
+    /// No Dolphin, but a spectate folder to read replays from (e.g. a
+    /// separate capture PC runs Dolphin and shares the folder)
+    OverlayOnly,
+    /// Neither: start.gg bracket management and static overlays only
+    BracketOnly,
+}
+
+impl AppMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            AppMode::Full => "Full",
+            AppMode::OverlayOnly => "Overlay-only",
+            AppMode::BracketOnly => "Bracket-only",
+        }
+    }
+}
+
+/// What the desktop session lets the window features (launcher lookup,
+/// Dolphin window arranging, OCR) do. Bracket, overlay and spoofing don't
+/// need any of it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct DisplaySupport {
+    /// "x11", "wayland" or "headless"
+    pub session: String,
+    /// An X server, XWayland included, accepted a connection
+    pub x11: bool,
+    /// Top-level windows can be listed, through X11 or the compositor
+    pub window_list: bool,
+    /// Why a window feature is off, when one is
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+    pub mode: AppMode,
+    /// Dolphin binary and Melee ISO both resolve
+    pub dolphin: bool,
+    pub slippi_launcher: bool,
+    pub spectate_folder: bool,
+    pub display: DisplaySupport,
+    /// Why anything above is missing, in the words the launch would fail with
+    pub issues: Vec<String>,
+    pub checked_at_ms: u64,
+}
+
+static CAPABILITIES: Mutex<Option<Capabilities>> = Mutex::new(None);
+static DISPLAY_PROBE: Mutex<Option<(Instant, DisplaySupport)>> = Mutex::new(None);
+
+pub fn classify(dolphin: bool, spectate_folder: bool) -> AppMode {
+    if dolphin {
+        AppMode::Full
+    } else if spectate_folder {
+        AppMode::OverlayOnly
+    } else {
+        AppMode::BracketOnly
+    }
+}
+
+fn display_support_from(session: &str, x11: Result<(), String>, wayland: Result<(), String>) -> DisplaySupport {
+    let reason = match (&x11, &wayland) {
+        (Ok(()), _) => None,
+        (Err(_), Ok(())) => Some("No X11 display; window capture and arranging need X11 or XWayland.".to_string()),
+        (Err(x11_err), Err(_)) if session == "headless" => {
+            Some(format!("No display (DISPLAY and WAYLAND_DISPLAY are unset): {x11_err}"))
+        }
+        (Err(x11_err), Err(wayland_err)) => Some(format!("No usable display. X11: {x11_err}. Wayland: {wayland_err}")),
+    };
+    DisplaySupport {
+        session: session.to_string(),
+        x11: x11.is_ok(),
+        window_list: x11.is_ok() || wayland.is_ok(),
+        reason,
+    }
+}
+
+/// Connect to the X server and the compositor to see what this session
+/// supports. `SLIPPI_DISPLAY` counts as an X display.
+pub fn probe_display() -> DisplaySupport {
+    let has_x = env::var_os("DISPLAY").is_some() || env::var_os("SLIPPI_DISPLAY").is_some();
+    let has_wayland = env::var_os("WAYLAND_DISPLAY").is_some();
+    let session = match (has_x, has_wayland) {
+        (_, true) => "wayland",
+        (true, false) => "x11",
+        (false, false) => "headless",
+    };
+    let x11 = slippi_x11_connect().map(|_| ());
+    let wayland = if has_wayland {
+        WaylandBackend.toplevels().map(|_| ())
+    } else {
+        Err("WAYLAND_DISPLAY is not set".to_string())
+    };
+    let support = display_support_from(session, x11, wayland);
+    *DISPLAY_PROBE.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), support.clone()));
+    support
+}
+
+/// The last probe, if it's recent, else a new one.
+pub fn display_support() -> DisplaySupport {
+    if let Some((at, support)) = DISPLAY_PROBE.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+        if at.elapsed() < DISPLAY_PROBE_TTL {
+            return support;
+        }
+    }
+    probe_display()
+}
+
+fn unsupported_session(action: &str, display: &DisplaySupport) -> String {
+    format!(
+        "{UNSUPPORTED_SESSION}: {action} isn't available on a {} session. {}",
+        display.session,
+        display.reason.as_deref().unwrap_or_default()
+    )
+    .trim_end()
+    .to_string()
+}
+
+/// Guard for finding windows by title or class.
+pub fn require_window_list(action: &str) -> Result<(), String> {
+    let display = display_support();
+    if display.window_list {
+        Ok(())
+    } else {
+        Err(unsupported_session(action, &display))
+    }
+}
+
+/// Guard for reading or changing windows through X11 (arranging Dolphin,
+/// screenshots for OCR).
+pub fn require_x11(action: &str) -> Result<(), String> {
+    let display = display_support();
+    if display.x11 {
+        Ok(())
+    } else {
+        Err(unsupported_session(action, &display))
+    }
+}
+
+/// Re-check paths from the current config and env. Cheap enough to run on
+/// every guarded command, so fixing a path in Settings takes effect at once.
+pub fn check_capabilities() -> Capabilities {
+    let config = cached_config().unwrap_or_default();
+    let mut issues = Vec::new();
+    let dolphin = dolphin_config().map_err(|e| issues.push(e)).is_ok();
+    let slippi_launcher = slippi_appimage_path().map_err(|e| issues.push(e)).is_ok();
+    let spectate_folder = !config.spectate_folder_path.trim().is_empty();
+    if !spectate_folder {
+        issues.push("Spectate folder path is not set in settings.".to_string());
+    }
+    let display = probe_display();
+    if let Some(reason) = display.reason.as_ref() {
+        issues.push(reason.clone());
+    }
+    let capabilities = Capabilities {
+        mode: classify(dolphin, spectate_folder),
+        dolphin,
+        slippi_launcher,
+        spectate_folder,
+        display,
+        issues,
+        checked_at_ms: now_ms(),
+    };
+    let mut guard = CAPABILITIES.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(previous) = guard.as_ref().filter(|previous| previous.mode != capabilities.mode) {
+        tracing::info!("App mode changed: {} -> {}", previous.mode.label(), capabilities.mode.label());
+    }
+    *guard = Some(capabilities.clone());
+    capabilities
+}
+
+/// Log the startup mode and tell the UI, which greys out what's unavailable.
+pub fn log_startup_capabilities(app: &AppHandle) {
+    let capabilities = check_capabilities();
+    if capabilities.mode == AppMode::Full {
+        tracing::info!("Starting in Full mode");
+    } else {
+        tracing::warn!(
+            "Starting in {} mode: {}",
+            capabilities.mode.label(),
+            capabilities.issues.join(" ")
+        );
+    }
+    let _ = app.emit("app-capabilities", &capabilities);
+}
+
+fn unavailable(action: &str, capabilities: &Capabilities) -> String {
+    format!(
+        "{action} is unavailable in {} mode. {}",
+        capabilities.mode.label(),
+        capabilities.issues.join(" ")
+    )
+}
+
+/// Guard for anything that starts a Dolphin (netplay, spectate, playback).
+pub fn require_dolphin(action: &str) -> Result<(), String> {
+    if test_flags().fake_streams {
+        return Ok(());
+    }
+    let capabilities = check_capabilities();
+    if capabilities.mode == AppMode::Full {
+        Ok(())
+    } else {
+        Err(unavailable(action, &capabilities))
+    }
+}
+
+/// Guard for spectating through the Slippi launcher.
+pub fn require_slippi_launcher(action: &str) -> Result<(), String> {
+    if test_flags().fake_streams {
+        return Ok(());
+    }
+    let capabilities = check_capabilities();
+    if capabilities.mode == AppMode::Full && capabilities.slippi_launcher {
+        Ok(())
+    } else {
+        Err(unavailable(action, &capabilities))
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Current mode. The last check is returned unless `refresh` is set or
+/// nothing has been checked yet.
+#[tauri::command]
+#[specta::specta]
+pub fn capabilities(refresh: Option<bool>) -> Capabilities {
+    if !refresh.unwrap_or(false) {
+        if let Some(cached) = CAPABILITIES.lock().unwrap_or_else(|e| e.into_inner()).clone() {
+            return cached;
+        }
+    }
+    check_capabilities()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mode_degrades_with_missing_pieces() {
+        assert_eq!(classify(true, true), AppMode::Full);
+        assert_eq!(classify(true, false), AppMode::Full);
+        assert_eq!(classify(false, true), AppMode::OverlayOnly);
+        assert_eq!(classify(false, false), AppMode::BracketOnly);
+        assert_eq!(AppMode::OverlayOnly.label(), "Overlay-only");
+    }
+
+    #[test]
+    fn window_features_degrade_with_the_display() {
+        let x11 = display_support_from("x11", Ok(()), Err("unset".to_string()));
+        assert!(x11.x11 && x11.window_list && x11.reason.is_none());
+
+        // Wayland without XWayland can still list windows, but not capture them.
+        let wayland = display_support_from("wayland", Err("refused".to_string()), Ok(()));
+        assert!(!wayland.x11 && wayland.window_list);
+        assert!(unsupported_session("Arranging Dolphin windows", &wayland)
+            .starts_with("Unsupported on this session: Arranging Dolphin windows isn't available on a wayland session."));
+
+        let headless = display_support_from("headless", Err("DISPLAY is not set".to_string()), Err("unset".to_string()));
+        assert!(!headless.x11 && !headless.window_list);
+        assert!(headless.reason.as_deref().is_some_and(|reason| reason.contains("DISPLAY and WAYLAND_DISPLAY")));
+    }
+}
@@ -0,0 +1,394 @@
+use crate::audit_log::audited;
+use crate::auto_stop::leave_break;
+use crate::config::*;
+use crate::idle_mode::exit_idle;
+use crate::mirror::end_mirror;
+use crate::run_of_show::current_bracket_state;
+use crate::setup_meta::{persist_setups, setup_label};
+use crate::slippi::{assign_stream, clear_assignment};
+use crate::startgg_sim::StartggSimSet;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{fs, path::PathBuf, sync::Mutex};
+use tauri::State;
+
+/// `SlippiStream::source` for a console station shown through a capture
+/// card. There's no Dolphin to launch; the stream carries the set being
+/// played there so the overlay, queue and OBS switching treat the station
+/// like any other setup.
+pub const CAPTURE_CARD_SOURCE: &str = "capture card";
+
+const CAPTURE_STREAM_ID_PREFIX: &str = "capture:";
+const V4L2_SYSFS_DIR: &str = "/sys/class/video4linux";
+/// Stable names for v4l2 devices, which keep pointing at the same card
+/// when `/dev/videoN` numbers shuffle after a replug.
+const V4L2_BY_ID_DIR: &str = "/dev/v4l/by-id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CaptureSourceKind {
+    /// A capture card OBS reads through its v4l2 input
+    V4l2,
+    /// A source already set up in OBS, e.g. a card on another machine
+    Obs,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureSource {
+    pub id: String,
+    /// What the operator calls it, e.g. "Elgato 2"; also the name of the
+    /// v4l2 input created for it in OBS
+    pub label: String,
+    pub kind: CaptureSourceKind,
+    /// The device path of a v4l2 card, or the input's name in OBS
+    pub device: String,
+}
+
+/// Loaded on first use; the OBS monitor reads it every few seconds.
+static CAPTURE_SOURCES: Mutex<Option<Vec<CaptureSource>>> = Mutex::new(None);
+
+pub fn capture_sources_path() -> PathBuf {
+    repo_root().join("capture_sources.json")
+}
+
+fn read_capture_sources() -> Vec<CaptureSource> {
+    let path = capture_sources_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable capture sources {}: {}", path.display(), e);
+        Vec::new()
+    })
+}
+
+fn with_capture_sources<R>(f: impl FnOnce(&mut Vec<CaptureSource>) -> R) -> R {
+    let mut guard = CAPTURE_SOURCES.lock().unwrap_or_else(|e| e.into_inner());
+    let sources = guard.get_or_insert_with(read_capture_sources);
+    f(sources)
+}
+
+fn write_capture_sources(sources: &[CaptureSource]) -> Result<(), String> {
+    let path = capture_sources_path();
+    let body = serde_json::to_vec_pretty(sources).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write capture sources {}: {e}", path.display()))
+}
+
+pub fn capture_sources() -> Vec<CaptureSource> {
+    with_capture_sources(|sources| sources.clone())
+}
+
+pub fn find_capture_source(id: &str) -> Result<CaptureSource, String> {
+    with_capture_sources(|sources| sources.iter().find(|source| source.id == id).cloned())
+        .ok_or_else(|| format!("No capture source {id}."))
+}
+
+pub fn is_capture_stream(stream: &SlippiStream) -> bool {
+    stream.source.as_deref() == Some(CAPTURE_CARD_SOURCE)
+}
+
+/// The registry id of the capture card behind a console station's stream.
+pub fn stream_capture_source(stream: &SlippiStream) -> Option<String> {
+    if !is_capture_stream(stream) {
+        return None;
+    }
+    stream.id.strip_prefix(CAPTURE_STREAM_ID_PREFIX).map(str::to_string)
+}
+
+/// An id from the operator's label, e.g. "Elgato 2" -> "elgato-2".
+fn slug(label: &str) -> String {
+    let mut slug = String::new();
+    for c in label.trim().chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+/// Check `source` and add it, or replace the source with its id. A blank id
+/// is made from the label.
+fn upsert_source(sources: &mut Vec<CaptureSource>, source: CaptureSource) -> Result<CaptureSource, String> {
+    let label = source.label.trim().to_string();
+    let device = source.device.trim().to_string();
+    if label.is_empty() {
+        return Err("Give the capture source a name.".to_string());
+    }
+    if device.is_empty() {
+        return Err(match source.kind {
+            CaptureSourceKind::V4l2 => format!("{label} needs a device, like /dev/video0."),
+            CaptureSourceKind::Obs => format!("{label} needs the name of its source in OBS."),
+        });
+    }
+    let id = match source.id.trim() {
+        "" => slug(&label),
+        id => id.to_string(),
+    };
+    if id.is_empty() {
+        return Err(format!("Couldn't make an id from '{label}'; use letters or numbers."));
+    }
+    if let Some(other) = sources.iter().find(|s| s.id != id && s.kind == source.kind && s.device == device) {
+        return Err(format!("{device} is already {}.", other.label));
+    }
+    let source = CaptureSource { id, label, kind: source.kind, device };
+    match sources.iter_mut().find(|s| s.id == source.id) {
+        Some(existing) => *existing = source.clone(),
+        None => sources.push(source.clone()),
+    }
+    Ok(source)
+}
+
+/// The device path OBS should open for `/dev/{node}`: its by-id link when
+/// there is one.
+fn stable_device_path(node: &str) -> String {
+    let dev = PathBuf::from("/dev").join(node);
+    fs::read_dir(V4L2_BY_ID_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|link| fs::canonicalize(link).is_ok_and(|target| target == dev))
+        .unwrap_or(dev)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Video capture devices the kernel knows about. A card registers several
+/// nodes; only the first (index 0) carries video.
+fn detect_v4l2_sources() -> Vec<CaptureSource> {
+    let mut nodes = fs::read_dir(V4L2_SYSFS_DIR)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let node = entry.file_name().to_string_lossy().into_owned();
+            if !node.starts_with("video") {
+                return None;
+            }
+            let index = fs::read_to_string(entry.path().join("index")).unwrap_or_default();
+            if index.trim().parse::<u32>().unwrap_or(0) != 0 {
+                return None;
+            }
+            let name = fs::read_to_string(entry.path().join("name")).unwrap_or_default();
+            let name = name.trim();
+            Some((node.clone(), if name.is_empty() { node } else { name.to_string() }))
+        })
+        .collect::<Vec<_>>();
+    nodes.sort_by_key(|(node, _)| node.trim_start_matches("video").parse::<u32>().unwrap_or(u32::MAX));
+    nodes
+        .into_iter()
+        .map(|(node, name)| CaptureSource {
+            id: node.clone(),
+            label: format!("{name} ({node})"),
+            kind: CaptureSourceKind::V4l2,
+            device: stable_device_path(&node),
+        })
+        .collect()
+}
+
+/// The stream a setup is assigned for a console station, playing `set`.
+pub fn capture_stream(source: &CaptureSource, set: Option<&StartggSimSet>) -> SlippiStream {
+    let slot = |idx: usize| set.and_then(|set| set.slots.get(idx));
+    SlippiStream {
+        id: format!("{CAPTURE_STREAM_ID_PREFIX}{}", source.id),
+        window_title: Some(source.label.clone()),
+        p1_tag: slot(0).and_then(|slot| slot.entrant_name.clone()),
+        p2_tag: slot(1).and_then(|slot| slot.entrant_name.clone()),
+        startgg_entrant_id: slot(0).and_then(|slot| slot.entrant_id),
+        source: Some(CAPTURE_CARD_SOURCE.to_string()),
+        startgg_set: set.cloned(),
+        ..Default::default()
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_capture_sources() -> Result<Vec<CaptureSource>, String> {
+    Ok(capture_sources())
+}
+
+/// Capture cards plugged into this machine that aren't registered yet.
+#[tauri::command]
+#[specta::specta]
+pub fn detect_capture_sources() -> Result<Vec<CaptureSource>, String> {
+    let registered = capture_sources();
+    Ok(detect_v4l2_sources()
+        .into_iter()
+        .filter(|found| {
+            !registered
+                .iter()
+                .any(|source| source.kind == CaptureSourceKind::V4l2 && source.device == found.device)
+        })
+        .collect())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn save_capture_source(source: CaptureSource) -> Result<CaptureSource, String> {
+    let params = json!({ "id": source.id, "label": source.label, "kind": source.kind, "device": source.device });
+    audited("saveCaptureSource", params, || {
+        with_capture_sources(|sources| {
+            let mut next = sources.clone();
+            let saved = upsert_source(&mut next, source)?;
+            write_capture_sources(&next)?;
+            *sources = next;
+            Ok(saved)
+        })
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn remove_capture_source(id: String, store: State<'_, SharedSetupStore>) -> Result<(), String> {
+    audited("removeCaptureSource", json!({ "id": id }), || {
+        {
+            let guard = store.lock().map_err(|e| e.to_string())?;
+            if let Some(setup) = guard.setups.iter().find(|s| s.capture_source_id.as_deref() == Some(id.as_str())) {
+                return Err(format!("{} is using {id}; take it off the setup first.", setup_label(setup)));
+            }
+        }
+        with_capture_sources(|sources| {
+            let next = sources.iter().filter(|source| source.id != id).cloned().collect::<Vec<_>>();
+            if next.len() == sources.len() {
+                return Err(format!("No capture source {id}."));
+            }
+            write_capture_sources(&next)?;
+            *sources = next;
+            Ok(())
+        })
+    })
+}
+
+/// Make `setup_id` a console station shown through `source_id`, playing
+/// `set_id` when given; call again with another set as the station moves
+/// on. Without `source_id` the setup goes back to spectating in Dolphin.
+#[tauri::command]
+#[specta::specta]
+pub fn assign_capture_source(
+    setup_id: u32,
+    source_id: Option<String>,
+    set_id: Option<u64>,
+    store: State<'_, SharedSetupStore>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<AssignStreamResult, String> {
+    let params = json!({ "setupId": setup_id, "sourceId": source_id, "setId": set_id });
+    audited("assignCaptureSource", params, || {
+        let Some(source_id) = source_id else {
+            let was_capture = {
+                let mut guard = store.lock().map_err(|e| e.to_string())?;
+                let setup = guard
+                    .setups
+                    .iter_mut()
+                    .find(|s| s.id == setup_id)
+                    .ok_or_else(|| format!("Setup {setup_id} not found."))?;
+                setup.capture_source_id = None;
+                setup.assigned_stream.as_ref().is_some_and(is_capture_stream)
+            };
+            if was_capture {
+                clear_assignment(setup_id, Some(false), &store)?;
+            }
+            let setups = store.lock().map_err(|e| e.to_string())?.setups.clone();
+            persist_setups(&setups);
+            return Ok(AssignStreamResult { setups, warning: None });
+        };
+
+        let source = find_capture_source(&source_id)?;
+        let set = match set_id {
+            Some(set_id) => {
+                let bracket = current_bracket_state(&test_state, &live_startgg)
+                    .ok_or_else(|| "No bracket is loaded.".to_string())?;
+                let set = bracket.sets.iter().find(|set| set.id == set_id).cloned();
+                Some(set.ok_or_else(|| format!("Set {set_id} isn't in the bracket."))?)
+            }
+            None => None,
+        };
+        {
+            let guard = store.lock().map_err(|e| e.to_string())?;
+            if let Some(other) = guard
+                .setups
+                .iter()
+                .find(|s| s.id != setup_id && s.capture_source_id.as_deref() == Some(source.id.as_str()))
+            {
+                return Err(format!("{} is already on {}.", source.label, setup_label(other)));
+            }
+        }
+        // Sets the setup's capture_source_id and stops its Dolphin, if it
+        // had one; nothing is started for a capture card.
+        let stream = capture_stream(&source, set.as_ref());
+        let result = assign_stream(setup_id, stream, Some(true), &store, &test_state)?;
+        persist_setups(&result.setups);
+        exit_idle(&format!("capture source assigned to setup {setup_id}"));
+        leave_break(setup_id);
+        end_mirror(setup_id);
+        Ok(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(id: &str, label: &str, device: &str) -> CaptureSource {
+        CaptureSource {
+            id: id.to_string(),
+            label: label.to_string(),
+            kind: CaptureSourceKind::V4l2,
+            device: device.to_string(),
+        }
+    }
+
+    fn elgato() -> (Vec<CaptureSource>, CaptureSource) {
+        let mut sources = Vec::new();
+        let saved = upsert_source(&mut sources, source("", " Elgato #2 ", "/dev/video2")).unwrap();
+        (sources, saved)
+    }
+
+    #[test]
+    fn a_new_source_gets_its_id_from_its_label() {
+        let (_, saved) = elgato();
+        assert_eq!(saved.id, "elgato-2");
+        assert_eq!(saved.label, "Elgato #2");
+    }
+
+    #[test]
+    fn a_device_belongs_to_one_source() {
+        let (mut sources, _) = elgato();
+        assert_eq!(
+            upsert_source(&mut sources, source("", "Other", "/dev/video2")).unwrap_err(),
+            "/dev/video2 is already Elgato #2."
+        );
+    }
+
+    #[test]
+    fn a_source_needs_a_device_and_a_usable_name() {
+        let mut sources = Vec::new();
+        assert!(upsert_source(&mut sources, source("", "Blank", " ")).is_err());
+        assert!(upsert_source(&mut sources, source("", "!!", "/dev/video4")).is_err());
+        assert!(sources.is_empty());
+    }
+
+    #[test]
+    fn saving_under_an_existing_id_replaces_the_source() {
+        let (mut sources, _) = elgato();
+        let renamed = upsert_source(&mut sources, source("elgato-2", "Station 4", "/dev/video2")).unwrap();
+        assert_eq!(sources, vec![renamed]);
+    }
+
+    #[test]
+    fn a_capture_stream_points_back_at_its_source() {
+        let (_, saved) = elgato();
+        let stream = capture_stream(&saved, None);
+        assert_eq!(stream.id, "capture:elgato-2");
+        assert!(is_capture_stream(&stream));
+        assert_eq!(stream_capture_source(&stream).as_deref(), Some("elgato-2"));
+        assert_eq!(stream.p1_code, None);
+    }
+}
@@ -0,0 +1,290 @@
+use crate::capabilities::{display_support, require_x11};
+use crate::config::*;
+use crate::slippi::{read_window_title, slippi_x11_connect};
+use crate::types::SharedSetupStore;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::State;
+use x11rb::{
+    connection::Connection,
+    protocol::xproto::{Atom, AtomEnum, ClientMessageEvent, ConnectionExt, EventMask, PropMode, Window},
+    rust_connection::RustConnection,
+    wrapper::ConnectionExt as _,
+};
+
+/// How long a freshly launched Dolphin gets to map its window.
+const WINDOW_WAIT: Duration = Duration::from_secs(20);
+const WINDOW_POLL: Duration = Duration::from_millis(500);
+
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+/// Source indication for pager/tool requests, which WMs always honour
+const NET_WM_SOURCE_PAGER: u32 = 2;
+const MOTIF_HINTS_DECORATIONS: u32 = 1 << 1;
+const WM_HINTS_INPUT: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureWindow {
+    pub setup_id: u32,
+    pub pid: u32,
+    pub window: u32,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureArrangement {
+    pub windows: Vec<CaptureWindow>,
+    pub warnings: Vec<String>,
+}
+
+/// `CAPTURE_WINDOW_HINTS=0` leaves Dolphin windows to the window manager.
+pub fn capture_window_hints_enabled() -> bool {
+    env_flag_true_default("CAPTURE_WINDOW_HINTS", true)
+}
+
+fn atom(conn: &RustConnection, name: &[u8]) -> Result<Atom, String> {
+    Ok(conn
+        .intern_atom(false, name)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .map_err(|e| e.to_string())?
+        .atom)
+}
+
+/// The parent pid from a `/proc/<pid>/stat` line. The command name can
+/// contain spaces and parentheses; fields resume after the last ')'.
+fn parent_pid(stat: &str) -> Option<u32> {
+    stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// `pid` and every process below it: Dolphin may sit under obs-gamecapture
+/// or the launcher wrapper script.
+fn process_tree(pid: u32) -> HashSet<u32> {
+    let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+    for entry in fs::read_dir("/proc").into_iter().flatten().flatten() {
+        let Ok(child) = entry.file_name().to_string_lossy().parse::<u32>() else {
+            continue;
+        };
+        let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        if let Some(ppid) = parent_pid(&stat) {
+            children.entry(ppid).or_default().push(child);
+        }
+    }
+    let mut out = HashSet::from([pid]);
+    let mut stack = vec![pid];
+    while let Some(next) = stack.pop() {
+        for child in children.get(&next).into_iter().flatten() {
+            if out.insert(*child) {
+                stack.push(*child);
+            }
+        }
+    }
+    out
+}
+
+fn window_pid(conn: &RustConnection, window: Window, net_wm_pid: Atom) -> Option<u32> {
+    let reply = conn
+        .get_property(false, window, net_wm_pid, AtomEnum::CARDINAL, 0, 1)
+        .ok()?
+        .reply()
+        .ok()?;
+    reply.value32()?.next()
+}
+
+/// Client windows owned by any of `pids`. Walks the whole tree because
+/// reparenting window managers put clients under frame windows.
+fn windows_for_pids(
+    conn: &RustConnection,
+    root: Window,
+    pids: &HashSet<u32>,
+) -> Result<Vec<(Window, u32)>, String> {
+    let net_wm_pid = atom(conn, b"_NET_WM_PID")?;
+    let mut out = Vec::new();
+    let mut stack = vec![root];
+    while let Some(window) = stack.pop() {
+        if let Some(pid) = window_pid(conn, window, net_wm_pid).filter(|pid| pids.contains(pid)) {
+            out.push((window, pid));
+            continue;
+        }
+        if let Ok(tree) = conn.query_tree(window).map_err(|e| e.to_string())?.reply() {
+            stack.extend(tree.children);
+        }
+    }
+    Ok(out)
+}
+
+fn set_wm_state(
+    conn: &RustConnection,
+    root: Window,
+    window: Window,
+    action: u32,
+    states: [Atom; 2],
+) -> Result<(), String> {
+    let net_wm_state = atom(conn, b"_NET_WM_STATE")?;
+    let data = [action, states[0], states[1], NET_WM_SOURCE_PAGER, 0];
+    let event = ClientMessageEvent::new(32, window, net_wm_state, data);
+    conn.send_event(
+        false,
+        root,
+        EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+        event,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Keep a capture window below everything, without decorations, and out of
+/// the focus chain so it neither steals focus nor gets raised over OBS.
+fn apply_capture_hints(conn: &RustConnection, root: Window, window: Window) -> Result<(), String> {
+    let motif_hints = atom(conn, b"_MOTIF_WM_HINTS")?;
+    // flags, functions, decorations, input mode, status: decorations off
+    let no_decorations = [MOTIF_HINTS_DECORATIONS, 0, 0, 0, 0];
+    conn.change_property32(PropMode::REPLACE, window, motif_hints, motif_hints, &no_decorations)
+        .map_err(|e| e.to_string())?;
+
+    // Keep the rest of WM_HINTS (icon, urgency); only turn off input focus.
+    let mut wm_hints = conn
+        .get_property(false, window, AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, 0, 9)
+        .map_err(|e| e.to_string())?
+        .reply()
+        .ok()
+        .and_then(|reply| reply.value32().map(|values| values.collect::<Vec<_>>()))
+        .unwrap_or_default();
+    wm_hints.resize(9, 0);
+    wm_hints[0] |= WM_HINTS_INPUT;
+    wm_hints[1] = 0;
+    conn.change_property32(PropMode::REPLACE, window, AtomEnum::WM_HINTS, AtomEnum::WM_HINTS, &wm_hints)
+        .map_err(|e| e.to_string())?;
+
+    // A user time of 0 tells the WM not to focus the window when it maps.
+    let user_time = atom(conn, b"_NET_WM_USER_TIME")?;
+    conn.change_property32(PropMode::REPLACE, window, user_time, AtomEnum::CARDINAL, &[0])
+        .map_err(|e| e.to_string())?;
+
+    let above = atom(conn, b"_NET_WM_STATE_ABOVE")?;
+    let hidden = atom(conn, b"_NET_WM_STATE_HIDDEN")?;
+    let below = atom(conn, b"_NET_WM_STATE_BELOW")?;
+    let skip_taskbar = atom(conn, b"_NET_WM_STATE_SKIP_TASKBAR")?;
+    set_wm_state(conn, root, window, NET_WM_STATE_REMOVE, [above, hidden])?;
+    set_wm_state(conn, root, window, NET_WM_STATE_ADD, [below, skip_taskbar])?;
+    conn.map_window(window).map_err(|e| e.to_string())?;
+    conn.flush().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Find and arrange the windows of each `(setup_id, pid)`. Setups whose
+/// Dolphin has no window yet are reported as warnings.
+pub fn arrange_windows(targets: &[(u32, u32)]) -> Result<CaptureArrangement, String> {
+    require_x11("Arranging Dolphin windows")?;
+    let (conn, screen_num) = slippi_x11_connect()?;
+    let root = conn.setup().roots[screen_num].root;
+    let mut arrangement = CaptureArrangement { windows: Vec::new(), warnings: Vec::new() };
+    for &(setup_id, pid) in targets {
+        let windows = windows_for_pids(&conn, root, &process_tree(pid))?;
+        if windows.is_empty() {
+            arrangement.warnings.push(format!("Setup {setup_id}: no window found for Dolphin (pid {pid})."));
+            continue;
+        }
+        for (window, window_pid) in windows {
+            if let Err(err) = apply_capture_hints(&conn, root, window) {
+                arrangement.warnings.push(format!("Setup {setup_id}: {err}"));
+                continue;
+            }
+            arrangement.windows.push(CaptureWindow {
+                setup_id,
+                pid: window_pid,
+                window,
+                title: read_window_title(&conn, window),
+            });
+        }
+    }
+    Ok(arrangement)
+}
+
+/// Arrange a just-launched Dolphin as soon as it maps its window, before
+/// it has a chance to take focus from the operator.
+pub fn arrange_when_mapped(setup_id: u32, pid: u32) {
+    if !capture_window_hints_enabled() || test_flags().fake_streams {
+        return;
+    }
+    if !display_support().x11 {
+        tracing::debug!("No X11 display; leaving setup {}'s Dolphin window as it is", setup_id);
+        return;
+    }
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        while started.elapsed() < WINDOW_WAIT {
+            sleep(WINDOW_POLL);
+            match arrange_windows(&[(setup_id, pid)]) {
+                Ok(arrangement) if !arrangement.windows.is_empty() => return,
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::warn!("Arrange Dolphin window for setup {}: {}", setup_id, err);
+                    return;
+                }
+            }
+        }
+        tracing::warn!("Dolphin for setup {} never mapped a window; not arranged", setup_id);
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Put every setup's Dolphin back below other windows, undecorated and
+/// unfocusable, e.g. after a window manager restart or a stray click.
+#[tauri::command]
+#[specta::specta]
+pub fn arrange_capture_windows(store: State<'_, SharedSetupStore>) -> Result<CaptureArrangement, String> {
+    let targets = {
+        let guard = store.lock().map_err(|e| e.to_string())?;
+        let mut targets = guard
+            .processes
+            .iter()
+            .map(|(setup_id, child)| (*setup_id, child.id()))
+            .chain(guard.process_pids.iter().map(|(setup_id, pid)| (*setup_id, *pid)))
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        targets
+    };
+    if targets.is_empty() {
+        return Err("No Dolphin is running for any setup.".to_string());
+    }
+    arrange_windows(&targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    #[test]
+    fn parent_pids_are_read_past_odd_command_names() {
+        assert_eq!(parent_pid("4242 (Dolphin) S 4200 4242 4242 0"), Some(4200));
+        assert_eq!(parent_pid("4243 (obs (game) capture) S 17 4243"), Some(17));
+        assert_eq!(parent_pid("4244 (truncated"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn a_process_tree_includes_its_children() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let tree = process_tree(std::process::id());
+        let below_child = process_tree(child.id());
+        let _ = child.kill();
+        let _ = child.wait();
+        assert!(tree.contains(&std::process::id()));
+        assert!(tree.contains(&child.id()));
+        assert!(!below_child.contains(&std::process::id()));
+    }
+}
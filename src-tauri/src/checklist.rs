@@ -0,0 +1,443 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::startgg::startgg_graphql_request;
+use crate::startup::check_obs_websocket;
+use crate::types::{AppConfig, FeatureFlag};
+use crate::MAIN_OVERLAY_ADDR;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use specta::Type;
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Read,
+    path::PathBuf,
+    sync::Mutex,
+    time::Duration,
+};
+
+const OVERLAY_TIMEOUT: Duration = Duration::from_secs(3);
+/// Disc images Dolphin can run whose game id isn't at the start of the file.
+const COMPRESSED_IMAGES: &[&str] = &["rvz", "wia", "gcz", "ciso"];
+
+/// Manual items the crew has ticked off this session.
+static DONE: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Checks the app can run by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoCheck {
+    /// The configured ISO is Melee NTSC 1.02.
+    IsoVerified,
+    /// obs-websocket answers at the configured URL.
+    ObsConnected,
+    /// The overlay server answers /state.json.
+    OverlayReachable,
+    /// The start.gg token is accepted.
+    StartggToken,
+    /// A file can be written to the spectate folder.
+    SpectateFolderWritable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistItem {
+    pub id: String,
+    pub label: String,
+    /// Run automatically; items without one are ticked off by hand.
+    #[serde(default)]
+    pub check: Option<AutoCheck>,
+    /// What to do when it fails, or how to check it by hand.
+    #[serde(default)]
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Checklist {
+    pub items: Vec<ChecklistItem>,
+}
+
+impl Default for Checklist {
+    fn default() -> Self {
+        let item = |id: &str, label: &str, check: Option<AutoCheck>, hint: &str| ChecklistItem {
+            id: id.to_string(),
+            label: label.to_string(),
+            check,
+            hint: Some(hint.to_string()),
+        };
+        Checklist {
+            items: vec![
+                item(
+                    "iso",
+                    "Melee ISO verified",
+                    Some(AutoCheck::IsoVerified),
+                    "Point Settings at an NTSC 1.02 ISO.",
+                ),
+                item(
+                    "obs",
+                    "OBS connected",
+                    Some(AutoCheck::ObsConnected),
+                    "Start OBS and enable obs-websocket.",
+                ),
+                item(
+                    "overlay",
+                    "Overlay server reachable",
+                    Some(AutoCheck::OverlayReachable),
+                    "Restart the app if port 17890 is taken.",
+                ),
+                item(
+                    "startgg",
+                    "start.gg token valid",
+                    Some(AutoCheck::StartggToken),
+                    "Paste a fresh token from start.gg developer settings.",
+                ),
+                item(
+                    "spectate",
+                    "Spectate folder writable",
+                    Some(AutoCheck::SpectateFolderWritable),
+                    "Pick a folder on a disk with free space.",
+                ),
+                item(
+                    "audio",
+                    "Commentary audio levels checked",
+                    None,
+                    "Both mics and game audio peak in the yellow.",
+                ),
+                item(
+                    "scenes",
+                    "Stream title and scenes look right",
+                    None,
+                    "Title, break scene and every setup's overlay.",
+                ),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not automated and not ticked off yet.
+    Manual,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistResult {
+    pub id: String,
+    pub label: String,
+    pub status: CheckStatus,
+    pub automated: bool,
+    pub message: Option<String>,
+    pub hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ChecklistRun {
+    pub items: Vec<ChecklistResult>,
+    pub passed: usize,
+    pub failed: usize,
+    pub manual: usize,
+    pub ran_at_ms: u64,
+}
+
+pub fn checklist_path() -> PathBuf {
+    repo_root().join("checklist.json")
+}
+
+pub fn load_checklist() -> Result<Checklist, String> {
+    let path = checklist_path();
+    if !path.is_file() {
+        return Ok(Checklist::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("read checklist {}: {e}", path.display()))?;
+    serde_json::from_str(&data).map_err(|e| format!("parse checklist {}: {e}", path.display()))
+}
+
+fn save_checklist_inner(mut checklist: Checklist) -> Result<Checklist, String> {
+    let mut ids = HashSet::new();
+    for item in &mut checklist.items {
+        item.id = item.id.trim().to_string();
+        if item.id.is_empty() {
+            return Err(format!("Checklist item '{}' needs an id.", item.label));
+        }
+        if !ids.insert(item.id.clone()) {
+            return Err(format!("Duplicate checklist item id '{}'.", item.id));
+        }
+    }
+    let path = checklist_path();
+    let body = serde_json::to_vec_pretty(&checklist).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write checklist {}: {e}", path.display()))?;
+    Ok(checklist)
+}
+
+/// Verdict on the first bytes of a disc image: game id, then the disc
+/// revision at offset 7.
+fn iso_header_verdict(header: &[u8]) -> Result<String, String> {
+    match header.get(..6) {
+        Some(b"GALE01") => match header.get(7) {
+            Some(2) => Ok("Melee NTSC 1.02.".to_string()),
+            Some(rev) => Err(format!("Melee NTSC 1.0{rev}; Slippi needs 1.02.")),
+            None => Err("The ISO is truncated.".to_string()),
+        },
+        Some(b"GALP01") => Err("PAL Melee; Slippi needs NTSC 1.02.".to_string()),
+        Some(id) => Err(format!("Not a Melee ISO (game id {}).", String::from_utf8_lossy(id))),
+        None => Err("The ISO is truncated.".to_string()),
+    }
+}
+
+fn check_iso(config: &AppConfig) -> Result<String, String> {
+    let raw = config.ssbm_iso_path.trim();
+    if raw.is_empty() {
+        return Err("No Melee ISO is configured.".to_string());
+    }
+    let path = resolve_repo_path(raw);
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let mut file = File::open(&path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    if COMPRESSED_IMAGES.contains(&extension.as_str()) {
+        return Ok(format!("Compressed {} image found; its version can't be checked.", extension.to_uppercase()));
+    }
+    let mut header = Vec::with_capacity(8);
+    file.by_ref()
+        .take(8)
+        .read_to_end(&mut header)
+        .map_err(|e| format!("read {}: {e}", path.display()))?;
+    iso_header_verdict(&header)
+}
+
+fn check_obs(config: &AppConfig) -> Result<String, String> {
+    if !feature_enabled(FeatureFlag::Obs) {
+        return Ok("OBS is disabled in feature flags; not checked.".to_string());
+    }
+    let url = config.obs_websocket_url.trim();
+    if url.is_empty() {
+        return Err("No obs-websocket URL is configured.".to_string());
+    }
+    check_obs_websocket(url).map(|version| format!("obs-websocket {version} at {url}."))
+}
+
+fn check_overlay() -> Result<String, String> {
+    let url = format!("http://{MAIN_OVERLAY_ADDR}/state.json");
+    let client = reqwest::blocking::Client::builder()
+        .no_proxy()
+        .timeout(OVERLAY_TIMEOUT)
+        .build()
+        .map_err(|e| format!("build overlay client: {e}"))?;
+    let response = client.get(&url).send().map_err(|e| format!("{url}: {e}"))?;
+    if !response.status().is_success() {
+        return Err(format!("{url} answered {}.", response.status()));
+    }
+    Ok(format!("{url} answers."))
+}
+
+fn check_startgg_token(config: &AppConfig) -> Result<String, String> {
+    let data: Value = startgg_graphql_request(config, "query { currentUser { id } }", json!({}))?;
+    if data.pointer("/currentUser/id").is_some_and(|id| !id.is_null()) {
+        Ok("Token accepted.".to_string())
+    } else {
+        Err("start.gg didn't recognise the token.".to_string())
+    }
+}
+
+fn check_spectate_folder(config: &AppConfig) -> Result<String, String> {
+    let raw = config.spectate_folder_path.trim();
+    if raw.is_empty() {
+        return Err("No spectate folder is configured.".to_string());
+    }
+    let dir = resolve_repo_path(raw);
+    if !dir.is_dir() {
+        return Err(format!("{} doesn't exist.", dir.display()));
+    }
+    let probe = dir.join(format!(".checklist-{}", now_ms()));
+    fs::write(&probe, b"ok").map_err(|e| format!("write to {}: {e}", dir.display()))?;
+    let _ = fs::remove_file(&probe);
+    Ok(format!("{} is writable.", dir.display()))
+}
+
+fn run_check(check: AutoCheck, config: &AppConfig) -> Result<String, String> {
+    match check {
+        AutoCheck::IsoVerified => check_iso(config),
+        AutoCheck::ObsConnected => check_obs(config),
+        AutoCheck::OverlayReachable => check_overlay(),
+        AutoCheck::StartggToken => check_startgg_token(config),
+        AutoCheck::SpectateFolderWritable => check_spectate_folder(config),
+    }
+}
+
+fn manual_result(item: &ChecklistItem, done: &[String]) -> ChecklistResult {
+    let ticked = done.contains(&item.id);
+    ChecklistResult {
+        id: item.id.clone(),
+        label: item.label.clone(),
+        status: if ticked { CheckStatus::Pass } else { CheckStatus::Manual },
+        automated: false,
+        message: ticked.then(|| "Checked off.".to_string()),
+        hint: item.hint.clone(),
+    }
+}
+
+fn summarize(items: Vec<ChecklistResult>, now: u64) -> ChecklistRun {
+    let count = |status: CheckStatus| items.iter().filter(|item| item.status == status).count();
+    ChecklistRun {
+        passed: count(CheckStatus::Pass),
+        failed: count(CheckStatus::Fail),
+        manual: count(CheckStatus::Manual),
+        items,
+        ran_at_ms: now,
+    }
+}
+
+/// Run every automated check, side by side since most wait on the network.
+pub fn run_checklist_now(checklist: &Checklist, config: &AppConfig) -> ChecklistRun {
+    let done = DONE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let items = std::thread::scope(|scope| {
+        let handles = checklist
+            .items
+            .iter()
+            .map(|item| (item, item.check.map(|check| scope.spawn(move || run_check(check, config)))))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|(item, handle)| {
+                let Some(handle) = handle else {
+                    return manual_result(item, &done);
+                };
+                let result = handle
+                    .join()
+                    .unwrap_or_else(|_| Err("The check crashed.".to_string()));
+                ChecklistResult {
+                    id: item.id.clone(),
+                    label: item.label.clone(),
+                    status: if result.is_ok() { CheckStatus::Pass } else { CheckStatus::Fail },
+                    automated: true,
+                    message: Some(result.unwrap_or_else(|err| err)),
+                    hint: item.hint.clone(),
+                }
+            })
+            .collect::<Vec<_>>()
+    });
+    summarize(items, now_ms())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_checklist() -> Result<Checklist, String> {
+    load_checklist()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn save_checklist(checklist: Checklist) -> Result<Checklist, String> {
+    let params = json!({ "items": checklist.items.len() });
+    audited("saveChecklist", params, || save_checklist_inner(checklist))
+}
+
+/// Pass, fail or manual for every item on the checklist.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn run_checklist() -> Result<ChecklistRun, String> {
+    let checklist = load_checklist()?;
+    let config = cached_config()?;
+    Ok(run_checklist_now(&checklist, &config))
+}
+
+/// Tick a manual item off, or untick it. Ticks last until the app restarts.
+#[tauri::command]
+#[specta::specta]
+pub fn set_checklist_item_done(id: String, done: bool) -> Result<(), String> {
+    let params = json!({ "itemId": id, "done": done });
+    audited("setChecklistItemDone", params, || {
+        let checklist = load_checklist()?;
+        let item = checklist
+            .items
+            .iter()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Checklist item '{id}' not found."))?;
+        if item.check.is_some() {
+            return Err(format!("'{}' is checked automatically.", item.label));
+        }
+        let mut ticked = DONE.lock().map_err(|e| e.to_string())?;
+        ticked.retain(|ticked_id| ticked_id != &id);
+        if done {
+            ticked.push(id);
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn only_ntsc_102_isos_pass() {
+        assert!(iso_header_verdict(b"GALE01\0\x02").is_ok());
+        assert_eq!(iso_header_verdict(b"GALE01\0\x01").unwrap_err(), "Melee NTSC 1.01; Slippi needs 1.02.");
+        assert!(iso_header_verdict(b"GALP01\0\0").is_err());
+        assert!(iso_header_verdict(b"GM8E01\0\0").unwrap_err().contains("GM8E01"));
+    }
+
+    #[test]
+    fn truncated_isos_fail() {
+        assert_eq!(iso_header_verdict(b"GAL").unwrap_err(), "The ISO is truncated.");
+        assert_eq!(iso_header_verdict(b"GALE01\0").unwrap_err(), "The ISO is truncated.");
+    }
+
+    #[test]
+    fn iso_files_are_read_unless_compressed() {
+        let dir = scratch_dir("checklist-iso");
+        let iso_path = dir.join("melee.iso").to_string_lossy().to_string();
+        let mut config = AppConfig { ssbm_iso_path: iso_path, ..AppConfig::default() };
+        assert!(check_iso(&config).unwrap_err().starts_with("open "));
+
+        fs::write(dir.join("melee.iso"), b"GALE01\0\x02 rest of the disc").unwrap();
+        assert_eq!(check_iso(&config).unwrap(), "Melee NTSC 1.02.");
+        fs::write(dir.join("melee.rvz"), b"RVZ\x01").unwrap();
+        config.ssbm_iso_path = dir.join("melee.rvz").to_string_lossy().to_string();
+        assert!(check_iso(&config).unwrap().starts_with("Compressed RVZ image"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn the_spectate_folder_must_exist_and_take_writes() {
+        let dir = scratch_dir("checklist-spectate");
+        let spectate_path = dir.to_string_lossy().to_string();
+        let mut config = AppConfig { spectate_folder_path: spectate_path, ..AppConfig::default() };
+        assert!(check_spectate_folder(&config).unwrap().ends_with("is writable."));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        config.spectate_folder_path = dir.join("missing").to_string_lossy().to_string();
+        assert!(check_spectate_folder(&config).unwrap_err().ends_with("doesn't exist."));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn manual_items_pass_once_ticked_off() {
+        let checklist = Checklist::default();
+        let audio = checklist.items.iter().find(|item| item.id == "audio").unwrap();
+        assert_eq!(manual_result(audio, &[]).status, CheckStatus::Manual);
+        assert_eq!(manual_result(audio, &["audio".to_string()]).status, CheckStatus::Pass);
+
+        let run = summarize(vec![manual_result(audio, &[]), manual_result(audio, &["audio".to_string()])], 0);
+        assert_eq!((run.passed, run.failed, run.manual), (1, 0, 1));
+    }
+}
@@ -0,0 +1,341 @@
+use crate::bindings::write_bindings;
+use crate::bracket_config::validate_bracket_config_str;
+use crate::config::*;
+use crate::replay::update_replay_index;
+use crate::startgg::load_startgg_sim_config_from;
+use crate::startgg_sim::{StartggSim, StartggSimState};
+use crate::types::OverlayReplayCache;
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const USAGE: &str = "\
+Usage:
+  to bracket validate <config.json> [--json]
+  to replays index <dir> [--json]
+  to sim run <config.json> [--complete] [--save] [--json]
+  to bindings [<out.ts>]
+
+Without a subcommand the desktop app starts as usual.";
+
+/// Why a subcommand failed. Bad arguments exit 2, like an unknown
+/// subcommand, and print the usage; anything else exits 1.
+#[derive(Debug)]
+enum CliError {
+    Usage(String),
+    Failed(String),
+}
+
+impl From<String> for CliError {
+    fn from(err: String) -> Self {
+        CliError::Failed(err)
+    }
+}
+
+/// Handle command-line subcommands. Returns the process exit code when the
+/// arguments named a subcommand, or None to start the GUI.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (first, second) = (args.first()?.as_str(), args.get(1).map(String::as_str));
+    let rest = args.get(2..).unwrap_or_default();
+    load_env_file();
+    let result = match (first, second) {
+        ("bracket", Some("validate")) => bracket_validate(rest),
+        ("replays", Some("index")) => replays_index(rest),
+        ("sim", Some("run")) => sim_run(rest),
+        ("bindings", _) => bindings(&args[1..]),
+        ("help", _) | ("--help", _) | ("-h", _) => {
+            println!("{USAGE}");
+            return Some(0);
+        }
+        ("bracket", _) | ("replays", _) | ("sim", _) => {
+            eprintln!("{USAGE}");
+            return Some(2);
+        }
+        _ => return None,
+    };
+    match result {
+        Ok(()) => Some(0),
+        Err(CliError::Usage(err)) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            Some(2)
+        }
+        Err(CliError::Failed(err)) => {
+            eprintln!("error: {err}");
+            Some(1)
+        }
+    }
+}
+
+/// Split `args` into at most one positional argument and the flags, which
+/// must be among `allowed`.
+fn parse_args<'a>(args: &'a [String], allowed: &[&str]) -> Result<(Option<&'a str>, Vec<&'a str>), CliError> {
+    let mut positional = None;
+    let mut flags = Vec::new();
+    for arg in args {
+        if arg.starts_with('-') {
+            if !allowed.contains(&arg.as_str()) {
+                return Err(CliError::Usage(format!("unknown option {arg}")));
+            }
+            flags.push(arg.as_str());
+        } else if positional.is_none() {
+            positional = Some(arg.as_str());
+        } else {
+            return Err(CliError::Usage(format!("unexpected argument {arg}")));
+        }
+    }
+    Ok((positional, flags))
+}
+
+fn required<'a>(positional: Option<&'a str>, what: &str) -> Result<&'a str, CliError> {
+    positional.ok_or_else(|| CliError::Usage(format!("missing {what}")))
+}
+
+/// Paths are taken relative to the working directory first, then resolved
+/// the way the app resolves them.
+fn cli_path(raw: &str, resolve: fn(&str) -> PathBuf) -> PathBuf {
+    let direct = PathBuf::from(raw);
+    if direct.exists() {
+        direct
+    } else {
+        resolve(raw)
+    }
+}
+
+fn bracket_config_path(raw: &str) -> Result<PathBuf, CliError> {
+    let path = cli_path(raw, resolve_startgg_sim_config_path);
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("bracket config not found at {}", path.display()).into())
+    }
+}
+
+// ── bracket validate ────────────────────────────────────────────────────
+
+fn bracket_validate(args: &[String]) -> Result<(), CliError> {
+    let (raw, flags) = parse_args(args, &["--json"])?;
+    let path = bracket_config_path(required(raw, "bracket config path")?)?;
+    let text = fs::read_to_string(&path).map_err(|e| format!("read {}: {e}", path.display()))?;
+    let issues = validate_bracket_config_str(&text);
+    if !issues.is_empty() {
+        if flags.contains(&"--json") {
+            let summary = json!({ "path": path.to_string_lossy(), "issues": issues });
+            println!("{}", serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?);
+        } else {
+            for issue in &issues {
+                eprintln!("{}: {issue}", path.display());
+            }
+        }
+        return Err(format!("{} problem(s) in {}", issues.len(), path.display()).into());
+    }
+    let summary = bracket_summary(&path)?;
+    if flags.contains(&"--json") {
+        println!("{}", serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?);
+    } else {
+        let event = |key: &str| summary["event"][key].as_str().unwrap_or_default().to_string();
+        println!("{}: ok", path.display());
+        println!("  event:    {} ({})", event("name"), event("slug"));
+        println!("  phases:   {}", summary["phases"]);
+        println!("  entrants: {}", summary["entrants"]);
+        println!("  sets:     {}", summary["sets"]);
+        if summary["referenceSets"] == json!(true) {
+            println!("  reference results included");
+        }
+    }
+    Ok(())
+}
+
+/// What `bracket validate --json` prints for a config that's valid.
+fn bracket_summary(path: &Path) -> Result<Value, String> {
+    let config = load_startgg_sim_config_from(path)?;
+    let mut sim = StartggSim::new(config, now_ms())?;
+    let state = sim.state(now_ms());
+    Ok(json!({
+        "path": path.to_string_lossy(),
+        "event": state.event,
+        "phases": state.phases.len(),
+        "entrants": state.entrants.len(),
+        "sets": state.sets.len(),
+        "referenceSets": sim.has_reference_sets(),
+    }))
+}
+
+// ── replays index ───────────────────────────────────────────────────────
+
+fn replays_index(args: &[String]) -> Result<(), CliError> {
+    let (raw, flags) = parse_args(args, &["--json"])?;
+    let dir = cli_path(required(raw, "replay directory")?, resolve_repo_path);
+    if !dir.is_dir() {
+        return Err(format!("replay directory not found at {}", dir.display()).into());
+    }
+    let mut cache = OverlayReplayCache::default();
+    update_replay_index(&mut cache, &dir)?;
+
+    if flags.contains(&"--json") {
+        let summary = index_summary(&dir, &cache);
+        println!("{}", serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    let mut codes = cache.code_index.iter().collect::<Vec<_>>();
+    codes.sort();
+    println!(
+        "{}: {} replays, {} connect codes",
+        dir.display(),
+        cache.replay_mtimes.len(),
+        codes.len()
+    );
+    for (code, path) in codes {
+        let name = Path::new(path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        println!("  {code:<12} {name}");
+    }
+    Ok(())
+}
+
+/// What `replays index --json` prints.
+fn index_summary(dir: &Path, cache: &OverlayReplayCache) -> Value {
+    json!({
+        "dir": dir.to_string_lossy(),
+        "replays": cache.replay_mtimes.len(),
+        "latestByCode": cache.code_index,
+    })
+}
+
+// ── bindings ────────────────────────────────────────────────────────────
+
+fn bindings(args: &[String]) -> Result<(), CliError> {
+    let (out, _) = parse_args(args, &[])?;
+    let path = write_bindings(out.map(Path::new))?;
+    println!("wrote {}", path.display());
+    Ok(())
+}
+
+// ── sim run ─────────────────────────────────────────────────────────────
+
+fn sim_run(args: &[String]) -> Result<(), CliError> {
+    let (raw, flags) = parse_args(args, &["--complete", "--save", "--json"])?;
+    let path = bracket_config_path(required(raw, "bracket config path")?)?;
+    let state = run_sim(&path, flags.contains(&"--complete"), flags.contains(&"--save"))?;
+    if flags.contains(&"--json") {
+        println!("{}", serde_json::to_string_pretty(&state).map_err(|e| e.to_string())?);
+        return Ok(());
+    }
+    let completed = state.sets.iter().filter(|set| set.state == "completed").count();
+    println!("{}: {}/{} sets completed", path.display(), completed, state.sets.len());
+    for set in state.sets.iter().filter(|set| set.state == "completed") {
+        let name = |slot: usize| {
+            set.slots
+                .get(slot)
+                .and_then(|slot| slot.entrant_name.clone())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let score = |slot: usize| set.slots.get(slot).and_then(|slot| slot.score).unwrap_or(0);
+        println!(
+            "  {:>6} {:<24} {} {} - {} {}",
+            set.id,
+            set.round_label,
+            name(0),
+            score(0),
+            score(1),
+            name(1)
+        );
+    }
+    if flags.contains(&"--save") {
+        println!("saved {}", StartggSim::persistence_path(&path).display());
+    }
+    Ok(())
+}
+
+/// Build the sim from the config at `path`, finish it with `complete`
+/// (from reference results when it has them) and save it with `save`.
+fn run_sim(path: &Path, complete: bool, save: bool) -> Result<StartggSimState, String> {
+    let config = load_startgg_sim_config_from(path)?;
+    let now = now_ms();
+    let mut sim = StartggSim::new(config, now)?;
+    if complete {
+        if sim.has_reference_sets() {
+            sim.complete_from_reference(now)?;
+        } else {
+            sim.complete_all_sets(now)?;
+        }
+    }
+    if save {
+        sim.save_state(path)?;
+    }
+    Ok(sim.state(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn bracket_file(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("cli-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let entrants = (1..=4)
+            .map(|i| json!({ "id": i, "name": format!("Player {i}"), "slippiCode": format!("P{i}#000"), "seed": i }))
+            .collect::<Vec<_>>();
+        let config = json!({
+            "event": { "id": "cli", "name": "CLI Weekly", "slug": "cli-weekly" },
+            "phases": [{ "id": "phase-1", "name": "Bracket", "bestOf": 3 }],
+            "entrants": entrants,
+            "simulation": { "manualMode": true },
+            "referenceTournamentLink": null,
+        });
+        let path = dir.join("bracket.json");
+        fs::write(&path, config.to_string()).unwrap();
+        path
+    }
+
+    #[test]
+    fn exit_codes_tell_usage_errors_from_failures() {
+        let path = bracket_file("exit");
+        let path = path.to_str().unwrap();
+        assert_eq!(run(&args(&["bracket", "validate", path])), Some(0));
+        assert_eq!(run(&args(&["bracket", "validate", path, "--json"])), Some(0));
+        assert_eq!(run(&args(&["bracket", "validate", path, "--complete"])), Some(2));
+        assert_eq!(run(&args(&["sim", "run", path, "--jsno"])), Some(2));
+        assert_eq!(run(&args(&["sim", "run"])), Some(2));
+        assert_eq!(run(&args(&["bracket", "validate", path, "extra"])), Some(2));
+        assert_eq!(run(&args(&["bindings", "out.ts", "--force"])), Some(2));
+        assert_eq!(run(&args(&["bracket", "lint"])), Some(2));
+        assert_eq!(run(&args(&["bracket", "validate", "/no/such/bracket.json"])), Some(1));
+        assert_eq!(run(&args(&["replays", "index", "/no/such/replays"])), Some(1));
+        assert_eq!(run(&args(&["help"])), Some(0));
+        assert_eq!(run(&args(&[])), None);
+        assert_eq!(run(&args(&["--devtools"])), None);
+    }
+
+    #[test]
+    fn json_summaries_keep_their_shape() {
+        let path = bracket_file("json");
+        let summary = bracket_summary(&path).unwrap();
+        let keys = summary.as_object().unwrap().keys().cloned().collect::<Vec<_>>();
+        assert_eq!(keys, ["entrants", "event", "path", "phases", "referenceSets", "sets"]);
+        assert_eq!(summary["event"]["slug"], "cli-weekly");
+        assert_eq!((summary["phases"].clone(), summary["entrants"].clone()), (json!(1), json!(4)));
+        assert_eq!(summary["referenceSets"], false);
+
+        let state = run_sim(&path, true, false).unwrap();
+        assert!(state.sets.iter().any(|set| set.state == "completed"));
+        assert!(state.sets.iter().all(|set| set.state == "completed" || set.state == "skipped"));
+        let value = serde_json::to_value(&state).unwrap();
+        assert!(value["sets"].is_array() && value["entrants"].is_array());
+
+        let dir = path.parent().unwrap();
+        let index = index_summary(dir, &OverlayReplayCache::default());
+        assert_eq!(index["replays"], 0);
+        assert!(index["latestByCode"].is_object());
+        assert_eq!(index["dir"], dir.to_string_lossy().as_ref());
+    }
+}
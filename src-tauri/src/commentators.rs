@@ -0,0 +1,209 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::types::{AllSetupsState, CommentaryState, MAX_SETUP_COUNT};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+/// More than this doesn't fit the commentary lower third.
+const MAX_COMMENTATORS: usize = 4;
+const MAX_NAME_CHARS: usize = 40;
+
+/// Who's on commentary: one desk for every setup, and setups with their
+/// own desk (e.g. a side stream) keyed by setup id.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CommentatorBoard {
+    pub global: Vec<CommentaryState>,
+    pub setups: BTreeMap<u32, Vec<CommentaryState>>,
+}
+
+/// Loaded on first use; the overlay reads it on every poll.
+static COMMENTATORS: Mutex<Option<CommentatorBoard>> = Mutex::new(None);
+
+pub fn commentators_path() -> PathBuf {
+    repo_root().join("commentators.json")
+}
+
+fn read_board() -> CommentatorBoard {
+    let path = commentators_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return CommentatorBoard::default();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable commentators {}: {}", path.display(), e);
+        CommentatorBoard::default()
+    })
+}
+
+fn with_board<R>(f: impl FnOnce(&mut CommentatorBoard) -> R) -> R {
+    let mut guard = COMMENTATORS.lock().unwrap_or_else(|e| e.into_inner());
+    let board = guard.get_or_insert_with(read_board);
+    f(board)
+}
+
+fn write_board(board: &CommentatorBoard) -> Result<(), String> {
+    let path = commentators_path();
+    let body = serde_json::to_vec_pretty(board).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write commentators {}: {e}", path.display()))
+}
+
+fn non_empty(raw: Option<String>) -> Option<String> {
+    raw.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// A Twitter username from "@name", "name" or a profile link.
+fn twitter_username(raw: &str) -> Option<String> {
+    let raw = raw.trim().trim_end_matches('/');
+    let raw = raw.rsplit_once('/').map(|(_, name)| name).unwrap_or(raw);
+    let name = raw.trim_start_matches('@');
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Trim each commentator and fill their handle from Twitter, like players'.
+/// Unnamed rows are dropped.
+fn normalize(commentators: Vec<CommentaryState>) -> Result<Vec<CommentaryState>, String> {
+    let commentators = commentators
+        .into_iter()
+        .filter_map(|commentator| {
+            let name = commentator.name.trim().to_string();
+            if name.is_empty() {
+                return None;
+            }
+            let twitter = commentator.twitter.as_deref().and_then(twitter_username);
+            let handle = non_empty(commentator.handle).or_else(|| twitter.as_ref().map(|name| format!("@{name}")));
+            Some(CommentaryState {
+                name,
+                handle,
+                active: commentator.active,
+                twitter,
+                pronouns: non_empty(commentator.pronouns),
+            })
+        })
+        .collect::<Vec<_>>();
+    if commentators.len() > MAX_COMMENTATORS {
+        return Err(format!("The overlay fits {MAX_COMMENTATORS} commentators."));
+    }
+    if let Some(long) = commentators.iter().find(|c| c.name.chars().count() > MAX_NAME_CHARS) {
+        return Err(format!("{} is over {MAX_NAME_CHARS} characters.", long.name));
+    }
+    Ok(commentators)
+}
+
+/// The commentators on a setup's overlay: its own desk, else the global one.
+fn commentators_for(board: &CommentatorBoard, setup_id: u32) -> &[CommentaryState] {
+    board.setups.get(&setup_id).unwrap_or(&board.global)
+}
+
+/// Put each setup's commentators on its overlay.
+pub fn apply_overlay_commentators(state: &mut AllSetupsState) {
+    with_board(|board| {
+        for (idx, setup) in state.setups.iter_mut().enumerate() {
+            setup.commentators = commentators_for(board, idx as u32 + 1).to_vec();
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_commentators() -> CommentatorBoard {
+    with_board(|board| board.clone())
+}
+
+/// Replace the global commentators, or with `setup_id` that setup's own.
+/// An empty list for a setup puts it back on the global desk.
+#[tauri::command]
+#[specta::specta]
+pub fn set_commentators(
+    setup_id: Option<u32>,
+    commentators: Vec<CommentaryState>,
+) -> Result<CommentatorBoard, String> {
+    let names = commentators.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+    audited("setCommentators", json!({ "setupId": setup_id, "names": names }), || {
+        if let Some(id) = setup_id {
+            if id == 0 || id as usize > MAX_SETUP_COUNT {
+                return Err(format!("Setup ids run from 1 to {MAX_SETUP_COUNT}."));
+            }
+        }
+        let commentators = normalize(commentators)?;
+        with_board(|board| {
+            let mut next = board.clone();
+            match setup_id {
+                Some(id) if commentators.is_empty() => {
+                    next.setups.remove(&id);
+                }
+                Some(id) => {
+                    next.setups.insert(id, commentators);
+                }
+                None => next.global = commentators,
+            }
+            write_board(&next)?;
+            *board = next.clone();
+            Ok(next)
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commentator(name: &str, twitter: Option<&str>) -> CommentaryState {
+        CommentaryState {
+            name: name.to_string(),
+            handle: None,
+            active: None,
+            twitter: twitter.map(str::to_string),
+            pronouns: Some(" she/her ".to_string()),
+        }
+    }
+
+    #[test]
+    fn names_and_pronouns_are_trimmed_and_blank_rows_dropped() {
+        let desk = normalize(vec![commentator(" Toph ", None), commentator("  ", None)]).unwrap();
+        assert_eq!(desk.len(), 1);
+        assert_eq!(desk[0].name, "Toph");
+        assert_eq!(desk[0].pronouns.as_deref(), Some("she/her"));
+    }
+
+    #[test]
+    fn twitter_links_and_handles_become_usernames() {
+        let desk = normalize(vec![
+            commentator("Toph", Some("https://twitter.com/TophSSBM/")),
+            commentator("Scar", Some("@scarbroadcasts")),
+            commentator("Ambi", Some("ambisinister")),
+        ])
+        .unwrap();
+        let usernames = desk.iter().map(|c| c.twitter.as_deref()).collect::<Vec<_>>();
+        assert_eq!(usernames, vec![Some("TophSSBM"), Some("scarbroadcasts"), Some("ambisinister")]);
+    }
+
+    #[test]
+    fn the_handle_is_filled_from_twitter_only_when_missing() {
+        let mut named = commentator("Scar", Some("@scarbroadcasts"));
+        named.handle = Some("Scar".to_string());
+        let desk = normalize(vec![commentator("Toph", Some("TophSSBM")), named, commentator("Ambi", None)]).unwrap();
+        assert_eq!(desk[0].handle.as_deref(), Some("@TophSSBM"));
+        assert_eq!(desk[1].handle.as_deref(), Some("Scar"));
+        assert_eq!(desk[2].handle, None);
+    }
+
+    #[test]
+    fn desks_that_do_not_fit_the_overlay_are_rejected() {
+        assert!(normalize((0..5).map(|i| commentator(&format!("C{i}"), None)).collect()).is_err());
+        assert!(normalize(vec![commentator(&"x".repeat(MAX_NAME_CHARS + 1), None)]).is_err());
+    }
+
+    #[test]
+    fn setups_fall_back_to_the_global_desk() {
+        let global = normalize(vec![commentator("Toph", None), commentator("Scar", None)]).unwrap();
+        let side = normalize(vec![commentator("Ambisinister", None)]).unwrap();
+        let board = CommentatorBoard { global, setups: BTreeMap::from([(2, side)]) };
+        assert_eq!(commentators_for(&board, 1).len(), 2);
+        assert_eq!(commentators_for(&board, 2).len(), 1);
+        assert_eq!(commentators_for(&board, 2)[0].name, "Ambisinister");
+    }
+}
@@ -1,4 +1,5 @@
 use crate::types::*;
+pub use melee_stream_core::text::*;
 use chrono::Local;
 use serde_json::Value;
 use std::{
@@ -7,10 +8,19 @@ use std::{
     fs,
     io::Write,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
     time::{SystemTime, UNIX_EPOCH},
 };
 
+/// The checkout the app reads and writes its files under. `APP_REPO_ROOT`
+/// points it somewhere else, e.g. a scratch tree for the command tests.
 pub fn repo_root() -> PathBuf {
+  if let Some(root) = env_default("APP_REPO_ROOT") {
+    return PathBuf::from(root);
+  }
   PathBuf::from(env!("CARGO_MANIFEST_DIR"))
     .parent()
     .map(|path| path.to_path_buf())
@@ -88,10 +98,55 @@ pub fn apply_env_defaults(mut config: AppConfig) -> AppConfig {
       config.startgg_token = value;
     }
   }
+  if config.asset_pack_path.trim().is_empty() {
+    if let Some(value) = env_default("ASSET_PACK_DIR") {
+      config.asset_pack_path = value;
+    }
+  }
+  if config.obs_path.trim().is_empty() {
+    if let Some(value) = env_default("OBS_PATH") {
+      config.obs_path = value;
+    }
+  }
+  if config.obs_websocket_password.trim().is_empty() {
+    if let Some(value) = env_default("OBS_WEBSOCKET_PASSWORD") {
+      config.obs_websocket_password = value;
+    }
+  }
+  if config.network_proxy_url.trim().is_empty() {
+    if let Some(value) = env_default("NETWORK_PROXY_URL") {
+      config.network_proxy_url = value;
+    }
+  }
+  if config.network_proxy_bypass.trim().is_empty() {
+    if let Some(value) = env_default("NETWORK_PROXY_BYPASS") {
+      config.network_proxy_bypass = value;
+    }
+  }
+  if config.startgg_webhook_secret.trim().is_empty() {
+    if let Some(value) = env_default("STARTGG_WEBHOOK_SECRET") {
+      config.startgg_webhook_secret = value;
+    }
+  }
+  // The URL has a non-empty default, so the env var wins unless the user
+  // saved something else.
+  let default_obs_url = AppConfig::default().obs_websocket_url;
+  if config.obs_websocket_url.trim().is_empty() || config.obs_websocket_url == default_obs_url {
+    if let Some(value) = env_default("OBS_WEBSOCKET_URL") {
+      config.obs_websocket_url = value;
+    }
+  }
   config
 }
 
-pub fn load_config_inner() -> Result<AppConfig, String> {
+// ── Config cache ────────────────────────────────────────────────────────
+
+/// In-process copy of config.json. All readers go through it, so the poller
+/// and file watchers never parse a half-written file, and saves update it
+/// while holding the write lock.
+static CONFIG_CACHE: RwLock<Option<AppConfig>> = RwLock::new(None);
+
+fn read_config_file() -> Result<AppConfig, String> {
   let path = config_path();
   if !path.is_file() {
     return Ok(apply_env_defaults(AppConfig::default()));
@@ -102,13 +157,113 @@ pub fn load_config_inner() -> Result<AppConfig, String> {
   Ok(apply_env_defaults(config))
 }
 
+/// Current config, read from disk only the first time.
+pub fn cached_config() -> Result<AppConfig, String> {
+  if let Some(config) = CONFIG_CACHE.read().unwrap_or_else(|e| e.into_inner()).as_ref() {
+    return Ok(config.clone());
+  }
+  let mut guard = CONFIG_CACHE.write().unwrap_or_else(|e| e.into_inner());
+  if let Some(config) = guard.as_ref() {
+    return Ok(config.clone());
+  }
+  let config = read_config_file()?;
+  *guard = Some(config.clone());
+  Ok(config)
+}
+
+/// Re-read config.json, picking up edits made outside the app.
+pub fn reload_config() -> Result<AppConfig, String> {
+  let mut guard = CONFIG_CACHE.write().unwrap_or_else(|e| e.into_inner());
+  let config = read_config_file()?;
+  *guard = Some(config.clone());
+  Ok(config)
+}
+
 pub fn save_config_inner(config: AppConfig) -> Result<AppConfig, String> {
+  let mut guard = CONFIG_CACHE.write().unwrap_or_else(|e| e.into_inner());
   let path = config_path();
   let payload = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
-  fs::write(&path, payload).map_err(|e| format!("write config {}: {e}", path.display()))?;
+  write_file_atomic(&path, payload.as_bytes()).map_err(|e| format!("write config {}: {e}", path.display()))?;
+  *guard = Some(apply_env_defaults(config.clone()));
   Ok(config)
 }
 
+/// Numbers each write's temp file, so threads writing the same file at once
+/// don't share one.
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write to a temp file next to `path` and rename it over `path`, so
+/// readers see either the old or the new contents, never a partial file.
+pub fn write_file_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+  write_atomic(path, contents, false)
+}
+
+/// [`write_file_atomic`] for secrets. On Unix the temp file is readable by
+/// the current user only before anything is written to it, so the secret is
+/// never on disk with looser permissions.
+pub fn write_private_file_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+  write_atomic(path, contents, true)
+}
+
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn write_atomic(path: &Path, contents: &[u8], private: bool) -> std::io::Result<()> {
+  let file_name = path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| "file".to_string());
+  let write_id = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+  let tmp_path = path.with_file_name(format!(".{file_name}.{}.{write_id}.tmp", std::process::id()));
+  let result = (|| {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    if private {
+      use std::os::unix::fs::OpenOptionsExt;
+      options.mode(0o600);
+    }
+    let mut file = options.open(&tmp_path)?;
+    #[cfg(unix)]
+    if private {
+      // `mode` only applies to a new file; a leftover temp file keeps its own.
+      use std::os::unix::fs::PermissionsExt;
+      file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)
+  })();
+  if result.is_err() {
+    let _ = fs::remove_file(&tmp_path);
+  }
+  result
+}
+
+/// Move `path` into `dir`, keeping its name. Copies when a rename can't
+/// cross devices. Nothing to do when `path` doesn't exist.
+pub fn archive_file(path: &Path, dir: &Path) -> Result<(), String> {
+  let Some(name) = path.file_name() else {
+    return Err(format!("{} has no file name.", path.display()));
+  };
+  if !path.exists() {
+    return Ok(());
+  }
+  fs::create_dir_all(dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+  let dest = dir.join(name);
+  if fs::rename(path, &dest).is_ok() {
+    return Ok(());
+  }
+  fs::copy(path, &dest).map_err(|e| format!("copy {} to {}: {e}", path.display(), dest.display()))?;
+  fs::remove_file(path).map_err(|e| format!("remove {}: {e}", path.display()))
+}
+
+/// Undo `archive_file`: move `dir`'s copy of `path` back where it was.
+pub fn restore_archived_file(path: &Path, dir: &Path) -> Result<(), String> {
+  let (Some(name), Some(parent)) = (path.file_name(), path.parent()) else {
+    return Err(format!("{} has no file name.", path.display()));
+  };
+  archive_file(&dir.join(name), parent)
+}
+
 pub fn load_env_file() {
   let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
   let env_path = manifest_dir.join("..").join(".env");
@@ -414,15 +569,21 @@ pub fn replay_pair_key(a: &str, b: &str) -> String {
   }
 }
 
-pub fn app_test_mode_enabled() -> bool {
-  match load_config_inner() {
-    Ok(config) => config.test_mode,
-    Err(_) => false,
+/// Test-mode flags in effect; all off if the config can't be read.
+pub fn test_flags() -> TestModeFlags {
+  match cached_config() {
+    Ok(config) => config.test_flags(),
+    Err(_) => TestModeFlags::default(),
   }
 }
 
+/// Whether anything at all is simulated.
+pub fn app_test_mode_enabled() -> bool {
+  test_flags().any()
+}
+
 pub fn log_env_warnings() {
-  let config = load_config_inner().unwrap_or_else(|_| AppConfig::default());
+  let config = cached_config().unwrap_or_else(|_| AppConfig::default());
   let mut warnings = Vec::new();
 
   if config.dolphin_path.trim().is_empty() && env_default("DOLPHIN_PATH").is_none() {
@@ -440,24 +601,36 @@ pub fn log_env_warnings() {
   }
 }
 
-pub fn normalize_broadcast_key(raw: &str) -> String {
-  raw.trim().to_lowercase()
-}
-
-pub fn normalize_tag_key(raw: &str) -> String {
-  let trimmed = strip_sponsor_tag(raw).trim();
-  if trimmed.is_empty() {
-    return String::new();
-  }
-  let without_code = trimmed.split('#').next().unwrap_or(trimmed);
-  without_code.trim().to_lowercase()
-}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::thread;
+
+  #[test]
+  fn concurrent_atomic_writes_each_land_whole() {
+    let dir = env::temp_dir().join(format!("write-atomic-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state.json");
+
+    let writers = (0..8u8)
+      .map(|idx| {
+        let path = path.clone();
+        thread::spawn(move || {
+          for _ in 0..20 {
+            write_file_atomic(&path, &[idx; 4096]).unwrap();
+          }
+        })
+      })
+      .collect::<Vec<_>>();
+    for writer in writers {
+      writer.join().unwrap();
+    }
 
-pub fn strip_sponsor_tag(raw: &str) -> &str {
-  let trimmed = raw.trim();
-  if let Some(idx) = trimmed.find('|') {
-    trimmed[idx + 1..].trim()
-  } else {
-    trimmed
+    let contents = fs::read(&path).unwrap();
+    assert_eq!(contents.len(), 4096);
+    assert!(contents.iter().all(|byte| *byte == contents[0]));
+    let leftovers = fs::read_dir(&dir).unwrap().count();
+    assert_eq!(leftovers, 1, "temp files were left behind");
   }
 }
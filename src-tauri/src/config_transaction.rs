@@ -0,0 +1,261 @@
+use crate::config::*;
+use crate::types::AppConfig;
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use std::{fs, io::ErrorKind, path::Path, sync::Mutex};
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with a `ConfigChange` once per committed transaction, however
+/// many steps it took.
+pub const CONFIG_CHANGED_EVENT: &str = "config-changed";
+
+/// Held for a whole transaction so two commands never interleave their
+/// steps, or roll back over each other's.
+static TRANSACTION: Mutex<()> = Mutex::new(());
+
+type Undo = Box<dyn FnOnce() -> Result<(), String>>;
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigChange {
+    /// The command that made the change, e.g. "saveConfig"
+    pub origin: String,
+    /// Config keys whose value changed, as they're named in config.json
+    pub changed: Vec<String>,
+    /// Steps applied besides writing the config, in order
+    pub steps: Vec<String>,
+    pub config: AppConfig,
+    pub at_ms: u64,
+}
+
+/// A change to the config and whatever depends on it (files, runtime state)
+/// that lands whole or not at all. Steps run in order; if one fails, the
+/// ones before it are undone in reverse and the config is put back.
+pub struct ConfigTransaction {
+    origin: &'static str,
+    before: AppConfig,
+    config: AppConfig,
+    saved: bool,
+    steps: Vec<String>,
+    undo: Vec<(String, Undo)>,
+}
+
+impl ConfigTransaction {
+    /// Outside `config_transaction` only in tests, which drive steps directly.
+    pub(crate) fn new(origin: &'static str, config: AppConfig) -> Self {
+        Self { origin, before: config.clone(), config, saved: false, steps: Vec::new(), undo: Vec::new() }
+    }
+
+    /// The config as it was when the transaction started.
+    pub fn before(&self) -> &AppConfig {
+        &self.before
+    }
+
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
+    /// The config to commit. Saved on commit unless `save` ran since.
+    pub fn config_mut(&mut self) -> &mut AppConfig {
+        self.saved = false;
+        &mut self.config
+    }
+
+    /// Apply one step with the new config; `undo` reverses it if a later
+    /// step fails.
+    pub fn step<R>(
+        &mut self,
+        name: &str,
+        apply: impl FnOnce(&AppConfig) -> Result<R, String>,
+        undo: impl FnOnce() -> Result<(), String> + 'static,
+    ) -> Result<R, String> {
+        let result = apply(&self.config).map_err(|e| format!("{name}: {e}"))?;
+        self.steps.push(name.to_string());
+        self.undo.push((name.to_string(), Box::new(undo)));
+        Ok(result)
+    }
+
+    /// Write config.json now, before the steps that follow, so they (and
+    /// anything they start) read the new config. Rolled back like a step.
+    pub fn save(&mut self) -> Result<(), String> {
+        save_config_inner(self.config.clone())?;
+        self.saved = true;
+        let before = self.before.clone();
+        self.undo.push(("config".to_string(), Box::new(move || save_config_inner(before).map(|_| ()))));
+        Ok(())
+    }
+
+    /// Replace `path` with `contents`; rolling back restores the old file,
+    /// or removes it if there wasn't one.
+    pub fn write_file(&mut self, name: &str, path: &Path, contents: &[u8]) -> Result<(), String> {
+        let previous = match fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(format!("{name}: read {}: {e}", path.display())),
+        };
+        let target = path.to_path_buf();
+        self.step(
+            name,
+            |_| write_file_atomic(path, contents).map_err(|e| format!("write {}: {e}", path.display())),
+            move || match previous {
+                Some(bytes) => {
+                    write_file_atomic(&target, &bytes).map_err(|e| format!("restore {}: {e}", target.display()))
+                }
+                None => match fs::remove_file(&target) {
+                    Err(e) if e.kind() != ErrorKind::NotFound => Err(format!("remove {}: {e}", target.display())),
+                    _ => Ok(()),
+                },
+            },
+        )
+    }
+
+    /// Undo every applied step, newest first. Returns what couldn't be undone.
+    pub(crate) fn rollback(&mut self) -> Vec<String> {
+        let mut failures = Vec::new();
+        while let Some((name, undo)) = self.undo.pop() {
+            if let Err(err) = undo() {
+                failures.push(format!("{name}: {err}"));
+            }
+        }
+        self.steps.clear();
+        failures
+    }
+
+    fn change(&self) -> ConfigChange {
+        ConfigChange {
+            origin: self.origin.to_string(),
+            changed: changed_keys(&self.before, &self.config),
+            steps: self.steps.clone(),
+            config: self.config.clone(),
+            at_ms: now_ms(),
+        }
+    }
+}
+
+/// Top-level keys that differ between two configs.
+fn changed_keys(before: &AppConfig, after: &AppConfig) -> Vec<String> {
+    let (Ok(Value::Object(before)), Ok(Value::Object(after))) =
+        (serde_json::to_value(before), serde_json::to_value(after))
+    else {
+        return Vec::new();
+    };
+    after
+        .iter()
+        .filter(|(key, value)| before.get(*key) != Some(*value))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Run `f` as one transaction over the current config. On success the
+/// config is saved (if `f` didn't already) and `config-changed` is emitted
+/// once, unless nothing changed; on failure every step is undone and the
+/// error says what, if anything, couldn't be.
+pub fn config_transaction<R>(
+    app: &AppHandle,
+    origin: &'static str,
+    f: impl FnOnce(&mut ConfigTransaction) -> Result<R, String>,
+) -> Result<(R, AppConfig), String> {
+    let _guard = TRANSACTION.lock().unwrap_or_else(|e| e.into_inner());
+    let mut txn = ConfigTransaction::new(origin, cached_config()?);
+    let result = f(&mut txn).and_then(|value| {
+        let change = txn.change();
+        if !txn.saved && !change.changed.is_empty() {
+            save_config_inner(txn.config.clone())?;
+        }
+        Ok((value, change))
+    });
+    match result {
+        Ok((value, change)) => {
+            if !change.changed.is_empty() || !change.steps.is_empty() {
+                tracing::info!("Config changed by {}: {}", origin, change.changed.join(", "));
+                let _ = app.emit(CONFIG_CHANGED_EVENT, &change);
+            }
+            Ok((value, change.config))
+        }
+        Err(err) => {
+            let failures = txn.rollback();
+            if failures.is_empty() {
+                tracing::warn!("Rolled back {}: {}", origin, err);
+                return Err(err);
+            }
+            tracing::error!("Rolling back {} left changes behind: {}", origin, failures.join("; "));
+            Err(format!("{err} Some changes couldn't be undone: {}.", failures.join("; ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, rc::Rc};
+
+    /// A step that records its name in `log` when undone.
+    fn logged_step(txn: &mut ConfigTransaction, log: &Rc<RefCell<Vec<&'static str>>>, name: &'static str) {
+        let undone = log.clone();
+        txn.step(name, |_| Ok(()), move || {
+            undone.borrow_mut().push(name);
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    #[test]
+    fn changed_keys_are_named_as_in_config_json() {
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        assert!(changed_keys(txn.before(), txn.config()).is_empty());
+        txn.config_mut().offline_mode = !txn.before().offline_mode;
+        assert_eq!(changed_keys(txn.before(), txn.config()), vec!["offlineMode"]);
+    }
+
+    #[test]
+    fn a_failed_step_is_not_recorded() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        logged_step(&mut txn, &log, "first");
+        let err = txn.step("second", |_| Err::<(), _>("disk full".to_string()), || Ok(())).unwrap_err();
+        assert_eq!(err, "second: disk full");
+        assert_eq!(txn.change().steps, vec!["first"]);
+    }
+
+    #[test]
+    fn rollback_undoes_steps_newest_first() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        for name in ["first", "second"] {
+            logged_step(&mut txn, &log, name);
+        }
+        assert!(txn.rollback().is_empty());
+        assert_eq!(*log.borrow(), vec!["second", "first"]);
+        assert!(txn.change().steps.is_empty());
+    }
+
+    #[test]
+    fn rollback_keeps_going_and_reports_what_could_not_be_undone() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        logged_step(&mut txn, &log, "first");
+        txn.step("broken undo", |_| Ok(()), || Err("gone".to_string())).unwrap();
+        assert_eq!(txn.rollback(), vec!["broken undo: gone"]);
+        assert_eq!(*log.borrow(), vec!["first"]);
+    }
+
+    #[test]
+    fn written_files_are_restored_on_rollback() {
+        let dir = std::env::temp_dir().join(format!("config-transaction-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let existing = dir.join("existing.txt");
+        let created = dir.join("created.txt");
+        fs::write(&existing, "before").unwrap();
+        let _ = fs::remove_file(&created);
+
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        txn.write_file("existing", &existing, b"after").unwrap();
+        txn.write_file("created", &created, b"new").unwrap();
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "after");
+        assert!(txn.rollback().is_empty());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "before");
+        assert!(!created.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::config::*;
+use crate::replay::extract_connect_codes;
+use crate::types::SharedOverlayCache;
+use peppi::io::slippi;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    fs,
+    io::{Cursor, Read},
+    path::Path,
+};
+use tauri::State;
+use unicode_normalization::UnicodeNormalization;
+
+/// Where a replay's connect codes came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum CodeSource {
+    /// Netplay block of the Game Start event; exact
+    GameStart,
+    /// Pattern scan over the raw file; can miss or misread codes
+    ByteScan,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayCodes {
+    pub codes: Vec<String>,
+    pub source: CodeSource,
+}
+
+/// `CONNECT_CODE_SOURCE`: `auto` (Game Start, then the byte scan),
+/// `game-start` or `byte-scan`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExtractionMode {
+    Auto,
+    GameStartOnly,
+    ByteScanOnly,
+}
+
+fn extraction_mode() -> ExtractionMode {
+    match env_default("CONNECT_CODE_SOURCE").as_deref().map(str::trim) {
+        Some("game-start") | Some("gameStart") => ExtractionMode::GameStartOnly,
+        Some("byte-scan") | Some("byteScan") => ExtractionMode::ByteScanOnly,
+        _ => ExtractionMode::Auto,
+    }
+}
+
+/// Codes in Game Start are Shift-JIS and may use full-width characters
+/// (`ＡＢＣ＃１２３`); fold them to the ASCII form the rest of the app uses.
+fn fold_connect_code(raw: &str) -> Option<String> {
+    let folded = raw.nfkc().collect::<String>();
+    let folded = folded.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+    folded.contains('#').then(|| folded.to_uppercase())
+}
+
+/// Netplay codes from the Game Start event, in port order. None when the
+/// replay can't be parsed or has no netplay block (offline or old replays).
+fn game_start_connect_codes(reader: &mut impl Read) -> Option<Vec<String>> {
+    slippi::de::parse_header(reader, None).ok()?;
+    let mut opts = slippi::de::Opts::default();
+    opts.skip_frames = true;
+    let state = slippi::de::parse_start(reader, Some(&opts)).ok()?;
+    let codes = state
+        .start()
+        .players
+        .iter()
+        .filter_map(|pl| pl.netplay.as_ref())
+        .filter_map(|netplay| fold_connect_code(&netplay.code.0))
+        .collect::<Vec<_>>();
+    (!codes.is_empty()).then_some(codes)
+}
+
+/// Connect codes in a replay, from Game Start when it has them and from
+/// the byte scan otherwise.
+pub fn connect_codes_from_bytes(bytes: &[u8]) -> ReplayCodes {
+    let mode = extraction_mode();
+    if mode != ExtractionMode::ByteScanOnly {
+        if let Some(codes) = game_start_connect_codes(&mut Cursor::new(bytes)) {
+            return ReplayCodes { codes, source: CodeSource::GameStart };
+        }
+        if mode == ExtractionMode::GameStartOnly {
+            return ReplayCodes { codes: Vec::new(), source: CodeSource::GameStart };
+        }
+    }
+    ReplayCodes {
+        codes: extract_connect_codes(bytes),
+        source: CodeSource::ByteScan,
+    }
+}
+
+pub fn connect_codes_for_file(path: &Path) -> Result<ReplayCodes, String> {
+    let bytes = fs::read(path).map_err(|e| format!("read replay {}: {e}", path.display()))?;
+    Ok(connect_codes_from_bytes(&bytes))
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Codes in one replay and how they were found, so the UI can flag matches
+/// that rest on the byte scan. Indexed replays answer from the cache.
+#[tauri::command]
+#[specta::specta]
+pub fn replay_connect_codes(
+    replay_path: String,
+    replay_cache: State<'_, SharedOverlayCache>,
+) -> Result<ReplayCodes, String> {
+    {
+        let cache = replay_cache.lock().map_err(|e| e.to_string())?;
+        let cached = cache.replay_codes.get(&replay_path).zip(cache.replay_code_sources.get(&replay_path));
+        if let Some((codes, source)) = cached {
+            return Ok(ReplayCodes { codes: codes.clone(), source: *source });
+        }
+    }
+    connect_codes_for_file(Path::new(&replay_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_width_codes_are_folded_to_ascii() {
+        assert_eq!(fold_connect_code("ＭＡＮＧ＃０\0\0").as_deref(), Some("MANG#0"));
+        assert_eq!(fold_connect_code("abc#123").as_deref(), Some("ABC#123"));
+        assert_eq!(fold_connect_code("Mang0"), None);
+    }
+
+    #[test]
+    fn bytes_without_a_game_start_fall_back_to_the_byte_scan() {
+        let found = connect_codes_from_bytes(b"not a replay ... ZAIN#908 ... MANG#001");
+        assert_eq!(found.source, CodeSource::ByteScan);
+        assert_eq!(found.codes, vec!["ZAIN#908", "MANG#001"]);
+    }
+}
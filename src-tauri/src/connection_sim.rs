@@ -0,0 +1,105 @@
+use crate::config::*;
+use crate::startgg_sim::SimRng;
+use crate::types::{AllSetupsState, ConnectionGrade, ConnectionQuality, PlayerState};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+/// `source` of simulated samples; a real integration would use its own.
+pub const SIMULATED_SOURCE: &str = "simulated";
+/// Samples hold for this long, so every poll within it agrees.
+const SAMPLE_MS: u64 = 1_000;
+/// One slow swing of a player's ping up and back down.
+const DRIFT_PERIOD_MS: u64 = 45_000;
+/// About one sample in this many is a lag spike.
+const SPIKE_ODDS: u64 = 40;
+
+fn player_seed(key: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    normalize_broadcast_key(key).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// A believable connection for the player keyed by `key` (connect code or
+/// tag) at `now_ms`: each player gets their own base ping that drifts
+/// slowly, with some jitter and the odd spike with packet loss.
+pub fn simulated_connection(key: &str, now_ms: u64) -> ConnectionQuality {
+    let seed = player_seed(key);
+    let sample = now_ms / SAMPLE_MS;
+    let base = 15 + (seed % 110) as f64;
+    let phase = (seed >> 8) as f64 / u64::MAX as f64 * std::f64::consts::TAU;
+    let cycle = (sample * SAMPLE_MS) as f64 / DRIFT_PERIOD_MS as f64 * std::f64::consts::TAU;
+    let drift = (cycle + phase).sin() * base * 0.15;
+
+    let mut rng = SimRng::new(seed ^ sample.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    let jitter_ms = (rng.next_u64() % (3 + base as u64 / 10)) as u32;
+    let spike = rng.next_u64() % SPIKE_ODDS == 0;
+    let (spike_ms, packet_loss_pct) = if spike {
+        (60 + rng.next_u64() % 60, (1 + rng.next_u64() % 8) as f32)
+    } else {
+        (0, 0.0)
+    };
+    let ping_ms = (base + drift).max(1.0) as u32 + spike_ms as u32;
+    ConnectionQuality {
+        ping_ms,
+        jitter_ms,
+        packet_loss_pct,
+        grade: ConnectionGrade::from_sample(ping_ms, jitter_ms, packet_loss_pct),
+        source: SIMULATED_SOURCE.to_string(),
+        sampled_at_ms: sample * SAMPLE_MS,
+    }
+}
+
+fn apply_player(player: &mut PlayerState, now_ms: u64) {
+    let key = player.connect_code.as_deref().unwrap_or(&player.tag);
+    if key.trim().is_empty() {
+        return;
+    }
+    player.connection = Some(simulated_connection(key, now_ms));
+}
+
+/// Fill in `connection` for every overlay player that doesn't have one.
+pub fn apply_simulated_connections(state: &mut AllSetupsState, now_ms: u64) {
+    for setup in state.setups.iter_mut() {
+        for player in [&mut setup.p1, &mut setup.p2] {
+            if player.connection.is_none() {
+                apply_player(player, now_ms);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_player_keeps_one_sample_until_the_next_is_due() {
+        let a = simulated_connection("AAA#123", 10_250);
+        assert_eq!(a, simulated_connection("aaa#123", 10_900));
+        assert_eq!(a.sampled_at_ms, 10_000);
+    }
+
+    #[test]
+    fn samples_say_they_are_simulated() {
+        assert_eq!(simulated_connection("AAA#123", 0).source, SIMULATED_SOURCE);
+    }
+
+    #[test]
+    fn pings_stay_plausible_and_match_their_grade() {
+        for at in (0..600_000).step_by(1_000) {
+            let sample = simulated_connection("BBB#456", at);
+            assert!(sample.ping_ms >= 1 && sample.ping_ms < 300);
+            let grade = ConnectionGrade::from_sample(sample.ping_ms, sample.jitter_ms, sample.packet_loss_pct);
+            assert_eq!(sample.grade, grade);
+        }
+    }
+
+    #[test]
+    fn any_packet_loss_or_a_high_ping_lowers_the_grade() {
+        assert_eq!(ConnectionGrade::from_sample(30, 5, 0.0), ConnectionGrade::Good);
+        assert_eq!(ConnectionGrade::from_sample(30, 5, 1.0), ConnectionGrade::Fair);
+        assert_eq!(ConnectionGrade::from_sample(110, 20, 0.0), ConnectionGrade::Poor);
+    }
+}
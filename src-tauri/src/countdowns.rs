@@ -0,0 +1,406 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::json_no_store;
+use crate::overlay_ws::publish;
+use crate::run_of_show::current_bracket_state;
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{sync::Mutex, thread::sleep, time::Duration};
+use tauri::State;
+
+/// Overlay socket topic the countdowns are pushed on.
+pub const COUNTDOWN_TOPIC: &str = "countdowns";
+
+const MAX_LABEL_CHARS: usize = 60;
+const MAX_DURATION_SECS: u64 = 24 * 60 * 60;
+/// How long a finished countdown stays up at 0:00 before it's dropped.
+const LINGER_MS: u64 = 10_000;
+pub const DEFAULT_DQ_MINUTES: u64 = 10;
+const MAX_DQ_MINUTES: u64 = 60;
+
+static BOARD: Mutex<CountdownBoard> = Mutex::new(CountdownBoard::new());
+
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CountdownRequest {
+    /// What it counts down to, e.g. "Top 8 starts in"
+    pub label: String,
+    /// Length from now; use this or `ends_at_ms`.
+    pub duration_secs: Option<u64>,
+    /// Wall-clock end, e.g. a scheduled start.
+    pub ends_at_ms: Option<u64>,
+    /// Only show on this setup's scenes; every scene when unset.
+    pub setup_id: Option<u32>,
+    /// Bracket set this is a DQ timer for; replaces the set's previous one.
+    #[serde(default)]
+    pub set_id: Option<u64>,
+}
+
+/// Changes to a running countdown; unset fields stay as they are.
+#[derive(Debug, Clone, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CountdownUpdate {
+    pub label: Option<String>,
+    /// Restart with this length from now.
+    pub duration_secs: Option<u64>,
+    pub ends_at_ms: Option<u64>,
+    /// Push the end back (or forward, when negative), e.g. "+5 minutes".
+    pub add_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct Countdown {
+    pub id: u64,
+    pub label: String,
+    pub setup_id: Option<u32>,
+    pub set_id: Option<u64>,
+    pub ends_at_ms: u64,
+    pub remaining_ms: u64,
+    pub finished: bool,
+}
+
+/// Payload of every push on the `countdowns` topic: all of them at once, so
+/// a scene never has to merge updates.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CountdownBoardState {
+    pub countdowns: Vec<Countdown>,
+    /// Server time of the tick, for scenes that want to interpolate.
+    pub now_ms: u64,
+}
+
+struct CountdownBoard {
+    countdowns: Vec<Countdown>,
+    next_id: u64,
+}
+
+fn clean_label(label: &str) -> Result<String, String> {
+    let label = label.trim().to_string();
+    if label.is_empty() {
+        return Err("Countdown label is empty.".to_string());
+    }
+    if label.chars().count() > MAX_LABEL_CHARS {
+        return Err(format!("Countdown label is over {MAX_LABEL_CHARS} characters."));
+    }
+    Ok(label)
+}
+
+fn end_from_duration(duration_secs: u64, now: u64) -> Result<u64, String> {
+    if duration_secs == 0 || duration_secs > MAX_DURATION_SECS {
+        return Err("Countdown length must be between 1 second and 24 hours.".to_string());
+    }
+    Ok(now + duration_secs * 1000)
+}
+
+fn end_from_wall_clock(ends_at_ms: u64, now: u64) -> Result<u64, String> {
+    if ends_at_ms <= now {
+        return Err("Countdown end is in the past.".to_string());
+    }
+    if ends_at_ms - now > MAX_DURATION_SECS * 1000 {
+        return Err("Countdown end is more than 24 hours away.".to_string());
+    }
+    Ok(ends_at_ms)
+}
+
+impl CountdownBoard {
+    const fn new() -> Self {
+        CountdownBoard {
+            countdowns: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    fn create(&mut self, request: CountdownRequest, now: u64) -> Result<Countdown, String> {
+        let label = clean_label(&request.label)?;
+        let ends_at_ms = match (request.duration_secs, request.ends_at_ms) {
+            (Some(secs), None) => end_from_duration(secs, now)?,
+            (None, Some(at)) => end_from_wall_clock(at, now)?,
+            _ => return Err("Give a countdown either a length or an end time.".to_string()),
+        };
+        let countdown = Countdown {
+            id: self.next_id,
+            label,
+            setup_id: request.setup_id,
+            set_id: request.set_id,
+            ends_at_ms,
+            remaining_ms: ends_at_ms - now,
+            finished: false,
+        };
+        self.next_id += 1;
+        if let Some(set_id) = countdown.set_id {
+            self.countdowns.retain(|existing| existing.set_id != Some(set_id));
+        }
+        self.countdowns.push(countdown.clone());
+        Ok(countdown)
+    }
+
+    fn update(&mut self, id: u64, update: CountdownUpdate, now: u64) -> Result<Countdown, String> {
+        let label = update.label.as_deref().map(clean_label).transpose()?;
+        let countdown = self
+            .countdowns
+            .iter_mut()
+            .find(|countdown| countdown.id == id)
+            .ok_or_else(|| format!("Countdown {id} not found."))?;
+        let mut ends_at_ms = match (update.duration_secs, update.ends_at_ms) {
+            (Some(_), Some(_)) => return Err("Give either a length or an end time, not both.".to_string()),
+            (Some(secs), None) => end_from_duration(secs, now)?,
+            (None, Some(at)) => end_from_wall_clock(at, now)?,
+            (None, None) => countdown.ends_at_ms,
+        };
+        if let Some(secs) = update.add_secs {
+            let shifted = ends_at_ms as i64 + secs.saturating_mul(1000);
+            ends_at_ms = end_from_wall_clock(shifted.max(0) as u64, now)?;
+        }
+        if let Some(label) = label {
+            countdown.label = label;
+        }
+        countdown.ends_at_ms = ends_at_ms;
+        countdown.remaining_ms = ends_at_ms.saturating_sub(now);
+        countdown.finished = countdown.remaining_ms == 0;
+        Ok(countdown.clone())
+    }
+
+    fn cancel(&mut self, id: u64) -> bool {
+        let before = self.countdowns.len();
+        self.countdowns.retain(|countdown| countdown.id != id);
+        self.countdowns.len() != before
+    }
+
+    /// Bring every countdown up to `now` and drop ones finished long enough
+    /// ago, soonest first.
+    fn tick(&mut self, now: u64) -> CountdownBoardState {
+        self.countdowns.retain(|countdown| now < countdown.ends_at_ms + LINGER_MS);
+        for countdown in self.countdowns.iter_mut() {
+            countdown.remaining_ms = countdown.ends_at_ms.saturating_sub(now);
+            countdown.finished = countdown.remaining_ms == 0;
+        }
+        self.countdowns.sort_by_key(|countdown| (countdown.ends_at_ms, countdown.id));
+        CountdownBoardState {
+            countdowns: self.countdowns.clone(),
+            now_ms: now,
+        }
+    }
+}
+
+fn board_state() -> CountdownBoardState {
+    BOARD.lock().unwrap_or_else(|e| e.into_inner()).tick(now_ms())
+}
+
+/// Push the board now rather than on the next tick, after a change.
+fn push_board() -> CountdownBoardState {
+    let state = board_state();
+    publish(COUNTDOWN_TOPIC, &state);
+    state
+}
+
+/// Tick on each whole second and push the board while anything is on it,
+/// plus once more when it empties so scenes clear.
+pub fn spawn_countdown_ticker() {
+    std::thread::spawn(|| {
+        let mut was_empty = true;
+        loop {
+            sleep(Duration::from_millis(1000 - now_ms() % 1000));
+            let state = board_state();
+            let empty = state.countdowns.is_empty();
+            if !empty || !was_empty {
+                publish(COUNTDOWN_TOPIC, &state);
+            }
+            was_empty = empty;
+        }
+    });
+}
+
+/// Start (or restart) a DQ timer for a bracket set, labelled with who has
+/// to show up, e.g. "DQ: Zain vs Cody".
+pub fn start_dq_timer_inner(
+    set_id: u64,
+    minutes: Option<u64>,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<Countdown, String> {
+    let minutes = minutes.unwrap_or(DEFAULT_DQ_MINUTES);
+    if minutes == 0 || minutes > MAX_DQ_MINUTES {
+        return Err(format!("DQ timers run between 1 and {MAX_DQ_MINUTES} minutes."));
+    }
+    let bracket = current_bracket_state(test_state, live_startgg).ok_or_else(|| "No bracket loaded.".to_string())?;
+    let set = bracket
+        .sets
+        .iter()
+        .find(|set| set.id == set_id)
+        .ok_or_else(|| format!("Set {set_id} not found."))?;
+    if set.state == "completed" {
+        return Err("Set is already complete.".to_string());
+    }
+    let names = set
+        .slots
+        .iter()
+        .map(|slot| slot.entrant_name.as_deref().unwrap_or("TBD"))
+        .collect::<Vec<_>>();
+    let request = CountdownRequest {
+        label: format!("DQ: {}", names.join(" vs ")).chars().take(MAX_LABEL_CHARS).collect(),
+        duration_secs: Some(minutes * 60),
+        ends_at_ms: None,
+        setup_id: None,
+        set_id: Some(set_id),
+    };
+    let countdown = BOARD.lock().map_err(|e| e.to_string())?.create(request, now_ms())?;
+    push_board();
+    Ok(countdown)
+}
+
+// ── HTTP routes ─────────────────────────────────────────────────────────
+
+/// GET /countdowns.json, for scenes that poll instead of using /ws.
+pub async fn get_countdowns_json() -> Response {
+    json_no_store(&board_state())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_countdowns() -> CountdownBoardState {
+    board_state()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_countdown(request: CountdownRequest) -> Result<Countdown, String> {
+    let params = json!({
+        "label": request.label,
+        "durationSecs": request.duration_secs,
+        "endsAtMs": request.ends_at_ms,
+        "setupId": request.setup_id,
+        "setId": request.set_id,
+    });
+    audited("createCountdown", params, || {
+        let countdown = BOARD.lock().map_err(|e| e.to_string())?.create(request, now_ms())?;
+        push_board();
+        Ok(countdown)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_countdown(id: u64, update: CountdownUpdate) -> Result<Countdown, String> {
+    let params = json!({
+        "id": id,
+        "label": update.label,
+        "durationSecs": update.duration_secs,
+        "endsAtMs": update.ends_at_ms,
+        "addSecs": update.add_secs,
+    });
+    audited("updateCountdown", params, || {
+        let countdown = BOARD.lock().map_err(|e| e.to_string())?.update(id, update, now_ms())?;
+        push_board();
+        Ok(countdown)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_countdown(id: u64) -> Result<bool, String> {
+    audited("cancelCountdown", json!({ "id": id }), || {
+        let removed = BOARD.lock().map_err(|e| e.to_string())?.cancel(id);
+        push_board();
+        Ok(removed)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn start_dq_timer(
+    set_id: u64,
+    minutes: Option<u64>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<Countdown, String> {
+    audited("startDqTimer", json!({ "setId": set_id, "minutes": minutes }), || {
+        start_dq_timer_inner(set_id, minutes, &test_state, &live_startgg)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(label: &str, secs: u64) -> CountdownRequest {
+        CountdownRequest {
+            label: label.to_string(),
+            duration_secs: Some(secs),
+            ends_at_ms: None,
+            setup_id: None,
+            set_id: None,
+        }
+    }
+
+    fn ids(state: CountdownBoardState) -> Vec<u64> {
+        state.countdowns.iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn a_countdown_needs_a_label_and_a_length() {
+        let mut board = CountdownBoard::new();
+        assert!(board.create(request("  ", 60), 0).is_err());
+        assert!(board.create(request("Never", 0), 0).is_err());
+        assert!(board.tick(0).countdowns.is_empty());
+    }
+
+    #[test]
+    fn the_board_shows_the_soonest_first() {
+        let mut board = CountdownBoard::new();
+        let top8 = board.create(request("Top 8 starts in", 600), 0).unwrap();
+        let brk = board.create(request("Break ends in", 60), 0).unwrap();
+        let state = board.tick(30_000);
+        assert_eq!(state.countdowns[0].remaining_ms, 30_000);
+        assert_eq!(ids(state), vec![brk.id, top8.id]);
+    }
+
+    #[test]
+    fn added_time_pushes_the_end_back() {
+        let mut board = CountdownBoard::new();
+        let brk = board.create(request("Break ends in", 60), 0).unwrap();
+        let pushed = CountdownUpdate { add_secs: Some(300), ..Default::default() };
+        assert_eq!(board.update(brk.id, pushed, 30_000).unwrap().ends_at_ms, 360_000);
+    }
+
+    #[test]
+    fn a_new_length_restarts_from_now() {
+        let mut board = CountdownBoard::new();
+        let top8 = board.create(request("Top 8 starts in", 600), 0).unwrap();
+        let restarted = CountdownUpdate { duration_secs: Some(120), label: Some("Back in".into()), ..Default::default() };
+        let updated = board.update(top8.id, restarted, 30_000).unwrap();
+        assert_eq!((updated.label.as_str(), updated.ends_at_ms), ("Back in", 150_000));
+    }
+
+    #[test]
+    fn a_finished_countdown_shows_zero_for_a_while_then_goes() {
+        let mut board = CountdownBoard::new();
+        let brk = board.create(request("Break ends in", 60), 0).unwrap();
+        let state = board.tick(60_000 + LINGER_MS - 1);
+        assert!(state.countdowns.iter().any(|c| c.id == brk.id && c.finished && c.remaining_ms == 0));
+        assert!(board.tick(60_000 + LINGER_MS).countdowns.is_empty());
+    }
+
+    #[test]
+    fn a_countdown_is_cancelled_once() {
+        let mut board = CountdownBoard::new();
+        let brk = board.create(request("Break ends in", 60), 0).unwrap();
+        assert!(board.cancel(brk.id));
+        assert!(!board.cancel(brk.id));
+        assert!(board.tick(0).countdowns.is_empty());
+    }
+
+    #[test]
+    fn a_set_has_one_dq_timer_at_a_time() {
+        let mut board = CountdownBoard::new();
+        let first = board.create(CountdownRequest { set_id: Some(7), ..request("DQ: A vs B", 600) }, 0).unwrap();
+        let second = board.create(CountdownRequest { set_id: Some(7), ..request("DQ: A vs B", 300) }, 0).unwrap();
+        assert_ne!(first.id, second.id);
+        assert_eq!(ids(board.tick(1000)), vec![second.id]);
+    }
+}
@@ -0,0 +1,370 @@
+use crate::audit_log::{audited, with_origin};
+use crate::config::*;
+use crate::json_no_store;
+use axum::{
+    extract::rejection::JsonRejection,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::sync::Mutex;
+
+const DEFAULT_TTL_MS: u64 = 8_000;
+const MIN_TTL_MS: u64 = 1_000;
+const MAX_TTL_MS: u64 = 60_000;
+const MAX_TEXT_CHARS: usize = 80;
+const MAX_QUEUED: usize = 10;
+/// A prompt still waiting after this long is dropped; "Clip that!" half a
+/// minute late is worse than nothing.
+const MAX_WAIT_MS: u64 = 30_000;
+const RATE_WINDOW_MS: u64 = 60_000;
+const RATE_LIMIT: usize = 12;
+
+/// Audit origin of prompts pushed over HTTP.
+const HTTP_ORIGIN: &str = "http";
+
+/// Error for a push over the rate limit; the HTTP route answers 429.
+pub const PROMPT_RATE_LIMITED: &str = "Too many crowd prompts; wait a moment before sending another.";
+
+static QUEUE: Mutex<PromptQueue> = Mutex::new(PromptQueue::new());
+
+/// A prompt as sent by production, from the app or over HTTP.
+#[derive(Debug, Clone, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRequest {
+    pub text: String,
+    /// Free-form style hint for the scene, e.g. "clip", "vote" or "hype".
+    pub kind: Option<String>,
+    /// Higher shows first and cuts off a lower prompt on screen. Default 0.
+    pub priority: Option<u8>,
+    pub ttl_ms: Option<u64>,
+    /// Only show on this setup's overlay; every setup when unset.
+    pub setup_id: Option<u32>,
+    /// 0-100 fill for a hype meter graphic.
+    pub meter: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CrowdPrompt {
+    pub id: u64,
+    pub text: String,
+    pub kind: Option<String>,
+    pub priority: u8,
+    pub setup_id: Option<u32>,
+    pub meter: Option<f32>,
+    pub ttl_ms: u64,
+    pub queued_at_ms: u64,
+    pub shown_at_ms: Option<u64>,
+    pub expires_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CrowdPromptState {
+    /// On screen now.
+    pub current: Option<CrowdPrompt>,
+    /// Waiting, in the order they will show.
+    pub queued: Vec<CrowdPrompt>,
+}
+
+/// One prompt on screen at a time, the rest waiting by priority.
+struct PromptQueue {
+    current: Option<CrowdPrompt>,
+    queued: Vec<CrowdPrompt>,
+    /// When recent prompts were accepted, for the rate limit.
+    accepted_at: Vec<u64>,
+    next_id: u64,
+}
+
+impl PromptQueue {
+    const fn new() -> Self {
+        PromptQueue {
+            current: None,
+            queued: Vec::new(),
+            accepted_at: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    fn show(prompt: &mut CrowdPrompt, now: u64) {
+        prompt.shown_at_ms = Some(now);
+        prompt.expires_at_ms = Some(now + prompt.ttl_ms);
+    }
+
+    /// Highest priority first, then oldest.
+    fn sort_queue(&mut self) {
+        self.queued.sort_by(|a, b| b.priority.cmp(&a.priority).then(a.id.cmp(&b.id)));
+    }
+
+    /// Expire the prompt on screen, drop stale waiting ones and show the
+    /// next if the screen is free.
+    fn advance(&mut self, now: u64) {
+        if self.current.as_ref().is_some_and(|p| p.expires_at_ms.is_some_and(|at| now >= at)) {
+            self.current = None;
+        }
+        self.queued.retain(|p| now.saturating_sub(p.queued_at_ms) < MAX_WAIT_MS);
+        if self.current.is_none() && !self.queued.is_empty() {
+            self.sort_queue();
+            let mut next = self.queued.remove(0);
+            PromptQueue::show(&mut next, now);
+            self.current = Some(next);
+        }
+    }
+
+    fn push(&mut self, request: PromptRequest, now: u64) -> Result<CrowdPrompt, String> {
+        let text = request.text.trim().to_string();
+        if text.is_empty() {
+            return Err("Prompt text is empty.".to_string());
+        }
+        if text.chars().count() > MAX_TEXT_CHARS {
+            return Err(format!("Prompt text is over {MAX_TEXT_CHARS} characters."));
+        }
+        if request.meter.is_some_and(|meter| !(0.0..=100.0).contains(&meter)) {
+            return Err("Meter must be between 0 and 100.".to_string());
+        }
+        self.accepted_at.retain(|at| now.saturating_sub(*at) < RATE_WINDOW_MS);
+        if self.accepted_at.len() >= RATE_LIMIT {
+            return Err(PROMPT_RATE_LIMITED.to_string());
+        }
+        self.advance(now);
+
+        let mut prompt = CrowdPrompt {
+            id: self.next_id,
+            text,
+            kind: request.kind.map(|kind| kind.trim().to_string()).filter(|kind| !kind.is_empty()),
+            priority: request.priority.unwrap_or(0),
+            setup_id: request.setup_id,
+            meter: request.meter,
+            ttl_ms: request.ttl_ms.unwrap_or(DEFAULT_TTL_MS).clamp(MIN_TTL_MS, MAX_TTL_MS),
+            queued_at_ms: now,
+            shown_at_ms: None,
+            expires_at_ms: None,
+        };
+        let preempts = self.current.as_ref().is_some_and(|current| prompt.priority > current.priority);
+        if self.current.is_none() || preempts {
+            PromptQueue::show(&mut prompt, now);
+            self.current = Some(prompt.clone());
+        } else {
+            if self.queued.len() >= MAX_QUEUED {
+                self.sort_queue();
+                // Make room by dropping the last to show, if it ranks lower.
+                match self.queued.last() {
+                    Some(last) if last.priority < prompt.priority => {
+                        self.queued.pop();
+                    }
+                    _ => return Err(format!("{MAX_QUEUED} prompts are already waiting.")),
+                }
+            }
+            self.queued.push(prompt.clone());
+        }
+        self.next_id += 1;
+        self.accepted_at.push(now);
+        Ok(prompt)
+    }
+
+    /// Take a prompt off screen or out of the queue; the one on screen
+    /// when `id` is unset.
+    fn dismiss(&mut self, id: Option<u64>, now: u64) -> bool {
+        let on_screen = match (id, self.current.as_ref()) {
+            (None, Some(_)) => true,
+            (Some(id), Some(current)) => current.id == id,
+            _ => false,
+        };
+        let removed = if on_screen {
+            self.current = None;
+            true
+        } else {
+            let before = self.queued.len();
+            self.queued.retain(|p| Some(p.id) != id);
+            self.queued.len() != before
+        };
+        self.advance(now);
+        removed
+    }
+
+    fn state(&mut self, now: u64) -> CrowdPromptState {
+        self.advance(now);
+        self.sort_queue();
+        CrowdPromptState {
+            current: self.current.clone(),
+            queued: self.queued.clone(),
+        }
+    }
+}
+
+pub fn push_prompt(request: PromptRequest) -> Result<CrowdPrompt, String> {
+    let prompt = QUEUE.lock().unwrap_or_else(|e| e.into_inner()).push(request, now_ms())?;
+    tracing::info!("Crowd prompt {}: {}", prompt.id, prompt.text);
+    Ok(prompt)
+}
+
+pub fn crowd_prompt_state() -> CrowdPromptState {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner()).state(now_ms())
+}
+
+// ── HTTP routes ─────────────────────────────────────────────────────────
+
+/// GET /prompts.json: what scenes should show now.
+pub async fn get_prompts_json() -> Response {
+    json_no_store(&crowd_prompt_state())
+}
+
+/// POST /prompts: fire a prompt from a Stream Deck, a commentary desk
+/// page or anything else that can send JSON. The body must be sent as
+/// `application/json`: a web page can only do that after a CORS preflight,
+/// which this server never answers, so pages open in the operator's browser
+/// can't put text on stream.
+pub async fn post_prompt(request: Result<Json<PromptRequest>, JsonRejection>) -> Response {
+    let Json(request) = match request {
+        Ok(request) => request,
+        Err(rejection) => return (rejection.status(), rejection.body_text()).into_response(),
+    };
+    let params = json!({ "text": request.text, "priority": request.priority });
+    match with_origin(HTTP_ORIGIN, || audited("pushCrowdPrompt", params, || push_prompt(request))) {
+        Ok(prompt) => (
+            StatusCode::CREATED,
+            [("Content-Type", "application/json")],
+            serde_json::to_string(&prompt).unwrap_or_else(|_| "{}".to_string()),
+        )
+            .into_response(),
+        Err(err) if err == PROMPT_RATE_LIMITED => (StatusCode::TOO_MANY_REQUESTS, err).into_response(),
+        Err(err) => (StatusCode::BAD_REQUEST, err).into_response(),
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn push_crowd_prompt(request: PromptRequest) -> Result<CrowdPrompt, String> {
+    let params = json!({ "text": request.text, "priority": request.priority });
+    audited("pushCrowdPrompt", params, || push_prompt(request))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn dismiss_crowd_prompt(id: Option<u64>) -> Result<bool, String> {
+    audited("dismissCrowdPrompt", json!({ "id": id }), || {
+        Ok(QUEUE.lock().map_err(|e| e.to_string())?.dismiss(id, now_ms()))
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn crowd_prompts() -> CrowdPromptState {
+    crowd_prompt_state()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(text: &str, priority: u8) -> PromptRequest {
+        PromptRequest {
+            text: text.to_string(),
+            kind: None,
+            priority: Some(priority),
+            ttl_ms: Some(5_000),
+            setup_id: None,
+            meter: None,
+        }
+    }
+
+    fn texts(prompts: &[CrowdPrompt]) -> Vec<&str> {
+        prompts.iter().map(|p| p.text.as_str()).collect()
+    }
+
+    #[test]
+    fn a_higher_priority_prompt_cuts_in_and_the_rest_wait_by_priority() {
+        let mut queue = PromptQueue::new();
+        queue.push(request("Clip that!", 0), 0).unwrap();
+        queue.push(request("Vote now", 0), 100).unwrap();
+        let urgent = queue.push(request("Game point", 5), 300).unwrap();
+        queue.push(request("Later", 1), 400).unwrap();
+
+        let state = queue.state(400);
+        assert_eq!(state.current.as_ref().map(|p| p.id), Some(urgent.id));
+        assert_eq!(texts(&state.queued), vec!["Later", "Vote now"]);
+    }
+
+    #[test]
+    fn the_next_prompt_shows_for_its_own_ttl() {
+        let mut queue = PromptQueue::new();
+        queue.push(request("Game point", 0), 300).unwrap();
+        queue.push(request("Later", 0), 400).unwrap();
+
+        let state = queue.state(5_300);
+        assert_eq!(state.current.as_ref().map(|p| p.text.as_str()), Some("Later"));
+        assert_eq!(state.current.as_ref().and_then(|p| p.expires_at_ms), Some(10_300));
+        assert!(queue.state(10_300).current.is_none());
+    }
+
+    #[test]
+    fn prompts_that_wait_too_long_are_dropped() {
+        let mut queue = PromptQueue::new();
+        queue.push(PromptRequest { ttl_ms: Some(MAX_TTL_MS), ..request("Long", 0) }, 0).unwrap();
+        queue.push(request("Stale", 0), 100).unwrap();
+        assert_eq!(texts(&queue.state(MAX_WAIT_MS).queued), vec!["Stale"]);
+        assert!(queue.state(MAX_WAIT_MS + 100).queued.is_empty());
+    }
+
+    #[test]
+    fn blank_long_and_out_of_range_prompts_are_rejected() {
+        let mut queue = PromptQueue::new();
+        assert!(queue.push(request("  ", 0), 0).is_err());
+        assert!(queue.push(request(&"x".repeat(MAX_TEXT_CHARS + 1), 0), 0).is_err());
+        assert!(queue.push(PromptRequest { meter: Some(101.0), ..request("Hype", 0) }, 0).is_err());
+        assert!(queue.state(0).current.is_none());
+    }
+
+    #[test]
+    fn pushes_are_rate_limited_per_window() {
+        let mut queue = PromptQueue::new();
+        // Each cuts in on the last, so the queue never fills first.
+        for i in 0..RATE_LIMIT {
+            queue.push(request("Hype", i as u8 + 1), i as u64).unwrap();
+        }
+        assert_eq!(queue.push(request("One more", 0), 1_000).unwrap_err(), PROMPT_RATE_LIMITED);
+        assert!(queue.push(request("One more", 0), RATE_WINDOW_MS).is_ok());
+    }
+
+    #[test]
+    fn dismissing_shows_the_next_prompt() {
+        let mut queue = PromptQueue::new();
+        queue.push(request("First", 0), 0).unwrap();
+        let second = queue.push(request("Second", 0), 0).unwrap();
+        queue.push(request("Third", 0), 0).unwrap();
+
+        assert!(queue.dismiss(Some(second.id), 10));
+        assert!(!queue.dismiss(Some(second.id), 10));
+        assert!(queue.dismiss(None, 20));
+        let state = queue.state(20);
+        assert_eq!(state.current.as_ref().map(|p| p.text.as_str()), Some("Third"));
+        assert_eq!(state.current.as_ref().and_then(|p| p.shown_at_ms), Some(20));
+    }
+
+    #[test]
+    fn a_prompt_posted_without_a_json_content_type_is_refused() {
+        let router = axum::Router::new().route("/prompts", axum::routing::post(post_prompt));
+        let status = tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+            reqwest::Client::new()
+                .post(format!("http://{addr}/prompts"))
+                .header("Content-Type", "text/plain")
+                .body(r#"{"text":"Clip that!"}"#)
+                .send()
+                .await
+                .unwrap()
+                .status()
+                .as_u16()
+        });
+        assert_eq!(status, 415);
+        assert!(crowd_prompt_state().current.is_none());
+    }
+}
@@ -1,5 +1,8 @@
+use crate::capabilities::require_dolphin;
+use crate::capture_windows::arrange_when_mapped;
 use crate::config::*;
 use crate::types::*;
+use crate::playback::PlaybackSession;
 use std::{
     collections::HashSet,
     env,
@@ -13,7 +16,7 @@ use std::{
 use tauri::State;
 
 pub fn dolphin_config() -> Result<DolphinConfig, String> {
-    if let Ok(config) = load_config_inner() {
+    if let Ok(config) = cached_config() {
         let dolphin_raw = config.dolphin_path.trim();
         let iso_raw = config.ssbm_iso_path.trim();
         if !dolphin_raw.is_empty() && !iso_raw.is_empty() {
@@ -248,7 +251,7 @@ pub fn apply_ld_preload(cmd: &mut Command, lib_path: &Path) {
 }
 
 pub fn dolphin_binary_path() -> Result<PathBuf, String> {
-    let config = load_config_inner()?;
+    let config = cached_config()?;
     let raw = config.dolphin_path.trim();
     if raw.is_empty() {
         return Err("Dolphin path is empty; set it in Settings or DOLPHIN_PATH.".to_string());
@@ -775,6 +778,25 @@ pub fn write_dolphin_config(user_dir: &Path) -> Result<(), String> {
     ini_set(&path, "Display", "Fullscreen", "True")
 }
 
+/// Write a setup's playback tuning into its Dolphin.ini. Only used for
+/// playback launches; live Dolphins keep their defaults.
+pub fn write_playback_tuning(user_dir: &Path, tuning: &PlaybackTuning) -> Result<(), String> {
+    let path = user_dir.join("Config").join("Dolphin.ini");
+    if let Some(backend) = tuning.audio_backend.as_deref().map(str::trim).filter(|b| !b.is_empty()) {
+        ini_set(&path, "DSP", "Backend", backend)?;
+    }
+    if let Some(dual_core) = tuning.dual_core {
+        ini_set(&path, "Core", "CPUThread", if dual_core { "True" } else { "False" })?;
+    }
+    if let Some(speed) = tuning.emulation_speed {
+        if !(0.0..=2.0).contains(&speed) {
+            return Err(format!("Emulation speed {speed} is out of range (0.0-2.0)."));
+        }
+        ini_set(&path, "Core", "EmulationSpeed", &format!("{speed:.2}"))?;
+    }
+    Ok(())
+}
+
 pub fn playback_output_dir() -> PathBuf {
     if let Ok(raw) = env::var("PLAYBACK_OUTPUT_DIR") {
         let trimmed = raw.trim();
@@ -786,7 +808,7 @@ pub fn playback_output_dir() -> PathBuf {
 }
 
 pub fn slippi_appimage_path() -> Result<PathBuf, String> {
-    let config = load_config_inner()?;
+    let config = cached_config()?;
     let trimmed = config.slippi_launcher_path.trim();
     if trimmed.is_empty() {
         return Err("Slippi launcher path is empty; set it in Settings or SLIPPI_APPIMAGE_PATH.".into());
@@ -815,6 +837,7 @@ pub fn target_display() -> Result<String, String> {
 }
 
 pub fn launch_dolphin_for_setup_internal(setup_id: u32) -> Result<Child, String> {
+    require_dolphin("Launching Dolphin")?;
     let config = dolphin_config()?;
     let user_dir = setup_user_dir(setup_id)?;
     write_gamesettings(&user_dir)?;
@@ -854,15 +877,29 @@ pub fn launch_dolphin_for_setup_internal(setup_id: u32) -> Result<Child, String>
         cmd.current_dir(dir);
     }
 
-    cmd.spawn()
-        .map_err(|e| format!("launch Dolphin for setup {setup_id}: {e}"))
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("launch Dolphin for setup {setup_id}: {e}"))?;
+    arrange_when_mapped(setup_id, child.id());
+    Ok(child)
 }
 
-pub fn launch_dolphin_playback_for_setup_internal(setup_id: u32, replay_path: &Path) -> Result<Child, String> {
+/// Launch a playback Dolphin for `replay_path`. The returned session owns the
+/// communication file Dolphin was started with, so the replay can be changed
+/// while it runs.
+pub fn launch_dolphin_playback_for_setup_internal(
+    setup_id: u32,
+    replay_path: &Path,
+) -> Result<(Child, PlaybackSession), String> {
+    require_dolphin("Replay playback")?;
     let config = dolphin_config()?;
     let user_dir = setup_user_dir(setup_id)?;
     write_gamesettings(&user_dir)?;
     write_dolphin_config(&user_dir)?;
+    if let Some(tuning) = cached_config()?.playback_tuning.get(&setup_id) {
+        write_playback_tuning(&user_dir, tuning)?;
+    }
+    crate::frame_pacing::clear_frame_pacing(setup_id);
 
     let output_dir = playback_output_dir();
     fs::create_dir_all(&output_dir)
@@ -870,9 +907,13 @@ pub fn launch_dolphin_playback_for_setup_internal(setup_id: u32, replay_path: &P
     let command_id = format!(
         "{}-{}",
         setup_id,
-        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis()
+        now_ms()
     );
-    let (playback_config, file_basename) = crate::replay::write_playback_config(replay_path, &output_dir, &command_id)?;
+    let file_basename = format!("playback_{command_id}");
+    let playback_config = output_dir.join(format!("{file_basename}.json"));
+    let mut session = PlaybackSession::new(setup_id, playback_config.clone(), &command_id);
+    session.load_replay(replay_path, None)?;
+    session.ensure_comm_file()?;
 
     let label = format!("dolphin-{setup_id}");
     let use_obs = obs_gamecapture_enabled();
@@ -914,11 +955,15 @@ pub fn launch_dolphin_playback_for_setup_internal(setup_id: u32, replay_path: &P
         cmd.current_dir(dir);
     }
 
-    cmd.spawn()
-        .map_err(|e| format!("launch Dolphin playback for setup {setup_id}: {e}"))
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("launch Dolphin playback for setup {setup_id}: {e}"))?;
+    arrange_when_mapped(setup_id, child.id());
+    Ok((child, session))
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn launch_dolphin_for_setup(setup_id: u32, store: State<'_, SharedSetupStore>) -> Result<(), String> {
     let (existing, existing_pid) = {
         let mut guard = store.lock().map_err(|e| e.to_string())?;
@@ -941,11 +986,14 @@ pub fn launch_dolphin_for_setup(setup_id: u32, store: State<'_, SharedSetupStore
     let child = launch_dolphin_for_setup_internal(setup_id)?;
     let mut guard = store.lock().map_err(|e| e.to_string())?;
     guard.processes.insert(setup_id, child);
+    guard.playback_sessions.remove(&setup_id);
     Ok(())
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn launch_dolphin_cli(extra_args: Option<Vec<String>>) -> Result<(), String> {
+    require_dolphin("Launching Dolphin")?;
     let config = dolphin_config()?;
     let mut cmd = Command::new(&config.dolphin_path);
     cmd.arg("-e")
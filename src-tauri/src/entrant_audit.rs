@@ -0,0 +1,330 @@
+use crate::config::*;
+use crate::reseed::has_started;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_sim::{StartggSimEntrant, StartggSimState};
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use crate::watch_party::parse_connect_code;
+use serde::Serialize;
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+use tauri::State;
+
+/// A tag this short only counts as the end of a longer one ("Mango" in
+/// "C9 Mango") when they're equal; otherwise "S" would match every tag
+/// ending in an S.
+const MIN_SIMILAR_TAG_LEN: usize = 3;
+
+/// Why two entrants look like the same player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateReason {
+    SameConnectCode,
+    /// Equal once case, width, sponsor prefix and punctuation are ignored
+    SameTag,
+    /// One tag ends with the other, e.g. an unpiped sponsor ("C9 Mango")
+    SimilarTag,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditedEntrant {
+    pub id: u32,
+    pub name: String,
+    pub seed: u32,
+    pub slippi_code: Option<String>,
+}
+
+/// Entrants that are probably one player registered more than once.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntrantCluster {
+    /// Sorted by seed
+    pub entrants: Vec<AuditedEntrant>,
+    pub reasons: Vec<DuplicateReason>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct EntrantAuditReport {
+    pub entrant_count: u32,
+    pub clusters: Vec<EntrantCluster>,
+    pub missing_codes: Vec<AuditedEntrant>,
+    /// Codes that aren't shaped like `ABCD#123`
+    pub invalid_codes: Vec<AuditedEntrant>,
+    /// Fixing registrations on start.gg gets harder once sets are underway.
+    pub started: bool,
+    /// Plain text for the TO to work from on start.gg.
+    pub text: String,
+    pub generated_at_ms: u64,
+}
+
+impl From<&StartggSimEntrant> for AuditedEntrant {
+    fn from(entrant: &StartggSimEntrant) -> Self {
+        Self {
+            id: entrant.id,
+            name: entrant.name.clone(),
+            seed: entrant.seed,
+            slippi_code: normalize_slippi_code(&entrant.slippi_code),
+        }
+    }
+}
+
+/// The tag with spaces and punctuation dropped, so "M2K" and "M-2K" match.
+fn compact_tag(key: &str) -> String {
+    key.chars().filter(|c| c.is_alphanumeric()).collect()
+}
+
+/// Whether one tag's words are the last words of the other's.
+fn tag_ends_with(a: &str, b: &str) -> bool {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    if compact_tag(short).chars().count() < MIN_SIMILAR_TAG_LEN {
+        return false;
+    }
+    let long = long.split_whitespace().collect::<Vec<_>>();
+    let short = short.split_whitespace().collect::<Vec<_>>();
+    short.len() < long.len() && long.ends_with(&short)
+}
+
+fn duplicate_reason(a: &AuditedEntrant, b: &AuditedEntrant) -> Option<DuplicateReason> {
+    if a.slippi_code.is_some() && a.slippi_code == b.slippi_code {
+        return Some(DuplicateReason::SameConnectCode);
+    }
+    let (key_a, key_b) = (normalize_tag_key(&a.name), normalize_tag_key(&b.name));
+    if key_a.is_empty() || key_b.is_empty() {
+        return None;
+    }
+    if key_a == key_b || compact_tag(&key_a) == compact_tag(&key_b) {
+        return Some(DuplicateReason::SameTag);
+    }
+    tag_ends_with(&key_a, &key_b).then_some(DuplicateReason::SimilarTag)
+}
+
+fn find_root(parents: &mut [usize], mut idx: usize) -> usize {
+    while parents[idx] != idx {
+        parents[idx] = parents[parents[idx]];
+        idx = parents[idx];
+    }
+    idx
+}
+
+/// Group entrants linked by any duplicate reason; a pair needn't match
+/// directly to end up together ("Mango", "C9 Mango", and a third entry
+/// with C9 Mango's code).
+fn cluster_entrants(entrants: &[AuditedEntrant]) -> Vec<EntrantCluster> {
+    let mut parents = (0..entrants.len()).collect::<Vec<_>>();
+    let mut reasons: Vec<(usize, DuplicateReason)> = Vec::new();
+    for (i, a) in entrants.iter().enumerate() {
+        for (j, b) in entrants.iter().enumerate().skip(i + 1) {
+            let Some(reason) = duplicate_reason(a, b) else {
+                continue;
+            };
+            let (root_a, root_b) = (find_root(&mut parents, i), find_root(&mut parents, j));
+            parents[root_b] = root_a;
+            reasons.push((i, reason));
+        }
+    }
+
+    let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for idx in 0..entrants.len() {
+        groups.entry(find_root(&mut parents, idx)).or_default().push(idx);
+    }
+    let mut group_reasons: HashMap<usize, Vec<DuplicateReason>> = HashMap::new();
+    for (idx, reason) in reasons {
+        group_reasons.entry(find_root(&mut parents, idx)).or_default().push(reason);
+    }
+
+    let mut clusters = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let mut members = members.into_iter().map(|idx| entrants[idx].clone()).collect::<Vec<_>>();
+            members.sort_by_key(|entrant| (entrant.seed, entrant.id));
+            let mut reasons = group_reasons.remove(&root).unwrap_or_default();
+            reasons.sort_unstable();
+            reasons.dedup();
+            EntrantCluster { entrants: members, reasons }
+        })
+        .collect::<Vec<_>>();
+    clusters.sort_by_key(|cluster| cluster.entrants.first().map(|entrant| (entrant.seed, entrant.id)));
+    clusters
+}
+
+fn describe(entrant: &AuditedEntrant) -> String {
+    match entrant.slippi_code.as_deref() {
+        Some(code) => format!("(seed {}) {} [{}]", entrant.seed, entrant.name, code),
+        None => format!("(seed {}) {}", entrant.seed, entrant.name),
+    }
+}
+
+fn reason_label(reason: DuplicateReason) -> &'static str {
+    match reason {
+        DuplicateReason::SameConnectCode => "same connect code",
+        DuplicateReason::SameTag => "same tag",
+        DuplicateReason::SimilarTag => "similar tag",
+    }
+}
+
+fn report_text(event_name: &str, report: &EntrantAuditReport) -> String {
+    let mut lines = vec![format!("Entrant audit for {event_name} ({} entrants)", report.entrant_count)];
+    if report.started {
+        lines.push("Note: sets have already started.".to_string());
+    }
+    if report.clusters.is_empty() && report.missing_codes.is_empty() && report.invalid_codes.is_empty() {
+        lines.push(String::new());
+        lines.push("Nothing to fix.".to_string());
+        return lines.join("\n");
+    }
+    if !report.clusters.is_empty() {
+        lines.push(String::new());
+        lines.push("Possible duplicates:".to_string());
+        for cluster in &report.clusters {
+            let reasons = cluster.reasons.iter().map(|r| reason_label(*r)).collect::<Vec<_>>().join(", ");
+            lines.push(format!("  {reasons}:"));
+            for entrant in &cluster.entrants {
+                lines.push(format!("    {}", describe(entrant)));
+            }
+        }
+    }
+    if !report.invalid_codes.is_empty() {
+        lines.push(String::new());
+        lines.push("Connect codes that look wrong:".to_string());
+        for entrant in &report.invalid_codes {
+            lines.push(format!("  {}", describe(entrant)));
+        }
+    }
+    if !report.missing_codes.is_empty() {
+        lines.push(String::new());
+        lines.push("No connect code:".to_string());
+        for entrant in &report.missing_codes {
+            lines.push(format!("  {}", describe(entrant)));
+        }
+    }
+    lines.join("\n")
+}
+
+pub fn audit_bracket_entrants(state: &StartggSimState) -> EntrantAuditReport {
+    let mut entrants = state.entrants.iter().map(AuditedEntrant::from).collect::<Vec<_>>();
+    entrants.sort_by_key(|entrant| (entrant.seed, entrant.id));
+    let missing_codes = entrants.iter().filter(|e| e.slippi_code.is_none()).cloned().collect();
+    let invalid_codes = entrants
+        .iter()
+        .filter(|e| e.slippi_code.as_deref().is_some_and(|code| parse_connect_code(code).is_err()))
+        .cloned()
+        .collect();
+    let mut report = EntrantAuditReport {
+        entrant_count: entrants.len() as u32,
+        clusters: cluster_entrants(&entrants),
+        missing_codes,
+        invalid_codes,
+        started: has_started(state),
+        text: String::new(),
+        generated_at_ms: now_ms(),
+    };
+    report.text = report_text(&state.event.name, &report);
+    report
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Likely duplicate registrations and missing or malformed connect codes in
+/// the current bracket, to clean up before it starts.
+#[tauri::command]
+#[specta::specta]
+pub fn audit_entrants(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<EntrantAuditReport, String> {
+    let bracket =
+        current_bracket_state(&test_state, &live_startgg).ok_or_else(|| "No bracket is loaded.".to_string())?;
+    Ok(audit_bracket_entrants(&bracket))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entrant(id: u32, name: &str, code: &str) -> AuditedEntrant {
+        AuditedEntrant { id, name: name.to_string(), seed: id, slippi_code: normalize_slippi_code(code) }
+    }
+
+    fn ids(clusters: &[EntrantCluster]) -> Vec<Vec<u32>> {
+        clusters.iter().map(|cluster| cluster.entrants.iter().map(|e| e.id).collect()).collect()
+    }
+
+    #[test]
+    fn a_shared_connect_code_is_a_duplicate() {
+        let reason = duplicate_reason(&entrant(3, "Zain", "ZAIN#0"), &entrant(4, "PG | zain", "zain#0"));
+        assert_eq!(reason, Some(DuplicateReason::SameConnectCode));
+    }
+
+    #[test]
+    fn tags_equal_but_for_case_and_punctuation_are_duplicates() {
+        let reason = duplicate_reason(&entrant(5, "M-2K", ""), &entrant(6, "m2k", "MK#2"));
+        assert_eq!(reason, Some(DuplicateReason::SameTag));
+    }
+
+    #[test]
+    fn an_unpiped_sponsor_is_a_similar_tag() {
+        let reason = duplicate_reason(&entrant(1, "Mango", "MANG#0"), &entrant(2, "C9 Mango", ""));
+        assert_eq!(reason, Some(DuplicateReason::SimilarTag));
+    }
+
+    #[test]
+    fn short_tags_and_partial_words_are_not_similar() {
+        assert_eq!(duplicate_reason(&entrant(7, "Ax", ""), &entrant(8, "Max", "")), None);
+        assert_eq!(duplicate_reason(&entrant(7, "Ax", ""), &entrant(8, "C9 Ax", "")), None);
+        assert_eq!(duplicate_reason(&entrant(1, "Mango", ""), &entrant(2, "Mangoes", "")), None);
+    }
+
+    #[test]
+    fn clusters_are_sorted_by_seed_and_leave_singles_out() {
+        let entrants = vec![
+            entrant(1, "Mango", "MANG#0"),
+            entrant(2, "C9 Mango", ""),
+            entrant(3, "Zain", "ZAIN#0"),
+            entrant(4, "PG | zain", "zain#0"),
+            entrant(9, "Hungrybox", "HBOX#305"),
+        ];
+        assert_eq!(ids(&cluster_entrants(&entrants)), vec![vec![1, 2], vec![3, 4]]);
+    }
+
+    #[test]
+    fn entrants_linked_through_a_third_share_a_cluster() {
+        let entrants = vec![entrant(1, "Mango", ""), entrant(2, "C9 Mango", "C9M#1"), entrant(3, "Juan", "c9m#1")];
+        let clusters = cluster_entrants(&entrants);
+        assert_eq!(ids(&clusters), vec![vec![1, 2, 3]]);
+        assert_eq!(clusters[0].reasons, vec![DuplicateReason::SameConnectCode, DuplicateReason::SimilarTag]);
+    }
+
+    #[test]
+    fn the_report_text_lists_each_problem() {
+        let entrants = vec![entrant(1, "Mango", "MANG#0"), entrant(2, "C9 Mango", "")];
+        let report = EntrantAuditReport {
+            entrant_count: 2,
+            clusters: cluster_entrants(&entrants),
+            missing_codes: vec![entrants[1].clone()],
+            invalid_codes: Vec::new(),
+            started: true,
+            text: String::new(),
+            generated_at_ms: 0,
+        };
+        let text = report_text("Weekly", &report);
+        assert!(text.starts_with("Entrant audit for Weekly (2 entrants)\nNote: sets have already started."));
+        assert!(text.contains("  similar tag:\n    (seed 1) Mango [MANG#0]\n    (seed 2) C9 Mango"));
+        assert!(text.ends_with("No connect code:\n  (seed 2) C9 Mango"));
+    }
+
+    #[test]
+    fn a_clean_report_says_nothing_to_fix() {
+        let report = EntrantAuditReport {
+            entrant_count: 0,
+            clusters: Vec::new(),
+            missing_codes: Vec::new(),
+            invalid_codes: Vec::new(),
+            started: false,
+            text: String::new(),
+            generated_at_ms: 0,
+        };
+        assert_eq!(report_text("Weekly", &report), "Entrant audit for Weekly (0 entrants)\n\nNothing to fix.");
+    }
+}
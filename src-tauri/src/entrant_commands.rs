@@ -1,8 +1,10 @@
 use tauri::State;
+use crate::audit_log::audited;
+use serde_json::json;
 use crate::types::{SharedEntrantManager, SharedLiveStartgg, SharedSetupStore, UnifiedEntrant};
 
 /// Setup info with seed-based sorting
-#[derive(Clone, Debug, serde::Serialize)]
+#[derive(Clone, Debug, serde::Serialize, specta::Type)]
 #[serde(rename_all = "camelCase")]
 pub struct SetupWithSeed {
     pub id: u32,
@@ -14,6 +16,7 @@ pub struct SetupWithSeed {
 
 /// Get all unified entrants sorted for display
 #[tauri::command]
+#[specta::specta]
 pub fn get_unified_entrants(
     entrant_manager: State<'_, SharedEntrantManager>,
 ) -> Result<Vec<UnifiedEntrant>, String> {
@@ -23,38 +26,50 @@ pub fn get_unified_entrants(
 
 /// Set slippi code for an entrant (user edit)
 #[tauri::command]
+#[specta::specta]
 pub fn set_entrant_slippi_code(
     entrant_id: u32,
     code: Option<String>,
     entrant_manager: State<'_, SharedEntrantManager>,
 ) -> Result<(), String> {
-    let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
-    guard.set_slippi_code(entrant_id, code)
+    let params = json!({ "entrantId": entrant_id, "code": code });
+    audited("setEntrantSlippiCode", params, || {
+        let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
+        guard.set_slippi_code(entrant_id, code)
+    })
 }
 
 /// Assign entrant to setup
 #[tauri::command]
+#[specta::specta]
 pub fn assign_entrant_to_setup(
     entrant_id: u32,
     setup_id: Option<u32>,
     entrant_manager: State<'_, SharedEntrantManager>,
 ) -> Result<(), String> {
-    let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
-    guard.assign_to_setup(entrant_id, setup_id, false)
+    let params = json!({ "entrantId": entrant_id, "setupId": setup_id });
+    audited("assignEntrant", params, || {
+        let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
+        guard.assign_to_setup(entrant_id, setup_id, false)
+    })
 }
 
 /// Unassign entrant from their current setup
 #[tauri::command]
+#[specta::specta]
 pub fn unassign_entrant(
     entrant_id: u32,
     entrant_manager: State<'_, SharedEntrantManager>,
 ) -> Result<(), String> {
-    let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
-    guard.unassign(entrant_id)
+    audited("unassignEntrant", json!({ "entrantId": entrant_id }), || {
+        let mut guard = entrant_manager.lock().map_err(|e| e.to_string())?;
+        guard.unassign(entrant_id)
+    })
 }
 
 /// Toggle auto-assignment
 #[tauri::command]
+#[specta::specta]
 pub fn toggle_auto_assignment(
     enabled: bool,
     entrant_manager: State<'_, SharedEntrantManager>,
@@ -66,6 +81,7 @@ pub fn toggle_auto_assignment(
 
 /// Get setups sorted by highest seed of assigned players
 #[tauri::command]
+#[specta::specta]
 pub fn get_setups_sorted_by_seed(
     entrant_manager: State<'_, SharedEntrantManager>,
     setup_store: State<'_, SharedSetupStore>,
@@ -104,6 +120,7 @@ pub fn get_setups_sorted_by_seed(
 
 /// Get auto-assignment status
 #[tauri::command]
+#[specta::specta]
 pub fn get_auto_assignment_status(
     entrant_manager: State<'_, SharedEntrantManager>,
 ) -> Result<bool, String> {
@@ -113,6 +130,7 @@ pub fn get_auto_assignment_status(
 
 /// Trigger manual auto-assignment run
 #[tauri::command]
+#[specta::specta]
 pub fn run_auto_assignment(
     entrant_manager: State<'_, SharedEntrantManager>,
     setup_store: State<'_, SharedSetupStore>,
@@ -127,6 +145,7 @@ pub fn run_auto_assignment(
 
 /// Sync entrant manager from current Start.gg state
 #[tauri::command]
+#[specta::specta]
 pub fn sync_entrants_from_startgg(
     entrant_manager: State<'_, SharedEntrantManager>,
     live_startgg: State<'_, SharedLiveStartgg>,
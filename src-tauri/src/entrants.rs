@@ -513,6 +513,9 @@ mod tests {
             started_at_ms: 0,
             now_ms: 0,
             reference_tournament_link: None,
+            version: 0,
+            not_modified: false,
+            warming: false,
         }
     }
 
@@ -0,0 +1,551 @@
+use crate::audit_log::{archive_audit_log, audited, restore_audit_log};
+use crate::config::*;
+use crate::config_transaction::{config_transaction, ConfigTransaction};
+use crate::exhibition::{archive_exhibitions, restore_exhibitions};
+use crate::mirror::end_mirror;
+use crate::replay_upload::{archive_replay_manifests, restore_replay_manifests};
+use crate::shutdown::write_event_report;
+use crate::slippi::clear_assignment;
+use crate::stage_strikes::{archive_stage_strikes, restore_stage_strikes};
+use crate::startgg::parse_startgg_link_info;
+use crate::test_mode::apply_broadcast_players;
+use crate::types::*;
+use crate::venue_time::venue_now;
+use crate::{create_setup_slot, remove_setup};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, State};
+
+const DEFAULT_ARCHIVE_PATTERN: &str = "archives/{template}/{date}_{slug}";
+
+/// Settings a recurring event reuses from week to week; only the start.gg
+/// link changes.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventTemplate {
+    pub name: String,
+    /// Overlay asset pack, as in `asset_pack_path`
+    pub asset_pack_path: String,
+    pub ruleset: EventRuleset,
+    pub setup_count: u32,
+    /// Only these players' broadcasts are listed; all of them when empty
+    pub broadcast_players: Vec<BroadcastPlayerSelection>,
+    /// Where an event's logs and replays are moved once the next one
+    /// starts. `{template}`, `{slug}` and `{date}` are filled in; relative
+    /// paths are under the repo root.
+    pub archive_folder_pattern: String,
+}
+
+impl Default for EventTemplate {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            asset_pack_path: String::new(),
+            ruleset: EventRuleset::default(),
+            setup_count: 4,
+            broadcast_players: Vec::new(),
+            archive_folder_pattern: DEFAULT_ARCHIVE_PATTERN.to_string(),
+        }
+    }
+}
+
+/// The event running now, and where it will be archived.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentEvent {
+    pub template: String,
+    pub startgg_link: String,
+    pub archive_dir: String,
+    pub started_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewEventReport {
+    pub event: CurrentEvent,
+    pub config: AppConfig,
+    pub setups: Vec<Setup>,
+    /// Where the previous event's logs and replays went
+    pub archived_to: String,
+    pub archived_replays: usize,
+    pub event_report_path: Option<String>,
+}
+
+// ── Storage ─────────────────────────────────────────────────────────────
+
+fn templates_path() -> PathBuf {
+    repo_root().join("event_templates.json")
+}
+
+fn current_event_path() -> PathBuf {
+    repo_root().join("current_event.json")
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(path: &Path) -> Option<T> {
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&data)
+        .map_err(|err| tracing::warn!("Ignoring unreadable {}: {}", path.display(), err))
+        .ok()
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let body = serde_json::to_vec_pretty(value).map_err(|e| format!("serialize {}: {e}", path.display()))?;
+    write_file_atomic(path, &body).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+pub fn load_templates() -> Vec<EventTemplate> {
+    read_json(&templates_path()).unwrap_or_default()
+}
+
+pub fn current_event_record() -> Option<CurrentEvent> {
+    read_json(&current_event_path())
+}
+
+fn find_template(name: &str) -> Result<EventTemplate, String> {
+    let key = name.trim().to_lowercase();
+    load_templates()
+        .into_iter()
+        .find(|template| template.name.trim().to_lowercase() == key)
+        .ok_or_else(|| format!("Event template \"{}\" not found.", name.trim()))
+}
+
+fn validate_template(template: &EventTemplate) -> Result<(), String> {
+    if template.name.trim().is_empty() {
+        return Err("Template name is required.".to_string());
+    }
+    if template.setup_count == 0 || template.setup_count as usize > MAX_SETUP_COUNT {
+        return Err(format!("Setup count must be 1 to {MAX_SETUP_COUNT}, got {}.", template.setup_count));
+    }
+    if template.ruleset.best_of % 2 != 1 {
+        return Err(format!("Best-of must be odd, got {}.", template.ruleset.best_of));
+    }
+    Ok(())
+}
+
+// ── Archive folders ─────────────────────────────────────────────────────
+
+/// Lowercase letters, digits, `-` and `_`, so a name is safe as one path
+/// segment.
+fn path_segment(raw: &str) -> String {
+    let mapped = raw
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '-' })
+        .collect::<String>();
+    let segment = mapped.split('-').filter(|part| !part.is_empty()).collect::<Vec<_>>().join("-");
+    if segment.is_empty() { "event".to_string() } else { segment }
+}
+
+/// The tournament slug of a start.gg link, e.g. "weekly-42".
+pub(crate) fn link_slug(link: &str) -> String {
+    path_segment(parse_startgg_link_info(link).tournament_slug.as_deref().unwrap_or_default())
+}
+
+fn archive_dir_for(pattern: &str, template: &str, link: &str, date: &str) -> String {
+    let pattern = if pattern.trim().is_empty() { DEFAULT_ARCHIVE_PATTERN } else { pattern.trim() };
+    pattern
+        .replace("{template}", &path_segment(template))
+        .replace("{slug}", &link_slug(link))
+        .replace("{date}", date)
+}
+
+fn collect_replays(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_replays(&path, out);
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("slp") {
+            out.push(path);
+        }
+    }
+}
+
+/// Where `replay` goes under `dest`, keeping its subfolder.
+fn replay_archive_dir(replay: &Path, spectate_dir: &Path, dest: &Path) -> PathBuf {
+    let parent = replay.parent().unwrap_or(spectate_dir);
+    dest.join(parent.strip_prefix(spectate_dir).unwrap_or(Path::new("")))
+}
+
+/// Move `replays`, found under the spectate folder, into `dest`. If one
+/// can't be moved, the ones before it are put back.
+fn archive_replays(replays: &[PathBuf], spectate_dir: &Path, dest: &Path) -> Result<(), String> {
+    for (moved, replay) in replays.iter().enumerate() {
+        if let Err(err) = archive_file(replay, &replay_archive_dir(replay, spectate_dir, dest)) {
+            restore_replays(&replays[..moved], spectate_dir, dest)?;
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Undo `archive_replays`.
+fn restore_replays(replays: &[PathBuf], spectate_dir: &Path, dest: &Path) -> Result<(), String> {
+    for replay in replays {
+        restore_archived_file(replay, &replay_archive_dir(replay, spectate_dir, dest))?;
+    }
+    Ok(())
+}
+
+/// Moves one log into, or back out of, an archive folder.
+type ArchiveFn = fn(&Path) -> Result<(), String>;
+
+/// Move the running event's logs and replays into `archive_dir`, each as a
+/// step `txn` can undo. Returns how many replays moved.
+fn archive_event(
+    txn: &mut ConfigTransaction,
+    archive_dir: &Path,
+    spectate_dir: Option<PathBuf>,
+) -> Result<usize, String> {
+    let moves: [(&str, ArchiveFn, ArchiveFn); 5] = [
+        ("audit log", archive_audit_log, restore_audit_log),
+        ("exhibitions", archive_exhibitions, restore_exhibitions),
+        ("stage strikes", archive_stage_strikes, restore_stage_strikes),
+        ("replay manifests", archive_replay_manifests, restore_replay_manifests),
+        ("start.gg log", archive_startgg_log, restore_startgg_log),
+    ];
+    for (name, archive, restore) in moves {
+        let dir = archive_dir.to_path_buf();
+        txn.step(name, |_| archive(archive_dir), move || restore(&dir))?;
+    }
+    match spectate_dir {
+        Some(spectate_dir) => archive_replays_step(txn, spectate_dir, archive_dir.join("replays")),
+        None => Ok(0),
+    }
+}
+
+fn archive_startgg_log(dir: &Path) -> Result<(), String> {
+    archive_file(&startgg_log_path(), dir)
+}
+
+fn restore_startgg_log(dir: &Path) -> Result<(), String> {
+    restore_archived_file(&startgg_log_path(), dir)
+}
+
+fn archive_replays_step(txn: &mut ConfigTransaction, spectate_dir: PathBuf, dest: PathBuf) -> Result<usize, String> {
+    let mut replays = Vec::new();
+    collect_replays(&spectate_dir, &mut replays);
+    let count = replays.len();
+    let (moved, from, to) = (replays.clone(), spectate_dir.clone(), dest.clone());
+    txn.step(
+        "replays",
+        |_| archive_replays(&replays, &spectate_dir, &dest),
+        move || restore_replays(&moved, &from, &to),
+    )?;
+    Ok(count)
+}
+
+// ── Starting an event ───────────────────────────────────────────────────
+
+/// Take a setup count to exactly `count`, dropping the highest ids first.
+fn resize_setups(count: usize, store: &SharedSetupStore, history: &SharedOverlayHistory) -> Result<(), String> {
+    let ids = store
+        .lock()
+        .map_err(|e| e.to_string())?
+        .setups
+        .iter()
+        .map(|setup| setup.id)
+        .collect::<Vec<_>>();
+    for id in ids.iter().rev().take(ids.len().saturating_sub(count)) {
+        remove_setup(*id, store, history)?;
+    }
+    for _ in ids.len()..count {
+        create_setup_slot(store)?;
+    }
+    Ok(())
+}
+
+/// Archive the running event and set up the next one from `template`: the
+/// finished event's report is written, its audit log, exhibitions, start.gg
+/// log and replays move to its archive folder, every setup is cleared and
+/// resized to the template's count, and set history, report locks and port
+/// sides start over. A simulated bracket is left as it is.
+///
+/// The archive moves, config and current_event.json land as one config
+/// transaction, so a failed move puts everything back where it was.
+pub fn start_new_event_inner(
+    app: &AppHandle,
+    template: &str,
+    startgg_link: &str,
+    store: &SharedSetupStore,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+    history: &SharedOverlayHistory,
+    replay_cache: &SharedOverlayCache,
+) -> Result<NewEventReport, String> {
+    let template = find_template(template)?;
+    validate_template(&template)?;
+    let startgg_link = startgg_link.trim();
+    if startgg_link.is_empty() {
+        return Err("start.gg link is required.".to_string());
+    }
+    let config = cached_config()?;
+    let now = now_ms();
+    let today = venue_now().format("%Y-%m-%d").to_string();
+
+    // The outgoing event; one started before templates existed is archived
+    // with the new template's pattern.
+    let archived_to = match current_event_record() {
+        Some(previous) => previous.archive_dir,
+        None => archive_dir_for(&template.archive_folder_pattern, &template.name, &config.startgg_link, &today),
+    };
+    let archive_dir = resolve_repo_path(&archived_to);
+
+    let sim_state = if config.test_flags().fake_bracket {
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        guard.startgg_sim.as_mut().map(|sim| sim.state(now))
+    } else {
+        None
+    };
+    let event_report_path = match write_event_report(test_state, live_startgg, history, sim_state) {
+        Ok(path) => {
+            // Ratings read reports from the logs folder, so it stays there
+            // and the archive gets a copy.
+            if let Some(name) = path.file_name() {
+                fs::create_dir_all(&archive_dir).map_err(|e| format!("create {}: {e}", archive_dir.display()))?;
+                fs::copy(&path, archive_dir.join(name)).map_err(|e| format!("copy {}: {e}", path.display()))?;
+            }
+            Some(path.display().to_string())
+        }
+        Err(err) => {
+            tracing::warn!("Starting a new event without a report for the last one: {}", err);
+            None
+        }
+    };
+
+    let setup_ids = store
+        .lock()
+        .map_err(|e| e.to_string())?
+        .setups
+        .iter()
+        .map(|setup| setup.id)
+        .collect::<Vec<_>>();
+    for id in setup_ids {
+        clear_assignment(id, Some(true), store)?;
+        end_mirror(id);
+    }
+
+    let event = CurrentEvent {
+        template: template.name.clone(),
+        startgg_link: startgg_link.to_string(),
+        archive_dir: archive_dir_for(&template.archive_folder_pattern, &template.name, startgg_link, &today),
+        started_at_ms: now,
+    };
+    let event_json = serde_json::to_vec_pretty(&event).map_err(|e| format!("serialize current event: {e}"))?;
+    let spectate = config.spectate_folder_path.trim();
+    let spectate_dir = (!spectate.is_empty()).then(|| resolve_repo_path(spectate));
+    let (archived_replays, config) = config_transaction(app, "startNewEvent", |txn| {
+        let archived_replays = archive_event(txn, &archive_dir, spectate_dir)?;
+        let next = txn.config_mut();
+        next.startgg_link = startgg_link.to_string();
+        next.asset_pack_path = template.asset_pack_path.clone();
+        next.ruleset = template.ruleset.clone();
+        txn.save()?;
+        txn.write_file("current event", &current_event_path(), &event_json)?;
+        Ok(archived_replays)
+    })?;
+    sync_live_startgg_from_config(&mut live_startgg.lock().map_err(|e| e.to_string())?, &config);
+
+    resize_setups(template.setup_count as usize, store, history)?;
+    history.lock().map_err(|e| e.to_string())?.clear();
+    *replay_cache.lock().map_err(|e| e.to_string())? = OverlayReplayCache::default();
+    {
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        guard.report_locks.clear();
+        apply_broadcast_players(&mut guard, template.broadcast_players.clone());
+    }
+
+    tracing::info!(
+        "Started {} ({}); archived the last event to {}",
+        event.template,
+        event.startgg_link,
+        archive_dir.display()
+    );
+
+    let setups = store.lock().map_err(|e| e.to_string())?.setups.clone();
+    Ok(NewEventReport {
+        event,
+        config,
+        setups,
+        archived_to: archive_dir.display().to_string(),
+        archived_replays,
+        event_report_path,
+    })
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_event_templates() -> Vec<EventTemplate> {
+    load_templates()
+}
+
+/// Add a template, or replace the one with the same name.
+#[tauri::command]
+#[specta::specta]
+pub fn save_event_template(template: EventTemplate) -> Result<Vec<EventTemplate>, String> {
+    let params = json!({ "name": template.name });
+    audited("saveEventTemplate", params, || {
+        validate_template(&template)?;
+        let mut template = template;
+        template.name = template.name.trim().to_string();
+        let key = template.name.to_lowercase();
+        let mut templates = load_templates();
+        match templates.iter_mut().find(|existing| existing.name.trim().to_lowercase() == key) {
+            Some(existing) => *existing = template,
+            None => templates.push(template),
+        }
+        write_json(&templates_path(), &templates)?;
+        Ok(templates)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn delete_event_template(name: String) -> Result<Vec<EventTemplate>, String> {
+    audited("deleteEventTemplate", json!({ "name": name }), || {
+        let key = name.trim().to_lowercase();
+        let mut templates = load_templates();
+        let before = templates.len();
+        templates.retain(|template| template.name.trim().to_lowercase() != key);
+        if templates.len() == before {
+            return Err(format!("Event template \"{}\" not found.", name.trim()));
+        }
+        write_json(&templates_path(), &templates)?;
+        Ok(templates)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn current_event() -> Option<CurrentEvent> {
+    current_event_record()
+}
+
+/// Archive the running event and start the next one from a template.
+#[tauri::command]
+#[specta::specta]
+pub fn start_new_event(
+    app: AppHandle,
+    template: String,
+    startgg_link: String,
+    store: State<'_, SharedSetupStore>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+    history: State<'_, SharedOverlayHistory>,
+    replay_cache: State<'_, SharedOverlayCache>,
+) -> Result<NewEventReport, String> {
+    let params = json!({ "template": template, "startggLink": startgg_link });
+    audited("startNewEvent", params, || {
+        start_new_event_inner(
+            &app,
+            &template,
+            &startgg_link,
+            &store,
+            &test_state,
+            &live_startgg,
+            &history,
+            &replay_cache,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch dir with a spectate folder holding two replays, one in a
+    /// month subfolder, and a note.
+    fn spectate_folder(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let spectate = dir.join("spectate");
+        fs::create_dir_all(spectate.join("2026-10")).unwrap();
+        fs::write(spectate.join("Game_1.slp"), b"a").unwrap();
+        fs::write(spectate.join("2026-10").join("Game_2.slp"), b"b").unwrap();
+        fs::write(spectate.join("notes.txt"), b"c").unwrap();
+        dir
+    }
+
+    #[test]
+    fn names_become_lowercase_dashed_path_segments() {
+        assert_eq!(path_segment("  Thursday Night Melee! "), "thursday-night-melee");
+    }
+
+    #[test]
+    fn names_with_nothing_usable_become_event() {
+        assert_eq!(path_segment("../.."), "event");
+    }
+
+    #[test]
+    fn the_default_pattern_uses_the_template_date_and_slug() {
+        assert_eq!(
+            archive_dir_for("", "Weekly", "https://www.start.gg/tournament/weekly-42/event/melee-singles", "2026-10-15"),
+            "archives/weekly/2026-10-15_weekly-42"
+        );
+    }
+
+    #[test]
+    fn a_missing_slug_becomes_event() {
+        assert_eq!(archive_dir_for("logs/{slug}", "Weekly", "", "2026-10-15"), "logs/event");
+    }
+
+    #[test]
+    fn archiving_moves_replays_and_keeps_their_subfolders() {
+        let dir = spectate_folder("event-archive-moves");
+        let (spectate, dest) = (dir.join("spectate"), dir.join("archive"));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        assert_eq!(archive_replays_step(&mut txn, spectate.clone(), dest.clone()).unwrap(), 2);
+        assert!(dest.join("Game_1.slp").is_file());
+        assert!(dest.join("2026-10").join("Game_2.slp").is_file());
+        assert!(!spectate.join("Game_1.slp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn archiving_leaves_other_files_behind() {
+        let dir = spectate_folder("event-archive-leaves");
+        let (spectate, dest) = (dir.join("spectate"), dir.join("archive"));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        archive_replays_step(&mut txn, spectate.clone(), dest.clone()).unwrap();
+        assert!(spectate.join("notes.txt").is_file());
+        assert!(!dest.join("notes.txt").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rolling_back_puts_archived_replays_back() {
+        let dir = spectate_folder("event-archive-rollback");
+        let (spectate, dest) = (dir.join("spectate"), dir.join("archive"));
+        let mut txn = ConfigTransaction::new("test", AppConfig::default());
+        archive_replays_step(&mut txn, spectate.clone(), dest.clone()).unwrap();
+        assert!(txn.rollback().is_empty());
+        assert_eq!(fs::read(spectate.join("Game_1.slp")).unwrap(), b"a");
+        assert_eq!(fs::read(spectate.join("2026-10").join("Game_2.slp")).unwrap(), b"b");
+        assert!(!dest.join("Game_1.slp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn a_replay_that_cannot_move_puts_the_earlier_ones_back() {
+        let dir = spectate_folder("event-archive-partial");
+        let (spectate, dest) = (dir.join("spectate"), dir.join("archive"));
+        // A file where the month subfolder should go, so Game_2 can't move.
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("2026-10"), b"").unwrap();
+        let replays = vec![spectate.join("Game_1.slp"), spectate.join("2026-10").join("Game_2.slp")];
+        assert!(archive_replays(&replays, &spectate, &dest).is_err());
+        assert!(spectate.join("Game_1.slp").is_file());
+        assert!(spectate.join("2026-10").join("Game_2.slp").is_file());
+        assert!(!dest.join("Game_1.slp").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
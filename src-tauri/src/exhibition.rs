@@ -0,0 +1,343 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::startgg_sim::{StartggSimSet, StartggSimSlot};
+use crate::types::SharedSetupStore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::State;
+
+/// Exhibition ids start here so they never collide with bracket set ids,
+/// which the overlay history and event report key on as well.
+const EXHIBITION_ID_BASE: u64 = 1 << 48;
+pub const EXHIBITION_PHASE_ID: &str = "exhibition";
+/// How long a finished exhibition stays on its setup's overlay, long enough
+/// for the result graphic and the overlay history to pick it up.
+const COMPLETED_LINGER_MS: u64 = 60_000;
+
+/// Loaded from disk on first use.
+static EXHIBITIONS: Mutex<Option<Vec<ExhibitionSet>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExhibitionPlayer {
+    pub name: String,
+    pub slippi_code: Option<String>,
+}
+
+/// A money match or exhibition: a set that isn't in the bracket but is
+/// shown, scored and archived like one.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ExhibitionSet {
+    pub id: u64,
+    pub label: String,
+    pub best_of: u8,
+    pub players: [ExhibitionPlayer; 2],
+    pub scores: [u8; 2],
+    /// "pending", "inProgress" or "completed", as for bracket sets
+    pub state: String,
+    pub setup_id: Option<u32>,
+    pub winner_slot: Option<usize>,
+    pub created_at_ms: u64,
+    pub started_at_ms: Option<u64>,
+    pub completed_at_ms: Option<u64>,
+}
+
+impl ExhibitionSet {
+    /// The exhibition as a bracket set, so the overlay and set history treat
+    /// it the same way. Slots carry no entrant id; players match by code
+    /// or name.
+    pub fn to_sim_set(&self) -> StartggSimSet {
+        let slot = |idx: usize| StartggSimSlot {
+            entrant_id: None,
+            entrant_name: Some(self.players[idx].name.clone()),
+            slippi_code: self.players[idx].slippi_code.clone(),
+            seed: None,
+            score: Some(self.scores[idx]),
+            result: self.winner_slot.map(|winner| if winner == idx { "win" } else { "loss" }.to_string()),
+            source_type: None,
+            source_set_id: None,
+            source_label: None,
+        };
+        StartggSimSet {
+            id: self.id,
+            startgg_id: None,
+            preview_id: None,
+            phase_id: EXHIBITION_PHASE_ID.to_string(),
+            phase_name: "Exhibition".to_string(),
+            round: 0,
+            round_label: self.label.clone(),
+            best_of: self.best_of,
+            state: self.state.clone(),
+            started_at_ms: self.started_at_ms,
+            completed_at_ms: self.completed_at_ms,
+            updated_at_ms: self.completed_at_ms.or(self.started_at_ms).unwrap_or(self.created_at_ms),
+            winner_id: None,
+            slots: vec![slot(0), slot(1)],
+        }
+    }
+
+    /// Count a game for `slot`; the set completes once someone has won a
+    /// majority of `best_of`.
+    fn record_game(&mut self, slot: usize, now_ms: u64) -> Result<(), String> {
+        if slot > 1 {
+            return Err(format!("Invalid player slot {slot}."));
+        }
+        if self.state == "completed" {
+            return Err(format!("{} is already over.", self.label));
+        }
+        self.scores[slot] = self.scores[slot].saturating_add(1);
+        self.started_at_ms.get_or_insert(now_ms);
+        if self.scores[slot] >= self.best_of / 2 + 1 {
+            self.state = "completed".to_string();
+            self.winner_slot = Some(slot);
+            self.completed_at_ms = Some(now_ms);
+        } else {
+            self.state = "inProgress".to_string();
+        }
+        Ok(())
+    }
+}
+
+fn exhibitions_path() -> PathBuf {
+    repo_root().join("logs").join("exhibitions.json")
+}
+
+fn load_exhibitions() -> Vec<ExhibitionSet> {
+    let path = exhibitions_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|err| {
+        tracing::warn!("Ignoring unreadable {}: {}", path.display(), err);
+        Vec::new()
+    })
+}
+
+fn save_exhibitions(exhibitions: &[ExhibitionSet]) -> Result<(), String> {
+    let path = exhibitions_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let body = serde_json::to_vec_pretty(exhibitions).map_err(|e| format!("serialize exhibitions: {e}"))?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+/// Run `f` on the stored exhibitions and save them if it succeeds.
+fn with_exhibitions<T>(f: impl FnOnce(&mut Vec<ExhibitionSet>) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = EXHIBITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let exhibitions = guard.get_or_insert_with(load_exhibitions);
+    let out = f(exhibitions)?;
+    save_exhibitions(exhibitions)?;
+    Ok(out)
+}
+
+pub fn all_exhibitions() -> Vec<ExhibitionSet> {
+    let mut guard = EXHIBITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(load_exhibitions).clone()
+}
+
+/// Move the stored exhibitions into `dir` and start the next event with
+/// none.
+pub fn archive_exhibitions(dir: &Path) -> Result<(), String> {
+    let mut guard = EXHIBITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    archive_file(&exhibitions_path(), dir)?;
+    *guard = Some(Vec::new());
+    Ok(())
+}
+
+/// Undo `archive_exhibitions`; they're read back from disk on next use.
+pub fn restore_exhibitions(dir: &Path) -> Result<(), String> {
+    let mut guard = EXHIBITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    restore_archived_file(&exhibitions_path(), dir)?;
+    *guard = None;
+    Ok(())
+}
+
+/// The exhibition on a setup's overlay, ahead of any bracket set its
+/// players are in. Finished ones drop off after a minute.
+pub fn exhibition_for_setup(setup_id: u32, now_ms: u64) -> Option<StartggSimSet> {
+    let mut guard = EXHIBITIONS.lock().unwrap_or_else(|e| e.into_inner());
+    guard
+        .get_or_insert_with(load_exhibitions)
+        .iter()
+        .filter(|exhibition| exhibition.setup_id == Some(setup_id))
+        .filter(|exhibition| match exhibition.completed_at_ms {
+            Some(at) => now_ms.saturating_sub(at) < COMPLETED_LINGER_MS,
+            None => true,
+        })
+        .max_by_key(|exhibition| exhibition.created_at_ms)
+        .map(ExhibitionSet::to_sim_set)
+}
+
+fn find_mut(exhibitions: &mut [ExhibitionSet], id: u64) -> Result<&mut ExhibitionSet, String> {
+    exhibitions
+        .iter_mut()
+        .find(|exhibition| exhibition.id == id)
+        .ok_or_else(|| format!("Exhibition {id} not found."))
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_exhibitions() -> Vec<ExhibitionSet> {
+    all_exhibitions()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn create_exhibition(
+    label: String,
+    best_of: u8,
+    players: [ExhibitionPlayer; 2],
+) -> Result<ExhibitionSet, String> {
+    let params = json!({ "label": label, "bestOf": best_of, "players": players });
+    audited("createExhibition", params, || {
+        let label = label.trim().to_string();
+        if label.is_empty() {
+            return Err("Exhibition label is required.".to_string());
+        }
+        if best_of % 2 != 1 {
+            return Err(format!("Best-of must be odd, got {best_of}."));
+        }
+        if players.iter().any(|player| player.name.trim().is_empty()) {
+            return Err("Both players need a name.".to_string());
+        }
+        let players = players.map(|player| ExhibitionPlayer {
+            name: player.name.trim().to_string(),
+            slippi_code: player.slippi_code.as_deref().and_then(normalize_slippi_code),
+        });
+        with_exhibitions(|exhibitions| {
+            let id = exhibitions.iter().map(|exhibition| exhibition.id + 1).max().unwrap_or(EXHIBITION_ID_BASE);
+            let exhibition = ExhibitionSet {
+                id,
+                label,
+                best_of,
+                players,
+                scores: [0, 0],
+                state: "pending".to_string(),
+                setup_id: None,
+                winner_slot: None,
+                created_at_ms: now_ms(),
+                started_at_ms: None,
+                completed_at_ms: None,
+            };
+            exhibitions.push(exhibition.clone());
+            Ok(exhibition)
+        })
+    })
+}
+
+/// Put an exhibition on a setup, or take it off with `setup_id: None`. A
+/// setup shows one exhibition at a time.
+#[tauri::command]
+#[specta::specta]
+pub fn assign_exhibition_to_setup(
+    exhibition_id: u64,
+    setup_id: Option<u32>,
+    store: State<'_, SharedSetupStore>,
+) -> Result<ExhibitionSet, String> {
+    let params = json!({ "exhibitionId": exhibition_id, "setupId": setup_id });
+    audited("assignExhibition", params, || {
+        if let Some(setup_id) = setup_id {
+            let guard = store.lock().map_err(|e| e.to_string())?;
+            if !guard.setups.iter().any(|setup| setup.id == setup_id) {
+                return Err("Setup not found.".to_string());
+            }
+        }
+        with_exhibitions(|exhibitions| {
+            for other in exhibitions.iter_mut() {
+                if other.id != exhibition_id && setup_id.is_some() && other.setup_id == setup_id {
+                    other.setup_id = None;
+                }
+            }
+            let exhibition = find_mut(exhibitions, exhibition_id)?;
+            exhibition.setup_id = setup_id;
+            Ok(exhibition.clone())
+        })
+    })
+}
+
+/// Record a game won by `winner_slot` (0 or 1).
+#[tauri::command]
+#[specta::specta]
+pub fn report_exhibition_game(exhibition_id: u64, winner_slot: usize) -> Result<ExhibitionSet, String> {
+    let params = json!({ "exhibitionId": exhibition_id, "winnerSlot": winner_slot });
+    audited("reportExhibitionGame", params, || {
+        with_exhibitions(|exhibitions| {
+            let exhibition = find_mut(exhibitions, exhibition_id)?;
+            exhibition.record_game(winner_slot, now_ms())?;
+            Ok(exhibition.clone())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money_match(best_of: u8) -> ExhibitionSet {
+        let player = |name: &str| ExhibitionPlayer { name: name.to_string(), slippi_code: None };
+        ExhibitionSet {
+            id: EXHIBITION_ID_BASE,
+            label: "Money match".to_string(),
+            best_of,
+            players: [player("A"), player("B")],
+            scores: [0, 0],
+            state: "pending".to_string(),
+            setup_id: Some(1),
+            winner_slot: None,
+            created_at_ms: 0,
+            started_at_ms: None,
+            completed_at_ms: None,
+        }
+    }
+
+    #[test]
+    fn an_exhibition_completes_once_someone_wins_a_majority() {
+        let mut exhibition = money_match(5);
+        for (slot, at) in [(0, 10), (1, 20), (0, 30)] {
+            exhibition.record_game(slot, at).unwrap();
+        }
+        assert_eq!(exhibition.state, "inProgress");
+        assert_eq!(exhibition.started_at_ms, Some(10));
+        exhibition.record_game(0, 40).unwrap();
+        assert_eq!(exhibition.state, "completed");
+        assert_eq!(exhibition.winner_slot, Some(0));
+        assert_eq!(exhibition.completed_at_ms, Some(40));
+    }
+
+    #[test]
+    fn games_after_the_end_or_for_a_third_player_are_rejected() {
+        let mut exhibition = money_match(1);
+        assert!(exhibition.record_game(2, 10).is_err());
+        assert_eq!(exhibition.scores, [0, 0]);
+        exhibition.record_game(1, 10).unwrap();
+        assert_eq!(exhibition.state, "completed");
+        assert!(exhibition.record_game(0, 20).is_err());
+        assert_eq!(exhibition.scores, [0, 1]);
+    }
+
+    #[test]
+    fn an_exhibition_shows_as_a_set_in_its_own_phase() {
+        let mut exhibition = money_match(3);
+        exhibition.record_game(0, 10).unwrap();
+        exhibition.record_game(0, 40).unwrap();
+
+        let set = exhibition.to_sim_set();
+        assert_eq!(set.phase_id, EXHIBITION_PHASE_ID);
+        assert_eq!(set.round_label, "Money match");
+        assert_eq!(set.slots[0].score, Some(2));
+        assert_eq!(set.slots[0].result.as_deref(), Some("win"));
+        assert_eq!(set.slots[1].result.as_deref(), Some("loss"));
+        assert!(set.slots.iter().all(|slot| slot.entrant_id.is_none()));
+        assert_eq!((set.started_at_ms, set.completed_at_ms, set.updated_at_ms), (Some(10), Some(40), 40));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::config_transaction::config_transaction;
+use crate::types::{AppConfig, FeatureFlag};
+use axum::{
+    extract::Request,
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with a `FeatureFlagStatus` whenever a flag is toggled.
+pub const FEATURE_FLAG_EVENT: &str = "feature-flag-changed";
+
+/// Every flag in display order, with a label and what switching it off does.
+const REGISTRY: &[(FeatureFlag, &str, &str)] = &[
+    (FeatureFlag::Obs, "OBS", "Launching and checking OBS at startup and in the checklist."),
+    (
+        FeatureFlag::OverlayServer,
+        "Overlay server",
+        "Overlay and prompt routes; scenes get 503 while it's off.",
+    ),
+    (FeatureFlag::StartggPolling, "Start.gg polling", "Refreshing the live bracket from start.gg."),
+    (FeatureFlag::AutoReport, "Auto-report", "Reporting set results from replays."),
+    (FeatureFlag::AutoSpectate, "Auto-spectate", "Clicking Watch in the Slippi Launcher for assigned streams."),
+    (FeatureFlag::AutoStop, "Auto-stop", "Stopping setups whose set is decided."),
+    (FeatureFlag::LauncherWatchdog, "Launcher watchdog", "Restarting the Slippi Launcher after a crash."),
+    (
+        FeatureFlag::BroadcastMonitor,
+        "Broadcast monitor",
+        "Viewer counts and ended-broadcast alerts for assigned streams.",
+    ),
+];
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureFlagStatus {
+    pub flag: FeatureFlag,
+    pub label: String,
+    pub description: String,
+    pub enabled: bool,
+}
+
+/// Label and description of `flag`.
+fn describe(flag: FeatureFlag) -> (&'static str, &'static str) {
+    REGISTRY
+        .iter()
+        .find(|(candidate, _, _)| *candidate == flag)
+        .map_or(("", ""), |(_, label, description)| (*label, *description))
+}
+
+fn flag_status(config: &AppConfig, flag: FeatureFlag) -> FeatureFlagStatus {
+    let (label, description) = describe(flag);
+    FeatureFlagStatus {
+        flag,
+        label: label.to_string(),
+        description: description.to_string(),
+        enabled: !config.disabled_features.contains(&flag),
+    }
+}
+
+/// Whether `flag` is on. Reads the cached config, so it's cheap enough for
+/// monitor loops and per-request checks.
+pub fn feature_enabled(flag: FeatureFlag) -> bool {
+    cached_config().map_or(true, |config| !config.disabled_features.contains(&flag))
+}
+
+/// Err naming the subsystem when `flag` is off, for commands that start it.
+pub fn require_feature(flag: FeatureFlag) -> Result<(), String> {
+    if feature_enabled(flag) {
+        return Ok(());
+    }
+    Err(format!("{} is disabled in feature flags.", describe(flag).0))
+}
+
+/// Turn a flag on or off in `config`; false when it was already that way.
+fn toggle(config: &mut AppConfig, flag: FeatureFlag, enabled: bool) -> bool {
+    let disabled = config.disabled_features.contains(&flag);
+    if disabled != enabled {
+        return false;
+    }
+    if enabled {
+        config.disabled_features.retain(|candidate| *candidate != flag);
+    } else {
+        config.disabled_features.push(flag);
+    }
+    true
+}
+
+/// Overlay middleware: 503 for every route while the server is switched off.
+pub async fn overlay_server_gate(request: Request, next: Next) -> Response {
+    if !feature_enabled(FeatureFlag::OverlayServer) {
+        return (StatusCode::SERVICE_UNAVAILABLE, "Overlay server is disabled.").into_response();
+    }
+    next.run(request).await
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn feature_flags() -> Result<Vec<FeatureFlagStatus>, String> {
+    let config = cached_config()?;
+    Ok(REGISTRY.iter().map(|(flag, _, _)| flag_status(&config, *flag)).collect())
+}
+
+/// Switch a subsystem on or off. Takes effect on its next check, without a
+/// restart; emits `feature-flag-changed` when the flag actually flips.
+#[tauri::command]
+#[specta::specta]
+pub fn set_feature_flag(app: AppHandle, flag: FeatureFlag, enabled: bool) -> Result<FeatureFlagStatus, String> {
+    audited("setFeatureFlag", json!({ "flag": flag, "enabled": enabled }), || {
+        let (changed, config) =
+            config_transaction(&app, "setFeatureFlag", |txn| Ok(toggle(txn.config_mut(), flag, enabled)))?;
+        let status = flag_status(&config, flag);
+        if changed {
+            tracing::info!("Feature flag {}: {}", status.label, if enabled { "on" } else { "off" });
+            let _ = app.emit(FEATURE_FLAG_EVENT, &status);
+        }
+        Ok(status)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_start_enabled() {
+        let config = AppConfig::default();
+        for (flag, _, _) in REGISTRY {
+            assert!(flag_status(&config, *flag).enabled);
+        }
+    }
+
+    #[test]
+    fn toggling_reports_only_real_changes() {
+        let mut config = AppConfig::default();
+        assert!(!toggle(&mut config, FeatureFlag::AutoReport, true));
+        assert!(toggle(&mut config, FeatureFlag::AutoReport, false));
+        assert!(!toggle(&mut config, FeatureFlag::AutoReport, false));
+        assert_eq!(config.disabled_features, vec![FeatureFlag::AutoReport]);
+        assert!(!flag_status(&config, FeatureFlag::AutoReport).enabled);
+    }
+
+    #[test]
+    fn re_enabling_removes_the_flag_from_config() {
+        let mut config = AppConfig::default();
+        toggle(&mut config, FeatureFlag::AutoReport, false);
+        assert!(toggle(&mut config, FeatureFlag::AutoReport, true));
+        assert!(config.disabled_features.is_empty());
+    }
+
+    #[test]
+    fn every_flag_is_registered_with_a_label() {
+        let config = AppConfig::default();
+        for (flag, label, _) in REGISTRY {
+            assert_eq!(flag_status(&config, *flag).label, *label);
+            assert!(!label.is_empty());
+        }
+        assert_eq!(REGISTRY.len(), 8);
+    }
+}
@@ -0,0 +1,478 @@
+use crate::config::*;
+use crate::ratings::{build_rating_table, RatingTable, DEFAULT_RATING};
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_sim::{StartggSimSet, StartggSimState};
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use serde::Serialize;
+use specta::Type;
+use std::{collections::HashMap, sync::Mutex, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with a fresh `BracketForecast` whenever the bracket changes.
+pub const FORECAST_EVENT: &str = "bracket-forecast";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(15);
+/// ETAs move with the clock, so a forecast is redone at least this often
+/// even when no result came in.
+const MAX_AGE_MS: u64 = 60_000;
+const MIN: u64 = 60_000;
+/// Set lengths to assume until the bracket has finished sets of its own.
+const DEFAULT_BO3_MS: u64 = 20 * MIN;
+const DEFAULT_BO5_MS: u64 = 30 * MIN;
+/// Rating given to an unrated top seed; each halving of the field costs
+/// `SEED_STEP` points.
+const SEED_STEP: f64 = 100.0;
+/// Players listed per set, most likely first.
+const PLAYERS_SHOWN: usize = 4;
+
+static CACHE: Mutex<Option<CachedForecast>> = Mutex::new(None);
+
+struct CachedForecast {
+    key: BracketKey,
+    forecast: BracketForecast,
+}
+
+/// Changes whenever a set in the bracket does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BracketKey {
+    event_id: String,
+    sets: usize,
+    version: u64,
+    last_update_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ForecastPlayer {
+    pub entrant_id: u32,
+    pub name: String,
+    /// Chance this player is in the set at all.
+    pub reach: f64,
+    /// Chance this player plays the set and wins it.
+    pub win: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetForecast {
+    pub set_id: u64,
+    pub round_label: String,
+    pub phase_name: String,
+    pub state: String,
+    /// When the set is expected to start; the real start once it has.
+    pub eta_ms: u64,
+    pub end_ms: u64,
+    pub players: Vec<ForecastPlayer>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketForecast {
+    pub event_name: String,
+    /// Sets not finished yet, soonest first.
+    pub sets: Vec<SetForecast>,
+    /// "ratings" when any entrant has a rating, otherwise "seeds".
+    pub basis: String,
+    pub generated_at_ms: u64,
+}
+
+/// Probability over entrant ids.
+type Dist = Vec<(u32, f64)>;
+
+fn add(dist: &mut Dist, id: u32, p: f64) {
+    match dist.iter_mut().find(|(entrant, _)| *entrant == id) {
+        Some((_, total)) => *total += p,
+        None => dist.push((id, p)),
+    }
+}
+
+/// Elo expectation that `a` beats `b`.
+fn win_chance(a: f64, b: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((b - a) / 400.0))
+}
+
+fn is_done(set: &StartggSimSet) -> bool {
+    set.state == "completed" || set.state == "skipped"
+}
+
+struct Forecaster<'a> {
+    state: &'a StartggSimState,
+    strength: HashMap<u32, f64>,
+    durations: HashMap<u8, u64>,
+    /// (winner, loser) per set id.
+    outcomes: HashMap<u64, (Dist, Dist)>,
+    /// (start, end) per set id.
+    times: HashMap<u64, (u64, u64)>,
+    now: u64,
+}
+
+impl<'a> Forecaster<'a> {
+    fn new(state: &'a StartggSimState, ratings: &RatingTable, now: u64) -> Self {
+        let strength = state
+            .entrants
+            .iter()
+            .map(|entrant| {
+                let seeded = DEFAULT_RATING - SEED_STEP * f64::from(entrant.seed.max(1)).log2();
+                let rated = ratings.rating_for(&entrant.name, Some(&entrant.slippi_code));
+                (entrant.id, rated.unwrap_or(seeded))
+            })
+            .collect();
+        Forecaster {
+            state,
+            strength,
+            durations: set_durations(&state.sets),
+            outcomes: HashMap::new(),
+            times: HashMap::new(),
+            now,
+        }
+    }
+
+    fn set(&self, id: u64) -> Option<&'a StartggSimSet> {
+        self.state.sets.iter().find(|set| set.id == id)
+    }
+
+    fn rating(&self, id: u32) -> f64 {
+        self.strength.get(&id).copied().unwrap_or(DEFAULT_RATING)
+    }
+
+    fn duration(&self, best_of: u8) -> u64 {
+        self.durations.get(&best_of).copied().unwrap_or(match best_of {
+            0..=3 => DEFAULT_BO3_MS,
+            _ => DEFAULT_BO5_MS,
+        })
+    }
+
+    /// Who ends up in each slot of `set`.
+    fn slot_dists(&mut self, set: &StartggSimSet, depth: usize) -> Vec<Dist> {
+        set.slots
+            .iter()
+            .map(|slot| {
+                if let Some(id) = slot.entrant_id {
+                    return vec![(id, 1.0)];
+                }
+                let Some(source) = slot.source_set_id else {
+                    return Vec::new();
+                };
+                let (winner, loser) = self.outcome(source, depth + 1);
+                match slot.source_type.as_deref() {
+                    Some("winner") => winner,
+                    Some("loser") => loser,
+                    _ => Vec::new(),
+                }
+            })
+            .collect()
+    }
+
+    fn outcome(&mut self, set_id: u64, depth: usize) -> (Dist, Dist) {
+        if let Some(done) = self.outcomes.get(&set_id) {
+            return done.clone();
+        }
+        // Guard against malformed brackets that loop back on themselves.
+        let Some(set) = self.set(set_id).filter(|_| depth <= self.state.sets.len()) else {
+            return (Vec::new(), Vec::new());
+        };
+        let slots = self.slot_dists(set, depth);
+        let mut winner = Dist::new();
+        let mut loser = Dist::new();
+        if let Some(winner_id) = set.winner_id.filter(|_| is_done(set)) {
+            winner.push((winner_id, 1.0));
+            for (id, p) in slots.iter().flatten().filter(|(id, _)| *id != winner_id) {
+                add(&mut loser, *id, *p);
+            }
+        } else if let [a, b] = slots.as_slice() {
+            match (a.is_empty(), b.is_empty()) {
+                // A bye: the other side goes through.
+                (false, true) => winner = a.clone(),
+                (true, false) => winner = b.clone(),
+                _ => {
+                    for (ia, pa) in a {
+                        for (ib, pb) in b {
+                            let p = pa * pb;
+                            let chance = win_chance(self.rating(*ia), self.rating(*ib));
+                            add(&mut winner, *ia, p * chance);
+                            add(&mut winner, *ib, p * (1.0 - chance));
+                            add(&mut loser, *ib, p * chance);
+                            add(&mut loser, *ia, p * (1.0 - chance));
+                        }
+                    }
+                }
+            }
+        }
+        self.outcomes.insert(set_id, (winner.clone(), loser.clone()));
+        (winner, loser)
+    }
+
+    /// Expected start and end of `set_id`: it can start once every set
+    /// feeding it is over.
+    fn timing(&mut self, set_id: u64, depth: usize) -> (u64, u64) {
+        if let Some(times) = self.times.get(&set_id) {
+            return *times;
+        }
+        let Some(set) = self.set(set_id).filter(|_| depth <= self.state.sets.len()) else {
+            return (self.now, self.now);
+        };
+        let duration = self.duration(set.best_of);
+        let times = if let (true, Some(end)) = (is_done(set), set.completed_at_ms) {
+            (set.started_at_ms.unwrap_or(end), end)
+        } else if let Some(start) = set.started_at_ms {
+            (start, (start + duration).max(self.now))
+        } else {
+            let ready = set
+                .slots
+                .iter()
+                .filter(|slot| slot.entrant_id.is_none())
+                .filter_map(|slot| slot.source_set_id)
+                .map(|source| self.timing(source, depth + 1).1)
+                .max()
+                .unwrap_or(self.now)
+                .max(self.now);
+            (ready, ready + duration)
+        };
+        self.times.insert(set_id, times);
+        times
+    }
+
+    fn forecast_set(&mut self, set: &StartggSimSet) -> SetForecast {
+        let (eta_ms, end_ms) = self.timing(set.id, 0);
+        let slots = self.slot_dists(set, 0);
+        let (winner, _) = self.outcome(set.id, 0);
+        let mut players = Dist::new();
+        for (id, p) in slots.iter().flatten() {
+            add(&mut players, *id, *p);
+        }
+        let mut players = players
+            .into_iter()
+            .map(|(id, reach)| ForecastPlayer {
+                entrant_id: id,
+                name: self
+                    .state
+                    .entrants
+                    .iter()
+                    .find(|entrant| entrant.id == id)
+                    .map(|entrant| entrant.name.clone())
+                    .unwrap_or_else(|| format!("Entrant {id}")),
+                reach,
+                win: winner.iter().find(|(w, _)| *w == id).map(|(_, p)| *p).unwrap_or(0.0),
+            })
+            .collect::<Vec<_>>();
+        players.sort_by(|a, b| b.reach.total_cmp(&a.reach).then(b.win.total_cmp(&a.win)));
+        players.truncate(PLAYERS_SHOWN);
+        SetForecast {
+            set_id: set.id,
+            round_label: set.round_label.clone(),
+            phase_name: set.phase_name.clone(),
+            state: set.state.clone(),
+            eta_ms,
+            end_ms,
+            players,
+        }
+    }
+}
+
+/// Average length of finished sets in this bracket, by best-of.
+fn set_durations(sets: &[StartggSimSet]) -> HashMap<u8, u64> {
+    let mut lengths: HashMap<u8, Vec<u64>> = HashMap::new();
+    for set in sets {
+        if let (Some(start), Some(end)) = (set.started_at_ms, set.completed_at_ms) {
+            if end > start {
+                lengths.entry(set.best_of).or_default().push(end - start);
+            }
+        }
+    }
+    lengths
+        .into_iter()
+        .map(|(best_of, lengths)| (best_of, lengths.iter().sum::<u64>() / lengths.len() as u64))
+        .collect()
+}
+
+pub fn build_forecast(state: &StartggSimState, ratings: &RatingTable, now: u64) -> BracketForecast {
+    let rated = state
+        .entrants
+        .iter()
+        .any(|entrant| ratings.rating_for(&entrant.name, Some(&entrant.slippi_code)).is_some());
+    let mut forecaster = Forecaster::new(state, ratings, now);
+    let mut sets = state
+        .sets
+        .iter()
+        .filter(|set| !is_done(set))
+        .filter(|set| !set.slots.iter().any(|slot| slot.source_type.as_deref() == Some("empty")))
+        .map(|set| forecaster.forecast_set(set))
+        .collect::<Vec<_>>();
+    sets.sort_by_key(|set| (set.eta_ms, set.set_id));
+    BracketForecast {
+        event_name: state.event.name.clone(),
+        sets,
+        basis: if rated { "ratings" } else { "seeds" }.to_string(),
+        generated_at_ms: now,
+    }
+}
+
+fn bracket_key(state: &StartggSimState) -> BracketKey {
+    BracketKey {
+        event_id: state.event.id.clone(),
+        sets: state.sets.len(),
+        version: state.version,
+        last_update_ms: state.sets.iter().map(|set| set.updated_at_ms).max().unwrap_or(0),
+    }
+}
+
+/// The forecast for the current bracket, redone when the bracket changed
+/// or the cached one is stale. The bool is true when it was redone.
+pub fn current_forecast(
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<(BracketForecast, bool), String> {
+    let state = current_bracket_state(test_state, live_startgg).ok_or_else(|| "No bracket is loaded.".to_string())?;
+    let key = bracket_key(&state);
+    let now = now_ms();
+    {
+        let cache = CACHE.lock().map_err(|e| e.to_string())?;
+        if let Some(cached) = cache.as_ref() {
+            if cached.key == key && now.saturating_sub(cached.forecast.generated_at_ms) < MAX_AGE_MS {
+                return Ok((cached.forecast.clone(), false));
+            }
+        }
+    }
+    let forecast = build_forecast(&state, &build_rating_table(Some(&state)), now);
+    let previous = CACHE.lock().map_err(|e| e.to_string())?.replace(CachedForecast {
+        key: key.clone(),
+        forecast: forecast.clone(),
+    });
+    let changed = previous.is_none_or(|cached| cached.key != key);
+    Ok((forecast, changed))
+}
+
+/// Redo the forecast when the bracket changes and emit `bracket-forecast`.
+pub fn spawn_forecast_monitor(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        sleep(MONITOR_INTERVAL);
+        let test_state = app.state::<SharedTestState>().inner().clone();
+        let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+        if let Ok((forecast, true)) = current_forecast(&test_state, &live_startgg) {
+            let _ = app.emit(FORECAST_EVENT, &forecast);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Expected start times and win chances for every set still to play.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn forecast(
+    test_state: tauri::State<'_, SharedTestState>,
+    live_startgg: tauri::State<'_, SharedLiveStartgg>,
+) -> Result<BracketForecast, String> {
+    current_forecast(&test_state, &live_startgg).map(|(forecast, _)| forecast)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::{StartggSimEntrant, StartggSimEventConfig, StartggSimSlot};
+
+    fn slot(entrant_id: Option<u32>, source: Option<(&str, u64)>) -> StartggSimSlot {
+        StartggSimSlot {
+            entrant_id,
+            source_type: source.map(|(kind, _)| kind.to_string()),
+            source_set_id: source.map(|(_, id)| id),
+            ..Default::default()
+        }
+    }
+
+    fn set(id: u64, state: &str, slots: Vec<StartggSimSlot>) -> StartggSimSet {
+        StartggSimSet {
+            id,
+            phase_id: "p1".to_string(),
+            phase_name: "Bracket".to_string(),
+            round: 1,
+            round_label: format!("Set {id}"),
+            best_of: 3,
+            state: state.to_string(),
+            slots,
+            ..Default::default()
+        }
+    }
+
+    fn event(sets: Vec<StartggSimSet>) -> StartggSimState {
+        let entrants = (1..=4)
+            .map(|id| StartggSimEntrant {
+                id,
+                name: format!("Player {id}"),
+                seed: id,
+                slippi_code: String::new(),
+            })
+            .collect();
+        StartggSimState {
+            event: StartggSimEventConfig {
+                id: "e1".to_string(),
+                name: "Test".to_string(),
+                slug: "test".to_string(),
+            },
+            phases: Vec::new(),
+            entrants,
+            sets,
+            started_at_ms: 0,
+            now_ms: 12 * MIN,
+            reference_tournament_link: None,
+            version: 1,
+            not_modified: false,
+            warming: false,
+        }
+    }
+
+    /// Set 1 (1 beat 4 in 10 minutes) is over, set 2 (2 vs 3) started at 5
+    /// minutes and set 3 is between their winners.
+    fn forecast_at_12_minutes() -> BracketForecast {
+        let mut done = set(1, "completed", vec![slot(Some(1), None), slot(Some(4), None)]);
+        done.started_at_ms = Some(0);
+        done.completed_at_ms = Some(10 * MIN);
+        done.winner_id = Some(1);
+        let mut running = set(2, "inProgress", vec![slot(Some(2), None), slot(Some(3), None)]);
+        running.started_at_ms = Some(5 * MIN);
+        let final_set = set(3, "pending", vec![slot(None, Some(("winner", 1))), slot(None, Some(("winner", 2)))]);
+        build_forecast(&event(vec![done, running, final_set]), &RatingTable::default(), 12 * MIN)
+    }
+
+    #[test]
+    fn finished_sets_are_left_out() {
+        let forecast = forecast_at_12_minutes();
+        assert_eq!(forecast.basis, "seeds");
+        assert_eq!(forecast.sets.iter().map(|set| set.set_id).collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn a_running_set_lasts_as_long_as_the_brackets_finished_ones() {
+        // Bo3s here take 10 minutes, so set 2 ends at 15.
+        let running = &forecast_at_12_minutes().sets[0];
+        assert_eq!((running.eta_ms, running.end_ms), (5 * MIN, 15 * MIN));
+    }
+
+    #[test]
+    fn a_set_starts_once_the_sets_feeding_it_end() {
+        let final_set = &forecast_at_12_minutes().sets[1];
+        assert_eq!((final_set.eta_ms, final_set.end_ms), (15 * MIN, 25 * MIN));
+        assert_eq!(final_set.players[0].entrant_id, 1);
+        assert_eq!(final_set.players[0].reach, 1.0);
+    }
+
+    #[test]
+    fn better_seeds_are_favoured_and_win_chances_sum_to_one() {
+        let forecast = forecast_at_12_minutes();
+        assert!(forecast.sets[0].players[0].win > 0.5);
+        for set in &forecast.sets {
+            let total: f64 = set.players.iter().map(|player| player.win).sum();
+            assert!((total - 1.0).abs() < 1e-9, "set {}", set.set_id);
+        }
+    }
+
+    #[test]
+    fn a_bye_sends_the_other_player_through() {
+        let bye = set(1, "pending", vec![slot(Some(4), None), slot(None, None)]);
+        let next = set(2, "pending", vec![slot(None, Some(("winner", 1))), slot(Some(1), None)]);
+        let forecast = build_forecast(&event(vec![bye, next]), &RatingTable::default(), 0);
+        let next = forecast.sets.iter().find(|set| set.set_id == 2).unwrap();
+        assert!(next.players.iter().all(|player| player.reach == 1.0));
+        assert_eq!(next.players.len(), 2);
+    }
+}
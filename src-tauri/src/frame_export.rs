@@ -0,0 +1,224 @@
+use crate::config::*;
+use crate::jobs::Job;
+use crate::replay::{map_internal_character, sort_replay_paths_by_start_time};
+use crate::replay_frames::record_last_frame;
+use crate::startgg::read_bracket_set_replay_paths;
+use crate::startgg_report::set_games;
+use crate::types::SharedTestState;
+use peppi::{game::Port, io::slippi};
+use serde::Serialize;
+use specta::Type;
+use std::{
+    fs,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use tauri::{AppHandle, State};
+
+/// Folder next to a set's replays that exports are written into.
+const ANALYSIS_DIR: &str = "analysis";
+
+const CSV_HEADER: &str = "frame,port,character,action_state,x,y,direction,percent,shield,stocks";
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetAnalysisExport {
+    pub set_id: u64,
+    pub format: String,
+    pub output_dir: String,
+    pub files: Vec<String>,
+    /// Replays that failed to parse, with the reason
+    pub skipped: Vec<String>,
+}
+
+fn port_number(port: Port) -> u8 {
+    match port {
+        Port::P1 => 1,
+        Port::P2 => 2,
+        Port::P3 => 3,
+        Port::P4 => 4,
+    }
+}
+
+/// Write one row per frame per port (leader only; Nana is left out) with
+/// positions, action state and percent.
+fn write_game_csv(replay: &Path, out_path: &Path) -> Result<usize, String> {
+    let file = fs::File::open(replay).map_err(|e| format!("open replay {}: {e}", replay.display()))?;
+    let game = slippi::de::read(file, None).map_err(|e| format!("parse replay {}: {e}", replay.display()))?;
+    let frames = &game.frames;
+    let frame_ids = frames.id.values();
+    if let Some(last) = frame_ids.iter().max() {
+        record_last_frame(replay, *last);
+    }
+
+    let out = fs::File::create(out_path).map_err(|e| format!("create {}: {e}", out_path.display()))?;
+    let mut writer = BufWriter::new(out);
+    let write_err = |e: std::io::Error| format!("write {}: {e}", out_path.display());
+    writeln!(writer, "{CSV_HEADER}").map_err(write_err)?;
+
+    let mut rows = 0;
+    for (idx, frame) in frame_ids.iter().enumerate() {
+        for port in &frames.ports {
+            let post = &port.leader.post;
+            let character = map_internal_character(post.character.values()[idx]).unwrap_or("Unknown");
+            writeln!(
+                writer,
+                "{},{},{},{},{:.2},{:.2},{},{:.2},{:.2},{}",
+                frame,
+                port_number(port.port),
+                character,
+                post.state.values()[idx],
+                post.position.x.values()[idx],
+                post.position.y.values()[idx],
+                post.direction.values()[idx],
+                post.percent.values()[idx],
+                post.shield.values()[idx],
+                post.stocks.values()[idx],
+            )
+            .map_err(write_err)?;
+            rows += 1;
+        }
+    }
+    writer.flush().map_err(write_err)?;
+    Ok(rows)
+}
+
+/// Parse each replay of a set and write per-frame stats to
+/// `<replay dir>/analysis/set_<id>/`.
+pub fn export_set_analysis_files(
+    set_id: u64,
+    format: &str,
+    replays: Vec<PathBuf>,
+    job: Option<&Job>,
+) -> Result<SetAnalysisExport, String> {
+    let format = format.trim().to_ascii_lowercase();
+    match format.as_str() {
+        "csv" => {}
+        "parquet" => return Err("Parquet export is not available in this build; use csv.".to_string()),
+        other => return Err(format!("Unknown export format '{other}'. Expected csv.")),
+    }
+    let replays = sort_replay_paths_by_start_time(replays);
+    let base = replays
+        .first()
+        .and_then(|path| path.parent())
+        .ok_or_else(|| format!("No replays found for set {set_id}."))?;
+    let output_dir = base.join(ANALYSIS_DIR).join(format!("set_{set_id}"));
+    fs::create_dir_all(&output_dir).map_err(|e| format!("create {}: {e}", output_dir.display()))?;
+
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for (game_idx, replay) in replays.iter().enumerate() {
+        if let Some(job) = job {
+            job.step(game_idx, replays.len(), format!("Exporting game {}", game_idx + 1))?;
+        }
+        let stem = replay.file_stem().and_then(|s| s.to_str()).unwrap_or("replay");
+        let out_path = output_dir.join(format!("game{}_{stem}.{format}", game_idx + 1));
+        match write_game_csv(replay, &out_path) {
+            Ok(rows) => {
+                tracing::info!("Exported {rows} frame rows to {}", out_path.display());
+                files.push(out_path.display().to_string());
+            }
+            Err(err) => {
+                let _ = fs::remove_file(&out_path);
+                skipped.push(err);
+            }
+        }
+    }
+    if files.is_empty() {
+        return Err(format!("No replays for set {set_id} could be exported: {}", skipped.join("; ")));
+    }
+    Ok(SetAnalysisExport {
+        set_id,
+        format,
+        output_dir: output_dir.display().to_string(),
+        files,
+        skipped,
+    })
+}
+
+/// A set's replays: the games detected for it this event, else the ones the
+/// set manifest (the bracket config's replay map) recorded when it was
+/// archived.
+fn set_replays(set_id: u64, config_path: &Path) -> Result<Vec<PathBuf>, String> {
+    if let Some((_, replays)) = set_games().remove(&set_id) {
+        return Ok(replays);
+    }
+    read_bracket_set_replay_paths(&config_path.to_string_lossy(), set_id)
+}
+
+/// Export per-frame stats for every game of a set, for coaches and
+/// analysts, into `analysis/set_<id>/` next to its replays. `format` is
+/// "csv"; parquet isn't supported. Parsing full replays is slow, so this
+/// runs off the main thread.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn export_set_analysis(
+    set_id: u64,
+    format: Option<String>,
+    test_state: State<'_, SharedTestState>,
+    app: AppHandle,
+) -> Result<SetAnalysisExport, String> {
+    let config_path = {
+        let guard = test_state.lock().map_err(|e| e.to_string())?;
+        guard
+            .startgg_config_path
+            .clone()
+            .unwrap_or_else(startgg_sim_config_path)
+    };
+    let replays = set_replays(set_id, &config_path)?;
+    let job = Job::start(&app, "setAnalysisExport", format!("Export set {set_id} analysis"), true);
+    let result = export_set_analysis_files(set_id, format.as_deref().unwrap_or("csv"), replays, Some(&job));
+    job.finish(result, |export| format!("Wrote {} file(s) to {}.", export.files.len(), export.output_dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_replays::fixture_replay;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("frame-export-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_game_exports_one_row_per_frame_per_port() {
+        let dir = scratch_dir("rows");
+        let replay = dir.join("Game_20261016T120000.slp");
+        fs::write(&replay, fixture_replay(5)).unwrap();
+
+        let export = export_set_analysis_files(7, "CSV", vec![replay], None).unwrap();
+        assert_eq!(export.format, "csv");
+        assert!(export.skipped.is_empty());
+        assert_eq!(PathBuf::from(&export.output_dir), dir.join(ANALYSIS_DIR).join("set_7"));
+        assert_eq!(export.files.len(), 1);
+
+        let csv = fs::read_to_string(&export.files[0]).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(lines.len(), 1 + 5 * 2);
+        assert_eq!(lines[1], "-123,1,Fox,14,0.00,0.00,1,12.50,60.00,4");
+        assert!(lines[2].starts_with("-123,2,Fox,14,10.00,"));
+    }
+
+    #[test]
+    fn unreadable_replays_are_skipped_and_parquet_is_refused() {
+        let dir = scratch_dir("skip");
+        let good = dir.join("good.slp");
+        let bad = dir.join("bad.slp");
+        fs::write(&good, fixture_replay(1)).unwrap();
+        fs::write(&bad, b"not a replay").unwrap();
+
+        let err = export_set_analysis_files(7, "parquet", vec![good.clone()], None).unwrap_err();
+        assert!(err.contains("Parquet"), "{err}");
+        assert!(export_set_analysis_files(7, "xlsx", vec![good.clone()], None).is_err());
+        assert!(export_set_analysis_files(7, "csv", Vec::new(), None).is_err());
+
+        let export = export_set_analysis_files(7, "csv", vec![good, bad.clone()], None).unwrap();
+        assert_eq!(export.files.len(), 1);
+        assert_eq!(export.skipped.len(), 1);
+        assert!(export_set_analysis_files(7, "csv", vec![bad], None).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::config::*;
+use crate::types::FramePacingStatus;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter};
+
+/// Melee runs at ~59.94 fps; below this, playback audio starts drifting
+/// from video.
+pub const MIN_PLAYBACK_FPS: f32 = 58.0;
+
+static PACING: Mutex<Vec<FramePacingStatus>> = Mutex::new(Vec::new());
+
+/// Fold one fps sample into a setup's status. Returns `Some(true)` when an
+/// alert starts and `Some(false)` when it clears.
+fn apply_sample(status: &mut FramePacingStatus, fps: f32, now_ms: u64, alert_after_ms: u64) -> Option<bool> {
+    status.fps = fps;
+    status.updated_at_ms = now_ms;
+    if fps >= MIN_PLAYBACK_FPS {
+        status.below_since_ms = None;
+        if status.alerting {
+            status.alerting = false;
+            return Some(false);
+        }
+        return None;
+    }
+    let since = *status.below_since_ms.get_or_insert(now_ms);
+    if !status.alerting && now_ms.saturating_sub(since) >= alert_after_ms {
+        status.alerting = true;
+        return Some(true);
+    }
+    None
+}
+
+/// Record a frame rate sample for a setup's playback Dolphin and emit
+/// `playback-frame-pacing` when it starts or stops lagging.
+pub fn record_playback_fps(app: &AppHandle, setup_id: u32, fps: f32) -> FramePacingStatus {
+    let alert_after_ms = cached_config().unwrap_or_default().playback_fps_alert_secs * 1000;
+    let now = now_ms();
+    let mut guard = PACING.lock().unwrap_or_else(|e| e.into_inner());
+    let idx = match guard.iter().position(|status| status.setup_id == setup_id) {
+        Some(idx) => idx,
+        None => {
+            guard.push(FramePacingStatus {
+                setup_id,
+                fps,
+                below_since_ms: None,
+                alerting: false,
+                updated_at_ms: now,
+            });
+            guard.len() - 1
+        }
+    };
+    let status = &mut guard[idx];
+    match apply_sample(status, fps, now, alert_after_ms) {
+        Some(true) => {
+            tracing::warn!(
+                "Setup {setup_id} playback below {MIN_PLAYBACK_FPS} fps for {}s (now {fps:.1})",
+                alert_after_ms / 1000
+            );
+            let _ = app.emit("playback-frame-pacing", &*status);
+        }
+        Some(false) => {
+            tracing::info!("Setup {setup_id} playback frame pacing recovered ({fps:.1} fps)");
+            let _ = app.emit("playback-frame-pacing", &*status);
+        }
+        None => {}
+    }
+    status.clone()
+}
+
+pub fn clear_frame_pacing(setup_id: u32) {
+    PACING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|status| status.setup_id != setup_id);
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Frame rate sample from whatever watches the playback Dolphin (the OBS
+/// stats script or the Dolphin wrapper).
+#[tauri::command]
+#[specta::specta]
+pub fn report_playback_fps(app: AppHandle, setup_id: u32, fps: f32) -> Result<FramePacingStatus, String> {
+    if !fps.is_finite() || fps < 0.0 {
+        return Err(format!("Invalid fps sample {fps}."));
+    }
+    Ok(record_playback_fps(&app, setup_id, fps))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_frame_pacing() -> Vec<FramePacingStatus> {
+    PACING.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status() -> FramePacingStatus {
+        FramePacingStatus {
+            setup_id: 1,
+            fps: 60.0,
+            below_since_ms: None,
+            alerting: false,
+            updated_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn alerts_only_after_a_sustained_dip() {
+        let mut status = status();
+        assert_eq!(apply_sample(&mut status, 55.0, 1_000, 5_000), None);
+        assert_eq!(apply_sample(&mut status, 59.9, 2_000, 5_000), None);
+        assert_eq!(status.below_since_ms, None);
+        assert_eq!(apply_sample(&mut status, 50.0, 3_000, 5_000), None);
+        assert_eq!(apply_sample(&mut status, 52.0, 7_999, 5_000), None);
+        assert_eq!(apply_sample(&mut status, 52.0, 8_000, 5_000), Some(true));
+        assert_eq!(apply_sample(&mut status, 52.0, 9_000, 5_000), None);
+    }
+
+    #[test]
+    fn recovering_clears_the_alert_once() {
+        let mut status = status();
+        apply_sample(&mut status, 50.0, 0, 0);
+        assert!(status.alerting);
+        assert_eq!(apply_sample(&mut status, 60.0, 1_000, 0), Some(false));
+        assert_eq!(apply_sample(&mut status, 60.0, 2_000, 0), None);
+        assert_eq!((status.alerting, status.below_since_ms), (false, None));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::exhibition::exhibition_for_setup;
+use crate::idle_mode::is_idle;
+use crate::replay::{find_set_for_player, latest_replay_for_code};
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_sim::{StartggSimSet, StartggSimState};
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{collections::HashSet, path::Path, sync::Mutex, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_CLASSIFICATIONS: usize = 500;
+
+/// Classified games, oldest first, with the operator's overrides applied.
+static CLASSIFICATIONS: Mutex<Vec<GameClassification>> = Mutex::new(Vec::new());
+
+/// Why a game looks like a friendly rather than a game of its set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum FriendlyReason {
+    /// No set on the setup, or its set hasn't been called or started
+    SetNotStarted,
+    /// The set is already over, or someone already has enough wins
+    SetAlreadyDecided,
+    /// The replay's connect codes aren't the set's players
+    PlayersMismatch,
+}
+
+/// Payload of `game-detected`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameClassification {
+    pub setup_id: u32,
+    pub replay_path: String,
+    pub set_id: Option<u64>,
+    pub friendly: bool,
+    pub reasons: Vec<FriendlyReason>,
+    /// Set by the operator; heuristics no longer apply
+    pub overridden: bool,
+    pub detected_at_ms: u64,
+}
+
+pub(crate) fn wins_needed(best_of: u8) -> u8 {
+    best_of / 2 + 1
+}
+
+/// Reported complete, or someone already has enough wins to take it.
+pub fn set_decided(set: &StartggSimSet) -> bool {
+    set.state == "completed"
+        || set
+            .slots
+            .iter()
+            .any(|slot| slot.score.unwrap_or(0) >= wins_needed(set.best_of))
+}
+
+/// Heuristics for a game that just started on a setup showing `set`.
+/// `replay_codes` are the connect codes found in the replay.
+pub fn friendly_reasons(set: Option<&StartggSimSet>, replay_codes: &[String]) -> Vec<FriendlyReason> {
+    let Some(set) = set else {
+        return vec![FriendlyReason::SetNotStarted];
+    };
+    let mut reasons = Vec::new();
+    if set.state == "pending" && set.started_at_ms.is_none() {
+        reasons.push(FriendlyReason::SetNotStarted);
+    }
+    if set_decided(set) {
+        reasons.push(FriendlyReason::SetAlreadyDecided);
+    }
+    let replay_keys = replay_codes.iter().map(|code| normalize_broadcast_key(code)).collect::<Vec<_>>();
+    let slot_keys = set
+        .slots
+        .iter()
+        .filter_map(|slot| slot.slippi_code.as_deref())
+        .map(normalize_broadcast_key)
+        .filter(|key| !key.is_empty())
+        .collect::<Vec<_>>();
+    // Only judge players when the replay and the set both name codes.
+    if !replay_keys.is_empty() && !slot_keys.is_empty() && !slot_keys.iter().all(|key| replay_keys.contains(key)) {
+        reasons.push(FriendlyReason::PlayersMismatch);
+    }
+    reasons
+}
+
+/// Whether a replay was classified (or ruled) a friendly. Unclassified
+/// replays, e.g. ones archived by hand, count as set games.
+pub fn is_friendly(replay_path: &Path) -> bool {
+    let key = replay_path.to_string_lossy();
+    CLASSIFICATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .any(|class| class.replay_path == key && class.friendly)
+}
+
+/// The set a setup's overlay shows, found the same way the overlay does.
+pub fn assigned_set(setup_id: u32, stream: &SlippiStream, bracket: Option<&StartggSimState>) -> Option<StartggSimSet> {
+    if let Some(exhibition) = exhibition_for_setup(setup_id, now_ms()) {
+        return Some(exhibition);
+    }
+    let player = BroadcastPlayerSelection {
+        id: stream.startgg_entrant_id.unwrap_or(0),
+        name: stream.p1_tag.clone().unwrap_or_default(),
+        slippi_code: stream.p1_code.clone().unwrap_or_default(),
+    };
+    bracket
+        .and_then(|state| find_set_for_player(&state.sets, &player, None).cloned())
+        .or_else(|| stream.startgg_set.clone())
+}
+
+/// Classify the newest replay of each assigned setup, once per replay.
+/// Replays in `seen` were already handled.
+fn classify_new_games(
+    store: &SharedSetupStore,
+    replay_cache: &SharedOverlayCache,
+    bracket: Option<&StartggSimState>,
+    seen: &mut HashSet<String>,
+) -> Vec<GameClassification> {
+    let setups = store.lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+    let cache = replay_cache.lock().unwrap_or_else(|e| e.into_inner());
+    let mut detected = Vec::new();
+    for setup in setups {
+        let Some(stream) = setup.assigned_stream.as_ref() else {
+            continue;
+        };
+        let Some(path) = stream.p1_code.as_deref().and_then(|code| latest_replay_for_code(&cache, code)) else {
+            continue;
+        };
+        let key = path.to_string_lossy().to_string();
+        if !seen.insert(key.clone()) {
+            continue;
+        }
+        let set = assigned_set(setup.id, stream, bracket);
+        let codes = cache.replay_codes.get(&key).cloned().unwrap_or_default();
+        let reasons = friendly_reasons(set.as_ref(), &codes);
+        detected.push(GameClassification {
+            setup_id: setup.id,
+            replay_path: key,
+            set_id: set.map(|set| set.id),
+            friendly: !reasons.is_empty(),
+            reasons,
+            overridden: false,
+            detected_at_ms: now_ms(),
+        });
+    }
+    detected
+}
+
+fn record(class: GameClassification) {
+    let mut classifications = CLASSIFICATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    classifications.retain(|existing| existing.replay_path != class.replay_path);
+    classifications.push(class);
+    let excess = classifications.len().saturating_sub(MAX_CLASSIFICATIONS);
+    classifications.drain(..excess);
+}
+
+/// Classify each new game as it starts and emit `game-detected`. Replays
+/// already in the folder at startup are taken as seen.
+pub fn spawn_friendlies_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut first_pass = true;
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if is_idle() {
+                continue;
+            }
+            let store = app.state::<SharedSetupStore>().inner().clone();
+            let replay_cache = app.state::<SharedOverlayCache>().inner().clone();
+            // Wait for the first index so startup replays become the baseline.
+            if replay_cache.lock().unwrap_or_else(|e| e.into_inner()).indexed_dir.is_none() {
+                continue;
+            }
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let bracket = current_bracket_state(&test_state, &live_startgg);
+            let detected = classify_new_games(&store, &replay_cache, bracket.as_ref(), &mut seen);
+            if std::mem::take(&mut first_pass) {
+                continue;
+            }
+            for class in detected {
+                if class.friendly {
+                    tracing::info!(
+                        "Setup {}: {} looks like a friendly ({:?})",
+                        class.setup_id,
+                        class.replay_path,
+                        class.reasons
+                    );
+                }
+                let _ = app.emit("game-detected", &class);
+                record(class);
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Classified games, most recent first.
+#[tauri::command]
+#[specta::specta]
+pub fn game_classifications() -> Vec<GameClassification> {
+    let classifications = CLASSIFICATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    classifications.iter().rev().cloned().collect()
+}
+
+/// Operator ruling on a game: count it for its set or treat it as a
+/// friendly. Re-emits `game-detected` with the ruling.
+#[tauri::command]
+#[specta::specta]
+pub fn override_game_classification(
+    app: AppHandle,
+    replay_path: String,
+    friendly: bool,
+) -> Result<GameClassification, String> {
+    let params = json!({ "replayPath": replay_path, "friendly": friendly });
+    audited("overrideGameClassification", params, || {
+        let mut class = CLASSIFICATIONS
+            .lock()
+            .map_err(|e| e.to_string())?
+            .iter()
+            .find(|class| class.replay_path == replay_path)
+            .cloned()
+            .ok_or_else(|| format!("No game detected for {replay_path}."))?;
+        class.friendly = friendly;
+        class.overridden = true;
+        let _ = app.emit("game-detected", &class);
+        record(class.clone());
+        Ok(class)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::StartggSimSlot;
+
+    fn slot(code: &str, score: u8) -> StartggSimSlot {
+        StartggSimSlot { slippi_code: Some(code.to_string()), score: Some(score), ..Default::default() }
+    }
+
+    fn set(state: &str, scores: [u8; 2]) -> StartggSimSet {
+        StartggSimSet {
+            id: 1,
+            phase_id: "p".to_string(),
+            phase_name: "Pools".to_string(),
+            round: 1,
+            round_label: "Round 1".to_string(),
+            best_of: 3,
+            state: state.to_string(),
+            started_at_ms: (state != "pending").then_some(1),
+            slots: vec![slot("AAA#1", scores[0]), slot("BBB#2", scores[1])],
+            ..Default::default()
+        }
+    }
+
+    fn players() -> Vec<String> {
+        vec!["BBB#2".to_string(), "aaa#1".to_string()]
+    }
+
+    #[test]
+    fn a_game_of_the_running_set_is_not_a_friendly() {
+        assert!(friendly_reasons(Some(&set("inProgress", [1, 0])), &players()).is_empty());
+    }
+
+    #[test]
+    fn a_game_before_the_set_starts_is_a_friendly() {
+        assert_eq!(friendly_reasons(None, &players()), vec![FriendlyReason::SetNotStarted]);
+        assert_eq!(friendly_reasons(Some(&set("pending", [0, 0])), &players()), vec![FriendlyReason::SetNotStarted]);
+    }
+
+    #[test]
+    fn a_game_after_the_set_is_decided_is_a_friendly() {
+        assert_eq!(
+            friendly_reasons(Some(&set("inProgress", [2, 1])), &players()),
+            vec![FriendlyReason::SetAlreadyDecided]
+        );
+    }
+
+    #[test]
+    fn a_game_with_someone_outside_the_set_is_a_friendly() {
+        let stranger = vec!["AAA#1".to_string(), "CCC#3".to_string()];
+        assert_eq!(
+            friendly_reasons(Some(&set("inProgress", [0, 0])), &stranger),
+            vec![FriendlyReason::PlayersMismatch]
+        );
+    }
+
+    #[test]
+    fn players_are_only_judged_when_the_replay_names_codes() {
+        assert!(friendly_reasons(Some(&set("inProgress", [0, 0])), &[]).is_empty());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::bracket_config::read_replay_map;
+use crate::config::*;
+use crate::replay::{map_character, map_stage};
+use crate::replay_frames::replay_frames;
+use crate::types::SharedTestState;
+use peppi::io::slippi;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::State;
+
+/// Archived replays don't change, so each is parsed once.
+static GAMES: Mutex<Option<HashMap<PathBuf, ArchivedGame>>> = Mutex::new(None);
+
+/// What the facts need from one archived game.
+#[derive(Debug, Clone, PartialEq)]
+struct ArchivedGame {
+    stage: Option<&'static str>,
+    /// (player, character) for each port in use. Players go by their
+    /// Slippi display name, or name tag offline.
+    players: Vec<(String, &'static str)>,
+    winner: Option<String>,
+    duration_ms: Option<u64>,
+}
+
+/// One line for the break screen to rotate through.
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FunFact {
+    /// "mostPickedStage", "commonMatchup" or "fastestSweep"
+    pub kind: String,
+    pub title: String,
+    pub detail: String,
+}
+
+fn read_game(path: &Path) -> Option<ArchivedGame> {
+    let file = fs::File::open(path).ok()?;
+    let mut opts = slippi::de::Opts::default();
+    opts.skip_frames = true;
+    let game = slippi::de::read(file, Some(&opts)).ok()?;
+    let players = game
+        .start
+        .players
+        .iter()
+        .filter_map(|player| {
+            let character = map_character(player.character)?;
+            let name = player
+                .netplay
+                .as_ref()
+                .map(|netplay| decode_netplay_name(&netplay.name.0))
+                .or_else(|| player.name_tag.as_ref().map(|tag| tag.0.clone()))
+                .filter(|name| !name.trim().is_empty())
+                .unwrap_or_else(|| format!("{:?}", player.port));
+            Some((player.port, name, character))
+        })
+        .collect::<Vec<_>>();
+    let winner_port = game
+        .end
+        .as_ref()
+        .and_then(|end| end.players.as_ref())
+        .and_then(|placements| placements.iter().min_by_key(|player| player.placement))
+        .map(|player| player.port);
+    let winner = players
+        .iter()
+        .find(|(port, _, _)| Some(*port) == winner_port)
+        .map(|(_, name, _)| name.clone());
+    Some(ArchivedGame {
+        stage: map_stage(game.start.stage),
+        players: players.into_iter().map(|(_, name, character)| (name, character)).collect(),
+        winner,
+        duration_ms: replay_frames(path).ok().map(|frames| frames.duration_ms),
+    })
+}
+
+fn archived_game(path: &Path) -> Option<ArchivedGame> {
+    {
+        let mut guard = GAMES.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(game) = guard.get_or_insert_with(HashMap::new).get(path) {
+            return Some(game.clone());
+        }
+    }
+    // Not cached on failure: the replay may still be being archived.
+    let game = read_game(path)?;
+    let mut guard = GAMES.lock().unwrap_or_else(|e| e.into_inner());
+    guard.get_or_insert_with(HashMap::new).insert(path.to_path_buf(), game.clone());
+    Some(game)
+}
+
+fn format_duration(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+fn most_picked_stage(games: &[&ArchivedGame]) -> Option<FunFact> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for stage in games.iter().filter_map(|game| game.stage) {
+        *counts.entry(stage).or_default() += 1;
+    }
+    let total = counts.values().sum::<usize>();
+    let (stage, count) = counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))?;
+    Some(FunFact {
+        kind: "mostPickedStage".to_string(),
+        title: "Most picked stage".to_string(),
+        detail: format!("{stage}: {count} of {total} games"),
+    })
+}
+
+fn common_matchup(games: &[&ArchivedGame]) -> Option<FunFact> {
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    for game in games {
+        let [(_, a), (_, b)] = game.players.as_slice() else {
+            continue;
+        };
+        let pair = if a <= b { (*a, *b) } else { (*b, *a) };
+        *counts.entry(pair).or_default() += 1;
+    }
+    let ((a, b), count) = counts.into_iter().max_by(|x, y| x.1.cmp(&y.1).then_with(|| y.0.cmp(&x.0)))?;
+    let matchup = if a == b { format!("{a} ditto") } else { format!("{a} vs {b}") };
+    Some(FunFact {
+        kind: "commonMatchup".to_string(),
+        title: "Most common matchup".to_string(),
+        detail: format!("{matchup}: {count} games"),
+    })
+}
+
+/// The quickest best-of-five won 3-0, by total game time.
+fn fastest_sweep(sets: &[Vec<ArchivedGame>]) -> Option<FunFact> {
+    let sweeps = sets.iter().filter_map(|games| {
+        let [first, ..] = games.as_slice() else {
+            return None;
+        };
+        let winner = first.winner.as_ref()?;
+        if games.len() != 3 || games.iter().any(|game| game.winner.as_ref() != Some(winner)) {
+            return None;
+        }
+        let total = games.iter().map(|game| game.duration_ms).sum::<Option<u64>>()?;
+        let loser = first.players.iter().map(|(name, _)| name).find(|name| *name != winner)?;
+        Some((total, winner, loser))
+    });
+    let (total, winner, loser) = sweeps.min_by_key(|(total, _, _)| *total)?;
+    Some(FunFact {
+        kind: "fastestSweep".to_string(),
+        title: "Fastest 3-0".to_string(),
+        detail: format!("{winner} over {loser} in {}", format_duration(total)),
+    })
+}
+
+/// Facts over `sets`, each the archived games of one set in order.
+fn facts_for(sets: &[Vec<ArchivedGame>]) -> Vec<FunFact> {
+    let games = sets.iter().flatten().collect::<Vec<_>>();
+    [most_picked_stage(&games), common_matchup(&games), fastest_sweep(sets)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Facts from every game archived in the bracket config so far. New sets
+/// count as soon as their replays are archived.
+pub fn event_fun_facts(test_state: &SharedTestState) -> Result<Vec<FunFact>, String> {
+    let config_path = test_state
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .startgg_config_path
+        .clone()
+        .unwrap_or_else(startgg_sim_config_path);
+    if !config_path.is_file() {
+        return Ok(Vec::new());
+    }
+    let Some(replay_map) = read_replay_map(&config_path)? else {
+        return Ok(Vec::new());
+    };
+    let sets = replay_map
+        .sets
+        .iter()
+        .map(|set| {
+            replay_map
+                .replay_paths(set)
+                .iter()
+                .filter_map(|path| archived_game(path))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    Ok(facts_for(&sets))
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command(async)]
+#[specta::specta]
+pub fn fun_facts(test_state: State<'_, SharedTestState>) -> Result<Vec<FunFact>, String> {
+    event_fun_facts(&test_state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Player<'a> = (&'a str, &'static str);
+
+    fn game(stage: &'static str, p1: Player, p2: Player, won: &str, secs: u64) -> ArchivedGame {
+        ArchivedGame {
+            stage: Some(stage),
+            players: vec![(p1.0.to_string(), p1.1), (p2.0.to_string(), p2.1)],
+            winner: Some(won.to_string()),
+            duration_ms: Some(secs * 1000),
+        }
+    }
+
+    const A: Player<'static> = ("AAA#1", "Fox");
+    const B: Player<'static> = ("BBB#2", "Marth");
+    const C: Player<'static> = ("CCC#3", "Fox");
+
+    fn sets() -> Vec<Vec<ArchivedGame>> {
+        vec![
+            // A 3-0 that took 8:00.
+            vec![
+                game("Battlefield", A, B, "AAA#1", 160),
+                game("Yoshi's Story", A, B, "AAA#1", 160),
+                game("Battlefield", A, B, "AAA#1", 160),
+            ],
+            // A 3-0 in 6:05.
+            vec![
+                game("Final Destination", C, B, "BBB#2", 120),
+                game("Battlefield", C, B, "BBB#2", 125),
+                game("Dream Land N64", C, B, "BBB#2", 120),
+            ],
+            // Quicker still, but not a sweep.
+            vec![
+                game("Battlefield", A, C, "AAA#1", 60),
+                game("Pokemon Stadium", A, C, "CCC#3", 60),
+                game("Battlefield", A, C, "AAA#1", 60),
+            ],
+        ]
+    }
+
+    fn detail(sets: &[Vec<ArchivedGame>], kind: &str) -> Option<String> {
+        facts_for(sets).into_iter().find(|fact| fact.kind == kind).map(|fact| fact.detail)
+    }
+
+    #[test]
+    fn the_most_picked_stage_counts_every_game() {
+        assert_eq!(detail(&sets(), "mostPickedStage").as_deref(), Some("Battlefield: 5 of 9 games"));
+    }
+
+    #[test]
+    fn matchups_count_either_side_and_name_dittos() {
+        assert_eq!(detail(&sets(), "commonMatchup").as_deref(), Some("Fox vs Marth: 6 games"));
+        let dittos = vec![sets().pop().unwrap()];
+        assert_eq!(detail(&dittos, "commonMatchup").as_deref(), Some("Fox ditto: 3 games"));
+    }
+
+    #[test]
+    fn the_fastest_sweep_ignores_quicker_sets_that_were_not_sweeps() {
+        assert_eq!(detail(&sets(), "fastestSweep").as_deref(), Some("BBB#2 over CCC#3 in 6:05"));
+        let no_sweeps = vec![sets().pop().unwrap()];
+        assert_eq!(detail(&no_sweeps, "fastestSweep"), None);
+    }
+
+    #[test]
+    fn a_sweep_with_an_unknown_game_length_is_skipped() {
+        let mut sets = sets();
+        sets[1][0].duration_ms = None;
+        assert_eq!(detail(&sets, "fastestSweep").as_deref(), Some("AAA#1 over BBB#2 in 8:00"));
+    }
+
+    #[test]
+    fn no_games_means_no_facts() {
+        assert!(facts_for(&[]).is_empty());
+    }
+}
@@ -0,0 +1,298 @@
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::idle_mode::is_idle;
+use crate::replay::{latest_replay_for_code, replay_modified_timestamp_ms};
+use crate::slippi::{cdp_targets, slippi_devtools_port};
+use crate::startgg_webhook::{webhooks_active, WEBHOOK_BACKUP_POLL_MS};
+use crate::test_mode::mock_streams_enabled;
+use crate::types::*;
+use axum::{
+    extract::{Query, State as AxumState},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{path::PathBuf, time::UNIX_EPOCH};
+
+/// A setup whose replay hasn't been written to for this long has no live
+/// data.
+const LIVE_DATA_WINDOW_MS: u64 = 30_000;
+/// Polls start.gg may miss in a row before polling counts as broken.
+const MISSED_POLLS: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum HealthLevel {
+    Ok,
+    /// Not in use right now, e.g. a simulated bracket; never fails the
+    /// report.
+    Skipped,
+    Failing,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthCheck {
+    /// "launcher", "setups" or "startgg"
+    pub name: String,
+    pub level: HealthLevel,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupHealth {
+    pub setup_id: u32,
+    pub live: bool,
+    /// When the setup's replay was last written; None before its first game
+    pub last_data_ms: Option<u64>,
+}
+
+/// Body of `/healthz`, answered with 503 unless `ok`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+    pub ok: bool,
+    pub checks: Vec<HealthCheck>,
+    /// Setups with a stream assigned
+    pub setups: Vec<SetupHealth>,
+    pub checked_at_ms: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HealthQuery {
+    /// "prometheus" for the text exposition format instead of JSON
+    format: Option<String>,
+}
+
+fn check(name: &str, level: HealthLevel, detail: impl Into<String>) -> HealthCheck {
+    HealthCheck { name: name.to_string(), level, detail: detail.into() }
+}
+
+fn launcher_check(config: &AppConfig) -> HealthCheck {
+    if mock_streams_enabled() || config.test_flags().fake_streams {
+        return check("launcher", HealthLevel::Skipped, "Streams are simulated.");
+    }
+    let port = slippi_devtools_port();
+    match cdp_targets(port) {
+        Ok(targets) if !targets.is_empty() => check("launcher", HealthLevel::Ok, format!("DevTools answering on port {port}.")),
+        Ok(_) => check("launcher", HealthLevel::Failing, format!("The launcher on port {port} has no page open.")),
+        Err(err) => check("launcher", HealthLevel::Failing, format!("Launcher unreachable: {err}")),
+    }
+}
+
+/// The replay each assigned setup is showing, if it has one yet.
+fn assigned_replays(state: &OverlayServerState, config: &AppConfig) -> Vec<(u32, Option<PathBuf>)> {
+    let setups = state.setup_store.lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+    let spoofed = if config.test_flags().fake_replays {
+        Some(state.test_state.lock().unwrap_or_else(|e| e.into_inner()).spoof_replays.clone())
+    } else {
+        None
+    };
+    let cache = state.replay_cache.lock().unwrap_or_else(|e| e.into_inner());
+    setups
+        .iter()
+        .filter_map(|setup| {
+            let stream = setup.assigned_stream.as_ref()?;
+            let replay = match spoofed.as_ref() {
+                Some(spoofed) => spoofed.get(&stream.id).cloned(),
+                None => stream.p1_code.as_deref().and_then(|code| latest_replay_for_code(&cache, code)),
+            };
+            Some((setup.id, replay))
+        })
+        .collect()
+}
+
+fn setup_health(setup_id: u32, last_data_ms: Option<u64>, now: u64) -> SetupHealth {
+    SetupHealth {
+        setup_id,
+        live: last_data_ms.is_some_and(|at| now.saturating_sub(at) <= LIVE_DATA_WINDOW_MS),
+        last_data_ms,
+    }
+}
+
+/// Between games a setup can go quiet on its own, so only every assigned
+/// setup going quiet at once counts as the pipeline being down.
+fn setups_check(setups: &[SetupHealth]) -> HealthCheck {
+    if setups.is_empty() {
+        return check("setups", HealthLevel::Skipped, "No setups have a stream assigned.");
+    }
+    let live = setups.iter().filter(|setup| setup.live).count();
+    let detail = format!(
+        "{live} of {} assigned setups had live data in the last {}s.",
+        setups.len(),
+        LIVE_DATA_WINDOW_MS / 1000
+    );
+    let level = if live == 0 { HealthLevel::Failing } else { HealthLevel::Ok };
+    check("setups", level, detail)
+}
+
+fn startgg_check(config: &AppConfig, live: &SharedLiveStartgg, now: u64) -> HealthCheck {
+    if config.test_flags().fake_bracket {
+        return check("startgg", HealthLevel::Skipped, "The bracket is simulated.");
+    }
+    if config.offline_mode || !config.startgg_polling || !feature_enabled(FeatureFlag::StartggPolling) {
+        return check("startgg", HealthLevel::Skipped, "start.gg polling is off.");
+    }
+    if is_idle() {
+        return check("startgg", HealthLevel::Skipped, "Idle mode; polling is slowed down.");
+    }
+    let (last_error, last_fetch_ms) = {
+        let guard = live.lock().unwrap_or_else(|e| e.into_inner());
+        let last_fetch_ms = guard
+            .last_fetch
+            .and_then(|at| at.duration_since(UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as u64);
+        (guard.last_error.clone(), last_fetch_ms)
+    };
+    if let Some(err) = last_error {
+        return check("startgg", HealthLevel::Failing, format!("Last poll failed: {err}"));
+    }
+    let interval_ms = if config.startgg_webhook_enabled && webhooks_active(now) {
+        WEBHOOK_BACKUP_POLL_MS
+    } else {
+        STARTGG_POLL_INTERVAL_MS
+    };
+    match last_fetch_ms {
+        Some(at) if now.saturating_sub(at) <= interval_ms * MISSED_POLLS => {
+            check("startgg", HealthLevel::Ok, format!("Last poll {}s ago.", now.saturating_sub(at) / 1000))
+        }
+        Some(at) => check(
+            "startgg",
+            HealthLevel::Failing,
+            format!("No successful poll for {}s.", now.saturating_sub(at) / 1000),
+        ),
+        None => check("startgg", HealthLevel::Failing, "start.gg hasn't been polled yet."),
+    }
+}
+
+pub fn health_report(state: &OverlayServerState) -> HealthReport {
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+    let now = now_ms();
+    let setups = assigned_replays(state, &config)
+        .into_iter()
+        .map(|(setup_id, replay)| {
+            let last_data_ms = replay
+                .as_deref()
+                .and_then(replay_modified_timestamp_ms)
+                .map(|at| at.max(0) as u64);
+            setup_health(setup_id, last_data_ms, now)
+        })
+        .collect::<Vec<_>>();
+    let checks = vec![
+        launcher_check(&config),
+        setups_check(&setups),
+        startgg_check(&config, &state.live_startgg, now),
+    ];
+    HealthReport {
+        ok: checks.iter().all(|check| check.level != HealthLevel::Failing),
+        checks,
+        setups,
+        checked_at_ms: now,
+    }
+}
+
+/// The report in Prometheus' text format. Skipped checks are left out.
+fn prometheus_text(report: &HealthReport) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP melee_stream_up 1 when every check passes.\n# TYPE melee_stream_up gauge\n");
+    out.push_str(&format!("melee_stream_up {}\n", u8::from(report.ok)));
+    out.push_str("# HELP melee_stream_check 1 when the check passes.\n# TYPE melee_stream_check gauge\n");
+    for check in report.checks.iter().filter(|check| check.level != HealthLevel::Skipped) {
+        let passing = u8::from(check.level == HealthLevel::Ok);
+        out.push_str(&format!("melee_stream_check{{check=\"{}\"}} {passing}\n", check.name));
+    }
+    out.push_str("# HELP melee_stream_setup_live 1 when the setup had live data recently.\n# TYPE melee_stream_setup_live gauge\n");
+    for setup in &report.setups {
+        out.push_str(&format!("melee_stream_setup_live{{setup=\"{}\"}} {}\n", setup.setup_id, u8::from(setup.live)));
+    }
+    out.push_str("# HELP melee_stream_setup_data_age_seconds Time since the setup's replay was written.\n# TYPE melee_stream_setup_data_age_seconds gauge\n");
+    for setup in &report.setups {
+        if let Some(at) = setup.last_data_ms {
+            let age = report.checked_at_ms.saturating_sub(at) as f64 / 1000.0;
+            out.push_str(&format!("melee_stream_setup_data_age_seconds{{setup=\"{}\"}} {age:.1}\n", setup.setup_id));
+        }
+    }
+    out
+}
+
+/// `/healthz`: 200 while the stream pipeline looks healthy, 503 otherwise,
+/// for uptime monitors and chat bots.
+pub async fn get_healthz(
+    AxumState(state): AxumState<OverlayServerState>,
+    Query(query): Query<HealthQuery>,
+) -> Response {
+    let report = match tokio::task::spawn_blocking(move || health_report(&state)).await {
+        Ok(report) => report,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    };
+    let status = if report.ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    if query.format.as_deref() == Some("prometheus") {
+        let headers = [("Content-Type", "text/plain; version=0.0.4"), ("Cache-Control", "no-store")];
+        return (status, headers, prometheus_text(&report)).into_response();
+    }
+    (status, crate::json_no_store(&report)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 100_000;
+
+    fn fresh() -> SetupHealth {
+        setup_health(1, Some(NOW - LIVE_DATA_WINDOW_MS), NOW)
+    }
+
+    fn stale() -> SetupHealth {
+        setup_health(2, Some(NOW - LIVE_DATA_WINDOW_MS - 1), NOW)
+    }
+
+    fn waiting() -> SetupHealth {
+        setup_health(3, None, NOW)
+    }
+
+    fn failing_report() -> HealthReport {
+        HealthReport {
+            ok: false,
+            checks: vec![check("launcher", HealthLevel::Skipped, ""), check("setups", HealthLevel::Failing, "")],
+            setups: vec![stale(), waiting()],
+            checked_at_ms: NOW,
+        }
+    }
+
+    #[test]
+    fn a_setup_is_live_while_its_replay_was_written_recently() {
+        assert!(fresh().live);
+        assert!(!stale().live);
+        assert!(!waiting().live);
+    }
+
+    #[test]
+    fn no_assigned_setups_skips_the_check() {
+        assert_eq!(setups_check(&[]).level, HealthLevel::Skipped);
+    }
+
+    #[test]
+    fn the_pipeline_is_down_only_when_every_setup_is_quiet() {
+        assert_eq!(setups_check(&[stale(), waiting()]).level, HealthLevel::Failing);
+        assert_eq!(setups_check(&[fresh(), stale()]).level, HealthLevel::Ok);
+    }
+
+    #[test]
+    fn prometheus_text_reports_up_and_leaves_out_skipped_checks() {
+        let text = prometheus_text(&failing_report());
+        assert!(text.contains("melee_stream_up 0\n"));
+        assert!(!text.contains("check=\"launcher\""));
+        assert!(text.contains("melee_stream_check{check=\"setups\"} 0\n"));
+    }
+
+    #[test]
+    fn prometheus_text_reports_data_age_only_for_setups_with_data() {
+        let text = prometheus_text(&failing_report());
+        assert!(text.contains("melee_stream_setup_live{setup=\"2\"} 0\n"));
+        assert!(text.contains("melee_stream_setup_data_age_seconds{setup=\"2\"} 30.0\n"));
+        assert!(!text.contains("melee_stream_setup_data_age_seconds{setup=\"3\"}"));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::jobs::Job;
+use crate::ratings::{event_report_paths, rated_sets_from_state};
+use crate::startgg::{
+    fetch_live_startgg_state, fetch_startgg_tournament_events, parse_startgg_link_info,
+    select_melee_singles_event_slug,
+};
+use crate::startgg_sim::StartggSimState;
+use crate::venue_time::venue_datetime;
+use serde_json::{json, Value};
+use std::{collections::HashSet, fs, path::PathBuf};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Default)]
+struct HistoryImportSummary {
+    imported: usize,
+    skipped: usize,
+    failed: usize,
+    sets_imported: usize,
+}
+
+/// What a pasted link or slug points at.
+#[derive(Debug, PartialEq, Eq)]
+enum ImportTarget {
+    Event(String),
+    /// Imported through its Melee singles event.
+    Tournament(String),
+}
+
+fn import_target(raw: &str) -> Result<ImportTarget, String> {
+    let info = parse_startgg_link_info(raw);
+    if let Some(event_slug) = info.event_slug {
+        return Ok(ImportTarget::Event(event_slug));
+    }
+    if let Some(tournament_slug) = info.tournament_slug {
+        return Ok(ImportTarget::Tournament(tournament_slug));
+    }
+    Err(format!("Not a start.gg tournament or event: {raw}"))
+}
+
+fn resolve_event_slug(config: &AppConfig, raw: &str) -> Result<String, String> {
+    match import_target(raw)? {
+        ImportTarget::Event(slug) => Ok(slug),
+        ImportTarget::Tournament(tournament_slug) => {
+            let events = fetch_startgg_tournament_events(config, &tournament_slug)?;
+            select_melee_singles_event_slug(&tournament_slug, &events)
+                .ok_or_else(|| format!("No Melee Singles event found for tournament {tournament_slug}."))
+        }
+    }
+}
+
+/// Event ids that already have a report in the logs folder, imported or not.
+fn reported_event_ids() -> HashSet<String> {
+    event_report_paths()
+        .iter()
+        .filter_map(|path| {
+            let data = fs::read_to_string(path).ok()?;
+            let report = serde_json::from_str::<Value>(&data).ok()?;
+            report.pointer("/event/id").and_then(Value::as_str).map(str::to_string)
+        })
+        .collect()
+}
+
+/// Named for when the event was played, so ratings replay it in order
+/// with the reports the tool wrote itself.
+fn import_report_path(played_at_ms: u64, event_id: &str) -> Option<PathBuf> {
+    let played = venue_datetime(played_at_ms as i64)?;
+    let id = event_id.chars().filter(char::is_ascii_alphanumeric).collect::<String>();
+    let name = format!("event_report_{}_import_{}.json", played.format("%Y%m%d_%H%M%S"), id);
+    Some(repo_root().join("logs").join(name))
+}
+
+/// Write a past event's completed sets as an event report, which is all
+/// ratings are built from. Returns the number of sets written.
+fn write_import_report(state: &StartggSimState, link: &str) -> Result<usize, String> {
+    let completed_sets = rated_sets_from_state(state);
+    let played_at_ms = completed_sets
+        .iter()
+        .filter_map(|set| set.completed_at_ms)
+        .min()
+        .ok_or_else(|| "No completed sets with a finish time.".to_string())?;
+    let path = import_report_path(played_at_ms, &state.event.id)
+        .ok_or_else(|| format!("Bad finish time {played_at_ms} for {}.", state.event.name))?;
+    let report = json!({
+        "generatedAtMs": now_ms(),
+        "importedFrom": link,
+        "event": state.event,
+        "setsTotal": state.sets.len(),
+        "setsCompleted": completed_sets.len(),
+        "completedSets": completed_sets,
+    });
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let body = serde_json::to_string_pretty(&report).map_err(|e| format!("serialize event report: {e}"))?;
+    write_file_atomic(&path, body.as_bytes()).map_err(|e| format!("write {}: {e}", path.display()))?;
+    Ok(completed_sets.len())
+}
+
+/// Import one event. `Ok(None)` when it is already in the history.
+fn import_event(config: &AppConfig, link: &str, known: &mut HashSet<String>) -> Result<Option<usize>, String> {
+    let slug = resolve_event_slug(config, link)?;
+    // Also refreshes the player DB from the event's entrants.
+    let state = fetch_live_startgg_state(config, &slug)?;
+    if !known.insert(state.event.id.clone()) {
+        return Ok(None);
+    }
+    write_import_report(&state, link).map(Some)
+}
+
+fn run_import(job: &Job, links: &[String]) -> Result<HistoryImportSummary, String> {
+    let config = cached_config()?;
+    let mut summary = HistoryImportSummary::default();
+    let mut known = reported_event_ids();
+    for (index, link) in links.iter().enumerate() {
+        job.step(index, links.len(), format!("Importing {link}"))?;
+        match import_event(&config, link, &mut known) {
+            Ok(Some(sets)) => {
+                summary.imported += 1;
+                summary.sets_imported += sets;
+            }
+            Ok(None) => summary.skipped += 1,
+            Err(err) => {
+                summary.failed += 1;
+                tracing::warn!("History import of {} failed: {}", link, err);
+            }
+        }
+    }
+    Ok(summary)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Import past events (start.gg links or slugs, one per entry) into the
+/// results history in the background. Returns the id of the job that
+/// reports its progress.
+#[tauri::command]
+#[specta::specta]
+pub fn import_event_history(links: Vec<String>, app: AppHandle) -> Result<String, String> {
+    let links = links
+        .iter()
+        .map(|link| link.trim().to_string())
+        .filter(|link| !link.is_empty())
+        .collect::<Vec<_>>();
+    if links.is_empty() {
+        return Err("Paste at least one start.gg event link.".to_string());
+    }
+    audited("importEventHistory", json!({ "links": links }), || {
+        for link in &links {
+            import_target(link)?;
+        }
+        let label = format!("Import {} past event(s)", links.len());
+        let job = Job::start_exclusive(&app, "historyImport", label, true)?;
+        let id = job.id().to_string();
+        std::thread::spawn(move || {
+            let result = run_import(&job, &links);
+            let _ = job.finish(result, |summary| {
+                format!(
+                    "{} imported ({} sets), {} already imported, {} failed.",
+                    summary.imported, summary.sets_imported, summary.skipped, summary.failed
+                )
+            });
+        });
+        Ok(id)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_links_import_that_event() {
+        let event = ImportTarget::Event("tournament/weekly-12/event/melee-singles".to_string());
+        assert_eq!(import_target("https://www.start.gg/tournament/weekly-12/event/melee-singles/overview"), Ok(event));
+    }
+
+    #[test]
+    fn tournament_links_and_slugs_import_through_the_tournament() {
+        assert_eq!(
+            import_target("start.gg/tournament/weekly-12/details"),
+            Ok(ImportTarget::Tournament("weekly-12".to_string()))
+        );
+        assert_eq!(import_target("weekly-12"), Ok(ImportTarget::Tournament("weekly-12".to_string())));
+    }
+
+    #[test]
+    fn blank_links_are_rejected() {
+        assert!(import_target("   ").is_err());
+    }
+}
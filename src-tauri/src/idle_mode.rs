@@ -0,0 +1,204 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_sim::StartggSimSet;
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{sync::Mutex, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted whenever idle mode is entered or left, with the new status.
+pub const IDLE_MODE_EVENT: &str = "idle-mode";
+/// Start.gg poll interval while idle. Webhooks still refresh right away.
+pub const IDLE_POLL_INTERVAL_MS: u64 = 5 * 60 * 1000;
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Set while idle: when it started. `None` means normal operation.
+static IDLE_SINCE: Mutex<Option<u64>> = Mutex::new(None);
+/// Why idle mode last ended, e.g. "stream assigned to setup 2".
+static LAST_EXIT: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SubsystemMode {
+    Running,
+    /// Still running, at a lower rate
+    Slowed,
+    Paused,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemState {
+    pub name: String,
+    pub mode: SubsystemMode,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SubsystemStatus {
+    pub idle: bool,
+    pub idle_since_ms: Option<u64>,
+    pub last_exit_reason: Option<String>,
+    pub subsystems: Vec<SubsystemState>,
+}
+
+/// Whether non-essential work should back off: start.gg polling slows,
+/// the replay indexer sleeps and telemetry stops.
+pub fn is_idle() -> bool {
+    IDLE_SINCE.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+fn idle_since() -> Option<u64> {
+    *IDLE_SINCE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn enter_idle() {
+    let mut since = IDLE_SINCE.lock().unwrap_or_else(|e| e.into_inner());
+    if since.is_none() {
+        *since = Some(now_ms());
+        tracing::info!("Entering idle mode");
+    }
+}
+
+/// Leave idle mode, if in it.
+pub fn exit_idle(reason: &str) {
+    let mut since = IDLE_SINCE.lock().unwrap_or_else(|e| e.into_inner());
+    if since.take().is_some() {
+        tracing::info!("Leaving idle mode: {}", reason);
+        *LAST_EXIT.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason.to_string());
+    }
+}
+
+/// A set started at or after `since_ms`, i.e. one called during the break.
+fn set_called_since(sets: &[StartggSimSet], since_ms: u64) -> Option<&StartggSimSet> {
+    sets.iter()
+        .filter(|set| set.state == "inProgress")
+        .find(|set| set.started_at_ms.is_some_and(|at| at >= since_ms))
+}
+
+fn subsystem(name: &str, mode: SubsystemMode, detail: Option<String>) -> SubsystemState {
+    SubsystemState {
+        name: name.to_string(),
+        mode,
+        detail,
+    }
+}
+
+pub fn current_status() -> SubsystemStatus {
+    let config = cached_config().unwrap_or_default();
+    let idle_since_ms = idle_since();
+    let idle = idle_since_ms.is_some();
+    let polling = if config.test_flags().fake_bracket {
+        subsystem("startggPolling", SubsystemMode::Paused, Some("Simulated bracket".to_string()))
+    } else if config.offline_mode {
+        subsystem("startggPolling", SubsystemMode::Paused, Some("Offline mode".to_string()))
+    } else if !config.startgg_polling || config.startgg_link.trim().is_empty() {
+        subsystem("startggPolling", SubsystemMode::Paused, Some("Polling is off".to_string()))
+    } else if idle {
+        subsystem("startggPolling", SubsystemMode::Slowed, Some("Every 5 minutes".to_string()))
+    } else {
+        subsystem("startggPolling", SubsystemMode::Running, None)
+    };
+    let paused_when_idle = |name: &str| {
+        if idle {
+            subsystem(name, SubsystemMode::Paused, Some("Idle mode".to_string()))
+        } else {
+            subsystem(name, SubsystemMode::Running, None)
+        }
+    };
+    SubsystemStatus {
+        idle,
+        idle_since_ms,
+        last_exit_reason: LAST_EXIT.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+        subsystems: vec![polling, paused_when_idle("replayIndexer"), paused_when_idle("telemetry")],
+    }
+}
+
+/// While idle, watch the bracket and leave idle mode once a set is called.
+/// Emits `idle-mode` whenever the mode flips, whatever flipped it.
+pub fn spawn_idle_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut was_idle = false;
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if let Some(since) = idle_since() {
+                let test_state = app.state::<SharedTestState>().inner().clone();
+                let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+                let called = current_bracket_state(&test_state, &live_startgg)
+                    .and_then(|state| set_called_since(&state.sets, since).map(|set| set.round_label.clone()));
+                if let Some(round) = called {
+                    exit_idle(&format!("{round} was called"));
+                }
+            }
+            let idle = is_idle();
+            if idle != was_idle {
+                was_idle = idle;
+                let _ = app.emit(IDLE_MODE_EVENT, current_status());
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn subsystem_status() -> SubsystemStatus {
+    current_status()
+}
+
+/// Enter or leave idle mode. It also ends by itself when a stream is
+/// assigned or a set is called. The monitor emits `idle-mode` for the flip.
+#[tauri::command]
+#[specta::specta]
+pub fn set_idle_mode(idle: bool) -> Result<SubsystemStatus, String> {
+    audited("setIdleMode", json!({ "idle": idle }), || {
+        if idle {
+            enter_idle();
+        } else {
+            exit_idle("ended by operator");
+        }
+        Ok(current_status())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(id: u64, state: &str, started_at_ms: Option<u64>) -> StartggSimSet {
+        StartggSimSet {
+            id,
+            phase_id: "p".to_string(),
+            phase_name: "Top 8".to_string(),
+            round: 1,
+            round_label: format!("Set {id}"),
+            best_of: 3,
+            state: state.to_string(),
+            started_at_ms,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn a_set_started_during_the_break_is_called() {
+        let sets = vec![set(1, "inProgress", Some(900)), set(2, "inProgress", Some(1_200))];
+        assert_eq!(set_called_since(&sets, 1_000).map(|set| set.id), Some(2));
+        assert_eq!(set_called_since(&sets, 1_200).map(|set| set.id), Some(2));
+    }
+
+    #[test]
+    fn a_set_already_running_before_the_break_is_not_called() {
+        assert!(set_called_since(&[set(1, "inProgress", Some(900))], 1_000).is_none());
+    }
+
+    #[test]
+    fn finished_and_pending_sets_are_not_called() {
+        let sets = vec![set(1, "completed", Some(1_500)), set(2, "pending", None)];
+        assert!(set_called_since(&sets, 1_000).is_none());
+    }
+}
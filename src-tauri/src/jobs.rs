@@ -0,0 +1,284 @@
+use crate::config::*;
+use serde::Serialize;
+use specta::Type;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Emitted with a `JobProgress` whenever a job starts, moves or ends.
+pub const JOB_PROGRESS_EVENT: &str = "job-progress";
+/// The error a job returns after `cancel_job`.
+pub const JOB_CANCELLED: &str = "Cancelled.";
+/// Finished jobs stay listed so the task drawer can show how they ended.
+const FINISHED_KEPT: usize = 20;
+
+static JOBS: Mutex<Vec<JobEntry>> = Mutex::new(Vec::new());
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum JobState {
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+/// The one progress shape every long-running operation reports.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    pub id: String,
+    /// What sort of work this is, e.g. "archiveVerify".
+    pub kind: String,
+    pub label: String,
+    /// 0-100; `None` until the job knows how much work there is.
+    pub percent: Option<f32>,
+    pub message: Option<String>,
+    pub cancellable: bool,
+    pub state: JobState,
+    pub started_at_ms: u64,
+    pub updated_at_ms: u64,
+}
+
+struct JobEntry {
+    progress: JobProgress,
+    cancel: Arc<AtomicBool>,
+}
+
+fn percent(done: usize, total: usize) -> f32 {
+    if total == 0 {
+        return 100.0;
+    }
+    (done.min(total) as f32 / total as f32 * 100.0).clamp(0.0, 100.0)
+}
+
+/// Drop the oldest finished jobs beyond `FINISHED_KEPT`.
+fn prune_finished(jobs: &mut Vec<JobEntry>) {
+    let finished = jobs.iter().filter(|job| job.progress.state != JobState::Running).count();
+    let mut excess = finished.saturating_sub(FINISHED_KEPT);
+    jobs.retain(|job| {
+        if excess > 0 && job.progress.state != JobState::Running {
+            excess -= 1;
+            return false;
+        }
+        true
+    });
+}
+
+/// Handle held by the code doing the work. Dropping it while still
+/// running, e.g. on a panic, marks the job failed.
+pub struct Job {
+    app: AppHandle,
+    id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    pub fn start(app: &AppHandle, kind: &str, label: impl Into<String>, cancellable: bool) -> Job {
+        Job::register(app, kind, label.into(), cancellable, false).expect("non-exclusive jobs always start")
+    }
+
+    /// Like `start`, but fails while another job of `kind` is running.
+    pub fn start_exclusive(
+        app: &AppHandle,
+        kind: &str,
+        label: impl Into<String>,
+        cancellable: bool,
+    ) -> Result<Job, String> {
+        Job::register(app, kind, label.into(), cancellable, true)
+    }
+
+    fn register(app: &AppHandle, kind: &str, label: String, cancellable: bool, exclusive: bool) -> Result<Job, String> {
+        let now = now_ms();
+        let id = format!("job-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let progress = JobProgress {
+            id: id.clone(),
+            kind: kind.to_string(),
+            label,
+            percent: None,
+            message: None,
+            cancellable,
+            state: JobState::Running,
+            started_at_ms: now,
+            updated_at_ms: now,
+        };
+        {
+            let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+            let busy = jobs.iter().find(|job| job.progress.kind == kind && job.progress.state == JobState::Running);
+            if let Some(busy) = busy.filter(|_| exclusive) {
+                return Err(format!("{} is already running.", busy.progress.label));
+            }
+            jobs.push(JobEntry {
+                progress: progress.clone(),
+                cancel: cancel.clone(),
+            });
+        }
+        let _ = app.emit(JOB_PROGRESS_EVENT, &progress);
+        Ok(Job {
+            app: app.clone(),
+            id,
+            cancel,
+        })
+    }
+
+    fn update(&self, f: impl FnOnce(&mut JobProgress)) {
+        let progress = {
+            let mut jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+            let Some(entry) = jobs.iter_mut().find(|job| job.progress.id == self.id) else {
+                return;
+            };
+            f(&mut entry.progress);
+            entry.progress.updated_at_ms = now_ms();
+            let progress = entry.progress.clone();
+            if progress.state != JobState::Running {
+                prune_finished(&mut jobs);
+            }
+            progress
+        };
+        let _ = self.app.emit(JOB_PROGRESS_EVENT, &progress);
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    /// Report `done` of `total` units of work. Fails with `JOB_CANCELLED`
+    /// once the job has been cancelled, so loops can stop with `?`.
+    pub fn step(&self, done: usize, total: usize, message: impl Into<String>) -> Result<(), String> {
+        if self.is_cancelled() {
+            return Err(JOB_CANCELLED.to_string());
+        }
+        let message = message.into();
+        self.update(|progress| {
+            progress.percent = Some(percent(done, total));
+            progress.message = Some(message);
+        });
+        Ok(())
+    }
+
+    /// End the job with the operation's result and hand the result back.
+    pub fn finish<T>(self, result: Result<T, String>, message: impl FnOnce(&T) -> String) -> Result<T, String> {
+        let (state, text) = match &result {
+            Ok(value) => (JobState::Done, message(value)),
+            Err(_) if self.is_cancelled() => (JobState::Cancelled, JOB_CANCELLED.to_string()),
+            Err(err) => (JobState::Failed, err.clone()),
+        };
+        self.update(|progress| {
+            if state == JobState::Done {
+                progress.percent = Some(100.0);
+            }
+            progress.state = state;
+            progress.message = Some(text);
+        });
+        result
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let running = JOBS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|job| job.progress.id == self.id && job.progress.state == JobState::Running);
+        if running {
+            self.update(|progress| {
+                progress.state = JobState::Failed;
+                progress.message = Some("Stopped unexpectedly.".to_string());
+            });
+        }
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Running jobs, then recently finished ones, newest first within each.
+#[tauri::command]
+#[specta::specta]
+pub fn list_jobs() -> Vec<JobProgress> {
+    let jobs = JOBS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut list = jobs.iter().rev().map(|job| job.progress.clone()).collect::<Vec<_>>();
+    list.sort_by_key(|job| job.state != JobState::Running);
+    list
+}
+
+/// Ask a cancellable job to stop. It ends as `cancelled` at its next step.
+#[tauri::command]
+#[specta::specta]
+pub fn cancel_job(id: String) -> Result<bool, String> {
+    let jobs = JOBS.lock().map_err(|e| e.to_string())?;
+    let job = jobs
+        .iter()
+        .find(|job| job.progress.id == id)
+        .ok_or_else(|| format!("Job {id} not found."))?;
+    if !job.progress.cancellable {
+        return Err(format!("{} can't be cancelled.", job.progress.label));
+    }
+    if job.progress.state != JobState::Running {
+        return Ok(false);
+    }
+    job.cancel.store(true, Ordering::SeqCst);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32, state: JobState) -> JobEntry {
+        JobEntry {
+            progress: JobProgress {
+                id: format!("job-{id}"),
+                kind: "test".to_string(),
+                label: "Test".to_string(),
+                percent: None,
+                message: None,
+                cancellable: false,
+                state,
+                started_at_ms: 0,
+                updated_at_ms: 0,
+            },
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    #[test]
+    fn percent_is_the_share_of_steps_done() {
+        assert_eq!(percent(1, 4), 25.0);
+        assert_eq!(percent(4, 4), 100.0);
+    }
+
+    #[test]
+    fn percent_is_clamped_and_empty_jobs_are_complete() {
+        assert_eq!(percent(9, 4), 100.0);
+        assert_eq!(percent(0, 0), 100.0);
+    }
+
+    #[test]
+    fn the_oldest_finished_jobs_are_pruned() {
+        let mut jobs = (1..=FINISHED_KEPT as u32 + 3).map(|id| entry(id, JobState::Done)).collect::<Vec<_>>();
+        prune_finished(&mut jobs);
+        assert_eq!(jobs.len(), FINISHED_KEPT);
+        assert_eq!(jobs[0].progress.id, "job-4");
+    }
+
+    #[test]
+    fn running_jobs_are_never_pruned() {
+        let mut jobs = vec![entry(0, JobState::Running)];
+        jobs.extend((1..=FINISHED_KEPT as u32 + 3).map(|id| entry(id, JobState::Done)));
+        jobs.push(entry(99, JobState::Running));
+        prune_finished(&mut jobs);
+        assert_eq!(jobs.len(), FINISHED_KEPT + 2);
+        assert_eq!(jobs[0].progress.id, "job-0");
+        assert_eq!(jobs[1].progress.id, "job-4");
+        assert_eq!(jobs.last().unwrap().progress.id, "job-99");
+    }
+}
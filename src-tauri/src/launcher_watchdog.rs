@@ -0,0 +1,243 @@
+use crate::capabilities::require_slippi_launcher;
+use crate::capture_sources::is_capture_stream;
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::dolphin::{list_slippi_pids, slippi_appimage_path};
+use crate::slippi::{assign_stream, cdp_targets, click_slippi_refresh, launch_slippi_app, slippi_devtools_port};
+use crate::slippi_login::ensure_slippi_logged_in;
+use crate::test_mode::mock_streams_enabled;
+use crate::types::*;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with a `LauncherAlert` when the launcher dies while setups are
+/// spectating, and again once recovery has finished.
+pub const LAUNCHER_ALERT_EVENT: &str = "slippi-launcher-alert";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(3);
+/// Polls in a row without a launcher process before it counts as crashed,
+/// so a relaunch from the UI isn't mistaken for one.
+const MISSES_BEFORE_CRASH: u32 = 2;
+const DEVTOOLS_READY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time for the spectate list to fill in after a refresh.
+const SPECTATE_LIST_SETTLE: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum LauncherAlertStage {
+    /// The launcher exited; recovery is starting.
+    Crashed,
+    /// Relaunched, with every setup re-watching its stream.
+    Recovered,
+    /// Relaunching or re-watching failed; see `message`.
+    RecoveryFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct LauncherAlert {
+    pub stage: LauncherAlertStage,
+    /// Setups that were spectating when the launcher went away.
+    pub setup_ids: Vec<u32>,
+    pub message: String,
+    pub at_ms: u64,
+}
+
+/// Launcher liveness as seen from one poll to the next.
+#[derive(Debug, Default)]
+struct LauncherWatch {
+    seen_running: bool,
+    misses: u32,
+}
+
+impl LauncherWatch {
+    /// Record a poll. True once, when a launcher that was running has been
+    /// gone for `MISSES_BEFORE_CRASH` polls.
+    fn observe(&mut self, running: bool) -> bool {
+        if running {
+            self.seen_running = true;
+            self.misses = 0;
+            return false;
+        }
+        if !self.seen_running {
+            return false;
+        }
+        self.misses += 1;
+        if self.misses < MISSES_BEFORE_CRASH {
+            return false;
+        }
+        self.seen_running = false;
+        self.misses = 0;
+        true
+    }
+}
+
+fn emit_alert(app: &AppHandle, stage: LauncherAlertStage, setup_ids: &[u32], message: String) {
+    match stage {
+        LauncherAlertStage::Recovered => tracing::info!("{}", message),
+        _ => tracing::error!("{}", message),
+    }
+    let alert = LauncherAlert {
+        stage,
+        setup_ids: setup_ids.to_vec(),
+        message,
+        at_ms: now_ms(),
+    };
+    let _ = app.emit(LAUNCHER_ALERT_EVENT, &alert);
+}
+
+fn wait_for_devtools(port: u16) -> Result<(), String> {
+    let deadline = Instant::now() + DEVTOOLS_READY_TIMEOUT;
+    loop {
+        match cdp_targets(port) {
+            Ok(targets) if !targets.is_empty() => return Ok(()),
+            Ok(_) | Err(_) if Instant::now() < deadline => sleep(Duration::from_millis(500)),
+            Ok(_) => return Err("the relaunched launcher has no DevTools page".to_string()),
+            Err(err) => return Err(format!("the relaunched launcher never opened DevTools: {err}")),
+        }
+    }
+}
+
+/// Relaunch the launcher and have each setup watch what it watched before.
+fn recover(app: &AppHandle, assigned: &[(u32, SlippiStream)]) -> Result<Vec<String>, String> {
+    let port = slippi_devtools_port();
+    launch_slippi_app()?;
+    wait_for_devtools(port)?;
+    ensure_slippi_logged_in(app, port)?;
+    // Cards only show up in the spectate list after a refresh.
+    click_slippi_refresh(port)?;
+    sleep(SPECTATE_LIST_SETTLE);
+
+    let mut warnings = Vec::new();
+    for (setup_id, stream) in assigned {
+        let store = app.state::<SharedSetupStore>();
+        let test_state = app.state::<SharedTestState>();
+        match assign_stream(*setup_id, stream.clone(), Some(true), &store, &test_state) {
+            Ok(result) => warnings.extend(result.warning),
+            Err(err) => warnings.push(format!("Setup {setup_id}: {err}")),
+        }
+    }
+    Ok(warnings)
+}
+
+fn handle_crash(app: &AppHandle) {
+    let assigned = app
+        .state::<SharedSetupStore>()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .setups
+        .iter()
+        .filter_map(|setup| Some((setup.id, setup.assigned_stream.clone()?)))
+        .filter(|(_, stream)| !is_capture_stream(stream))
+        .collect::<Vec<_>>();
+    // Nothing was spectating, so nothing stopped; leave a closed launcher be.
+    if assigned.is_empty() {
+        tracing::info!("Slippi launcher exited with no setups spectating");
+        return;
+    }
+    let setup_ids = assigned.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    emit_alert(
+        app,
+        LauncherAlertStage::Crashed,
+        &setup_ids,
+        format!(
+            "Slippi launcher stopped while {} setup(s) were spectating. Relaunching it and re-watching their streams.",
+            setup_ids.len()
+        ),
+    );
+    match recover(app, &assigned) {
+        Ok(warnings) if warnings.is_empty() => emit_alert(
+            app,
+            LauncherAlertStage::Recovered,
+            &setup_ids,
+            "Slippi launcher relaunched; every setup is watching its stream again.".to_string(),
+        ),
+        Ok(warnings) => emit_alert(
+            app,
+            LauncherAlertStage::RecoveryFailed,
+            &setup_ids,
+            format!("Slippi launcher relaunched, but not every stream came back: {}", warnings.join(" ")),
+        ),
+        Err(err) => emit_alert(
+            app,
+            LauncherAlertStage::RecoveryFailed,
+            &setup_ids,
+            format!("Couldn't recover the Slippi launcher: {err}"),
+        ),
+    }
+}
+
+/// Watch the launcher process and recover from it crashing mid-set.
+/// Does nothing with mock streams or without launcher support.
+pub fn spawn_launcher_watchdog(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut watch = LauncherWatch::default();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if mock_streams_enabled()
+                || test_flags().fake_streams
+                || !feature_enabled(FeatureFlag::LauncherWatchdog)
+                || require_slippi_launcher("Watching").is_err()
+            {
+                watch = LauncherWatch::default();
+                continue;
+            }
+            let Ok(appimage) = slippi_appimage_path() else {
+                continue;
+            };
+            let running = !list_slippi_pids(&appimage).is_empty();
+            if watch.observe(running) {
+                handle_crash(&app);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_launcher_never_seen_running_does_not_crash() {
+        let mut watch = LauncherWatch::default();
+        for _ in 0..5 {
+            assert!(!watch.observe(false));
+        }
+    }
+
+    #[test]
+    fn one_missed_poll_is_not_a_crash() {
+        let mut watch = LauncherWatch::default();
+        assert!(!watch.observe(true));
+        // E.g. a relaunch from the UI.
+        assert!(!watch.observe(false));
+        assert!(!watch.observe(true));
+        assert!(!watch.observe(false));
+    }
+
+    #[test]
+    fn repeated_misses_are_reported_once() {
+        let mut watch = LauncherWatch::default();
+        watch.observe(true);
+        assert!(!watch.observe(false));
+        assert!(watch.observe(false));
+        assert!(!watch.observe(false));
+        assert!(!watch.observe(false));
+    }
+
+    #[test]
+    fn a_relaunched_launcher_is_watched_again() {
+        let mut watch = LauncherWatch::default();
+        watch.observe(true);
+        watch.observe(false);
+        assert!(watch.observe(false));
+        watch.observe(true);
+        assert!(!watch.observe(false));
+        assert!(watch.observe(false));
+    }
+}
@@ -5,21 +5,99 @@ pub mod dolphin;
 pub mod startgg;
 pub mod test_mode;
 pub mod slippi;
+pub mod slippi_login;
 pub mod startgg_sim_commands;
 pub mod entrants;
 pub mod entrant_commands;
-mod startgg_sim;
+pub mod report_lock;
+pub mod assets;
+pub mod slippi_rank;
+pub mod overlay_history;
+pub mod startup;
+pub mod shutdown;
+pub mod playback;
+pub mod realtime_playback;
+pub mod cli;
+pub mod network;
+pub mod scenes;
+pub mod frame_export;
+pub mod ratings;
+pub mod archive;
+pub mod startgg_webhook;
+pub mod audit_log;
+pub mod frame_pacing;
+pub mod spoof_latency;
+pub mod run_of_show;
+pub mod player_db;
+pub mod capabilities;
+pub mod overlay_refresh;
+pub mod slippi_ocr;
+pub mod capture_windows;
+pub mod session_stats;
+pub mod slp_scan;
+mod connect_codes;
+mod exhibition;
+mod friendlies;
+mod linked_bracket;
+mod venue_time;
+mod idle_mode;
+mod auto_stop;
+mod stream_identity;
+mod sim_worker;
+mod replay_frames;
+mod connection_sim;
+mod mirror;
+mod reseed;
+mod entrant_audit;
+mod history_import;
+mod jobs;
+mod fun_facts;
+mod watch_party;
+mod capture_sources;
+mod commentators;
+mod config_transaction;
+mod stage_strikes;
+mod slippi_ws;
+mod launcher_watchdog;
+mod process_health;
+mod obs;
+mod health;
+mod auto_assign;
+mod event_templates;
+mod startgg_report;
+mod replay_upload;
+mod score_tracker;
+mod set_codes;
+mod ready_check;
+mod crowd_prompts;
+mod forecast;
+mod bindings;
+mod broadcast_watch;
+mod checklist;
+mod feature_flags;
+pub mod setup_meta;
+mod overlay_ws;
+mod overlay_sse;
+mod stream_delay;
+mod countdowns;
+mod result_card;
+mod remote_api;
+mod windowing;
+pub mod bracket_config;
+#[cfg(test)]
+mod test_replays;
+pub use melee_stream_core::startgg_sim;
 
 use types::*;
 use config::*;
-use startgg::init_startgg_sim;
-use config::normalize_slippi_code;
 use replay::{
     build_overlay_state, is_replay_file_path, replay_slots_from_file,
 };
 use entrants::EntrantManager;
+use bracket_config::{read_replay_map, write_replay_map, BracketReplayEntry};
+use startgg_sim::StartggSimState;
 
-use serde_json::{json, Value};
+use serde_json::json;
 use std::{
     collections::{HashMap, HashSet},
     fs,
@@ -28,20 +106,22 @@ use std::{
     time::UNIX_EPOCH,
 };
 use axum::{
-    extract::State as AxumState,
-    response::IntoResponse,
-    routing::{get, get_service},
+    extract::{Path as AxumPath, Query, State as AxumState},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, get_service, post},
     Router,
 };
 use tokio::net::TcpListener;
 use tower_http::services::ServeDir;
-use tauri::{path::BaseDirectory, Manager, State};
+use tauri::{path::BaseDirectory, AppHandle, Emitter, Manager, State};
 use tracing::{info, error};
 use tracing_subscriber::EnvFilter;
 
 // ── Setup CRUD commands ────────────────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 fn list_setups_stub() -> SetupsPayload {
     SetupsPayload {
         setups: vec![
@@ -54,13 +134,22 @@ fn list_setups_stub() -> SetupsPayload {
 }
 
 #[tauri::command]
+#[specta::specta]
 fn list_setups(store: State<'_, SharedSetupStore>) -> Result<Vec<Setup>, String> {
     let guard = store.lock().map_err(|e| e.to_string())?;
     Ok(guard.setups.clone())
 }
 
 #[tauri::command]
+#[specta::specta]
 fn create_setup(store: State<'_, SharedSetupStore>) -> Result<Setup, String> {
+    let result = create_setup_slot(&store);
+    let setup_id = result.as_ref().ok().map(|setup| setup.id);
+    audit_log::record_local("createSetup", json!({ "setupId": setup_id }), &result);
+    result
+}
+
+pub fn create_setup_slot(store: &SharedSetupStore) -> Result<Setup, String> {
     let mut guard = store.lock().map_err(|e| e.to_string())?;
     if guard.setups.len() >= MAX_SETUP_COUNT {
         return Err(format!("Max setups ({MAX_SETUP_COUNT}) reached."));
@@ -74,22 +163,88 @@ fn create_setup(store: State<'_, SharedSetupStore>) -> Result<Setup, String> {
         }
     }
     let setup_id = setup_id.ok_or_else(|| "No setup slots available.".to_string())?;
-    let setup = Setup {
-        id: setup_id,
-        name: format!("Setup {setup_id}"),
-        assigned_stream: None,
-    };
+    let setup = Setup::new(setup_id);
     guard.setups.push(setup.clone());
     guard.setups.sort_by_key(|s| s.id);
+    setup_meta::persist_setups(&guard.setups);
     Ok(setup)
 }
 
 #[tauri::command]
-fn delete_setup(id: u32, store: State<'_, SharedSetupStore>) -> Result<(), String> {
+#[specta::specta]
+fn delete_setup(
+    id: u32,
+    force: Option<bool>,
+    app: AppHandle,
+    store: State<'_, SharedSetupStore>,
+    history: State<'_, SharedOverlayHistory>,
+) -> Result<(), String> {
+    let force = force.unwrap_or(false);
+    audit_log::audited("deleteSetup", json!({ "setupId": id, "force": force }), || {
+        delete_setup_inner(id, force, &store, &history, |warning| {
+            let _ = app.emit("setup-deleting-live", warning);
+            let setup = store
+                .lock()
+                .ok()
+                .and_then(|guard| guard.setups.iter().find(|s| s.id == id).cloned());
+            match setup.map(|setup| obs::release_setup_scene(&setup)) {
+                Some(Ok(Some(scene))) => info!("Switched OBS to '{}' before closing setup {}", scene, id),
+                Some(Err(err)) => tracing::warn!("Taking setup {} off OBS before deleting it: {}", id, err),
+                _ => {}
+            }
+        })
+    })
+}
+
+/// Delete a setup, refusing one that's on stream unless `force`d.
+/// `on_live` hears about a forced delete before Dolphin closes, so the OBS
+/// scene showing this setup's capture is switched away first.
+pub fn delete_setup_inner(
+    id: u32,
+    force: bool,
+    store: &SharedSetupStore,
+    history: &SharedOverlayHistory,
+    on_live: impl FnOnce(&SetupDeleteWarning),
+) -> Result<(), String> {
+    let live = live_setup_stream(id, store)?;
+    if let Some(stream) = live.as_ref() {
+        let label = stream
+            .p1_tag
+            .as_deref()
+            .or(stream.p1_code.as_deref())
+            .unwrap_or(&stream.id);
+        if !force {
+            return Err(format!(
+                "Setup {id} is on stream ({label}). Clear its stream first, or delete it anyway to close its Dolphin."
+            ));
+        }
+        let warning = SetupDeleteWarning {
+            setup_id: id,
+            stream_id: stream.id.clone(),
+            label: label.to_string(),
+        };
+        on_live(&warning);
+        tracing::warn!("Deleting setup {} while it is on stream ({})", id, label);
+    }
+    remove_setup(id, store, history)
+}
+
+/// The stream assigned to a setup, if it has one.
+fn live_setup_stream(id: u32, store: &SharedSetupStore) -> Result<Option<SlippiStream>, String> {
+    let guard = store.lock().map_err(|e| e.to_string())?;
+    Ok(guard
+        .setups
+        .iter()
+        .find(|s| s.id == id)
+        .and_then(|s| s.assigned_stream.clone()))
+}
+
+pub fn remove_setup(id: u32, store: &SharedSetupStore, history: &SharedOverlayHistory) -> Result<(), String> {
     let (existing, existing_pid) = {
         let mut guard = store.lock().map_err(|e| e.to_string())?;
         guard.setups.retain(|s| s.id != id);
         guard.setups.sort_by_key(|s| s.id);
+        setup_meta::persist_setups(&guard.setups);
         (
             guard.processes.remove(&id),
             guard.process_pids.remove(&id),
@@ -101,12 +256,146 @@ fn delete_setup(id: u32, store: State<'_, SharedSetupStore>) -> Result<(), Strin
     if let Some(pid) = existing_pid {
         dolphin::stop_process_by_pid(pid)?;
     }
+    history.lock().map_err(|e| e.to_string())?.clear_setup(id);
+    frame_pacing::clear_frame_pacing(id);
+    mirror::end_mirror(id);
     Ok(())
 }
 
+pub fn update_side_mapping(
+    store: &SharedSetupStore,
+    setup_id: u32,
+    update: impl FnOnce(&mut SideMapping),
+) -> Result<Setup, String> {
+    setup_meta::update_setup(setup_id, store, |setup| update(&mut setup.side_mapping))
+}
+
+/// Keep a player on one overlay side for this setup no matter which port
+/// they pick. Switches the setup to the manual side policy.
+#[tauri::command]
+#[specta::specta]
+fn pin_player_side(
+    setup_id: u32,
+    code: String,
+    side: String,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "code": code, "side": side });
+    audit_log::audited("pinPlayerSide", params, || pin_player_side_inner(setup_id, &code, &side, &store))
+}
+
+pub fn pin_player_side_inner(setup_id: u32, code: &str, side: &str, store: &SharedSetupStore) -> Result<Setup, String> {
+    let code = normalize_slippi_code(code).ok_or_else(|| "Connect code is required.".to_string())?;
+    let side = side.trim().to_ascii_lowercase();
+    if side != "left" && side != "right" {
+        return Err(format!("Unknown side '{side}'. Expected left or right."));
+    }
+    update_side_mapping(store, setup_id, |mapping| {
+        mapping.policy = SideMappingPolicy::Manual;
+        mapping.pinned_code = Some(code);
+        mapping.pinned_side = Some(side);
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+fn set_side_mapping_policy(
+    setup_id: u32,
+    policy: SideMappingPolicy,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "policy": policy });
+    audit_log::audited("setSideMappingPolicy", params, || {
+        update_side_mapping(&store, setup_id, |mapping| mapping.policy = policy)
+    })
+}
+
+/// Choose which bracket a setup's overlay takes its sets from.
+#[tauri::command]
+#[specta::specta]
+fn set_setup_bracket(
+    setup_id: u32,
+    bracket: SetupBracket,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "bracket": bracket });
+    audit_log::audited("setSetupBracket", params, || set_setup_bracket_inner(setup_id, bracket, &store))
+}
+
+pub fn set_setup_bracket_inner(setup_id: u32, bracket: SetupBracket, store: &SharedSetupStore) -> Result<Setup, String> {
+    setup_meta::update_setup(setup_id, store, |setup| setup.bracket = bracket)
+}
+
+/// What a setup does once its set is over: nothing, clear the assignment,
+/// or switch to the break screen, after `grace_secs`.
+#[tauri::command]
+#[specta::specta]
+fn set_setup_auto_stop(
+    setup_id: u32,
+    policy: AutoStopPolicy,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "policy": policy });
+    audit_log::audited("setSetupAutoStop", params, || set_setup_auto_stop_inner(setup_id, policy, &store))
+}
+
+pub fn set_setup_auto_stop_inner(setup_id: u32, policy: AutoStopPolicy, store: &SharedSetupStore) -> Result<Setup, String> {
+    setup_meta::update_setup(setup_id, store, |setup| {
+        setup.auto_stop = policy;
+        if policy.action != AutoStopAction::BreakScene {
+            auto_stop::leave_break(setup_id);
+        }
+    })
+}
+
+/// Delay a setup's public overlays to match its stream's broadcast delay,
+/// so scores don't show up before the game does. 0 turns it off.
+#[tauri::command]
+#[specta::specta]
+fn set_setup_stream_delay(setup_id: u32, secs: u32, store: State<'_, SharedSetupStore>) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "secs": secs });
+    audit_log::audited("setSetupStreamDelay", params, || set_setup_stream_delay_inner(setup_id, secs, &store))
+}
+
+pub fn set_setup_stream_delay_inner(setup_id: u32, secs: u32, store: &SharedSetupStore) -> Result<Setup, String> {
+    if secs > stream_delay::MAX_STREAM_DELAY_SECS {
+        return Err(format!("Stream delay can be at most {}s.", stream_delay::MAX_STREAM_DELAY_SECS));
+    }
+    setup_meta::update_setup(setup_id, store, |setup| setup.stream_delay_secs = secs)
+}
+
+/// Choose when a setup's overlay is rebuilt.
+#[tauri::command]
+#[specta::specta]
+fn set_overlay_refresh_driver(
+    setup_id: u32,
+    driver: OverlayRefreshDriver,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "driver": driver });
+    audit_log::audited("setOverlayRefreshDriver", params, || set_overlay_refresh_driver_inner(setup_id, driver, &store))
+}
+
+pub fn set_overlay_refresh_driver_inner(
+    setup_id: u32,
+    driver: OverlayRefreshDriver,
+    store: &SharedSetupStore,
+) -> Result<Setup, String> {
+    if let OverlayRefreshDriver::Interval { ms } = driver {
+        if ms < overlay_refresh::MIN_REFRESH_INTERVAL_MS {
+            return Err(format!(
+                "Refresh interval must be at least {} ms.",
+                overlay_refresh::MIN_REFRESH_INTERVAL_MS
+            ));
+        }
+    }
+    setup_meta::update_setup(setup_id, store, |setup| setup.refresh_driver = driver)
+}
+
 // ── Bracket replay management commands ─────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 fn list_bracket_configs() -> Result<Vec<BracketConfigInfo>, String> {
     let dir = startgg_sim_configs_dir();
     if !dir.is_dir() {
@@ -137,6 +426,7 @@ fn list_bracket_configs() -> Result<Vec<BracketConfigInfo>, String> {
 }
 
 #[tauri::command]
+#[specta::specta]
 fn list_bracket_set_replay_paths(config_path: String, set_id: u64) -> Result<Vec<String>, String> {
     let paths = startgg::read_bracket_set_replay_paths(&config_path, set_id)?;
     Ok(paths
@@ -146,38 +436,28 @@ fn list_bracket_set_replay_paths(config_path: String, set_id: u64) -> Result<Vec
 }
 
 #[tauri::command]
+#[specta::specta]
 fn list_bracket_replay_sets(config_path: String) -> Result<Vec<u64>, String> {
     let resolved = resolve_startgg_sim_config_path(&config_path);
     if !resolved.is_file() {
         return Ok(Vec::new());
     }
-    let data = fs::read_to_string(&resolved)
-        .map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
-    let value: Value = serde_json::from_str(&data)
-        .map_err(|e| format!("parse bracket config {}: {e}", resolved.display()))?;
-
-    let mut out = Vec::new();
-    if let Some(sets) = value
-        .get("referenceReplayMap")
-        .and_then(|map| map.get("sets"))
-        .and_then(|sets| sets.as_array())
-    {
-        for set in sets {
-            let id = set.get("id").and_then(|v| v.as_u64());
-            let replays = set.get("replays").and_then(|v| v.as_array());
-            if let (Some(id), Some(replays)) = (id, replays) {
-                if replays.iter().any(|entry| entry.get("path").and_then(|p| p.as_str()).is_some()) {
-                    out.push(id);
-                }
-            }
-        }
-    }
+    let Some(replay_map) = read_replay_map(&resolved)? else {
+        return Ok(Vec::new());
+    };
+    let mut out = replay_map
+        .sets
+        .iter()
+        .filter(|set| !set.replays.is_empty())
+        .map(|set| set.id)
+        .collect::<Vec<_>>();
     out.sort();
     out.dedup();
     Ok(out)
 }
 
 #[tauri::command]
+#[specta::specta]
 fn update_bracket_set_replays(
     config_path: String,
     set_id: u64,
@@ -191,7 +471,9 @@ fn update_bracket_set_replays(
         return Err("No replay paths provided.".to_string());
     }
 
-    let mut unique_paths: Vec<PathBuf> = Vec::new();
+    // Paths into a zip pack (`pack.zip/game1.slp`) are stored as given and
+    // read from the extracted copy.
+    let mut unique_paths: Vec<(PathBuf, PathBuf)> = Vec::new();
     let mut seen_paths: HashSet<PathBuf> = HashSet::new();
     for raw in replay_paths {
         let trimmed = raw.trim();
@@ -199,14 +481,15 @@ fn update_bracket_set_replays(
             continue;
         }
         let path = PathBuf::from(trimmed);
-        if !is_replay_file_path(&path) {
+        if !is_replay_file_path(&path) || friendlies::is_friendly(&path) {
             continue;
         }
-        if !path.is_file() {
+        let readable = slp_scan::resolve_zip_member(&path);
+        if !readable.is_file() {
             continue;
         }
         if seen_paths.insert(path.clone()) {
-            unique_paths.push(path);
+            unique_paths.push((path, readable));
         }
     }
 
@@ -214,108 +497,51 @@ fn update_bracket_set_replays(
         return Err("No valid .slp files found.".to_string());
     }
 
-    let data = fs::read_to_string(&resolved)
-        .map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
-    let mut value: Value = serde_json::from_str(&data)
-        .map_err(|e| format!("parse bracket config {}: {e}", resolved.display()))?;
-
-    let root = value
-        .as_object_mut()
-        .ok_or_else(|| "Bracket config must be a JSON object.".to_string())?;
-    let replay_map = root
-        .entry("referenceReplayMap")
-        .or_insert_with(|| json!({ "sets": [] }));
-    let replay_map_obj = replay_map
-        .as_object_mut()
-        .ok_or_else(|| "referenceReplayMap must be an object.".to_string())?;
-    let sets_value = replay_map_obj
-        .entry("sets")
-        .or_insert_with(|| Value::Array(Vec::new()));
-    let sets = sets_value
-        .as_array_mut()
-        .ok_or_else(|| "referenceReplayMap sets must be an array.".to_string())?;
-
-    let mut entries = Vec::new();
-    for path in unique_paths {
-        let slots = replay_slots_from_file(&path);
-        let mut entry = serde_json::Map::new();
-        entry.insert("path".to_string(), Value::String(path.to_string_lossy().to_string()));
-        if !slots.is_empty() {
-            entry.insert("slots".to_string(), Value::Array(slots));
-        }
-        entries.push(Value::Object(entry));
-    }
-    let replay_entries = Value::Array(entries);
-
-    let mut updated = false;
-    for set in sets.iter_mut() {
-        if set.get("id").and_then(|v| v.as_u64()) == Some(set_id) {
-            if let Some(obj) = set.as_object_mut() {
-                obj.insert("replays".to_string(), replay_entries.clone());
-            } else {
-                *set = json!({ "id": set_id, "replays": replay_entries.clone() });
-            }
-            updated = true;
-            break;
-        }
-    }
-    if !updated {
-        sets.push(json!({ "id": set_id, "replays": replay_entries }));
-    }
-
-    let payload = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
-    fs::write(&resolved, payload)
-        .map_err(|e| format!("write bracket config {}: {e}", resolved.display()))?;
-    Ok(())
+    let entries = unique_paths
+        .iter()
+        .map(|(path, readable)| {
+            Ok(BracketReplayEntry {
+                path: path.to_string_lossy().to_string(),
+                slots: replay_slots_from_file(readable),
+                sha256: Some(archive::sha256_file(readable)?),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    let mut replay_map = read_replay_map(&resolved)?.unwrap_or_default();
+    replay_map.set_replays(set_id, entries);
+    write_replay_map(&resolved, &replay_map)
 }
 
 #[tauri::command]
+#[specta::specta]
 fn list_bracket_replay_pairs(config_path: String) -> Result<Vec<String>, String> {
     let resolved = resolve_startgg_sim_config_path(&config_path);
     if !resolved.is_file() {
         return Ok(Vec::new());
     }
-    let data = fs::read_to_string(&resolved)
-        .map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
-    let value: Value = serde_json::from_str(&data)
-        .map_err(|e| format!("parse bracket config {}: {e}", resolved.display()))?;
+    let Some(replay_map) = read_replay_map(&resolved)? else {
+        return Ok(Vec::new());
+    };
 
     let mut pairs: HashSet<String> = HashSet::new();
-    if let Some(sets) = value
-        .get("referenceReplayMap")
-        .and_then(|map| map.get("sets"))
-        .and_then(|sets| sets.as_array())
-    {
-        for set in sets {
-            let replays = match set.get("replays").and_then(|v| v.as_array()) {
-                Some(replays) => replays,
-                None => continue,
-            };
-            for replay_entry in replays {
-                let path = replay_entry.get("path").and_then(|v| v.as_str()).unwrap_or("").trim();
-                if path.is_empty() {
-                    continue;
-                }
-                let mut unique: Vec<String> = Vec::new();
-                let mut seen: HashSet<String> = HashSet::new();
-                if let Some(slots) = replay_entry.get("slots").and_then(|v| v.as_array()) {
-                    for slot in slots {
-                        if let Some(code) = slot.get("slippiCode").and_then(|v| v.as_str()) {
-                            if let Some(normalized) = normalize_slippi_code(code) {
-                                if seen.insert(normalized.clone()) {
-                                    unique.push(normalized);
-                                }
-                            }
-                        }
-                    }
-                }
-                if unique.len() != 2 {
-                    continue;
+    for replay_entry in replay_map.sets.iter().flat_map(|set| set.replays.iter()) {
+        if replay_entry.path.trim().is_empty() {
+            continue;
+        }
+        let mut unique: Vec<String> = Vec::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        for code in replay_entry.slots.iter().filter_map(|slot| slot.slippi_code.as_deref()) {
+            if let Some(normalized) = normalize_slippi_code(code) {
+                if seen.insert(normalized.clone()) {
+                    unique.push(normalized);
                 }
-                let key = config::replay_pair_key(&unique[0], &unique[1]);
-                pairs.insert(key);
             }
         }
+        if unique.len() != 2 {
+            continue;
+        }
+        let key = config::replay_pair_key(&unique[0], &unique[1]);
+        pairs.insert(key);
     }
     let mut out: Vec<String> = pairs.into_iter().collect();
     out.sort();
@@ -325,37 +551,73 @@ fn list_bracket_replay_pairs(config_path: String) -> Result<Vec<String>, String>
 // ── Config commands ────────────────────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 fn load_config() -> Result<AppConfig, String> {
-    let config = load_config_inner()?;
+    let config = reload_config()?;
     let _ = dolphin::ensure_slippi_wrapper();
     Ok(config)
 }
 
+/// Save settings and bring the sim and live start.gg state in line with
+/// them, as one transaction.
 #[tauri::command]
+#[specta::specta]
 fn save_config(
+    app: AppHandle,
     config: AppConfig,
     test_state: State<'_, SharedTestState>,
     live_startgg: State<'_, SharedLiveStartgg>,
 ) -> Result<AppConfig, String> {
-    let saved = save_config_inner(config)?;
+    let test_state = test_state.inner().clone();
+    let live_startgg = live_startgg.inner().clone();
+    let ((), saved) = config_transaction::config_transaction(&app, "saveConfig", |txn| {
+        // Spoofed replays keep writing to the folder they started in.
+        let moved = txn.before().spectate_folder_path.trim() != config.spectate_folder_path.trim();
+        if moved && !test_state.lock().map_err(|e| e.to_string())?.active_replay_sets.is_empty() {
+            return Err("Cancel the running replay spoofs before changing the spectate folder.".to_string());
+        }
+        *txn.config_mut() = config;
+        txn.save()?;
+        let before = txn.before().clone();
+        let state = test_state.clone();
+        txn.step(
+            "start.gg sim path",
+            |config| {
+                sync_startgg_sim_path_from_config(&mut test_state.lock().map_err(|e| e.to_string())?, config);
+                Ok(())
+            },
+            move || {
+                sync_startgg_sim_path_from_config(&mut state.lock().map_err(|e| e.to_string())?, &before);
+                Ok(())
+            },
+        )?;
+        let before = txn.before().clone();
+        let state = live_startgg.clone();
+        txn.step(
+            "live start.gg",
+            |config| {
+                sync_live_startgg_from_config(&mut live_startgg.lock().map_err(|e| e.to_string())?, config);
+                Ok(())
+            },
+            move || {
+                sync_live_startgg_from_config(&mut state.lock().map_err(|e| e.to_string())?, &before);
+                Ok(())
+            },
+        )
+    })?;
     let _ = dolphin::ensure_slippi_wrapper();
-    if let Ok(mut guard) = test_state.lock() {
-        sync_startgg_sim_path_from_config(&mut guard, &saved);
-    }
-    if let Ok(mut guard) = live_startgg.lock() {
-        sync_live_startgg_from_config(&mut guard, &saved);
-    }
     Ok(saved)
 }
 
 // ── Start.gg live snapshot command ─────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 fn startgg_live_snapshot(
     live_startgg: State<'_, SharedLiveStartgg>,
     force: Option<bool>,
 ) -> StartggLiveSnapshot {
-    let config = load_config_inner().unwrap_or_else(|_| AppConfig::default());
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
     let state = startgg::maybe_refresh_live_startgg(&config, &live_startgg, force.unwrap_or(false));
     let (last_error, last_fetch_ms) = {
         let guard = live_startgg.lock().unwrap_or_else(|e| e.into_inner());
@@ -376,6 +638,9 @@ fn startgg_live_snapshot(
 
 // ── Overlay HTTP server ────────────────────────────────────────────────
 
+/// Where the main overlay server listens.
+pub(crate) const MAIN_OVERLAY_ADDR: &str = "127.0.0.1:17890";
+
 fn resolve_overlay_dirs(app: &tauri::App) -> OverlayDirs {
     let root = if let Some(raw) = env_default("OVERLAY_DIR") {
         resolve_repo_path(&raw)
@@ -403,8 +668,23 @@ fn overlay_router(state: OverlayServerState, static_dir: PathBuf, resources_dir:
 
     Router::new()
         .route("/state.json", get(get_overlay_state_json))
+        .route("/overlay/:setup_id", get(overlay_sse::get_setup_overlay_json))
+        .route("/overlay/:setup_id/events", get(overlay_sse::setup_overlay_events))
+        .route("/assets/*path", get(get_asset_file))
+        .route("/history.json", get(get_overlay_history_json))
+        .route("/schedule.json", get(get_overlay_schedule_json))
+        .route("/fun_facts.json", get(get_overlay_fun_facts_json))
+        .route("/forecast.json", get(get_overlay_forecast_json))
+        .route("/prompts.json", get(crowd_prompts::get_prompts_json))
+        .route("/prompts", post(crowd_prompts::post_prompt))
+        .route("/countdowns.json", get(countdowns::get_countdowns_json))
+        .route("/ws", get(overlay_ws::overlay_socket))
+        .route("/healthz", get(health::get_healthz))
+        .route("/scenes/default", get(scenes::get_default_scene_index))
+        .route("/scenes/default/*path", get(scenes::get_default_scene_file))
         .nest_service("/resources", resource_files)
         .nest_service("/", static_files)
+        .layer(axum::middleware::from_fn(feature_flags::overlay_server_gate))
         .with_state(state)
 }
 
@@ -429,41 +709,197 @@ async fn start_overlay_server(
     }
 }
 
-async fn get_overlay_state_json(AxumState(state): AxumState<OverlayServerState>) -> impl IntoResponse {
+/// JSON body for overlay polling routes, which must never be cached.
+pub(crate) fn json_no_store<T: serde::Serialize>(value: &T) -> Response {
+    (
+        [
+            ("Content-Type", "application/json"),
+            ("Cache-Control", "no-store"),
+        ],
+        serde_json::to_string(value).unwrap_or_else(|_| "{}".to_string()),
+    )
+        .into_response()
+}
+
+async fn get_asset_file(AxumPath(path): AxumPath<String>) -> Response {
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+    match assets::read_pack_file(&config, &path) {
+        Some((bytes, content_type)) => {
+            ([("Content-Type", content_type), ("Cache-Control", "no-cache")], bytes).into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// `?live=1` on the overlay routes skips each setup's stream delay, for the
+/// operator's own preview.
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct OverlayQuery {
+    live: Option<u8>,
+}
+
+impl OverlayQuery {
+    pub(crate) fn live(&self) -> bool {
+        self.live.is_some_and(|live| live != 0)
+    }
+}
+
+async fn get_overlay_history_json(
+    AxumState(state): AxumState<OverlayServerState>,
+    Query(query): Query<OverlayQuery>,
+) -> Response {
+    let delays = state
+        .setup_store
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .setups
+        .iter()
+        .map(|setup| (setup.id, setup.stream_delay_secs))
+        .collect::<HashMap<_, _>>();
+    let now = now_ms();
+    let mut last = state
+        .history
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .last_completed_all();
+    if !query.live() {
+        last.retain(|setup_id, snapshot| {
+            let delay = delays.get(setup_id).copied().unwrap_or(0);
+            stream_delay::revealed(snapshot.completed_at_ms, delay, now)
+        });
+    }
+    json_no_store(&json!({ "setups": last }))
+}
+
+/// Run-of-show timeline for a schedule widget.
+async fn get_overlay_schedule_json(AxumState(state): AxumState<OverlayServerState>) -> Response {
+    match run_of_show::current_timeline(&state.test_state, &state.live_startgg) {
+        Ok(timeline) => json_no_store(&timeline),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+    }
+}
+
+/// Fun facts from the event's archived games, for the break screen.
+async fn get_overlay_fun_facts_json(AxumState(state): AxumState<OverlayServerState>) -> Response {
+    let test_state = state.test_state.clone();
+    let facts = tokio::task::spawn_blocking(move || fun_facts::event_fun_facts(&test_state)).await;
+    match facts {
+        Ok(Ok(facts)) => json_no_store(&json!({ "facts": facts })),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Expected start times and win chances, for "up next" widgets.
+async fn get_overlay_forecast_json(AxumState(state): AxumState<OverlayServerState>) -> Response {
+    let (test_state, live_startgg) = (state.test_state.clone(), state.live_startgg.clone());
+    let forecast = tokio::task::spawn_blocking(move || forecast::current_forecast(&test_state, &live_startgg)).await;
+    match forecast {
+        Ok(Ok((forecast, _))) => json_no_store(&forecast),
+        Ok(Err(err)) => (StatusCode::INTERNAL_SERVER_ERROR, err).into_response(),
+        Err(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response(),
+    }
+}
+
+/// Every setup's overlay state as built from the replays and the bracket,
+/// before mirroring and stream delay, with what it was built from.
+struct BuiltOverlays {
+    payload: AllSetupsState,
+    startgg_state: Option<StartggSimState>,
+    config: AppConfig,
+    setups: Vec<Setup>,
+}
+
+fn build_overlays(state: &OverlayServerState) -> BuiltOverlays {
     let setups = {
         let guard = state.setup_store.lock().unwrap_or_else(|e| e.into_inner());
         guard.setups.clone()
     };
-    let config = load_config_inner().unwrap_or_else(|_| AppConfig::default());
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
 
-    let (startgg_state, active_sets, replay_map) = if config.test_mode {
-        let now = now_ms();
-        let mut guard = state.test_state.lock().unwrap_or_else(|e| e.into_inner());
-        sync_startgg_sim_path_from_config(&mut guard, &config);
-
-        let should_use_startgg = !config.test_bracket_path.trim().is_empty() || guard.startgg_sim.is_some();
-        let startgg_state = if should_use_startgg && init_startgg_sim(&mut guard, now).is_ok() {
-            guard.startgg_sim.as_mut().map(|sim| sim.state(now))
+    let test_flags = config.test_flags();
+    let startgg_state = if test_flags.fake_bracket {
+        let should_use_startgg = {
+            let mut guard = state.test_state.lock().unwrap_or_else(|e| e.into_inner());
+            sync_startgg_sim_path_from_config(&mut guard, &config);
+            !config.test_bracket_path.trim().is_empty() || guard.startgg_sim.is_some()
+        };
+        // Overlays show no bracket while the sim warms up.
+        if should_use_startgg {
+            sim_worker::snapshot_or_warm(&state.test_state).ok().flatten().map(|state| (*state).clone())
         } else {
             None
-        };
-        let active_sets = guard.active_replay_sets.clone();
-        let replay_map = guard.spoof_replays.clone();
-        (startgg_state, Some(active_sets), replay_map)
+        }
+    } else {
+        let mut live_state = startgg::maybe_refresh_live_startgg(&config, &state.live_startgg, false);
+        if live_state.is_none() && config.offline_mode {
+            // Nothing cached from start.gg yet: fall back to a loaded sim.
+            live_state = sim_worker::loaded_state(&state.test_state);
+        }
+        live_state
+    };
+    let (active_sets, replay_map) = if test_flags.fake_replays {
+        let guard = state.test_state.lock().unwrap_or_else(|e| e.into_inner());
+        (Some(guard.active_replay_sets.clone()), guard.spoof_replays.clone())
     } else {
-        let live_state = startgg::maybe_refresh_live_startgg(&config, &state.live_startgg, false);
-        (live_state, None, HashMap::new())
+        (None, HashMap::new())
     };
 
+    let linked_state = linked_bracket::linked_bracket_state(&config, startgg_state.as_ref());
     let mut cache = state.replay_cache.lock().unwrap_or_else(|e| e.into_inner());
-    let payload = build_overlay_state(
+    let mut payload = build_overlay_state(
         &setups,
         startgg_state.as_ref(),
+        linked_state.as_ref(),
         active_sets.as_ref(),
         &config,
         &replay_map,
         &mut cache,
     );
+    player_db::apply_overlay_profiles(&mut payload);
+    commentators::apply_overlay_commentators(&mut payload);
+    replay::apply_ascii_tags(&mut payload);
+    auto_stop::apply_break_screens(&mut payload);
+    score_tracker::apply_tracked_scores(&mut payload);
+    stage_strikes::apply_stage_picks(&mut payload);
+    assets::apply_overlay_assets(&mut payload, &config, &state.resources_dir);
+    if config.show_player_ranks {
+        slippi_rank::apply_overlay_ranks(&mut payload, &state.rank_cache);
+    }
+    if test_flags.fake_connection {
+        connection_sim::apply_simulated_connections(&mut payload, now_ms());
+    }
+    BuiltOverlays { payload, startgg_state, config, setups }
+}
+
+/// Every setup's overlay state, as served on `/state.json`; each setup's
+/// stream delay behind unless `live`.
+pub(crate) fn overlay_payload(state: &OverlayServerState, live: bool) -> AllSetupsState {
+    let BuiltOverlays { mut payload, setups, .. } = build_overlays(state);
+    mirror::apply_mirror_overlays(&mut payload);
+    stream_delay::delay_overlays(&mut payload, &setups, now_ms(), live);
+    payload
+}
+
+/// Build the overlays from the latest replays and bracket, record sets that
+/// just finished and put up their result cards. Driven by
+/// `overlay_history::spawn_history_recorder`, not by overlay requests, so
+/// nothing is missed while no browser source is open.
+pub(crate) fn record_overlay_history(state: &OverlayServerState) {
+    let BuiltOverlays { payload, startgg_state, config, setups } = build_overlays(state);
+    let completed = state
+        .history
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .observe_all(&payload, startgg_state.as_ref(), now_ms());
+    result_card::publish_completed(completed, &config, &setups);
+}
+
+async fn get_overlay_state_json(
+    AxumState(state): AxumState<OverlayServerState>,
+    Query(query): Query<OverlayQuery>,
+) -> impl IntoResponse {
+    let payload = overlay_payload(&state, query.live());
     let body = serde_json::to_string(&payload).unwrap_or_else(|_| "{}".to_string());
     (
         [
@@ -478,6 +914,200 @@ async fn get_overlay_state_json(AxumState(state): AxumState<OverlayServerState>)
 
 // ── Entry point ────────────────────────────────────────────────────────
 
+/// Every command the frontend can invoke. The handler and the TypeScript
+/// bindings are both built from this list.
+pub(crate) fn specta_builder() -> tauri_specta::Builder<tauri::Wry> {
+    tauri_specta::Builder::<tauri::Wry>::new().commands(tauri_specta::collect_commands![
+        list_setups_stub,
+        list_setups,
+        create_setup,
+        delete_setup,
+        pin_player_side,
+        set_side_mapping_policy,
+        set_overlay_refresh_driver,
+        set_setup_bracket,
+        set_setup_auto_stop,
+        set_setup_stream_delay,
+        slippi::find_slippi_launcher_window,
+        slippi::scan_slippi_streams,
+        slippi::refresh_slippi_launcher,
+        slippi::watch_slippi_stream,
+        slippi_login::slippi_login_status,
+        slippi_login::slippi_login_now,
+        slippi_login::set_slippi_login_credentials,
+        slippi_login::clear_slippi_login_credentials,
+        network::network_status,
+        network::set_offline_mode,
+        frame_export::export_set_analysis,
+        ratings::session_ratings,
+        archive::verify_archive,
+        archive::backfill_archive_checksums,
+        startgg_webhook::startgg_webhook_status,
+        audit_log::query_audit_log,
+        frame_pacing::report_playback_fps,
+        frame_pacing::playback_frame_pacing,
+        run_of_show::get_run_of_show,
+        run_of_show::save_run_of_show,
+        run_of_show::set_schedule_item_actual,
+        run_of_show::run_of_show_timeline,
+        player_db::list_player_profiles,
+        player_db::save_player_profile,
+        commentators::list_commentators,
+        commentators::set_commentators,
+        capabilities::capabilities,
+        overlay_refresh::force_overlay_refresh,
+        capture_windows::arrange_capture_windows,
+        session_stats::session_summary,
+        connect_codes::replay_connect_codes,
+        exhibition::list_exhibitions,
+        exhibition::create_exhibition,
+        exhibition::assign_exhibition_to_setup,
+        exhibition::report_exhibition_game,
+        stage_strikes::stage_strikes,
+        stage_strikes::record_stage_strike,
+        stage_strikes::undo_stage_strike,
+        stage_strikes::clear_stage_strikes,
+        friendlies::game_classifications,
+        friendlies::override_game_classification,
+        score_tracker::get_setup_score,
+        score_tracker::reset_setup_score,
+        linked_bracket::bracket_link,
+        linked_bracket::set_bracket_link,
+        linked_bracket::feed_linked_bracket,
+        linked_bracket::bracket_link_status,
+        linked_bracket::linked_bracket,
+        venue_time::venue_timezone,
+        idle_mode::subsystem_status,
+        idle_mode::set_idle_mode,
+        mirror::mirror_links,
+        mirror::set_mirror_setup,
+        mirror::clear_mirror_setup,
+        reseed::reseed_preview,
+        reseed::reseed_swap,
+        reseed::reseed_undo,
+        reseed::reseed_report,
+        entrant_audit::audit_entrants,
+        reseed::apply_sim_reseed,
+        history_import::import_event_history,
+        jobs::list_jobs,
+        jobs::cancel_job,
+        fun_facts::fun_facts,
+        dolphin::launch_dolphin_for_setup,
+        slippi::assign_stream_to_setup,
+        watch_party::assign_watch_party,
+        capture_sources::list_capture_sources,
+        capture_sources::detect_capture_sources,
+        capture_sources::save_capture_source,
+        capture_sources::remove_capture_source,
+        capture_sources::assign_capture_source,
+        set_codes::set_codes,
+        set_codes::quick_assign,
+        ready_check::set_ready_status,
+        crowd_prompts::push_crowd_prompt,
+        crowd_prompts::dismiss_crowd_prompt,
+        crowd_prompts::crowd_prompts,
+        forecast::forecast,
+        bindings::generate_bindings,
+        checklist::get_checklist,
+        checklist::save_checklist,
+        checklist::run_checklist,
+        checklist::set_checklist_item_done,
+        feature_flags::feature_flags,
+        feature_flags::set_feature_flag,
+        setup_meta::rename_setup,
+        setup_meta::update_setup_metadata,
+        countdowns::list_countdowns,
+        countdowns::create_countdown,
+        countdowns::update_countdown,
+        countdowns::cancel_countdown,
+        countdowns::start_dq_timer,
+        remote_api::remote_api_status,
+        remote_api::create_remote_token,
+        remote_api::revoke_remote_token,
+        slippi::clear_setup_assignment,
+        slippi::launch_slippi_app,
+        slippi::relaunch_slippi_app,
+        dolphin::launch_dolphin_cli,
+        test_mode::spoof_live_games,
+        test_mode::spoof_bracket_set_replays,
+        test_mode::spoof_bracket_set_replay,
+        test_mode::cancel_spoof_bracket_set_replays,
+        list_bracket_configs,
+        list_bracket_replay_sets,
+        list_bracket_set_replay_paths,
+        update_bracket_set_replays,
+        list_bracket_replay_pairs,
+        bracket_config::validate_bracket_config,
+        bracket_config::migrate_bracket_config,
+        startgg_sim_commands::startgg_sim_state,
+        startgg_sim_commands::startgg_sim_reset,
+        startgg_sim_commands::startgg_sim_advance_set,
+        startgg_sim_commands::startgg_sim_force_winner,
+        startgg_sim_commands::startgg_sim_mark_dq,
+        startgg_sim_commands::startgg_sim_raw_state,
+        startgg_sim_commands::startgg_sim_raw_reset,
+        startgg_sim_commands::startgg_sim_raw_advance_set,
+        startgg_sim_commands::startgg_sim_raw_start_set,
+        startgg_sim_commands::startgg_sim_raw_update_scores,
+        startgg_sim_commands::startgg_sim_raw_apply_replay_result,
+        startgg_sim_commands::startgg_sim_raw_step_set,
+        startgg_sim_commands::startgg_sim_raw_finalize_reference_set,
+        startgg_sim_commands::startgg_sim_raw_finish_set,
+        startgg_sim_commands::startgg_sim_raw_complete_bracket,
+        startgg_sim_commands::startgg_sim_raw_force_winner,
+        startgg_sim_commands::startgg_sim_raw_mark_dq,
+        startgg_sim_commands::startgg_sim_raw_reset_set,
+        startgg_sim_commands::startgg_sim_clear_persisted_state,
+        startgg_sim_commands::startgg_sim_persistence_status,
+        startgg_sim_commands::pending_reports,
+        startgg_sim_commands::release_set_report_lock,
+        startgg_sim_commands::set_report_history,
+        startgg_sim_commands::reconcile_set,
+        startgg_report::startgg_result_proposals,
+        startgg_report::report_startgg_set_result,
+        assets::validate_asset_pack,
+        slippi_rank::get_player_rank,
+        overlay_history::last_completed_set,
+        overlay_history::setup_set_history,
+        result_card::replay_result_card,
+        replay_upload::replay_uploads,
+        replay_upload::upload_set_replays,
+        obs::obs_connect,
+        obs::obs_list_scenes,
+        obs::obs_switch_scene,
+        obs::obs_bind_setup_source,
+        startup::orchestrate_startup,
+        shutdown::orchestrate_shutdown,
+        event_templates::list_event_templates,
+        event_templates::save_event_template,
+        event_templates::delete_event_template,
+        event_templates::current_event,
+        event_templates::start_new_event,
+        playback::playback_session,
+        playback::playback_load_replay,
+        playback::playback_seek,
+        playback::playback_set_queue,
+        playback::playback_enqueue,
+        playback::prepare_set_playback,
+        playback::playback_load_set_game,
+        realtime_playback::playback_follow_replay,
+        realtime_playback::playback_buffer_status,
+        test_mode::set_broadcast_players,
+        startgg_live_snapshot,
+        load_config,
+        save_config,
+        entrant_commands::get_unified_entrants,
+        entrant_commands::set_entrant_slippi_code,
+        entrant_commands::assign_entrant_to_setup,
+        entrant_commands::unassign_entrant,
+        entrant_commands::toggle_auto_assignment,
+        entrant_commands::get_setups_sorted_by_seed,
+        entrant_commands::get_auto_assignment_status,
+        entrant_commands::run_auto_assignment,
+        entrant_commands::sync_entrants_from_startgg
+    ])
+}
+
 pub fn run() {
     load_env_file();
 
@@ -496,13 +1126,23 @@ pub fn run() {
         .init();
     info!("Melee Stream Tool starting");
     log_env_warnings();
+    assets::log_asset_pack_warnings();
+    playback::prune_stale_playback_configs();
 
-    let setup_store: SharedSetupStore = Arc::new(Mutex::new(SetupStore::bootstrap_from_existing()));
+    let mut setups = SetupStore::bootstrap_from_existing();
+    if let Some(saved) = setup_meta::load_saved_setups() {
+        setups.setups = saved;
+    }
+    let setup_store: SharedSetupStore = Arc::new(Mutex::new(setups));
     let test_state: SharedTestState = Arc::new(Mutex::new(TestModeState::default()));
     let live_startgg: SharedLiveStartgg = Arc::new(Mutex::new(LiveStartggState::default()));
     let replay_cache: SharedOverlayCache = Arc::new(Mutex::new(OverlayReplayCache::default()));
     let entrant_manager: SharedEntrantManager = Arc::new(Mutex::new(EntrantManager::new()));
+    let rank_cache: SharedRankCache = Arc::new(Mutex::new(slippi_rank::RankCache::default()));
+    let overlay_history: SharedOverlayHistory =
+        Arc::new(Mutex::new(overlay_history::OverlayHistory::new()));
     startgg::spawn_startgg_polling(live_startgg.clone(), Some(entrant_manager.clone()));
+    sim_worker::spawn_sim_advance_loop(test_state.clone());
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
@@ -511,7 +1151,29 @@ pub fn run() {
         .manage(live_startgg.clone())
         .manage(replay_cache.clone())
         .manage(entrant_manager.clone())
+        .manage(rank_cache.clone())
+        .manage(overlay_history.clone())
         .setup(move |app| {
+            network::spawn_network_monitor(app.handle().clone());
+            run_of_show::spawn_run_of_show_monitor(app.handle().clone());
+            session_stats::spawn_session_stats_monitor(app.handle().clone());
+            friendlies::spawn_friendlies_monitor(app.handle().clone());
+            score_tracker::spawn_score_tracker(app.handle().clone());
+            idle_mode::spawn_idle_monitor(app.handle().clone());
+            auto_stop::spawn_auto_stop_monitor(app.handle().clone());
+            mirror::spawn_mirror_monitor(app.handle().clone());
+            slippi_ws::spawn_native_spectate_monitor(app.handle().clone());
+            launcher_watchdog::spawn_launcher_watchdog(app.handle().clone());
+            process_health::spawn_process_health_monitor(app.handle().clone());
+            obs::spawn_obs_scene_monitor(app.handle().clone());
+            auto_assign::spawn_auto_assign_monitor(app.handle().clone());
+            startgg_report::spawn_startgg_report_monitor(app.handle().clone());
+            replay_upload::spawn_replay_upload_monitor(app.handle().clone());
+            forecast::spawn_forecast_monitor(app.handle().clone());
+            broadcast_watch::spawn_broadcast_monitor(app.handle().clone());
+            countdowns::spawn_countdown_ticker();
+            capabilities::log_startup_capabilities(app.handle());
+
             let overlay_dirs = resolve_overlay_dirs(app);
             let OverlayDirs { root, resources, upcoming, dual, quad } = overlay_dirs;
 
@@ -526,13 +1188,16 @@ pub fn run() {
                 test_state: test_state.clone(),
                 live_startgg: live_startgg.clone(),
                 replay_cache: replay_cache.clone(),
+                resources_dir: resources.clone(),
+                rank_cache: rank_cache.clone(),
+                history: overlay_history.clone(),
             };
 
             tauri::async_runtime::spawn(start_overlay_server(
                 overlay_state.clone(),
                 root,
                 resources.clone(),
-                "127.0.0.1:17890",
+                MAIN_OVERLAY_ADDR,
                 "Main",
             ));
 
@@ -552,6 +1217,26 @@ pub fn run() {
                 "Dual",
             ));
 
+            let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+            if config.startgg_webhook_enabled {
+                tauri::async_runtime::spawn(startgg_webhook::start_webhook_server(
+                    config.startgg_webhook_bind.clone(),
+                ));
+            }
+            if config.remote_api_enabled {
+                tauri::async_runtime::spawn(remote_api::start_remote_api(
+                    app.handle().clone(),
+                    config.remote_api_bind.clone(),
+                ));
+            }
+
+            overlay_history::spawn_history_recorder(overlay_state.clone());
+            slippi_rank::spawn_rank_refresher(rank_cache.clone());
+            tauri::async_runtime::spawn(overlay_ws::push_setup_states(overlay_state.clone()));
+            if config.overlay_ws_port != 0 {
+                tauri::async_runtime::spawn(overlay_ws::start_socket_server(config.overlay_ws_port));
+            }
+
             tauri::async_runtime::spawn(start_overlay_server(
                 overlay_state,
                 quad,
@@ -562,64 +1247,7 @@ pub fn run() {
 
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            list_setups_stub,
-            list_setups,
-            create_setup,
-            delete_setup,
-            slippi::find_slippi_launcher_window,
-            slippi::scan_slippi_streams,
-            slippi::refresh_slippi_launcher,
-            slippi::watch_slippi_stream,
-            dolphin::launch_dolphin_for_setup,
-            slippi::assign_stream_to_setup,
-            slippi::clear_setup_assignment,
-            slippi::launch_slippi_app,
-            slippi::relaunch_slippi_app,
-            dolphin::launch_dolphin_cli,
-            test_mode::spoof_live_games,
-            test_mode::spoof_bracket_set_replays,
-            test_mode::spoof_bracket_set_replay,
-            test_mode::cancel_spoof_bracket_set_replays,
-            list_bracket_configs,
-            list_bracket_replay_sets,
-            list_bracket_set_replay_paths,
-            update_bracket_set_replays,
-            list_bracket_replay_pairs,
-            startgg_sim_commands::startgg_sim_state,
-            startgg_sim_commands::startgg_sim_reset,
-            startgg_sim_commands::startgg_sim_advance_set,
-            startgg_sim_commands::startgg_sim_force_winner,
-            startgg_sim_commands::startgg_sim_mark_dq,
-            startgg_sim_commands::startgg_sim_raw_state,
-            startgg_sim_commands::startgg_sim_raw_reset,
-            startgg_sim_commands::startgg_sim_raw_advance_set,
-            startgg_sim_commands::startgg_sim_raw_start_set,
-            startgg_sim_commands::startgg_sim_raw_update_scores,
-            startgg_sim_commands::startgg_sim_raw_apply_replay_result,
-            startgg_sim_commands::startgg_sim_raw_step_set,
-            startgg_sim_commands::startgg_sim_raw_finalize_reference_set,
-            startgg_sim_commands::startgg_sim_raw_finish_set,
-            startgg_sim_commands::startgg_sim_raw_complete_bracket,
-            startgg_sim_commands::startgg_sim_raw_force_winner,
-            startgg_sim_commands::startgg_sim_raw_mark_dq,
-            startgg_sim_commands::startgg_sim_raw_reset_set,
-            startgg_sim_commands::startgg_sim_clear_persisted_state,
-            startgg_sim_commands::startgg_sim_persistence_status,
-            test_mode::set_broadcast_players,
-            startgg_live_snapshot,
-            load_config,
-            save_config,
-            entrant_commands::get_unified_entrants,
-            entrant_commands::set_entrant_slippi_code,
-            entrant_commands::assign_entrant_to_setup,
-            entrant_commands::unassign_entrant,
-            entrant_commands::toggle_auto_assignment,
-            entrant_commands::get_setups_sorted_by_seed,
-            entrant_commands::get_auto_assignment_status,
-            entrant_commands::run_auto_assignment,
-            entrant_commands::sync_entrants_from_startgg
-        ])
+        .invoke_handler(specta_builder().invoke_handler())
         .run(tauri::generate_context!())
         .expect("error while running tauri app");
 }
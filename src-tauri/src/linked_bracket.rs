@@ -0,0 +1,444 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg::{fetch_live_startgg_state, load_startgg_sim_config_from};
+use crate::startgg_sim::{StartggSim, StartggSimEntrantConfig, StartggSimEventConfig, StartggSimState};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use tauri::State;
+
+/// The linked event is fetched from start.gg at most this often.
+const LIVE_REFRESH_MS: u64 = 30_000;
+
+/// Loaded from `bracket_link.json` on first use.
+static LINKED: Mutex<Option<LinkedBracket>> = Mutex::new(None);
+
+/// A second bracket (usually an amateur bracket) fed by players knocked out
+/// of the main one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BracketLink {
+    /// Sim config of the linked bracket, used in test mode
+    pub sim_config_path: Option<String>,
+    /// start.gg event slug of the linked bracket, used live
+    pub event_slug: Option<String>,
+    /// Phase of the main event whose eliminated players feed the linked
+    /// bracket; the whole event when unset
+    pub feed_phase_id: Option<String>,
+    /// When the linked sim was seeded; it is only fed once
+    pub fed_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct BracketLinkStatus {
+    pub link: Option<BracketLink>,
+    pub linked_event: Option<StartggSimEventConfig>,
+    /// Players out of the main bracket so far, best seed first
+    pub eliminated: Vec<StartggSimEntrantConfig>,
+    /// Eliminated players not yet in the linked bracket. Live events have
+    /// to be fed on start.gg; these are the players still to add there.
+    pub pending_entrants: Vec<StartggSimEntrantConfig>,
+    /// Every set of the feed phase is done
+    pub feed_ready: bool,
+}
+
+#[derive(Default)]
+struct LinkedBracket {
+    link: Option<BracketLink>,
+    sim: Option<StartggSim>,
+    live: Option<StartggSimState>,
+    live_fetched_ms: Option<u64>,
+}
+
+fn link_path() -> PathBuf {
+    repo_root().join("bracket_link.json")
+}
+
+fn load_link() -> Option<BracketLink> {
+    let path = link_path();
+    let data = fs::read_to_string(&path).ok()?;
+    serde_json::from_str(&data)
+        .map_err(|err| tracing::warn!("Ignoring unreadable {}: {}", path.display(), err))
+        .ok()
+}
+
+fn save_link(link: Option<&BracketLink>) -> Result<(), String> {
+    let path = link_path();
+    match link {
+        Some(link) => {
+            let body = serde_json::to_vec_pretty(link).map_err(|e| format!("serialize bracket link: {e}"))?;
+            write_file_atomic(&path, &body).map_err(|e| format!("write {}: {e}", path.display()))
+        }
+        None if path.exists() => fs::remove_file(&path).map_err(|e| format!("remove {}: {e}", path.display())),
+        None => Ok(()),
+    }
+}
+
+fn in_feed_phase(set_phase: &str, phase_id: Option<&str>) -> bool {
+    match phase_id {
+        Some(phase_id) => set_phase == phase_id,
+        None => true,
+    }
+}
+
+/// Players who lost a completed set in the feed phase and have nothing
+/// left to play anywhere in the event, best seed first.
+pub fn eliminated_entrants(state: &StartggSimState, phase_id: Option<&str>) -> Vec<StartggSimEntrantConfig> {
+    let still_playing = state
+        .sets
+        .iter()
+        .filter(|set| set.state == "pending" || set.state == "inProgress")
+        .flat_map(|set| set.slots.iter().filter_map(|slot| slot.entrant_id))
+        .collect::<HashSet<_>>();
+    let losers = state
+        .sets
+        .iter()
+        .filter(|set| set.state == "completed" && in_feed_phase(&set.phase_id, phase_id))
+        .filter_map(|set| Some((set, set.winner_id?)))
+        .flat_map(|(set, winner)| set.slots.iter().filter_map(move |slot| slot.entrant_id.filter(|id| *id != winner)))
+        .filter(|id| !still_playing.contains(id))
+        .collect::<HashSet<_>>();
+    let mut out = state
+        .entrants
+        .iter()
+        .filter(|entrant| losers.contains(&entrant.id))
+        .map(|entrant| StartggSimEntrantConfig {
+            id: entrant.id,
+            name: entrant.name.clone(),
+            slippi_code: entrant.slippi_code.clone(),
+            seed: Some(entrant.seed),
+        })
+        .collect::<Vec<_>>();
+    out.sort_by_key(|entrant| (entrant.seed.unwrap_or(u32::MAX), entrant.id));
+    out
+}
+
+/// Whether every set of the feed phase has been played out.
+pub fn feed_ready(state: &StartggSimState, phase_id: Option<&str>) -> bool {
+    let mut sets = state.sets.iter().filter(|set| in_feed_phase(&set.phase_id, phase_id)).peekable();
+    sets.peek().is_some() && sets.all(|set| set.state == "completed" || set.state == "skipped")
+}
+
+/// Main and linked sets in one state, for setups that show either bracket.
+pub fn merge_states(main: Option<&StartggSimState>, linked: Option<&StartggSimState>) -> Option<StartggSimState> {
+    let mut merged = main.or(linked)?.clone();
+    if let (Some(_), Some(linked)) = (main, linked) {
+        merged.sets.extend(linked.sets.iter().cloned());
+        merged.entrants.extend(linked.entrants.iter().cloned());
+        merged.version = merged.version.wrapping_add(linked.version);
+    }
+    Some(merged)
+}
+
+fn sim_config_path(link: &BracketLink) -> Result<PathBuf, String> {
+    link.sim_config_path
+        .as_deref()
+        .filter(|path| !path.trim().is_empty())
+        .map(resolve_startgg_sim_config_path)
+        .ok_or_else(|| "The bracket link has no sim config for the linked bracket.".to_string())
+}
+
+/// Seed the linked sim with `entrants` in order, replacing whatever its
+/// config listed, and drop its old saved progress.
+fn seed_linked_sim(path: &Path, entrants: &[StartggSimEntrantConfig], now: u64) -> Result<StartggSim, String> {
+    let mut config = load_startgg_sim_config_from(path)?;
+    config.entrants = entrants
+        .iter()
+        .enumerate()
+        .map(|(idx, entrant)| StartggSimEntrantConfig { seed: Some(idx as u32 + 1), ..entrant.clone() })
+        .collect();
+    let body = serde_json::to_vec_pretty(&config).map_err(|e| format!("serialize linked bracket: {e}"))?;
+    write_file_atomic(path, &body).map_err(|e| format!("write {}: {e}", path.display()))?;
+    StartggSim::delete_state_file(path)?;
+    StartggSim::new(config, now)
+}
+
+impl LinkedBracket {
+    fn load() -> Self {
+        LinkedBracket { link: load_link(), ..LinkedBracket::default() }
+    }
+
+    fn feed(&mut self, main: &StartggSimState, now: u64) -> Result<usize, String> {
+        let Some(link) = self.link.as_mut() else {
+            return Err("No linked bracket is set up.".to_string());
+        };
+        let entrants = eliminated_entrants(main, link.feed_phase_id.as_deref());
+        if entrants.len() < 2 {
+            return Err(format!("Only {} player(s) are out of the main bracket so far.", entrants.len()));
+        }
+        let sim = seed_linked_sim(&sim_config_path(link)?, &entrants, now)?;
+        link.fed_at_ms = Some(now);
+        save_link(Some(link))?;
+        self.sim = Some(sim);
+        tracing::info!("Fed {} player(s) into the linked bracket", entrants.len());
+        Ok(entrants.len())
+    }
+
+    /// The linked sim's state once it has been fed.
+    fn sim_state(&mut self, now: u64) -> Option<StartggSimState> {
+        let link = self.link.as_ref()?;
+        link.fed_at_ms?;
+        if self.sim.is_none() {
+            let path = sim_config_path(link).ok()?;
+            let loaded = load_startgg_sim_config_from(&path).and_then(|config| StartggSim::new(config, now));
+            match loaded {
+                Ok(mut sim) => {
+                    if let Err(err) = sim.load_state(&path) {
+                        tracing::warn!("Failed to load linked bracket state: {}", err);
+                    }
+                    self.sim = Some(sim);
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to load linked bracket: {}", err);
+                    return None;
+                }
+            }
+        }
+        let path = sim_config_path(link).ok();
+        let sim = self.sim.as_mut()?;
+        let state = sim.state(now);
+        if let Some(path) = path {
+            if let Err(err) = sim.save_state(&path) {
+                tracing::warn!("Failed to save linked bracket state: {}", err);
+            }
+        }
+        Some(state)
+    }
+}
+
+fn with_linked<T>(f: impl FnOnce(&mut LinkedBracket) -> T) -> T {
+    let mut guard = LINKED.lock().unwrap_or_else(|e| e.into_inner());
+    f(guard.get_or_insert_with(LinkedBracket::load))
+}
+
+/// The live linked event, refetched every 30s. The fetch happens without
+/// the lock held so overlay polls aren't stuck behind start.gg.
+fn live_linked_state(config: &AppConfig, slug: &str, now: u64) -> Option<StartggSimState> {
+    let (cached, fetched_ms) = with_linked(|linked| (linked.live.clone(), linked.live_fetched_ms));
+    let fresh = fetched_ms.is_some_and(|at| now.saturating_sub(at) < LIVE_REFRESH_MS);
+    if fresh || config.offline_mode {
+        return cached;
+    }
+    // Mark the attempt first so a slow or failing fetch isn't retried by
+    // every poll in the meantime.
+    with_linked(|linked| linked.live_fetched_ms = Some(now));
+    match fetch_live_startgg_state(config, slug) {
+        Ok(state) => {
+            with_linked(|linked| linked.live = Some(state.clone()));
+            Some(state)
+        }
+        Err(err) => {
+            tracing::warn!("Failed to fetch linked event {}: {}", slug, err);
+            cached
+        }
+    }
+}
+
+/// The linked bracket's state, feeding the linked sim first if the main
+/// bracket's feed phase has just finished. None without a link.
+pub fn linked_bracket_state(config: &AppConfig, main: Option<&StartggSimState>) -> Option<StartggSimState> {
+    let now = now_ms();
+    let link = with_linked(|linked| linked.link.clone())?;
+    if !config.test_flags().fake_bracket {
+        let slug = link.event_slug.as_deref().map(str::trim).filter(|slug| !slug.is_empty())?;
+        return live_linked_state(config, slug, now);
+    }
+    with_linked(|linked| {
+        let ready = main.filter(|main| feed_ready(main, link.feed_phase_id.as_deref()));
+        if let (None, Some(main)) = (link.fed_at_ms, ready) {
+            if let Err(err) = linked.feed(main, now) {
+                tracing::warn!("Could not feed the linked bracket: {}", err);
+            }
+        }
+        linked.sim_state(now)
+    })
+}
+
+fn link_status(config: &AppConfig, main: Option<&StartggSimState>) -> BracketLinkStatus {
+    let linked_state = linked_bracket_state(config, main);
+    let link = with_linked(|linked| linked.link.clone());
+    let phase_id = link.as_ref().and_then(|link| link.feed_phase_id.clone());
+    let eliminated = main
+        .map(|main| eliminated_entrants(main, phase_id.as_deref()))
+        .unwrap_or_default();
+    let registered = linked_state
+        .iter()
+        .flat_map(|state| state.entrants.iter())
+        .map(|entrant| player_key(&entrant.name, Some(&entrant.slippi_code)))
+        .collect::<HashSet<_>>();
+    let pending_entrants = eliminated
+        .iter()
+        .filter(|entrant| !registered.contains(&player_key(&entrant.name, Some(&entrant.slippi_code))))
+        .cloned()
+        .collect();
+    BracketLinkStatus {
+        link,
+        linked_event: linked_state.map(|state| state.event),
+        eliminated,
+        pending_entrants,
+        feed_ready: main.is_some_and(|main| feed_ready(main, phase_id.as_deref())),
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn bracket_link() -> Option<BracketLink> {
+    with_linked(|linked| linked.link.clone())
+}
+
+/// Link a second bracket to the main one, or unlink with `None`. Changing
+/// the link starts the linked bracket over.
+#[tauri::command]
+#[specta::specta]
+pub fn set_bracket_link(link: Option<BracketLink>) -> Result<Option<BracketLink>, String> {
+    audited("setBracketLink", json!({ "link": link }), || {
+        let link = link.map(|link| BracketLink { fed_at_ms: None, ..link });
+        save_link(link.as_ref())?;
+        with_linked(|linked| {
+            *linked = LinkedBracket { link: link.clone(), ..LinkedBracket::default() };
+        });
+        Ok(link)
+    })
+}
+
+/// Feed whoever is out of the main bracket into the linked sim now,
+/// without waiting for the feed phase to finish.
+#[tauri::command]
+#[specta::specta]
+pub fn feed_linked_bracket(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<usize, String> {
+    audited("feedLinkedBracket", json!({}), || {
+        if !test_flags().fake_bracket {
+            return Err("Live linked events are fed on start.gg; see the link status for who to add.".to_string());
+        }
+        let main = current_bracket_state(&test_state, &live_startgg)
+            .ok_or_else(|| "The main bracket isn't loaded.".to_string())?;
+        with_linked(|linked| linked.feed(&main, now_ms()))
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn bracket_link_status(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<BracketLinkStatus, String> {
+    let config = cached_config()?;
+    let main = current_bracket_state(&test_state, &live_startgg);
+    Ok(link_status(&config, main.as_ref()))
+}
+
+/// The linked bracket for the queue, like the main bracket's state.
+#[tauri::command]
+#[specta::specta]
+pub fn linked_bracket(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<Option<StartggSimState>, String> {
+    let config = cached_config()?;
+    let main = current_bracket_state(&test_state, &live_startgg);
+    Ok(linked_bracket_state(&config, main.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::{StartggSimEntrant, StartggSimSet, StartggSimSlot};
+
+    fn set(id: u64, phase: &str, state: &str, entrants: [u32; 2], winner: Option<u32>) -> StartggSimSet {
+        let slot = |entrant_id| StartggSimSlot { entrant_id: Some(entrant_id), ..Default::default() };
+        StartggSimSet {
+            id,
+            phase_id: phase.to_string(),
+            phase_name: phase.to_string(),
+            round: 1,
+            round_label: "Round 1".to_string(),
+            best_of: 3,
+            state: state.to_string(),
+            winner_id: winner,
+            slots: vec![slot(entrants[0]), slot(entrants[1])],
+            ..Default::default()
+        }
+    }
+
+    fn main_event() -> StartggSimState {
+        let entrant = |id: u32| StartggSimEntrant {
+            id,
+            name: format!("P{id}"),
+            seed: id,
+            slippi_code: format!("P#{id}"),
+        };
+        StartggSimState {
+            event: StartggSimEventConfig { id: "e".to_string(), name: "Main".to_string(), slug: "main".to_string() },
+            phases: Vec::new(),
+            entrants: (1..=4).map(entrant).collect(),
+            sets: vec![
+                set(1, "pools", "completed", [1, 4], Some(1)),
+                set(2, "pools", "completed", [2, 3], Some(2)),
+                set(3, "pools", "pending", [4, 3], None),
+                set(4, "top", "pending", [1, 2], None),
+            ],
+            started_at_ms: 0,
+            now_ms: 0,
+            reference_tournament_link: None,
+            version: 1,
+            not_modified: false,
+            warming: false,
+        }
+    }
+
+    fn eliminated_names(state: &StartggSimState) -> Vec<String> {
+        eliminated_entrants(state, Some("pools")).into_iter().map(|e| e.name).collect()
+    }
+
+    #[test]
+    fn players_with_a_set_left_are_not_fed() {
+        // 3 and 4 each lost once but still have the losers set to play.
+        let state = main_event();
+        assert!(eliminated_names(&state).is_empty());
+        assert!(!feed_ready(&state, Some("pools")));
+    }
+
+    #[test]
+    fn eliminated_players_are_fed_best_seed_first() {
+        let mut state = main_event();
+        state.sets[2] = set(3, "pools", "completed", [4, 3], Some(4));
+        assert_eq!(eliminated_names(&state), vec!["P3", "P4"]);
+        assert!(eliminated_entrants(&state, Some("top")).is_empty());
+    }
+
+    #[test]
+    fn the_feed_waits_for_every_set_of_its_phase() {
+        let mut state = main_event();
+        state.sets[2] = set(3, "pools", "skipped", [4, 3], None);
+        assert!(feed_ready(&state, Some("pools")));
+        assert!(!feed_ready(&state, None));
+        assert!(!feed_ready(&state, Some("swiss")));
+    }
+
+    #[test]
+    fn merged_states_carry_both_brackets() {
+        let main = main_event();
+        let mut linked = main_event();
+        linked.sets.truncate(1);
+        linked.version = 2;
+        let merged = merge_states(Some(&main), Some(&linked)).unwrap();
+        assert_eq!(merged.sets.len(), 5);
+        assert_eq!(merged.version, 3);
+        assert_eq!(merge_states(None, Some(&linked)).unwrap().sets.len(), 1);
+        assert!(merge_states(None, None).is_none());
+    }
+}
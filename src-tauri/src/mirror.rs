@@ -0,0 +1,319 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::dolphin::launch_dolphin_playback_for_setup_internal;
+use crate::playback::{with_playback_session, PlaybackCommand};
+use crate::realtime_playback::start_follow;
+use crate::replay::latest_replay_for_code;
+use crate::slippi::clear_assignment;
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(1);
+
+static LINKS: Mutex<Vec<MirrorLink>> = Mutex::new(Vec::new());
+
+/// A setup that shows whatever another setup shows, e.g. a clean feed of
+/// the main stage. The mirror runs its own playback Dolphin fed from the
+/// primary's replay and has no stream of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorLink {
+    pub primary_id: u32,
+    pub mirror_id: u32,
+    pub created_at_ms: u64,
+}
+
+/// Payload of `mirror-stopped`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct MirrorStopped {
+    pub primary_id: u32,
+    pub mirror_id: u32,
+    pub reason: String,
+}
+
+/// What the mirror should play to match its primary.
+enum MirrorSource {
+    /// The primary runs replay playback: play the same command.
+    Playback(PlaybackCommand),
+    /// The primary spectates through the launcher: follow the replay
+    /// the launcher is writing.
+    Spectate(PathBuf),
+}
+
+impl MirrorSource {
+    /// A replay to start the mirror's Dolphin with.
+    fn first_replay(&self) -> Option<PathBuf> {
+        match self {
+            MirrorSource::Playback(command) => command
+                .replay
+                .clone()
+                .or_else(|| command.queue.first().map(|entry| entry.path.clone()))
+                .map(PathBuf::from),
+            MirrorSource::Spectate(path) => Some(path.clone()),
+        }
+    }
+}
+
+/// Mirrors can't be chained or shared: a primary isn't itself a mirror,
+/// and a mirror isn't anyone's primary.
+fn check_link(links: &[MirrorLink], primary_id: u32, mirror_id: u32) -> Result<(), String> {
+    if primary_id == mirror_id {
+        return Err("A setup can't mirror itself.".to_string());
+    }
+    if links.iter().any(|link| link.mirror_id == primary_id) {
+        return Err(format!("Setup {primary_id} is already a mirror."));
+    }
+    if links.iter().any(|link| link.primary_id == mirror_id) {
+        return Err(format!("Setup {mirror_id} is mirrored by another setup."));
+    }
+    Ok(())
+}
+
+/// Stop mirroring onto `mirror_id`, e.g. because it was given a stream of
+/// its own or deleted. Leaves its Dolphin to the caller.
+pub fn end_mirror(mirror_id: u32) -> Option<MirrorLink> {
+    let mut links = LINKS.lock().unwrap_or_else(|e| e.into_inner());
+    let idx = links.iter().position(|link| link.mirror_id == mirror_id)?;
+    Some(links.remove(idx))
+}
+
+/// Show each primary's overlay on its mirror too. Runs after overlay
+/// history has seen the payload, so a set isn't recorded twice.
+pub fn apply_mirror_overlays(state: &mut AllSetupsState) {
+    let links = LINKS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    for link in links {
+        let (Some(primary), Some(mirror)) = (
+            (link.primary_id as usize).checked_sub(1),
+            (link.mirror_id as usize).checked_sub(1),
+        ) else {
+            continue;
+        };
+        if let Some(overlay) = state.setups.get(primary).cloned() {
+            if let Some(slot) = state.setups.get_mut(mirror) {
+                *slot = overlay;
+            }
+        }
+    }
+}
+
+fn stop_mirror(app: &AppHandle, link: MirrorLink, reason: &str) {
+    end_mirror(link.mirror_id);
+    let store = app.state::<SharedSetupStore>().inner().clone();
+    if let Err(err) = clear_assignment(link.mirror_id, Some(true), &store) {
+        tracing::warn!("Mirror setup {}: {}", link.mirror_id, err);
+    }
+    tracing::info!("Setup {} stopped mirroring setup {}: {}", link.mirror_id, link.primary_id, reason);
+    let event = MirrorStopped {
+        primary_id: link.primary_id,
+        mirror_id: link.mirror_id,
+        reason: reason.to_string(),
+    };
+    let _ = app.emit("mirror-stopped", &event);
+}
+
+/// Bring one mirror in line with its primary. `following` is the replay
+/// each mirror's follower was last started on.
+fn sync_mirror(app: &AppHandle, link: MirrorLink, following: &mut HashMap<u32, PathBuf>) -> Result<(), String> {
+    let store = app.state::<SharedSetupStore>();
+    let (primary_stream, primary_command, mirror_alive) = {
+        let mut guard = store.lock().map_err(|e| e.to_string())?;
+        let Some(primary) = guard.setups.iter().find(|setup| setup.id == link.primary_id) else {
+            drop(guard);
+            stop_mirror(app, link, "primary setup was deleted");
+            return Ok(());
+        };
+        let stream = primary.assigned_stream.clone();
+        let command = guard
+            .playback_sessions
+            .get(&link.primary_id)
+            .and_then(|session| session.command.clone());
+        let alive = guard
+            .processes
+            .get_mut(&link.mirror_id)
+            .is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+        (stream, command, alive)
+    };
+
+    // Nothing on the primary: tear the mirror's Dolphin down with it.
+    let Some(stream) = primary_stream else {
+        if mirror_alive {
+            let inner = store.inner().clone();
+            clear_assignment(link.mirror_id, Some(true), &inner)?;
+        }
+        following.remove(&link.mirror_id);
+        return Ok(());
+    };
+    let source = match primary_command {
+        Some(command) => MirrorSource::Playback(command),
+        None => {
+            let cache = app.state::<SharedOverlayCache>();
+            let cache = cache.lock().map_err(|e| e.to_string())?;
+            let latest = stream.p1_code.as_deref().and_then(|code| latest_replay_for_code(&cache, code));
+            match latest {
+                Some(path) => MirrorSource::Spectate(path),
+                // No game on disk yet.
+                None => return Ok(()),
+            }
+        }
+    };
+
+    if !mirror_alive {
+        let Some(replay) = source.first_replay() else {
+            return Ok(());
+        };
+        let (child, session) = launch_dolphin_playback_for_setup_internal(link.mirror_id, &replay)?;
+        let mut guard = store.lock().map_err(|e| e.to_string())?;
+        guard.processes.insert(link.mirror_id, child);
+        guard.playback_sessions.insert(link.mirror_id, session);
+        following.remove(&link.mirror_id);
+    }
+
+    match source {
+        MirrorSource::Playback(command) => {
+            following.remove(&link.mirror_id);
+            with_playback_session(&store, link.mirror_id, |session| session.copy_command(&command))?;
+        }
+        MirrorSource::Spectate(path) => {
+            if following.get(&link.mirror_id).map(PathBuf::as_path) != Some(Path::new(&path)) {
+                start_follow(app, link.mirror_id, path.clone())?;
+                following.insert(link.mirror_id, path);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keep every mirror playing what its primary plays. A mirror whose
+/// Dolphin can't be started is stopped and reported as `mirror-stopped`.
+pub fn spawn_mirror_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut following: HashMap<u32, PathBuf> = HashMap::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let links = LINKS.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            following.retain(|mirror_id, _| links.iter().any(|link| link.mirror_id == *mirror_id));
+            for link in links {
+                if let Err(err) = sync_mirror(&app, link, &mut following) {
+                    stop_mirror(&app, link, &err);
+                }
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn mirror_links() -> Vec<MirrorLink> {
+    LINKS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Make `mirror_id` follow `primary_id`'s stream or replay on a second
+/// playback Dolphin. The mirror must not have a stream of its own.
+#[tauri::command]
+#[specta::specta]
+pub fn set_mirror_setup(
+    primary_id: u32,
+    mirror_id: u32,
+    store: State<'_, SharedSetupStore>,
+) -> Result<MirrorLink, String> {
+    let params = json!({ "primaryId": primary_id, "mirrorId": mirror_id });
+    audited("setMirrorSetup", params, || {
+        {
+            let guard = store.lock().map_err(|e| e.to_string())?;
+            for id in [primary_id, mirror_id] {
+                if !guard.setups.iter().any(|setup| setup.id == id) {
+                    return Err(format!("Setup {id} not found."));
+                }
+            }
+            let mirror = guard.setups.iter().find(|setup| setup.id == mirror_id);
+            if mirror.is_some_and(|setup| setup.assigned_stream.is_some()) {
+                return Err(format!("Clear setup {mirror_id}'s stream before mirroring onto it."));
+            }
+        }
+        let mut links = LINKS.lock().map_err(|e| e.to_string())?;
+        links.retain(|link| link.mirror_id != mirror_id);
+        check_link(&links, primary_id, mirror_id)?;
+        let link = MirrorLink {
+            primary_id,
+            mirror_id,
+            created_at_ms: now_ms(),
+        };
+        links.push(link);
+        Ok(link)
+    })
+}
+
+/// Stop mirroring onto `mirror_id` and close its Dolphin.
+#[tauri::command]
+#[specta::specta]
+pub fn clear_mirror_setup(mirror_id: u32, store: State<'_, SharedSetupStore>) -> Result<bool, String> {
+    audited("clearMirrorSetup", json!({ "mirrorId": mirror_id }), || {
+        if end_mirror(mirror_id).is_none() {
+            return Ok(false);
+        }
+        clear_assignment(mirror_id, Some(true), &store)?;
+        Ok(true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::playback::PlaybackQueueEntry;
+
+    fn links() -> Vec<MirrorLink> {
+        vec![MirrorLink { primary_id: 1, mirror_id: 2, created_at_ms: 0 }]
+    }
+
+    fn playback(replay: Option<&str>, queue: &[&str]) -> MirrorSource {
+        MirrorSource::Playback(PlaybackCommand {
+            mode: if queue.is_empty() { "normal" } else { "queue" }.to_string(),
+            replay: replay.map(str::to_string),
+            start_frame: None,
+            end_frame: None,
+            queue: queue
+                .iter()
+                .map(|path| PlaybackQueueEntry { path: path.to_string(), start_frame: None, end_frame: None })
+                .collect(),
+            is_real_time_mode: false,
+            command_id: "1".to_string(),
+        })
+    }
+
+    #[test]
+    fn a_primary_can_have_several_mirrors_but_not_itself() {
+        assert!(check_link(&links(), 1, 3).is_ok());
+        assert!(check_link(&links(), 3, 3).is_err());
+    }
+
+    #[test]
+    fn mirrors_do_not_chain() {
+        // 2 is a mirror, so it can't be mirrored in turn.
+        assert_eq!(check_link(&links(), 2, 3).unwrap_err(), "Setup 2 is already a mirror.");
+        // 1 is a primary, so it can't become a mirror.
+        assert_eq!(check_link(&links(), 3, 1).unwrap_err(), "Setup 1 is mirrored by another setup.");
+    }
+
+    #[test]
+    fn a_mirror_starts_on_its_primarys_replay() {
+        assert_eq!(playback(Some("a.slp"), &[]).first_replay(), Some(PathBuf::from("a.slp")));
+        assert_eq!(playback(None, &["b.slp", "c.slp"]).first_replay(), Some(PathBuf::from("b.slp")));
+        assert_eq!(playback(None, &[]).first_replay(), None);
+        let spectating = MirrorSource::Spectate(PathBuf::from("live.slp"));
+        assert_eq!(spectating.first_replay(), Some(PathBuf::from("live.slp")));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::config_transaction::config_transaction;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Emitted whenever offline mode is toggled or start.gg reachability flips.
+pub const NETWORK_STATUS_EVENT: &str = "network-status";
+
+/// Loopback traffic (DevTools, the overlay server) never goes through the
+/// venue proxy.
+const LOCAL_BYPASS: &str = "localhost,127.0.0.1,::1";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+/// How often to probe start.gg while it is unreachable.
+const PROBE_INTERVAL: Duration = Duration::from_secs(15);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+const PROBE_URL: &str = "https://api.start.gg/gql/alpha";
+
+/// Whether the last outbound request got an answer. Set by the start.gg and
+/// Slippi clients and by the monitor's probe.
+static REACHABLE: AtomicBool = AtomicBool::new(true);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+/// Last status sent to the frontend, so each flip is emitted once.
+static LAST_EMITTED: Mutex<Option<NetworkStatus>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub offline_mode: bool,
+    pub reachable: bool,
+    pub proxy_enabled: bool,
+    pub last_error: Option<String>,
+}
+
+// ── Clients ─────────────────────────────────────────────────────────────
+
+/// Client builder with the configured proxy applied. Every reqwest client
+/// in the app starts from this.
+pub fn client_builder() -> Result<reqwest::blocking::ClientBuilder, String> {
+    let config = cached_config().unwrap_or_default();
+    let builder = reqwest::blocking::Client::builder();
+    let url = config.network_proxy_url.trim();
+    if url.is_empty() {
+        return Ok(builder);
+    }
+    let bypass = proxy_bypass_list(&config.network_proxy_bypass);
+    let proxy = reqwest::Proxy::all(url)
+        .map_err(|e| format!("invalid proxy {url}: {e}"))?
+        .no_proxy(reqwest::NoProxy::from_string(&bypass));
+    Ok(builder.proxy(proxy))
+}
+
+/// Comma-separated NO_PROXY list: the configured hosts plus loopback.
+fn proxy_bypass_list(raw: &str) -> String {
+    let mut hosts = raw
+        .split([',', '\n', ' '])
+        .map(str::trim)
+        .filter(|host| !host.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    for host in LOCAL_BYPASS.split(',') {
+        if !hosts.iter().any(|existing| existing == host) {
+            hosts.push(host.to_string());
+        }
+    }
+    hosts.join(",")
+}
+
+// ── Offline mode ────────────────────────────────────────────────────────
+
+pub fn offline_mode_enabled() -> bool {
+    cached_config().map(|config| config.offline_mode).unwrap_or(false)
+}
+
+/// Error for outbound requests skipped because offline mode is on.
+pub fn offline_error(service: &str) -> String {
+    format!("Offline mode is on; {service} requests are paused.")
+}
+
+/// Record whether an outbound request reached its server.
+pub fn record_request_result(result: Result<(), &str>) {
+    REACHABLE.store(result.is_ok(), Ordering::SeqCst);
+    let mut guard = LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner());
+    *guard = result.err().map(|err| err.to_string());
+}
+
+pub fn current_network_status() -> NetworkStatus {
+    let config = cached_config().unwrap_or_default();
+    NetworkStatus {
+        offline_mode: config.offline_mode,
+        reachable: REACHABLE.load(Ordering::SeqCst),
+        proxy_enabled: !config.network_proxy_url.trim().is_empty(),
+        last_error: LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+    }
+}
+
+fn probe_startgg() {
+    let result = client_builder().and_then(|builder| {
+        builder
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .map_err(|e| format!("build probe client: {e}"))?
+            .head(PROBE_URL)
+            .send()
+            .map_err(|e| format!("start.gg unreachable: {e}"))
+    });
+    match result {
+        // Any HTTP answer means the network path works.
+        Ok(_) => record_request_result(Ok(())),
+        Err(err) => record_request_result(Err(err.as_str())),
+    }
+}
+
+/// Emit `network-status` if offline mode or reachability changed since the
+/// last emit.
+fn emit_if_changed(app: &AppHandle) -> NetworkStatus {
+    let status = current_network_status();
+    let mut last = LAST_EMITTED.lock().unwrap_or_else(|e| e.into_inner());
+    let changed = match last.as_ref() {
+        Some(prev) => prev.offline_mode != status.offline_mode || prev.reachable != status.reachable,
+        None => true,
+    };
+    if changed {
+        tracing::info!(
+            "Network status changed: offline_mode={} reachable={}",
+            status.offline_mode,
+            status.reachable
+        );
+        let _ = app.emit(NETWORK_STATUS_EVENT, &status);
+        *last = Some(status.clone());
+    }
+    status
+}
+
+/// Watch reachability and emit `network-status` whenever it or offline mode
+/// changes. While start.gg is unreachable it is probed so the flip back is
+/// noticed without waiting for the next real request.
+pub fn spawn_network_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut since_probe = Duration::ZERO;
+        loop {
+            let status = emit_if_changed(&app);
+            sleep(MONITOR_INTERVAL);
+            since_probe += MONITOR_INTERVAL;
+            if !status.offline_mode && !REACHABLE.load(Ordering::SeqCst) && since_probe >= PROBE_INTERVAL {
+                since_probe = Duration::ZERO;
+                probe_startgg();
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn network_status() -> NetworkStatus {
+    current_network_status()
+}
+
+/// Turn offline mode on or off. Start.gg polling pauses while it's on and
+/// consumers keep serving the last fetched (or simulated) bracket.
+#[tauri::command]
+#[specta::specta]
+pub fn set_offline_mode(app: AppHandle, offline: bool) -> Result<NetworkStatus, String> {
+    audited("setOfflineMode", json!({ "offline": offline }), || {
+        config_transaction(&app, "setOfflineMode", |txn| {
+            txn.config_mut().offline_mode = offline;
+            Ok(())
+        })?;
+        Ok(emit_if_changed(&app))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn configured_hosts_are_split_and_trimmed() {
+        assert_eq!(
+            proxy_bypass_list(" .venue.lan, api.local\nbracket.local "),
+            ".venue.lan,api.local,bracket.local,localhost,127.0.0.1,::1"
+        );
+    }
+
+    #[test]
+    fn loopback_is_always_bypassed_once() {
+        assert_eq!(proxy_bypass_list(""), LOCAL_BYPASS);
+        assert_eq!(proxy_bypass_list("localhost"), LOCAL_BYPASS);
+        assert_eq!(proxy_bypass_list("::1, api.local"), "::1,api.local,localhost,127.0.0.1");
+    }
+}
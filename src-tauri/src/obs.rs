@@ -0,0 +1,554 @@
+use crate::audit_log::audited;
+use crate::capture_sources::{capture_sources, CaptureSource, CaptureSourceKind};
+use crate::config::*;
+use crate::feature_flags::{feature_enabled, require_feature};
+use crate::friendlies::assigned_set;
+use crate::run_of_show::current_bracket_state;
+use crate::setup_meta::setup_label;
+use crate::types::*;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::{
+    collections::HashMap,
+    net::{TcpStream, ToSocketAddrs},
+    sync::Mutex,
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager, State};
+use tungstenite::{client::IntoClientRequest, client_tls, stream::MaybeTlsStream, Message, WebSocket};
+
+// Drives OBS over obs-websocket v5: one scene per setup holding a
+// vkcapture source for that setup's Dolphin, which obs-gamecapture labels
+// `dolphin-{setup_id}`, or for a console station the input of its capture
+// card. A setup's scene is found by that source, so renaming the setup
+// renames its scene instead of making a new one.
+
+const RPC_VERSION: u64 = 1;
+const OP_HELLO: u64 = 0;
+const OP_IDENTIFY: u64 = 1;
+const OP_IDENTIFIED: u64 = 2;
+const OP_REQUEST: u64 = 6;
+const OP_REQUEST_RESPONSE: u64 = 7;
+/// Close code obs-websocket sends when the password is wrong.
+const AUTHENTICATION_FAILED: u16 = 4009;
+const VKCAPTURE_KIND: &str = "vkcapture-source";
+const V4L2_KIND: &str = "v4l2_input";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+struct ObsClient {
+    socket: Socket,
+    url: String,
+    next_id: u64,
+    /// Set once the socket fails; the client is dropped and the next
+    /// request reconnects.
+    broken: bool,
+}
+
+static CLIENT: Mutex<Option<ObsClient>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ObsStatus {
+    pub url: String,
+    pub obs_version: String,
+    pub websocket_version: String,
+    pub program_scene: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ObsSceneList {
+    pub program_scene: String,
+    /// In OBS's order, top of the list first
+    pub scenes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ObsSetupBinding {
+    pub setup_id: u32,
+    pub scene: String,
+    /// The vkcapture source, named after the Dolphin's capture label, or
+    /// the capture card's input
+    pub source: String,
+    pub created_scene: bool,
+    pub renamed_from: Option<String>,
+    pub created_source: bool,
+}
+
+/// The `authentication` string for an obs-websocket Hello's salt and
+/// challenge.
+fn auth_response(password: &str, salt: &str, challenge: &str) -> String {
+    let secret = STANDARD.encode(Sha256::digest(format!("{password}{salt}")));
+    STANDARD.encode(Sha256::digest(format!("{secret}{challenge}")))
+}
+
+/// The capture label obs-gamecapture gives a setup's Dolphin, which is also
+/// the name of its source in OBS.
+fn capture_label(setup_id: u32) -> String {
+    format!("dolphin-{setup_id}")
+}
+
+/// The OBS input that shows a setup.
+struct SetupInput {
+    name: String,
+    /// Kind and settings of an input this app manages; None for a source
+    /// the operator made in OBS, which is used as it is.
+    managed: Option<(&'static str, Value)>,
+}
+
+fn setup_input(setup: &Setup, sources: &[CaptureSource]) -> Result<SetupInput, String> {
+    let Some(source_id) = setup.capture_source_id.as_deref() else {
+        let label = capture_label(setup.id);
+        let settings = json!({ "window": label, "show_cursor": false });
+        return Ok(SetupInput { name: label, managed: Some((VKCAPTURE_KIND, settings)) });
+    };
+    let source = sources
+        .iter()
+        .find(|source| source.id == source_id)
+        .ok_or_else(|| format!("No capture source {source_id}."))?;
+    Ok(match source.kind {
+        CaptureSourceKind::V4l2 => SetupInput {
+            name: source.label.clone(),
+            managed: Some((V4L2_KIND, json!({ "device_id": source.device }))),
+        },
+        CaptureSourceKind::Obs => SetupInput { name: source.device.clone(), managed: None },
+    })
+}
+
+impl ObsClient {
+    fn connect(url: &str, password: &str) -> Result<Self, String> {
+        let request = url.into_client_request().map_err(|e| format!("obs-websocket URL {url}: {e}"))?;
+        let host = request.uri().host().unwrap_or_default().to_string();
+        let default_port = if request.uri().scheme_str() == Some("wss") { 443 } else { 80 };
+        let port = request.uri().port_u16().unwrap_or(default_port);
+        let addr = (host.as_str(), port)
+            .to_socket_addrs()
+            .map_err(|e| format!("resolve {host}: {e}"))?
+            .next()
+            .ok_or_else(|| format!("resolve {host}: no address"))?;
+        let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| format!("connect {url}: {e}"))?;
+        stream.set_read_timeout(Some(READ_TIMEOUT)).map_err(|e| e.to_string())?;
+        let (socket, _) = client_tls(request, stream).map_err(|e| format!("obs-websocket connect {url}: {e}"))?;
+        let mut client = ObsClient { socket, url: url.to_string(), next_id: 0, broken: false };
+
+        let hello = client.read_op(OP_HELLO)?;
+        let mut identify = json!({ "rpcVersion": RPC_VERSION, "eventSubscriptions": 0 });
+        if let Some(auth) = hello.get("authentication") {
+            if password.is_empty() {
+                return Err(format!("obs-websocket at {url} needs a password; set it in Settings."));
+            }
+            let challenge = auth.get("challenge").and_then(Value::as_str).unwrap_or_default();
+            let salt = auth.get("salt").and_then(Value::as_str).unwrap_or_default();
+            identify["authentication"] = json!(auth_response(password, salt, challenge));
+        }
+        client.send(OP_IDENTIFY, identify)?;
+        client.read_op(OP_IDENTIFIED)?;
+        Ok(client)
+    }
+
+    fn send(&mut self, op: u64, data: Value) -> Result<(), String> {
+        let message = json!({ "op": op, "d": data }).to_string();
+        self.socket.send(Message::Text(message)).map_err(|e| {
+            self.broken = true;
+            format!("obs-websocket send: {e}")
+        })
+    }
+
+    /// The data of the next message with opcode `op`, skipping others.
+    fn read_op(&mut self, op: u64) -> Result<Value, String> {
+        loop {
+            let message = match self.socket.read() {
+                Ok(message) => message,
+                Err(e) => {
+                    self.broken = true;
+                    return Err(format!("obs-websocket read: {e}"));
+                }
+            };
+            match message {
+                Message::Text(text) => {
+                    let value = serde_json::from_str::<Value>(&text).map_err(|e| format!("parse obs-websocket message: {e}"))?;
+                    if value.get("op").and_then(Value::as_u64) == Some(op) {
+                        return Ok(value.get("d").cloned().unwrap_or(Value::Null));
+                    }
+                }
+                Message::Close(frame) => {
+                    self.broken = true;
+                    return Err(match frame {
+                        Some(frame) if u16::from(frame.code) == AUTHENTICATION_FAILED => {
+                            "obs-websocket rejected the password.".to_string()
+                        }
+                        Some(frame) => format!("obs-websocket closed the connection: {}", frame.reason),
+                        None => "obs-websocket closed the connection.".to_string(),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Send a request and wait for its response data.
+    fn request(&mut self, request_type: &str, data: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let request_id = self.next_id.to_string();
+        self.send(
+            OP_REQUEST,
+            json!({ "requestType": request_type, "requestId": request_id, "requestData": data }),
+        )?;
+        loop {
+            let response = self.read_op(OP_REQUEST_RESPONSE)?;
+            if response.get("requestId").and_then(Value::as_str) != Some(request_id.as_str()) {
+                continue;
+            }
+            let status = response.get("requestStatus").cloned().unwrap_or(Value::Null);
+            if status.get("result").and_then(Value::as_bool) != Some(true) {
+                let reason = status
+                    .get("comment")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("code {}", status.get("code").unwrap_or(&Value::Null)));
+                return Err(format!("OBS {request_type}: {reason}"));
+            }
+            return Ok(response.get("responseData").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    fn scene_names(&mut self) -> Result<ObsSceneList, String> {
+        let list = self.request("GetSceneList", json!({}))?;
+        let program_scene = list
+            .get("currentProgramSceneName")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+        // OBS lists scenes bottom first.
+        let scenes = list
+            .get("scenes")
+            .and_then(Value::as_array)
+            .map(|scenes| {
+                scenes
+                    .iter()
+                    .rev()
+                    .filter_map(|scene| scene.get("sceneName").and_then(Value::as_str).map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(ObsSceneList { program_scene, scenes })
+    }
+
+    fn scene_has_source(&mut self, scene: &str, source: &str) -> Result<bool, String> {
+        let items = self.request("GetSceneItemList", json!({ "sceneName": scene }))?;
+        Ok(items
+            .get("sceneItems")
+            .and_then(Value::as_array)
+            .is_some_and(|items| {
+                items
+                    .iter()
+                    .any(|item| item.get("sourceName").and_then(Value::as_str) == Some(source))
+            }))
+    }
+
+    /// The scene holding a setup's input, if any.
+    fn setup_scene(&mut self, input: &SetupInput) -> Result<Option<String>, String> {
+        for scene in self.scene_names()?.scenes {
+            if self.scene_has_source(&scene, &input.name)? {
+                return Ok(Some(scene));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whether there's an input named `name`, of `kind` when given.
+    fn input_exists(&mut self, name: &str, kind: Option<&str>) -> Result<bool, String> {
+        let params = kind.map(|kind| json!({ "inputKind": kind })).unwrap_or_else(|| json!({}));
+        let inputs = self.request("GetInputList", params)?;
+        Ok(inputs
+            .get("inputs")
+            .and_then(Value::as_array)
+            .is_some_and(|inputs| {
+                inputs
+                    .iter()
+                    .any(|input| input.get("inputName").and_then(Value::as_str) == Some(name))
+            }))
+    }
+}
+
+/// Run `f` against the OBS connection, connecting with the saved settings
+/// when there isn't one. A connection that fails is dropped.
+fn with_client<R>(f: impl FnOnce(&mut ObsClient) -> Result<R, String>) -> Result<R, String> {
+    require_feature(FeatureFlag::Obs)?;
+    let mut guard = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let config = cached_config()?;
+        *guard = Some(ObsClient::connect(config.obs_websocket_url.trim(), &config.obs_websocket_password)?);
+    }
+    let client = guard.as_mut().expect("connected above");
+    let result = f(client);
+    if client.broken {
+        *guard = None;
+    }
+    result
+}
+
+/// Make sure a setup has its own scene named after it, holding a vkcapture
+/// source for its Dolphin.
+fn bind_setup_source(
+    client: &mut ObsClient,
+    setup_id: u32,
+    scene: &str,
+    input: &SetupInput,
+) -> Result<ObsSetupBinding, String> {
+    let label = input.name.clone();
+    let mut binding = ObsSetupBinding {
+        setup_id,
+        scene: scene.to_string(),
+        source: label.clone(),
+        created_scene: false,
+        renamed_from: None,
+        created_source: false,
+    };
+    let scenes = client.scene_names()?.scenes;
+    match client.setup_scene(input)? {
+        Some(current) if current != scene => {
+            if scenes.iter().any(|name| name == scene) {
+                return Err(format!("OBS already has a scene named '{scene}'; rename it or the setup first."));
+            }
+            client.request("SetSceneName", json!({ "sceneName": current, "newSceneName": scene }))?;
+            binding.renamed_from = Some(current);
+        }
+        Some(_) => {}
+        None if scenes.iter().any(|name| name == scene) => {}
+        None => {
+            client.request("CreateScene", json!({ "sceneName": scene }))?;
+            binding.created_scene = true;
+        }
+    }
+
+    match &input.managed {
+        Some((kind, settings)) if !client.input_exists(&label, Some(*kind))? => {
+            client.request(
+                "CreateInput",
+                json!({
+                    "sceneName": scene,
+                    "inputName": label,
+                    "inputKind": kind,
+                    "inputSettings": settings,
+                    "sceneItemEnabled": true,
+                }),
+            )?;
+            binding.created_source = true;
+            return Ok(binding);
+        }
+        Some((_, settings)) => {
+            client.request("SetInputSettings", json!({ "inputName": label, "inputSettings": settings }))?;
+        }
+        None if !client.input_exists(&label, None)? => {
+            return Err(format!("OBS has no source named '{label}'."));
+        }
+        None => {}
+    }
+    if !client.scene_has_source(scene, &label)? {
+        client.request(
+            "CreateSceneItem",
+            json!({ "sceneName": scene, "sourceName": label, "sceneItemEnabled": true }),
+        )?;
+    }
+    Ok(binding)
+}
+
+fn switch_scene(client: &mut ObsClient, scene: &str) -> Result<(), String> {
+    client.request("SetCurrentProgramScene", json!({ "sceneName": scene }))?;
+    Ok(())
+}
+
+/// Put a setup's scene on program, if it has one.
+fn switch_to_setup(setup: &Setup, sources: &[CaptureSource]) -> Result<Option<String>, String> {
+    let input = setup_input(setup, sources)?;
+    with_client(|client| {
+        let Some(scene) = client.setup_scene(&input)? else {
+            return Ok(None);
+        };
+        switch_scene(client, &scene)?;
+        Ok(Some(scene))
+    })
+}
+
+/// The scene to put on program in place of `scene`: the first other one in
+/// OBS's list.
+fn fallback_scene(list: &ObsSceneList, scene: &str) -> Option<String> {
+    list.scenes.iter().find(|name| name.as_str() != scene).cloned()
+}
+
+/// Take a setup off air before its Dolphin is closed: if its scene is on
+/// program, switch to another scene, and hide its source so the dead
+/// capture never shows. Returns the scene switched to. Nothing happens with
+/// OBS control off.
+pub fn release_setup_scene(setup: &Setup) -> Result<Option<String>, String> {
+    if !feature_enabled(FeatureFlag::Obs) {
+        return Ok(None);
+    }
+    let input = setup_input(setup, &capture_sources())?;
+    with_client(|client| {
+        let Some(scene) = client.setup_scene(&input)? else {
+            return Ok(None);
+        };
+        let list = client.scene_names()?;
+        let switched = match fallback_scene(&list, &scene) {
+            Some(fallback) if list.program_scene == scene => {
+                switch_scene(client, &fallback)?;
+                Some(fallback)
+            }
+            _ => None,
+        };
+        let item = client.request("GetSceneItemId", json!({ "sceneName": scene, "sourceName": input.name }))?;
+        if let Some(item_id) = item.get("sceneItemId").and_then(Value::as_i64) {
+            client.request(
+                "SetSceneItemEnabled",
+                json!({ "sceneName": scene, "sceneItemId": item_id, "sceneItemEnabled": false }),
+            )?;
+        }
+        Ok(switched)
+    })
+}
+
+/// Switch OBS to a setup's scene when the set it's showing starts, with
+/// `obs_switch_on_live` on. A setup with no bound scene is skipped.
+pub fn spawn_obs_scene_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        // The set each setup last went live with.
+        let mut live: HashMap<u32, u64> = HashMap::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if !feature_enabled(FeatureFlag::Obs) || !cached_config().is_ok_and(|config| config.obs_switch_on_live) {
+                live.clear();
+                continue;
+            }
+            let setups = app.state::<SharedSetupStore>().lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+            let bracket = current_bracket_state(&app.state::<SharedTestState>(), &app.state::<SharedLiveStartgg>());
+            let sources = capture_sources();
+            for setup in setups {
+                let Some(set) = setup
+                    .assigned_stream
+                    .as_ref()
+                    .and_then(|stream| assigned_set(setup.id, stream, bracket.as_ref()))
+                else {
+                    continue;
+                };
+                if set.state != "inProgress" || live.get(&setup.id) == Some(&set.id) {
+                    continue;
+                }
+                live.insert(setup.id, set.id);
+                match switch_to_setup(&setup, &sources) {
+                    Ok(Some(scene)) => tracing::info!("Set {} is live on setup {}; OBS on '{}'", set.id, setup.id, scene),
+                    Ok(None) => tracing::info!("Set {} is live on setup {}, which has no OBS scene", set.id, setup.id),
+                    Err(err) => tracing::warn!("Switching OBS to setup {}: {}", setup.id, err),
+                }
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Connect to obs-websocket, replacing any open connection. Without
+/// arguments the saved URL and password are used.
+#[tauri::command]
+#[specta::specta]
+pub fn obs_connect(url: Option<String>, password: Option<String>) -> Result<ObsStatus, String> {
+    require_feature(FeatureFlag::Obs)?;
+    let config = cached_config()?;
+    let url = url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| config.obs_websocket_url.trim().to_string());
+    let password = password.unwrap_or(config.obs_websocket_password);
+    let mut guard = CLIENT.lock().unwrap_or_else(|e| e.into_inner());
+    *guard = None;
+    let mut client = ObsClient::connect(&url, &password)?;
+    let version = client.request("GetVersion", json!({}))?;
+    let program_scene = client.scene_names()?.program_scene;
+    let status = ObsStatus {
+        url: client.url.clone(),
+        obs_version: version.get("obsVersion").and_then(Value::as_str).unwrap_or("unknown").to_string(),
+        websocket_version: version
+            .get("obsWebSocketVersion")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string(),
+        program_scene,
+    };
+    *guard = Some(client);
+    Ok(status)
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn obs_list_scenes() -> Result<ObsSceneList, String> {
+    with_client(|client| client.scene_names())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn obs_switch_scene(scene: String) -> Result<(), String> {
+    audited("obsSwitchScene", json!({ "scene": scene }), || {
+        with_client(|client| switch_scene(client, &scene))
+    })
+}
+
+/// Create or rename a setup's scene to match its name and point its
+/// vkcapture source at the setup's Dolphin, or add its capture card.
+#[tauri::command]
+#[specta::specta]
+pub fn obs_bind_setup_source(
+    setup_id: u32,
+    store: State<'_, SharedSetupStore>,
+) -> Result<ObsSetupBinding, String> {
+    audited("obsBindSetupSource", json!({ "setupId": setup_id }), || {
+        let (scene, input) = {
+            let guard = store.lock().map_err(|e| e.to_string())?;
+            let setup = guard
+                .setups
+                .iter()
+                .find(|s| s.id == setup_id)
+                .ok_or_else(|| format!("Setup {setup_id} not found."))?;
+            (setup_label(setup).to_string(), setup_input(setup, &capture_sources())?)
+        };
+        with_client(|client| bind_setup_source(client, setup_id, &scene, &input))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authentication_matches_the_protocol_example() {
+        // The example from the obs-websocket v5 protocol docs.
+        let auth = auth_response(
+            "supersecretpassword",
+            "lM1GncleQOaCu9lT1yeUZhFYnqhsLLP1G5lAGo3ixaI=",
+            "+IxH4CnCiqpX1rM9scsNynZzbOe4KhDeYcTNS3PDaeY=",
+        );
+        assert_eq!(auth, "1Ct943GAT+6YQUUX47Ia/ncufilbe6+oD6lY+5kaCu4=");
+        assert_eq!(capture_label(3), "dolphin-3");
+    }
+
+    #[test]
+    fn a_deleted_setup_falls_back_to_the_next_scene() {
+        let list = ObsSceneList {
+            program_scene: "Setup 1".to_string(),
+            scenes: vec!["Setup 1".to_string(), "Setup 2".to_string(), "Break".to_string()],
+        };
+        assert_eq!(fallback_scene(&list, "Setup 1").as_deref(), Some("Setup 2"));
+        assert_eq!(fallback_scene(&list, "Setup 2").as_deref(), Some("Setup 1"));
+        let only = ObsSceneList { program_scene: "Setup 1".to_string(), scenes: vec!["Setup 1".to_string()] };
+        assert_eq!(fallback_scene(&only, "Setup 1"), None);
+    }
+}
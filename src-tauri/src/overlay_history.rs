@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    thread::sleep,
+    time::Duration,
+};
+use crate::startgg_sim::StartggSimState;
+use crate::venue_time::venue_rfc3339;
+use crate::types::{
+    AllSetupsState, OverlayServerState, OverlayState, PlayerState, SetResultPlayer, SetResultSnapshot,
+    SharedOverlayHistory,
+};
+use tauri::State;
+
+/// Completed sets kept per setup.
+const HISTORY_PER_SETUP: usize = 20;
+/// How often the overlays are rebuilt to look for finished sets.
+const RECORD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// OverlayHistory watches the overlay state each setup is showing and keeps
+/// a rolling list of finished sets, so a "set result" graphic can be shown
+/// right after a set ends without going back to start.gg.
+#[derive(Default)]
+pub struct OverlayHistory {
+    /// Set currently on screen for each setup, with the characters seen so far
+    tracking: HashMap<u32, TrackedSet>,
+    /// Finished sets per setup, most recent last
+    completed: HashMap<u32, VecDeque<SetResultSnapshot>>,
+}
+
+struct TrackedSet {
+    set_id: u64,
+    p1_characters: Vec<String>,
+    p2_characters: Vec<String>,
+    first_seen_ms: u64,
+}
+
+fn push_character(list: &mut Vec<String>, player: &PlayerState) {
+    // Placeholder characters come from default_player; only replay-parsed
+    // players carry a character ID.
+    if player.character_id.is_none() {
+        return;
+    }
+    if list.last() != Some(&player.character) {
+        list.push(player.character.clone());
+    }
+}
+
+fn result_player(player: &PlayerState, characters: &[String]) -> SetResultPlayer {
+    SetResultPlayer {
+        tag: player.tag.clone(),
+        connect_code: player.connect_code.clone(),
+        score: player.score,
+        characters: characters.to_vec(),
+    }
+}
+
+impl OverlayHistory {
+    pub fn new() -> Self {
+        OverlayHistory::default()
+    }
+
+    /// Feed the overlay states just built for every setup. `setups[i]` is
+    /// setup `i + 1`, matching `build_overlay_state`. Returns the sets that
+    /// just finished.
+    pub fn observe_all(
+        &mut self,
+        state: &AllSetupsState,
+        startgg_state: Option<&StartggSimState>,
+        now_ms: u64,
+    ) -> Vec<SetResultSnapshot> {
+        state
+            .setups
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, overlay)| self.observe(idx as u32 + 1, overlay, startgg_state, now_ms))
+            .collect()
+    }
+
+    pub fn observe(
+        &mut self,
+        setup_id: u32,
+        overlay: &OverlayState,
+        startgg_state: Option<&StartggSimState>,
+        now_ms: u64,
+    ) -> Option<SetResultSnapshot> {
+        let set_id = overlay.meta.set_id?;
+        let tracked = self.tracking.entry(setup_id).or_insert_with(|| TrackedSet {
+            set_id,
+            p1_characters: Vec::new(),
+            p2_characters: Vec::new(),
+            first_seen_ms: now_ms,
+        });
+        if tracked.set_id != set_id {
+            *tracked = TrackedSet {
+                set_id,
+                p1_characters: Vec::new(),
+                p2_characters: Vec::new(),
+                first_seen_ms: now_ms,
+            };
+        }
+        push_character(&mut tracked.p1_characters, &overlay.p1);
+        push_character(&mut tracked.p2_characters, &overlay.p2);
+
+        if overlay.meta.set_state.as_deref() != Some("completed") {
+            return None;
+        }
+        let history = self.completed.entry(setup_id).or_default();
+        if history.iter().any(|snapshot| snapshot.set_id == set_id) {
+            return None;
+        }
+
+        let set = startgg_state.and_then(|state| state.sets.iter().find(|set| set.id == set_id));
+        let started_at_ms = set.and_then(|set| set.started_at_ms).or(Some(tracked.first_seen_ms));
+        let completed_at_ms = set.and_then(|set| set.completed_at_ms).or(Some(now_ms));
+        let duration_ms = match (started_at_ms, completed_at_ms) {
+            (Some(start), Some(end)) => Some(end.saturating_sub(start)),
+            _ => None,
+        };
+        let winner = if overlay.p1.score > overlay.p2.score {
+            Some(overlay.p1.side.clone())
+        } else if overlay.p2.score > overlay.p1.score {
+            Some(overlay.p2.side.clone())
+        } else {
+            None
+        };
+
+        let snapshot = SetResultSnapshot {
+            setup_id,
+            set_id,
+            tournament: overlay.meta.tournament.clone(),
+            round: overlay.meta.round.clone(),
+            best_of: overlay.meta.best_of,
+            p1: result_player(&overlay.p1, &tracked.p1_characters),
+            p2: result_player(&overlay.p2, &tracked.p2_characters),
+            winner,
+            started_at_ms,
+            completed_at_ms,
+            started_at_local: started_at_ms.and_then(venue_rfc3339),
+            completed_at_local: completed_at_ms.and_then(venue_rfc3339),
+            duration_ms,
+            replay_urls: Vec::new(),
+        };
+        history.push_back(snapshot.clone());
+        while history.len() > HISTORY_PER_SETUP {
+            history.pop_front();
+        }
+        Some(snapshot)
+    }
+
+    /// The latest snapshot of `set_id` on any setup.
+    pub fn find_set(&self, set_id: u64) -> Option<SetResultSnapshot> {
+        self.completed
+            .values()
+            .flatten()
+            .filter(|snapshot| snapshot.set_id == set_id)
+            .max_by_key(|snapshot| snapshot.completed_at_ms)
+            .cloned()
+    }
+
+    pub fn last_completed(&self, setup_id: u32) -> Option<SetResultSnapshot> {
+        self.completed.get(&setup_id).and_then(|history| history.back().cloned())
+    }
+
+    /// Completed sets for a setup, most recent first.
+    pub fn history(&self, setup_id: u32) -> Vec<SetResultSnapshot> {
+        self.completed
+            .get(&setup_id)
+            .map(|history| history.iter().rev().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Most recent completed set for every setup that has one.
+    pub fn last_completed_all(&self) -> HashMap<u32, SetResultSnapshot> {
+        self.completed
+            .iter()
+            .filter_map(|(id, history)| history.back().map(|snapshot| (*id, snapshot.clone())))
+            .collect()
+    }
+
+    /// Every setup's history, most recent first.
+    pub fn all(&self) -> HashMap<u32, Vec<SetResultSnapshot>> {
+        self.completed.keys().map(|id| (*id, self.history(*id))).collect()
+    }
+
+    pub fn clear_setup(&mut self, setup_id: u32) {
+        self.tracking.remove(&setup_id);
+        self.completed.remove(&setup_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.tracking.clear();
+        self.completed.clear();
+    }
+}
+
+/// Follow the replays and the bracket in the background and record sets as
+/// they finish, whether or not anything is requesting overlays.
+pub fn spawn_history_recorder(state: OverlayServerState) {
+    std::thread::spawn(move || loop {
+        sleep(RECORD_INTERVAL);
+        crate::record_overlay_history(&state);
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn last_completed_set(
+    setup_id: u32,
+    history: State<'_, SharedOverlayHistory>,
+) -> Result<Option<SetResultSnapshot>, String> {
+    let guard = history.lock().map_err(|e| e.to_string())?;
+    Ok(guard.last_completed(setup_id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn setup_set_history(
+    setup_id: u32,
+    history: State<'_, SharedOverlayHistory>,
+) -> Result<Vec<SetResultSnapshot>, String> {
+    let guard = history.lock().map_err(|e| e.to_string())?;
+    Ok(guard.history(setup_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::default_overlay_state;
+
+    fn overlay(set_id: u64, state: &str, scores: (u32, u32), characters: (&str, &str)) -> OverlayState {
+        let mut overlay = default_overlay_state(1);
+        overlay.meta.set_id = Some(set_id);
+        overlay.meta.set_state = Some(state.to_string());
+        for (player, score, character) in
+            [(&mut overlay.p1, scores.0, characters.0), (&mut overlay.p2, scores.1, characters.1)]
+        {
+            player.score = score;
+            player.character = character.to_string();
+            player.character_id = Some(0);
+        }
+        overlay
+    }
+
+    #[test]
+    fn a_finished_set_is_recorded_once_with_every_character_played() {
+        let mut history = OverlayHistory::new();
+        assert!(history.observe(1, &overlay(7, "inProgress", (0, 0), ("Fox", "Marth")), None, 1_000).is_none());
+        history.observe(1, &overlay(7, "inProgress", (1, 0), ("Fox", "Sheik")), None, 2_000);
+        history.observe(1, &overlay(7, "inProgress", (1, 1), ("Fox", "Sheik")), None, 3_000);
+
+        let done = history.observe(1, &overlay(7, "completed", (2, 1), ("Falco", "Sheik")), None, 5_000).unwrap();
+        assert_eq!(done.p1.characters, vec!["Fox", "Falco"]);
+        assert_eq!(done.p2.characters, vec!["Marth", "Sheik"]);
+        assert_eq!(done.winner.as_deref(), Some("left"));
+        assert_eq!((done.started_at_ms, done.duration_ms), (Some(1_000), Some(4_000)));
+
+        // Still on screen after it ends: not recorded again.
+        assert!(history.observe(1, &overlay(7, "completed", (2, 1), ("Falco", "Sheik")), None, 6_000).is_none());
+        assert_eq!(history.history(1).len(), 1);
+        assert_eq!(history.find_set(7).map(|snapshot| snapshot.setup_id), Some(1));
+    }
+
+    #[test]
+    fn a_new_set_on_the_setup_starts_a_fresh_record() {
+        let mut history = OverlayHistory::new();
+        history.observe(2, &overlay(7, "inProgress", (0, 0), ("Fox", "Marth")), None, 1_000);
+        let next = history.observe(2, &overlay(8, "completed", (0, 2), ("Peach", "Puff")), None, 9_000).unwrap();
+        assert_eq!(next.p1.characters, vec!["Peach"]);
+        assert_eq!(next.winner.as_deref(), Some("right"));
+        assert_eq!(next.started_at_ms, Some(9_000));
+
+        let mut state = AllSetupsState { setups: vec![default_overlay_state(1)], timezone: None };
+        state.setups.push(overlay(9, "completed", (2, 0), ("Fox", "Fox")));
+        let completed = history.observe_all(&state, None, 10_000);
+        assert_eq!(completed.iter().map(|snapshot| (snapshot.setup_id, snapshot.set_id)).collect::<Vec<_>>(), [(2, 9)]);
+        history.clear_setup(2);
+        assert!(history.last_completed(2).is_none());
+    }
+}
@@ -0,0 +1,193 @@
+use crate::startgg_sim::StartggSimState;
+use crate::types::*;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+};
+use tauri::State;
+
+/// Overlays poll every few hundred ms; a shorter interval is just `Live`.
+pub const MIN_REFRESH_INTERVAL_MS: u64 = 250;
+
+/// Last overlay built for a setup and the inputs it was built from.
+#[derive(Debug, Clone)]
+pub struct OverlayRefreshEntry {
+    pub overlay: OverlayState,
+    pub built_at_ms: u64,
+    pub inputs: RefreshInputs,
+}
+
+/// Fingerprints of what an overlay depends on. A driver rebuilds when the
+/// one it watches changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RefreshInputs {
+    /// Assignment, side mapping and driver of the setup itself
+    pub setup: u64,
+    /// Replays in the spectate folder (or spoofed replays in test mode)
+    pub replays: u64,
+    pub bracket: u64,
+}
+
+fn hash_of(value: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn setup_fingerprint(setup: Option<&Setup>) -> u64 {
+    hash_of(setup.and_then(|setup| serde_json::to_string(setup).ok()))
+}
+
+/// Changes whenever a replay is added or grows: the folder index keeps an
+/// mtime per file, and spoofed replays are stat'ed directly.
+pub fn replay_fingerprint(cache: &OverlayReplayCache, replay_map: &HashMap<String, PathBuf>) -> u64 {
+    let newest_indexed = cache.replay_mtimes.values().max().copied();
+    let mut spoofed = replay_map
+        .iter()
+        .map(|(stream_id, path)| (stream_id, fs::metadata(path).and_then(|meta| meta.modified()).ok()))
+        .collect::<Vec<_>>();
+    spoofed.sort();
+    hash_of((&cache.indexed_dir, cache.replay_mtimes.len(), newest_indexed, spoofed))
+}
+
+pub fn bracket_fingerprint(state: Option<&StartggSimState>) -> u64 {
+    let Some(state) = state else {
+        return 0;
+    };
+    let sets = state
+        .sets
+        .iter()
+        .map(|set| (set.id, &set.state, set.updated_at_ms, set.winner_id))
+        .collect::<Vec<_>>();
+    hash_of((state.version, sets))
+}
+
+/// Whether a setup's overlay should be rebuilt on this poll. A setup whose
+/// own assignment changed always rebuilds, whatever its driver.
+pub fn needs_rebuild(
+    driver: OverlayRefreshDriver,
+    entry: Option<&OverlayRefreshEntry>,
+    inputs: &RefreshInputs,
+    now_ms: u64,
+) -> bool {
+    let Some(entry) = entry else {
+        return true;
+    };
+    if entry.inputs.setup != inputs.setup {
+        return true;
+    }
+    match driver {
+        OverlayRefreshDriver::Live => true,
+        OverlayRefreshDriver::FileWatcher => entry.inputs.replays != inputs.replays,
+        OverlayRefreshDriver::Interval { ms } => now_ms.saturating_sub(entry.built_at_ms) >= ms,
+        OverlayRefreshDriver::BracketEvents => entry.inputs.bracket != inputs.bracket,
+        OverlayRefreshDriver::Manual => false,
+    }
+}
+
+/// Serve the cached overlay for `setup_id` unless its driver says to rebuild.
+pub fn refresh_overlay(
+    cache: &mut OverlayReplayCache,
+    setup_id: u32,
+    driver: OverlayRefreshDriver,
+    inputs: RefreshInputs,
+    now_ms: u64,
+    build: impl FnOnce(&mut OverlayReplayCache) -> OverlayState,
+) -> OverlayState {
+    let cached = cache.refresh.get(&setup_id);
+    if let Some(entry) = cached.filter(|entry| !needs_rebuild(driver, Some(entry), &inputs, now_ms)) {
+        return entry.overlay.clone();
+    }
+    let overlay = build(cache);
+    cache.refresh.insert(
+        setup_id,
+        OverlayRefreshEntry {
+            overlay: overlay.clone(),
+            built_at_ms: now_ms,
+            inputs,
+        },
+    );
+    overlay
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Drop a setup's cached overlay so the next poll rebuilds it, whatever
+/// its driver.
+#[tauri::command]
+#[specta::specta]
+pub fn force_overlay_refresh(setup_id: u32, replay_cache: State<'_, SharedOverlayCache>) -> Result<(), String> {
+    replay_cache.lock().map_err(|e| e.to_string())?.refresh.remove(&setup_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::default_overlay_state;
+
+    const INPUTS: RefreshInputs = RefreshInputs { setup: 1, replays: 2, bracket: 3 };
+    const NEW_REPLAY: RefreshInputs = RefreshInputs { replays: 9, ..INPUTS };
+    const NEW_BRACKET: RefreshInputs = RefreshInputs { bracket: 9, ..INPUTS };
+
+    fn entry() -> OverlayRefreshEntry {
+        OverlayRefreshEntry {
+            overlay: default_overlay_state(1),
+            built_at_ms: 1_000,
+            inputs: INPUTS,
+        }
+    }
+
+    #[test]
+    fn live_rebuilds_every_poll() {
+        assert!(needs_rebuild(OverlayRefreshDriver::Live, Some(&entry()), &INPUTS, 1_000));
+    }
+
+    #[test]
+    fn file_watcher_rebuilds_on_new_replays_only() {
+        assert!(!needs_rebuild(OverlayRefreshDriver::FileWatcher, Some(&entry()), &NEW_BRACKET, 1_000));
+        assert!(needs_rebuild(OverlayRefreshDriver::FileWatcher, Some(&entry()), &NEW_REPLAY, 1_000));
+    }
+
+    #[test]
+    fn bracket_events_rebuild_on_bracket_changes_only() {
+        assert!(!needs_rebuild(OverlayRefreshDriver::BracketEvents, Some(&entry()), &NEW_REPLAY, 1_000));
+        assert!(needs_rebuild(OverlayRefreshDriver::BracketEvents, Some(&entry()), &NEW_BRACKET, 1_000));
+    }
+
+    #[test]
+    fn interval_rebuilds_once_it_has_passed() {
+        let interval = OverlayRefreshDriver::Interval { ms: 5_000 };
+        assert!(!needs_rebuild(interval, Some(&entry()), &NEW_REPLAY, 5_999));
+        assert!(needs_rebuild(interval, Some(&entry()), &INPUTS, 6_000));
+    }
+
+    #[test]
+    fn manual_rebuilds_only_when_reassigned_or_uncached() {
+        let reassigned = RefreshInputs { setup: 9, ..INPUTS };
+        assert!(!needs_rebuild(OverlayRefreshDriver::Manual, Some(&entry()), &NEW_BRACKET, 60_000));
+        assert!(needs_rebuild(OverlayRefreshDriver::Manual, Some(&entry()), &reassigned, 1_000));
+        assert!(needs_rebuild(OverlayRefreshDriver::Manual, None, &INPUTS, 1_000));
+    }
+
+    #[test]
+    fn cached_overlays_are_served_until_a_rebuild_is_due() {
+        let mut cache = OverlayReplayCache::default();
+        let driver = OverlayRefreshDriver::FileWatcher;
+        let served_score = |cache: &mut OverlayReplayCache, inputs: RefreshInputs, now_ms: u64, p1_score: u32| {
+            refresh_overlay(cache, 1, driver, inputs, now_ms, |_| {
+                let mut overlay = default_overlay_state(1);
+                overlay.p1.score = p1_score;
+                overlay
+            })
+            .p1
+            .score
+        };
+        assert_eq!(served_score(&mut cache, INPUTS, 1_000, 1), 1);
+        assert_eq!(served_score(&mut cache, NEW_BRACKET, 2_000, 2), 1);
+        assert_eq!(served_score(&mut cache, NEW_REPLAY, 3_000, 3), 3);
+        assert_eq!(cache.refresh[&1].built_at_ms, 3_000);
+    }
+}
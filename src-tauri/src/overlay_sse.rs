@@ -0,0 +1,101 @@
+use crate::{overlay_payload, OverlayQuery};
+use crate::types::*;
+use axum::{
+    extract::{Path as AxumPath, Query, State as AxumState},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use std::{convert::Infallible, future::Future, time::Duration};
+
+/// How often an SSE stream checks its setup for changes.
+const SSE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One setup's overlay state; None when there's no such setup. Setups are
+/// numbered from 1 in the order `/state.json` lists them.
+fn setup_overlay(state: &OverlayServerState, setup_id: u32, live: bool) -> Option<OverlayState> {
+    let idx = (setup_id as usize).checked_sub(1)?;
+    overlay_payload(state, live).setups.into_iter().nth(idx)
+}
+
+async fn setup_overlay_json(state: OverlayServerState, setup_id: u32, live: bool) -> Option<String> {
+    let overlay = tokio::task::spawn_blocking(move || setup_overlay(&state, setup_id, live)).await.ok()??;
+    serde_json::to_string(&overlay).ok()
+}
+
+/// `/overlay/:setup_id`: one setup's overlay state as JSON, for browser
+/// sources that poll.
+pub async fn get_setup_overlay_json(
+    AxumState(state): AxumState<OverlayServerState>,
+    AxumPath(setup_id): AxumPath<u32>,
+    Query(query): Query<OverlayQuery>,
+) -> Response {
+    match setup_overlay_json(state, setup_id, query.live()).await {
+        Some(body) => ([("Content-Type", "application/json"), ("Cache-Control", "no-store")], body).into_response(),
+        None => (StatusCode::NOT_FOUND, format!("No setup {setup_id}.")).into_response(),
+    }
+}
+
+/// Bodies from `fetch`, polled every `interval`: the first right away, then
+/// each one that differs from the last sent.
+fn changes<F, Fut>(interval: Duration, fetch: F) -> impl Stream<Item = String>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = String>,
+{
+    stream::unfold((fetch, None::<String>), move |(fetch, last)| async move {
+        loop {
+            if last.is_some() {
+                tokio::time::sleep(interval).await;
+            }
+            let body = fetch().await;
+            if last.as_deref() == Some(body.as_str()) {
+                continue;
+            }
+            return Some((body.clone(), (fetch, Some(body))));
+        }
+    })
+}
+
+/// `/overlay/:setup_id/events`: the same state as Server-Sent Events. An
+/// `overlay` event is sent on connect and again whenever the state changes,
+/// so OBS browser sources update without the app window open.
+pub async fn setup_overlay_events(
+    AxumState(state): AxumState<OverlayServerState>,
+    AxumPath(setup_id): AxumPath<u32>,
+    Query(query): Query<OverlayQuery>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let live = query.live();
+    let events = changes(SSE_INTERVAL, move || {
+        let state = state.clone();
+        async move { setup_overlay_json(state, setup_id, live).await.unwrap_or_else(|| "null".to_string()) }
+    })
+    .map(|body| Ok(Event::default().event("overlay").data(body)));
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::VecDeque,
+        sync::{Arc, Mutex},
+    };
+
+    #[test]
+    fn only_changed_states_are_sent() {
+        let polls = ["a", "a", "b", "b", "b", "a", "c"].map(str::to_string);
+        let polls = Arc::new(Mutex::new(VecDeque::from(polls)));
+        let fetch = move || {
+            let body = polls.lock().unwrap().pop_front().unwrap_or_else(|| "c".to_string());
+            async move { body }
+        };
+        let sent = tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(changes(Duration::ZERO, fetch).take(4).collect::<Vec<_>>());
+        assert_eq!(sent, vec!["a", "b", "a", "c"]);
+    }
+}
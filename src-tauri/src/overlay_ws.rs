@@ -0,0 +1,300 @@
+use crate::feature_flags::{feature_enabled, overlay_server_gate};
+use crate::overlay_payload;
+use crate::types::{FeatureFlag, OverlayServerState};
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query,
+    },
+    response::Response,
+    routing::get,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+use tokio::{
+    net::TcpListener,
+    sync::broadcast::{self, error::RecvError},
+};
+
+/// Messages a slow client can fall behind by before it skips ahead.
+const CHANNEL_CAPACITY: usize = 64;
+/// How often setup states are checked for changes to push.
+const SETUP_PUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The last message on each topic, sent to sockets as they connect so a
+/// browser source that reloads mid-countdown shows it right away.
+static RETAINED: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+/// One message as sent to sockets: `{"topic": ..., "payload": ...}`. With
+/// `"patch": true` the payload is a JSON merge patch (RFC 7386) against the
+/// topic's previous payload rather than the whole thing.
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    topic: &'a str,
+    payload: &'a T,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    patch: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Published {
+    topic: String,
+    text: String,
+}
+
+fn channel() -> &'static broadcast::Sender<Published> {
+    static CHANNEL: OnceLock<broadcast::Sender<Published>> = OnceLock::new();
+    CHANNEL.get_or_init(|| broadcast::channel(CHANNEL_CAPACITY).0)
+}
+
+fn envelope_text<T: Serialize>(topic: &str, payload: &T, patch: bool) -> Option<String> {
+    match serde_json::to_string(&Envelope { topic, payload, patch }) {
+        Ok(text) => Some(text),
+        Err(e) => {
+            tracing::warn!("Overlay socket message on {} not sent: {}", topic, e);
+            None
+        }
+    }
+}
+
+/// Retain `retained` for sockets that connect later and send `text` to the
+/// ones connected now.
+fn send(topic: &str, retained: String, text: String) {
+    RETAINED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_or_insert_with(HashMap::new)
+        .insert(topic.to_string(), retained);
+    // Err only means no socket is connected.
+    let _ = channel().send(Published { topic: topic.to_string(), text });
+}
+
+/// Push `payload` to every socket subscribed to `topic`. Callable from any
+/// thread; a topic nobody is listening to is only retained.
+pub fn publish<T: Serialize>(topic: &str, payload: &T) {
+    if let Some(text) = envelope_text(topic, payload, false) {
+        send(topic, text.clone(), text);
+    }
+}
+
+/// Push only what changed in `full` to subscribers of `topic`; sockets that
+/// connect later get `full`.
+fn publish_patch(topic: &str, full: &Value, patch: &Value) {
+    let (Some(retained), Some(text)) = (envelope_text(topic, full, false), envelope_text(topic, patch, true)) else {
+        return;
+    };
+    send(topic, retained, text);
+}
+
+/// The JSON merge patch that turns `old` into `new`, or None when they're
+/// equal. Keys that went away are patched to null, which overlays read the
+/// same as a null value.
+fn merge_patch(old: &Value, new: &Value) -> Option<Value> {
+    let (Value::Object(old), Value::Object(new)) = (old, new) else {
+        return (old != new).then(|| new.clone());
+    };
+    let mut patch = Map::new();
+    for (key, value) in new {
+        let changed = match old.get(key) {
+            Some(previous) => merge_patch(previous, value),
+            None => Some(value.clone()),
+        };
+        if let Some(changed) = changed {
+            patch.insert(key.clone(), changed);
+        }
+    }
+    for key in old.keys().filter(|key| !new.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    (!patch.is_empty()).then_some(Value::Object(patch))
+}
+
+fn setup_topic(idx: usize) -> String {
+    format!("setup/{}", idx + 1)
+}
+
+/// Push each setup's public overlay state on `setup/1`, `setup/2`, ... as
+/// replays are parsed and the bracket moves: the whole state first, then
+/// merge patches of what changed. A setup that's deleted gets null. Idle
+/// while no socket is connected; the first pass after one connects sends
+/// whole states again.
+pub async fn push_setup_states(state: OverlayServerState) {
+    let mut last: Vec<Value> = Vec::new();
+    loop {
+        tokio::time::sleep(SETUP_PUSH_INTERVAL).await;
+        if channel().receiver_count() == 0 || !feature_enabled(FeatureFlag::OverlayServer) {
+            last.clear();
+            continue;
+        }
+        let snapshot = state.clone();
+        let Ok(payload) = tokio::task::spawn_blocking(move || overlay_payload(&snapshot, false)).await else {
+            continue;
+        };
+        let setups = payload
+            .setups
+            .iter()
+            .map(|setup| serde_json::to_value(setup).unwrap_or(Value::Null))
+            .collect::<Vec<_>>();
+        for (idx, setup) in setups.iter().enumerate() {
+            let topic = setup_topic(idx);
+            match last.get(idx) {
+                Some(previous) => {
+                    if let Some(patch) = merge_patch(previous, setup) {
+                        publish_patch(&topic, setup, &patch);
+                    }
+                }
+                None => publish(&topic, setup),
+            }
+        }
+        for idx in setups.len()..last.len() {
+            publish(&setup_topic(idx), &Value::Null);
+        }
+        last = setups;
+    }
+}
+
+/// Whether a socket subscribed to `topics` gets `topic`: no topics means
+/// everything, and "setup" covers "setup/1".
+fn wants(topics: &[String], topic: &str) -> bool {
+    topics.is_empty()
+        || topics.iter().any(|wanted| {
+            topic == wanted || topic.strip_prefix(wanted.as_str()).is_some_and(|rest| rest.starts_with('/'))
+        })
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SocketQuery {
+    /// Comma-separated topics, e.g. "countdowns"; everything when unset.
+    topics: Option<String>,
+}
+
+/// GET /ws: a WebSocket that pushes overlay messages as they happen.
+pub async fn overlay_socket(ws: WebSocketUpgrade, Query(query): Query<SocketQuery>) -> Response {
+    let topics = query
+        .topics
+        .unwrap_or_default()
+        .split(',')
+        .map(|topic| topic.trim().to_string())
+        .filter(|topic| !topic.is_empty())
+        .collect::<Vec<_>>();
+    ws.on_upgrade(move |socket| serve_socket(socket, topics))
+}
+
+async fn serve_socket(mut socket: WebSocket, topics: Vec<String>) {
+    // Subscribe before reading the retained messages so nothing published
+    // in between is missed.
+    let mut rx = channel().subscribe();
+    let retained = RETAINED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .flatten()
+        .filter(|(topic, _)| wants(&topics, topic))
+        .map(|(_, text)| text.clone())
+        .collect::<Vec<_>>();
+    for text in retained {
+        if socket.send(Message::Text(text)).await.is_err() {
+            return;
+        }
+    }
+    loop {
+        tokio::select! {
+            published = rx.recv() => match published {
+                Ok(published) if wants(&topics, &published.topic) => {
+                    if socket.send(Message::Text(published.text)).await.is_err() {
+                        break;
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Serve `/ws` alone on `port`, for browser sources that shouldn't share
+/// the main overlay server's port.
+pub async fn start_socket_server(port: u16) {
+    let addr = format!("127.0.0.1:{port}");
+    let router = Router::new()
+        .route("/ws", get(overlay_socket))
+        .layer(axum::middleware::from_fn(overlay_server_gate));
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Overlay socket server failed to bind {addr}: {e}");
+            return;
+        }
+    };
+    tracing::info!("Overlay socket server listening at ws://{addr}/ws");
+    if let Err(e) = axum::serve(listener, router).await {
+        tracing::error!("Overlay socket server error: {e}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn topics(topics: &[&str]) -> Vec<String> {
+        topics.iter().map(|topic| topic.to_string()).collect()
+    }
+
+    #[test]
+    fn patches_carry_only_what_changed() {
+        let old = json!({ "p1": { "tag": "Mango", "stocks": 4, "percent": 0 }, "meta": { "round": "WF" } });
+        let new = json!({ "p1": { "tag": "Mango", "stocks": 3, "percent": 0 }, "meta": { "round": "WF" } });
+        assert_eq!(merge_patch(&old, &new), Some(json!({ "p1": { "stocks": 3 } })));
+    }
+
+    #[test]
+    fn removed_keys_are_patched_to_null() {
+        assert_eq!(merge_patch(&json!({ "a": 1, "note": "x" }), &json!({ "a": 1 })), Some(json!({ "note": null })));
+    }
+
+    #[test]
+    fn equal_states_need_no_patch() {
+        let state = json!({ "p1": { "stocks": 4 } });
+        assert_eq!(merge_patch(&state, &state), None);
+    }
+
+    #[test]
+    fn non_objects_are_replaced_whole() {
+        assert_eq!(merge_patch(&json!([1, 2]), &json!([1, 3])), Some(json!([1, 3])));
+        assert_eq!(merge_patch(&json!({ "a": 1 }), &Value::Null), Some(Value::Null));
+    }
+
+    #[test]
+    fn sockets_without_topics_get_everything() {
+        assert!(wants(&[], "countdowns"));
+        assert!(wants(&[], "setup/1"));
+    }
+
+    #[test]
+    fn a_topic_covers_its_subtopics_only() {
+        assert!(wants(&topics(&["setup"]), "setup/2"));
+        assert!(wants(&topics(&["setup/1"]), "setup/1"));
+        assert!(!wants(&topics(&["setup/1"]), "setup/12"));
+        assert!(!wants(&topics(&["setup"]), "setups"));
+        assert!(!wants(&topics(&["countdowns"]), "setup/1"));
+    }
+
+    #[test]
+    fn envelopes_flag_patches_only() {
+        let whole = envelope_text("setup/1", &json!({ "a": 1 }), false).unwrap();
+        assert_eq!(whole, r#"{"topic":"setup/1","payload":{"a":1}}"#);
+        let patch = envelope_text("setup/1", &json!({ "a": 2 }), true).unwrap();
+        assert_eq!(patch, r#"{"topic":"setup/1","payload":{"a":2},"patch":true}"#);
+    }
+}
@@ -0,0 +1,554 @@
+use crate::config::*;
+use crate::dolphin::{list_dolphin_like_pids, playback_output_dir, read_proc_cmdline};
+use crate::replay_frames::replay_frames;
+use crate::startgg::read_bracket_set_replay_paths;
+use crate::types::{PlaybackBufferStatus, SharedSetupStore};
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread,
+};
+use tauri::{AppHandle, Manager, State};
+
+/// First frame of a Melee replay (the pre-game countdown).
+pub const PLAYBACK_FIRST_FRAME: i32 = -123;
+
+// ── Communication file ──────────────────────────────────────────────────
+//
+// Playback Dolphin is started with `-i <file>` and keeps watching that file.
+// Whenever the file's `commandId` changes it re-reads it and switches to
+// the new replay, frame range or queue, so a running Dolphin can be driven
+// without relaunching it.
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackQueueEntry {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_frame: Option<i32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackCommand {
+    /// "normal" plays `replay`; "queue" plays `queue` in order.
+    pub mode: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_frame: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_frame: Option<i32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub queue: Vec<PlaybackQueueEntry>,
+    pub is_real_time_mode: bool,
+    pub command_id: String,
+}
+
+/// Frame range covering a whole replay. The end frame is left open when
+/// the replay can't be read yet.
+pub fn replay_frame_range(replay_path: &Path, start_frame: Option<i32>) -> (i32, Option<i32>) {
+    let start = start_frame.unwrap_or(PLAYBACK_FIRST_FRAME);
+    let end = replay_frames(replay_path).ok().map(|frames| {
+        let end = frames.last_frame.saturating_sub(1);
+        if end <= start {
+            start + 1
+        } else {
+            end
+        }
+    });
+    (start, end)
+}
+
+fn queue_entry(path: &Path) -> PlaybackQueueEntry {
+    let (start, end) = replay_frame_range(path, None);
+    PlaybackQueueEntry {
+        path: path.to_string_lossy().to_string(),
+        start_frame: Some(start),
+        end_frame: end,
+    }
+}
+
+/// PlaybackSession tracks the communication file of one running playback
+/// Dolphin and rewrites it when the operator changes what it plays.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackSession {
+    pub setup_id: u32,
+    pub comm_path: PathBuf,
+    /// Last command written; None until the first write
+    pub command: Option<PlaybackCommand>,
+    /// Buffer telemetry while following a growing replay
+    pub buffer: Option<PlaybackBufferStatus>,
+    /// Bumped each time a follower thread is started; older threads exit
+    #[serde(skip)]
+    pub follow_id: u64,
+    /// Bumped on every write and folded into the commandId
+    #[serde(skip)]
+    seq: u64,
+    #[serde(skip)]
+    base_id: String,
+}
+
+impl PlaybackSession {
+    pub fn new(setup_id: u32, comm_path: PathBuf, base_id: &str) -> Self {
+        PlaybackSession {
+            setup_id,
+            comm_path,
+            command: None,
+            buffer: None,
+            follow_id: 0,
+            seq: 0,
+            base_id: base_id.to_string(),
+        }
+    }
+
+    /// Write `next` to the communication file with a fresh commandId.
+    /// Returns false without touching the file when nothing changed, so
+    /// Dolphin doesn't restart the current replay.
+    fn write(&mut self, mut next: PlaybackCommand) -> Result<bool, String> {
+        if let Some(current) = self.command.as_ref() {
+            next.command_id = current.command_id.clone();
+            if *current == next {
+                return Ok(false);
+            }
+        }
+        self.seq += 1;
+        next.command_id = format!("{}-{}", self.base_id, self.seq);
+        let contents = serde_json::to_string_pretty(&next).map_err(|e| e.to_string())?;
+        // Write then rename so Dolphin never reads a half-written file.
+        let tmp_path = self.comm_path.with_extension("json.tmp");
+        fs::write(&tmp_path, contents)
+            .map_err(|e| format!("write playback config {}: {e}", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.comm_path)
+            .map_err(|e| format!("write playback config {}: {e}", self.comm_path.display()))?;
+        self.command = Some(next);
+        Ok(true)
+    }
+
+    /// Play a single replay from `start_frame` (or the start of the game).
+    pub fn load_replay(&mut self, replay_path: &Path, start_frame: Option<i32>) -> Result<bool, String> {
+        let (start, end) = replay_frame_range(replay_path, start_frame);
+        self.write(PlaybackCommand {
+            mode: "normal".to_string(),
+            replay: Some(replay_path.to_string_lossy().to_string()),
+            start_frame: Some(start),
+            end_frame: end,
+            queue: Vec::new(),
+            is_real_time_mode: false,
+            command_id: String::new(),
+        })
+    }
+
+    /// Play a game whose frame range was computed ahead of time.
+    pub fn load_prepared(&mut self, entry: &PlaybackQueueEntry) -> Result<bool, String> {
+        self.write(PlaybackCommand {
+            mode: "normal".to_string(),
+            replay: Some(entry.path.clone()),
+            start_frame: entry.start_frame,
+            end_frame: entry.end_frame,
+            queue: Vec::new(),
+            is_real_time_mode: false,
+            command_id: String::new(),
+        })
+    }
+
+    /// Follow a replay that is still being written. Dolphin waits for new
+    /// frames instead of stopping at the end of the file; `end_frame` is set
+    /// once the game is over so playback finishes cleanly.
+    pub fn follow_replay(&mut self, replay_path: &Path, end_frame: Option<i32>) -> Result<bool, String> {
+        self.write(PlaybackCommand {
+            mode: "normal".to_string(),
+            replay: Some(replay_path.to_string_lossy().to_string()),
+            start_frame: Some(PLAYBACK_FIRST_FRAME),
+            end_frame,
+            queue: Vec::new(),
+            is_real_time_mode: true,
+            command_id: String::new(),
+        })
+    }
+
+    /// Play what another session is playing, as a mirror of its setup.
+    pub fn copy_command(&mut self, command: &PlaybackCommand) -> Result<bool, String> {
+        self.write(command.clone())
+    }
+
+    /// Whether this session is currently following `replay_path` in
+    /// real-time mode.
+    pub fn is_following(&self, replay_path: &Path) -> bool {
+        self.command.as_ref().is_some_and(|command| {
+            command.is_real_time_mode
+                && command.replay.as_deref() == Some(replay_path.to_string_lossy().as_ref())
+        })
+    }
+
+    /// Restart the current replay at `frame`.
+    pub fn seek(&mut self, frame: i32) -> Result<bool, String> {
+        let replay = self
+            .command
+            .as_ref()
+            .filter(|command| command.mode == "normal")
+            .and_then(|command| command.replay.clone())
+            .ok_or_else(|| "Seeking needs a single replay loaded, not a queue.".to_string())?;
+        self.load_replay(Path::new(&replay), Some(frame))
+    }
+
+    /// Replace the queue. Dolphin starts again from the first entry whenever
+    /// the queue is rewritten, so callers pass only the games still to play.
+    pub fn set_queue(&mut self, replay_paths: &[PathBuf]) -> Result<bool, String> {
+        if replay_paths.is_empty() {
+            return Err("Playback queue is empty.".to_string());
+        }
+        self.write(PlaybackCommand {
+            mode: "queue".to_string(),
+            replay: None,
+            start_frame: None,
+            end_frame: None,
+            queue: replay_paths.iter().map(|path| queue_entry(path)).collect(),
+            is_real_time_mode: false,
+            command_id: String::new(),
+        })
+    }
+
+    /// Add games after the current replay or queue. A single loaded replay
+    /// becomes the first queue entry, resuming from its original start frame.
+    pub fn enqueue(&mut self, replay_paths: &[PathBuf]) -> Result<bool, String> {
+        let mut queue = match self.command.as_ref() {
+            Some(command) if command.mode == "queue" => command.queue.clone(),
+            Some(command) => command
+                .replay
+                .as_ref()
+                .map(|replay| {
+                    vec![PlaybackQueueEntry {
+                        path: replay.clone(),
+                        start_frame: command.start_frame,
+                        end_frame: command.end_frame,
+                    }]
+                })
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+        queue.extend(replay_paths.iter().map(|path| queue_entry(path)));
+        if queue.is_empty() {
+            return Err("Playback queue is empty.".to_string());
+        }
+        self.write(PlaybackCommand {
+            mode: "queue".to_string(),
+            replay: None,
+            start_frame: None,
+            end_frame: None,
+            queue,
+            is_real_time_mode: false,
+            command_id: String::new(),
+        })
+    }
+
+    /// Check the communication file before Dolphin is started on it: every
+    /// replay it names must exist, and the file must hold the last command
+    /// written. A file that doesn't (cut short by a crash, or left over from
+    /// an earlier run) is written again once.
+    pub fn ensure_comm_file(&mut self) -> Result<(), String> {
+        let command = self
+            .command
+            .clone()
+            .ok_or_else(|| "No playback command written yet.".to_string())?;
+        if let Some(missing) = command_replays(&command).into_iter().find(|path| !Path::new(path).is_file()) {
+            return Err(format!("Replay not found at {missing}"));
+        }
+        let Err(problem) = check_comm_file(&self.comm_path, &command.command_id) else {
+            return Ok(());
+        };
+        tracing::warn!("Playback config {} {}; regenerating", self.comm_path.display(), problem);
+        self.command = None;
+        self.write(command)?;
+        let command_id = self.command.as_ref().map(|command| command.command_id.as_str()).unwrap_or_default();
+        check_comm_file(&self.comm_path, command_id)
+            .map_err(|problem| format!("Playback config {} {problem}", self.comm_path.display()))
+    }
+}
+
+// ── Recovery ────────────────────────────────────────────────────────────
+
+/// Every replay a command points Dolphin at.
+fn command_replays(command: &PlaybackCommand) -> Vec<&str> {
+    command
+        .replay
+        .iter()
+        .map(String::as_str)
+        .chain(command.queue.iter().map(|entry| entry.path.as_str()))
+        .collect()
+}
+
+/// Err describing why `contents` isn't the command `command_id`.
+fn check_comm_contents(contents: &str, command_id: &str) -> Result<(), String> {
+    let value = serde_json::from_str::<Value>(contents).map_err(|e| format!("is unreadable ({e})"))?;
+    match value.get("commandId").and_then(Value::as_str) {
+        Some(found) if found == command_id => Ok(()),
+        Some(found) => Err(format!("has commandId {found}, expected {command_id}")),
+        None => Err("has no commandId".to_string()),
+    }
+}
+
+fn check_comm_file(path: &Path, command_id: &str) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("can't be read ({e})"))?;
+    check_comm_contents(&contents, command_id)
+}
+
+fn is_playback_config(name: &str) -> bool {
+    name.starts_with("playback_") && (name.ends_with(".json") || name.ends_with(".json.tmp"))
+}
+
+/// Communication files a running Dolphin was started with (`-i <file>`),
+/// e.g. one that outlived a crash of this app.
+fn comm_files_in_use() -> HashSet<PathBuf> {
+    list_dolphin_like_pids()
+        .into_iter()
+        .filter_map(|pid| read_proc_cmdline(pid).ok())
+        .filter_map(|cmdline| {
+            let index = cmdline.iter().position(|arg| arg == "-i")?;
+            cmdline.get(index + 1).map(PathBuf::from)
+        })
+        .collect()
+}
+
+/// Delete playback configs left behind by earlier runs, so none can be
+/// picked up again later. Files a running Dolphin still reads are kept.
+/// Returns how many were removed.
+pub fn prune_stale_playback_configs() -> usize {
+    let dir = playback_output_dir();
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return 0;
+    };
+    let in_use = comm_files_in_use();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let stale = entry.file_name().to_str().is_some_and(is_playback_config)
+            && !in_use.contains(&path)
+            && !in_use.contains(&path.with_extension(""));
+        if !stale {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => removed += 1,
+            Err(e) => tracing::warn!("Failed to remove stale playback config {}: {}", path.display(), e),
+        }
+    }
+    if removed > 0 {
+        tracing::info!("Removed {} stale playback config(s) from {}", removed, dir.display());
+    }
+    removed
+}
+
+// ── Prepared sets ───────────────────────────────────────────────────────
+//
+// Working out a replay's frame range parses it, which takes long enough to
+// leave a visible gap between games. Archived sets can have every game's
+// playback config computed up front.
+
+/// Playback configs for every game of an archived set, in game order.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PreparedSetPlayback {
+    pub set_id: u64,
+    pub config_path: String,
+    pub games: Vec<PlaybackQueueEntry>,
+    pub prepared_at_ms: u64,
+}
+
+static PREPARED_SETS: Mutex<Vec<PreparedSetPlayback>> = Mutex::new(Vec::new());
+
+/// Parse every replay of the set at once and keep the resulting configs.
+pub fn prepare_set(config_path: &str, set_id: u64) -> Result<PreparedSetPlayback, String> {
+    let paths = read_bracket_set_replay_paths(config_path, set_id)?;
+    if let Some(missing) = paths.iter().find(|path| !path.is_file()) {
+        return Err(format!("Replay not found at {}", missing.display()));
+    }
+    let games = thread::scope(|scope| {
+        let handles = paths
+            .iter()
+            .map(|path| scope.spawn(move || queue_entry(path)))
+            .collect::<Vec<_>>();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().map_err(|_| "Replay parse thread panicked.".to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    })?;
+    let prepared = PreparedSetPlayback {
+        set_id,
+        config_path: config_path.to_string(),
+        games,
+        prepared_at_ms: now_ms(),
+    };
+    let mut guard = PREPARED_SETS.lock().unwrap_or_else(|e| e.into_inner());
+    guard.retain(|existing| existing.set_id != set_id);
+    guard.push(prepared.clone());
+    Ok(prepared)
+}
+
+pub fn prepared_set(set_id: u64) -> Option<PreparedSetPlayback> {
+    PREPARED_SETS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .find(|prepared| prepared.set_id == set_id)
+        .cloned()
+}
+
+fn bracket_config_arg(config_path: Option<String>) -> String {
+    config_path
+        .filter(|raw| !raw.trim().is_empty())
+        .unwrap_or_else(|| startgg_sim_config_path().to_string_lossy().to_string())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Run `f` on the playback session for `setup_id`, dropping the session if
+/// its Dolphin is gone.
+pub(crate) fn with_playback_session<F, R>(store: &State<'_, SharedSetupStore>, setup_id: u32, f: F) -> Result<R, String>
+where
+    F: FnOnce(&mut PlaybackSession) -> Result<R, String>,
+{
+    let mut guard = store.lock().map_err(|e| e.to_string())?;
+    let alive = match guard.processes.get_mut(&setup_id) {
+        Some(child) => matches!(child.try_wait(), Ok(None)),
+        None => false,
+    };
+    if !alive {
+        guard.playback_sessions.remove(&setup_id);
+        return Err(format!("No playback Dolphin running for setup {setup_id}."));
+    }
+    let session = guard
+        .playback_sessions
+        .get_mut(&setup_id)
+        .ok_or_else(|| format!("Setup {setup_id} is not running replay playback."))?;
+    f(session)
+}
+
+fn existing_replay(raw: &str) -> Result<PathBuf, String> {
+    let path = crate::config::resolve_repo_path(raw.trim());
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("Replay not found at {}", path.display()))
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_session(
+    setup_id: u32,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Option<PlaybackSession>, String> {
+    let guard = store.lock().map_err(|e| e.to_string())?;
+    Ok(guard.playback_sessions.get(&setup_id).cloned())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_load_replay(
+    setup_id: u32,
+    replay_path: String,
+    start_frame: Option<i32>,
+    store: State<'_, SharedSetupStore>,
+) -> Result<bool, String> {
+    let path = existing_replay(&replay_path)?;
+    with_playback_session(&store, setup_id, |session| session.load_replay(&path, start_frame))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_seek(setup_id: u32, frame: i32, store: State<'_, SharedSetupStore>) -> Result<bool, String> {
+    with_playback_session(&store, setup_id, |session| session.seek(frame))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_set_queue(
+    setup_id: u32,
+    replay_paths: Vec<String>,
+    store: State<'_, SharedSetupStore>,
+) -> Result<bool, String> {
+    let paths = replay_paths.iter().map(|raw| existing_replay(raw)).collect::<Result<Vec<_>, _>>()?;
+    with_playback_session(&store, setup_id, |session| session.set_queue(&paths))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_enqueue(
+    setup_id: u32,
+    replay_paths: Vec<String>,
+    store: State<'_, SharedSetupStore>,
+) -> Result<bool, String> {
+    let paths = replay_paths.iter().map(|raw| existing_replay(raw)).collect::<Result<Vec<_>, _>>()?;
+    with_playback_session(&store, setup_id, |session| session.enqueue(&paths))
+}
+
+/// Precompute playback configs for every game of an archived set so
+/// switching between its games doesn't wait on a replay parse. Uses the
+/// current bracket config unless `config_path` is given.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn prepare_set_playback(set_id: u64, config_path: Option<String>) -> Result<PreparedSetPlayback, String> {
+    prepare_set(&bracket_config_arg(config_path), set_id)
+}
+
+/// Play game `game_number` (1-based) of a set, preparing the set first if
+/// it hasn't been.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn playback_load_set_game(
+    setup_id: u32,
+    set_id: u64,
+    game_number: usize,
+    config_path: Option<String>,
+    app: AppHandle,
+) -> Result<bool, String> {
+    let prepared = match prepared_set(set_id) {
+        Some(prepared) => prepared,
+        None => prepare_set(&bracket_config_arg(config_path), set_id)?,
+    };
+    let entry = game_number
+        .checked_sub(1)
+        .and_then(|idx| prepared.games.get(idx))
+        .ok_or_else(|| format!("Set {set_id} has no game {game_number}; it has {}.", prepared.games.len()))?;
+    with_playback_session(&app.state::<SharedSetupStore>(), setup_id, |session| session.load_prepared(entry))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WRITTEN: &str = r#"{"mode":"normal","replay":"/r/Game_1.slp","isRealTimeMode":false,"commandId":"3-1700-2"}"#;
+
+    #[test]
+    fn a_comm_file_holding_the_last_command_checks_out() {
+        assert!(check_comm_contents(WRITTEN, "3-1700-2").is_ok());
+    }
+
+    #[test]
+    fn a_comm_file_holding_an_older_command_is_stale() {
+        assert_eq!(check_comm_contents(WRITTEN, "3-1700-3").unwrap_err(), "has commandId 3-1700-2, expected 3-1700-3");
+        assert_eq!(check_comm_contents(r#"{"mode":"normal"}"#, "3-1700-2").unwrap_err(), "has no commandId");
+    }
+
+    #[test]
+    fn a_comm_file_cut_off_mid_write_is_unreadable() {
+        assert!(check_comm_contents(r#"{"mode":"normal","repl"#, "3-1700-2").is_err());
+    }
+
+    #[test]
+    fn playback_configs_are_known_by_name() {
+        assert!(is_playback_config("playback_3-1700.json"));
+        assert!(is_playback_config("playback_3-1700.json.tmp"));
+        assert!(!is_playback_config("playback_3-1700-unmerged.dtm"));
+        assert!(!is_playback_config("slippi_watch_label.txt"));
+    }
+}
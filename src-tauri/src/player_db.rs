@@ -0,0 +1,333 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::startgg::extract_slippi_code;
+use crate::types::{AllSetupsState, PlayerSocials, PlayerState, StartggEntrantNode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+/// Socials and location for a player, keyed like ratings (connect code,
+/// else folded tag). Filled from start.gg entrants on every live fetch so
+/// lower thirds don't need hand-entered data.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlayerProfile {
+    pub tag: String,
+    pub slippi_code: Option<String>,
+    pub socials: PlayerSocials,
+    /// Country name as start.gg reports it
+    pub country: Option<String>,
+    /// ISO 3166-1 alpha-2, for flag assets
+    pub country_code: Option<String>,
+    pub updated_at_ms: u64,
+}
+
+type PlayerDb = BTreeMap<String, PlayerProfile>;
+
+/// Loaded on first use; the overlay reads it on every poll.
+static PLAYER_DB: Mutex<Option<PlayerDb>> = Mutex::new(None);
+
+pub fn player_db_path() -> PathBuf {
+    repo_root().join("players.json")
+}
+
+fn read_player_db() -> PlayerDb {
+    let path = player_db_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return PlayerDb::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        tracing::warn!("Ignoring unreadable player DB {}: {}", path.display(), e);
+        PlayerDb::new()
+    })
+}
+
+fn with_player_db<R>(f: impl FnOnce(&mut PlayerDb) -> R) -> R {
+    let mut guard = PLAYER_DB.lock().unwrap_or_else(|e| e.into_inner());
+    let db = guard.get_or_insert_with(read_player_db);
+    f(db)
+}
+
+fn write_player_db(db: &PlayerDb) -> Result<(), String> {
+    let path = player_db_path();
+    let body = serde_json::to_vec_pretty(db).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write player DB {}: {e}", path.display()))
+}
+
+/// ISO code for the country names start.gg uses, or a code passed through.
+pub fn country_code_for(country: &str) -> Option<String> {
+    let trimmed = country.trim();
+    if trimmed.len() == 2 && trimmed.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Some(trimmed.to_ascii_uppercase());
+    }
+    let code = match trimmed.to_lowercase().as_str() {
+        "united states" | "united states of america" | "usa" => "US",
+        "canada" => "CA",
+        "mexico" => "MX",
+        "puerto rico" => "PR",
+        "dominican republic" => "DO",
+        "brazil" => "BR",
+        "argentina" => "AR",
+        "chile" => "CL",
+        "peru" => "PE",
+        "colombia" => "CO",
+        "united kingdom" | "uk" | "great britain" => "GB",
+        "ireland" => "IE",
+        "france" => "FR",
+        "germany" => "DE",
+        "spain" => "ES",
+        "portugal" => "PT",
+        "italy" => "IT",
+        "netherlands" => "NL",
+        "belgium" => "BE",
+        "switzerland" => "CH",
+        "austria" => "AT",
+        "sweden" => "SE",
+        "norway" => "NO",
+        "finland" => "FI",
+        "denmark" => "DK",
+        "poland" => "PL",
+        "russia" => "RU",
+        "israel" => "IL",
+        "japan" => "JP",
+        "south korea" | "korea" | "korea, republic of" => "KR",
+        "china" => "CN",
+        "taiwan" => "TW",
+        "hong kong" => "HK",
+        "singapore" => "SG",
+        "philippines" => "PH",
+        "australia" => "AU",
+        "new zealand" => "NZ",
+        _ => return None,
+    };
+    Some(code.to_string())
+}
+
+fn non_empty(raw: Option<&str>) -> Option<String> {
+    raw.map(str::trim).filter(|value| !value.is_empty()).map(str::to_string)
+}
+
+/// What start.gg knows about an entrant's player. Team entrants are skipped.
+pub fn profile_from_entrant(entrant: &StartggEntrantNode, now_ms: u64) -> Option<PlayerProfile> {
+    let participants = entrant.participants.as_deref()?;
+    let [participant] = participants else {
+        return None;
+    };
+    let tag = non_empty(participant.gamer_tag.as_deref())
+        .or_else(|| non_empty(participant.player.as_ref()?.gamer_tag.as_deref()))
+        .or_else(|| non_empty(entrant.name.as_deref()))?;
+    let mut profile = PlayerProfile {
+        tag,
+        slippi_code: extract_slippi_code(entrant).as_deref().and_then(normalize_slippi_code),
+        updated_at_ms: now_ms,
+        ..Default::default()
+    };
+    if let Some(user) = participant.user.as_ref() {
+        for auth in user.authorizations.as_ref().into_iter().flatten() {
+            let username = non_empty(auth.external_username.as_deref());
+            match auth.kind.as_deref().unwrap_or_default().to_ascii_uppercase().as_str() {
+                "TWITTER" => profile.socials.twitter = username,
+                "DISCORD" => profile.socials.discord = username,
+                "TWITCH" => profile.socials.twitch = username,
+                _ => {}
+            }
+        }
+        profile.country = non_empty(user.location.as_ref().and_then(|location| location.country.as_deref()));
+        profile.country_code = profile.country.as_deref().and_then(country_code_for);
+    }
+    Some(profile)
+}
+
+/// Merge scraped fields into an existing profile. Only values start.gg
+/// actually returned overwrite, so hand-entered values survive a refetch.
+/// Returns whether anything changed.
+fn merge_profile(existing: &mut PlayerProfile, scraped: PlayerProfile) -> bool {
+    let before = existing.clone();
+    existing.tag = scraped.tag;
+    let merge = |target: &mut Option<String>, value: Option<String>| {
+        if value.is_some() {
+            *target = value;
+        }
+    };
+    merge(&mut existing.slippi_code, scraped.slippi_code);
+    merge(&mut existing.socials.twitter, scraped.socials.twitter);
+    merge(&mut existing.socials.discord, scraped.socials.discord);
+    merge(&mut existing.socials.twitch, scraped.socials.twitch);
+    merge(&mut existing.country, scraped.country);
+    merge(&mut existing.country_code, scraped.country_code);
+    let changed = *existing != before;
+    if changed {
+        existing.updated_at_ms = scraped.updated_at_ms;
+    }
+    changed
+}
+
+/// Store socials and country for every entrant of a live fetch.
+pub fn update_player_db_from_entrants(entrants: &[StartggEntrantNode]) -> Result<usize, String> {
+    let now = now_ms();
+    with_player_db(|db| {
+        let mut changed = 0;
+        for profile in entrants.iter().filter_map(|entrant| profile_from_entrant(entrant, now)) {
+            let key = player_key(&profile.tag, profile.slippi_code.as_deref());
+            match db.get_mut(&key) {
+                Some(existing) => {
+                    if merge_profile(existing, profile) {
+                        changed += 1;
+                    }
+                }
+                None => {
+                    db.insert(key, profile);
+                    changed += 1;
+                }
+            }
+        }
+        if changed > 0 {
+            write_player_db(db)?;
+        }
+        Ok(changed)
+    })
+}
+
+fn apply_profile(player: &mut PlayerState, db: &PlayerDb) {
+    let profile = player
+        .connect_code
+        .as_deref()
+        .and_then(|code| db.get(&player_key(&player.tag, Some(code))))
+        .or_else(|| db.get(&player_key(&player.tag, None)));
+    let Some(profile) = profile else {
+        return;
+    };
+    if player.handle.is_none() {
+        player.handle = profile.socials.twitter.as_ref().map(|handle| format!("@{}", handle.trim_start_matches('@')));
+    }
+    if player.country_code.is_none() {
+        player.country_code = profile.country_code.clone();
+    }
+    if profile.socials != PlayerSocials::default() {
+        player.socials = Some(profile.socials.clone());
+    }
+}
+
+/// Fill handles, socials and flags from the player DB for lower thirds.
+pub fn apply_overlay_profiles(state: &mut AllSetupsState) {
+    with_player_db(|db| {
+        if db.is_empty() {
+            return;
+        }
+        for setup in state.setups.iter_mut() {
+            apply_profile(&mut setup.p1, db);
+            apply_profile(&mut setup.p2, db);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn list_player_profiles() -> Vec<PlayerProfile> {
+    with_player_db(|db| db.values().cloned().collect())
+}
+
+/// Correct a profile by hand; later start.gg fetches only overwrite fields
+/// start.gg returns.
+#[tauri::command]
+#[specta::specta]
+pub fn save_player_profile(mut profile: PlayerProfile) -> Result<PlayerProfile, String> {
+    let params = json!({ "tag": profile.tag, "slippiCode": profile.slippi_code });
+    audited("savePlayerProfile", params, || {
+        if profile.tag.trim().is_empty() {
+            return Err("Player tag is required.".to_string());
+        }
+        profile.slippi_code = profile.slippi_code.as_deref().and_then(normalize_slippi_code);
+        if profile.country_code.is_none() {
+            profile.country_code = profile.country.as_deref().and_then(country_code_for);
+        }
+        profile.updated_at_ms = now_ms();
+        let key = player_key(&profile.tag, profile.slippi_code.as_deref());
+        with_player_db(|db| {
+            db.insert(key, profile.clone());
+            write_player_db(db)
+        })?;
+        Ok(profile)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn amsa() -> StartggEntrantNode {
+        serde_json::from_value(json!({
+            "id": 1,
+            "name": "SPONSOR | aMSa",
+            "participants": [{
+                "gamerTag": "aMSa",
+                "user": {
+                    "location": { "country": "Japan" },
+                    "authorizations": [
+                        { "type": "TWITTER", "externalUsername": "amsa_yoshi" },
+                        { "type": "TWITCH", "externalUsername": "amsa" },
+                        { "type": "SLIPPI", "externalUsername": "amsa#1" }
+                    ]
+                }
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn the_gamer_tag_is_used_over_the_sponsored_name() {
+        assert_eq!(profile_from_entrant(&amsa(), 5).unwrap().tag, "aMSa");
+    }
+
+    #[test]
+    fn socials_and_the_connect_code_come_from_linked_accounts() {
+        let profile = profile_from_entrant(&amsa(), 5).unwrap();
+        assert_eq!(profile.slippi_code.as_deref(), Some("AMSA#1"));
+        assert_eq!(profile.socials.twitter.as_deref(), Some("amsa_yoshi"));
+        assert_eq!(profile.socials.twitch.as_deref(), Some("amsa"));
+        assert_eq!(profile.socials.discord, None);
+    }
+
+    #[test]
+    fn the_country_is_stored_with_its_code() {
+        let profile = profile_from_entrant(&amsa(), 5).unwrap();
+        assert_eq!(profile.country.as_deref(), Some("Japan"));
+        assert_eq!(profile.country_code.as_deref(), Some("JP"));
+    }
+
+    #[test]
+    fn teams_get_no_profile() {
+        let entrant: StartggEntrantNode = serde_json::from_value(json!({
+            "id": 2,
+            "name": "aMSa / Kodorin",
+            "participants": [{ "gamerTag": "aMSa" }, { "gamerTag": "Kodorin" }]
+        }))
+        .unwrap();
+        assert!(profile_from_entrant(&entrant, 5).is_none());
+    }
+
+    #[test]
+    fn a_refetch_keeps_hand_entered_socials() {
+        let profile = profile_from_entrant(&amsa(), 5).unwrap();
+        let mut existing = PlayerProfile {
+            socials: PlayerSocials { discord: Some("amsa".to_string()), ..Default::default() },
+            ..profile.clone()
+        };
+        assert!(!merge_profile(&mut existing, profile));
+        assert_eq!(existing.socials.discord.as_deref(), Some("amsa"));
+    }
+
+    #[test]
+    fn a_changed_social_is_merged_and_stamped() {
+        let mut existing = profile_from_entrant(&amsa(), 5).unwrap();
+        let mut scraped = existing.clone();
+        scraped.socials.twitter = Some("amsa_new".to_string());
+        scraped.updated_at_ms = 9;
+        assert!(merge_profile(&mut existing, scraped));
+        assert_eq!(existing.socials.twitter.as_deref(), Some("amsa_new"));
+        assert_eq!(existing.updated_at_ms, 9);
+    }
+}
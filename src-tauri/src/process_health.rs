@@ -0,0 +1,294 @@
+use crate::config::*;
+use crate::dolphin::launch_dolphin_for_setup_internal;
+use crate::mirror::mirror_links;
+use crate::slippi::assign_stream;
+use crate::types::*;
+use serde::Serialize;
+use specta::Type;
+use std::{
+    collections::HashMap,
+    os::unix::process::ExitStatusExt,
+    path::Path,
+    process::ExitStatus,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with a `SetupProcessStatus` when a setup's Dolphin exits on its
+/// own, and again after an automatic relaunch.
+pub const SETUP_PROCESS_STATUS_EVENT: &str = "setup-process-status";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+/// Relaunches allowed per setup within `RELAUNCH_WINDOW` before a crash
+/// loop is left for the operator.
+const MAX_RELAUNCHES: usize = 3;
+const RELAUNCH_WINDOW: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupProcessState {
+    /// Exited cleanly, e.g. closed by hand. Never relaunched.
+    Exited,
+    /// Exited with an error code or was killed by a signal.
+    Crashed,
+    Relaunched,
+    /// Relaunching failed or was given up on; see `message`.
+    RelaunchFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupProcessStatus {
+    pub setup_id: u32,
+    pub state: SetupProcessState,
+    /// None when the process was killed by a signal, or wasn't our child
+    pub exit_code: Option<i32>,
+    pub signal: Option<i32>,
+    pub message: String,
+    pub at_ms: u64,
+}
+
+/// How a setup's Dolphin went away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ProcessExit {
+    code: Option<i32>,
+    signal: Option<i32>,
+}
+
+impl ProcessExit {
+    fn from_status(status: ExitStatus) -> Self {
+        ProcessExit { code: status.code(), signal: status.signal() }
+    }
+
+    /// A Dolphin started by the Slippi Launcher isn't our child, so only
+    /// its disappearance is known. It counts as a crash.
+    fn unknown() -> Self {
+        ProcessExit { code: None, signal: None }
+    }
+
+    fn is_crash(&self) -> bool {
+        self.code != Some(0)
+    }
+
+    fn describe(&self) -> String {
+        match (self.code, self.signal) {
+            (Some(code), _) => format!("exited with code {code}"),
+            (None, Some(signal)) => format!("was killed by signal {signal}"),
+            (None, None) => "is no longer running".to_string(),
+        }
+    }
+}
+
+/// Recent relaunches per setup, to stop relaunching a Dolphin that keeps
+/// crashing.
+#[derive(Debug, Default)]
+struct RelaunchBudget {
+    recent: HashMap<u32, Vec<Instant>>,
+}
+
+impl RelaunchBudget {
+    /// Record a relaunch at `now` if the setup has any left.
+    fn try_take(&mut self, setup_id: u32, now: Instant) -> bool {
+        let recent = self.recent.entry(setup_id).or_default();
+        recent.retain(|at| now.duration_since(*at) < RELAUNCH_WINDOW);
+        if recent.len() >= MAX_RELAUNCHES {
+            return false;
+        }
+        recent.push(now);
+        true
+    }
+}
+
+fn emit_status(app: &AppHandle, setup_id: u32, state: SetupProcessState, exit: ProcessExit, message: String) {
+    match state {
+        SetupProcessState::Exited | SetupProcessState::Relaunched => tracing::info!("{}", message),
+        SetupProcessState::Crashed | SetupProcessState::RelaunchFailed => tracing::error!("{}", message),
+    }
+    let status = SetupProcessStatus {
+        setup_id,
+        state,
+        exit_code: exit.code,
+        signal: exit.signal,
+        message,
+        at_ms: now_ms(),
+    };
+    let _ = app.emit(SETUP_PROCESS_STATUS_EVENT, &status);
+}
+
+/// Take every setup process that has exited out of the store. Processes
+/// stopped on purpose are removed before they're killed, so everything
+/// found here went away on its own.
+fn collect_exits(store: &SharedSetupStore) -> Vec<(u32, ProcessExit)> {
+    let mut guard = store.lock().unwrap_or_else(|e| e.into_inner());
+    let mut exits = Vec::new();
+    for (setup_id, child) in guard.processes.iter_mut() {
+        match child.try_wait() {
+            Ok(Some(status)) => exits.push((*setup_id, ProcessExit::from_status(status))),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Checking setup {} Dolphin: {}", setup_id, e),
+        }
+    }
+    for (setup_id, pid) in guard.process_pids.iter() {
+        if !Path::new("/proc").join(pid.to_string()).exists() {
+            exits.push((*setup_id, ProcessExit::unknown()));
+        }
+    }
+    for (setup_id, _) in &exits {
+        guard.processes.remove(setup_id);
+        guard.process_pids.remove(setup_id);
+    }
+    exits
+}
+
+/// Start the setup's Dolphin again the way it was started: through its
+/// stream assignment when it has one.
+fn relaunch(app: &AppHandle, setup_id: u32) -> Result<Vec<String>, String> {
+    let store = app.state::<SharedSetupStore>();
+    let assigned = store
+        .lock()
+        .map_err(|e| e.to_string())?
+        .setups
+        .iter()
+        .find(|setup| setup.id == setup_id)
+        .ok_or_else(|| "Setup not found.".to_string())?
+        .assigned_stream
+        .clone();
+    match assigned {
+        Some(stream) => {
+            let result = assign_stream(setup_id, stream, Some(true), &store, &app.state::<SharedTestState>())?;
+            Ok(result.warning.into_iter().collect())
+        }
+        None => {
+            let child = launch_dolphin_for_setup_internal(setup_id)?;
+            let mut guard = store.lock().map_err(|e| e.to_string())?;
+            guard.processes.insert(setup_id, child);
+            guard.playback_sessions.remove(&setup_id);
+            Ok(Vec::new())
+        }
+    }
+}
+
+fn handle_exit(app: &AppHandle, setup_id: u32, exit: ProcessExit, budget: &mut RelaunchBudget) {
+    if !exit.is_crash() {
+        emit_status(
+            app,
+            setup_id,
+            SetupProcessState::Exited,
+            exit,
+            format!("Setup {setup_id} Dolphin closed."),
+        );
+        return;
+    }
+    emit_status(
+        app,
+        setup_id,
+        SetupProcessState::Crashed,
+        exit,
+        format!("Setup {setup_id} Dolphin {}.", exit.describe()),
+    );
+    if !cached_config().is_ok_and(|config| config.dolphin_auto_relaunch) {
+        return;
+    }
+    // The mirror monitor restarts its own Dolphins.
+    if mirror_links().iter().any(|link| link.mirror_id == setup_id) {
+        return;
+    }
+    if !budget.try_take(setup_id, Instant::now()) {
+        emit_status(
+            app,
+            setup_id,
+            SetupProcessState::RelaunchFailed,
+            exit,
+            format!(
+                "Setup {setup_id} Dolphin crashed {MAX_RELAUNCHES} times in {} minutes; not relaunching it again.",
+                RELAUNCH_WINDOW.as_secs() / 60
+            ),
+        );
+        return;
+    }
+    match relaunch(app, setup_id) {
+        Ok(warnings) if warnings.is_empty() => emit_status(
+            app,
+            setup_id,
+            SetupProcessState::Relaunched,
+            exit,
+            format!("Setup {setup_id} Dolphin relaunched."),
+        ),
+        Ok(warnings) => emit_status(
+            app,
+            setup_id,
+            SetupProcessState::RelaunchFailed,
+            exit,
+            format!("Setup {setup_id} Dolphin relaunched with problems: {}", warnings.join(" ")),
+        ),
+        Err(err) => emit_status(
+            app,
+            setup_id,
+            SetupProcessState::RelaunchFailed,
+            exit,
+            format!("Couldn't relaunch setup {setup_id} Dolphin: {err}"),
+        ),
+    }
+}
+
+/// Watch every setup's Dolphin and report the ones that exit on their own,
+/// relaunching crashed ones when `dolphin_auto_relaunch` is on.
+pub fn spawn_process_health_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut budget = RelaunchBudget::default();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let exits = collect_exits(&app.state::<SharedSetupStore>());
+            for (setup_id, exit) in exits {
+                handle_exit(&app, setup_id, exit, &mut budget);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn used_up(start: Instant) -> RelaunchBudget {
+        let mut budget = RelaunchBudget::default();
+        for _ in 0..MAX_RELAUNCHES {
+            assert!(budget.try_take(1, start));
+        }
+        budget
+    }
+
+    #[test]
+    fn only_a_clean_exit_is_not_a_crash() {
+        assert!(!ProcessExit { code: Some(0), signal: None }.is_crash());
+        assert!(ProcessExit { code: Some(1), signal: None }.is_crash());
+        assert!(ProcessExit { code: None, signal: Some(11) }.is_crash());
+        assert!(ProcessExit::unknown().is_crash());
+    }
+
+    #[test]
+    fn exits_are_described_by_code_then_signal() {
+        assert_eq!(ProcessExit { code: Some(1), signal: None }.describe(), "exited with code 1");
+        assert_eq!(ProcessExit { code: None, signal: Some(11) }.describe(), "was killed by signal 11");
+        assert_eq!(ProcessExit::unknown().describe(), "is no longer running");
+    }
+
+    #[test]
+    fn relaunches_stop_once_the_budget_runs_out() {
+        let start = Instant::now();
+        assert!(!used_up(start).try_take(1, start));
+    }
+
+    #[test]
+    fn each_setup_has_its_own_budget() {
+        let start = Instant::now();
+        assert!(used_up(start).try_take(2, start));
+    }
+
+    #[test]
+    fn old_relaunches_age_out_of_the_window() {
+        let start = Instant::now();
+        assert!(used_up(start).try_take(1, start + RELAUNCH_WINDOW));
+    }
+}
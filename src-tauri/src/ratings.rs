@@ -0,0 +1,296 @@
+use crate::config::*;
+use crate::startgg::init_startgg_sim;
+use crate::startgg_sim::{StartggSimEntrantConfig, StartggSimState};
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::{collections::HashMap, fs, path::PathBuf};
+use tauri::State;
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+const K_FACTOR: f64 = 32.0;
+
+/// A completed set reduced to what ratings need. Event reports store these
+/// under `completedSets`, which makes the logs folder the results history
+/// that later events are rated from.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RatedSet {
+    pub p1_name: String,
+    pub p1_code: Option<String>,
+    pub p2_name: String,
+    pub p2_code: Option<String>,
+    pub p1_won: bool,
+    pub completed_at_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRating {
+    pub name: String,
+    pub slippi_code: Option<String>,
+    pub rating: f64,
+    pub wins: u32,
+    pub losses: u32,
+}
+
+/// Completed sets with two entrants and a winner, oldest first.
+pub fn rated_sets_from_state(state: &StartggSimState) -> Vec<RatedSet> {
+    let mut sets = state
+        .sets
+        .iter()
+        .filter(|set| set.state == "completed" && set.slots.len() >= 2)
+        .filter_map(|set| {
+            let (p1, p2) = (&set.slots[0], &set.slots[1]);
+            let winner = set.winner_id?;
+            let (p1_id, p2_id) = (p1.entrant_id?, p2.entrant_id?);
+            if winner != p1_id && winner != p2_id {
+                return None;
+            }
+            Some(RatedSet {
+                p1_name: p1.entrant_name.clone().unwrap_or_default(),
+                p1_code: p1.slippi_code.clone(),
+                p2_name: p2.entrant_name.clone().unwrap_or_default(),
+                p2_code: p2.slippi_code.clone(),
+                p1_won: winner == p1_id,
+                completed_at_ms: set.completed_at_ms,
+            })
+        })
+        .collect::<Vec<_>>();
+    sets.sort_by_key(|set| set.completed_at_ms.unwrap_or(u64::MAX));
+    sets
+}
+
+// ── Rating table ────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+pub struct RatingTable {
+    players: HashMap<String, PlayerRating>,
+}
+
+impl RatingTable {
+    fn entry(&mut self, name: &str, code: Option<&str>) -> &mut PlayerRating {
+        self.players
+            .entry(player_key(name, code))
+            .or_insert_with(|| PlayerRating {
+                name: name.trim().to_string(),
+                slippi_code: code.map(|code| code.trim().to_string()).filter(|code| !code.is_empty()),
+                rating: DEFAULT_RATING,
+                wins: 0,
+                losses: 0,
+            })
+    }
+
+    /// Standard Elo update for one set.
+    pub fn apply(&mut self, set: &RatedSet) {
+        let r1 = self.entry(&set.p1_name, set.p1_code.as_deref()).rating;
+        let r2 = self.entry(&set.p2_name, set.p2_code.as_deref()).rating;
+        let expected_p1 = 1.0 / (1.0 + 10f64.powf((r2 - r1) / 400.0));
+        let actual_p1 = if set.p1_won { 1.0 } else { 0.0 };
+        let delta = K_FACTOR * (actual_p1 - expected_p1);
+
+        let p1 = self.entry(&set.p1_name, set.p1_code.as_deref());
+        p1.rating += delta;
+        if set.p1_won { p1.wins += 1 } else { p1.losses += 1 }
+        let p2 = self.entry(&set.p2_name, set.p2_code.as_deref());
+        p2.rating -= delta;
+        if set.p1_won { p2.losses += 1 } else { p2.wins += 1 }
+    }
+
+    pub fn rating_for(&self, name: &str, code: Option<&str>) -> Option<f64> {
+        self.players.get(&player_key(name, code)).map(|player| player.rating)
+    }
+
+    /// Highest rating first.
+    pub fn ranked(&self) -> Vec<PlayerRating> {
+        let mut players = self.players.values().cloned().collect::<Vec<_>>();
+        players.sort_by(|a, b| b.rating.total_cmp(&a.rating).then_with(|| a.name.cmp(&b.name)));
+        players
+    }
+}
+
+// ── History ─────────────────────────────────────────────────────────────
+
+pub(crate) fn event_report_paths() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(repo_root().join("logs")) else {
+        return Vec::new();
+    };
+    let mut paths = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event_report_") && name.ends_with(".json"))
+        })
+        .collect::<Vec<_>>();
+    // File names carry the timestamp, so this is chronological.
+    paths.sort();
+    paths
+}
+
+/// Completed sets from earlier event reports, skipping reports for
+/// `current_event_id` so an event isn't counted twice.
+fn prior_rated_sets(current_event_id: Option<&str>) -> Vec<RatedSet> {
+    let mut sets = Vec::new();
+    for path in event_report_paths() {
+        let Ok(data) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(report) = serde_json::from_str::<Value>(&data) else {
+            tracing::warn!("Skipping unreadable event report {}", path.display());
+            continue;
+        };
+        let event_id = report.pointer("/event/id").and_then(|id| id.as_str());
+        if current_event_id.is_some() && event_id == current_event_id {
+            continue;
+        }
+        if let Some(completed) = report.get("completedSets") {
+            if let Ok(mut parsed) = serde_json::from_value::<Vec<RatedSet>>(completed.clone()) {
+                sets.append(&mut parsed);
+            }
+        }
+    }
+    sets
+}
+
+/// Ratings from prior events followed by the current one.
+pub fn build_rating_table(current: Option<&StartggSimState>) -> RatingTable {
+    let mut table = RatingTable::default();
+    for set in prior_rated_sets(current.map(|state| state.event.id.as_str())) {
+        table.apply(&set);
+    }
+    if let Some(state) = current {
+        for set in rated_sets_from_state(state) {
+            table.apply(&set);
+        }
+    }
+    table
+}
+
+/// Reseed entrants by rating, highest first. Unrated entrants keep their
+/// relative order after the rated ones. Does nothing if nobody is rated.
+pub fn seed_entrants_by_rating(entrants: &mut [StartggSimEntrantConfig], table: &RatingTable) {
+    let rating = |entrant: &StartggSimEntrantConfig| table.rating_for(&entrant.name, Some(&entrant.slippi_code));
+    if !entrants.iter().any(|entrant| rating(entrant).is_some()) {
+        return;
+    }
+    entrants.sort_by(|a, b| match (rating(a), rating(b)) {
+        (Some(ra), Some(rb)) => rb.total_cmp(&ra),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+    for (idx, entrant) in entrants.iter_mut().enumerate() {
+        entrant.seed = Some(idx as u32 + 1);
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Running Elo ratings for this session: prior event reports plus the
+/// current bracket's completed sets.
+#[tauri::command]
+#[specta::specta]
+pub fn session_ratings(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<Vec<PlayerRating>, String> {
+    let config = cached_config()?;
+    let state = if config.test_flags().fake_bracket {
+        let now = now_ms();
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        init_startgg_sim(&mut guard, now)?;
+        guard.startgg_sim.as_mut().map(|sim| sim.state(now))
+    } else {
+        live_startgg.lock().map_err(|e| e.to_string())?.state.clone()
+    };
+    Ok(build_rating_table(state.as_ref()).ranked())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(p1: &str, p2: &str, p1_won: bool) -> RatedSet {
+        RatedSet {
+            p1_name: p1.to_string(),
+            p1_code: None,
+            p2_name: p2.to_string(),
+            p2_code: None,
+            p1_won,
+            completed_at_ms: None,
+        }
+    }
+
+    /// Gamma beats both, Alpha beats Beta.
+    fn table() -> RatingTable {
+        let mut table = RatingTable::default();
+        table.apply(&set("Alpha", "Beta", true));
+        table.apply(&set("Gamma", "Alpha", true));
+        table.apply(&set("Gamma", "Beta", true));
+        table
+    }
+
+    fn entrant(id: u32, name: &str) -> StartggSimEntrantConfig {
+        StartggSimEntrantConfig {
+            id,
+            name: name.to_string(),
+            slippi_code: String::new(),
+            seed: Some(id),
+        }
+    }
+
+    #[test]
+    fn the_winner_takes_what_the_loser_gives_up() {
+        let mut table = RatingTable::default();
+        table.apply(&set("Alpha", "Beta", false));
+        let beta = table.rating_for("Beta", None).unwrap();
+        assert_eq!(beta, DEFAULT_RATING + K_FACTOR / 2.0);
+        assert_eq!(table.rating_for("Alpha", None), Some(DEFAULT_RATING - K_FACTOR / 2.0));
+    }
+
+    #[test]
+    fn ranking_is_by_rating_with_records() {
+        let ranked = table().ranked();
+        let names = ranked.iter().map(|player| player.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Gamma", "Alpha", "Beta"]);
+        assert_eq!((ranked[0].wins, ranked[0].losses), (2, 0));
+        assert_eq!((ranked[2].wins, ranked[2].losses), (0, 2));
+    }
+
+    #[test]
+    fn ratings_are_zero_sum() {
+        let total: f64 = table().ranked().iter().map(|player| player.rating).sum();
+        assert!((total - 3.0 * DEFAULT_RATING).abs() < 1e-9);
+    }
+
+    #[test]
+    fn players_with_a_connect_code_are_known_by_it() {
+        let mut table = RatingTable::default();
+        let renamed = RatedSet { p1_code: Some("alph#1".to_string()), ..set("Alpha", "Beta", true) };
+        table.apply(&renamed);
+        assert!(table.rating_for("New Tag", Some("ALPH#1")).is_some());
+        assert!(table.rating_for("Alpha", None).is_none());
+    }
+
+    #[test]
+    fn rated_entrants_are_seeded_first_by_rating() {
+        let mut entrants = vec![entrant(1, "Beta"), entrant(2, "Newcomer"), entrant(3, "Gamma"), entrant(4, "Alpha")];
+        seed_entrants_by_rating(&mut entrants, &table());
+        let order = entrants.iter().map(|e| (e.name.as_str(), e.seed)).collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            vec![("Gamma", Some(1)), ("Alpha", Some(2)), ("Beta", Some(3)), ("Newcomer", Some(4))]
+        );
+    }
+
+    #[test]
+    fn seeding_is_left_alone_when_nobody_is_rated() {
+        let mut entrants = vec![entrant(2, "Newcomer"), entrant(1, "Rookie")];
+        seed_entrants_by_rating(&mut entrants, &table());
+        let order = entrants.iter().map(|e| (e.name.as_str(), e.seed)).collect::<Vec<_>>();
+        assert_eq!(order, vec![("Newcomer", Some(2)), ("Rookie", Some(1))]);
+    }
+}
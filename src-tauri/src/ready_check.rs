@@ -0,0 +1,199 @@
+use crate::config::{normalize_slippi_code, now_ms};
+use crate::run_of_show::current_bracket_state;
+use crate::slippi::scan_slippi_streams;
+use crate::startgg_sim::StartggSimSlot;
+use crate::stream_identity::stream_id_codes;
+use crate::types::*;
+use serde::Serialize;
+use specta::Type;
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum StreamAvailability {
+    /// On the spectate list and in a game right now.
+    Playing,
+    /// On the spectate list, not in a game.
+    Listed,
+    /// Has a connect code, but isn't broadcasting.
+    Missing,
+    /// The bracket has no connect code for this player.
+    NoCode,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerReadiness {
+    pub entrant_id: Option<u32>,
+    pub name: Option<String>,
+    pub connect_code: Option<String>,
+    pub availability: StreamAvailability,
+    /// The spectate card to assign, when there is one.
+    pub stream_id: Option<String>,
+}
+
+/// Whether a set can be called to stream: only when both players are on
+/// the launcher's spectate list. Otherwise it goes to a regular station.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetReadyStatus {
+    pub set_id: u64,
+    pub round_label: String,
+    pub players: Vec<PlayerReadiness>,
+    pub stream_ready: bool,
+    pub checked_at_ms: u64,
+}
+
+/// Every connect code a spectate card shows.
+fn stream_codes(stream: &SlippiStream) -> Vec<String> {
+    let mut codes = stream_id_codes(&stream.id);
+    codes.extend(
+        [stream.p1_code.as_deref(), stream.p2_code.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(normalize_slippi_code),
+    );
+    codes
+}
+
+fn player_readiness(slot: &StartggSimSlot, streams: &[SlippiStream]) -> PlayerReadiness {
+    let code = slot.slippi_code.as_deref().and_then(normalize_slippi_code);
+    let matching = code.as_ref().map(|code| {
+        streams
+            .iter()
+            .filter(|stream| stream_codes(stream).contains(code))
+            .collect::<Vec<_>>()
+    });
+    let card = matching.as_ref().and_then(|cards| {
+        cards
+            .iter()
+            .find(|stream| stream.is_playing == Some(true))
+            .or_else(|| cards.first())
+            .copied()
+    });
+    let availability = match (&code, card) {
+        (None, _) => StreamAvailability::NoCode,
+        (Some(_), None) => StreamAvailability::Missing,
+        (Some(_), Some(stream)) if stream.is_playing == Some(true) => StreamAvailability::Playing,
+        (Some(_), Some(_)) => StreamAvailability::Listed,
+    };
+    PlayerReadiness {
+        entrant_id: slot.entrant_id,
+        name: slot.entrant_name.clone(),
+        connect_code: code,
+        availability,
+        stream_id: card.map(|stream| stream.id.clone()),
+    }
+}
+
+fn is_available(player: &PlayerReadiness) -> bool {
+    matches!(player.availability, StreamAvailability::Playing | StreamAvailability::Listed)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Check a set's players against a fresh scan of the spectate list.
+#[tauri::command]
+#[specta::specta]
+pub fn set_ready_status(
+    set_id: u64,
+    app: AppHandle,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+    replay_cache: State<'_, SharedOverlayCache>,
+    store: State<'_, SharedSetupStore>,
+) -> Result<SetReadyStatus, String> {
+    let bracket =
+        current_bracket_state(&test_state, &live_startgg).ok_or_else(|| "No bracket is loaded.".to_string())?;
+    let set = bracket
+        .sets
+        .iter()
+        .find(|set| set.id == set_id)
+        .ok_or_else(|| format!("Set {set_id} not found."))?;
+    let streams = scan_slippi_streams(app, test_state, replay_cache, store)?;
+    let players = set
+        .slots
+        .iter()
+        .map(|slot| player_readiness(slot, &streams))
+        .collect::<Vec<_>>();
+    Ok(SetReadyStatus {
+        set_id,
+        round_label: set.round_label.clone(),
+        stream_ready: !players.is_empty() && players.iter().all(is_available),
+        players,
+        checked_at_ms: now_ms(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(code: Option<&str>) -> StartggSimSlot {
+        StartggSimSlot {
+            entrant_id: Some(1),
+            entrant_name: Some("Player".to_string()),
+            slippi_code: code.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn card(id: &str, p1_code: Option<&str>, playing: bool) -> SlippiStream {
+        SlippiStream {
+            id: id.to_string(),
+            p1_code: p1_code.map(str::to_string),
+            is_playing: Some(playing),
+            ..Default::default()
+        }
+    }
+
+    fn streams() -> Vec<SlippiStream> {
+        vec![
+            card("codes:AAAA#1|BBBB#2", Some("AAAA#1"), true),
+            card("card-3", Some("cccc#3"), false),
+        ]
+    }
+
+    fn availability(code: Option<&str>) -> StreamAvailability {
+        player_readiness(&slot(code), &streams()).availability
+    }
+
+    #[test]
+    fn a_player_on_a_card_in_game_is_playing() {
+        assert_eq!(availability(Some("AAAA#1")), StreamAvailability::Playing);
+    }
+
+    #[test]
+    fn codes_in_a_stable_id_match_even_when_not_shown_as_p1() {
+        assert_eq!(availability(Some("bbbb#2")), StreamAvailability::Playing);
+    }
+
+    #[test]
+    fn a_player_on_an_idle_card_is_listed_and_can_be_called() {
+        let ready = player_readiness(&slot(Some("CCCC#3")), &streams());
+        assert_eq!(ready.availability, StreamAvailability::Listed);
+        assert_eq!(ready.stream_id.as_deref(), Some("card-3"));
+        assert!(is_available(&ready));
+    }
+
+    #[test]
+    fn a_player_not_broadcasting_is_missing() {
+        let ready = player_readiness(&slot(Some("DDDD#4")), &streams());
+        assert_eq!(ready.availability, StreamAvailability::Missing);
+        assert_eq!(ready.stream_id, None);
+        assert!(!is_available(&ready));
+    }
+
+    #[test]
+    fn a_player_without_a_code_has_no_code() {
+        assert_eq!(availability(None), StreamAvailability::NoCode);
+        assert_eq!(availability(Some("  ")), StreamAvailability::NoCode);
+    }
+
+    #[test]
+    fn a_card_in_game_wins_over_an_idle_one() {
+        let streams = vec![card("card-1", Some("EEEE#5"), false), card("card-2", Some("EEEE#5"), true)];
+        let ready = player_readiness(&slot(Some("EEEE#5")), &streams);
+        assert_eq!(ready.stream_id.as_deref(), Some("card-2"));
+    }
+}
@@ -0,0 +1,338 @@
+use crate::config::*;
+use crate::idle_mode::is_idle;
+use crate::playback::with_playback_session;
+use crate::types::{LiveReplay, PlaybackBufferStatus, SharedSetupStore};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::Read,
+    path::PathBuf,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// No growth for this long while the game is running counts as a stall.
+const STALL_AFTER_MS: u64 = 5_000;
+/// Give up on a replay that hasn't grown in this long.
+const FOLLOW_IDLE_TIMEOUT_MS: u64 = 10 * 60 * 1000;
+/// Below this many frames ahead of playback the buffer is reported as low.
+const MIN_BUFFER_FRAMES: i32 = 60;
+const FRAMES_PER_MS: f64 = 60.0 / 1000.0;
+const FIRST_FRAME: i32 = crate::playback::PLAYBACK_FIRST_FRAME;
+
+/// Start of every .slp file: `{U\x03raw[$U#l` followed by the u32 raw length
+/// (zero while the game is still being written).
+pub(crate) const SLP_HEADER: &[u8] = b"{U\x03raw[$U#l";
+pub(crate) const SLP_HEADER_LEN: usize = 15;
+
+const EVENT_PAYLOADS: u8 = 0x35;
+const EVENT_POST_FRAME: u8 = 0x38;
+const EVENT_FRAME_BOOKEND: u8 = 0x3C;
+
+// ── Incremental replay scan ─────────────────────────────────────────────
+
+/// Reads a replay that is still being written, only looking at the bytes
+/// appended since the last poll. The event stream is decoded by the same
+/// [`LiveReplay`] the overlay HUD uses; this checks the file is a replay
+/// first and turns an undecodable stream into an error.
+struct ReplayScanner {
+    path: PathBuf,
+    header_checked: bool,
+    live: LiveReplay,
+}
+
+impl ReplayScanner {
+    fn new(path: PathBuf) -> Self {
+        ReplayScanner { path, header_checked: false, live: LiveReplay::default() }
+    }
+
+    /// Read newly appended bytes. Returns whether the file grew.
+    fn poll(&mut self) -> Result<bool, String> {
+        if !self.header_checked {
+            let mut header = Vec::with_capacity(SLP_HEADER_LEN);
+            File::open(&self.path)
+                .and_then(|file| file.take(SLP_HEADER_LEN as u64).read_to_end(&mut header))
+                .map_err(|e| format!("read {}: {e}", self.path.display()))?;
+            if header.len() < SLP_HEADER_LEN {
+                return Ok(false);
+            }
+            if !header.starts_with(SLP_HEADER) {
+                return Err(format!("{} is not a Slippi replay.", self.path.display()));
+            }
+            self.header_checked = true;
+        }
+        let grew = self.live.read_from(&self.path).map_err(|e| format!("read {}: {e}", self.path.display()))?;
+        if self.live.unreadable {
+            return Err(format!("{} has an event its header doesn't declare.", self.path.display()));
+        }
+        Ok(grew)
+    }
+}
+
+/// End of the raw event section of a finished replay (where the metadata
+/// starts), from the length in its header.
+pub(crate) fn raw_section_end(bytes: &[u8]) -> Result<usize, String> {
+    if bytes.len() < SLP_HEADER_LEN || !bytes.starts_with(SLP_HEADER) {
+        return Err("Not a Slippi replay.".to_string());
+    }
+    let raw_len = u32::from_be_bytes([bytes[11], bytes[12], bytes[13], bytes[14]]) as usize;
+    if raw_len == 0 {
+        return Err("Replay is still being written (raw length is 0).".to_string());
+    }
+    Ok((SLP_HEADER_LEN + raw_len).min(bytes.len()))
+}
+
+/// For each frame in file order, the frame number and the file offset just
+/// past its last event. Used to write a finished replay back out at the pace
+/// it was recorded.
+pub(crate) fn frame_end_offsets(bytes: &[u8]) -> Result<Vec<(i32, usize)>, String> {
+    let raw_end = raw_section_end(bytes)?;
+    let mut payload_sizes: HashMap<u8, u16> = HashMap::new();
+    let mut frames: Vec<(i32, usize)> = Vec::new();
+    let mut pos = SLP_HEADER_LEN;
+    while pos < raw_end {
+        let command = bytes[pos];
+        let size = if command == EVENT_PAYLOADS {
+            bytes.get(pos + 1).copied().unwrap_or_default() as usize
+        } else {
+            *payload_sizes
+                .get(&command)
+                .ok_or_else(|| format!("Unknown event 0x{command:02X} at byte {pos}"))? as usize
+        };
+        let end = (pos + 1 + size).min(raw_end);
+        let payload = &bytes[pos + 1..end];
+        match command {
+            EVENT_PAYLOADS => {
+                for entry in payload.get(1..).unwrap_or_default().chunks_exact(3) {
+                    payload_sizes.insert(entry[0], u16::from_be_bytes([entry[1], entry[2]]));
+                }
+            }
+            EVENT_POST_FRAME | EVENT_FRAME_BOOKEND if payload.len() >= 4 => {
+                let frame = i32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]);
+                match frames.last_mut() {
+                    Some(last) if last.0 == frame => last.1 = end,
+                    _ => frames.push((frame, end)),
+                }
+            }
+            _ => {}
+        }
+        pos = end;
+    }
+    Ok(frames)
+}
+
+// ── Follower ────────────────────────────────────────────────────────────
+
+fn buffer_state(
+    live: &LiveReplay,
+    buffered_frames: i32,
+    caught_up: bool,
+    idle_ms: u64,
+) -> &'static str {
+    if live.ended {
+        if caught_up { "ended" } else { "live" }
+    } else if !live.started || live.latest_frame.is_none() {
+        "waiting"
+    } else if idle_ms >= STALL_AFTER_MS {
+        "stalled"
+    } else if buffered_frames < MIN_BUFFER_FRAMES {
+        "buffering"
+    } else {
+        "live"
+    }
+}
+
+/// Poll `path` until the game ends and playback catches up, the operator
+/// loads something else, or the Dolphin goes away. The communication file
+/// is only written once the game has started, since Dolphin can't open a
+/// replay without its Game Start event.
+fn follow_replay_loop(app: AppHandle, setup_id: u32, path: PathBuf, follow_id: u64) {
+    let store = app.state::<SharedSetupStore>();
+    let mut scanner = ReplayScanner::new(path.clone());
+    let mut started = false;
+    let mut end_written = false;
+    let mut playback_frame = FIRST_FRAME as f64;
+    let mut last_growth_ms: Option<u64> = None;
+    let follow_started_ms = now_ms();
+    let mut last_tick = Instant::now();
+    let mut last_state = "";
+
+    loop {
+        let now = now_ms();
+        match scanner.poll() {
+            Ok(true) => last_growth_ms = Some(now),
+            Ok(false) => {}
+            Err(err) => {
+                tracing::warn!("Real-time playback for setup {}: {}", setup_id, err);
+                let _ = app.emit("playback-error", serde_json::json!({ "setupId": setup_id, "message": err }));
+                return;
+            }
+        }
+
+        let latest = scanner.live.latest_frame;
+        let elapsed = last_tick.elapsed();
+        last_tick = Instant::now();
+        if started {
+            if let Some(latest) = latest {
+                playback_frame = (playback_frame + elapsed.as_millis() as f64 * FRAMES_PER_MS).min(latest as f64);
+            }
+        }
+
+        // Drive the communication file: start following once the game is
+        // on disk, then pin the end frame when the game is over.
+        let write_result = if !started && scanner.live.started {
+            started = true;
+            Some(with_playback_session(&store, setup_id, |session| {
+                if session.follow_id != follow_id {
+                    return Err(String::new());
+                }
+                session.follow_replay(&path, None)
+            }))
+        } else if started && scanner.live.ended && !end_written {
+            end_written = true;
+            Some(with_playback_session(&store, setup_id, |session| {
+                if session.follow_id != follow_id {
+                    return Err(String::new());
+                }
+                session.follow_replay(&path, latest)
+            }))
+        } else {
+            None
+        };
+        if let Some(Err(err)) = write_result {
+            if !err.is_empty() {
+                tracing::warn!("Real-time playback for setup {}: {}", setup_id, err);
+            }
+            return;
+        }
+
+        let playback = started.then_some(playback_frame.floor() as i32);
+        let buffered_frames = match (latest, playback) {
+            (Some(latest), Some(playback)) => (latest - playback).max(0),
+            (Some(latest), None) => latest - FIRST_FRAME,
+            _ => 0,
+        };
+        let idle_ms = now.saturating_sub(last_growth_ms.unwrap_or(follow_started_ms));
+        let state = buffer_state(&scanner.live, buffered_frames, buffered_frames == 0, idle_ms);
+        let status = PlaybackBufferStatus {
+            setup_id,
+            replay_path: path.to_string_lossy().to_string(),
+            state: state.to_string(),
+            bytes: scanner.live.offset,
+            latest_frame: latest,
+            playback_frame: playback,
+            buffered_frames,
+            game_ended: scanner.live.ended,
+            last_growth_ms,
+            updated_at_ms: now,
+        };
+
+        // Publish on the session; stop if another follower or command
+        // replaced this one.
+        let still_current = with_playback_session(&store, setup_id, |session| {
+            let current = session.follow_id == follow_id && (!started || session.is_following(&path));
+            if current {
+                session.buffer = Some(status.clone());
+            }
+            Ok(current)
+        })
+        .unwrap_or(false);
+        if !still_current {
+            return;
+        }
+        // Telemetry goes quiet in idle mode; the session still has the status.
+        if (state != last_state || last_growth_ms == Some(now)) && !is_idle() {
+            let _ = app.emit("playback-telemetry", &status);
+            last_state = state;
+        }
+        if state == "ended" || idle_ms >= FOLLOW_IDLE_TIMEOUT_MS {
+            return;
+        }
+        sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Play a replay that is still being written (e.g. a set uploaded live from
+/// another venue) in real-time mode. Buffer status is published on the
+/// playback session and as `playback-telemetry` events.
+#[tauri::command]
+#[specta::specta]
+pub fn playback_follow_replay(setup_id: u32, replay_path: String, app: AppHandle) -> Result<(), String> {
+    let path = resolve_repo_path(replay_path.trim());
+    if !path.is_file() {
+        return Err(format!("Replay not found at {}", path.display()));
+    }
+    start_follow(&app, setup_id, path)
+}
+
+/// Start following `path` on a setup's playback Dolphin, replacing any
+/// follower already running there.
+pub fn start_follow(app: &AppHandle, setup_id: u32, path: PathBuf) -> Result<(), String> {
+    let store = app.state::<SharedSetupStore>();
+    let follow_id = with_playback_session(&store, setup_id, |session| {
+        session.follow_id += 1;
+        session.buffer = None;
+        Ok(session.follow_id)
+    })?;
+    let app = app.clone();
+    std::thread::spawn(move || follow_replay_loop(app, setup_id, path, follow_id));
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn playback_buffer_status(
+    setup_id: u32,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Option<PlaybackBufferStatus>, String> {
+    let guard = store.lock().map_err(|e| e.to_string())?;
+    Ok(guard
+        .playback_sessions
+        .get(&setup_id)
+        .and_then(|session| session.buffer.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, fs};
+
+    #[test]
+    fn the_scanner_follows_a_replay_as_it_is_written() {
+        let dir = env::temp_dir().join(format!("realtime-scan-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Game.slp");
+        fs::write(&path, &SLP_HEADER[..6]).unwrap();
+        let mut scanner = ReplayScanner::new(path.clone());
+        assert!(!scanner.poll().unwrap());
+
+        // Game Start 4 bytes, Frame Bookend 8 bytes, Game End 1 byte
+        let mut bytes = SLP_HEADER.to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, 0, EVENT_PAYLOADS, 10, 0x36, 0, 4, EVENT_FRAME_BOOKEND, 0, 8, 0x39, 0, 1]);
+        bytes.extend_from_slice(&[0x36, 0, 0, 0, 0, EVENT_FRAME_BOOKEND]);
+        bytes.extend_from_slice(&(-123i32).to_be_bytes());
+        fs::write(&path, &bytes).unwrap();
+        assert!(scanner.poll().unwrap());
+        assert_eq!(buffer_state(&scanner.live, 0, true, 0), "waiting");
+
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0x39, 2]);
+        fs::write(&path, &bytes).unwrap();
+        assert!(scanner.poll().unwrap());
+        assert_eq!(scanner.live.latest_frame, Some(-123));
+        assert_eq!(buffer_state(&scanner.live, 0, true, 0), "ended");
+
+        fs::write(&path, b"not a replay at all").unwrap();
+        let err = ReplayScanner::new(path).poll().unwrap_err();
+        assert!(err.contains("not a Slippi replay"), "{err}");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_buffer_state_waits_for_frames() {
+        assert_eq!(buffer_state(&LiveReplay::default(), 0, true, 0), "waiting");
+    }
+}
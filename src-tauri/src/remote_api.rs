@@ -0,0 +1,434 @@
+use crate::audit_log::{audited, record_action, with_origin};
+use crate::config::*;
+use crate::config_transaction::config_transaction;
+use crate::countdowns::start_dq_timer_inner;
+use crate::run_of_show::current_bracket_state;
+use crate::slippi::clear_assignment;
+use crate::startgg_report::{report_startgg_set_result_inner, start_startgg_set_inner};
+use crate::startgg_sim_commands::{startgg_sim_raw_start_set_inner, startgg_sim_raw_update_scores_inner};
+use crate::types::*;
+use axum::{
+    extract::{Path, State as AxumState},
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::{fs::File, io::Read};
+use subtle::ConstantTimeEq;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+const MAX_TOKEN_NAME_CHARS: usize = 40;
+
+/// Everything a remote request can ask for. Each route maps to exactly one
+/// action, and `role_allows` is the only place deciding who may do what.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteAction {
+    ViewBracket,
+    CallSet,
+    StartDqTimer,
+    ReportScore,
+    /// Anything that touches setups, and through them Dolphin and OBS
+    ManageSetups,
+}
+
+pub fn role_allows(role: RemoteRole, action: RemoteAction) -> bool {
+    match role {
+        RemoteRole::Operator => true,
+        RemoteRole::BracketRunner => matches!(
+            action,
+            RemoteAction::ViewBracket | RemoteAction::CallSet | RemoteAction::StartDqTimer | RemoteAction::ReportScore
+        ),
+    }
+}
+
+/// A token as the UI lists it; the secret itself is only shown on creation.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteTokenInfo {
+    pub name: String,
+    pub role: RemoteRole,
+}
+
+/// A token just issued, with the secret the staff device will present.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct NewRemoteToken {
+    pub name: String,
+    pub role: RemoteRole,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteApiStatus {
+    pub enabled: bool,
+    pub bind_addr: String,
+    pub tokens: Vec<RemoteTokenInfo>,
+}
+
+/// Compared in constant time, so response timing doesn't leak how much of a
+/// guessed token was right.
+fn same_token(a: &str, b: &str) -> bool {
+    bool::from(a.as_bytes().ct_eq(b.as_bytes()))
+}
+
+/// What's stored for a secret, so config.json and the UI never hold it.
+fn hash_token(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn find_token(tokens: &[RemoteToken], presented: &str) -> Option<RemoteToken> {
+    if presented.is_empty() {
+        return None;
+    }
+    let presented = hash_token(presented);
+    tokens
+        .iter()
+        .find(|token| !token.token_hash.is_empty() && same_token(&token.token_hash, &presented))
+        .cloned()
+}
+
+fn bearer(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(str::trim)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
+
+fn origin(token: &RemoteToken) -> String {
+    format!("remote:{}", token.name)
+}
+
+/// Who is calling, if they may do `action`. Refusals are audited so a
+/// runner poking at operator routes shows up after the event.
+fn authorize(headers: &HeaderMap, action: RemoteAction) -> Result<RemoteToken, Response> {
+    authorize_with(&cached_config().unwrap_or_default(), headers, action)
+}
+
+fn authorize_with(config: &AppConfig, headers: &HeaderMap, action: RemoteAction) -> Result<RemoteToken, Response> {
+    if !config.remote_api_enabled {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    let Some(token) = bearer(headers).and_then(|presented| find_token(&config.remote_tokens, presented)) else {
+        warn!("Rejected remote API request with a bad token");
+        return Err(error_response(StatusCode::UNAUTHORIZED, "Unknown token."));
+    };
+    if !role_allows(token.role, action) {
+        let message = "This token's role doesn't allow that.";
+        record_action(&origin(&token), "remoteDenied", json!({ "action": action }), Some(message));
+        return Err(error_response(StatusCode::FORBIDDEN, message));
+    }
+    Ok(token)
+}
+
+/// Authorize, then run `f` off the async runtime with everything it audits
+/// recorded under the token's origin.
+async fn run_remote<T, F>(app: AppHandle, headers: HeaderMap, action: RemoteAction, f: F) -> Response
+where
+    T: Serialize + Send + 'static,
+    F: FnOnce(&AppHandle, &RemoteToken) -> Result<T, String> + Send + 'static,
+{
+    let token = match authorize(&headers, action) {
+        Ok(token) => token,
+        Err(response) => return response,
+    };
+    let result = tokio::task::spawn_blocking(move || with_origin(&origin(&token), || f(&app, &token))).await;
+    match result {
+        Ok(Ok(value)) => Json(value).into_response(),
+        Ok(Err(message)) => error_response(StatusCode::BAD_REQUEST, &message),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct DqTimerBody {
+    minutes: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ScoreBody {
+    scores: Vec<u8>,
+}
+
+// ── HTTP routes ─────────────────────────────────────────────────────────
+
+async fn whoami(headers: HeaderMap) -> Response {
+    match authorize(&headers, RemoteAction::ViewBracket) {
+        Ok(token) => Json(RemoteTokenInfo { name: token.name, role: token.role }).into_response(),
+        Err(response) => response,
+    }
+}
+
+/// GET /remote/sets: every set in the bracket the app is showing.
+async fn list_sets(AxumState(app): AxumState<AppHandle>, headers: HeaderMap) -> Response {
+    run_remote(app, headers, RemoteAction::ViewBracket, |app, _| {
+        let test_state = app.state::<SharedTestState>();
+        let live_startgg = app.state::<SharedLiveStartgg>();
+        let bracket = current_bracket_state(&test_state, &live_startgg).ok_or_else(|| "No bracket loaded.".to_string())?;
+        Ok(bracket.sets)
+    })
+    .await
+}
+
+/// Start a set on the bracket the app is running: the sim when the bracket
+/// is simulated, start.gg otherwise.
+fn call_bracket_set(app: &AppHandle, set_id: u64, reporter: String) -> Result<Value, String> {
+    let test_state = app.state::<SharedTestState>();
+    if cached_config()?.test_flags().fake_bracket {
+        return startgg_sim_raw_start_set_inner(set_id, Some(reporter), Some(app), &test_state);
+    }
+    audited("startStartggSet", json!({ "setId": set_id }), || {
+        let state = start_startgg_set_inner(set_id, &reporter, &test_state, &app.state::<SharedLiveStartgg>())?;
+        Ok(json!({ "setId": set_id, "state": state }))
+    })
+}
+
+/// Report a set's scores to the bracket the app is running. Start.gg only
+/// takes finished sets, so a live report is the final result.
+fn report_bracket_scores(app: &AppHandle, set_id: u64, scores: Vec<u8>, reporter: String) -> Result<Value, String> {
+    let test_state = app.state::<SharedTestState>();
+    if cached_config()?.test_flags().fake_bracket {
+        return startgg_sim_raw_update_scores_inner(set_id, scores, Some(reporter), Some(app), &test_state);
+    }
+    let [p1, p2] = scores[..] else {
+        return Err("Scores must include exactly two values.".to_string());
+    };
+    audited("reportStartggSet", json!({ "setId": set_id, "scores": [p1, p2] }), || {
+        let live_startgg = app.state::<SharedLiveStartgg>();
+        let result = report_startgg_set_result_inner(set_id, Some([p1, p2]), &reporter, &test_state, &live_startgg)?;
+        serde_json::to_value(result).map_err(|e| e.to_string())
+    })
+}
+
+async fn call_set(AxumState(app): AxumState<AppHandle>, headers: HeaderMap, Path(set_id): Path<u64>) -> Response {
+    run_remote(app, headers, RemoteAction::CallSet, move |app, token| call_bracket_set(app, set_id, origin(token)))
+        .await
+}
+
+async fn dq_timer(
+    AxumState(app): AxumState<AppHandle>,
+    headers: HeaderMap,
+    Path(set_id): Path<u64>,
+    body: Option<Json<DqTimerBody>>,
+) -> Response {
+    let minutes = body.and_then(|Json(body)| body.minutes);
+    run_remote(app, headers, RemoteAction::StartDqTimer, move |app, _| {
+        audited("startDqTimer", json!({ "setId": set_id, "minutes": minutes }), || {
+            start_dq_timer_inner(set_id, minutes, &app.state::<SharedTestState>(), &app.state::<SharedLiveStartgg>())
+        })
+    })
+    .await
+}
+
+async fn report_score(
+    AxumState(app): AxumState<AppHandle>,
+    headers: HeaderMap,
+    Path(set_id): Path<u64>,
+    Json(body): Json<ScoreBody>,
+) -> Response {
+    run_remote(app, headers, RemoteAction::ReportScore, move |app, token| {
+        report_bracket_scores(app, set_id, body.scores, origin(token))
+    })
+    .await
+}
+
+/// POST /remote/setups/:id/clear: take a setup off its stream and stop its
+/// Dolphin. Operators only.
+async fn clear_setup(AxumState(app): AxumState<AppHandle>, headers: HeaderMap, Path(setup_id): Path<u32>) -> Response {
+    run_remote(app, headers, RemoteAction::ManageSetups, move |app, _| {
+        let params = json!({ "setupId": setup_id, "stop": true });
+        audited("clearAssignment", params, || clear_assignment(setup_id, Some(true), &app.state::<SharedSetupStore>()))
+    })
+    .await
+}
+
+/// Serve the remote API for staff devices. Requests carry
+/// `Authorization: Bearer <token>`, and the token's role decides which
+/// routes answer. It binds to loopback unless `remote_api_bind` opens it to
+/// the venue network.
+pub async fn start_remote_api(app: AppHandle, addr: String) {
+    let router = Router::new()
+        .route("/remote/whoami", get(whoami))
+        .route("/remote/sets", get(list_sets))
+        .route("/remote/sets/:set_id/call", post(call_set))
+        .route("/remote/sets/:set_id/dq-timer", post(dq_timer))
+        .route("/remote/sets/:set_id/score", post(report_score))
+        .route("/remote/setups/:setup_id/clear", post(clear_setup))
+        .with_state(app);
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Remote API failed to bind {addr}: {e}");
+            return;
+        }
+    };
+    info!("Remote API listening at http://{addr}/remote");
+    if let Err(e) = axum::serve(listener, router).await {
+        error!("Remote API server error: {e}");
+    }
+}
+
+fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 24];
+    File::open("/dev/urandom")
+        .and_then(|mut file| file.read_exact(&mut bytes))
+        .map_err(|e| format!("generate token: {e}"))?;
+    Ok(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn remote_api_status() -> Result<RemoteApiStatus, String> {
+    let config = cached_config()?;
+    Ok(RemoteApiStatus {
+        enabled: config.remote_api_enabled,
+        bind_addr: config.remote_api_bind,
+        tokens: config
+            .remote_tokens
+            .into_iter()
+            .map(|token| RemoteTokenInfo { name: token.name, role: token.role })
+            .collect(),
+    })
+}
+
+/// Issue a token for a staff device. The returned token is the only time
+/// the secret is shown; only its hash is kept.
+#[tauri::command]
+#[specta::specta]
+pub fn create_remote_token(app: AppHandle, name: String, role: RemoteRole) -> Result<NewRemoteToken, String> {
+    audited("createRemoteToken", json!({ "name": name, "role": role }), || {
+        let name = name.trim().to_string();
+        if name.is_empty() || name.chars().count() > MAX_TOKEN_NAME_CHARS {
+            return Err(format!("Token names are 1 to {MAX_TOKEN_NAME_CHARS} characters."));
+        }
+        let (token, _) = config_transaction(&app, "createRemoteToken", |txn| {
+            if txn.config().remote_tokens.iter().any(|token| token.name == name) {
+                return Err(format!("A token named {name} already exists."));
+            }
+            let secret = generate_token()?;
+            let token_hash = hash_token(&secret);
+            txn.config_mut().remote_tokens.push(RemoteToken { name: name.clone(), token_hash, role });
+            Ok(NewRemoteToken { name: name.clone(), role, token: secret })
+        })?;
+        Ok(token)
+    })
+}
+
+/// Revoke a token; requests using it fail from the next one on.
+#[tauri::command]
+#[specta::specta]
+pub fn revoke_remote_token(app: AppHandle, name: String) -> Result<bool, String> {
+    audited("revokeRemoteToken", json!({ "name": name }), || {
+        let (removed, _) = config_transaction(&app, "revokeRemoteToken", |txn| {
+            let tokens = &mut txn.config_mut().remote_tokens;
+            let before = tokens.len();
+            tokens.retain(|token| token.name != name);
+            Ok(tokens.len() != before)
+        })?;
+        Ok(removed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::{any, MethodRouter};
+    use std::net::SocketAddr;
+
+    fn token(name: &str, role: RemoteRole) -> RemoteToken {
+        RemoteToken { name: name.into(), token_hash: hash_token(&format!("{name}-secret")), role }
+    }
+
+    /// A route that answers with the caller's token once `authorize_with`
+    /// lets them do `action`.
+    fn probe(config: AppConfig, action: RemoteAction) -> MethodRouter {
+        any(move |headers: HeaderMap| async move {
+            match authorize_with(&config, &headers, action) {
+                Ok(token) => Json(RemoteTokenInfo { name: token.name, role: token.role }).into_response(),
+                Err(response) => response,
+            }
+        })
+    }
+
+    async fn status(addr: SocketAddr, path: &str, bearer: Option<&str>) -> u16 {
+        let mut request = reqwest::Client::new().post(format!("http://{addr}{path}"));
+        if let Some(bearer) = bearer {
+            request = request.bearer_auth(bearer);
+        }
+        request.send().await.unwrap().status().as_u16()
+    }
+
+    #[test]
+    fn bracket_runners_only_reach_the_bracket() {
+        for action in [RemoteAction::ViewBracket, RemoteAction::CallSet, RemoteAction::StartDqTimer, RemoteAction::ReportScore] {
+            assert!(role_allows(RemoteRole::BracketRunner, action));
+            assert!(role_allows(RemoteRole::Operator, action));
+        }
+        assert!(!role_allows(RemoteRole::BracketRunner, RemoteAction::ManageSetups));
+        assert!(role_allows(RemoteRole::Operator, RemoteAction::ManageSetups));
+    }
+
+    #[test]
+    fn tokens_match_exactly_and_blank_ones_never_match() {
+        let tokens = vec![
+            RemoteToken { name: "runner".into(), token_hash: hash_token("abc123"), role: RemoteRole::BracketRunner },
+            RemoteToken { name: "blank".into(), token_hash: String::new(), role: RemoteRole::Operator },
+        ];
+        assert_eq!(find_token(&tokens, "abc123").map(|t| t.name), Some("runner".to_string()));
+        assert!(find_token(&tokens, "abc124").is_none());
+        assert!(find_token(&tokens, "").is_none());
+    }
+
+    #[test]
+    fn only_the_hash_of_a_secret_is_kept() {
+        let hash = hash_token("abc123");
+        assert_eq!(hash.len(), 64);
+        assert!(!hash.contains("abc123"));
+        assert_eq!(hash, hash_token("abc123"));
+        assert_ne!(hash, hash_token("abc124"));
+    }
+
+    #[test]
+    fn http_requests_are_answered_by_token_role() {
+        let mut config = AppConfig::default();
+        assert!(config.remote_api_bind.starts_with("127.0.0.1:"));
+        config.remote_api_enabled = true;
+        config.remote_tokens = vec![token("runner", RemoteRole::BracketRunner), token("op", RemoteRole::Operator)];
+        let mut disabled = config.clone();
+        disabled.remote_api_enabled = false;
+        let router = Router::new()
+            .route("/sets", probe(config.clone(), RemoteAction::ViewBracket))
+            .route("/setups", probe(config, RemoteAction::ManageSetups))
+            .route("/off", probe(disabled, RemoteAction::ViewBracket));
+
+        tokio::runtime::Runtime::new().unwrap().block_on(async {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+
+            assert_eq!(status(addr, "/sets", None).await, 401);
+            assert_eq!(status(addr, "/sets", Some("runner-secrets")).await, 401);
+            assert_eq!(status(addr, "/sets", Some("runner-secret")).await, 200);
+            assert_eq!(status(addr, "/setups", Some("runner-secret")).await, 403);
+            assert_eq!(status(addr, "/setups", Some("op-secret")).await, 200);
+            assert_eq!(status(addr, "/off", Some("op-secret")).await, 404);
+        });
+    }
+}
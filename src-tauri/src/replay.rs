@@ -1,73 +1,38 @@
+use crate::bracket_config::BracketReplaySlot;
 use crate::config::*;
+use crate::connect_codes::{connect_codes_from_bytes, CodeSource};
+use crate::exhibition::exhibition_for_setup;
+use crate::friendlies::is_friendly;
+use crate::idle_mode::is_idle;
+use crate::linked_bracket::merge_states;
+use crate::overlay_refresh::{self, RefreshInputs};
+use crate::slp_scan::{collect_slp_files_with, SlpScanOptions};
 use crate::types::*;
+use crate::venue_time::timezone_info;
 use crate::startgg_sim::{StartggSimSet, StartggSimSlot, StartggSimState};
-use chrono::{DateTime, Datelike, Local, NaiveDateTime, Timelike, Utc};
+pub use melee_stream_core::replay::{
+    costume_names, extract_connect_codes, map_character, map_color, map_internal_character, map_stage,
+    normalize_timestamp_ms, parse_game_start, parse_metadata_timestamp_ms, replay_metadata_timestamp_ms,
+    replay_modified_timestamp_ms, sort_replay_paths_by_start_time, tag_from_code,
+};
+use chrono::{DateTime, Datelike, Local, Timelike};
 use peppi::{game::{Game, Port}, io::slippi};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::{
     collections::{HashMap, HashSet},
     fs,
-    io::BufReader,
     path::{Path, PathBuf},
-    process::Command,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime},
 };
 
-pub fn collect_slp_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
-    let mut files = Vec::new();
-    let entries = fs::read_dir(dir).map_err(|e| format!("read dir {}: {e}", dir.display()))?;
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("read dir entry {}: {e}", dir.display()))?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let ext = path
-            .extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_ascii_lowercase();
-        if ext == "slp" || ext == "slippi" {
-            files.push(path);
-        }
-    }
-    files.sort();
-    Ok(files)
-}
+/// A replay that hasn't grown for this long without a Game End was
+/// abandoned, e.g. after a disconnect.
+const LIVE_REPLAY_STALE: Duration = Duration::from_secs(10);
 
-pub fn extract_connect_codes(bytes: &[u8]) -> Vec<String> {
-    let mut out = Vec::new();
-    let mut i = 0;
-    while i < bytes.len() {
-        if bytes[i] == b'#' {
-            let mut start = i;
-            while start > 0 && bytes[start - 1].is_ascii_alphanumeric() {
-                start -= 1;
-            }
-            let mut end = i + 1;
-            while end < bytes.len() && bytes[end].is_ascii_digit() {
-                end += 1;
-            }
-            let left_len = i - start;
-            let right_len = end.saturating_sub(i + 1);
-            if (2..=12).contains(&left_len) && (3..=4).contains(&right_len) {
-                let left = &bytes[start..i];
-                let right = &bytes[i + 1..end];
-                if left.iter().all(|b| b.is_ascii_alphanumeric()) && right.iter().all(|b| b.is_ascii_digit()) {
-                    let code = format!(
-                        "{}#{}",
-                        String::from_utf8_lossy(left),
-                        String::from_utf8_lossy(right)
-                    );
-                    out.push(code);
-                }
-            }
-            i = end;
-        } else {
-            i += 1;
-        }
-    }
-    out
+/// Replays under `dir`, including subfolders and zip packs as far as
+/// `SLP_SCAN_DEPTH`/`SLP_SCAN_ZIPS` allow.
+pub fn collect_slp_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    collect_slp_files_with(dir, &SlpScanOptions::from_env())
 }
 
 pub fn most_common_connect_code(files: &[PathBuf]) -> Result<String, String> {
@@ -75,7 +40,7 @@ pub fn most_common_connect_code(files: &[PathBuf]) -> Result<String, String> {
     for file in files {
         let bytes = fs::read(file)
             .map_err(|e| format!("read replay {}: {e}", file.display()))?;
-        let codes = extract_connect_codes(&bytes);
+        let codes = connect_codes_from_bytes(&bytes).codes;
         let mut seen: HashSet<String> = HashSet::new();
         for code in codes {
             if seen.insert(code.clone()) {
@@ -93,7 +58,7 @@ pub fn most_common_connect_code(files: &[PathBuf]) -> Result<String, String> {
 pub fn find_opponent_code(primary: &str, files: &[PathBuf]) -> Option<String> {
     for file in files {
         let bytes = fs::read(file).ok()?;
-        let codes = extract_connect_codes(&bytes);
+        let codes = connect_codes_from_bytes(&bytes).codes;
         for code in codes {
             if code != primary {
                 return Some(code);
@@ -106,7 +71,7 @@ pub fn find_opponent_code(primary: &str, files: &[PathBuf]) -> Option<String> {
 pub fn find_opponent_code_in_replay(primary: &str, replay_path: &Path) -> Option<String> {
     let primary_norm = normalize_slippi_code(primary)?;
     let bytes = fs::read(replay_path).ok()?;
-    let codes = extract_connect_codes(&bytes);
+    let codes = connect_codes_from_bytes(&bytes).codes;
     for code in codes {
         let Some(norm) = normalize_slippi_code(&code) else {
             continue;
@@ -118,121 +83,6 @@ pub fn find_opponent_code_in_replay(primary: &str, replay_path: &Path) -> Option
     None
 }
 
-pub fn tag_from_code(code: &str) -> String {
-    code.split('#').next().unwrap_or(code).to_string()
-}
-
-pub fn map_character(id: u8) -> Option<&'static str> {
-    match id {
-        0x00 => Some("Captain Falcon"),
-        0x01 => Some("Donkey Kong"),
-        0x02 => Some("Fox"),
-        0x03 => Some("Mr Game & Watch"),
-        0x04 => Some("Kirby"),
-        0x05 => Some("Bowser"),
-        0x06 => Some("Link"),
-        0x07 => Some("Luigi"),
-        0x08 => Some("Mario"),
-        0x09 => Some("Marth"),
-        0x0A => Some("Mewtwo"),
-        0x0B => Some("Ness"),
-        0x0C => Some("Peach"),
-        0x0D => Some("Pikachu"),
-        0x0E => Some("Ice Climbers"),
-        0x0F => Some("Jigglypuff"),
-        0x10 => Some("Samus"),
-        0x11 => Some("Yoshi"),
-        0x12 => Some("Zelda"),
-        0x13 => Some("Sheik"),
-        0x14 => Some("Falco"),
-        0x15 => Some("Young Link"),
-        0x16 => Some("Dr Mario"),
-        0x17 => Some("Roy"),
-        0x18 => Some("Pichu"),
-        0x19 => Some("Ganondorf"),
-        _ => None,
-    }
-}
-
-pub fn map_color(char_name: &str, costume: u8) -> &'static str {
-    match char_name {
-        "Fox" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", _ => "Default" },
-        "Falco" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", _ => "Default" },
-        "Marth" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "White", 5 => "Black", _ => "Default" },
-        "Sheik" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Purple", _ => "Default" },
-        "Zelda" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Purple", _ => "Default" },
-        "Jigglypuff" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Yellow", _ => "Default" },
-        "Captain Falcon" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "White", 5 => "Black", _ => "Default" },
-        "Peach" => match costume { 1 => "Blue", 2 => "Green", 3 => "White", 4 => "Yellow", _ => "Default" },
-        "Luigi" => match costume { 1 => "Blue", 2 => "Pink", 3 => "White", _ => "Default" },
-        "Mario" => match costume { 1 => "Blue", 2 => "Brown", 3 => "Green", 4 => "Yellow", _ => "Default" },
-        "Dr Mario" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Black", _ => "Default" },
-        "Pikachu" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", _ => "Default" },
-        "Samus" => match costume { 1 => "Brown", 2 => "Green", 3 => "Pink", 4 => "Purple", _ => "Default" },
-        "Ganondorf" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Purple", _ => "Default" },
-        "Roy" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Yellow", _ => "Default" },
-        "Young Link" => match costume { 1 => "Red", 2 => "Blue", 3 => "White", 4 => "Black", _ => "Default" },
-        "Link" => match costume { 1 => "Red", 2 => "Blue", 3 => "White", 4 => "Black", _ => "Default" },
-        "Yoshi" => match costume { 1 => "Red", 2 => "Blue", 3 => "Cyan", 4 => "Pink", 5 => "Yellow", _ => "Default" },
-        "Ice Climbers" => match costume { 1 => "Red", 2 => "Green", 3 => "Orange", _ => "Default" },
-        "Kirby" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "White", 5 => "Yellow", _ => "Default" },
-        "Mewtwo" => match costume { 1 => "Blue", 2 => "Green", 3 => "Yellow", _ => "Default" },
-        "Ness" => match costume { 1 => "Blue", 2 => "Green", 3 => "Yellow", _ => "Default" },
-        "Bowser" => match costume { 1 => "Red", 2 => "Blue", 3 => "Black", _ => "Default" },
-        "Pichu" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", _ => "Default" },
-        "Mr Game & Watch" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", _ => "Default" },
-        "Donkey Kong" => match costume { 1 => "Red", 2 => "Blue", 3 => "Green", 4 => "Purple", _ => "Default" },
-        _ => "Default",
-    }
-}
-
-pub fn parse_game_start(path: &Path) -> Option<ParsedGameInfo> {
-    let file = fs::File::open(path).ok()?;
-    let mut reader = BufReader::new(file);
-    if slippi::de::parse_header(&mut reader, None).is_err() {
-        return None;
-    }
-
-    let mut opts = slippi::de::Opts::default();
-    opts.skip_frames = true;
-    let state = slippi::de::parse_start(&mut reader, Some(&opts)).ok()?;
-    let start = state.start();
-    let mut players = Vec::new();
-
-    for pl in start.players.iter() {
-        let name = match map_character(pl.character) {
-            Some(n) => n.to_string(),
-            None => continue,
-        };
-        let color = map_color(&name, pl.costume).to_string();
-        let netplay = pl.netplay.as_ref().map(|n| (n.name.0.clone(), n.code.0.clone()));
-        let tag = netplay
-            .as_ref()
-            .map(|(n, _)| n.clone())
-            .or_else(|| pl.name_tag.as_ref().map(|s| s.0.clone()));
-        let code = netplay.as_ref().map(|(_, c)| c.clone());
-        let port = match pl.port {
-            Port::P1 => 1,
-            Port::P2 => 2,
-            Port::P3 => 3,
-            Port::P4 => 4,
-        };
-
-        players.push(ParsedPlayerInfo {
-            port,
-            tag,
-            code,
-            character: Some(name),
-            color: Some(color),
-        });
-    }
-
-    if players.is_empty() {
-        return None;
-    }
-    Some(ParsedGameInfo { players })
-}
-
 pub fn parse_replay_cached(cache: &mut OverlayReplayCache, path: &Path) -> Option<ParsedGameInfo> {
     let meta = fs::metadata(path).ok()?;
     let modified = meta.modified().ok()?;
@@ -290,7 +140,7 @@ pub fn replay_winner_identity(replay_path: &Path) -> Result<(Option<String>, Opt
     let tag = start_player
         .netplay
         .as_ref()
-        .map(|netplay| netplay.name.0.clone())
+        .map(|netplay| decode_netplay_name(&netplay.name.0))
         .or_else(|| start_player.name_tag.as_ref().map(|tag| tag.0.clone()));
     Ok((code, tag))
 }
@@ -330,10 +180,73 @@ pub fn set_slot_index_for_identity(
     None
 }
 
+/// Move cache entries indexed under `old_dir` to the same relative path under
+/// `new_dir` when the file is there, so a renamed or moved spectate folder
+/// doesn't force every replay to be re-read. Everything else is dropped.
+fn rebase_replay_cache(cache: &mut OverlayReplayCache, old_dir: &Path, new_dir: &Path) {
+    let rebase = |key: &str| -> Option<String> {
+        let rel = Path::new(key).strip_prefix(old_dir).ok()?;
+        let moved = new_dir.join(rel);
+        moved.is_file().then(|| moved.to_string_lossy().to_string())
+    };
+    cache.replay_mtimes = std::mem::take(&mut cache.replay_mtimes)
+        .into_iter()
+        .filter_map(|(key, modified)| Some((rebase(&key)?, modified)))
+        .collect();
+    cache.replay_codes = std::mem::take(&mut cache.replay_codes)
+        .into_iter()
+        .filter_map(|(key, codes)| Some((rebase(&key)?, codes)))
+        .collect();
+    cache.replay_code_sources = std::mem::take(&mut cache.replay_code_sources)
+        .into_iter()
+        .filter_map(|(key, source)| Some((rebase(&key)?, source)))
+        .collect();
+    cache.code_index = std::mem::take(&mut cache.code_index)
+        .into_iter()
+        .filter_map(|(code, key)| Some((code, rebase(&key)?)))
+        .collect();
+    cache.parsed = std::mem::take(&mut cache.parsed)
+        .into_iter()
+        .filter_map(|(key, parsed)| Some((rebase(&key)?, parsed)))
+        .collect();
+    cache.live.clear();
+}
+
+fn clear_replay_cache(cache: &mut OverlayReplayCache) {
+    cache.replay_mtimes.clear();
+    cache.replay_codes.clear();
+    cache.replay_code_sources.clear();
+    cache.code_index.clear();
+    cache.parsed.clear();
+    cache.live.clear();
+}
+
 pub fn update_replay_index(cache: &mut OverlayReplayCache, dir: &Path) -> Result<(), String> {
     if !dir.is_dir() {
+        // The folder was moved or renamed out from under us; don't keep
+        // serving paths that no longer exist.
+        if cache.indexed_dir.as_deref() == Some(dir) {
+            tracing::warn!("Spectate folder {} disappeared; clearing replay index", dir.display());
+            clear_replay_cache(cache);
+            cache.indexed_dir = None;
+        }
         return Ok(());
     }
+    if cache.indexed_dir.as_deref() != Some(dir) {
+        if let Some(old_dir) = cache.indexed_dir.take() {
+            tracing::info!(
+                "Spectate folder changed from {} to {}; rebasing replay index",
+                old_dir.display(),
+                dir.display()
+            );
+            rebase_replay_cache(cache, &old_dir, dir);
+        } else {
+            clear_replay_cache(cache);
+        }
+        cache.indexed_dir = Some(dir.to_path_buf());
+        // Skip the throttle so the new folder is scanned right away.
+        cache.last_scan = None;
+    }
     let now = SystemTime::now();
     if let Some(last) = cache.last_scan {
         if now
@@ -348,31 +261,29 @@ pub fn update_replay_index(cache: &mut OverlayReplayCache, dir: &Path) -> Result
 
     let mut next_mtimes = HashMap::new();
     let mut next_codes = HashMap::new();
+    let mut next_sources: HashMap<String, CodeSource> = HashMap::new();
     let mut next_index = HashMap::new();
-    let entries = fs::read_dir(dir).map_err(|e| format!("read spectate dir {}: {e}", dir.display()))?;
-    for entry in entries {
-        let entry = entry.map_err(|e| format!("read spectate entry {}: {e}", dir.display()))?;
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        if !is_replay_file_path(&path) {
-            continue;
-        }
-        let meta = entry.metadata().map_err(|e| format!("read metadata {}: {e}", path.display()))?;
+    for path in collect_slp_files(dir).map_err(|e| format!("scan spectate dir: {e}"))? {
+        let meta = fs::metadata(&path).map_err(|e| format!("read metadata {}: {e}", path.display()))?;
         let modified = match meta.modified() {
             Ok(modified) => modified,
             Err(_) => continue,
         };
         let key = path.to_string_lossy().to_string();
-        let codes = if cache.replay_mtimes.get(&key) == Some(&modified) {
-            cache.replay_codes.get(&key).cloned().unwrap_or_default()
-        } else {
-            let bytes = fs::read(&path).map_err(|e| format!("read replay {}: {e}", path.display()))?;
-            extract_connect_codes(&bytes)
+        let cached_source = cache.replay_code_sources.get(&key).copied();
+        let (codes, source) = match cached_source {
+            Some(source) if cache.replay_mtimes.get(&key) == Some(&modified) => {
+                (cache.replay_codes.get(&key).cloned().unwrap_or_default(), source)
+            }
+            _ => {
+                let bytes = fs::read(&path).map_err(|e| format!("read replay {}: {e}", path.display()))?;
+                let found = connect_codes_from_bytes(&bytes);
+                (found.codes, found.source)
+            }
         };
         next_mtimes.insert(key.clone(), modified);
         next_codes.insert(key.clone(), codes.clone());
+        next_sources.insert(key.clone(), source);
 
         for code in codes {
             let normalized = normalize_broadcast_key(&code);
@@ -394,6 +305,7 @@ pub fn update_replay_index(cache: &mut OverlayReplayCache, dir: &Path) -> Result
 
     cache.replay_mtimes = next_mtimes;
     cache.replay_codes = next_codes;
+    cache.replay_code_sources = next_sources;
     cache.code_index = next_index;
     cache.parsed.retain(|path, _| cache.replay_mtimes.contains_key(path));
     Ok(())
@@ -458,6 +370,7 @@ pub fn apply_parsed_player(target: &mut PlayerState, parsed: &ParsedPlayerInfo)
     if let Some(tag) = parsed.tag.as_ref() {
         if !tag.trim().is_empty() {
             target.tag = tag.clone();
+            target.tag_romaji = parsed.tag_romaji.clone();
         }
     } else if let Some(code) = parsed.code.as_ref() {
         if target.tag.trim().is_empty() || target.tag == "Waiting" {
@@ -475,22 +388,50 @@ pub fn apply_parsed_player(target: &mut PlayerState, parsed: &ParsedPlayerInfo)
     if let Some(color) = parsed.color.as_ref() {
         target.character_color = color.clone();
     }
+    if parsed.code.is_some() {
+        target.connect_code = parsed.code.clone();
+    }
+    if parsed.character_id.is_some() {
+        target.character_id = parsed.character_id;
+        target.costume_id = parsed.costume_id;
+    }
     if parsed.port > 0 {
         target.port = Some(parsed.port);
     }
 }
 
+/// Fill each player's ASCII tag from the tag the overlay ends up showing,
+/// which may come from start.gg or a profile rather than the replay.
+pub fn apply_ascii_tags(state: &mut AllSetupsState) {
+    for setup in state.setups.iter_mut() {
+        for player in [&mut setup.p1, &mut setup.p2] {
+            player.tag_romaji = ascii_tag(&player.tag, player.connect_code.as_deref());
+        }
+    }
+}
+
 pub fn default_player(side: &str, port: u8, tag: &str, character: &str) -> PlayerState {
     PlayerState {
         side: side.to_string(),
         port: Some(port),
         tag: tag.to_string(),
+        tag_romaji: None,
         sponsor: None,
         handle: None,
         character: character.to_string(),
         character_color: "Default".to_string(),
+        character_id: None,
+        costume_id: None,
+        portrait_url: None,
+        stock_icon_url: None,
         score: 0,
         country_code: None,
+        connect_code: None,
+        rank: None,
+        socials: None,
+        connection: None,
+        stocks: None,
+        percent: None,
     }
 }
 
@@ -503,8 +444,12 @@ pub fn default_overlay_state(setup_id: u32) -> OverlayState {
             round: format!("Setup {setup_id}"),
             best_of: 3,
             game_number: None,
+            set_id: None,
+            set_state: None,
             stage: None,
+            stage_image_url: None,
             notes: None,
+            on_break: false,
         },
         commentators: Vec::new(),
     }
@@ -517,12 +462,12 @@ pub fn is_replay_file_path(path: &Path) -> bool {
     }
 }
 
-pub fn replay_slots_from_file(path: &Path) -> Vec<Value> {
+pub fn replay_slots_from_file(path: &Path) -> Vec<BracketReplaySlot> {
     let bytes = match fs::read(path) {
         Ok(bytes) => bytes,
         Err(_) => return Vec::new(),
     };
-    let codes = extract_connect_codes(&bytes);
+    let codes = connect_codes_from_bytes(&bytes).codes;
     let mut unique: Vec<String> = Vec::new();
     let mut seen: HashSet<String> = HashSet::new();
     for code in codes {
@@ -538,7 +483,7 @@ pub fn replay_slots_from_file(path: &Path) -> Vec<Value> {
     }
     unique
         .into_iter()
-        .map(|code| json!({ "slippiCode": code }))
+        .map(|code| BracketReplaySlot { slippi_code: Some(code) })
         .collect()
 }
 
@@ -566,6 +511,67 @@ pub fn scores_from_set(set: &StartggSimSet, player: &BroadcastPlayerSelection) -
     (p1_score, p2_score)
 }
 
+/// Compare a set's reported scores with the game winners found in `replays`.
+/// Replays whose winner can't be matched to either slot are listed but not
+/// counted, and friendlies are skipped. DQ'd sets are never flagged since
+/// no games were played.
+pub fn reconcile_set_scores(set: &StartggSimSet, replays: &[PathBuf]) -> ScoreReconciliation {
+    let winners = replays
+        .iter()
+        .filter(|path| !is_friendly(path))
+        .map(|path| (path.to_string_lossy().to_string(), replay_winner_identity(path).ok()));
+    reconcile_winners(set, winners)
+}
+
+/// The winner's code and tag of one game, None when its replay can't say.
+type GameWinner = Option<(Option<String>, Option<String>)>;
+
+fn reconcile_winners(set: &StartggSimSet, winners: impl Iterator<Item = (String, GameWinner)>) -> ScoreReconciliation {
+    let reported_scores = [
+        set.slots.get(0).and_then(|slot| slot.score).unwrap_or(0),
+        set.slots.get(1).and_then(|slot| slot.score).unwrap_or(0),
+    ];
+    let mut replay_scores = [0u8; 2];
+    let mut games_counted = 0usize;
+    let mut unmatched_replays = Vec::new();
+    for (replay, winner) in winners {
+        let slot = winner.and_then(|(code, tag)| {
+            let tag = tag.or_else(|| code.as_deref().map(tag_from_code));
+            set_slot_index_for_identity(set, code.as_deref(), tag.as_deref())
+        });
+        match slot {
+            Some(idx) if idx < 2 => {
+                replay_scores[idx] = replay_scores[idx].saturating_add(1);
+                games_counted += 1;
+            }
+            _ => unmatched_replays.push(replay),
+        }
+    }
+
+    let is_dq = set
+        .slots
+        .iter()
+        .any(|slot| slot.result.as_deref() == Some("dq"));
+    let matches = is_dq || games_counted == 0 || reported_scores == replay_scores;
+    let proposed_winner_slot = if replay_scores[0] > replay_scores[1] {
+        Some(0)
+    } else if replay_scores[1] > replay_scores[0] {
+        Some(1)
+    } else {
+        None
+    };
+    ScoreReconciliation {
+        set_id: set.id,
+        reported_scores,
+        replay_scores,
+        games_counted,
+        unmatched_replays,
+        matches,
+        proposed_scores: if matches { None } else { Some(replay_scores) },
+        proposed_winner_slot: if matches { None } else { proposed_winner_slot },
+    }
+}
+
 pub fn slot_label(slot: Option<&StartggSimSlot>) -> (Option<String>, Option<String>) {
     match slot {
         Some(slot) => {
@@ -636,7 +642,7 @@ pub fn build_overlay_for_setup(
     let mut expected_p2_tag = stream.p2_tag.clone();
     let mut expected_p2_code = stream.p2_code.clone();
     let mut round_label = "Waiting".to_string();
-    let mut best_of = 3u8;
+    let mut best_of = config.ruleset.best_of;
     let mut game_number = None;
     let mut p1_score = 0u32;
     let mut p2_score = 0u32;
@@ -649,10 +655,12 @@ pub fn build_overlay_for_setup(
         slippi_code: stream.p1_code.clone().unwrap_or_default(),
     };
 
-    let mut matched_set: Option<StartggSimSet> = None;
+    // An exhibition put on this setup wins over the players' bracket sets.
+    let mut matched_set: Option<StartggSimSet> = exhibition_for_setup(setup_id, now_ms());
     if let Some(state_ref) = startgg_state {
         tournament = Some(state_ref.event.name.clone());
-        if !player.name.trim().is_empty() || !player.slippi_code.trim().is_empty() {
+        let has_identity = !player.name.trim().is_empty() || !player.slippi_code.trim().is_empty();
+        if matched_set.is_none() && has_identity {
             matched_set = find_set_for_player(&state_ref.sets, &player, active_sets).cloned();
         }
     }
@@ -687,10 +695,17 @@ pub fn build_overlay_for_setup(
 
     state.meta.tournament = tournament;
     state.meta.round = round_label;
+    state.meta.set_id = matched_set.as_ref().map(|set| set.id);
+    state.meta.set_state = set_state.clone();
     state.meta.best_of = best_of;
 
     state.p1.tag = p1_tag;
     state.p1.score = p1_score;
+    state.p1.connect_code = p1_code.clone();
+    // Until a replay says otherwise, e.g. a test folder's pinned character.
+    if let Some(character) = stream.p1_character.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        state.p1.character = character.to_string();
+    }
     let mut p2_tag = expected_p2_tag
         .or_else(|| expected_p2_code.clone())
         .unwrap_or_else(|| "Waiting".to_string());
@@ -699,20 +714,18 @@ pub fn build_overlay_for_setup(
     }
     state.p2.tag = p2_tag;
     state.p2.score = p2_score;
+    state.p2.connect_code = expected_p2_code;
 
     let is_playing = stream.is_playing.unwrap_or(false)
         || matches!(set_state.as_deref(), Some("inProgress"));
-    let replay_path = if config.test_mode {
-        replay_map.get(&stream.id).cloned()
-    } else {
-        p1_code
-            .as_deref()
-            .and_then(|code| latest_replay_for_code(replay_cache, code))
-    };
+    let replay_path = stream_replay_path(stream, config, replay_map, replay_cache);
+    let mut ports_known = false;
     if let Some(path) = replay_path {
         if let Some(parsed) = parse_replay_cached(replay_cache, &path) {
+            state.meta.stage = parsed.stage.clone();
             let (parsed_p1, parsed_p2) =
                 select_parsed_players(&parsed, p1_code.as_deref(), Some(&state.p1.tag));
+            ports_known = parsed_p1.is_some() && parsed_p2.is_some();
             if let Some(parsed_player) = parsed_p1 {
                 apply_parsed_player(&mut state.p1, &parsed_player);
             }
@@ -726,168 +739,203 @@ pub fn build_overlay_for_setup(
     }
 
     state.meta.game_number = game_number;
+    apply_side_mapping(setup_id, &setup.side_mapping, ports_known, &mut state, replay_cache);
     state
 }
 
+/// The replay a stream's players are read from: its spoofed replay in test
+/// mode, otherwise the newest one with its P1 code.
+fn stream_replay_path(
+    stream: &SlippiStream,
+    config: &AppConfig,
+    replay_map: &HashMap<String, PathBuf>,
+    replay_cache: &OverlayReplayCache,
+) -> Option<PathBuf> {
+    if config.test_flags().fake_replays {
+        replay_map.get(&stream.id).cloned()
+    } else {
+        stream
+            .p1_code
+            .as_deref()
+            .and_then(|code| latest_replay_for_code(replay_cache, code))
+    }
+}
+
+/// Decode what's been written to `path` since it was last read. None once
+/// the game has ended, or when the file has stopped growing without an end
+/// (a disconnect).
+fn tail_live_replay<'a>(cache: &'a mut OverlayReplayCache, path: &Path) -> Option<&'a LiveReplay> {
+    let meta = fs::metadata(path).ok()?;
+    let stale = meta
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|since| since > LIVE_REPLAY_STALE);
+    let live = cache.live.entry(path.to_string_lossy().to_string()).or_default();
+    live.read_from(path).ok()?;
+    (!live.ended && !stale).then_some(&*live)
+}
+
+/// Stocks and percent from the game in progress, matched by port; cleared
+/// when there's none.
+fn apply_live_hud(state: &mut OverlayState, live: Option<&LiveReplay>) {
+    for player in [&mut state.p1, &mut state.p2] {
+        let frame = live.zip(player.port).and_then(|(live, port)| live.player(port));
+        player.stocks = frame.map(|frame| frame.stocks);
+        player.percent = frame.map(|frame| frame.percent);
+    }
+}
+
+fn side_key(player: &PlayerState) -> Option<String> {
+    player
+        .connect_code
+        .as_deref()
+        .map(normalize_broadcast_key)
+        .filter(|key| !key.is_empty())
+}
+
+/// Put the players on the sides the setup's policy asks for. Everything
+/// about a player (score, character, code) moves with them.
+pub fn apply_side_mapping(
+    setup_id: u32,
+    mapping: &SideMapping,
+    ports_known: bool,
+    state: &mut OverlayState,
+    cache: &mut OverlayReplayCache,
+) {
+    let swap = match mapping.policy {
+        SideMappingPolicy::ConnectCode => false,
+        SideMappingPolicy::Manual => {
+            let Some(code) = mapping.pinned_code.as_deref().map(normalize_broadcast_key) else {
+                return;
+            };
+            if mapping.pinned_side.as_deref() == Some("right") {
+                side_key(&state.p1).as_deref() == Some(code.as_str())
+            } else {
+                side_key(&state.p2).as_deref() == Some(code.as_str())
+            }
+        }
+        SideMappingPolicy::Port => {
+            let set_id = state.meta.set_id;
+            let locked = cache
+                .port_side_locks
+                .get(&setup_id)
+                .filter(|(locked_set, _)| *locked_set == set_id)
+                .map(|(_, code)| code.clone());
+            let left = match locked {
+                Some(code) => code,
+                None => {
+                    // Only lock on real ports from a replay, not the defaults.
+                    if !ports_known {
+                        return;
+                    }
+                    let (Some(p1_port), Some(p2_port)) = (state.p1.port, state.p2.port) else {
+                        return;
+                    };
+                    let lower = if p1_port <= p2_port { &state.p1 } else { &state.p2 };
+                    let Some(code) = side_key(lower) else {
+                        return;
+                    };
+                    cache.port_side_locks.insert(setup_id, (set_id, code.clone()));
+                    code
+                }
+            };
+            side_key(&state.p2).as_deref() == Some(left.as_str())
+        }
+    };
+    if swap {
+        std::mem::swap(&mut state.p1, &mut state.p2);
+        state.p1.side = "left".to_string();
+        state.p2.side = "right".to_string();
+    }
+}
+
 pub fn build_overlay_state(
     setups: &[Setup],
     startgg_state: Option<&StartggSimState>,
+    linked_state: Option<&StartggSimState>,
     active_sets: Option<&HashSet<u64>>,
     config: &AppConfig,
     replay_map: &HashMap<String, PathBuf>,
     replay_cache: &mut OverlayReplayCache,
 ) -> AllSetupsState {
-    if !config.test_mode {
+    // The indexer sleeps through idle mode; the last index still serves.
+    if !config.test_flags().fake_replays && !is_idle() {
         let spectate = config.spectate_folder_path.trim();
         if !spectate.is_empty() {
             let dir = resolve_repo_path(spectate);
             let _ = update_replay_index(replay_cache, &dir);
         }
     }
+    let now = now_ms();
+    let replays = overlay_refresh::replay_fingerprint(replay_cache, replay_map);
+    let merged_state = setups
+        .iter()
+        .any(|s| s.bracket == SetupBracket::Either)
+        .then(|| merge_states(startgg_state, linked_state))
+        .flatten();
     let mut out = Vec::with_capacity(MAX_SETUP_COUNT);
+    let mut tailed = HashSet::new();
     for id in 1..=MAX_SETUP_COUNT as u32 {
         let setup = setups.iter().find(|s| s.id == id);
-        out.push(build_overlay_for_setup(
-            id,
-            setup,
-            startgg_state,
-            active_sets,
-            config,
-            replay_map,
-            replay_cache,
-        ));
-    }
-    AllSetupsState { setups: out }
-}
-
-pub fn normalize_timestamp_ms(value: i64) -> i64 {
-    if value > 10_000_000_000 {
-        value
-    } else {
-        value * 1000
-    }
-}
-
-pub fn parse_metadata_timestamp_ms(value: &Value) -> Option<i64> {
-    match value {
-        Value::String(raw) => {
-            if let Ok(parsed) = DateTime::parse_from_rfc3339(raw) {
-                return Some(parsed.timestamp_millis());
-            }
-            if let Ok(parsed) = raw.parse::<i64>() {
-                return Some(normalize_timestamp_ms(parsed));
-            }
-            for fmt in ["%Y-%m-%d %H:%M:%S%.f", "%Y-%m-%dT%H:%M:%S%.f"] {
-                if let Ok(parsed) = NaiveDateTime::parse_from_str(raw, fmt) {
-                    return Some(DateTime::<Utc>::from_naive_utc_and_offset(parsed, Utc).timestamp_millis());
-                }
+        let driver = setup.map(|s| s.refresh_driver).unwrap_or_default();
+        let bracket_state = match setup.map(|s| s.bracket).unwrap_or_default() {
+            SetupBracket::Main => startgg_state,
+            SetupBracket::Linked => linked_state,
+            SetupBracket::Either => merged_state.as_ref(),
+        };
+        let inputs = RefreshInputs {
+            setup: overlay_refresh::setup_fingerprint(setup),
+            replays,
+            bracket: overlay_refresh::bracket_fingerprint(bracket_state),
+        };
+        let mut overlay = overlay_refresh::refresh_overlay(replay_cache, id, driver, inputs, now, |cache| {
+            build_overlay_for_setup(id, setup, bracket_state, active_sets, config, replay_map, cache)
+        });
+        // Live HUD data follows every frame, whatever the setup's driver.
+        let replay = setup
+            .and_then(|setup| setup.assigned_stream.as_ref())
+            .and_then(|stream| stream_replay_path(stream, config, replay_map, replay_cache));
+        let live = match replay {
+            Some(path) => {
+                tailed.insert(path.to_string_lossy().to_string());
+                tail_live_replay(replay_cache, &path)
             }
-            None
-        }
-        Value::Number(num) => num.as_i64().map(normalize_timestamp_ms),
-        _ => None,
+            None => None,
+        };
+        apply_live_hud(&mut overlay, live);
+        out.push(overlay);
     }
-}
-
-pub fn replay_metadata_timestamp_ms(path: &Path) -> Option<i64> {
-    let file = fs::File::open(path).ok()?;
-    let mut opts = slippi::de::Opts::default();
-    opts.skip_frames = true;
-    let game = slippi::de::read(file, Some(&opts)).ok()?;
-    let metadata = game.metadata?;
-    for key in ["startAt", "playedOn", "startTime", "date"] {
-        if let Some(value) = metadata.get(key) {
-            if let Some(timestamp) = parse_metadata_timestamp_ms(value) {
-                return Some(timestamp);
-            }
-        }
+    replay_cache.live.retain(|key, _| tailed.contains(key));
+    AllSetupsState {
+        setups: out,
+        timezone: Some(timezone_info(now)),
     }
-    None
-}
-
-pub fn replay_modified_timestamp_ms(path: &Path) -> Option<i64> {
-    let modified = fs::metadata(path).ok()?.modified().ok()?;
-    let duration = modified.duration_since(UNIX_EPOCH).ok()?;
-    Some(duration.as_millis() as i64)
-}
-
-pub fn sort_replay_paths_by_start_time(paths: Vec<PathBuf>) -> Vec<PathBuf> {
-    let mut entries: Vec<(i64, usize, PathBuf)> = paths
-        .into_iter()
-        .enumerate()
-        .map(|(idx, path)| {
-            let key = replay_metadata_timestamp_ms(&path)
-                .or_else(|| replay_modified_timestamp_ms(&path))
-                .unwrap_or(i64::MAX);
-            (key, idx, path)
-        })
-        .collect();
-    entries.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.cmp(&b.1)));
-    entries.into_iter().map(|(_, _, path)| path).collect()
 }
 
+/// Last frame of a replay: `lastFrame` from its metadata when it has one,
+/// else the highest frame number in it.
 pub fn slippi_last_frame(replay_path: &Path) -> Result<i32, String> {
-    let node_path = build_node_path()?;
-    let script = r#"
-const { SlippiGame } = require('@slippi/slippi-js/node');
-const input = process.argv[1];
-if (!input) process.exit(2);
-const game = new SlippiGame(input);
-const meta = game.getMetadata() || {};
-let last = typeof meta.lastFrame === 'number' ? meta.lastFrame : null;
-if (last === null) {
-  const frames = game.getFrames() || {};
-  for (const key of Object.keys(frames)) {
-    const num = Number(key);
-    if (Number.isFinite(num)) {
-      if (last === null || num > last) last = num;
-    }
-  }
-}
-if (last === null) process.exit(2);
-console.log(last);
-"#;
-    let output = Command::new("node")
-        .env("NODE_PATH", node_path)
-        .arg("-e")
-        .arg(script)
-        .arg(replay_path)
-        .output()
-        .map_err(|e| format!("run node for replay length: {e}"))?;
-    if !output.status.success() {
-        return Err(format!(
-            "node failed to read replay length: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-    let raw = String::from_utf8_lossy(&output.stdout).trim().to_string();
-    raw
-        .parse::<i32>()
-        .map_err(|e| format!("parse replay length from node output '{raw}': {e}"))
-}
-
-pub fn write_playback_config(replay_path: &Path, output_dir: &Path, command_id: &str) -> Result<(PathBuf, String), String> {
-    let last_frame = slippi_last_frame(replay_path)?;
-    let start_frame = -123i32;
-    let mut end_frame = last_frame.saturating_sub(1);
-    if end_frame <= start_frame {
-        end_frame = start_frame + 1;
-    }
-
-    let file_basename = format!("playback_{command_id}");
-    let config_path = output_dir.join(format!("{file_basename}.json"));
-    let payload = json!({
-        "mode": "normal",
-        "replay": replay_path.to_string_lossy(),
-        "startFrame": start_frame,
-        "endFrame": end_frame,
-        "isRealTimeMode": false,
-        "commandId": command_id,
-    });
-    let contents = serde_json::to_string_pretty(&payload).map_err(|e| e.to_string())?;
-    fs::write(&config_path, contents)
-        .map_err(|e| format!("write playback config {}: {e}", config_path.display()))?;
-    Ok((config_path, file_basename))
+    let read = |opts: Option<&slippi::de::Opts>| {
+        let file = fs::File::open(replay_path).map_err(|e| format!("open replay {}: {e}", replay_path.display()))?;
+        slippi::de::read(file, opts).map_err(|e| format!("parse replay {}: {e}", replay_path.display()))
+    };
+    let mut opts = slippi::de::Opts::default();
+    opts.skip_frames = true;
+    let last = read(Some(&opts))?
+        .metadata
+        .and_then(|metadata| metadata.get("lastFrame").and_then(Value::as_i64));
+    if let Some(last) = last {
+        return Ok(last as i32);
+    }
+    read(None)?
+        .frames
+        .id
+        .values()
+        .iter()
+        .max()
+        .copied()
+        .ok_or_else(|| format!("Replay {} has no frames.", replay_path.display()))
 }
 
 pub fn format_game_name(now: DateTime<Local>) -> String {
@@ -1043,3 +1091,113 @@ pub fn set_matches_broadcast(set: &StartggSimSet, guard: &TestModeState) -> bool
     }
     false
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_replays::{fixture_replay, fixture_replay_with_metadata};
+
+    fn set(scores: [u8; 2], result: Option<&str>) -> StartggSimSet {
+        let slot = |name: &str, code: &str, score: u8| StartggSimSlot {
+            entrant_name: Some(name.to_string()),
+            slippi_code: Some(code.to_string()),
+            score: Some(score),
+            result: result.map(str::to_string),
+            ..Default::default()
+        };
+        StartggSimSet {
+            id: 7,
+            phase_id: "p".to_string(),
+            phase_name: "Pools".to_string(),
+            round: 1,
+            round_label: "Round 1".to_string(),
+            best_of: 3,
+            state: "completed".to_string(),
+            started_at_ms: Some(1),
+            completed_at_ms: Some(2),
+            updated_at_ms: 2,
+            slots: vec![slot("Mango", "MANG#001", scores[0]), slot("Zain", "ZAIN#908", scores[1])],
+            ..Default::default()
+        }
+    }
+
+    fn won_by(replay: &str, code: Option<&str>, tag: Option<&str>) -> (String, GameWinner) {
+        (replay.to_string(), Some((code.map(str::to_string), tag.map(str::to_string))))
+    }
+
+    #[test]
+    fn replays_that_agree_with_the_reported_score_match() {
+        let games = vec![
+            won_by("1.slp", Some("mang#001"), None),
+            won_by("2.slp", Some("ZAIN#908"), None),
+            won_by("3.slp", None, Some("Mango")),
+        ];
+        let result = reconcile_winners(&set([2, 1], None), games.into_iter());
+        assert!(result.matches);
+        assert_eq!(result.replay_scores, [2, 1]);
+        assert_eq!(result.games_counted, 3);
+        assert_eq!(result.proposed_scores, None);
+        assert_eq!(result.proposed_winner_slot, None);
+    }
+
+    #[test]
+    fn replays_that_disagree_propose_their_score() {
+        let games = vec![won_by("1.slp", Some("ZAIN#908"), None), won_by("2.slp", Some("ZAIN#908"), None)];
+        let result = reconcile_winners(&set([2, 0], None), games.into_iter());
+        assert!(!result.matches);
+        assert_eq!(result.reported_scores, [2, 0]);
+        assert_eq!(result.proposed_scores, Some([0, 2]));
+        assert_eq!(result.proposed_winner_slot, Some(1));
+    }
+
+    #[test]
+    fn unreadable_and_unknown_winners_are_listed_but_not_counted() {
+        let games = vec![
+            won_by("1.slp", Some("MANG#001"), None),
+            ("2.slp".to_string(), None),
+            won_by("3.slp", Some("HBOX#305"), Some("Hbox")),
+        ];
+        let result = reconcile_winners(&set([1, 0], None), games.into_iter());
+        assert!(result.matches);
+        assert_eq!(result.games_counted, 1);
+        assert_eq!(result.unmatched_replays, vec!["2.slp", "3.slp"]);
+
+        // Only some of the games' replays: the score they show is proposed.
+        let partial = vec![won_by("1.slp", Some("MANG#001"), None), won_by("2.slp", Some("ZAIN#908"), None)];
+        let result = reconcile_winners(&set([2, 1], None), partial.into_iter());
+        assert!(!result.matches);
+        assert_eq!(result.proposed_scores, Some([1, 1]));
+        assert_eq!(result.proposed_winner_slot, None);
+    }
+
+    #[test]
+    fn sets_without_counted_games_or_with_a_dq_are_never_flagged() {
+        assert!(reconcile_winners(&set([2, 0], None), std::iter::empty()).matches);
+        let games = vec![won_by("1.slp", Some("ZAIN#908"), None)];
+        assert!(reconcile_winners(&set([0, 0], Some("dq")), games.into_iter()).matches);
+    }
+
+    fn write_replay(name: &str, bytes: Vec<u8>) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{name}-{}.slp", std::process::id()));
+        fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn the_last_frame_comes_from_metadata_when_it_has_one() {
+        // `lastFrame` as a UBJSON int32; deliberately not the replay's real last frame.
+        let mut metadata = b"{U\x09lastFramel".to_vec();
+        metadata.extend(4000i32.to_be_bytes());
+        metadata.push(b'}');
+        let path = write_replay("last-frame-metadata", fixture_replay_with_metadata(5, &metadata));
+        assert_eq!(slippi_last_frame(&path).unwrap(), 4000);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn without_metadata_the_last_frame_is_the_highest_one_in_the_replay() {
+        let path = write_replay("last-frame-scan", fixture_replay(5));
+        assert_eq!(slippi_last_frame(&path).unwrap(), -119);
+        let _ = fs::remove_file(&path);
+    }
+}
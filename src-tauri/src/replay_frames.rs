@@ -0,0 +1,134 @@
+use crate::config::*;
+use crate::playback::PLAYBACK_FIRST_FRAME;
+use crate::replay::slippi_last_frame;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+/// Loaded from disk on first use, keyed by replay path.
+static FRAMES: Mutex<Option<HashMap<String, ReplayFrames>>> = Mutex::new(None);
+
+/// Length of one replay, valid while the file's mtime and size match what
+/// was read. Replays still being written miss until they stop growing.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayFrames {
+    pub last_frame: i32,
+    pub duration_ms: u64,
+    modified_ms: u64,
+    size: u64,
+}
+
+impl ReplayFrames {
+    fn new(last_frame: i32, (modified_ms, size): (u64, u64)) -> Self {
+        let frames = (last_frame - PLAYBACK_FIRST_FRAME + 1).max(0) as u64;
+        ReplayFrames {
+            last_frame,
+            duration_ms: frames * 1000 / 60,
+            modified_ms,
+            size,
+        }
+    }
+
+    fn matches(&self, stamp: (u64, u64)) -> bool {
+        (self.modified_ms, self.size) == stamp
+    }
+}
+
+fn frames_path() -> PathBuf {
+    repo_root().join("logs").join("replay_frames.json")
+}
+
+/// Modified time and size, which together say whether a cached entry
+/// still describes the file.
+fn file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((modified.as_millis() as u64, meta.len()))
+}
+
+/// Entries for replays that have since been deleted are dropped.
+fn load_frames() -> HashMap<String, ReplayFrames> {
+    let path = frames_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return HashMap::new();
+    };
+    let mut frames: HashMap<String, ReplayFrames> = serde_json::from_str(&data).unwrap_or_else(|err| {
+        tracing::warn!("Ignoring unreadable {}: {}", path.display(), err);
+        HashMap::new()
+    });
+    frames.retain(|key, _| Path::new(key).is_file());
+    frames
+}
+
+fn save_frames(frames: &HashMap<String, ReplayFrames>) -> Result<(), String> {
+    let path = frames_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let body = serde_json::to_vec(frames).map_err(|e| format!("serialize replay frames: {e}"))?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+fn cached(path: &Path, stamp: (u64, u64)) -> Option<ReplayFrames> {
+    let mut guard = FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+    let frames = guard.get_or_insert_with(load_frames);
+    frames.get(&*path.to_string_lossy()).filter(|entry| entry.matches(stamp)).cloned()
+}
+
+fn store(path: &Path, entry: ReplayFrames) {
+    let mut guard = FRAMES.lock().unwrap_or_else(|e| e.into_inner());
+    let frames = guard.get_or_insert_with(load_frames);
+    frames.insert(path.to_string_lossy().to_string(), entry);
+    if let Err(err) = save_frames(frames) {
+        tracing::warn!("Failed to save replay frame counts: {}", err);
+    }
+}
+
+/// Last frame and duration of a replay, reading the file only the first
+/// time it is seen at its current size and mtime.
+pub fn replay_frames(path: &Path) -> Result<ReplayFrames, String> {
+    let stamp = file_stamp(path).ok_or_else(|| format!("Replay not found: {}", path.display()))?;
+    if let Some(entry) = cached(path, stamp) {
+        return Ok(entry);
+    }
+    let entry = ReplayFrames::new(slippi_last_frame(path)?, stamp);
+    store(path, entry.clone());
+    Ok(entry)
+}
+
+/// Record a frame count found while parsing a replay for something else,
+/// so later lookups don't read it again.
+pub fn record_last_frame(path: &Path, last_frame: i32) {
+    if let Some(stamp) = file_stamp(path) {
+        if cached(path, stamp).is_none() {
+            store(path, ReplayFrames::new(last_frame, stamp));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_counts_from_the_first_frame() {
+        assert_eq!(ReplayFrames::new(PLAYBACK_FIRST_FRAME + 59, (1_000, 2_048)).duration_ms, 1_000);
+        assert_eq!(ReplayFrames::new(PLAYBACK_FIRST_FRAME - 5, (0, 0)).duration_ms, 0);
+    }
+
+    #[test]
+    fn cached_frames_are_reused_only_for_the_same_file() {
+        let entry = ReplayFrames::new(PLAYBACK_FIRST_FRAME + 59, (1_000, 2_048));
+        assert!(entry.matches((1_000, 2_048)));
+        // Still being written: the size moved on.
+        assert!(!entry.matches((1_000, 4_096)));
+        assert!(!entry.matches((2_000, 2_048)));
+    }
+}
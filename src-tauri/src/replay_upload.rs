@@ -0,0 +1,465 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::event_templates::link_slug;
+use crate::jobs::Job;
+use crate::network::client_builder;
+use crate::replay::sort_replay_paths_by_start_time;
+use crate::result_card::attach_replay_urls;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_report::set_games;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use specta::Type;
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Manager};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Serializes writes to the manifest file.
+static MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedReplay {
+    pub path: String,
+    pub url: String,
+    pub bytes: u64,
+    pub sha256: String,
+}
+
+/// Where one set's replays ended up.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetReplayManifest {
+    pub set_id: u64,
+    pub setup_id: Option<u32>,
+    pub round_label: String,
+    pub backend: ReplayUploadBackend,
+    /// In play order
+    pub files: Vec<UploadedReplay>,
+    pub uploaded_at_ms: u64,
+}
+
+fn manifest_path() -> PathBuf {
+    repo_root().join("replay_uploads.json")
+}
+
+/// Every set uploaded this event, in upload order.
+pub fn replay_manifests() -> Vec<SetReplayManifest> {
+    fs::read_to_string(manifest_path())
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Links to a set's uploaded replays; empty until they're uploaded.
+pub fn replay_urls(set_id: u64) -> Vec<String> {
+    replay_manifests()
+        .into_iter()
+        .find(|manifest| manifest.set_id == set_id)
+        .map(|manifest| manifest.files.into_iter().map(|file| file.url).collect())
+        .unwrap_or_default()
+}
+
+fn record_manifest(manifest: SetReplayManifest) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut manifests = replay_manifests();
+    manifests.retain(|existing| existing.set_id != manifest.set_id);
+    manifests.push(manifest);
+    let body = serde_json::to_vec_pretty(&manifests).map_err(|e| format!("serialize replay uploads: {e}"))?;
+    write_file_atomic(&manifest_path(), &body)
+}
+
+/// Move the manifest into `dir` when a new event starts.
+pub fn archive_replay_manifests(dir: &Path) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    archive_file(&manifest_path(), dir)
+}
+
+/// Undo `archive_replay_manifests`.
+pub fn restore_replay_manifests(dir: &Path) -> Result<(), String> {
+    let _guard = MANIFEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    restore_archived_file(&manifest_path(), dir)
+}
+
+// ── Backends ────────────────────────────────────────────────────────────
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// The SigV4 signing key for a day's requests to one service.
+fn signing_key(secret_key: &str, date: &str, region: &str, service: &str) -> [u8; 32] {
+    let mut key = hmac_sha256(format!("AWS4{secret_key}").as_bytes(), date.as_bytes());
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    key
+}
+
+/// The SigV4 signature of a canonical request sent at `amz_date`.
+fn sigv4_signature(secret_key: &str, amz_date: &str, region: &str, service: &str, canonical: &str) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let to_sign = format!("AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}", hex(&Sha256::digest(canonical.as_bytes())));
+    hex(&hmac_sha256(&signing_key(secret_key, date, region, service), to_sign.as_bytes()))
+}
+
+/// Percent-encode a URL path, keeping the slashes.
+fn encode_path(path: &str) -> String {
+    let mut out = String::new();
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => out.push(byte as char),
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// The folder a set's replays go in, from `path_pattern`.
+fn set_folder(pattern: &str, event: &str, set_id: u64) -> String {
+    let pattern = if pattern.trim().is_empty() { "{event}/set-{set}" } else { pattern };
+    pattern
+        .replace("{event}", event)
+        .replace("{set}", &set_id.to_string())
+        .split('/')
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn join_url(base: &str, key: &str) -> String {
+    format!("{}/{}", base.trim().trim_end_matches('/'), encode_path(key))
+}
+
+/// AWS Signature V4 `Authorization` header for an unsigned-query PUT.
+fn s3_authorization(
+    upload: &ReplayUploadConfig,
+    host: &str,
+    uri: &str,
+    payload_hash: &str,
+    amz_date: &str,
+) -> String {
+    let date = &amz_date[..8];
+    let region = if upload.region.trim().is_empty() { "us-east-1" } else { upload.region.trim() };
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let headers = format!("host:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n");
+    let canonical = format!("PUT\n{uri}\n\n{headers}\n{signed_headers}\n{payload_hash}");
+    let signature = sigv4_signature(upload.secret_key.trim(), amz_date, region, "s3", &canonical);
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        upload.access_key.trim()
+    )
+}
+
+fn check_status(response: reqwest::blocking::Response, url: &str) -> Result<(), String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(());
+    }
+    let body = response.text().unwrap_or_default();
+    Err(format!("PUT {url}: {status} {}", body.trim()))
+}
+
+fn put_s3(
+    client: &reqwest::blocking::Client,
+    upload: &ReplayUploadConfig,
+    key: &str,
+    body: Vec<u8>,
+) -> Result<String, String> {
+    if upload.access_key.trim().is_empty() || upload.secret_key.trim().is_empty() {
+        return Err("S3 uploads need an access key and secret key.".to_string());
+    }
+    let url = join_url(&upload.target, key);
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("invalid bucket URL {url}: {e}"))?;
+    let host = match (parsed.host_str(), parsed.port()) {
+        (Some(host), Some(port)) => format!("{host}:{port}"),
+        (Some(host), None) => host.to_string(),
+        (None, _) => return Err(format!("Bucket URL {url} has no host.")),
+    };
+    let payload_hash = hex(&Sha256::digest(&body));
+    let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let authorization = s3_authorization(upload, &host, parsed.path(), &payload_hash, &amz_date);
+    let response = client
+        .put(parsed)
+        .header("x-amz-content-sha256", &payload_hash)
+        .header("x-amz-date", &amz_date)
+        .header("Authorization", authorization)
+        .header("Content-Type", "application/octet-stream")
+        .body(body)
+        .send()
+        .map_err(|e| format!("PUT {url}: {e}"))?;
+    check_status(response, &url)?;
+    Ok(url)
+}
+
+fn put_webdav(
+    client: &reqwest::blocking::Client,
+    upload: &ReplayUploadConfig,
+    key: &str,
+    body: Vec<u8>,
+) -> Result<String, String> {
+    let with_auth = |request: reqwest::blocking::RequestBuilder| {
+        if upload.access_key.trim().is_empty() {
+            request
+        } else {
+            request.basic_auth(upload.access_key.trim(), Some(upload.secret_key.as_str()))
+        }
+    };
+    // Collections have to exist before anything is put in them; MKCOL on
+    // one that already exists fails, which is fine.
+    let parts = key.split('/').collect::<Vec<_>>();
+    for depth in 1..parts.len() {
+        let url = format!("{}/", join_url(&upload.target, &parts[..depth].join("/")));
+        let method = reqwest::Method::from_bytes(b"MKCOL").map_err(|e| e.to_string())?;
+        with_auth(client.request(method, &url))
+            .send()
+            .map_err(|e| format!("MKCOL {url}: {e}"))?;
+    }
+    let url = join_url(&upload.target, key);
+    let response = with_auth(client.put(&url))
+        .body(body)
+        .send()
+        .map_err(|e| format!("PUT {url}: {e}"))?;
+    check_status(response, &url)?;
+    Ok(url)
+}
+
+fn copy_to_share(upload: &ReplayUploadConfig, key: &str, source: &Path) -> Result<String, String> {
+    let dest = resolve_repo_path(upload.target.trim()).join(key);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    fs::copy(source, &dest).map_err(|e| format!("copy {} to {}: {e}", source.display(), dest.display()))?;
+    Ok(dest.display().to_string())
+}
+
+/// Upload one replay; returns the URL it was stored at.
+fn upload_file(
+    client: &reqwest::blocking::Client,
+    upload: &ReplayUploadConfig,
+    key: &str,
+    source: &Path,
+    body: Vec<u8>,
+) -> Result<String, String> {
+    let stored = match upload.backend {
+        ReplayUploadBackend::Off => return Err("Replay uploads are off in settings.".to_string()),
+        ReplayUploadBackend::S3 => put_s3(client, upload, key, body)?,
+        ReplayUploadBackend::WebDav => put_webdav(client, upload, key, body)?,
+        ReplayUploadBackend::LocalShare => copy_to_share(upload, key, source)?,
+    };
+    if upload.public_base_url.trim().is_empty() {
+        Ok(stored)
+    } else {
+        Ok(join_url(&upload.public_base_url, key))
+    }
+}
+
+fn upload_replays(
+    job: &Job,
+    client: &reqwest::blocking::Client,
+    upload: &ReplayUploadConfig,
+    folder: &str,
+    replays: Vec<PathBuf>,
+) -> Result<Vec<UploadedReplay>, String> {
+    let total = replays.len();
+    let mut files = Vec::new();
+    for (idx, replay) in replays.iter().enumerate() {
+        let name = replay
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("{} has no file name.", replay.display()))?;
+        job.step(idx, total, format!("Uploading {name}"))?;
+        let body = fs::read(replay).map_err(|e| format!("read {}: {e}", replay.display()))?;
+        let sha256 = hex(&Sha256::digest(&body));
+        let bytes = body.len() as u64;
+        let url = upload_file(client, upload, &format!("{folder}/{name}"), replay, body)?;
+        files.push(UploadedReplay { path: replay.display().to_string(), url, bytes, sha256 });
+    }
+    Ok(files)
+}
+
+fn validate(config: &AppConfig) -> Result<(), String> {
+    let upload = &config.replay_upload;
+    if upload.backend == ReplayUploadBackend::Off {
+        return Err("Replay uploads are off in settings.".to_string());
+    }
+    if upload.target.trim().is_empty() {
+        return Err("Set where replays are uploaded to first.".to_string());
+    }
+    if config.offline_mode && upload.backend != ReplayUploadBackend::LocalShare {
+        return Err("Offline mode is on; replays can only go to a local share.".to_string());
+    }
+    Ok(())
+}
+
+/// Upload `replays` for `set_id` and record where they went. The set's
+/// result card, if it's on screen, picks up the links.
+pub fn upload_set_replays_inner(
+    app: &AppHandle,
+    config: &AppConfig,
+    set_id: u64,
+    setup_id: Option<u32>,
+    round_label: &str,
+    replays: Vec<PathBuf>,
+) -> Result<SetReplayManifest, String> {
+    validate(config)?;
+    if replays.is_empty() {
+        return Err(format!("No replays were recorded for set {set_id}."));
+    }
+    let upload = &config.replay_upload;
+    let folder = set_folder(&upload.path_pattern, &link_slug(&config.startgg_link), set_id);
+    let client = client_builder()?
+        .timeout(UPLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| format!("build upload client: {e}"))?;
+    let job = Job::start(app, "replayUpload", format!("Upload set {set_id} replays"), true);
+    let result = upload_replays(&job, &client, upload, &folder, sort_replay_paths_by_start_time(replays));
+    let files = job.finish(result, |files| format!("Uploaded {} replay(s).", files.len()))?;
+    let manifest = SetReplayManifest {
+        set_id,
+        setup_id,
+        round_label: round_label.to_string(),
+        backend: upload.backend,
+        files,
+        uploaded_at_ms: now_ms(),
+    };
+    record_manifest(manifest.clone())?;
+    tracing::info!("Uploaded {} replay(s) for set {}", manifest.files.len(), set_id);
+    let urls = manifest.files.iter().map(|file| file.url.clone()).collect::<Vec<_>>();
+    attach_replay_urls(set_id, &urls);
+    Ok(manifest)
+}
+
+/// Upload the replays of each set that finishes while an upload backend is
+/// configured. Each set is tried once; `upload_set_replays` retries.
+pub fn spawn_replay_upload_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut attempted: HashSet<u64> = replay_manifests().iter().map(|manifest| manifest.set_id).collect();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let Ok(config) = cached_config() else {
+                continue;
+            };
+            if validate(&config).is_err() {
+                continue;
+            }
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let Some(bracket) = current_bracket_state(&test_state, &live_startgg) else {
+                continue;
+            };
+            for (set_id, (setup_id, replays)) in set_games() {
+                let Some(set) = bracket.sets.iter().find(|set| set.id == set_id && set.state == "completed") else {
+                    continue;
+                };
+                if !attempted.insert(set_id) {
+                    continue;
+                }
+                if let Err(err) =
+                    upload_set_replays_inner(&app, &config, set_id, Some(setup_id), &set.round_label, replays)
+                {
+                    tracing::warn!("Uploading set {} replays: {}", set_id, err);
+                }
+            }
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Where each uploaded set's replays went.
+#[tauri::command]
+#[specta::specta]
+pub fn replay_uploads() -> Vec<SetReplayManifest> {
+    replay_manifests()
+}
+
+/// Upload a set's replays now, e.g. after a failed upload or a backend
+/// change. Replaces the set's earlier links.
+#[tauri::command(async)]
+#[specta::specta]
+pub fn upload_set_replays(set_id: u64, app: AppHandle) -> Result<SetReplayManifest, String> {
+    audited("uploadSetReplays", json!({ "setId": set_id }), || {
+        let config = cached_config()?;
+        let (setup_id, replays) = set_games()
+            .remove(&set_id)
+            .ok_or_else(|| format!("No games were detected for set {set_id}."))?;
+        let test_state = app.state::<SharedTestState>().inner().clone();
+        let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+        let round_label = current_bracket_state(&test_state, &live_startgg)
+            .and_then(|state| state.sets.into_iter().find(|set| set.id == set_id))
+            .map(|set| set.round_label)
+            .unwrap_or_default();
+        upload_set_replays_inner(&app, &config, set_id, Some(setup_id), &round_label, replays)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_match_the_published_sigv4_examples() {
+        // The signing key example from AWS's SigV4 documentation.
+        let key = signing_key("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY", "20120215", "us-east-1", "iam");
+        assert_eq!(hex(&key), "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d");
+
+        // get-vanilla from the AWS SigV4 test suite.
+        let canonical = "GET\n/\n\nhost:example.amazonaws.com\nx-amz-date:20150830T123600Z\n\nhost;x-amz-date\n\
+                         e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        assert_eq!(
+            sigv4_signature(
+                "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+                "20150830T123600Z",
+                "us-east-1",
+                "service",
+                canonical
+            ),
+            "5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+        );
+    }
+
+    #[test]
+    fn uploads_are_placed_under_the_event_folder() {
+        assert_eq!(set_folder("", "weekly-42", 7), "weekly-42/set-7");
+        assert_eq!(set_folder("/replays/../{event}//{set}/", "weekly-42", 7), "replays/weekly-42/7");
+        assert_eq!(
+            join_url("https://cdn.example.com/vods/", "weekly-42/set-7/Game 1.slp"),
+            "https://cdn.example.com/vods/weekly-42/set-7/Game%201.slp"
+        );
+
+        let upload = ReplayUploadConfig {
+            backend: ReplayUploadBackend::S3,
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "secret".to_string(),
+            ..ReplayUploadConfig::default()
+        };
+        let auth = s3_authorization(&upload, "s3.example.com", "/bucket/a.slp", "abc", "20261016T120000Z");
+        let canonical = "PUT\n/bucket/a.slp\n\nhost:s3.example.com\nx-amz-content-sha256:abc\n\
+                         x-amz-date:20261016T120000Z\n\nhost;x-amz-content-sha256;x-amz-date\nabc";
+        let signature = sigv4_signature("secret", "20261016T120000Z", "us-east-1", "s3", canonical);
+        assert!(auth.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20261016/us-east-1/s3/aws4_request, "));
+        assert!(auth.ends_with(&format!("SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={signature}")));
+    }
+}
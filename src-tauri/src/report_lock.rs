@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use crate::startgg_sim::StartggSimState;
+use crate::types::{PendingReport, ReportAuditEntry, SetReportLock};
+
+/// Reporter name used when a command doesn't say who is reporting.
+pub const MANUAL_REPORTER: &str = "manual";
+/// Reporter name used for results derived from replay files.
+pub const REPLAY_REPORTER: &str = "replay";
+
+/// Oldest audit entries are dropped once the trail grows past this.
+const MAX_AUDIT_ENTRIES: usize = 2000;
+
+/// ReportLockManager keeps a reporting lock per set so replay-driven
+/// auto-reporting and manual reporting can't both write results for the
+/// same set. The first reporter to mutate a set holds it until the lock is
+/// released; every mutation is kept in an audit trail.
+#[derive(Default)]
+pub struct ReportLockManager {
+    /// Active locks indexed by set ID
+    locks: HashMap<u64, SetReportLock>,
+    /// Every reporting action, oldest first
+    audit: Vec<ReportAuditEntry>,
+}
+
+impl ReportLockManager {
+    pub fn new() -> Self {
+        ReportLockManager::default()
+    }
+
+    /// Fail if `set_id` is locked by anyone other than `reporter`.
+    pub fn check(&self, set_id: u64, reporter: &str) -> Result<(), String> {
+        match self.locks.get(&set_id) {
+            Some(lock) if lock.holder != reporter => Err(format!(
+                "Set {} is locked for reporting by {}; release the lock before reporting as {}.",
+                set_id, lock.holder, reporter
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail if any set is locked by anyone other than `reporter`.
+    pub fn check_all(&self, reporter: &str) -> Result<(), String> {
+        for lock in self.locks.values() {
+            self.check(lock.set_id, reporter)?;
+        }
+        Ok(())
+    }
+
+    /// Record a successful mutation of `set_id`, taking the lock for
+    /// `reporter` if the set wasn't locked yet.
+    pub fn record(
+        &mut self,
+        set_id: u64,
+        reporter: &str,
+        action: &str,
+        scores: Option<[u8; 2]>,
+        now_ms: u64,
+    ) {
+        let lock = self.locks.entry(set_id).or_insert_with(|| SetReportLock {
+            set_id,
+            holder: reporter.to_string(),
+            acquired_at_ms: now_ms,
+            last_action: String::new(),
+            last_action_at_ms: now_ms,
+            reported_scores: None,
+        });
+        lock.last_action = action.to_string();
+        lock.last_action_at_ms = now_ms;
+        lock.reported_scores = scores;
+        self.push_audit(set_id, reporter, action, scores, now_ms);
+    }
+
+    /// Drop the lock on `set_id` for its holder, or for anyone else with
+    /// `force`, which the audit trail records as a forced release. Returns
+    /// whether a lock was held.
+    pub fn release(&mut self, set_id: u64, reporter: &str, force: bool, now_ms: u64) -> Result<bool, String> {
+        let Some(lock) = self.locks.get(&set_id) else {
+            return Ok(false);
+        };
+        let action = if lock.holder == reporter {
+            "release"
+        } else if force {
+            "forceRelease"
+        } else {
+            return Err(format!(
+                "Set {} is locked for reporting by {}; only they can release it unless it's forced.",
+                set_id, lock.holder
+            ));
+        };
+        self.locks.remove(&set_id);
+        self.push_audit(set_id, reporter, action, None, now_ms);
+        Ok(true)
+    }
+
+    /// Drop all locks and audit history (used when the bracket is reset).
+    pub fn clear(&mut self) {
+        self.locks.clear();
+        self.audit.clear();
+    }
+
+    /// Audit entries for one set, or for every set when `set_id` is None.
+    pub fn history(&self, set_id: Option<u64>) -> Vec<ReportAuditEntry> {
+        self.audit
+            .iter()
+            .filter(|entry| set_id.map_or(true, |id| entry.set_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Locked sets whose reported result isn't confirmed by `state` yet:
+    /// either the set isn't completed, or the bracket shows different scores
+    /// than the lock holder last reported.
+    pub fn pending(&self, state: Option<&StartggSimState>) -> Vec<PendingReport> {
+        let mut pending = self
+            .locks
+            .values()
+            .filter_map(|lock| {
+                let set = state.and_then(|s| s.sets.iter().find(|set| set.id == lock.set_id));
+                let bracket_scores = set.map(|set| {
+                    [
+                        set.slots.get(0).and_then(|slot| slot.score).unwrap_or(0),
+                        set.slots.get(1).and_then(|slot| slot.score).unwrap_or(0),
+                    ]
+                });
+                let confirmed = set.is_some_and(|set| set.state == "completed")
+                    && lock.reported_scores.is_some()
+                    && bracket_scores == lock.reported_scores;
+                if confirmed {
+                    return None;
+                }
+                Some(PendingReport {
+                    lock: lock.clone(),
+                    set_state: set.map(|set| set.state.clone()),
+                    bracket_scores,
+                    history: self.history(Some(lock.set_id)),
+                })
+            })
+            .collect::<Vec<_>>();
+        pending.sort_by_key(|report| report.lock.acquired_at_ms);
+        pending
+    }
+
+    fn push_audit(
+        &mut self,
+        set_id: u64,
+        reporter: &str,
+        action: &str,
+        scores: Option<[u8; 2]>,
+        now_ms: u64,
+    ) {
+        self.audit.push(ReportAuditEntry {
+            set_id,
+            reporter: reporter.to_string(),
+            action: action.to_string(),
+            scores,
+            at_ms: now_ms,
+        });
+        if self.audit.len() > MAX_AUDIT_ENTRIES {
+            let excess = self.audit.len() - MAX_AUDIT_ENTRIES;
+            self.audit.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_reporter_takes_lock() {
+        let mut locks = ReportLockManager::new();
+        assert!(locks.check(1, REPLAY_REPORTER).is_ok());
+        locks.record(1, REPLAY_REPORTER, "updateScores", Some([1, 0]), 1000);
+        assert!(locks.check(1, REPLAY_REPORTER).is_ok());
+        assert!(locks.check(1, MANUAL_REPORTER).is_err());
+        assert!(locks.check(2, MANUAL_REPORTER).is_ok());
+        assert!(locks.check_all(MANUAL_REPORTER).is_err());
+    }
+
+    #[test]
+    fn test_release_unblocks_other_reporter() {
+        let mut locks = ReportLockManager::new();
+        locks.record(1, REPLAY_REPORTER, "finishSet", Some([2, 1]), 1000);
+        assert!(locks.release(1, REPLAY_REPORTER, false, 2000).unwrap());
+        assert!(!locks.release(1, REPLAY_REPORTER, false, 2000).unwrap());
+        assert!(locks.check(1, MANUAL_REPORTER).is_ok());
+
+        let history = locks.history(Some(1));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].action, "release");
+        assert_eq!(history[1].reporter, REPLAY_REPORTER);
+    }
+
+    #[test]
+    fn test_only_the_holder_releases_without_force() {
+        let mut locks = ReportLockManager::new();
+        locks.record(1, REPLAY_REPORTER, "finishSet", Some([2, 1]), 1000);
+        assert!(locks.release(1, MANUAL_REPORTER, false, 2000).is_err());
+        assert!(locks.check(1, MANUAL_REPORTER).is_err());
+
+        assert!(locks.release(1, MANUAL_REPORTER, true, 3000).unwrap());
+        assert!(locks.check(1, MANUAL_REPORTER).is_ok());
+        let history = locks.history(Some(1));
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1].action, "forceRelease");
+        assert_eq!(history[1].reporter, MANUAL_REPORTER);
+    }
+
+    #[test]
+    fn test_pending_without_state() {
+        let mut locks = ReportLockManager::new();
+        locks.record(7, MANUAL_REPORTER, "startSet", Some([0, 0]), 1000);
+        let pending = locks.pending(None);
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].lock.set_id, 7);
+        assert!(pending[0].set_state.is_none());
+    }
+}
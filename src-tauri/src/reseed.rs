@@ -0,0 +1,328 @@
+use crate::audit_log::audited;
+use crate::bracket_config::write_entrant_seeds;
+use crate::config::*;
+use crate::sim_worker::{loaded_state, SIM_WARMING};
+use crate::startgg::{load_startgg_sim_config_from, maybe_refresh_live_startgg};
+use crate::startgg_sim::{first_round_matchups, swap_entrant_seeds, SeedMatchup, StartggSimEntrant, StartggSimState};
+use crate::startgg_sim_commands::{check_fake_bracket, rebuild_sim};
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{collections::HashMap, sync::Mutex};
+use tauri::State;
+
+/// Swaps being tried out, in the order they were made. Cleared when the
+/// bracket they were made against changes between sim and live.
+static DRAFT: Mutex<ReseedDraft> = Mutex::new(ReseedDraft { live: false, swaps: Vec::new() });
+
+struct ReseedDraft {
+    live: bool,
+    swaps: Vec<SeedSwap>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedSwap {
+    pub entrant_a: u32,
+    pub entrant_b: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SeedChange {
+    pub entrant_id: u32,
+    pub name: String,
+    pub from_seed: u32,
+    pub to_seed: u32,
+}
+
+/// The bracket's seeding with the draft's swaps applied. Nothing here is
+/// committed until `apply_sim_reseed`.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReseedPreview {
+    pub live: bool,
+    pub swaps: Vec<SeedSwap>,
+    /// Sorted by proposed seed.
+    pub entrants: Vec<StartggSimEntrant>,
+    pub changes: Vec<SeedChange>,
+    pub matchups: Vec<SeedMatchup>,
+    /// Reseeding only makes sense until the first set starts.
+    pub started: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ReseedReport {
+    pub preview: ReseedPreview,
+    /// Plain text for the TO to work from on start.gg.
+    pub text: String,
+}
+
+pub(crate) fn has_started(state: &StartggSimState) -> bool {
+    state.sets.iter().any(|set| set.started_at_ms.is_some() || set.state == "completed")
+}
+
+/// The bracket reseeding is previewed against: the simulated one, or the
+/// live start.gg event.
+fn bracket_state(
+    live: bool,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<StartggSimState, String> {
+    if live {
+        let config = cached_config()?;
+        return maybe_refresh_live_startgg(&config, live_startgg, false)
+            .ok_or_else(|| "No live start.gg bracket is loaded.".to_string());
+    }
+    check_fake_bracket()?;
+    loaded_state(test_state).ok_or_else(|| SIM_WARMING.to_string())
+}
+
+fn build_preview(state: &StartggSimState, live: bool, swaps: &[SeedSwap]) -> Result<ReseedPreview, String> {
+    let mut entrants = state.entrants.clone();
+    for swap in swaps {
+        swap_entrant_seeds(&mut entrants, swap.entrant_a, swap.entrant_b)?;
+    }
+    entrants.sort_by_key(|e| (e.seed, e.id));
+    let original = state.entrants.iter().map(|e| (e.id, e.seed)).collect::<HashMap<_, _>>();
+    let changes = entrants
+        .iter()
+        .filter_map(|e| {
+            let from_seed = original.get(&e.id).copied()?;
+            (from_seed != e.seed).then(|| SeedChange {
+                entrant_id: e.id,
+                name: e.name.clone(),
+                from_seed,
+                to_seed: e.seed,
+            })
+        })
+        .collect();
+    Ok(ReseedPreview {
+        live,
+        swaps: swaps.to_vec(),
+        matchups: first_round_matchups(&entrants),
+        entrants,
+        changes,
+        started: has_started(state),
+    })
+}
+
+/// The draft's swaps for `live`, starting a new draft if the last one was
+/// made against the other bracket.
+fn draft_swaps(live: bool) -> Vec<SeedSwap> {
+    let mut draft = DRAFT.lock().unwrap_or_else(|e| e.into_inner());
+    if draft.live != live {
+        draft.live = live;
+        draft.swaps.clear();
+    }
+    draft.swaps.clone()
+}
+
+fn matchup_side(seed: u32, entrant: Option<&StartggSimEntrant>) -> String {
+    match entrant {
+        Some(entrant) => format!("({}) {}", seed, entrant.name),
+        None => "bye".to_string(),
+    }
+}
+
+fn report_text(event_name: &str, preview: &ReseedPreview) -> String {
+    let mut lines = vec![format!("Proposed reseed for {event_name}")];
+    if preview.started {
+        lines.push("Note: sets have already started; start.gg won't allow reseeding them.".to_string());
+    }
+    lines.push(String::new());
+    if preview.changes.is_empty() {
+        lines.push("No seed changes.".to_string());
+    } else {
+        lines.push("Seed changes:".to_string());
+        for change in &preview.changes {
+            lines.push(format!("  {}: seed {} -> {}", change.name, change.from_seed, change.to_seed));
+        }
+    }
+    lines.push(String::new());
+    lines.push("First round after reseeding:".to_string());
+    for matchup in &preview.matchups {
+        lines.push(format!(
+            "  {} vs {}",
+            matchup_side(matchup.high_seed, matchup.high.as_ref()),
+            matchup_side(matchup.low_seed, matchup.low.as_ref())
+        ));
+    }
+    lines.join("\n")
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn reseed_preview(
+    live: bool,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<ReseedPreview, String> {
+    let state = bracket_state(live, &test_state, &live_startgg)?;
+    build_preview(&state, live, &draft_swaps(live))
+}
+
+/// Add a swap of two entrants' seeds to the draft and preview the result.
+#[tauri::command]
+#[specta::specta]
+pub fn reseed_swap(
+    live: bool,
+    entrant_a: u32,
+    entrant_b: u32,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<ReseedPreview, String> {
+    let state = bracket_state(live, &test_state, &live_startgg)?;
+    let mut swaps = draft_swaps(live);
+    swaps.push(SeedSwap { entrant_a, entrant_b });
+    let preview = build_preview(&state, live, &swaps)?;
+    let mut draft = DRAFT.lock().map_err(|e| e.to_string())?;
+    draft.swaps = swaps;
+    Ok(preview)
+}
+
+/// Drop the last swap, or every swap when `all` is set.
+#[tauri::command]
+#[specta::specta]
+pub fn reseed_undo(
+    live: bool,
+    all: Option<bool>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<ReseedPreview, String> {
+    let mut swaps = draft_swaps(live);
+    if all.unwrap_or(false) {
+        swaps.clear();
+    } else {
+        swaps.pop();
+    }
+    DRAFT.lock().map_err(|e| e.to_string())?.swaps = swaps.clone();
+    let state = bracket_state(live, &test_state, &live_startgg)?;
+    build_preview(&state, live, &swaps)
+}
+
+/// The live draft as text a TO can follow to reseed on start.gg by hand.
+#[tauri::command]
+#[specta::specta]
+pub fn reseed_report(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<ReseedReport, String> {
+    let state = bracket_state(true, &test_state, &live_startgg)?;
+    let preview = build_preview(&state, true, &draft_swaps(true))?;
+    Ok(ReseedReport {
+        text: report_text(&state.event.name, &preview),
+        preview,
+    })
+}
+
+/// Write the draft's seeds into the sim's bracket config and rebuild the
+/// sim from it. Refused once any set has started.
+#[tauri::command]
+#[specta::specta]
+pub fn apply_sim_reseed(test_state: State<'_, SharedTestState>) -> Result<StartggSimState, String> {
+    check_fake_bracket()?;
+    let swaps = draft_swaps(false);
+    if swaps.is_empty() {
+        return Err("No seed swaps to apply.".to_string());
+    }
+    let params = json!({ "swaps": swaps });
+    audited("applySimReseed", params, || {
+        let now = now_ms();
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        let sim = guard.startgg_sim.as_mut().ok_or_else(|| SIM_WARMING.to_string())?;
+        if sim.has_reference_sets() {
+            return Err("This bracket replays a recorded event, so its seeding can't change.".to_string());
+        }
+        let preview = build_preview(&sim.state(now), false, &swaps)?;
+        if preview.started {
+            return Err("Sets have already started; reset the bracket before reseeding.".to_string());
+        }
+        let config_path = guard.startgg_config_path.clone().unwrap_or_else(startgg_sim_config_path);
+        let seeds = preview.entrants.iter().map(|e| (e.id, e.seed)).collect::<HashMap<_, _>>();
+        write_entrant_seeds(&config_path, &seeds)?;
+        let config = load_startgg_sim_config_from(&config_path)?;
+        let state = rebuild_sim(&mut guard, config, &config_path, now)?;
+        DRAFT.lock().map_err(|e| e.to_string())?.swaps.clear();
+        Ok(state)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::{StartggSimEventConfig, StartggSimSet};
+
+    const SWAPS: [SeedSwap; 1] = [SeedSwap { entrant_a: 2, entrant_b: 4 }];
+
+    fn weekly() -> StartggSimState {
+        let mut state = StartggSimState::warming(0);
+        state.event = StartggSimEventConfig {
+            id: "e1".to_string(),
+            name: "Weekly".to_string(),
+            slug: "weekly".to_string(),
+        };
+        state.entrants = ["Alpha", "Beta", "Gamma", "Delta"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| StartggSimEntrant {
+                id: i as u32 + 1,
+                name: name.to_string(),
+                seed: i as u32 + 1,
+                slippi_code: String::new(),
+            })
+            .collect();
+        state
+    }
+
+    #[test]
+    fn the_preview_lists_each_changed_seed_in_new_seed_order() {
+        let preview = build_preview(&weekly(), true, &SWAPS).unwrap();
+        let changes = preview.changes.iter().map(|c| (c.entrant_id, c.from_seed, c.to_seed)).collect::<Vec<_>>();
+        assert_eq!(changes, vec![(4, 4, 2), (2, 2, 4)]);
+    }
+
+    #[test]
+    fn an_entrant_outside_the_bracket_cannot_be_swapped() {
+        let swaps = [SeedSwap { entrant_a: 2, entrant_b: 9 }];
+        assert_eq!(build_preview(&weekly(), true, &swaps).unwrap_err(), "Entrant 9 is not in this bracket.");
+    }
+
+    #[test]
+    fn the_report_lists_changes_and_the_new_first_round() {
+        let text = report_text("Weekly", &build_preview(&weekly(), true, &SWAPS).unwrap());
+        assert!(text.contains("Beta: seed 2 -> 4"));
+        assert!(text.contains("(1) Alpha vs (4) Beta"));
+        assert!(text.contains("(2) Delta vs (3) Gamma"));
+    }
+
+    #[test]
+    fn the_report_says_when_nothing_changes() {
+        let text = report_text("Weekly", &build_preview(&weekly(), true, &[]).unwrap());
+        assert!(text.contains("No seed changes."));
+        assert!(!text.contains("Note:"));
+    }
+
+    #[test]
+    fn a_started_set_marks_the_preview_started() {
+        let mut state = weekly();
+        assert!(!build_preview(&state, true, &SWAPS).unwrap().started);
+        state.sets.push(StartggSimSet {
+            id: 1,
+            phase_id: "p1".to_string(),
+            phase_name: "Bracket".to_string(),
+            round: 1,
+            round_label: "Winners Round 1".to_string(),
+            best_of: 3,
+            state: "inProgress".to_string(),
+            started_at_ms: Some(10),
+            updated_at_ms: 10,
+            ..Default::default()
+        });
+        assert!(build_preview(&state, true, &SWAPS).unwrap().started);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::overlay_ws::publish;
+use crate::replay_upload::replay_urls;
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    thread::sleep,
+    time::Duration,
+};
+use tauri::State;
+
+/// Overlay socket topic the result lower-third is pushed on.
+pub const RESULT_CARD_TOPIC: &str = "result-card";
+
+const MAX_CARD_SECS: u64 = 120;
+
+/// Bumped each time a card is shown, so only the latest card's timer hides
+/// it.
+static SHOWING: AtomicU64 = AtomicU64::new(0);
+/// The card on screen, so replay links can be added once they're uploaded.
+static CURRENT: Mutex<Option<ResultCardState>> = Mutex::new(None);
+
+/// What the lower-third shows: a finished set, or nothing once its time is
+/// up.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ResultCardState {
+    pub card: Option<SetResultSnapshot>,
+    pub shown_at_ms: u64,
+    pub hide_at_ms: u64,
+}
+
+/// Whether a set finishing on `setup_id` puts the card up by itself.
+fn triggers(config: &AppConfig, setup_id: u32) -> bool {
+    config.result_card_enabled
+        && config.result_card_secs > 0
+        && (config.result_card_setups.is_empty() || config.result_card_setups.contains(&setup_id))
+}
+
+/// Put `snapshot` up for `secs`, replacing any card already showing.
+fn show(mut snapshot: SetResultSnapshot, secs: u64) -> ResultCardState {
+    snapshot.replay_urls = replay_urls(snapshot.set_id);
+    let secs = secs.clamp(1, MAX_CARD_SECS);
+    let showing = SHOWING.fetch_add(1, Ordering::SeqCst) + 1;
+    let now = now_ms();
+    let state = ResultCardState {
+        card: Some(snapshot),
+        shown_at_ms: now,
+        hide_at_ms: now + secs * 1000,
+    };
+    publish(RESULT_CARD_TOPIC, &state);
+    *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = Some(state.clone());
+    std::thread::spawn(move || {
+        sleep(Duration::from_secs(secs));
+        if SHOWING.load(Ordering::SeqCst) == showing {
+            *CURRENT.lock().unwrap_or_else(|e| e.into_inner()) = None;
+            let now = now_ms();
+            publish(
+                RESULT_CARD_TOPIC,
+                &ResultCardState { card: None, shown_at_ms: now, hide_at_ms: now },
+            );
+        }
+    });
+    state
+}
+
+/// Show the card for sets that just finished on setups configured to
+/// trigger it, once the setup's stream delay has passed. When several
+/// finish at once the last one stays up.
+pub fn publish_completed(completed: Vec<SetResultSnapshot>, config: &AppConfig, setups: &[Setup]) {
+    let Some(snapshot) = completed.into_iter().filter(|snapshot| triggers(config, snapshot.setup_id)).last() else {
+        return;
+    };
+    let secs = config.result_card_secs;
+    let delay = setups
+        .iter()
+        .find(|setup| setup.id == snapshot.setup_id)
+        .map(|setup| setup.stream_delay_secs)
+        .unwrap_or(0);
+    tracing::info!("Showing result card for set {} on setup {}", snapshot.set_id, snapshot.setup_id);
+    if delay == 0 {
+        show(snapshot, secs);
+        return;
+    }
+    std::thread::spawn(move || {
+        sleep(Duration::from_secs(u64::from(delay)));
+        show(snapshot, secs);
+    });
+}
+
+/// Add the replay links to `set_id`'s card if it's still on screen.
+pub fn attach_replay_urls(set_id: u64, urls: &[String]) {
+    let mut current = CURRENT.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(state) = current.as_mut() else {
+        return;
+    };
+    let Some(card) = state.card.as_mut().filter(|card| card.set_id == set_id) else {
+        return;
+    };
+    card.replay_urls = urls.to_vec();
+    publish(RESULT_CARD_TOPIC, &*state);
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Show a finished set's card again, whichever setup it was played on.
+#[tauri::command]
+#[specta::specta]
+pub fn replay_result_card(set_id: u64, history: State<'_, SharedOverlayHistory>) -> Result<ResultCardState, String> {
+    audited("replayResultCard", json!({ "setId": set_id }), || {
+        let snapshot = history
+            .lock()
+            .map_err(|e| e.to_string())?
+            .find_set(set_id)
+            .ok_or_else(|| format!("Set {set_id} hasn't finished on any setup."))?;
+        Ok(show(snapshot, cached_config()?.result_card_secs))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> AppConfig {
+        AppConfig { result_card_enabled: true, ..AppConfig::default() }
+    }
+
+    #[test]
+    fn the_card_is_off_until_enabled() {
+        assert!(!triggers(&AppConfig::default(), 1));
+    }
+
+    #[test]
+    fn every_setup_triggers_without_a_setup_list() {
+        let config = enabled();
+        assert!(triggers(&config, 1));
+        assert!(triggers(&config, 4));
+    }
+
+    #[test]
+    fn only_listed_setups_trigger_with_a_setup_list() {
+        let config = AppConfig { result_card_setups: vec![2, 3], ..enabled() };
+        assert!(!triggers(&config, 1));
+        assert!(triggers(&config, 3));
+    }
+
+    #[test]
+    fn a_zero_second_card_never_shows() {
+        let config = AppConfig { result_card_secs: 0, ..enabled() };
+        assert!(!triggers(&config, 1));
+    }
+}
@@ -0,0 +1,319 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::sim_worker::loaded_state;
+use crate::startgg_sim::StartggSimState;
+use crate::types::{SharedLiveStartgg, SharedTestState};
+use crate::venue_time::{timezone_info, venue_rfc3339, TimeZoneInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{collections::HashSet, fs, path::PathBuf, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_BEHIND_THRESHOLD_MIN: u64 = 15;
+
+/// One block of the event day, e.g. doors, pools, top 8, finals.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleItem {
+    pub id: String,
+    pub label: String,
+    /// Planned start (epoch ms)
+    pub scheduled_ms: u64,
+    /// The item counts as started once a set whose round label contains
+    /// this (case-insensitive) is underway, e.g. "Top 8" or "Grand Final".
+    /// Items without one only start when marked by hand.
+    #[serde(default)]
+    pub round_match: Option<String>,
+    /// Start entered by the operator; wins over bracket detection
+    #[serde(default)]
+    pub actual_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunOfShow {
+    pub items: Vec<ScheduleItem>,
+    /// Notify once the event is this many minutes behind
+    pub behind_threshold_min: u64,
+}
+
+impl Default for RunOfShow {
+    fn default() -> Self {
+        RunOfShow {
+            items: Vec::new(),
+            behind_threshold_min: DEFAULT_BEHIND_THRESHOLD_MIN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleEntry {
+    pub id: String,
+    pub label: String,
+    pub scheduled_ms: u64,
+    /// Actual start if started, otherwise the schedule pushed back by the
+    /// current delay
+    pub estimated_ms: u64,
+    pub actual_ms: Option<u64>,
+    /// "upcoming", "late" (should have started) or "started"
+    pub status: String,
+    /// `scheduled_ms`, `estimated_ms` and `actual_ms` in venue time
+    pub scheduled_local: Option<String>,
+    pub estimated_local: Option<String>,
+    pub actual_local: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleTimeline {
+    pub items: Vec<ScheduleEntry>,
+    pub behind_ms: u64,
+    pub behind: bool,
+    pub now_ms: u64,
+    pub timezone: TimeZoneInfo,
+}
+
+pub fn run_of_show_path() -> PathBuf {
+    repo_root().join("run_of_show.json")
+}
+
+pub fn load_run_of_show() -> Result<RunOfShow, String> {
+    let path = run_of_show_path();
+    if !path.is_file() {
+        return Ok(RunOfShow::default());
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("read run of show {}: {e}", path.display()))?;
+    serde_json::from_str(&data).map_err(|e| format!("parse run of show {}: {e}", path.display()))
+}
+
+fn save_run_of_show_inner(mut run_of_show: RunOfShow) -> Result<RunOfShow, String> {
+    let mut ids = HashSet::new();
+    for item in &mut run_of_show.items {
+        item.id = item.id.trim().to_string();
+        if item.id.is_empty() {
+            return Err(format!("Schedule item '{}' needs an id.", item.label));
+        }
+        if !ids.insert(item.id.clone()) {
+            return Err(format!("Duplicate schedule item id '{}'.", item.id));
+        }
+    }
+    run_of_show.items.sort_by_key(|item| item.scheduled_ms);
+    let path = run_of_show_path();
+    let body = serde_json::to_vec_pretty(&run_of_show).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write run of show {}: {e}", path.display()))?;
+    Ok(run_of_show)
+}
+
+/// Earliest start of any set matching `round_match`.
+fn detected_start(state: &StartggSimState, round_match: &str) -> Option<u64> {
+    let needle = round_match.trim().to_lowercase();
+    if needle.is_empty() {
+        return None;
+    }
+    state
+        .sets
+        .iter()
+        .filter(|set| set.round_label.to_lowercase().contains(&needle))
+        .filter(|set| set.state == "inProgress" || set.state == "completed")
+        .filter_map(|set| set.started_at_ms.or(set.completed_at_ms))
+        .min()
+}
+
+/// Lay the schedule against what has actually happened. Once an item
+/// starts late (or is overdue), everything after it shifts by that delay.
+pub fn build_timeline(run_of_show: &RunOfShow, bracket: Option<&StartggSimState>, now_ms: u64) -> ScheduleTimeline {
+    let mut items = run_of_show.items.clone();
+    items.sort_by_key(|item| item.scheduled_ms);
+    let mut delay: i64 = 0;
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        let actual = item.actual_ms.or_else(|| {
+            let round_match = item.round_match.as_deref()?;
+            detected_start(bracket?, round_match)
+        });
+        let (estimated, status) = match actual {
+            Some(actual) => {
+                delay = actual as i64 - item.scheduled_ms as i64;
+                (actual, "started")
+            }
+            None => {
+                let estimated = (item.scheduled_ms as i64 + delay.max(0)) as u64;
+                if now_ms > estimated {
+                    delay = now_ms as i64 - item.scheduled_ms as i64;
+                    (now_ms, "late")
+                } else {
+                    (estimated, "upcoming")
+                }
+            }
+        };
+        entries.push(ScheduleEntry {
+            id: item.id,
+            label: item.label,
+            scheduled_ms: item.scheduled_ms,
+            estimated_ms: estimated,
+            actual_ms: actual,
+            status: status.to_string(),
+            scheduled_local: venue_rfc3339(item.scheduled_ms),
+            estimated_local: venue_rfc3339(estimated),
+            actual_local: actual.and_then(venue_rfc3339),
+        });
+    }
+    let behind_ms = delay.max(0) as u64;
+    ScheduleTimeline {
+        items: entries,
+        behind_ms,
+        behind: behind_ms >= run_of_show.behind_threshold_min * 60_000,
+        now_ms,
+        timezone: timezone_info(now_ms),
+    }
+}
+
+/// The bracket the rest of the app is showing, without triggering a
+/// start.gg fetch.
+pub fn current_bracket_state(test_state: &SharedTestState, live_startgg: &SharedLiveStartgg) -> Option<StartggSimState> {
+    if test_flags().fake_bracket {
+        loaded_state(test_state)
+    } else {
+        live_startgg.lock().unwrap_or_else(|e| e.into_inner()).state.clone()
+    }
+}
+
+pub fn current_timeline(test_state: &SharedTestState, live_startgg: &SharedLiveStartgg) -> Result<ScheduleTimeline, String> {
+    let run_of_show = load_run_of_show()?;
+    let bracket = current_bracket_state(test_state, live_startgg);
+    Ok(build_timeline(&run_of_show, bracket.as_ref(), now_ms()))
+}
+
+/// Emit `run-of-show-behind` when the event falls behind by the threshold,
+/// and again (with `behind: false`) once it catches up.
+pub fn spawn_run_of_show_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut was_behind = false;
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let Ok(timeline) = current_timeline(&test_state, &live_startgg) else {
+                continue;
+            };
+            if timeline.items.is_empty() || timeline.behind == was_behind {
+                continue;
+            }
+            was_behind = timeline.behind;
+            if timeline.behind {
+                tracing::warn!("Event is {} min behind schedule", timeline.behind_ms / 60_000);
+            }
+            let _ = app.emit("run-of-show-behind", &timeline);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_run_of_show() -> Result<RunOfShow, String> {
+    load_run_of_show()
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn save_run_of_show(run_of_show: RunOfShow) -> Result<RunOfShow, String> {
+    let params = json!({ "items": run_of_show.items.len() });
+    audited("saveRunOfShow", params, || save_run_of_show_inner(run_of_show))
+}
+
+/// Record (or clear, with `None`) when a schedule item actually started.
+#[tauri::command]
+#[specta::specta]
+pub fn set_schedule_item_actual(id: String, actual_ms: Option<u64>) -> Result<RunOfShow, String> {
+    let params = json!({ "itemId": id, "actualMs": actual_ms });
+    audited("setScheduleItemActual", params, || {
+        let mut run_of_show = load_run_of_show()?;
+        let item = run_of_show
+            .items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| format!("Schedule item '{id}' not found."))?;
+        item.actual_ms = actual_ms;
+        save_run_of_show_inner(run_of_show)
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn run_of_show_timeline(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<ScheduleTimeline, String> {
+    current_timeline(&test_state, &live_startgg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, scheduled_ms: u64, actual_ms: Option<u64>) -> ScheduleItem {
+        ScheduleItem {
+            id: id.to_string(),
+            label: id.to_string(),
+            scheduled_ms,
+            round_match: None,
+            actual_ms,
+        }
+    }
+
+    const MIN: u64 = 60_000;
+
+    fn day(pools_started: Option<u64>) -> RunOfShow {
+        RunOfShow {
+            items: vec![
+                item("doors", 0, Some(0)),
+                item("pools", 60 * MIN, pools_started),
+                item("top8", 240 * MIN, None),
+                item("finals", 300 * MIN, None),
+            ],
+            behind_threshold_min: 15,
+        }
+    }
+
+    fn estimates(timeline: &ScheduleTimeline) -> Vec<u64> {
+        timeline.items.iter().map(|entry| entry.estimated_ms / MIN).collect()
+    }
+
+    #[test]
+    fn a_late_start_pushes_later_items_back() {
+        let timeline = build_timeline(&day(Some(80 * MIN)), None, 90 * MIN);
+        assert_eq!(estimates(&timeline), vec![0, 80, 260, 320]);
+        assert_eq!(timeline.items[2].status, "upcoming");
+        assert_eq!(timeline.behind_ms, 20 * MIN);
+        assert!(timeline.behind);
+    }
+
+    #[test]
+    fn an_overdue_item_is_late_from_now() {
+        let overdue = build_timeline(&day(Some(80 * MIN)), None, 270 * MIN);
+        assert_eq!(overdue.items[2].status, "late");
+        assert_eq!(overdue.items[2].estimated_ms, 270 * MIN);
+        assert_eq!(overdue.items[3].estimated_ms, 330 * MIN);
+        assert_eq!(overdue.behind_ms, 30 * MIN);
+    }
+
+    #[test]
+    fn an_early_start_does_not_pull_the_schedule_forward() {
+        let timeline = build_timeline(&day(Some(50 * MIN)), None, 55 * MIN);
+        assert_eq!(estimates(&timeline), vec![0, 50, 240, 300]);
+        assert_eq!(timeline.behind_ms, 0);
+        assert!(!timeline.behind);
+    }
+
+    #[test]
+    fn small_delays_stay_under_the_behind_threshold() {
+        let timeline = build_timeline(&day(Some(74 * MIN)), None, 75 * MIN);
+        assert_eq!(timeline.behind_ms, 14 * MIN);
+        assert!(!timeline.behind);
+    }
+}
@@ -0,0 +1,71 @@
+use axum::{
+    extract::Path as AxumPath,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use rust_embed::RustEmbed;
+
+/// Scoreboard and break screen compiled into the binary, so a fresh install
+/// has a working browser source at /scenes/default before any overlay is
+/// built.
+#[derive(RustEmbed)]
+#[folder = "scenes/default/"]
+struct DefaultScenes;
+
+fn scene_content_type(path: &str) -> &'static str {
+    match path.rsplit('.').next().map(|ext| ext.to_ascii_lowercase()).as_deref() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn serve_default_scene(path: &str) -> Response {
+    let path = path.trim_matches('/');
+    let path = if path.is_empty() { "index.html" } else { path };
+    match DefaultScenes::get(path) {
+        Some(file) => (
+            [("Content-Type", scene_content_type(path)), ("Cache-Control", "no-cache")],
+            file.data.into_owned(),
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+pub async fn get_default_scene_index() -> Response {
+    serve_default_scene("")
+}
+
+pub async fn get_default_scene_file(AxumPath(path): AxumPath<String>) -> Response {
+    serve_default_scene(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_bundle_has_scoreboard_and_break_scenes() {
+        for file in ["index.html", "break.html", "scene.css", "scene.js"] {
+            assert!(DefaultScenes::get(file).is_some(), "missing {file}");
+        }
+    }
+
+    #[test]
+    fn scene_files_are_served_with_their_content_type() {
+        assert_eq!(scene_content_type("break.html"), "text/html; charset=utf-8");
+        assert_eq!(scene_content_type("scene.CSS"), "text/css; charset=utf-8");
+        assert_eq!(scene_content_type("scene.js"), "text/javascript; charset=utf-8");
+        assert_eq!(scene_content_type("notes"), "application/octet-stream");
+    }
+
+    #[test]
+    fn the_folder_serves_the_scoreboard_and_unknown_files_are_not_found() {
+        assert_eq!(serve_default_scene("/").status(), StatusCode::OK);
+        assert_eq!(serve_default_scene("missing.html").status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,307 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::idle_mode::is_idle;
+use crate::replay::{latest_replay_for_code, replay_winner_identity, update_replay_index};
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread::sleep,
+    time::{Duration, SystemTime},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emitted with a `SetupScore` whenever a setup's tracked score changes.
+pub const SCORE_UPDATED_EVENT: &str = "setup-score-updated";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(2);
+/// A replay that never got end data, e.g. after a disconnect, is given up
+/// on once it's this old and a newer game has started.
+const ABANDONED_AFTER: Duration = Duration::from_secs(120);
+
+static SCORES: Mutex<BTreeMap<u32, SetupScore>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerGameWins {
+    pub connect_code: Option<String>,
+    pub tag: Option<String>,
+    pub wins: u32,
+}
+
+/// Games won on a setup, counted from its finished replays.
+#[derive(Debug, Clone, Default, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupScore {
+    pub setup_id: u32,
+    /// The stream the games were counted for; the score starts over when
+    /// another stream is assigned
+    pub stream_id: Option<String>,
+    /// In the order they first won a game
+    pub players: Vec<PlayerGameWins>,
+    /// Replays counted, oldest first
+    pub games: Vec<String>,
+    pub updated_at_ms: u64,
+}
+
+impl SetupScore {
+    fn new(setup_id: u32, stream_id: Option<String>) -> Self {
+        Self { setup_id, stream_id, updated_at_ms: now_ms(), ..Self::default() }
+    }
+
+    /// Wins of the player with `code`.
+    pub fn wins_for(&self, code: &str) -> u32 {
+        let key = normalize_broadcast_key(code);
+        self.players
+            .iter()
+            .find(|player| player.connect_code.as_deref().is_some_and(|code| normalize_broadcast_key(code) == key))
+            .map(|player| player.wins)
+            .unwrap_or(0)
+    }
+
+    /// Count a finished game for its winner; false if it was already
+    /// counted or has no winner to credit.
+    fn add_game(&mut self, replay: String, code: Option<String>, tag: Option<String>) -> bool {
+        if self.games.contains(&replay) || (code.is_none() && tag.is_none()) {
+            return false;
+        }
+        let key = code.as_deref().map(normalize_broadcast_key);
+        let existing = self.players.iter_mut().find(|player| match (&key, &player.connect_code) {
+            (Some(key), Some(player_code)) => normalize_broadcast_key(player_code) == *key,
+            (None, None) => player.tag == tag,
+            _ => false,
+        });
+        match existing {
+            Some(player) => player.wins += 1,
+            None => self.players.push(PlayerGameWins { connect_code: code, tag, wins: 1 }),
+        }
+        self.games.push(replay);
+        self.updated_at_ms = now_ms();
+        true
+    }
+}
+
+pub fn setup_score(setup_id: u32) -> SetupScore {
+    SCORES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&setup_id)
+        .cloned()
+        .unwrap_or_else(|| SetupScore::new(setup_id, None))
+}
+
+/// Fill in scores from the tracker on setups whose set isn't in the
+/// bracket; bracket sets keep their reported scores.
+pub fn apply_tracked_scores(state: &mut AllSetupsState) {
+    let scores = SCORES.lock().unwrap_or_else(|e| e.into_inner());
+    for (setup_id, score) in scores.iter() {
+        let idx = (*setup_id as usize).checked_sub(1);
+        let Some(overlay) = idx.and_then(|idx| state.setups.get_mut(idx)) else {
+            continue;
+        };
+        if overlay.meta.set_id.is_some() {
+            continue;
+        }
+        for player in [&mut overlay.p1, &mut overlay.p2] {
+            if let Some(code) = player.connect_code.as_deref() {
+                player.score = score.wins_for(code);
+            }
+        }
+    }
+}
+
+/// Start the setup's score over when a different stream is assigned.
+/// Returns the fresh score when it was reset.
+fn sync_stream(setup_id: u32, stream_id: Option<&str>) -> Option<SetupScore> {
+    let mut scores = SCORES.lock().unwrap_or_else(|e| e.into_inner());
+    let current = scores.get(&setup_id).and_then(|score| score.stream_id.as_deref());
+    if current == stream_id {
+        return None;
+    }
+    let fresh = SetupScore::new(setup_id, stream_id.map(str::to_string));
+    scores.insert(setup_id, fresh.clone());
+    Some(fresh)
+}
+
+fn record_game(setup_id: u32, replay: &Path, code: Option<String>, tag: Option<String>) -> Option<SetupScore> {
+    let mut scores = SCORES.lock().unwrap_or_else(|e| e.into_inner());
+    let score = scores.entry(setup_id).or_insert_with(|| SetupScore::new(setup_id, None));
+    score
+        .add_game(replay.to_string_lossy().to_string(), code, tag)
+        .then(|| score.clone())
+}
+
+fn older_than(path: &Path, age: Duration) -> bool {
+    fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|since| since >= age)
+}
+
+/// Start watching each assigned setup's newest replay, and count the
+/// watched ones that have finished.
+fn track_games(
+    app: &AppHandle,
+    config: &AppConfig,
+    watching: &mut HashMap<u32, Vec<PathBuf>>,
+    seen: &mut HashSet<String>,
+    baseline: bool,
+) {
+    let store = app.state::<SharedSetupStore>().inner().clone();
+    let replay_cache = app.state::<SharedOverlayCache>().inner().clone();
+    let setups = store.lock().unwrap_or_else(|e| e.into_inner()).setups.clone();
+    let mut latest: HashMap<u32, PathBuf> = HashMap::new();
+    {
+        let mut cache = replay_cache.lock().unwrap_or_else(|e| e.into_inner());
+        let spectate = config.spectate_folder_path.trim();
+        if !spectate.is_empty() {
+            let _ = update_replay_index(&mut cache, &resolve_repo_path(spectate));
+        }
+        for setup in &setups {
+            let stream_id = setup.assigned_stream.as_ref().map(|stream| stream.id.as_str());
+            if let Some(fresh) = sync_stream(setup.id, stream_id) {
+                watching.remove(&setup.id);
+                let _ = app.emit(SCORE_UPDATED_EVENT, &fresh);
+            }
+            let Some(path) = setup
+                .assigned_stream
+                .as_ref()
+                .and_then(|stream| stream.p1_code.as_deref())
+                .and_then(|code| latest_replay_for_code(&cache, code))
+            else {
+                continue;
+            };
+            if seen.insert(path.to_string_lossy().to_string()) && !baseline {
+                watching.entry(setup.id).or_default().push(path.clone());
+            }
+            latest.insert(setup.id, path);
+        }
+    }
+
+    for (setup_id, replays) in watching.iter_mut() {
+        replays.retain(|replay| match replay_winner_identity(replay) {
+            Ok((code, tag)) => {
+                if let Some(score) = record_game(*setup_id, replay, code, tag) {
+                    tracing::info!("Setup {}: counted {} ({} game(s))", setup_id, replay.display(), score.games.len());
+                    let _ = app.emit(SCORE_UPDATED_EVENT, &score);
+                }
+                false
+            }
+            // Still being played, unless a newer game has long since started.
+            Err(_) => latest.get(setup_id) == Some(replay) || !older_than(replay, ABANDONED_AFTER),
+        });
+    }
+    watching.retain(|_, replays| !replays.is_empty());
+}
+
+/// Count each game played on an assigned setup once its replay has a
+/// winner, and emit `setup-score-updated`. Replays already in the folder
+/// at startup are taken as seen.
+pub fn spawn_score_tracker(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut watching: HashMap<u32, Vec<PathBuf>> = HashMap::new();
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut first_pass = true;
+        loop {
+            sleep(MONITOR_INTERVAL);
+            if is_idle() {
+                continue;
+            }
+            let Ok(config) = cached_config() else {
+                continue;
+            };
+            if config.test_flags().fake_replays {
+                continue;
+            }
+            track_games(&app, &config, &mut watching, &mut seen, std::mem::take(&mut first_pass));
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Games won on a setup since its stream was assigned or its score reset.
+#[tauri::command]
+#[specta::specta]
+pub fn get_setup_score(setup_id: u32) -> SetupScore {
+    setup_score(setup_id)
+}
+
+/// Start a setup's score over, e.g. between sets played on one stream.
+#[tauri::command]
+#[specta::specta]
+pub fn reset_setup_score(app: AppHandle, setup_id: u32) -> Result<SetupScore, String> {
+    audited("resetSetupScore", json!({ "setupId": setup_id }), || {
+        let mut scores = SCORES.lock().map_err(|e| e.to_string())?;
+        let stream_id = scores.get(&setup_id).and_then(|score| score.stream_id.clone());
+        let fresh = SetupScore::new(setup_id, stream_id);
+        scores.insert(setup_id, fresh.clone());
+        let _ = app.emit(SCORE_UPDATED_EVENT, &fresh);
+        Ok(fresh)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some(raw: &str) -> Option<String> {
+        Some(raw.to_string())
+    }
+
+    #[test]
+    fn wins_are_counted_by_connect_code_in_any_case() {
+        let mut score = SetupScore::new(1, None);
+        assert!(score.add_game("g1.slp".to_string(), some("MANG#0"), some("Mango")));
+        assert!(score.add_game("g2.slp".to_string(), some("ZAIN#0"), some("Zain")));
+        assert!(score.add_game("g3.slp".to_string(), some("mang#0"), some("Mango")));
+        assert_eq!(score.wins_for("Mang#0"), 2);
+        assert_eq!(score.wins_for("ZAIN#0"), 1);
+        assert_eq!(score.wins_for("HBOX#0"), 0);
+        assert_eq!(score.games, vec!["g1.slp", "g2.slp", "g3.slp"]);
+    }
+
+    #[test]
+    fn a_replay_is_counted_once() {
+        let mut score = SetupScore::new(1, None);
+        assert!(score.add_game("g1.slp".to_string(), some("MANG#0"), None));
+        assert!(!score.add_game("g1.slp".to_string(), some("MANG#0"), None));
+        assert_eq!(score.wins_for("MANG#0"), 1);
+    }
+
+    #[test]
+    fn games_without_a_winner_are_not_counted() {
+        let mut score = SetupScore::new(1, None);
+        assert!(!score.add_game("g1.slp".to_string(), None, None));
+        assert!(score.games.is_empty());
+    }
+
+    #[test]
+    fn offline_players_are_credited_by_tag() {
+        let mut score = SetupScore::new(1, None);
+        assert!(score.add_game("g1.slp".to_string(), None, some("Mango")));
+        assert!(score.add_game("g2.slp".to_string(), None, some("Mango")));
+        assert!(score.add_game("g3.slp".to_string(), None, some("Zain")));
+        let wins = score.players.iter().map(|player| (player.tag.as_deref(), player.wins)).collect::<Vec<_>>();
+        assert_eq!(wins, vec![(Some("Mango"), 2), (Some("Zain"), 1)]);
+    }
+
+    #[test]
+    fn a_new_stream_starts_the_score_over() {
+        // A setup id no other test touches; scores are shared.
+        let setup_id = 901;
+        assert!(sync_stream(setup_id, Some("codes:MANG#0")).is_some());
+        record_game(setup_id, Path::new("g1.slp"), some("MANG#0"), None);
+        assert!(sync_stream(setup_id, Some("codes:MANG#0")).is_none());
+        assert_eq!(setup_score(setup_id).wins_for("MANG#0"), 1);
+        let fresh = sync_stream(setup_id, Some("codes:ZAIN#0")).unwrap();
+        assert!(fresh.games.is_empty());
+        assert_eq!(setup_score(setup_id).stream_id.as_deref(), Some("codes:ZAIN#0"));
+    }
+}
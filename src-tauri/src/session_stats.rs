@@ -0,0 +1,247 @@
+use crate::config::*;
+use crate::idle_mode::is_idle;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg_sim::StartggSimSet;
+use crate::types::{SharedLiveStartgg, SharedSetupStore, SharedTestState};
+use serde::Serialize;
+use specta::Type;
+use std::{collections::HashMap, sync::Mutex, thread::sleep, time::Duration};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+const SUMMARY_WINDOW_MS: u64 = 60 * 60 * 1000;
+
+/// Setup usage at one moment: how many setups had a stream assigned.
+#[derive(Debug, Clone, Copy)]
+struct UtilizationSample {
+    at_ms: u64,
+    busy: u32,
+    total: u32,
+}
+
+static SAMPLES: Mutex<Vec<UtilizationSample>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub at_ms: u64,
+    /// Start of the window the `*_in_window` and utilization figures cover
+    pub window_start_ms: u64,
+    pub sets_completed: usize,
+    pub sets_completed_in_window: usize,
+    pub sets_remaining: usize,
+    /// Mean start-to-finish time of completed sets
+    pub avg_set_length_ms: Option<u64>,
+    /// Sets with both players known that haven't started
+    pub waiting_sets: usize,
+    /// How long the longest-waiting of those has been ready
+    pub longest_queue_wait_ms: Option<u64>,
+    pub setups_in_use: u32,
+    pub setup_count: u32,
+    /// Share of setup time in use over the window, 0.0-1.0
+    pub setup_utilization: Option<f32>,
+}
+
+/// When a set could have been called: once the sets feeding it finished.
+fn ready_at_ms(set: &StartggSimSet, completed_at: &HashMap<u64, u64>) -> u64 {
+    set.slots
+        .iter()
+        .filter_map(|slot| slot.source_set_id.and_then(|id| completed_at.get(&id).copied()))
+        .max()
+        .unwrap_or(set.updated_at_ms)
+}
+
+fn summarize(
+    sets: &[StartggSimSet],
+    samples: &[UtilizationSample],
+    setups: (u32, u32),
+    now_ms: u64,
+) -> SessionSummary {
+    let window_start_ms = now_ms.saturating_sub(SUMMARY_WINDOW_MS);
+    let completed_at = sets
+        .iter()
+        .filter_map(|set| Some((set.id, set.completed_at_ms?)))
+        .collect::<HashMap<_, _>>();
+    let completed = sets.iter().filter(|set| set.state == "completed").collect::<Vec<_>>();
+    let lengths = completed
+        .iter()
+        .filter_map(|set| Some(set.completed_at_ms?.checked_sub(set.started_at_ms?)?))
+        .collect::<Vec<_>>();
+    let waiting = sets
+        .iter()
+        .filter(|set| set.state == "pending" && set.slots.iter().all(|slot| slot.entrant_id.is_some()))
+        .collect::<Vec<_>>();
+    let window_samples = samples.iter().filter(|sample| sample.at_ms >= window_start_ms);
+    let (busy, total) = window_samples.fold((0u64, 0u64), |(busy, total), sample| {
+        (busy + u64::from(sample.busy), total + u64::from(sample.total))
+    });
+    SessionSummary {
+        at_ms: now_ms,
+        window_start_ms,
+        sets_completed: completed.len(),
+        sets_completed_in_window: completed
+            .iter()
+            .filter(|set| set.completed_at_ms.is_some_and(|at| at >= window_start_ms))
+            .count(),
+        sets_remaining: sets
+            .iter()
+            .filter(|set| set.state == "pending" || set.state == "inProgress")
+            .count(),
+        avg_set_length_ms: (!lengths.is_empty()).then(|| lengths.iter().sum::<u64>() / lengths.len() as u64),
+        waiting_sets: waiting.len(),
+        longest_queue_wait_ms: waiting
+            .iter()
+            .map(|set| now_ms.saturating_sub(ready_at_ms(set, &completed_at)))
+            .max(),
+        setups_in_use: setups.0,
+        setup_count: setups.1,
+        setup_utilization: (total > 0).then(|| busy as f32 / total as f32),
+    }
+}
+
+/// Setups with a stream assigned, and setups in total.
+fn setup_usage(store: &SharedSetupStore) -> (u32, u32) {
+    let guard = store.lock().unwrap_or_else(|e| e.into_inner());
+    let busy = guard.setups.iter().filter(|setup| setup.assigned_stream.is_some()).count();
+    (busy as u32, guard.setups.len() as u32)
+}
+
+fn record_sample(store: &SharedSetupStore, now_ms: u64) {
+    let (busy, total) = setup_usage(store);
+    let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    samples.retain(|sample| now_ms.saturating_sub(sample.at_ms) <= SUMMARY_WINDOW_MS);
+    samples.push(UtilizationSample { at_ms: now_ms, busy, total });
+}
+
+pub fn current_summary(
+    store: &SharedSetupStore,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> SessionSummary {
+    let bracket = current_bracket_state(test_state, live_startgg);
+    let samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let sets = bracket.as_ref().map(|state| state.sets.as_slice()).unwrap_or_default();
+    summarize(sets, &samples, setup_usage(store), now_ms())
+}
+
+/// Sample setup usage every minute and emit `session-summary` every hour.
+pub fn spawn_session_stats_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut last_summary_ms = now_ms();
+        loop {
+            sleep(SAMPLE_INTERVAL);
+            let store = app.state::<SharedSetupStore>().inner().clone();
+            let now = now_ms();
+            if !is_idle() {
+                record_sample(&store, now);
+            }
+            if now.saturating_sub(last_summary_ms) < SUMMARY_WINDOW_MS {
+                continue;
+            }
+            last_summary_ms = now;
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let summary = current_summary(&store, &test_state, &live_startgg);
+            tracing::info!(
+                "Session summary: {} sets done ({} this hour), {} waiting, utilization {:?}",
+                summary.sets_completed,
+                summary.sets_completed_in_window,
+                summary.waiting_sets,
+                summary.setup_utilization
+            );
+            let _ = app.emit("session-summary", &summary);
+        }
+    });
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// The summary as of now, over the last hour.
+#[tauri::command]
+#[specta::specta]
+pub fn session_summary(
+    store: State<'_, SharedSetupStore>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> SessionSummary {
+    current_summary(&store, &test_state, &live_startgg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::StartggSimSlot;
+
+    fn set(
+        id: u64,
+        state: &str,
+        started: Option<u64>,
+        completed: Option<u64>,
+        sources: [Option<u64>; 2],
+    ) -> StartggSimSet {
+        let slot = |source_set_id| StartggSimSlot { entrant_id: Some(1), source_set_id, ..Default::default() };
+        StartggSimSet {
+            id,
+            phase_id: "p".to_string(),
+            phase_name: "Pools".to_string(),
+            round: 1,
+            round_label: "Round 1".to_string(),
+            best_of: 3,
+            state: state.to_string(),
+            started_at_ms: started,
+            completed_at_ms: completed,
+            slots: vec![slot(sources[0]), slot(sources[1])],
+            ..Default::default()
+        }
+    }
+
+    const MIN: u64 = 60_000;
+    const NOW: u64 = 120 * MIN;
+
+    fn session() -> Vec<StartggSimSet> {
+        vec![
+            set(1, "completed", Some(0), Some(20 * MIN), [None, None]),
+            set(2, "completed", Some(50 * MIN), Some(80 * MIN), [None, None]),
+            set(3, "pending", None, None, [Some(1), Some(2)]),
+            set(4, "inProgress", Some(100 * MIN), None, [None, None]),
+        ]
+    }
+
+    #[test]
+    fn completed_sets_are_counted_overall_and_in_the_last_hour() {
+        let summary = summarize(&session(), &[], (1, 2), NOW);
+        assert_eq!(summary.window_start_ms, NOW - SUMMARY_WINDOW_MS);
+        assert_eq!(summary.sets_completed, 2);
+        assert_eq!(summary.sets_completed_in_window, 1);
+        assert_eq!(summary.sets_remaining, 2);
+        assert_eq!(summary.avg_set_length_ms, Some(25 * MIN));
+    }
+
+    #[test]
+    fn a_waiting_set_has_waited_since_its_last_feeder_finished() {
+        let summary = summarize(&session(), &[], (1, 2), NOW);
+        assert_eq!(summary.waiting_sets, 1);
+        assert_eq!(summary.longest_queue_wait_ms, Some(40 * MIN));
+    }
+
+    #[test]
+    fn utilization_only_counts_samples_in_the_window() {
+        let samples = [
+            UtilizationSample { at_ms: 30 * MIN, busy: 0, total: 2 },
+            UtilizationSample { at_ms: 90 * MIN, busy: 2, total: 2 },
+            UtilizationSample { at_ms: 100 * MIN, busy: 1, total: 2 },
+        ];
+        let summary = summarize(&session(), &samples, (1, 2), NOW);
+        assert_eq!(summary.setup_utilization, Some(0.75));
+        assert_eq!((summary.setups_in_use, summary.setup_count), (1, 2));
+    }
+
+    #[test]
+    fn an_empty_session_has_no_averages() {
+        let summary = summarize(&[], &[], (0, 0), NOW);
+        assert_eq!(summary.sets_completed, 0);
+        assert_eq!(summary.avg_set_length_ms, None);
+        assert_eq!(summary.longest_queue_wait_ms, None);
+        assert_eq!(summary.setup_utilization, None);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::audit_log::audited;
+use crate::auto_stop::leave_break;
+use crate::config::*;
+use crate::idle_mode::exit_idle;
+use crate::mirror::end_mirror;
+use crate::replay::slot_label;
+use crate::run_of_show::current_bracket_state;
+use crate::slippi::assign_stream;
+use crate::startgg_sim::{StartggSimSet, StartggSimState};
+use crate::stream_identity::stable_stream_id;
+use crate::test_mode::test_mode_bracket_streams;
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::sync::Mutex;
+use tauri::State;
+
+const FIRST_CODE: u8 = 10;
+const LAST_CODE: u8 = 99;
+/// A code isn't handed to another set for this long after its set is done,
+/// so a code called out on comms a moment ago can't land on a new set.
+const RETIRED_HOLD_MS: u64 = 10 * 60 * 1000;
+
+static TABLE: Mutex<CodeTable> = Mutex::new(CodeTable {
+    codes: Vec::new(),
+    retired: Vec::new(),
+    next: FIRST_CODE,
+});
+
+/// A short code for a set that is ready or running, for assigning it to a
+/// setup by number ("set 47 to setup 2").
+#[derive(Debug, Clone, PartialEq, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCode {
+    pub code: u8,
+    pub set_id: u64,
+    pub round_label: String,
+    pub p1: Option<String>,
+    pub p2: Option<String>,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct QuickAssignResult {
+    /// The set the code resolved to, so the operator can confirm it back.
+    pub set: SetCode,
+    pub setups: Vec<Setup>,
+    pub warning: Option<String>,
+}
+
+/// Two-digit codes handed out in rotation. A set keeps its code until it
+/// is finished or leaves the bracket.
+struct CodeTable {
+    /// (code, set id)
+    codes: Vec<(u8, u64)>,
+    /// (code, retired at)
+    retired: Vec<(u8, u64)>,
+    next: u8,
+}
+
+/// Pending with both players known, i.e. ready to be called.
+fn is_ready(set: &StartggSimSet) -> bool {
+    set.state == "pending" && !set.slots.is_empty() && set.slots.iter().all(|slot| slot.entrant_id.is_some())
+}
+
+impl CodeTable {
+    fn is_free(&self, code: u8, now: u64) -> bool {
+        !self.codes.iter().any(|(c, _)| *c == code)
+            && !self
+                .retired
+                .iter()
+                .any(|(c, at)| *c == code && now.saturating_sub(*at) < RETIRED_HOLD_MS)
+    }
+
+    /// The next free code after the last one handed out, or if every code
+    /// is taken, the one retired longest ago.
+    fn take_code(&mut self, now: u64) -> Option<u8> {
+        let span = (LAST_CODE - FIRST_CODE + 1) as u16;
+        let start = (self.next.clamp(FIRST_CODE, LAST_CODE) - FIRST_CODE) as u16;
+        let code = (0..span)
+            .map(|offset| FIRST_CODE + ((start + offset) % span) as u8)
+            .find(|code| self.is_free(*code, now))
+            .or_else(|| {
+                self.retired
+                    .iter()
+                    .filter(|(code, _)| !self.codes.iter().any(|(c, _)| c == code))
+                    .min_by_key(|(_, at)| *at)
+                    .map(|(code, _)| *code)
+            })?;
+        self.retired.retain(|(c, _)| *c != code);
+        self.next = if code == LAST_CODE { FIRST_CODE } else { code + 1 };
+        Some(code)
+    }
+
+    /// Bring the table in line with `sets`: retire codes of sets that are
+    /// done or gone, and give new ready sets a code.
+    fn refresh(&mut self, sets: &[StartggSimSet], now: u64) {
+        let keep = |set_id: u64| {
+            sets.iter()
+                .any(|set| set.id == set_id && (is_ready(set) || set.state == "inProgress"))
+        };
+        let (kept, done): (Vec<_>, Vec<_>) = std::mem::take(&mut self.codes)
+            .into_iter()
+            .partition(|(_, set_id)| keep(*set_id));
+        self.codes = kept;
+        self.retired.extend(done.into_iter().map(|(code, _)| (code, now)));
+        self.retired.retain(|(_, at)| now.saturating_sub(*at) < RETIRED_HOLD_MS);
+
+        let mut ready = sets.iter().filter(|set| is_ready(set)).collect::<Vec<_>>();
+        ready.sort_by_key(|set| (set.round.abs(), set.id));
+        for set in ready {
+            if self.codes.iter().any(|(_, set_id)| *set_id == set.id) {
+                continue;
+            }
+            let Some(code) = self.take_code(now) else {
+                break;
+            };
+            self.codes.push((code, set.id));
+        }
+    }
+
+    fn list(&self, sets: &[StartggSimSet]) -> Vec<SetCode> {
+        let mut out = self
+            .codes
+            .iter()
+            .filter_map(|(code, set_id)| {
+                let set = sets.iter().find(|set| set.id == *set_id)?;
+                Some(SetCode {
+                    code: *code,
+                    set_id: set.id,
+                    round_label: set.round_label.clone(),
+                    p1: slot_label(set.slots.first()).0,
+                    p2: slot_label(set.slots.get(1)).0,
+                    state: set.state.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+        out.sort_by_key(|code| code.code);
+        out
+    }
+}
+
+fn current_codes(
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<(Vec<SetCode>, StartggSimState), String> {
+    let bracket =
+        current_bracket_state(test_state, live_startgg).ok_or_else(|| "No bracket is loaded.".to_string())?;
+    let mut table = TABLE.lock().map_err(|e| e.to_string())?;
+    table.refresh(&bracket.sets, now_ms());
+    Ok((table.list(&bracket.sets), bracket))
+}
+
+/// The stream to put on a setup for `set`: the test bracket's stream for
+/// it, or otherwise the spectate card of whichever player has a code.
+fn stream_for_set(set: &StartggSimSet, test_state: &SharedTestState) -> Result<SlippiStream, String> {
+    if test_flags().fake_streams {
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        let streams = test_mode_bracket_streams(&mut guard)?;
+        let found = streams
+            .into_iter()
+            .find(|stream| stream.startgg_set.as_ref().map(|s| s.id) == Some(set.id));
+        if let Some(stream) = found {
+            return Ok(stream);
+        }
+    }
+    let p1_idx = set
+        .slots
+        .iter()
+        .position(|slot| slot.slippi_code.as_deref().and_then(normalize_slippi_code).is_some())
+        .ok_or_else(|| format!("Neither player in set {} has a connect code; assign their stream by hand.", set.id))?;
+    let p1 = &set.slots[p1_idx];
+    let (p1_tag, p1_code) = slot_label(Some(p1));
+    let opponent = set.slots.iter().enumerate().find(|(idx, _)| *idx != p1_idx).map(|(_, slot)| slot);
+    let (p2_tag, p2_code) = slot_label(opponent);
+    let p1_code = p1_code.as_deref().and_then(normalize_slippi_code);
+    let id = stable_stream_id(&[p1_code.as_deref().unwrap_or_default()])
+        .ok_or_else(|| format!("No stream id for set {}.", set.id))?;
+    Ok(SlippiStream {
+        id,
+        p1_tag,
+        p2_tag,
+        p1_code,
+        p2_code,
+        startgg_entrant_id: p1.entrant_id,
+        is_playing: Some(set.state == "inProgress"),
+        startgg_set: Some(set.clone()),
+        ..Default::default()
+    })
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Codes for every ready or running set, lowest code first.
+#[tauri::command]
+#[specta::specta]
+pub fn set_codes(
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<Vec<SetCode>, String> {
+    current_codes(&test_state, &live_startgg).map(|(codes, _)| codes)
+}
+
+/// Put the set with `code` on `setup_id`. Fails rather than guessing when
+/// the code isn't current or its set has finished.
+#[tauri::command]
+#[specta::specta]
+pub fn quick_assign(
+    code: u8,
+    setup_id: u32,
+    launch: Option<bool>,
+    store: State<'_, SharedSetupStore>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<QuickAssignResult, String> {
+    let params = json!({ "code": code, "setupId": setup_id, "launch": launch });
+    audited("quickAssign", params, || {
+        let (codes, bracket) = current_codes(&test_state, &live_startgg)?;
+        let set_code = codes
+            .into_iter()
+            .find(|entry| entry.code == code)
+            .ok_or_else(|| format!("No ready set has code {code}. Check the bracket view for current codes."))?;
+        let set = bracket
+            .sets
+            .iter()
+            .find(|set| set.id == set_code.set_id)
+            .ok_or_else(|| format!("Set {} is no longer in the bracket.", set_code.set_id))?;
+        let stream = stream_for_set(set, &test_state)?;
+        let result = assign_stream(setup_id, stream, launch, &store, &test_state)?;
+        exit_idle(&format!("set code {code} assigned to setup {setup_id}"));
+        leave_break(setup_id);
+        end_mirror(setup_id);
+        Ok(QuickAssignResult {
+            set: set_code,
+            setups: result.setups,
+            warning: result.warning,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::StartggSimSlot;
+
+    fn set(id: u64, state: &str) -> StartggSimSet {
+        let slot = |entrant_id: u32| StartggSimSlot {
+            entrant_id: Some(entrant_id),
+            entrant_name: Some(format!("Player {entrant_id}")),
+            ..Default::default()
+        };
+        StartggSimSet {
+            id,
+            phase_id: "p1".to_string(),
+            phase_name: "Bracket".to_string(),
+            round: 1,
+            round_label: "Winners Round 1".to_string(),
+            best_of: 3,
+            state: state.to_string(),
+            slots: vec![slot(id as u32 * 2), slot(id as u32 * 2 + 1)],
+            ..Default::default()
+        }
+    }
+
+    fn table(retired: Vec<(u8, u64)>, next: u8) -> CodeTable {
+        CodeTable { codes: Vec::new(), retired, next }
+    }
+
+    #[test]
+    fn ready_sets_get_codes_in_turn() {
+        let mut table = table(Vec::new(), FIRST_CODE);
+        table.refresh(&[set(1, "pending"), set(2, "pending"), set(3, "completed")], 0);
+        assert_eq!(table.codes, vec![(10, 1), (11, 2)]);
+    }
+
+    #[test]
+    fn a_set_keeps_its_code_once_it_starts() {
+        let mut table = table(Vec::new(), FIRST_CODE);
+        let mut sets = vec![set(1, "pending")];
+        table.refresh(&sets, 0);
+        sets[0].state = "inProgress".to_string();
+        table.refresh(&sets, 1000);
+        assert_eq!(table.codes, vec![(10, 1)]);
+    }
+
+    #[test]
+    fn a_finished_set_gives_up_its_code_for_a_while() {
+        let mut table = table(Vec::new(), FIRST_CODE);
+        let mut sets = vec![set(1, "pending"), set(2, "pending")];
+        table.refresh(&sets, 0);
+        sets[1].state = "completed".to_string();
+        sets.push(set(4, "pending"));
+        table.refresh(&sets, 1000);
+        assert_eq!(table.codes, vec![(10, 1), (12, 4)]);
+        assert_eq!(table.list(&sets).iter().map(|c| c.set_id).collect::<Vec<_>>(), vec![1, 4]);
+    }
+
+    #[test]
+    fn codes_wrap_around_past_retired_ones() {
+        let mut table = table(vec![(10, 0)], LAST_CODE);
+        table.refresh(&[set(5, "pending"), set(6, "pending")], 1000);
+        assert_eq!(table.codes, vec![(99, 5), (11, 6)]);
+    }
+
+    #[test]
+    fn a_retired_code_is_handed_out_again_after_the_hold() {
+        let mut table = table(vec![(11, 0)], 11);
+        table.refresh(&[set(7, "pending")], RETIRED_HOLD_MS);
+        assert_eq!(table.codes, vec![(11, 7)]);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::types::*;
+use serde::Deserialize;
+use serde_json::json;
+use specta::Type;
+use std::{fs, path::PathBuf};
+use tauri::State;
+
+const MAX_DISPLAY_NAME_CHARS: usize = 40;
+const MAX_NOTE_CHARS: usize = 200;
+
+/// Editable labels on a setup. Unset fields are cleared.
+#[derive(Debug, Clone, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SetupMetadata {
+    pub display_name: Option<String>,
+    pub note: Option<String>,
+    pub station_number: Option<u32>,
+}
+
+pub fn setups_path() -> PathBuf {
+    repo_root().join("setups.json")
+}
+
+/// Setups saved by a previous run, without their stream assignments.
+/// None when nothing was saved or the file can't be read.
+pub fn load_saved_setups() -> Option<Vec<Setup>> {
+    let path = setups_path();
+    let data = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str::<Vec<Setup>>(&data) {
+        Ok(setups) if !setups.is_empty() => Some(setups),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Ignoring saved setups {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Save the setup list. Stream assignments are left out; they only mean
+/// something while the streams they point at are live.
+pub fn save_setups(setups: &[Setup]) -> Result<(), String> {
+    let saved = setups
+        .iter()
+        .cloned()
+        .map(|setup| Setup { assigned_stream: None, ..setup })
+        .collect::<Vec<_>>();
+    let path = setups_path();
+    let payload = serde_json::to_string_pretty(&saved).map_err(|e| e.to_string())?;
+    write_file_atomic(&path, payload.as_bytes()).map_err(|e| format!("write setups {}: {e}", path.display()))
+}
+
+/// Save after a change a command has already made; failing to save
+/// shouldn't undo it.
+pub fn persist_setups(setups: &[Setup]) {
+    if let Err(err) = save_setups(setups) {
+        tracing::warn!("Failed to save setups: {}", err);
+    }
+}
+
+/// Change one setup in place and save the list; returns the changed setup.
+pub fn update_setup(
+    setup_id: u32,
+    store: &SharedSetupStore,
+    update: impl FnOnce(&mut Setup),
+) -> Result<Setup, String> {
+    let mut guard = store.lock().map_err(|e| e.to_string())?;
+    let setup = guard
+        .setups
+        .iter_mut()
+        .find(|s| s.id == setup_id)
+        .ok_or_else(|| format!("Setup {setup_id} not found."))?;
+    update(setup);
+    let setup = setup.clone();
+    persist_setups(&guard.setups);
+    Ok(setup)
+}
+
+fn trimmed(value: Option<String>, max_chars: usize, field: &str) -> Result<Option<String>, String> {
+    let Some(value) = value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > max_chars {
+        return Err(format!("{field} is over {max_chars} characters."));
+    }
+    Ok(Some(value))
+}
+
+/// Validate `metadata` and apply it to the setup. Station numbers are unique
+/// so "station 4" always means one setup.
+fn apply_metadata(setups: &mut [Setup], setup_id: u32, metadata: SetupMetadata) -> Result<Setup, String> {
+    let display_name = trimmed(metadata.display_name, MAX_DISPLAY_NAME_CHARS, "Setup name")?;
+    let note = trimmed(metadata.note, MAX_NOTE_CHARS, "Note")?;
+    if let Some(station) = metadata.station_number {
+        if station == 0 {
+            return Err("Station numbers start at 1.".to_string());
+        }
+        if let Some(other) = setups.iter().find(|s| s.id != setup_id && s.station_number == Some(station)) {
+            return Err(format!("Station {station} is already {}.", setup_label(other)));
+        }
+    }
+    let setup = setups
+        .iter_mut()
+        .find(|s| s.id == setup_id)
+        .ok_or_else(|| format!("Setup {setup_id} not found."))?;
+    setup.display_name = display_name;
+    setup.note = note;
+    setup.station_number = metadata.station_number;
+    Ok(setup.clone())
+}
+
+/// What the operator calls a setup: its display name, else its name.
+pub fn setup_label(setup: &Setup) -> &str {
+    setup.display_name.as_deref().unwrap_or(&setup.name)
+}
+
+fn update_metadata(
+    store: &SharedSetupStore,
+    setup_id: u32,
+    update: impl FnOnce(&Setup) -> SetupMetadata,
+) -> Result<Setup, String> {
+    let mut guard = store.lock().map_err(|e| e.to_string())?;
+    let current = guard
+        .setups
+        .iter()
+        .find(|s| s.id == setup_id)
+        .ok_or_else(|| format!("Setup {setup_id} not found."))?;
+    let metadata = update(current);
+    let setup = apply_metadata(&mut guard.setups, setup_id, metadata)?;
+    save_setups(&guard.setups)?;
+    Ok(setup)
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Give a setup a display name; an empty name goes back to "Setup N".
+#[tauri::command]
+#[specta::specta]
+pub fn rename_setup(setup_id: u32, name: String, store: State<'_, SharedSetupStore>) -> Result<Setup, String> {
+    let params = json!({ "setupId": setup_id, "name": name });
+    audited("renameSetup", params, || rename_setup_inner(setup_id, name, &store))
+}
+
+pub fn rename_setup_inner(setup_id: u32, name: String, store: &SharedSetupStore) -> Result<Setup, String> {
+    update_metadata(store, setup_id, |setup| SetupMetadata {
+        display_name: Some(name),
+        note: setup.note.clone(),
+        station_number: setup.station_number,
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn update_setup_metadata(
+    setup_id: u32,
+    metadata: SetupMetadata,
+    store: State<'_, SharedSetupStore>,
+) -> Result<Setup, String> {
+    let params = json!({
+        "setupId": setup_id,
+        "displayName": metadata.display_name,
+        "note": metadata.note,
+        "stationNumber": metadata.station_number,
+    });
+    audited("updateSetupMetadata", params, || update_setup_metadata_inner(setup_id, metadata, &store))
+}
+
+pub fn update_setup_metadata_inner(
+    setup_id: u32,
+    metadata: SetupMetadata,
+    store: &SharedSetupStore,
+) -> Result<Setup, String> {
+    update_metadata(store, setup_id, |_| metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setups() -> Vec<Setup> {
+        SetupStore::bootstrap_from_existing().setups
+    }
+
+    fn station(number: u32) -> SetupMetadata {
+        SetupMetadata { station_number: Some(number), ..Default::default() }
+    }
+
+    #[test]
+    fn names_and_notes_are_trimmed() {
+        let metadata = SetupMetadata {
+            display_name: Some("  Stream Setup ".to_string()),
+            note: Some(" Elgato 2, main desk ".to_string()),
+            station_number: Some(4),
+        };
+        let setup = apply_metadata(&mut setups(), 1, metadata).unwrap();
+        assert_eq!(setup.display_name.as_deref(), Some("Stream Setup"));
+        assert_eq!(setup.note.as_deref(), Some("Elgato 2, main desk"));
+        assert_eq!(setup.station_number, Some(4));
+    }
+
+    #[test]
+    fn setups_are_labelled_by_display_name_else_name() {
+        let mut setups = setups();
+        assert_eq!(setup_label(&setups[0]), "Setup 1");
+        let named = SetupMetadata { display_name: Some("Stream Setup".to_string()), ..Default::default() };
+        assert_eq!(setup_label(&apply_metadata(&mut setups, 1, named).unwrap()), "Stream Setup");
+    }
+
+    #[test]
+    fn blank_fields_clear() {
+        let mut setups = setups();
+        let named = SetupMetadata { display_name: Some("Stream Setup".to_string()), ..station(4) };
+        apply_metadata(&mut setups, 1, named).unwrap();
+        let blank = SetupMetadata { display_name: Some(" ".to_string()), ..Default::default() };
+        let cleared = apply_metadata(&mut setups, 1, blank).unwrap();
+        assert_eq!(cleared.display_name, None);
+        assert_eq!(cleared.station_number, None);
+    }
+
+    #[test]
+    fn a_station_number_belongs_to_one_setup() {
+        let mut setups = setups();
+        let named = SetupMetadata { display_name: Some("Stream Setup".to_string()), ..station(4) };
+        apply_metadata(&mut setups, 1, named).unwrap();
+        assert_eq!(apply_metadata(&mut setups, 2, station(4)).unwrap_err(), "Station 4 is already Stream Setup.");
+        // Keeping its own number is fine.
+        assert!(apply_metadata(&mut setups, 1, station(4)).is_ok());
+    }
+
+    #[test]
+    fn invalid_metadata_is_rejected() {
+        let mut setups = setups();
+        assert!(apply_metadata(&mut setups, 1, station(0)).is_err());
+        let long = SetupMetadata { note: Some("x".repeat(MAX_NOTE_CHARS + 1)), ..Default::default() };
+        assert!(apply_metadata(&mut setups, 1, long).is_err());
+        assert!(apply_metadata(&mut setups, 9, SetupMetadata::default()).is_err());
+    }
+}
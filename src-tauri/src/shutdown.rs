@@ -0,0 +1,370 @@
+use crate::config::*;
+use crate::dolphin::{stop_child_process, stop_dolphin_child, stop_process_by_pid};
+use crate::exhibition::all_exhibitions;
+use crate::ratings::rated_sets_from_state;
+use crate::replay_upload::replay_manifests;
+use crate::startgg::set_startgg_polling_suspended;
+use crate::startgg_sim::StartggSimState;
+use crate::startup::StartupProgress;
+use crate::types::*;
+use crate::venue_time::{timezone_info, venue_now, venue_rfc3339};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    process::Child,
+    sync::atomic::{AtomicBool, Ordering},
+    thread::sleep,
+    time::{Duration, SystemTime},
+};
+use tauri::{AppHandle, Emitter, Manager};
+
+/// How long the spectate folder must stay unchanged before shutdown calls it
+/// idle.
+const SPECTATE_QUIET_WINDOW: Duration = Duration::from_secs(3);
+
+static SHUTDOWN_RUNNING: AtomicBool = AtomicBool::new(false);
+
+// ── Auto-report ─────────────────────────────────────────────────────────
+
+/// Cancel replay-driven reporting and stop start.gg polling.
+fn stop_auto_report(progress: &mut StartupProgress, test_state: &SharedTestState) {
+    set_startgg_polling_suspended(true);
+    let children: Vec<Child> = {
+        let mut guard = test_state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut targets = guard.active_replay_sets.iter().copied().collect::<Vec<_>>();
+        targets.extend(guard.active_replay_children.keys().copied());
+        targets.sort_unstable();
+        targets.dedup();
+        let mut children = Vec::new();
+        for id in targets {
+            guard.cancel_replay_sets.insert(id);
+            guard.active_replay_sets.remove(&id);
+            guard.active_replay_paths.remove(&id);
+            if let Some(child) = guard.active_replay_children.remove(&id) {
+                children.push(child);
+            }
+        }
+        children
+    };
+    let cancelled = children.len();
+    for child in children {
+        let _ = stop_child_process(child);
+    }
+    progress.emit(
+        "auto-report",
+        "ok",
+        Some(format!("Start.gg polling paused; {cancelled} replay run(s) cancelled.")),
+    );
+}
+
+// ── Flush ───────────────────────────────────────────────────────────────
+
+/// Persist the bracket and fail the step if any set still has a reported
+/// result the bracket doesn't reflect.
+fn flush_pending(progress: &mut StartupProgress, test_state: &SharedTestState) -> Option<StartggSimState> {
+    let now = now_ms();
+    let mut guard = test_state.lock().unwrap_or_else(|e| e.into_inner());
+    let config_path = guard
+        .startgg_config_path
+        .clone()
+        .unwrap_or_else(startgg_sim_config_path);
+    let state = guard.startgg_sim.as_mut().map(|sim| {
+        if let Err(e) = sim.save_state(&config_path) {
+            tracing::warn!("Failed to save bracket state: {}", e);
+        }
+        sim.state(now)
+    });
+    let pending = guard.report_locks.pending(state.as_ref());
+    drop(guard);
+
+    if pending.is_empty() {
+        progress.emit("flush", "ok", Some("No pending reports.".to_string()));
+    } else {
+        let ids = pending
+            .iter()
+            .map(|report| report.lock.set_id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress.emit(
+            "flush",
+            "failed",
+            Some(format!("{} set(s) have unconfirmed reports: {}", pending.len(), ids)),
+        );
+    }
+    state
+}
+
+// ── Event report ────────────────────────────────────────────────────────
+
+pub(crate) fn write_event_report(
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+    history: &SharedOverlayHistory,
+    sim_state: Option<StartggSimState>,
+) -> Result<PathBuf, String> {
+    let bracket = match sim_state {
+        Some(state) => Some(state),
+        None => live_startgg
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .state
+            .clone(),
+    };
+    let (audit, pending) = {
+        let guard = test_state.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            guard.report_locks.history(None),
+            guard.report_locks.pending(bracket.as_ref()),
+        )
+    };
+    let results = history.lock().unwrap_or_else(|e| e.into_inner()).all();
+
+    let completed_sets = bracket.as_ref().map(rated_sets_from_state).unwrap_or_default();
+    let (event, sets_total, sets_completed, unfinished) = match bracket.as_ref() {
+        Some(state) => {
+            let unfinished = state
+                .sets
+                .iter()
+                .filter(|set| set.state != "completed")
+                .map(|set| json!({ "id": set.id, "roundLabel": set.round_label, "state": set.state }))
+                .collect::<Vec<_>>();
+            (
+                Some(&state.event),
+                state.sets.len(),
+                state.sets.iter().filter(|set| set.state == "completed").count(),
+                unfinished,
+            )
+        }
+        None => (None, 0, 0, Vec::new()),
+    };
+
+    let generated_at_ms = now_ms();
+    let report = json!({
+        "generatedAtMs": generated_at_ms,
+        "generatedAtLocal": venue_rfc3339(generated_at_ms),
+        "timezone": timezone_info(generated_at_ms),
+        "event": event,
+        "setsTotal": sets_total,
+        "setsCompleted": sets_completed,
+        "unfinishedSets": unfinished,
+        "completedSets": completed_sets,
+        "exhibitions": all_exhibitions(),
+        "pendingReports": pending,
+        "setResults": results,
+        "replayUploads": replay_manifests(),
+        "reportAudit": audit,
+    });
+
+    let dir = repo_root().join("logs");
+    fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+    let path = dir.join(format!("event_report_{}.json", venue_now().format("%Y%m%d_%H%M%S")));
+    let body = serde_json::to_string_pretty(&report).map_err(|e| format!("serialize event report: {e}"))?;
+    fs::write(&path, body).map_err(|e| format!("write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+// ── Children ────────────────────────────────────────────────────────────
+
+fn stop_children(progress: &mut StartupProgress, store: &SharedSetupStore) {
+    let (children, pids) = {
+        let mut guard = store.lock().unwrap_or_else(|e| e.into_inner());
+        (
+            guard.processes.drain().collect::<Vec<_>>(),
+            guard.process_pids.drain().collect::<Vec<_>>(),
+        )
+    };
+    let mut errors = Vec::new();
+    let stopped = children.len() + pids.len();
+    for (id, child) in children {
+        if let Err(err) = stop_dolphin_child(child) {
+            errors.push(format!("Setup {id}: {err}"));
+        }
+    }
+    for (id, pid) in pids {
+        if let Err(err) = stop_process_by_pid(pid) {
+            errors.push(format!("Setup {id}: {err}"));
+        }
+    }
+    if errors.is_empty() {
+        progress.emit("children", "ok", Some(format!("Stopped {stopped} Dolphin process(es).")));
+    } else {
+        progress.emit("children", "failed", Some(errors.join(" ")));
+    }
+}
+
+// ── Spectate folder ─────────────────────────────────────────────────────
+
+fn collect_replay_files(dir: &Path, out: &mut HashMap<PathBuf, (u64, Option<SystemTime>)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if meta.is_dir() {
+            collect_replay_files(&path, out);
+        } else if path.extension().and_then(|ext| ext.to_str()) == Some("slp") {
+            out.insert(path, (meta.len(), meta.modified().ok()));
+        }
+    }
+}
+
+/// Files that were created, grew or were touched between two scans of `dir`.
+fn spectate_folder_writes(dir: &Path, window: Duration) -> Vec<PathBuf> {
+    let mut before = HashMap::new();
+    collect_replay_files(dir, &mut before);
+    sleep(window);
+    let mut after = HashMap::new();
+    collect_replay_files(dir, &mut after);
+    let mut changed = after
+        .into_iter()
+        .filter(|(path, meta)| before.get(path) != Some(meta))
+        .map(|(path, _)| path)
+        .collect::<Vec<_>>();
+    changed.sort();
+    changed
+}
+
+fn verify_spectate_idle(progress: &mut StartupProgress, config: &AppConfig) -> bool {
+    let raw = config.spectate_folder_path.trim();
+    if raw.is_empty() {
+        progress.emit("spectate-folder", "skipped", Some("No spectate folder configured.".to_string()));
+        return true;
+    }
+    let dir = resolve_repo_path(raw);
+    if !dir.is_dir() {
+        progress.emit(
+            "spectate-folder",
+            "skipped",
+            Some(format!("Spectate folder {} not found.", dir.display())),
+        );
+        return true;
+    }
+    progress.emit(
+        "spectate-folder",
+        "running",
+        Some(format!("Watching {} for writes.", dir.display())),
+    );
+    let changed = spectate_folder_writes(&dir, SPECTATE_QUIET_WINDOW);
+    if changed.is_empty() {
+        progress.emit("spectate-folder", "ok", Some("No replay files are being written.".to_string()));
+        true
+    } else {
+        let names = changed
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        progress.emit(
+            "spectate-folder",
+            "failed",
+            Some(format!("Still being written: {names}")),
+        );
+        false
+    }
+}
+
+// ── Orchestration ───────────────────────────────────────────────────────
+
+fn run_shutdown(app: AppHandle) -> ShutdownReport {
+    let started_at_ms = now_ms();
+    let store = app.state::<SharedSetupStore>().inner().clone();
+    let test_state = app.state::<SharedTestState>().inner().clone();
+    let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+    let history = app.state::<SharedOverlayHistory>().inner().clone();
+    let mut progress = StartupProgress::new(app.clone(), "shutdown-progress");
+    let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+
+    stop_auto_report(&mut progress, &test_state);
+    let sim_state = flush_pending(&mut progress, &test_state);
+
+    let event_report_path = match write_event_report(&test_state, &live_startgg, &history, sim_state) {
+        Ok(path) => {
+            let path = path.display().to_string();
+            progress.emit("event-report", "ok", Some(format!("Wrote {path}.")));
+            Some(path)
+        }
+        Err(err) => {
+            progress.emit("event-report", "failed", Some(err));
+            None
+        }
+    };
+
+    stop_children(&mut progress, &store);
+    verify_spectate_idle(&mut progress, &config);
+
+    ShutdownReport {
+        safe_to_power_down: progress.steps.iter().all(|step| step.status != "failed"),
+        steps: progress.steps,
+        event_report_path,
+        started_at_ms,
+        finished_at_ms: now_ms(),
+    }
+}
+
+/// End-of-day routine: stop auto-reporting, flush pending reports, write the
+/// event report, stop Dolphin children and check nothing is still writing to
+/// the spectate folder. Progress goes out as `shutdown-progress` events and
+/// the final report as `shutdown-complete`.
+#[tauri::command]
+#[specta::specta]
+pub fn orchestrate_shutdown(app: AppHandle) -> Result<(), String> {
+    if SHUTDOWN_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Shutdown is already in progress.".to_string());
+    }
+    std::thread::spawn(move || {
+        let report = run_shutdown(app.clone());
+        SHUTDOWN_RUNNING.store(false, Ordering::SeqCst);
+        if !report.safe_to_power_down {
+            tracing::warn!("Shutdown finished with failed steps");
+        }
+        let _ = app.emit("shutdown-complete", &report);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, io::Write};
+
+    fn spectate_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("2024-06")).unwrap();
+        fs::write(dir.join("2024-06").join("Game_1.slp"), b"done").unwrap();
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_quiet_spectate_folder_has_no_writes() {
+        let dir = spectate_dir("shutdown-quiet");
+        assert!(spectate_folder_writes(&dir, Duration::from_millis(50)).is_empty());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replays_still_growing_are_reported() {
+        let dir = spectate_dir("shutdown-writing");
+        let live = dir.join("2024-06").join("Game_2.slp");
+        fs::write(&live, b"start").unwrap();
+        let writer = {
+            let live = live.clone();
+            let notes = dir.join("notes.txt");
+            std::thread::spawn(move || {
+                sleep(Duration::from_millis(100));
+                fs::OpenOptions::new().append(true).open(&live).unwrap().write_all(b"frames").unwrap();
+                fs::write(notes, b"not a replay").unwrap();
+            })
+        };
+        let changed = spectate_folder_writes(&dir, Duration::from_millis(400));
+        writer.join().unwrap();
+        assert_eq!(changed, vec![live]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
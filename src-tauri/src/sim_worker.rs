@@ -0,0 +1,240 @@
+use crate::config::*;
+use crate::startgg::{install_startgg_sim, load_startgg_sim};
+use crate::startgg_sim::{StartggSim, StartggSimState};
+use crate::types::{SharedTestState, TestModeState};
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::Duration,
+};
+
+/// Returned by commands that need the sim while it is still loading.
+pub const SIM_WARMING: &str = "The simulated bracket is still loading; try again in a moment.";
+const ADVANCE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Latest full state of the sim, kept by the advance loop so polls don't
+/// hold the test-state lock while the sim advances. Shared, so a poll
+/// clones only the sets it answers with.
+static SNAPSHOT: Mutex<Option<Arc<StartggSimState>>> = Mutex::new(None);
+/// Config path being loaded in the background, if any.
+static WARMING: Mutex<Option<PathBuf>> = Mutex::new(None);
+/// Why the last background load failed, handed to the next caller.
+static LOAD_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Replace the snapshot, e.g. right after a command changed the sim.
+pub fn publish(state: StartggSimState) {
+    *SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(state));
+}
+
+fn published_version() -> Option<u64> {
+    SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner()).as_ref().map(|state| state.version)
+}
+
+/// Put a new sim in place of the current one, e.g. on a reset, and publish
+/// it so no poll is answered from the old bracket's snapshot.
+pub fn replace_sim(guard: &mut TestModeState, sim: StartggSim, now: u64) -> &mut StartggSim {
+    let sim = guard.startgg_sim.insert(sim);
+    publish(sim.state(now));
+    sim
+}
+
+/// Load the sim on a background thread unless it is loaded or already
+/// loading. A reset or a new config path while it loads wins over it.
+fn start_warmup(test_state: &SharedTestState, config_path: Option<PathBuf>) {
+    let effective_path = config_path.clone().unwrap_or_else(startgg_sim_config_path);
+    {
+        let mut warming = WARMING.lock().unwrap_or_else(|e| e.into_inner());
+        if warming.as_ref() == Some(&effective_path) {
+            return;
+        }
+        *warming = Some(effective_path.clone());
+    }
+    tracing::info!("Loading bracket sim from {} in the background", effective_path.display());
+    let test_state = test_state.clone();
+    std::thread::spawn(move || {
+        let now = now_ms();
+        let loaded = load_startgg_sim(config_path.as_deref(), now);
+        let mut guard = test_state.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.startgg_sim.is_none() && guard.startgg_config_path == config_path {
+            match loaded {
+                Ok(loaded) => {
+                    install_startgg_sim(&mut guard, loaded);
+                    tracing::info!("Bracket sim loaded in {} ms", now_ms().saturating_sub(now));
+                }
+                Err(err) => {
+                    tracing::warn!("Failed to load bracket sim: {}", err);
+                    *LOAD_ERROR.lock().unwrap_or_else(|e| e.into_inner()) = Some(err);
+                }
+            }
+        }
+        drop(guard);
+        let mut warming = WARMING.lock().unwrap_or_else(|e| e.into_inner());
+        if warming.as_ref() == Some(&effective_path) {
+            *warming = None;
+        }
+    });
+}
+
+/// Start loading the sim for the configured path unless it is loading
+/// already. A failed load is reported once, and the next call tries again.
+pub fn warm_up(test_state: &SharedTestState, guard: &TestModeState) -> Result<(), String> {
+    if let Some(err) = LOAD_ERROR.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        return Err(err);
+    }
+    start_warmup(test_state, guard.startgg_config_path.clone());
+    Ok(())
+}
+
+fn snapshot(guard: &mut TestModeState) -> Option<Arc<StartggSimState>> {
+    let sim = guard.startgg_sim.as_mut()?;
+    let mut snapshot = SNAPSHOT.lock().unwrap_or_else(|e| e.into_inner());
+    // Only until the advance loop's first pass.
+    Some(snapshot.get_or_insert_with(|| Arc::new(sim.state(now_ms()))).clone())
+}
+
+/// The latest sim state without waiting on a load: `Ok(None)` while the
+/// sim is still warming up, which this starts if needed.
+pub fn snapshot_or_warm(test_state: &SharedTestState) -> Result<Option<Arc<StartggSimState>>, String> {
+    let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+    if let Some(state) = snapshot(&mut guard) {
+        return Ok(Some(state));
+    }
+    warm_up(test_state, &guard)?;
+    Ok(None)
+}
+
+/// The latest sim state, or `None` if the sim hasn't loaded. Never starts
+/// a load.
+pub fn loaded_state(test_state: &SharedTestState) -> Option<StartggSimState> {
+    snapshot(&mut test_state.lock().unwrap_or_else(|e| e.into_inner())).map(|state| (*state).clone())
+}
+
+/// Advance the sim once and publish its state if a set changed since the
+/// last publish. Manual brackets only change through commands, which
+/// publish themselves, so they never build a snapshot here.
+fn advance_and_publish(test_state: &SharedTestState) {
+    let mut guard = test_state.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(sim) = guard.startgg_sim.as_mut() else {
+        return;
+    };
+    let now = now_ms();
+    if published_version() != Some(sim.tick(now)) {
+        publish(sim.state(now));
+    }
+}
+
+/// Advance the sim on a timer and publish its state, so polls and
+/// overlays read a snapshot instead of advancing it under the lock.
+pub fn spawn_sim_advance_loop(test_state: SharedTestState) {
+    std::thread::spawn(move || loop {
+        sleep(ADVANCE_INTERVAL);
+        if test_flags().fake_bracket {
+            advance_and_publish(&test_state);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::{fs, path::Path, time::Instant};
+
+    /// The snapshot and warm-up state are process-wide.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn write_sim_config(path: &Path, name: &str) {
+        let entrants = (1..=4)
+            .map(|id| json!({ "id": id, "name": format!("P{id}"), "slippiCode": format!("P{id}#0"), "seed": id }))
+            .collect::<Vec<_>>();
+        let config = json!({
+            "event": { "id": name, "name": name, "slug": name },
+            "phases": [{ "id": "bracket", "name": "Bracket", "bestOf": 3 }],
+            "entrants": entrants,
+            "simulation": { "manualMode": true },
+            "referenceTournamentLink": null,
+        });
+        fs::write(path, serde_json::to_vec(&config).unwrap()).unwrap();
+    }
+
+    fn test_state_for(path: &Path) -> SharedTestState {
+        let state = TestModeState { startgg_config_path: Some(path.to_path_buf()), ..TestModeState::default() };
+        Arc::new(Mutex::new(state))
+    }
+
+    /// Poll like a command until the warm-up settles one way or the other.
+    fn poll_until_settled(test_state: &SharedTestState) -> Result<Option<Arc<StartggSimState>>, String> {
+        let started = Instant::now();
+        loop {
+            let result = snapshot_or_warm(test_state);
+            if !matches!(result, Ok(None)) || started.elapsed() > Duration::from_secs(10) {
+                return result;
+            }
+            sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sim-worker-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn polls_see_warming_then_the_loaded_bracket() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = test_dir("load");
+        let path = dir.join("bracket.json");
+        write_sim_config(&path, "Warm Event");
+        let test_state = test_state_for(&path);
+
+        assert!(matches!(snapshot_or_warm(&test_state), Ok(None)));
+        let state = poll_until_settled(&test_state).unwrap().expect("the sim should load");
+        assert_eq!(state.event.name, "Warm Event");
+        assert!(!state.warming);
+        assert!(test_state.lock().unwrap().startgg_sim.is_some());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_failed_load_is_reported_once_then_retried() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = test_dir("fail");
+        let test_state = test_state_for(&dir.join("missing.json"));
+
+        assert!(matches!(snapshot_or_warm(&test_state), Ok(None)));
+        let err = poll_until_settled(&test_state).expect_err("the load should fail");
+        assert!(err.contains("not found"), "{err}");
+        // Reported once; the next poll starts another load instead.
+        assert!(matches!(snapshot_or_warm(&test_state), Ok(None)));
+        assert!(poll_until_settled(&test_state).is_err());
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn a_new_sim_replaces_the_old_snapshot() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = test_dir("reset");
+        let (old_path, new_path) = (dir.join("old.json"), dir.join("new.json"));
+        write_sim_config(&old_path, "Old Event");
+        write_sim_config(&new_path, "New Event");
+        let test_state = test_state_for(&old_path);
+        let load = |path: &Path| load_startgg_sim(Some(path), now_ms()).unwrap();
+
+        install_startgg_sim(&mut test_state.lock().unwrap(), load(&old_path));
+        assert_eq!(loaded_state(&test_state).unwrap().event.name, "Old Event");
+
+        let mut guard = test_state.lock().unwrap();
+        replace_sim(&mut guard, load(&new_path).sim, now_ms());
+        drop(guard);
+        assert_eq!(loaded_state(&test_state).unwrap().event.name, "New Event");
+
+        // Nothing changed since, so the advance loop leaves it alone.
+        let published = SNAPSHOT.lock().unwrap().clone().unwrap();
+        advance_and_publish(&test_state);
+        assert!(Arc::ptr_eq(&published, &SNAPSHOT.lock().unwrap().clone().unwrap()));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
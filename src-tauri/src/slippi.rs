@@ -8,6 +8,22 @@ use crate::dolphin::{
     write_slippi_watch_label, clear_slippi_watch_label, slippi_launches_dolphin, list_slippi_pids,
     target_display, slippi_appimage_path,
 };
+use crate::audit_log::audited;
+use crate::idle_mode::exit_idle;
+use crate::windowing::{find_toplevel, TopLevel, X11_BACKEND};
+use crate::auto_stop::leave_break;
+use crate::mirror::end_mirror;
+use crate::watch_party::WATCH_PARTY_SOURCE;
+use crate::capture_sources::{is_capture_stream, stream_capture_source};
+use crate::broadcast_watch::{broadcast_status_from_lines, viewer_count_from_lines};
+use crate::capabilities::{require_slippi_launcher, require_window_list};
+use crate::feature_flags::require_feature;
+use crate::slippi_ocr::ocr_fallback_streams;
+use crate::slippi_ws::{native_spectate_enabled, scan_streams as scan_native_streams, stop_setup_watch, watch_natively};
+use crate::stream_identity::{migrate_assignments, stable_stream_id, stream_id_codes, CODES_ID_PREFIX};
+use crate::network::client_builder;
+use crate::playback::PlaybackSession;
+use crate::slippi_login::ensure_slippi_logged_in;
 use crate::replay::{
     filter_broadcast_streams, find_opponent_code_in_replay, tag_from_code,
     update_replay_index, latest_replay_for_code,
@@ -16,15 +32,15 @@ use serde_json::{json, Value};
 use std::{
     collections::HashMap,
     env,
+    net::{TcpStream, ToSocketAddrs},
     path::PathBuf,
     process::{Command, Stdio},
     thread::sleep,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tauri::State;
+use tauri::{AppHandle, State};
 use tungstenite::Message;
 use x11rb::{
-    connection::Connection,
     protocol::xproto::{AtomEnum, ConnectionExt, Window},
     rust_connection::RustConnection,
 };
@@ -96,9 +112,20 @@ pub fn slippi_x11_connect() -> Result<(RustConnection, usize), String> {
 
 // ── CDP automation ──────────────────────────────────────────────────────
 
+/// How long a single Runtime.evaluate may take before it counts as hung.
+const CDP_EVAL_TIMEOUT: Duration = Duration::from_secs(5);
+const CDP_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Attempts per evaluate, reconnecting to a fresh target between them.
+const CDP_MAX_ATTEMPTS: u32 = 3;
+const CDP_RETRY_DELAY: Duration = Duration::from_millis(250);
+
 pub fn cdp_targets(port: u16) -> Result<Vec<CdpTarget>, String> {
   let url = format!("http://127.0.0.1:{port}/json/list");
-  let resp = reqwest::blocking::get(&url).map_err(|e| format!("fetch {url}: {e}"))?;
+  let client = client_builder()?
+    .timeout(CDP_EVAL_TIMEOUT)
+    .build()
+    .map_err(|e| format!("build DevTools client: {e}"))?;
+  let resp = client.get(&url).send().map_err(|e| format!("fetch {url}: {e}"))?;
   if !resp.status().is_success() {
     return Err(format!("DevTools list {url} returned {}", resp.status()));
   }
@@ -119,8 +146,58 @@ pub fn pick_slippi_target(targets: Vec<CdpTarget>) -> Option<CdpTarget> {
   fallback
 }
 
-pub fn cdp_eval(ws_url: &str, expr: &str) -> Result<Value, String> {
-  let (mut socket, _) = tungstenite::connect(ws_url).map_err(|e| format!("cdp connect {ws_url}: {e}"))?;
+#[derive(Debug)]
+pub enum CdpError {
+  /// Couldn't open the DevTools websocket
+  Connect(String),
+  /// The websocket closed or failed before the reply arrived
+  Closed(String),
+  /// No reply to the message within the timeout
+  Timeout { ws_url: String, timeout_ms: u64 },
+  /// The page answered with an exception or protocol error
+  Eval(String),
+}
+
+impl std::fmt::Display for CdpError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CdpError::Connect(err) => write!(f, "cdp connect failed: {err}"),
+      CdpError::Closed(err) => write!(f, "cdp connection closed: {err}"),
+      CdpError::Timeout { ws_url, timeout_ms } => {
+        write!(f, "cdp timeout: no reply from {ws_url} within {timeout_ms}ms")
+      }
+      CdpError::Eval(err) => write!(f, "cdp eval error: {err}"),
+    }
+  }
+}
+
+fn cdp_connect(ws_url: &str, timeout: Duration) -> Result<tungstenite::WebSocket<TcpStream>, CdpError> {
+  let host = ws_url
+    .trim_start_matches("ws://")
+    .split('/')
+    .next()
+    .unwrap_or_default();
+  let addr = host
+    .to_socket_addrs()
+    .map_err(|e| CdpError::Connect(format!("resolve {host}: {e}")))?
+    .next()
+    .ok_or_else(|| CdpError::Connect(format!("resolve {host}: no address")))?;
+  let stream = TcpStream::connect_timeout(&addr, CDP_CONNECT_TIMEOUT)
+    .map_err(|e| CdpError::Connect(format!("{ws_url}: {e}")))?;
+  stream
+    .set_read_timeout(Some(timeout))
+    .map_err(|e| CdpError::Connect(e.to_string()))?;
+  stream
+    .set_write_timeout(Some(timeout))
+    .map_err(|e| CdpError::Connect(e.to_string()))?;
+  let (socket, _) = tungstenite::client(ws_url, stream).map_err(|e| CdpError::Connect(format!("{ws_url}: {e}")))?;
+  Ok(socket)
+}
+
+/// Evaluate `expr` once on `ws_url`, giving up after `timeout`.
+pub fn cdp_eval_with_timeout(ws_url: &str, expr: &str, timeout: Duration) -> Result<Value, CdpError> {
+  let deadline = Instant::now() + timeout;
+  let mut socket = cdp_connect(ws_url, timeout)?;
   let msg = json!({
     "id": 1,
     "method": "Runtime.evaluate",
@@ -130,33 +207,133 @@ pub fn cdp_eval(ws_url: &str, expr: &str) -> Result<Value, String> {
       "awaitPromise": true,
     }
   });
-  socket.send(Message::Text(msg.to_string())).map_err(|e| e.to_string())?;
+  socket
+    .send(Message::Text(msg.to_string()))
+    .map_err(|e| CdpError::Closed(e.to_string()))?;
 
+  let timed_out = || CdpError::Timeout {
+    ws_url: ws_url.to_string(),
+    timeout_ms: timeout.as_millis() as u64,
+  };
   loop {
-    let msg = socket.read().map_err(|e| e.to_string())?;
-    if let Message::Text(txt) = msg {
-      if let Ok(val) = serde_json::from_str::<Value>(&txt) {
-        if val.get("id").and_then(|v| v.as_i64()) == Some(1) {
-          if let Some(err) = val.get("error") {
-            return Err(format!("cdp eval error: {err}"));
-          }
-          if let Some(result) = val
-            .get("result")
-            .and_then(|r| r.get("result"))
-            .and_then(|r| r.get("value"))
-          {
-            return Ok(result.clone());
-          }
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+      return Err(timed_out());
+    }
+    let _ = socket.get_ref().set_read_timeout(Some(remaining));
+    let msg = match socket.read() {
+      Ok(msg) => msg,
+      Err(tungstenite::Error::Io(e))
+        if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+      {
+        return Err(timed_out());
+      }
+      Err(e) => return Err(CdpError::Closed(e.to_string())),
+    };
+    let txt = match msg {
+      Message::Text(txt) => txt,
+      Message::Close(frame) => {
+        return Err(CdpError::Closed(
+          frame.map(|f| f.reason.to_string()).unwrap_or_else(|| "closed by peer".to_string()),
+        ))
+      }
+      _ => continue,
+    };
+    let Ok(val) = serde_json::from_str::<Value>(&txt) else {
+      continue;
+    };
+    if val.get("id").and_then(|v| v.as_i64()) != Some(1) {
+      continue;
+    }
+    if let Some(err) = val.get("error") {
+      return Err(CdpError::Eval(err.to_string()));
+    }
+    let result = val.get("result");
+    if let Some(details) = result.and_then(|r| r.get("exceptionDetails")) {
+      return Err(CdpError::Eval(details.to_string()));
+    }
+    let _ = socket.close(None);
+    return Ok(result
+      .and_then(|r| r.get("result"))
+      .and_then(|r| r.get("value"))
+      .cloned()
+      .unwrap_or(Value::Null));
+  }
+}
+
+pub fn cdp_eval(ws_url: &str, expr: &str) -> Result<Value, String> {
+  cdp_eval_with_timeout(ws_url, expr, CDP_EVAL_TIMEOUT).map_err(|e| e.to_string())
+}
+
+/// CdpSession holds the Launcher page we're talking to. An evaluate that
+/// couldn't reach the page is retried on a fresh target, a bounded number
+/// of times. One that may have reached it (the socket dropped, or no reply
+/// came in time) is only retried by [`CdpSession::eval_read`], since running
+/// a click twice isn't safe.
+pub struct CdpSession {
+  port: u16,
+  target: CdpTarget,
+  ws_url: String,
+  eval_timeout: Duration,
+}
+
+impl CdpSession {
+  pub fn connect(port: u16) -> Result<Self, String> {
+    let targets = cdp_targets(port)?;
+    let target = pick_slippi_target(targets)
+      .ok_or_else(|| "No DevTools targets found; is Slippi running with --remote-debugging-port?".to_string())?;
+    let ws_url = target
+      .ws_url
+      .clone()
+      .ok_or_else(|| "Target missing webSocketDebuggerUrl".to_string())?;
+    Ok(CdpSession { port, target, ws_url, eval_timeout: CDP_EVAL_TIMEOUT })
+  }
+
+  pub fn title(&self) -> Option<String> {
+    self.target.title.clone()
+  }
+
+  /// Evaluate `expr`, which may change the page (e.g. click a button).
+  /// Only retried when the page was never reached.
+  pub fn eval(&mut self, expr: &str) -> Result<Value, String> {
+    self.eval_retrying(expr, |err| matches!(err, CdpError::Connect(_)))
+  }
+
+  /// Evaluate `expr`, which only reads the page, so it's also retried after
+  /// a dropped socket or a timeout.
+  pub fn eval_read(&mut self, expr: &str) -> Result<Value, String> {
+    self.eval_retrying(expr, |err| !matches!(err, CdpError::Eval(_)))
+  }
+
+  fn eval_retrying(&mut self, expr: &str, retry: impl Fn(&CdpError) -> bool) -> Result<Value, String> {
+    let mut last_err = None;
+    for attempt in 0..CDP_MAX_ATTEMPTS {
+      if attempt > 0 {
+        sleep(CDP_RETRY_DELAY * attempt);
+        match CdpSession::connect(self.port) {
+          Ok(fresh) => *self = CdpSession { eval_timeout: self.eval_timeout, ..fresh },
+          Err(err) => tracing::debug!("CDP reconnect to port {} failed: {}", self.port, err),
+        }
+      }
+      match cdp_eval_with_timeout(&self.ws_url, expr, self.eval_timeout) {
+        Ok(value) => return Ok(value),
+        Err(err) if !retry(&err) => return Err(err.to_string()),
+        Err(err) => {
+          tracing::debug!("CDP evaluate attempt {} failed: {}", attempt + 1, err);
+          last_err = Some(err);
         }
       }
     }
+    Err(format!(
+      "{} (after {} attempts)",
+      last_err.map(|e| e.to_string()).unwrap_or_default(),
+      CDP_MAX_ATTEMPTS
+    ))
   }
 }
 
 pub fn scrape_slippi_via_cdp(port: u16) -> Result<Vec<SlippiStream>, String> {
-  let targets = cdp_targets(port)?;
-  let target = pick_slippi_target(targets).ok_or_else(|| "No DevTools targets found; is Slippi running with --remote-debugging-port?".to_string())?;
-  let ws_url = target.ws_url.ok_or_else(|| "Target missing webSocketDebuggerUrl".to_string())?;
+  let mut session = CdpSession::connect(port)?;
 
   let expr = r#"
     (() => {
@@ -178,11 +355,13 @@ pub fn scrape_slippi_via_cdp(port: u16) -> Result<Vec<SlippiStream>, String> {
         const hasIdle = lower.some(line => idleTokens.some(token => line.includes(token)));
         const isPlaying = hasPlaying && !hasIdle;
         const name = text[0] || null;
-        const code = text.find(t => t.includes('#')) || null;
+        const codes = text.filter(t => t.includes('#'));
+        const code = codes[0] || null;
         return {
           id: c.id || `card-${idx}`,
           name,
           code,
+          codes,
           isPlaying,
           text,
         };
@@ -190,7 +369,8 @@ pub fn scrape_slippi_via_cdp(port: u16) -> Result<Vec<SlippiStream>, String> {
     })()
   "#;
 
-  let value = cdp_eval(&ws_url, expr)?;
+  let value = session.eval_read(expr)?;
+  let window_title = session.title();
   let arr = value.as_array().ok_or_else(|| "Unexpected CDP eval result (not array)".to_string())?;
 
   let mut out = vec![];
@@ -198,15 +378,30 @@ pub fn scrape_slippi_via_cdp(port: u16) -> Result<Vec<SlippiStream>, String> {
     let name = item.get("name").and_then(|v| v.as_str()).map(|s| s.to_string());
     let code = item.get("code").and_then(|v| v.as_str()).map(|s| s.to_string());
     let is_playing = item.get("isPlaying").and_then(|v| v.as_bool());
-    let id = item
+    let card_id = item
       .get("id")
       .and_then(|v| v.as_str())
       .map(|s| s.to_string())
       .unwrap_or_else(|| format!("card-{idx}"));
+    let codes = item
+      .get("codes")
+      .and_then(|v| v.as_array())
+      .map(|codes| codes.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
+      .unwrap_or_default();
+    let lines = item
+      .get("text")
+      .and_then(|v| v.as_array())
+      .map(|lines| lines.iter().filter_map(|v| v.as_str()).map(str::to_string).collect::<Vec<_>>())
+      .unwrap_or_default();
+    // DOM ids change between refreshes; connect codes don't. A second
+    // card with the same codes keeps its DOM id so ids stay unique.
+    let id = stable_stream_id(&codes)
+      .filter(|id| !out.iter().any(|stream: &SlippiStream| &stream.id == id))
+      .unwrap_or_else(|| card_id.clone());
 
     out.push(SlippiStream {
       id,
-      window_title: target.title.clone(),
+      window_title: window_title.clone(),
       p1_tag: name.clone(),
       p2_tag: None,
       p1_code: code.clone(),
@@ -216,17 +411,19 @@ pub fn scrape_slippi_via_cdp(port: u16) -> Result<Vec<SlippiStream>, String> {
       is_playing,
       source: Some(format!("cdp port {port}")),
       startgg_set: None,
+      p1_character: None,
+      card_id: Some(card_id),
+      viewer_count: viewer_count_from_lines(&lines),
+      broadcast_status: broadcast_status_from_lines(&lines),
     });
   }
   Ok(out)
 }
 
 pub fn click_slippi_refresh(port: u16) -> Result<(), String> {
-  let targets = cdp_targets(port)?;
-  let target = pick_slippi_target(targets).ok_or_else(|| "No DevTools targets found; is Slippi running with --remote-debugging-port?".to_string())?;
-  let ws_url = target.ws_url.ok_or_else(|| "Target missing webSocketDebuggerUrl".to_string())?;
+  let mut session = CdpSession::connect(port)?;
 
-  fn try_click_refresh(ws_url: &str) -> Result<(bool, Option<String>), String> {
+  fn try_click_refresh(session: &mut CdpSession) -> Result<(bool, Option<String>), String> {
     let expr = r#"
       (() => {
         const buttons = Array.from(document.querySelectorAll('button'));
@@ -241,13 +438,13 @@ pub fn click_slippi_refresh(port: u16) -> Result<(), String> {
       })()
     "#;
 
-    let result = cdp_eval(ws_url, expr)?;
+    let result = session.eval(expr)?;
     let clicked = result.get("clicked").and_then(|v| v.as_bool()).unwrap_or(false);
     let reason = result.get("reason").and_then(|v| v.as_str()).map(|s| s.to_string());
     Ok((clicked, reason))
   }
 
-  let (clicked, reason) = try_click_refresh(&ws_url)?;
+  let (clicked, reason) = try_click_refresh(&mut session)?;
   if clicked {
     return Ok(());
   }
@@ -267,7 +464,7 @@ pub fn click_slippi_refresh(port: u16) -> Result<(), String> {
     })()
   "#;
 
-  let nav_result = cdp_eval(&ws_url, nav_expr)?;
+  let nav_result = session.eval(nav_expr)?;
   let nav_clicked = nav_result.get("clicked").and_then(|v| v.as_bool()).unwrap_or(false);
   if !nav_clicked {
     let nav_reason = nav_result.get("reason").and_then(|v| v.as_str()).unwrap_or("unknown reason");
@@ -279,7 +476,7 @@ pub fn click_slippi_refresh(port: u16) -> Result<(), String> {
 
   // Let navigation settle, then try the refresh button again.
   sleep(Duration::from_millis(600));
-  let (clicked_after_nav, reason_after_nav) = try_click_refresh(&ws_url)?;
+  let (clicked_after_nav, reason_after_nav) = try_click_refresh(&mut session)?;
   if clicked_after_nav {
     Ok(())
   } else {
@@ -288,34 +485,66 @@ pub fn click_slippi_refresh(port: u16) -> Result<(), String> {
   }
 }
 
-pub fn click_slippi_watch(port: u16, target_id: String, target_code: Option<String>, target_tag: Option<String>) -> Result<(), String> {
-  let targets = cdp_targets(port)?;
-  let target = pick_slippi_target(targets).ok_or_else(|| "No DevTools targets found; is Slippi running with --remote-debugging-port?".to_string())?;
-  let ws_url = target.ws_url.ok_or_else(|| "Target missing webSocketDebuggerUrl".to_string())?;
-
-  let id_json = serde_json::to_string(&target_id).map_err(|e| e.to_string())?;
+/// Click Watch on the spectate card for a stream. Cards are matched by the
+/// connect codes in a stable stream id first, since the launcher's DOM ids
+/// change between refreshes, then by the old DOM id, then code, then tag.
+/// Several cards matching is reported rather than guessed at.
+pub fn click_slippi_watch(
+  port: u16,
+  target_id: String,
+  card_id: Option<String>,
+  target_code: Option<String>,
+  target_tag: Option<String>,
+) -> Result<(), String> {
+  let mut session = CdpSession::connect(port)?;
+
+  let codes = stream_id_codes(&target_id);
+  let legacy_ids = [Some(target_id.as_str()), card_id.as_deref()]
+    .into_iter()
+    .flatten()
+    .filter(|id| !id.starts_with(CODES_ID_PREFIX))
+    .collect::<Vec<_>>();
+  let codes_json = serde_json::to_string(&codes).map_err(|e| e.to_string())?;
+  let ids_json = serde_json::to_string(&legacy_ids).map_err(|e| e.to_string())?;
   let code_json = serde_json::to_string(&target_code).map_err(|e| e.to_string())?;
   let tag_json = serde_json::to_string(&target_tag).map_err(|e| e.to_string())?;
 
   let expr = format!(
     r#"
       (() => {{
-        const targetId = {id};
+        const targetCodes = {codes};
+        const targetIds = {ids};
         const targetCode = {code};
         const targetTag = {tag};
         const cards = Array.from(document.querySelectorAll('.css-7xs1xn, [data-testid="spectate-card"], .css-o8b25d .MuiPaper-root'));
         const normalize = (txt) => (txt || '').toLowerCase().trim();
+        const containing = (needles) => cards.filter(c => needles.every(n => normalize(c.innerText).includes(normalize(n))));
 
-        let card = cards.find(c => c.id === targetId);
-        if (!card && targetCode) {{
-          card = cards.find(c => normalize(c.innerText).includes(normalize(targetCode)));
+        let matches = [];
+        let matchedBy = null;
+        if (targetCodes.length) {{
+          matches = containing(targetCodes);
+          matchedBy = 'codes';
+        }}
+        if (!matches.length && targetIds.length) {{
+          matches = cards.filter(c => targetIds.includes(c.id));
+          matchedBy = 'card id';
         }}
-        if (!card && targetTag) {{
-          card = cards.find(c => normalize(c.innerText).includes(normalize(targetTag)));
+        if (!matches.length && targetCode) {{
+          matches = containing([targetCode]);
+          matchedBy = 'code';
         }}
-        if (!card) {{
+        if (!matches.length && targetTag) {{
+          matches = containing([targetTag]);
+          matchedBy = 'tag';
+        }}
+        if (!matches.length) {{
           return {{ clicked: false, reason: 'card not found', count: cards.length }};
         }}
+        if (matches.length > 1) {{
+          return {{ clicked: false, reason: 'ambiguous', matchedBy, count: matches.length }};
+        }}
+        const card = matches[0];
 
         const buttons = Array.from(card.querySelectorAll('button'));
         const byIcon = buttons.find(btn => btn.querySelector('[data-testid="PlayCircleOutlineIcon"]'));
@@ -325,29 +554,107 @@ pub fn click_slippi_watch(port: u16, target_id: String, target_code: Option<Stri
           return {{ clicked: false, reason: 'watch button not found in card' }};
         }}
         btn.click();
-        return {{ clicked: true, label: btn.innerText || null, cardId: card.id || null }};
+        return {{ clicked: true, label: btn.innerText || null, cardId: card.id || null, matchedBy }};
       }})()
     "#,
-    id = id_json,
+    codes = codes_json,
+    ids = ids_json,
     code = code_json,
     tag = tag_json
   );
 
-  let result = cdp_eval(&ws_url, &expr)?;
+  let result = session.eval(&expr)?;
   let clicked = result.get("clicked").and_then(|v| v.as_bool()).unwrap_or(false);
   if clicked {
-    Ok(())
-  } else {
-    let reason = result.get("reason").and_then(|v| v.as_str()).unwrap_or("unknown reason");
-    Err(format!("Failed to click Slippi Watch: {reason}"))
+    return Ok(());
+  }
+  let reason = result.get("reason").and_then(|v| v.as_str()).unwrap_or("unknown reason");
+  let label = if codes.is_empty() { target_id.clone() } else { codes.join(" vs ") };
+  match reason {
+    "card not found" => Err(format!(
+      "Failed to click Slippi Watch: {label} is no longer in the launcher's spectate list. Refresh the launcher and assign the stream again."
+    )),
+    "ambiguous" => {
+      let count = result.get("count").and_then(|v| v.as_u64()).unwrap_or(0);
+      let matched_by = result.get("matchedBy").and_then(|v| v.as_str()).unwrap_or("card");
+      Err(format!(
+        "Failed to click Slippi Watch: {count} spectate cards match {label} by {matched_by}. Refresh the launcher, then pick the right card and assign it again."
+      ))
+    }
+    _ => Err(format!("Failed to click Slippi Watch: {reason}")),
+  }
+}
+
+/// Spectate a remote broadcaster through the launcher's connect-by-code
+/// form on the Spectate tab, for players who aren't in the spectate list.
+pub fn connect_slippi_by_code(port: u16, code: &str) -> Result<(), String> {
+  let mut session = CdpSession::connect(port)?;
+  let code_json = serde_json::to_string(code).map_err(|e| e.to_string())?;
+
+  let expr = format!(
+    r#"
+      (() => {{
+        const code = {code};
+        const normalize = (txt) => (txt || '').toLowerCase().trim();
+        const inputs = Array.from(document.querySelectorAll('input'));
+        const input = inputs.find(i =>
+          normalize(i.placeholder).includes('code') || normalize(i.getAttribute('aria-label')).includes('code')
+        );
+        if (!input) {{
+          const anchors = Array.from(document.querySelectorAll('a'));
+          const spectate = anchors.find(a => (a.getAttribute('href') || '').includes('/spectate'));
+          if (spectate) {{
+            spectate.click();
+            return {{ connected: false, reason: 'navigated' }};
+          }}
+          return {{ connected: false, reason: 'code input not found' }};
+        }}
+        const setter = Object.getOwnPropertyDescriptor(HTMLInputElement.prototype, 'value').set;
+        setter.call(input, code);
+        input.dispatchEvent(new Event('input', {{ bubbles: true }}));
+        const scope = input.closest('form') || input.parentElement?.parentElement?.parentElement || document;
+        const buttons = Array.from(scope.querySelectorAll('button'));
+        const btn = buttons.find(b => ['connect', 'spectate', 'watch'].some(w => normalize(b.innerText).includes(w)));
+        if (!btn) {{
+          return {{ connected: false, reason: 'connect button not found' }};
+        }}
+        if (btn.disabled) {{
+          return {{ connected: false, reason: 'connect button disabled' }};
+        }}
+        btn.click();
+        return {{ connected: true }};
+      }})()
+    "#,
+    code = code_json
+  );
+
+  let mut result = session.eval(&expr)?;
+  if result.get("reason").and_then(|v| v.as_str()) == Some("navigated") {
+    // Let the Spectate tab render, then fill in the form.
+    sleep(Duration::from_millis(600));
+    result = session.eval(&expr)?;
+  }
+  if result.get("connected").and_then(|v| v.as_bool()).unwrap_or(false) {
+    return Ok(());
+  }
+  let reason = result.get("reason").and_then(|v| v.as_str()).unwrap_or("unknown reason");
+  match reason {
+    "code input not found" | "navigated" => Err(format!(
+      "Failed to connect to {code}: the launcher's Spectate tab has no connect code field. Update the launcher and try again."
+    )),
+    "connect button disabled" => Err(format!(
+      "Failed to connect to {code}: the launcher rejected the code. Check it and try again."
+    )),
+    _ => Err(format!("Failed to connect to {code}: {reason}")),
   }
 }
 
 // ── Tauri commands ──────────────────────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 pub fn find_slippi_launcher_window() -> Result<Option<SlippiWindowInfo>, String> {
-  if mock_streams_enabled() || app_test_mode_enabled() {
+  if mock_streams_enabled() || test_flags().fake_streams {
     return Ok(Some(SlippiWindowInfo {
       id: 0,
       title: Some("Mock Slippi Launcher".to_string()),
@@ -356,75 +663,60 @@ pub fn find_slippi_launcher_window() -> Result<Option<SlippiWindowInfo>, String>
       width: 1280,
       height: 720,
       screen: 0,
+      backend: X11_BACKEND.to_string(),
     }));
   }
 
-  let (conn, screen_num) = slippi_x11_connect()?;
-  let root = conn.setup().roots[screen_num].root;
-  let tree = conn
-    .query_tree(root)
-    .map_err(|e| e.to_string())?
-    .reply()
-    .map_err(|e| e.to_string())?;
-
-  let mut best: Option<(SlippiWindowInfo, u32)> = None;
-
-  for win in tree.children {
-    let title = read_window_title(&conn, win).unwrap_or_default();
-    let wm_class = read_wm_class(&conn, win).unwrap_or_default();
-    let title_lower = title.to_lowercase();
-    let class_lower: Vec<String> = wm_class.iter().map(|c| c.to_lowercase()).collect();
-
-    let is_match = title_lower.contains("slippi launcher")
-      || (title_lower.contains("slippi") && title_lower.contains("launcher"))
-      || class_lower.iter().any(|c| c.contains("slippi-launcher") || c.contains("slippi launcher") || c.contains("slippi"));
-    if !is_match {
-      continue;
-    }
-
-    let geo = conn
-      .get_geometry(win)
-      .map_err(|e| e.to_string())?
-      .reply()
-      .map_err(|e| e.to_string())?;
-
-    let area = (geo.width as u32) * (geo.height as u32);
-    if geo.width < 200 || geo.height < 200 {
-      // Likely a tiny helper window; skip unless no other candidates.
-      if best.is_some() {
-        continue;
-      }
-    }
-
-    let info = SlippiWindowInfo {
-      id: win,
-      title: if title.is_empty() { None } else { Some(title) },
-      x: geo.x.into(),
-      y: geo.y.into(),
-      width: geo.width.into(),
-      height: geo.height.into(),
-      screen: screen_num as u32,
-    };
-
-    match &best {
-      Some((_, best_area)) if area <= *best_area => {}
-      _ => best = Some((info, area)),
-    }
-  }
+  require_window_list("Finding the Slippi Launcher window")?;
+  let Some((window, backend)) = find_toplevel(is_slippi_launcher)? else {
+    return Ok(None);
+  };
+  let geometry = window.geometry.unwrap_or_default();
+  Ok(Some(SlippiWindowInfo {
+    id: window.id,
+    title: if window.title.is_empty() { None } else { Some(window.title) },
+    x: geometry.x,
+    y: geometry.y,
+    width: geometry.width,
+    height: geometry.height,
+    screen: window.screen,
+    backend: backend.to_string(),
+  }))
+}
 
-  Ok(best.map(|(info, _)| info))
+fn is_slippi_launcher(window: &TopLevel) -> bool {
+  let title_lower = window.title.to_lowercase();
+  let class_lower: Vec<String> = window.app_ids.iter().map(|c| c.to_lowercase()).collect();
+  title_lower.contains("slippi launcher")
+    || (title_lower.contains("slippi") && title_lower.contains("launcher"))
+    || class_lower.iter().any(|c| c.contains("slippi-launcher") || c.contains("slippi launcher") || c.contains("slippi"))
 }
 
-/// Scan the Slippi Launcher window, screenshot it, OCR the contents, and try to extract tags/connect codes.
+/// List spectate cards from Slippi's spectate server when native spectating
+/// is on, else scrape them from the Slippi Launcher over CDP, falling back to
+/// OCR of a window screenshot when that finds nothing. Assignments whose
+/// card changed ids are moved to the card's current id.
 #[tauri::command]
+#[specta::specta]
 pub fn scan_slippi_streams(
+  app: AppHandle,
   test_state: State<'_, SharedTestState>,
   replay_cache: State<'_, SharedOverlayCache>,
+  store: State<'_, SharedSetupStore>,
+) -> Result<Vec<SlippiStream>, String> {
+  scan_slippi_streams_inner(&app, &test_state, &replay_cache, &store)
+}
+
+pub fn scan_slippi_streams_inner(
+  app: &AppHandle,
+  test_state: &SharedTestState,
+  replay_cache: &SharedOverlayCache,
+  store: &SharedSetupStore,
 ) -> Result<Vec<SlippiStream>, String> {
   if mock_streams_enabled() {
     return test_mode_streams();
   }
-  if app_test_mode_enabled() {
+  if test_flags().fake_streams {
     let mut guard = test_state.lock().map_err(|e| e.to_string())?;
     if guard.broadcast_filter_enabled {
       return test_mode_broadcast_streams(&mut guard);
@@ -440,8 +732,28 @@ pub fn scan_slippi_streams(
     return Ok(filter_broadcast_streams(&streams, &guard));
   }
   let devtools_port = slippi_devtools_port();
-  let mut streams = scrape_slippi_via_cdp(devtools_port)?;
-  let config = load_config_inner()?;
+  let native = if native_spectate_enabled() {
+    scan_native_streams()
+      .map_err(|err| tracing::warn!("Native Slippi scan failed, using the launcher: {}", err))
+      .ok()
+  } else {
+    None
+  };
+  let mut streams = match native {
+    Some(streams) => streams,
+    None => match scrape_slippi_via_cdp(devtools_port) {
+      Ok(streams) if !streams.is_empty() => streams,
+      cdp_result => match ocr_fallback_streams(&cdp_result) {
+        Some(streams) => streams,
+        None => {
+          let streams = cdp_result?;
+          ensure_slippi_logged_in(app, devtools_port)?;
+          streams
+        }
+      },
+    },
+  };
+  let config = cached_config()?;
   let spectate = config.spectate_folder_path.trim();
   if !spectate.is_empty() {
     let dir = resolve_repo_path(spectate);
@@ -462,37 +774,98 @@ pub fn scan_slippi_streams(
       }
     }
   }
+  migrate_assignments(&mut store.lock().map_err(|e| e.to_string())?.setups, &streams);
   Ok(streams)
 }
 
 #[tauri::command]
-pub fn refresh_slippi_launcher() -> Result<(), String> {
-  if mock_streams_enabled() || app_test_mode_enabled() {
+#[specta::specta]
+pub fn refresh_slippi_launcher(app: AppHandle) -> Result<(), String> {
+  if mock_streams_enabled() || test_flags().fake_streams {
     return Ok(());
   }
   let devtools_port = slippi_devtools_port();
-  click_slippi_refresh(devtools_port)
+  click_slippi_refresh(devtools_port).or_else(|err| {
+    ensure_slippi_logged_in(&app, devtools_port)?;
+    Err(err)
+  })
 }
 
 #[tauri::command]
-pub fn watch_slippi_stream(stream_id: String, p1_code: Option<String>, p1_tag: Option<String>) -> Result<(), String> {
-  if mock_streams_enabled() || app_test_mode_enabled() {
+#[specta::specta]
+pub fn watch_slippi_stream(
+  app: AppHandle,
+  stream_id: String,
+  card_id: Option<String>,
+  p1_code: Option<String>,
+  p1_tag: Option<String>,
+) -> Result<(), String> {
+  if mock_streams_enabled() || test_flags().fake_streams {
     return Ok(());
   }
+  if native_spectate_enabled() {
+    match watch_natively(p1_code.as_deref(), p1_tag.as_deref(), None) {
+      Ok(()) => return Ok(()),
+      Err(err) => tracing::warn!("Native Slippi watch failed, using the launcher: {}", err),
+    }
+  }
   let devtools_port = slippi_devtools_port();
-  click_slippi_watch(devtools_port, stream_id, p1_code, p1_tag)
+  watch_stream(stream_id, card_id, p1_code, p1_tag).or_else(|err| {
+    ensure_slippi_logged_in(&app, devtools_port)?;
+    Err(err)
+  })
+}
+
+fn watch_stream(
+  stream_id: String,
+  card_id: Option<String>,
+  p1_code: Option<String>,
+  p1_tag: Option<String>,
+) -> Result<(), String> {
+  require_feature(FeatureFlag::AutoSpectate)?;
+  require_slippi_launcher("Spectating")?;
+  click_slippi_watch(slippi_devtools_port(), stream_id, card_id, p1_code, p1_tag)
+}
+
+fn connect_stream(code: &str) -> Result<(), String> {
+  require_slippi_launcher("Spectating")?;
+  connect_slippi_by_code(slippi_devtools_port(), code)
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn assign_stream_to_setup(
   setup_id: u32,
   stream: SlippiStream,
   launch: Option<bool>,
   store: State<'_, SharedSetupStore>,
   test_state: State<'_, SharedTestState>,
+) -> Result<AssignStreamResult, String> {
+  let params = json!({
+    "setupId": setup_id,
+    "streamId": stream.id,
+    "p1Code": stream.p1_code,
+    "p1Tag": stream.p1_tag,
+    "launch": launch,
+  });
+  audited("assignStream", params, || {
+    let result = assign_stream(setup_id, stream, launch, &store, &test_state)?;
+    exit_idle(&format!("stream assigned to setup {setup_id}"));
+    leave_break(setup_id);
+    end_mirror(setup_id);
+    Ok(result)
+  })
+}
+
+pub fn assign_stream(
+  setup_id: u32,
+  stream: SlippiStream,
+  launch: Option<bool>,
+  store: &SharedSetupStore,
+  test_state: &SharedTestState,
 ) -> Result<AssignStreamResult, String> {
   let should_launch = launch.unwrap_or(true);
-  let test_mode = app_test_mode_enabled();
+  let test_mode = test_flags().fake_streams;
   let (changed_assignments, processes_to_stop, pids_to_stop, updated_setups) = {
     let mut guard = store.lock().map_err(|e| e.to_string())?;
     if !guard.setups.iter().any(|s| s.id == setup_id) {
@@ -554,6 +927,10 @@ pub fn assign_stream_to_setup(
       if prev_id != next_id || prev_playing != next_playing || replay_changed {
         changed_assignments.push((*id, new_assignment.clone()));
       }
+      // A console station's capture card goes wherever its stream does.
+      if new_assignment.is_some() || setup.assigned_stream.as_ref().is_some_and(is_capture_stream) {
+        setup.capture_source_id = new_assignment.as_ref().and_then(stream_capture_source);
+      }
       setup.assigned_stream = new_assignment.clone();
     }
 
@@ -574,6 +951,7 @@ pub fn assign_stream_to_setup(
     let mut processes_to_stop = Vec::new();
     let mut pids_to_stop = Vec::new();
     for (id, _) in &changed_assignments {
+      stop_setup_watch(*id);
       if should_launch {
         if let Some(child) = guard.processes.remove(id) {
           processes_to_stop.push(child);
@@ -597,20 +975,25 @@ pub fn assign_stream_to_setup(
     }
   }
 
-  let replay_map = if should_launch && test_mode {
+  let (replay_map, replay_queues) = if should_launch && test_mode {
     let guard = test_state.lock().map_err(|e| e.to_string())?;
-    guard.spoof_replays.clone()
+    (guard.spoof_replays.clone(), guard.spoof_replay_queues.clone())
   } else {
-    HashMap::new()
+    (HashMap::new(), HashMap::new())
   };
 
   let mut warning_messages = Vec::new();
   let mut new_children: Vec<(u32, std::process::Child)> = Vec::new();
+  let mut new_sessions: Vec<(u32, PlaybackSession)> = Vec::new();
   let mut new_pids: Vec<(u32, u32)> = Vec::new();
 
   if should_launch {
     for (id, assignment) in changed_assignments {
       let Some(assigned_stream) = assignment else { continue; };
+      // Console stations are captured by OBS; there's no Dolphin to start.
+      if is_capture_stream(&assigned_stream) {
+        continue;
+      }
       if test_mode {
         if assigned_stream.is_playing == Some(true) {
           let replay = assigned_stream
@@ -626,7 +1009,16 @@ pub fn assign_stream_to_setup(
             continue;
           };
           match launch_dolphin_playback_for_setup_internal(id, &replay) {
-            Ok(child) => new_children.push((id, child)),
+            Ok((child, mut session)) => {
+              // Test folders with several matchups play them all in turn.
+              if let Some(queue) = replay_queues.get(&assigned_stream.id) {
+                if let Err(err) = session.set_queue(queue) {
+                  warning_messages.push(format!("Setup {id}: {err}"));
+                }
+              }
+              new_children.push((id, child));
+              new_sessions.push((id, session));
+            }
             Err(err) => warning_messages.push(format!("Setup {id}: {err}")),
           }
         } else {
@@ -636,6 +1028,13 @@ pub fn assign_stream_to_setup(
           }
         }
       } else {
+        // The native watch's playback Dolphin is started by its monitor.
+        if native_spectate_enabled() && assigned_stream.source.as_deref() != Some(WATCH_PARTY_SOURCE) {
+          match watch_natively(assigned_stream.p1_code.as_deref(), assigned_stream.p1_tag.as_deref(), Some(id)) {
+            Ok(()) => continue,
+            Err(err) => tracing::warn!("Native Slippi watch for setup {} failed, using the launcher: {}", id, err),
+          }
+        }
         let slippi_auto = slippi_launches_dolphin();
         let existing_pids = if slippi_auto {
           Some(list_dolphin_like_pids())
@@ -660,11 +1059,17 @@ pub fn assign_stream_to_setup(
           }
         }
 
-        if let Err(err) = watch_slippi_stream(
-          assigned_stream.id.clone(),
-          assigned_stream.p1_code.clone(),
-          assigned_stream.p1_tag.clone(),
-        ) {
+        let watched = if assigned_stream.source.as_deref() == Some(WATCH_PARTY_SOURCE) {
+          connect_stream(assigned_stream.p1_code.as_deref().unwrap_or_default())
+        } else {
+          watch_stream(
+            assigned_stream.id.clone(),
+            assigned_stream.card_id.clone(),
+            assigned_stream.p1_code.clone(),
+            assigned_stream.p1_tag.clone(),
+          )
+        };
+        if let Err(err) = watched {
           warning_messages.push(format!("Setup {id}: {err}"));
           if let Some(path) = label_path.as_ref() {
             clear_slippi_watch_label(path);
@@ -720,6 +1125,10 @@ pub fn assign_stream_to_setup(
     let mut guard = store.lock().map_err(|e| e.to_string())?;
     for (id, child) in new_children {
       guard.processes.insert(id, child);
+      guard.playback_sessions.remove(&id);
+    }
+    for (id, session) in new_sessions {
+      guard.playback_sessions.insert(id, session);
     }
     for (id, pid) in new_pids {
       guard.process_pids.insert(id, pid);
@@ -739,11 +1148,17 @@ pub fn assign_stream_to_setup(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn clear_setup_assignment(
   setup_id: u32,
   stop: Option<bool>,
   store: State<'_, SharedSetupStore>,
 ) -> Result<Setup, String> {
+  let params = json!({ "setupId": setup_id, "stop": stop });
+  audited("clearAssignment", params, || clear_assignment(setup_id, stop, &store))
+}
+
+pub fn clear_assignment(setup_id: u32, stop: Option<bool>, store: &SharedSetupStore) -> Result<Setup, String> {
   let should_stop = stop.unwrap_or(true);
   let (setup, existing, existing_pid) = {
     let mut guard = store.lock().map_err(|e| e.to_string())?;
@@ -754,6 +1169,7 @@ pub fn clear_setup_assignment(
       .ok_or_else(|| "Setup not found.".to_string())?;
     setup.assigned_stream = None;
     let cloned = setup.clone();
+    stop_setup_watch(setup_id);
     let (existing, existing_pid) = if should_stop {
       (
         guard.processes.remove(&setup_id),
@@ -778,7 +1194,9 @@ pub fn clear_setup_assignment(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn launch_slippi_app() -> Result<(), String> {
+  require_slippi_launcher("Launching Slippi")?;
   let appimage = slippi_appimage_path()?;
   let devtools_port = slippi_devtools_port();
 
@@ -799,6 +1217,7 @@ pub fn launch_slippi_app() -> Result<(), String> {
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn relaunch_slippi_app() -> Result<(), String> {
   let appimage = slippi_appimage_path()?;
   let existing = list_slippi_pids(&appimage);
@@ -814,3 +1233,73 @@ pub fn relaunch_slippi_app() -> Result<(), String> {
   sleep(Duration::from_millis(400));
   launch_slippi_app()
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::{
+    net::TcpListener,
+    sync::{
+      atomic::{AtomicUsize, Ordering},
+      Arc,
+    },
+    thread,
+  };
+
+  /// A DevTools websocket that takes every evaluate and never answers.
+  /// Returns its URL and how many evaluates it has received.
+  fn silent_devtools() -> (String, Arc<AtomicUsize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let ws_url = format!("ws://{}/devtools/page/1", listener.local_addr().unwrap());
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = received.clone();
+    thread::spawn(move || {
+      for stream in listener.incoming().flatten() {
+        let counter = counter.clone();
+        thread::spawn(move || {
+          let Ok(mut socket) = tungstenite::accept(stream) else {
+            return;
+          };
+          while let Ok(msg) = socket.read() {
+            if msg.is_text() {
+              counter.fetch_add(1, Ordering::SeqCst);
+            }
+          }
+        });
+      }
+    });
+    (ws_url, received)
+  }
+
+  /// A session on `ws_url` whose port has no DevTools, so reconnecting
+  /// keeps the same target.
+  fn session(ws_url: &str) -> CdpSession {
+    CdpSession {
+      port: 0,
+      target: CdpTarget { title: None, kind: None, ws_url: Some(ws_url.to_string()) },
+      ws_url: ws_url.to_string(),
+      eval_timeout: Duration::from_millis(200),
+    }
+  }
+
+  #[test]
+  fn an_unanswered_evaluate_times_out() {
+    let (ws_url, received) = silent_devtools();
+    let err = cdp_eval_with_timeout(&ws_url, "1 + 1", Duration::from_millis(200)).unwrap_err();
+    assert!(matches!(err, CdpError::Timeout { timeout_ms: 200, .. }), "{err}");
+    assert_eq!(received.load(Ordering::SeqCst), 1);
+  }
+
+  #[test]
+  fn a_click_that_timed_out_is_not_sent_again() {
+    let (ws_url, received) = silent_devtools();
+    let err = session(&ws_url).eval("document.querySelector('button').click()").unwrap_err();
+    assert!(err.starts_with("cdp timeout"), "{err}");
+    assert_eq!(received.load(Ordering::SeqCst), 1);
+
+    // Reads are safe to repeat.
+    let err = session(&ws_url).eval_read("document.title").unwrap_err();
+    assert!(err.ends_with(&format!("(after {CDP_MAX_ATTEMPTS} attempts)")), "{err}");
+    assert_eq!(received.load(Ordering::SeqCst), 1 + CDP_MAX_ATTEMPTS as usize);
+  }
+}
@@ -0,0 +1,312 @@
+use crate::config::*;
+use crate::slippi::{slippi_devtools_port, CdpSession};
+use crate::test_mode::mock_streams_enabled;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter};
+
+/// Prefix of errors returned while the Launcher is logged out, so the
+/// frontend can tell them apart from other CDP failures.
+pub const SLIPPI_NEEDS_LOGIN: &str = "slippi-needs-login";
+
+/// How long to wait for the Launcher to finish logging in after submitting.
+const LOGIN_SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+const LOGIN_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SlippiLoginStatus {
+    pub logged_in: bool,
+    /// Whether a login form is on screen (so it can be filled in)
+    pub login_form: bool,
+    pub url: Option<String>,
+    pub message: Option<String>,
+    /// Whether credentials are stored for automatic login
+    pub has_credentials: bool,
+    pub auto_login: bool,
+}
+
+// ── Credentials ─────────────────────────────────────────────────────────
+//
+// Kept out of config.json so they never reach the frontend or a shared
+// config; on Unix the file is readable by the current user only, from the
+// moment it's created.
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SlippiCredentials {
+    email: String,
+    password: String,
+}
+
+fn credentials_path() -> PathBuf {
+    repo_root().join("slippi_login.json")
+}
+
+fn load_credentials() -> Option<SlippiCredentials> {
+    read_credentials(&credentials_path())
+}
+
+fn read_credentials(path: &Path) -> Option<SlippiCredentials> {
+    let data = fs::read_to_string(path).ok()?;
+    serde_json::from_str::<SlippiCredentials>(&data)
+        .ok()
+        .filter(|creds| !creds.email.trim().is_empty() && !creds.password.is_empty())
+}
+
+fn save_credentials(creds: &SlippiCredentials) -> Result<(), String> {
+    write_credentials(&credentials_path(), creds)
+}
+
+fn write_credentials(path: &Path, creds: &SlippiCredentials) -> Result<(), String> {
+    let payload = serde_json::to_string(creds).map_err(|e| e.to_string())?;
+    write_private_file_atomic(path, payload.as_bytes()).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+// ── Detection ───────────────────────────────────────────────────────────
+
+/// Logged out means a login form or prompt is showing and the Spectate
+/// navigation is not.
+const DETECT_LOGIN_EXPR: &str = r#"
+  (() => {
+    const text = (document.body && document.body.innerText || '').toLowerCase();
+    const password = document.querySelector('input[type="password"]');
+    const buttons = Array.from(document.querySelectorAll('button, a'));
+    const loginButton = buttons.find(btn => /^(log ?in|sign ?in)$/i.test((btn.innerText || '').trim()));
+    const spectate = Array.from(document.querySelectorAll('a')).some(a =>
+      (a.getAttribute('href') || '').includes('/spectate') ||
+      (a.getAttribute('aria-label') || '').toLowerCase().includes('spectate'));
+    const prompt = text.includes('log in to') || text.includes('sign in to') || text.includes('not logged in');
+    return {
+      loggedOut: !spectate && (!!password || !!loginButton || prompt),
+      loginForm: !!password,
+      url: location.href,
+    };
+  })()
+"#;
+
+fn detect_login(session: &mut CdpSession) -> Result<(bool, bool, Option<String>), String> {
+    let value = session.eval_read(DETECT_LOGIN_EXPR)?;
+    let logged_out = value.get("loggedOut").and_then(|v| v.as_bool()).unwrap_or(false);
+    let login_form = value.get("loginForm").and_then(|v| v.as_bool()).unwrap_or(false);
+    let url = value.get("url").and_then(|v| v.as_str()).map(|s| s.to_string());
+    Ok((!logged_out, login_form, url))
+}
+
+/// Open the login form if only a "Log in" button is showing, then fill in
+/// and submit the credentials. React inputs ignore plain `.value` writes,
+/// so the native setter is used and an input event dispatched.
+fn fill_login_form(session: &mut CdpSession, creds: &SlippiCredentials) -> Result<(), String> {
+    let email = serde_json::to_string(&creds.email).map_err(|e| e.to_string())?;
+    let password = serde_json::to_string(&creds.password).map_err(|e| e.to_string())?;
+    let expr = format!(
+        r#"
+        (async () => {{
+          const sleep = ms => new Promise(r => setTimeout(r, ms));
+          if (!document.querySelector('input[type="password"]')) {{
+            const open = Array.from(document.querySelectorAll('button, a'))
+              .find(btn => /^(log ?in|sign ?in)$/i.test((btn.innerText || '').trim()));
+            if (open) {{ open.click(); await sleep(800); }}
+          }}
+          const password = document.querySelector('input[type="password"]');
+          const email = document.querySelector('input[type="email"], input[name="email"], input[autocomplete="username"]');
+          if (!password || !email) return {{ ok: false, reason: 'login form not found' }};
+          const set = (el, value) => {{
+            const setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(el), 'value').set;
+            setter.call(el, value);
+            el.dispatchEvent(new Event('input', {{ bubbles: true }}));
+          }};
+          set(email, {email});
+          set(password, {password});
+          const form = password.closest('form');
+          const submit = (form && form.querySelector('button[type="submit"]')) ||
+            Array.from(document.querySelectorAll('button'))
+              .find(btn => /log ?in|sign ?in/i.test((btn.innerText || '').trim()));
+          if (submit) submit.click();
+          else if (form) form.requestSubmit();
+          else return {{ ok: false, reason: 'submit button not found' }};
+          return {{ ok: true }};
+        }})()
+        "#
+    );
+    let result = session.eval(&expr)?;
+    if result.get("ok").and_then(|v| v.as_bool()).unwrap_or(false) {
+        Ok(())
+    } else {
+        let reason = result.get("reason").and_then(|v| v.as_str()).unwrap_or("unknown reason");
+        Err(format!("Slippi login automation failed: {reason}"))
+    }
+}
+
+fn login_status(port: u16) -> Result<SlippiLoginStatus, String> {
+    let mut session = CdpSession::connect(port)?;
+    let (logged_in, login_form, url) = detect_login(&mut session)?;
+    let config = cached_config().unwrap_or_default();
+    Ok(SlippiLoginStatus {
+        logged_in,
+        login_form,
+        url,
+        message: None,
+        has_credentials: load_credentials().is_some(),
+        auto_login: config.slippi_auto_login,
+    })
+}
+
+/// Try the stored credentials and wait for the Launcher to leave the login
+/// screen.
+fn auto_login(port: u16) -> Result<SlippiLoginStatus, String> {
+    let creds = load_credentials().ok_or_else(|| "No Slippi credentials stored.".to_string())?;
+    let mut session = CdpSession::connect(port)?;
+    fill_login_form(&mut session, &creds)?;
+    let deadline = std::time::Instant::now() + LOGIN_SETTLE_TIMEOUT;
+    loop {
+        sleep(LOGIN_POLL_INTERVAL);
+        let status = login_status(port)?;
+        if status.logged_in || std::time::Instant::now() >= deadline {
+            return Ok(status);
+        }
+    }
+}
+
+/// Check whether the Launcher is stuck behind its login screen. When it is,
+/// try the stored credentials if the operator opted in; if it is still
+/// logged out, emit `slippi-needs-login` and return an error starting with
+/// SLIPPI_NEEDS_LOGIN.
+pub fn ensure_slippi_logged_in(app: &AppHandle, port: u16) -> Result<(), String> {
+    let mut status = login_status(port)?;
+    if status.logged_in {
+        return Ok(());
+    }
+    if status.auto_login && status.has_credentials {
+        tracing::info!("Slippi Launcher is logged out; trying stored credentials");
+        match auto_login(port) {
+            Ok(next) if next.logged_in => return Ok(()),
+            Ok(next) => status = next,
+            Err(err) => status.message = Some(err),
+        }
+    }
+    let message = status
+        .message
+        .clone()
+        .unwrap_or_else(|| "Slippi Launcher is logged out; log in to reach the Spectate tab.".to_string());
+    status.message = Some(message.clone());
+    let _ = app.emit(SLIPPI_NEEDS_LOGIN, &status);
+    Err(format!("{SLIPPI_NEEDS_LOGIN}: {message}"))
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn slippi_login_status() -> Result<SlippiLoginStatus, String> {
+    if mock_streams_enabled() || test_flags().fake_streams {
+        return Ok(SlippiLoginStatus {
+            logged_in: true,
+            login_form: false,
+            url: None,
+            message: None,
+            has_credentials: load_credentials().is_some(),
+            auto_login: false,
+        });
+    }
+    login_status(slippi_devtools_port())
+}
+
+/// Store the Launcher credentials used by automatic login. They are never
+/// returned to the frontend.
+#[tauri::command]
+#[specta::specta]
+pub fn set_slippi_login_credentials(email: String, password: String) -> Result<(), String> {
+    let email = email.trim().to_string();
+    if email.is_empty() || password.is_empty() {
+        return Err("Email and password are both required.".to_string());
+    }
+    save_credentials(&SlippiCredentials { email, password })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn clear_slippi_login_credentials() -> Result<(), String> {
+    let path = credentials_path();
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("delete {}: {e}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Log the Launcher in now with the stored credentials, whether or not
+/// automatic login is enabled.
+#[tauri::command]
+#[specta::specta]
+pub fn slippi_login_now(app: AppHandle) -> Result<SlippiLoginStatus, String> {
+    let port = slippi_devtools_port();
+    let status = login_status(port)?;
+    if status.logged_in {
+        return Ok(status);
+    }
+    let status = auto_login(port)?;
+    if !status.logged_in {
+        let _ = app.emit(SLIPPI_NEEDS_LOGIN, &status);
+    }
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn creds(email: &str, password: &str) -> SlippiCredentials {
+        SlippiCredentials { email: email.to_string(), password: password.to_string() }
+    }
+
+    /// An empty scratch dir and the credentials path inside it.
+    fn login_file(name: &str) -> (PathBuf, PathBuf) {
+        let dir = env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("slippi_login.json");
+        (dir, path)
+    }
+
+    #[test]
+    fn no_file_means_no_credentials() {
+        let (dir, path) = login_file("slippi-login-missing");
+        assert!(read_credentials(&path).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn credentials_round_trip_through_the_file() {
+        let (dir, path) = login_file("slippi-login-round-trip");
+        write_credentials(&path, &creds("to@example.com", "hunter2")).unwrap();
+        let stored = read_credentials(&path).unwrap();
+        assert_eq!((stored.email.as_str(), stored.password.as_str()), ("to@example.com", "hunter2"));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1, "temp file was left behind");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_file_is_only_readable_by_its_owner() {
+        use std::os::unix::fs::PermissionsExt;
+        let (dir, path) = login_file("slippi-login-private");
+        write_credentials(&path, &creds("to@example.com", "hunter2")).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn half_filled_credentials_are_as_good_as_none() {
+        let (dir, path) = login_file("slippi-login-half");
+        write_credentials(&path, &creds("to@example.com", "")).unwrap();
+        assert!(read_credentials(&path).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,200 @@
+use crate::capabilities::require_x11;
+use crate::slippi::{find_slippi_launcher_window, slippi_x11_connect};
+use crate::stream_identity::stable_stream_id;
+use crate::types::{SlippiStream, SlippiWindowInfo};
+use crate::windowing::X11_BACKEND;
+use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+/// Marks streams read off a screenshot. OCR misreads codes often enough
+/// that these should be confirmed before a stream is assigned.
+pub const OCR_SOURCE: &str = "ocr (low confidence)";
+
+const PLAYING_TOKENS: &[&str] = &["in game", "playing", "in progress", "in-progress", "in match"];
+const IDLE_TOKENS: &[&str] = &["in lobby", "lobby", "waiting", "idle", "menu"];
+
+/// Connect code from one OCR token, fixing the usual letter/digit mix-ups
+/// in the number part. None for anything that isn't shaped like a code.
+pub fn ocr_connect_code(token: &str) -> Option<String> {
+    let token = token.trim_matches(|c: char| !c.is_ascii_alphanumeric() && c != '#');
+    let (name, number) = token.split_once('#')?;
+    if name.is_empty() || name.len() > 8 || !name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let number = number
+        .chars()
+        .map(|c| match c {
+            'O' | 'o' | 'D' => '0',
+            'I' | 'l' | '|' => '1',
+            'S' | 's' => '5',
+            'B' => '8',
+            other => other,
+        })
+        .collect::<String>();
+    if number.is_empty() || number.len() > 4 || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{}#{number}", name.to_ascii_uppercase()))
+}
+
+/// Spectate cards from the Launcher's OCR'd text: the display name sits on
+/// the line above the connect code, and the card's status below it.
+pub fn parse_spectate_text(text: &str) -> Vec<SlippiStream> {
+    let lines = text.lines().map(str::trim).filter(|line| !line.is_empty()).collect::<Vec<_>>();
+    let code_lines = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| line.split_whitespace().find_map(ocr_connect_code).map(|code| (idx, code)))
+        .collect::<Vec<_>>();
+
+    let mut out = Vec::with_capacity(code_lines.len());
+    for (n, (idx, code)) in code_lines.iter().enumerate() {
+        let tag = idx
+            .checked_sub(1)
+            .map(|prev| lines[prev])
+            .filter(|prev| ocr_connect_code(prev).is_none())
+            .map(str::to_string);
+        let card_end = code_lines.get(n + 1).map(|(next, _)| next.saturating_sub(1)).unwrap_or(lines.len());
+        let status = lines[idx + 1..card_end.max(idx + 1)].join(" ").to_lowercase();
+        let has_playing = PLAYING_TOKENS.iter().any(|token| status.contains(token));
+        let has_idle = IDLE_TOKENS.iter().any(|token| status.contains(token));
+        out.push(SlippiStream {
+            id: stable_stream_id(&[code]).unwrap_or_else(|| format!("ocr-{n}")),
+            window_title: None,
+            p1_tag: tag,
+            p2_tag: None,
+            p1_code: Some(code.clone()),
+            p2_code: None,
+            startgg_entrant_id: None,
+            replay_path: None,
+            is_playing: Some(has_playing && !has_idle),
+            source: Some(OCR_SOURCE.to_string()),
+            startgg_set: None,
+            p1_character: None,
+            card_id: None,
+            viewer_count: None,
+            broadcast_status: None,
+        });
+    }
+    out
+}
+
+/// Grab the window as a binary PPM, which leptonica reads from memory.
+fn capture_window_ppm(info: &SlippiWindowInfo) -> Result<Vec<u8>, String> {
+    if info.backend != X11_BACKEND {
+        return Err("Capturing the Slippi Launcher window needs X11 or XWayland.".to_string());
+    }
+    let (conn, _) = slippi_x11_connect()?;
+    let (width, height) = (info.width.min(u16::MAX as u32) as u16, info.height.min(u16::MAX as u32) as u16);
+    let image = conn
+        .get_image(ImageFormat::Z_PIXMAP, info.id, 0, 0, width, height, !0)
+        .map_err(|e| format!("capture Slippi Launcher window: {e}"))?
+        .reply()
+        .map_err(|e| format!("capture Slippi Launcher window: {e}"))?;
+    let pixels = width as usize * height as usize;
+    if image.data.len() < pixels * 4 {
+        return Err(format!(
+            "Unsupported Slippi Launcher window format (depth {}, {} bytes for {width}x{height}).",
+            image.depth,
+            image.data.len()
+        ));
+    }
+    let mut ppm = format!("P6\n{width} {height}\n255\n").into_bytes();
+    ppm.reserve(pixels * 3);
+    // 24/32-bit ZPixmaps are BGRX on little-endian X servers.
+    for px in image.data.chunks_exact(4).take(pixels) {
+        ppm.extend_from_slice(&[px[2], px[1], px[0]]);
+    }
+    Ok(ppm)
+}
+
+#[cfg(feature = "ocr")]
+fn ocr_image(image: &[u8]) -> Result<String, String> {
+    let mut tess = leptess::LepTess::new(None, "eng").map_err(|e| format!("start tesseract: {e}"))?;
+    tess.set_image_from_mem(image).map_err(|e| format!("load Launcher screenshot: {e}"))?;
+    tess.get_utf8_text().map_err(|e| format!("OCR Launcher screenshot: {e}"))
+}
+
+#[cfg(not(feature = "ocr"))]
+fn ocr_image(_image: &[u8]) -> Result<String, String> {
+    Err("OCR fallback is not built in; rebuild with `--features ocr` (needs tesseract and leptonica).".to_string())
+}
+
+/// Read spectate cards off a screenshot of the Launcher's Spectate tab.
+pub fn scrape_slippi_via_ocr() -> Result<Vec<SlippiStream>, String> {
+    require_x11("Reading the Slippi Launcher by OCR")?;
+    let info = find_slippi_launcher_window()?.ok_or_else(|| "Slippi Launcher window not found.".to_string())?;
+    let text = ocr_image(&capture_window_ppm(&info)?)?;
+    let mut streams = parse_spectate_text(&text);
+    for stream in &mut streams {
+        stream.window_title = info.title.clone();
+    }
+    Ok(streams)
+}
+
+/// Last resort when CDP scraping errors or finds no cards (usually a
+/// Launcher update changed its DOM). None when OCR found nothing either.
+pub fn ocr_fallback_streams(cdp_result: &Result<Vec<SlippiStream>, String>) -> Option<Vec<SlippiStream>> {
+    match scrape_slippi_via_ocr() {
+        Ok(streams) if !streams.is_empty() => {
+            tracing::warn!(
+                "CDP scan {}; using {} stream(s) read by OCR",
+                match cdp_result {
+                    Ok(_) => "found no spectate cards".to_string(),
+                    Err(err) => format!("failed ({err})"),
+                },
+                streams.len()
+            );
+            Some(streams)
+        }
+        Ok(_) => None,
+        Err(err) => {
+            tracing::debug!("OCR fallback unavailable: {}", err);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAUNCHER_TEXT: &str =
+        "Spectate\nRefresh\n\nMang0\nMANG#0\nIn game\nWatch\n\nzain\nZA1N#9O8\nIn lobby\nWatch\n";
+
+    #[test]
+    fn misread_digits_in_codes_are_fixed() {
+        assert_eq!(ocr_connect_code("ZA1N#9O8").as_deref(), Some("ZA1N#908"));
+        assert_eq!(ocr_connect_code("(mang#l5)").as_deref(), Some("MANG#15"));
+    }
+
+    #[test]
+    fn tokens_not_shaped_like_codes_are_ignored() {
+        assert_eq!(ocr_connect_code("#123"), None);
+        assert_eq!(ocr_connect_code("hello"), None);
+        assert_eq!(ocr_connect_code("MANG#12345"), None);
+        assert_eq!(ocr_connect_code("TOOLONGNAME#1"), None);
+    }
+
+    #[test]
+    fn each_code_becomes_a_card_named_by_the_line_above() {
+        let streams = parse_spectate_text(LAUNCHER_TEXT);
+        assert_eq!(streams.len(), 2);
+        assert_eq!(streams[0].p1_tag.as_deref(), Some("Mang0"));
+        assert_eq!(streams[0].p1_code.as_deref(), Some("MANG#0"));
+        assert_eq!(streams[0].id, "codes:MANG#0");
+        assert_eq!(streams[1].p1_tag.as_deref(), Some("zain"));
+        assert_eq!(streams[1].p1_code.as_deref(), Some("ZA1N#908"));
+    }
+
+    #[test]
+    fn card_status_decides_whether_it_is_playing() {
+        let streams = parse_spectate_text(LAUNCHER_TEXT);
+        assert_eq!(streams[0].is_playing, Some(true));
+        assert_eq!(streams[1].is_playing, Some(false));
+    }
+
+    #[test]
+    fn cards_read_by_ocr_are_marked_low_confidence() {
+        assert!(parse_spectate_text(LAUNCHER_TEXT).iter().all(|stream| stream.source.as_deref() == Some(OCR_SOURCE)));
+    }
+}
@@ -0,0 +1,321 @@
+use crate::config::*;
+use crate::network::{client_builder, offline_error, offline_mode_enabled, record_request_result};
+use crate::types::{AllSetupsState, PlayerRank, PlayerState, SharedRankCache};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::{HashMap, HashSet}, thread::sleep, time::Duration};
+use tauri::State;
+
+const SLIPPI_GQL_URL: &str = "https://gql-gateway-2-dot-slippi.uc.r.appspot.com/graphql";
+/// How long a successful lookup is reused before refetching.
+const RANK_TTL_MS: u64 = 10 * 60 * 1000;
+/// How long a failed or empty lookup is remembered, so a bad code doesn't
+/// hit the API on every refresh.
+const RANK_MISS_TTL_MS: u64 = 60 * 1000;
+/// How often the background refresher fetches ranks the overlays are missing.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+const SLIPPI_RANK_QUERY: &str = r#"
+query UserRankQuery($cc: String!) {
+  getConnectCode(code: $cc) {
+    user {
+      displayName
+      connectCode { code }
+      rankedNetplayProfile {
+        ratingOrdinal
+        ratingUpdateCount
+        wins
+        losses
+        dailyGlobalPlacement
+        dailyRegionalPlacement
+        continent
+      }
+    }
+  }
+}
+"#;
+
+/// Upper rating bound (exclusive) for each rank below Master 3.
+const RANK_THRESHOLDS: [(f64, &str); 17] = [
+    (765.43, "Bronze 1"),
+    (913.72, "Bronze 2"),
+    (1054.87, "Bronze 3"),
+    (1188.88, "Silver 1"),
+    (1315.68, "Silver 2"),
+    (1435.48, "Silver 3"),
+    (1548.73, "Gold 1"),
+    (1653.52, "Gold 2"),
+    (1751.83, "Gold 3"),
+    (1842.74, "Platinum 1"),
+    (1927.90, "Platinum 2"),
+    (2003.92, "Platinum 3"),
+    (2074.30, "Diamond 1"),
+    (2136.28, "Diamond 2"),
+    (2191.75, "Diamond 3"),
+    (2274.99, "Master 1"),
+    (2350.00, "Master 2"),
+];
+
+/// Ranks are only shown once a player has played enough ranked sets.
+const MIN_RATED_GAMES: u32 = 5;
+
+#[derive(Default)]
+pub struct RankCache {
+    entries: HashMap<String, CachedRank>,
+    /// Codes the overlays showed without a fresh rank, for the refresher
+    wanted: HashSet<String>,
+}
+
+struct CachedRank {
+    rank: Option<PlayerRank>,
+    fetched_at_ms: u64,
+}
+
+impl RankCache {
+    fn fresh(&self, code: &str, now: u64) -> Option<Option<PlayerRank>> {
+        let entry = self.entries.get(code)?;
+        let ttl = if entry.rank.is_some() { RANK_TTL_MS } else { RANK_MISS_TTL_MS };
+        (now.saturating_sub(entry.fetched_at_ms) < ttl).then(|| entry.rank.clone())
+    }
+
+    /// Wanted codes that still need fetching.
+    fn take_wanted(&mut self, now: u64) -> Vec<String> {
+        let mut codes = std::mem::take(&mut self.wanted)
+            .into_iter()
+            .filter(|code| self.fresh(code, now).is_none())
+            .collect::<Vec<_>>();
+        codes.sort();
+        codes
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RankResponse {
+    data: Option<RankData>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RankData {
+    get_connect_code: Option<RankConnectCode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RankConnectCode {
+    user: Option<RankUser>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RankUser {
+    display_name: Option<String>,
+    ranked_netplay_profile: Option<RankProfile>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RankProfile {
+    rating_ordinal: Option<f64>,
+    rating_update_count: Option<u32>,
+    wins: Option<u32>,
+    losses: Option<u32>,
+    daily_global_placement: Option<u32>,
+    daily_regional_placement: Option<u32>,
+    continent: Option<String>,
+}
+
+pub fn rank_name(rating: f64, rated_games: u32, global_placement: Option<u32>, regional_placement: Option<u32>) -> String {
+    if rated_games < MIN_RATED_GAMES {
+        return "Pending".to_string();
+    }
+    if rating >= 2191.75 && (global_placement.is_some() || regional_placement.is_some()) {
+        return "Grandmaster".to_string();
+    }
+    RANK_THRESHOLDS
+        .iter()
+        .find(|(upper, _)| rating < *upper)
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| "Master 3".to_string())
+}
+
+fn fetch_player_rank(code: &str) -> Result<Option<PlayerRank>, String> {
+    if offline_mode_enabled() {
+        return Err(offline_error("Slippi rank"));
+    }
+    let client = client_builder()?
+        .timeout(Duration::from_secs(8))
+        .build()
+        .map_err(|e| format!("build Slippi client: {e}"))?;
+    let resp = client
+        .post(SLIPPI_GQL_URL)
+        .header("User-Agent", "new-melee-stream-tool")
+        .json(&json!({
+            "operationName": "UserRankQuery",
+            "query": SLIPPI_RANK_QUERY,
+            "variables": { "cc": code },
+        }))
+        .send()
+        .map_err(|e| {
+            let err = format!("Slippi rank request failed: {e}");
+            record_request_result(Err(err.as_str()));
+            err
+        })?;
+    record_request_result(Ok(()));
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("Slippi rank error {status}"));
+    }
+    let body: RankResponse = resp
+        .json()
+        .map_err(|e| format!("Slippi rank parse failed: {e}"))?;
+    let Some(user) = body
+        .data
+        .and_then(|data| data.get_connect_code)
+        .and_then(|cc| cc.user)
+    else {
+        return Ok(None);
+    };
+    let Some(profile) = user.ranked_netplay_profile else {
+        return Ok(None);
+    };
+    let rating = profile.rating_ordinal.unwrap_or(0.0);
+    let rated_games = profile.rating_update_count.unwrap_or(0);
+    Ok(Some(PlayerRank {
+        connect_code: code.to_string(),
+        display_name: user.display_name,
+        rating,
+        rank: rank_name(
+            rating,
+            rated_games,
+            profile.daily_global_placement,
+            profile.daily_regional_placement,
+        ),
+        wins: profile.wins.unwrap_or(0),
+        losses: profile.losses.unwrap_or(0),
+        global_placement: profile.daily_global_placement,
+        regional_placement: profile.daily_regional_placement,
+        continent: profile.continent,
+        fetched_at_ms: now_ms(),
+    }))
+}
+
+/// Fetch a rank, going through the cache. Blocks on a cache miss.
+pub fn lookup_player_rank(cache: &SharedRankCache, code: &str, force: bool) -> Result<Option<PlayerRank>, String> {
+    let code = normalize_slippi_code(code).ok_or_else(|| "Connect code is empty.".to_string())?;
+    if !force {
+        let guard = cache.lock().map_err(|e| e.to_string())?;
+        if let Some(rank) = guard.fresh(&code, now_ms()) {
+            return Ok(rank);
+        }
+    }
+    let result = fetch_player_rank(&code);
+    let mut guard = cache.lock().map_err(|e| e.to_string())?;
+    match result {
+        Ok(rank) => {
+            guard.entries.insert(code, CachedRank { rank: rank.clone(), fetched_at_ms: now_ms() });
+            Ok(rank)
+        }
+        Err(e) => {
+            guard.entries.insert(code, CachedRank { rank: None, fetched_at_ms: now_ms() });
+            Err(e)
+        }
+    }
+}
+
+/// The last rank fetched for `code`, queuing a refresh when it's missing or
+/// stale. Never touches the network, so it's safe to call from overlay
+/// requests.
+fn cached_rank(cache: &SharedRankCache, code: &str) -> Option<PlayerRank> {
+    let code = normalize_slippi_code(code)?;
+    let mut guard = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.fresh(&code, now_ms()).is_none() {
+        guard.wanted.insert(code.clone());
+    }
+    guard.entries.get(&code).and_then(|entry| entry.rank.clone())
+}
+
+fn apply_player_rank(player: &mut PlayerState, cache: &SharedRankCache) {
+    player.rank = player
+        .connect_code
+        .as_deref()
+        .and_then(|code| cached_rank(cache, code));
+}
+
+/// Attach ranks to every player with a known connect code.
+pub fn apply_overlay_ranks(state: &mut AllSetupsState, cache: &SharedRankCache) {
+    for setup in state.setups.iter_mut() {
+        apply_player_rank(&mut setup.p1, cache);
+        apply_player_rank(&mut setup.p2, cache);
+    }
+}
+
+/// Fetch the ranks the overlays asked for in the background, one at a time,
+/// so overlay requests only ever read the cache.
+pub fn spawn_rank_refresher(cache: SharedRankCache) {
+    std::thread::spawn(move || loop {
+        sleep(REFRESH_INTERVAL);
+        let codes = cache.lock().unwrap_or_else(|e| e.into_inner()).take_wanted(now_ms());
+        for code in codes {
+            if let Err(e) = lookup_player_rank(&cache, &code, true) {
+                tracing::debug!("Slippi rank lookup for {} failed: {}", code, e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn get_player_rank(
+    code: String,
+    force_refresh: Option<bool>,
+    rank_cache: State<'_, SharedRankCache>,
+) -> Result<Option<PlayerRank>, String> {
+    lookup_player_rank(&rank_cache, &code, force_refresh.unwrap_or(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn ranks_change_at_each_threshold() {
+        assert_eq!(rank_name(765.42, 5, None, None), "Bronze 1");
+        assert_eq!(rank_name(765.43, 5, None, None), "Bronze 2");
+        assert_eq!(rank_name(2003.92, 5, None, None), "Diamond 1");
+        assert_eq!(rank_name(2349.99, 5, None, None), "Master 2");
+        assert_eq!(rank_name(2350.0, 5, None, None), "Master 3");
+        assert_eq!(rank_name(0.0, 5, None, None), "Bronze 1");
+    }
+
+    #[test]
+    fn too_few_games_are_pending_and_grandmaster_needs_a_placement() {
+        assert_eq!(rank_name(2400.0, MIN_RATED_GAMES - 1, Some(1), None), "Pending");
+        assert_eq!(rank_name(2191.75, MIN_RATED_GAMES, None, Some(20)), "Grandmaster");
+        assert_eq!(rank_name(2191.74, MIN_RATED_GAMES, Some(20), None), "Diamond 3");
+        assert_eq!(rank_name(2191.75, MIN_RATED_GAMES, None, None), "Master 1");
+    }
+
+    #[test]
+    fn overlays_queue_missing_ranks_for_the_refresher() {
+        let cache: SharedRankCache = Arc::new(Mutex::new(RankCache::default()));
+        assert!(cached_rank(&cache, " mang#001 ").is_none());
+        assert!(cached_rank(&cache, "").is_none());
+
+        let mut guard = cache.lock().unwrap();
+        let now = now_ms();
+        assert_eq!(guard.take_wanted(now), vec!["MANG#001"]);
+        assert!(guard.take_wanted(now).is_empty());
+
+        // A code with no rank is only asked about again once the miss expires.
+        guard.entries.insert("MANG#001".to_string(), CachedRank { rank: None, fetched_at_ms: now });
+        drop(guard);
+        assert!(cached_rank(&cache, "MANG#001").is_none());
+        let mut guard = cache.lock().unwrap();
+        assert!(guard.take_wanted(now).is_empty());
+        guard.wanted.insert("MANG#001".to_string());
+        assert_eq!(guard.take_wanted(now + RANK_MISS_TTL_MS), vec!["MANG#001"]);
+    }
+}
@@ -0,0 +1,557 @@
+use crate::config::*;
+use crate::dolphin::launch_dolphin_playback_for_setup_internal;
+use crate::feature_flags::require_feature;
+use crate::realtime_playback::{start_follow, SLP_HEADER};
+use crate::slippi::{slippi_devtools_port, CdpSession};
+use crate::stream_identity::stable_stream_id;
+use crate::types::*;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{Seek, SeekFrom, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Manager};
+use tungstenite::{
+    client::IntoClientRequest, client_tls, http::HeaderValue, stream::MaybeTlsStream, Message, WebSocket,
+};
+
+// Talks to Slippi's spectate server the way the launcher does, so streams
+// can be listed and watched without driving the launcher's UI over CDP.
+// The launcher still has to be running and logged in: its Firebase session
+// is what the server authenticates.
+
+/// Source of streams listed straight from the spectate server.
+pub const SLIPPI_WS_SOURCE: &str = "slippi ws";
+
+const SPECTATE_PROTOCOL: &str = "spectate-protocol";
+const API_VERSION: &str = "2";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Socket read timeout; watchers check their stop flag this often.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+const LIST_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const MAX_RECONNECTS: u32 = 5;
+const MONITOR_INTERVAL: Duration = Duration::from_secs(1);
+/// Refresh the launcher's token this long before it expires.
+const TOKEN_MARGIN_MS: u64 = 60_000;
+
+/// Read the launcher's Firebase session out of its IndexedDB.
+const TOKEN_EXPR: &str = r#"
+  new Promise((resolve) => {
+    const open = indexedDB.open('firebaseLocalStorageDb');
+    open.onerror = () => resolve(null);
+    open.onsuccess = () => {
+      try {
+        const all = open.result.transaction('firebaseLocalStorage', 'readonly').objectStore('firebaseLocalStorage').getAll();
+        all.onsuccess = () => {
+          const user = (all.result || []).map(row => row.value).find(v => v && v.stsTokenManager);
+          resolve(user ? { token: user.stsTokenManager.accessToken, expiresAt: user.stsTokenManager.expirationTime } : null);
+        };
+        all.onerror = () => resolve(null);
+      } catch (e) {
+        resolve(null);
+      }
+    };
+  })
+"#;
+
+type Socket = WebSocket<MaybeTlsStream<TcpStream>>;
+
+struct CachedToken {
+    token: String,
+    expires_at_ms: u64,
+}
+
+static TOKEN: Mutex<Option<CachedToken>> = Mutex::new(None);
+
+/// Streams being watched natively. Ones with a setup get a playback Dolphin
+/// following their newest game.
+static WATCHES: Mutex<Vec<Watch>> = Mutex::new(Vec::new());
+
+struct Watch {
+    broadcast_id: String,
+    setup_id: Option<u32>,
+    stop: Arc<AtomicBool>,
+    /// Replay of the game being written, once one has started
+    replay: Arc<Mutex<Option<PathBuf>>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Broadcaster {
+    pub name: String,
+    pub connect_code: Option<String>,
+}
+
+/// One entry of the server's `list-broadcasts-resp`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Broadcast {
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub broadcaster: Broadcaster,
+}
+
+pub fn native_spectate_enabled() -> bool {
+    cached_config().is_ok_and(|config| config.slippi_native_spectate && !config.slippi_ws_url.trim().is_empty())
+}
+
+fn launcher_token() -> Result<String, String> {
+    let mut cached = TOKEN.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(token) = cached.as_ref().filter(|token| token.expires_at_ms > now_ms() + TOKEN_MARGIN_MS) {
+        return Ok(token.token.clone());
+    }
+    let value = CdpSession::connect(slippi_devtools_port())?.eval_read(TOKEN_EXPR)?;
+    let token = value
+        .get("token")
+        .and_then(Value::as_str)
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| "the Slippi Launcher isn't logged in".to_string())?
+        .to_string();
+    let expires_at_ms = value.get("expiresAt").and_then(Value::as_f64).unwrap_or(0.0) as u64;
+    *cached = Some(CachedToken { token: token.clone(), expires_at_ms });
+    Ok(token)
+}
+
+fn connect(url: &str) -> Result<Socket, String> {
+    let token = launcher_token()?;
+    let mut request = url.into_client_request().map_err(|e| format!("Slippi WS URL {url}: {e}"))?;
+    let headers = request.headers_mut();
+    headers.insert(
+        "authorization",
+        HeaderValue::from_str(&format!("Bearer {token}")).map_err(|e| e.to_string())?,
+    );
+    headers.insert("api-version", HeaderValue::from_static(API_VERSION));
+    headers.insert("sec-websocket-protocol", HeaderValue::from_static(SPECTATE_PROTOCOL));
+    let host = request.uri().host().unwrap_or_default().to_string();
+    let default_port = if request.uri().scheme_str() == Some("wss") { 443 } else { 80 };
+    let port = request.uri().port_u16().unwrap_or(default_port);
+    let addr = (host.as_str(), port)
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve {host}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("resolve {host}: no address"))?;
+    let stream = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT).map_err(|e| format!("connect to {url}: {e}"))?;
+    stream.set_read_timeout(Some(READ_TIMEOUT)).map_err(|e| e.to_string())?;
+    let (socket, _) = client_tls(request, stream).map_err(|e| format!("connect to {url}: {e}"))?;
+    Ok(socket)
+}
+
+fn send(socket: &mut Socket, message: Value) -> Result<(), String> {
+    socket.send(Message::Text(message.to_string())).map_err(|e| e.to_string())
+}
+
+/// Next JSON message, or None when nothing arrived within the read timeout.
+fn read_json(socket: &mut Socket) -> Result<Option<Value>, String> {
+    match socket.read() {
+        Ok(Message::Text(text)) => Ok(serde_json::from_str(&text).ok()),
+        Ok(Message::Close(_)) => Err("closed by the server".to_string()),
+        Ok(_) => Ok(None),
+        Err(tungstenite::Error::Io(e))
+            if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn message_type(message: &Value) -> &str {
+    message.get("type").and_then(Value::as_str).unwrap_or_default()
+}
+
+fn config_url() -> Result<String, String> {
+    let config = cached_config()?;
+    if !config.slippi_native_spectate {
+        return Err("Native spectating is off.".to_string());
+    }
+    Ok(config.slippi_ws_url.trim().to_string())
+}
+
+pub fn list_broadcasts() -> Result<Vec<Broadcast>, String> {
+    let url = config_url()?;
+    let mut socket = connect(&url)?;
+    send(&mut socket, json!({ "type": "list-broadcasts" }))?;
+    let deadline = Instant::now() + LIST_TIMEOUT;
+    while Instant::now() < deadline {
+        let Some(message) = read_json(&mut socket)? else {
+            continue;
+        };
+        if message_type(&message) == "list-broadcasts-resp" {
+            let _ = socket.close(None);
+            let broadcasts = message.get("broadcasts").cloned().unwrap_or_else(|| json!([]));
+            return serde_json::from_value(broadcasts).map_err(|e| format!("parse broadcast list: {e}"));
+        }
+    }
+    Err("The Slippi spectate server didn't send its broadcast list.".to_string())
+}
+
+/// A broadcast as a spectate card. Ids come from connect codes, like
+/// cards scraped from the launcher, so assignments survive switching
+/// between the two.
+fn broadcast_stream(broadcast: &Broadcast) -> SlippiStream {
+    let code = broadcast.broadcaster.connect_code.clone().filter(|code| !code.trim().is_empty());
+    let name = Some(&broadcast.broadcaster.name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or(&broadcast.name);
+    SlippiStream {
+        id: stable_stream_id(code.as_slice()).unwrap_or_else(|| format!("broadcast-{}", broadcast.id)),
+        p1_tag: Some(name.clone()).filter(|name| !name.is_empty()),
+        p1_code: code,
+        source: Some(SLIPPI_WS_SOURCE.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Spectate cards straight from the server, for `scan_slippi_streams`.
+pub fn scan_streams() -> Result<Vec<SlippiStream>, String> {
+    Ok(list_broadcasts()?.iter().map(broadcast_stream).collect())
+}
+
+/// The broadcast a card was for: by connect code, else by name.
+fn find_broadcast<'a>(broadcasts: &'a [Broadcast], code: Option<&str>, tag: Option<&str>) -> Option<&'a Broadcast> {
+    let code = code.and_then(normalize_slippi_code);
+    broadcasts
+        .iter()
+        .find(|broadcast| {
+            code.is_some() && broadcast.broadcaster.connect_code.as_deref().and_then(normalize_slippi_code) == code
+        })
+        .or_else(|| {
+            let tag = tag?.trim();
+            broadcasts
+                .iter()
+                .find(|broadcast| broadcast.broadcaster.name.eq_ignore_ascii_case(tag) || broadcast.name.eq_ignore_ascii_case(tag))
+        })
+}
+
+// ── Replay files ────────────────────────────────────────────────────────
+
+/// Writes a broadcast game as a .slp, the way the launcher would in the
+/// spectate folder. The raw length stays 0 until the game ends, which is
+/// what real-time playback expects of a replay still being written.
+struct SlpWriter {
+    path: PathBuf,
+    file: File,
+    raw_len: u32,
+}
+
+impl SlpWriter {
+    fn create(dir: &Path) -> Result<Self, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("create spectate folder {}: {e}", dir.display()))?;
+        let stamp = chrono::Local::now().format("%Y%m%dT%H%M%S");
+        let mut path = dir.join(format!("Game_{stamp}.slp"));
+        let mut suffix = 1;
+        while path.exists() {
+            suffix += 1;
+            path = dir.join(format!("Game_{stamp}_{suffix}.slp"));
+        }
+        let mut file = File::create(&path).map_err(|e| format!("create {}: {e}", path.display()))?;
+        file.write_all(SLP_HEADER)
+            .and_then(|_| file.write_all(&[0; 4]))
+            .map_err(|e| format!("write {}: {e}", path.display()))?;
+        Ok(SlpWriter { path, file, raw_len: 0 })
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.file
+            .write_all(bytes)
+            .map_err(|e| format!("write {}: {e}", self.path.display()))?;
+        self.raw_len = self.raw_len.saturating_add(bytes.len() as u32);
+        Ok(())
+    }
+
+    /// Close the raw array with empty metadata and fill in its length.
+    fn finish(mut self) -> Result<PathBuf, String> {
+        let header_len = SLP_HEADER.len() as u64;
+        self.file
+            .write_all(b"U\x08metadata{}}")
+            .and_then(|_| self.file.seek(SeekFrom::Start(header_len)))
+            .and_then(|_| self.file.write_all(&self.raw_len.to_be_bytes()))
+            .map_err(|e| format!("finish {}: {e}", self.path.display()))?;
+        Ok(self.path)
+    }
+}
+
+/// Where one watch is in its broadcast.
+struct WatchWriter {
+    dir: PathBuf,
+    writer: Option<SlpWriter>,
+    replay: Arc<Mutex<Option<PathBuf>>>,
+}
+
+impl WatchWriter {
+    fn start_game(&mut self) -> Result<(), String> {
+        self.end_game()?;
+        let writer = SlpWriter::create(&self.dir)?;
+        *self.replay.lock().unwrap_or_else(|e| e.into_inner()) = Some(writer.path.clone());
+        self.writer = Some(writer);
+        Ok(())
+    }
+
+    fn end_game(&mut self) -> Result<(), String> {
+        match self.writer.take() {
+            Some(writer) => writer.finish().map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    /// Apply one entry of an `events` message.
+    fn apply(&mut self, event: &Value) -> Result<(), String> {
+        match message_type(event) {
+            "start_game" => self.start_game(),
+            "game_event" => {
+                let payload = event.get("payload").and_then(Value::as_str).unwrap_or_default();
+                let bytes = STANDARD.decode(payload).map_err(|e| format!("decode game event: {e}"))?;
+                // Joined mid-game: the server's backlog still starts with
+                // the game's first events.
+                if self.writer.is_none() {
+                    self.start_game()?;
+                }
+                self.writer.as_mut().map_or(Ok(()), |writer| writer.write(&bytes))
+            }
+            "end_game" => self.end_game(),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn watch_request(broadcast_id: &str) -> Value {
+    json!({ "type": "watch-broadcast", "broadcastId": broadcast_id })
+}
+
+/// Read a broadcast until it ends or the watch is stopped. A dropped
+/// connection finishes the current file; the next game starts a new one.
+fn run_watch(mut socket: Socket, url: String, broadcast_id: String, mut games: WatchWriter, stop: Arc<AtomicBool>) {
+    let mut reconnects = 0;
+    while !stop.load(Ordering::SeqCst) {
+        let message = match read_json(&mut socket) {
+            Ok(Some(message)) => message,
+            Ok(None) => continue,
+            Err(err) => {
+                if let Err(err) = games.end_game() {
+                    tracing::warn!("Slippi broadcast {}: {}", broadcast_id, err);
+                }
+                if reconnects >= MAX_RECONNECTS {
+                    tracing::warn!("Slippi broadcast {} lost: {}", broadcast_id, err);
+                    break;
+                }
+                reconnects += 1;
+                sleep(RECONNECT_DELAY);
+                match connect(&url).and_then(|mut fresh| send(&mut fresh, watch_request(&broadcast_id)).map(|_| fresh)) {
+                    Ok(fresh) => socket = fresh,
+                    Err(err) => tracing::debug!("Reconnect to Slippi broadcast {}: {}", broadcast_id, err),
+                }
+                continue;
+            }
+        };
+        reconnects = 0;
+        match message_type(&message) {
+            "events" => {
+                for event in message.get("events").and_then(Value::as_array).into_iter().flatten() {
+                    if let Err(err) = games.apply(event) {
+                        tracing::warn!("Slippi broadcast {}: {}", broadcast_id, err);
+                    }
+                }
+            }
+            "end-broadcast" | "end_broadcast" => break,
+            _ => {}
+        }
+    }
+    if let Err(err) = games.end_game() {
+        tracing::warn!("Slippi broadcast {}: {}", broadcast_id, err);
+    }
+    let _ = socket.close(None);
+    WATCHES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .retain(|watch| !Arc::ptr_eq(&watch.stop, &stop));
+    tracing::info!("Stopped watching Slippi broadcast {}", broadcast_id);
+}
+
+/// Stop the native watch feeding `setup_id`, if there is one.
+pub fn stop_setup_watch(setup_id: u32) {
+    WATCHES.lock().unwrap_or_else(|e| e.into_inner()).retain(|watch| {
+        let matches = watch.setup_id == Some(setup_id);
+        if matches {
+            watch.stop.store(true, Ordering::SeqCst);
+        }
+        !matches
+    });
+}
+
+/// Watch a player's broadcast straight from the spectate server, writing
+/// its games to the spectate folder. With a setup, the setup's playback
+/// Dolphin follows each game as it's written. Errors before anything
+/// starts, so callers can fall back to the launcher.
+pub fn watch_natively(code: Option<&str>, tag: Option<&str>, setup_id: Option<u32>) -> Result<(), String> {
+    require_feature(FeatureFlag::AutoSpectate)?;
+    let url = config_url()?;
+    let spectate = cached_config()?.spectate_folder_path.trim().to_string();
+    if spectate.is_empty() {
+        return Err("Set the spectate folder to spectate without the launcher.".to_string());
+    }
+    let broadcasts = list_broadcasts()?;
+    let broadcast = find_broadcast(&broadcasts, code, tag)
+        .ok_or_else(|| format!("No Slippi broadcast for {}.", code.or(tag).unwrap_or("that stream")))?;
+    let mut socket = connect(&url)?;
+    send(&mut socket, watch_request(&broadcast.id))?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let replay = Arc::new(Mutex::new(None));
+    {
+        let mut watches = WATCHES.lock().unwrap_or_else(|e| e.into_inner());
+        watches.retain(|watch| {
+            let replaced = match setup_id {
+                Some(_) => watch.setup_id == setup_id,
+                None => watch.setup_id.is_none() && watch.broadcast_id == broadcast.id,
+            };
+            if replaced {
+                watch.stop.store(true, Ordering::SeqCst);
+            }
+            !replaced
+        });
+        watches.push(Watch {
+            broadcast_id: broadcast.id.clone(),
+            setup_id,
+            stop: stop.clone(),
+            replay: replay.clone(),
+        });
+    }
+    let games = WatchWriter { dir: resolve_repo_path(&spectate), writer: None, replay };
+    let broadcast_id = broadcast.id.clone();
+    tracing::info!("Watching Slippi broadcast {} natively", broadcast_id);
+    std::thread::spawn(move || run_watch(socket, url, broadcast_id, games, stop));
+    Ok(())
+}
+
+/// Point a setup's playback Dolphin at `path`, starting one if needed.
+fn follow_on_setup(app: &AppHandle, setup_id: u32, path: &Path) -> Result<(), String> {
+    let store = app.state::<SharedSetupStore>();
+    let alive = {
+        let mut guard = store.lock().map_err(|e| e.to_string())?;
+        let running = guard
+            .processes
+            .get_mut(&setup_id)
+            .is_some_and(|child| matches!(child.try_wait(), Ok(None)));
+        running && guard.playback_sessions.contains_key(&setup_id)
+    };
+    if !alive {
+        let (child, session) = launch_dolphin_playback_for_setup_internal(setup_id, path)?;
+        let mut guard = store.lock().map_err(|e| e.to_string())?;
+        guard.processes.insert(setup_id, child);
+        guard.playback_sessions.insert(setup_id, session);
+    }
+    start_follow(app, setup_id, path.to_path_buf())
+}
+
+/// Keep each natively watched setup's playback on its broadcast's newest
+/// game.
+pub fn spawn_native_spectate_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut following: HashMap<u32, PathBuf> = HashMap::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let targets = WATCHES
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .filter_map(|watch| {
+                    let replay = watch.replay.lock().unwrap_or_else(|e| e.into_inner()).clone()?;
+                    Some((watch.setup_id?, replay))
+                })
+                .collect::<Vec<_>>();
+            following.retain(|setup_id, _| targets.iter().any(|(id, _)| id == setup_id));
+            for (setup_id, replay) in targets {
+                if following.get(&setup_id) == Some(&replay) {
+                    continue;
+                }
+                match follow_on_setup(&app, setup_id, &replay) {
+                    Ok(()) => {
+                        following.insert(setup_id, replay);
+                    }
+                    Err(err) => tracing::warn!("Native spectate for setup {}: {}", setup_id, err),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn broadcasts() -> Vec<Broadcast> {
+        serde_json::from_value(json!([
+            { "id": "b-1", "name": "Zain", "broadcaster": { "uid": "u1", "name": "Zain", "connectCode": "ZAIN#0" } },
+            { "id": "b-2", "name": "Friday Netplay" },
+        ]))
+        .unwrap()
+    }
+
+    fn watch(name: &str) -> WatchWriter {
+        let dir = std::env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        WatchWriter { dir, writer: None, replay: Arc::new(Mutex::new(None)) }
+    }
+
+    fn event(payload: &[u8]) -> Value {
+        json!({ "type": "game_event", "payload": STANDARD.encode(payload) })
+    }
+
+    #[test]
+    fn a_broadcaster_card_is_keyed_like_a_launcher_card() {
+        let card = broadcast_stream(&broadcasts()[0]);
+        assert_eq!(Some(card.id.clone()), stable_stream_id(&["ZAIN#0"]));
+        assert_eq!(card.p1_tag.as_deref(), Some("Zain"));
+    }
+
+    #[test]
+    fn a_broadcast_without_a_broadcaster_is_keyed_by_its_id() {
+        assert_eq!(broadcast_stream(&broadcasts()[1]).id, "broadcast-b-2");
+    }
+
+    #[test]
+    fn broadcasts_are_found_by_code_or_name() {
+        let broadcasts = broadcasts();
+        assert_eq!(find_broadcast(&broadcasts, Some("zain#0"), None).map(|b| b.id.as_str()), Some("b-1"));
+        assert_eq!(find_broadcast(&broadcasts, None, Some("friday netplay")).map(|b| b.id.as_str()), Some("b-2"));
+        assert!(find_broadcast(&broadcasts, Some("NOPE#1"), None).is_none());
+    }
+
+    #[test]
+    fn a_watched_game_is_written_as_a_replay() {
+        let mut games = watch("slippi-ws-game");
+        games.apply(&json!({ "type": "start_game" })).unwrap();
+        games.apply(&event(&[0x36, 1, 2])).unwrap();
+        games.apply(&json!({ "type": "end_game" })).unwrap();
+        let path = games.replay.lock().unwrap().clone().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert!(bytes.starts_with(SLP_HEADER));
+        assert_eq!(&bytes[SLP_HEADER.len()..SLP_HEADER.len() + 4], &3u32.to_be_bytes());
+        assert_eq!(&bytes[SLP_HEADER.len() + 4..SLP_HEADER.len() + 7], &[0x36, 1, 2]);
+        assert!(bytes.ends_with(b"metadata{}}"));
+        let _ = fs::remove_dir_all(&games.dir);
+    }
+
+    #[test]
+    fn joining_mid_game_starts_a_replay() {
+        let mut games = watch("slippi-ws-joined");
+        games.apply(&event(&[0x36, 1, 2])).unwrap();
+        assert!(games.writer.is_some());
+        assert!(games.replay.lock().unwrap().is_some());
+        games.end_game().unwrap();
+        let _ = fs::remove_dir_all(&games.dir);
+    }
+}
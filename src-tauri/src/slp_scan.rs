@@ -0,0 +1,237 @@
+use crate::config::*;
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::UNIX_EPOCH,
+};
+
+const DEFAULT_SCAN_DEPTH: usize = 3;
+/// Written next to extracted replays; holds the zip's size and mtime so a
+/// pack is only unpacked again when it changes.
+const EXTRACTED_MARKER: &str = ".extracted";
+
+/// The indexer and test mode can reach the same pack at once.
+static EXTRACT_LOCK: Mutex<()> = Mutex::new(());
+
+/// How far replay scans reach below the folder they're given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlpScanOptions {
+    /// Subdirectory levels to descend; 0 scans only the folder itself
+    pub max_depth: usize,
+    /// Read replays out of `.zip` packs
+    pub include_zips: bool,
+}
+
+impl Default for SlpScanOptions {
+    fn default() -> Self {
+        SlpScanOptions {
+            max_depth: DEFAULT_SCAN_DEPTH,
+            include_zips: true,
+        }
+    }
+}
+
+impl SlpScanOptions {
+    /// `SLP_SCAN_DEPTH` and `SLP_SCAN_ZIPS` override the defaults.
+    pub fn from_env() -> Self {
+        let defaults = SlpScanOptions::default();
+        SlpScanOptions {
+            max_depth: env_default("SLP_SCAN_DEPTH")
+                .and_then(|raw| raw.parse::<usize>().ok())
+                .unwrap_or(defaults.max_depth),
+            include_zips: env_flag_true_default("SLP_SCAN_ZIPS", defaults.include_zips),
+        }
+    }
+}
+
+fn has_extension(path: &Path, exts: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| exts.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+fn is_slp_path(path: &Path) -> bool {
+    has_extension(path, &["slp", "slippi"])
+}
+
+fn is_zip_path(path: &Path) -> bool {
+    has_extension(path, &["zip"])
+}
+
+/// Replays under `dir`, sorted. Hidden folders are skipped, and replays in
+/// zip packs are returned as their extracted copies.
+pub fn collect_slp_files_with(dir: &Path, options: &SlpScanOptions) -> Result<Vec<PathBuf>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![(dir.to_path_buf(), 0usize)];
+    while let Some((current, depth)) = pending.pop() {
+        let entries = fs::read_dir(&current).map_err(|e| format!("read dir {}: {e}", current.display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| format!("read dir entry {}: {e}", current.display()))?;
+            let path = entry.path();
+            if path.is_dir() {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if depth < options.max_depth && !hidden {
+                    pending.push((path, depth + 1));
+                }
+            } else if is_slp_path(&path) {
+                files.push(path);
+            } else if options.include_zips && is_zip_path(&path) {
+                match extract_zip_replays(&path) {
+                    Ok(extracted) => files.extend(extracted),
+                    // One bad pack shouldn't hide every other replay.
+                    Err(err) => tracing::warn!("Skipping replay pack {}: {}", path.display(), err),
+                }
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn zip_cache_dir(zip_path: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    zip_path.hash(&mut hasher);
+    let stem = zip_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "pack".to_string());
+    repo_root()
+        .join("airlock")
+        .join("zip_cache")
+        .join(format!("{stem}-{:016x}", hasher.finish()))
+}
+
+fn zip_fingerprint(zip_path: &Path) -> Result<String, String> {
+    let meta = fs::metadata(zip_path).map_err(|e| format!("read metadata {}: {e}", zip_path.display()))?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|since| since.as_millis())
+        .unwrap_or_default();
+    Ok(format!("{} {modified}", meta.len()))
+}
+
+/// Unpack the replays in a zip to the cache (once per version of the zip)
+/// and return their extracted paths. Entry paths inside the zip are kept,
+/// so `sets/game1.slp` stays distinct from `pools/game1.slp`.
+pub fn extract_zip_replays(zip_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let _guard = EXTRACT_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let out_dir = zip_cache_dir(zip_path);
+    let marker = out_dir.join(EXTRACTED_MARKER);
+    let fingerprint = zip_fingerprint(zip_path)?;
+    if fs::read_to_string(&marker).is_ok_and(|existing| existing == fingerprint) {
+        let options = SlpScanOptions { max_depth: usize::MAX, include_zips: false };
+        return collect_slp_files_with(&out_dir, &options);
+    }
+
+    let file = File::open(zip_path).map_err(|e| format!("open {}: {e}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("read zip {}: {e}", zip_path.display()))?;
+    if out_dir.exists() {
+        fs::remove_dir_all(&out_dir).map_err(|e| format!("clear {}: {e}", out_dir.display()))?;
+    }
+    let mut out = Vec::new();
+    for idx in 0..archive.len() {
+        let mut entry = archive
+            .by_index(idx)
+            .map_err(|e| format!("read zip entry {idx} of {}: {e}", zip_path.display()))?;
+        // enclosed_name rejects absolute paths and `..` escapes.
+        let Some(name) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir() || !is_slp_path(&name) {
+            continue;
+        }
+        let dest = out_dir.join(&name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("extract {} from {}: {e}", name.display(), zip_path.display()))?;
+        write_file_atomic(&dest, &bytes).map_err(|e| format!("write {}: {e}", dest.display()))?;
+        out.push(dest);
+    }
+    fs::create_dir_all(&out_dir).map_err(|e| format!("create {}: {e}", out_dir.display()))?;
+    write_file_atomic(&marker, fingerprint.as_bytes()).map_err(|e| format!("write {}: {e}", marker.display()))?;
+    out.sort();
+    Ok(out)
+}
+
+/// Paths like `packs/top8.zip/game1.slp` point into a zip. Returns the
+/// extracted copy of that member; any other path comes back unchanged.
+pub fn resolve_zip_member(path: &Path) -> PathBuf {
+    if path.exists() {
+        return path.to_path_buf();
+    }
+    let zip_path = path.ancestors().skip(1).find(|ancestor| is_zip_path(ancestor) && ancestor.is_file());
+    let Some(zip_path) = zip_path else {
+        return path.to_path_buf();
+    };
+    let Ok(member) = path.strip_prefix(zip_path) else {
+        return path.to_path_buf();
+    };
+    match extract_zip_replays(zip_path) {
+        Ok(_) => zip_cache_dir(zip_path).join(member),
+        Err(err) => {
+            tracing::warn!("Could not read {} from {}: {}", member.display(), zip_path.display(), err);
+            path.to_path_buf()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A replay folder under a fresh temp dir, with a replay at each depth,
+    /// one in a hidden folder and a file that isn't a replay.
+    fn replay_folder(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("slp-scan-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for dir in ["a/b", ".hidden"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        for file in ["top.slp", "a/one.SLP", "a/b/two.slp", ".hidden/three.slp", "a/notes.txt"] {
+            fs::write(root.join(file), b"").unwrap();
+        }
+        root
+    }
+
+    fn names(root: &Path, options: SlpScanOptions) -> Vec<String> {
+        collect_slp_files_with(root, &options)
+            .unwrap()
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_string_lossy().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn depth_zero_scans_only_the_folder_itself() {
+        let root = replay_folder("depth");
+        assert_eq!(names(&root, SlpScanOptions { max_depth: 0, include_zips: false }), vec!["top.slp"]);
+        assert_eq!(names(&root, SlpScanOptions { max_depth: 1, include_zips: false }), vec!["a/one.SLP", "top.slp"]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn default_scan_finds_nested_replays_sorted_and_skips_the_rest() {
+        let root = replay_folder("default");
+        assert_eq!(names(&root, SlpScanOptions::default()), vec!["a/b/two.slp", "a/one.SLP", "top.slp"]);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn paths_outside_a_zip_resolve_to_themselves() {
+        let root = replay_folder("resolve");
+        let replay = root.join("top.slp");
+        assert_eq!(resolve_zip_member(&replay), replay);
+        assert_eq!(resolve_zip_member(&root.join("gone.slp")), root.join("gone.slp"));
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::config::*;
+use crate::realtime_playback::{frame_end_offsets, raw_section_end, SLP_HEADER_LEN};
+use crate::startgg_sim::SimRng;
+use std::{
+    env,
+    fs::{self, File},
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const FRAME_MS: f64 = 1000.0 / 60.0;
+/// Offset of the raw section length in the replay header.
+const RAW_LEN_OFFSET: usize = SLP_HEADER_LEN - 4;
+
+/// How a spoofed replay reaches the spectate folder. Real spectating trails
+/// the game by seconds and the file grows in bursts, not one frame at a time.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyProfile {
+    /// Delay between a frame being played and it reaching the file
+    pub latency_ms: u64,
+    /// Extra random delay per write, up to this much
+    pub jitter_ms: u64,
+    /// Time between writes; 0 writes every frame
+    pub burst_ms: u64,
+}
+
+fn env_ms(key: &str) -> u64 {
+    env::var(key)
+        .ok()
+        .and_then(|raw| raw.trim().parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+impl LatencyProfile {
+    /// `SPOOF_LATENCY_MS`, `SPOOF_JITTER_MS` and `SPOOF_BURST_MS`.
+    pub fn from_env() -> Self {
+        LatencyProfile {
+            latency_ms: env_ms("SPOOF_LATENCY_MS"),
+            jitter_ms: env_ms("SPOOF_JITTER_MS"),
+            burst_ms: env_ms("SPOOF_BURST_MS"),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.latency_ms > 0 || self.jitter_ms > 0 || self.burst_ms > 0
+    }
+}
+
+/// When to write and how far: `(ms after start, file offset to have written)`.
+/// Offsets never go backwards and the last entry covers the whole raw section.
+pub fn burst_schedule(
+    frame_ends: &[(i32, usize)],
+    raw_end: usize,
+    profile: &LatencyProfile,
+    seed: u64,
+) -> Vec<(u64, usize)> {
+    let Some(first_frame) = frame_ends.first().map(|(frame, _)| *frame) else {
+        return vec![(profile.latency_ms, raw_end)];
+    };
+    let frame_time = |frame: i32| ((frame - first_frame).max(0) as f64 * FRAME_MS) as u64;
+    let last_time = frame_ends.iter().map(|(frame, _)| frame_time(*frame)).max().unwrap_or(0);
+    let step = if profile.burst_ms > 0 { profile.burst_ms } else { FRAME_MS.ceil() as u64 };
+
+    let mut rng = SimRng::new(seed);
+    let mut schedule = Vec::new();
+    let mut next_frame = 0;
+    let mut written = 0;
+    let mut tick = 0u64;
+    loop {
+        tick += step;
+        let jitter = if profile.jitter_ms > 0 { rng.next_u64() % (profile.jitter_ms + 1) } else { 0 };
+        if let Some(cutoff) = tick.checked_sub(profile.latency_ms + jitter) {
+            // Frames are delivered in file order, so a rollback to an earlier
+            // frame number still waits for everything before it.
+            while next_frame < frame_ends.len() && frame_time(frame_ends[next_frame].0) <= cutoff {
+                written = frame_ends[next_frame].1;
+                next_frame += 1;
+            }
+        }
+        if next_frame == frame_ends.len() || tick > last_time + profile.latency_ms + profile.jitter_ms + step {
+            schedule.push((tick, raw_end));
+            break;
+        }
+        if written > 0 && schedule.last().map(|(_, upto)| *upto) != Some(written) {
+            schedule.push((tick, written));
+        }
+    }
+    schedule
+}
+
+/// Write `src` to `dest` the way a spectating Dolphin would: header first
+/// with a zero raw length, frames in delayed bursts, then the metadata.
+/// Returns `Ok(false)` if `cancelled` fired before the game was written.
+pub fn write_replay_with_latency(
+    src: &Path,
+    dest: &Path,
+    profile: &LatencyProfile,
+    cancelled: impl Fn() -> bool,
+) -> Result<bool, String> {
+    let bytes = fs::read(src).map_err(|e| format!("read replay {}: {e}", src.display()))?;
+    let raw_end = raw_section_end(&bytes).map_err(|e| format!("{}: {e}", src.display()))?;
+    let frame_ends = frame_end_offsets(&bytes).map_err(|e| format!("{}: {e}", src.display()))?;
+    let schedule = burst_schedule(&frame_ends, raw_end, profile, now_ms());
+
+    let write_err = |e: std::io::Error| format!("write {}: {e}", dest.display());
+    let mut file = File::create(dest).map_err(|e| format!("create {}: {e}", dest.display()))?;
+    file.write_all(&bytes[..RAW_LEN_OFFSET]).map_err(write_err)?;
+    file.write_all(&[0; 4]).map_err(write_err)?;
+    file.flush().map_err(write_err)?;
+
+    let started = Instant::now();
+    let mut written = SLP_HEADER_LEN;
+    for (at_ms, upto) in schedule {
+        if cancelled() {
+            return Ok(false);
+        }
+        let elapsed = started.elapsed().as_millis() as u64;
+        if at_ms > elapsed {
+            sleep(Duration::from_millis(at_ms - elapsed));
+        }
+        if upto > written {
+            file.write_all(&bytes[written..upto]).map_err(write_err)?;
+            file.flush().map_err(write_err)?;
+            written = upto;
+        }
+    }
+    file.write_all(&bytes[written..]).map_err(write_err)?;
+    file.seek(SeekFrom::Start(RAW_LEN_OFFSET as u64)).map_err(write_err)?;
+    file.write_all(&bytes[RAW_LEN_OFFSET..SLP_HEADER_LEN]).map_err(write_err)?;
+    file.flush().map_err(write_err)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 120 frames, 100 bytes each, after a 15 byte header.
+    fn frame_ends() -> Vec<(i32, usize)> {
+        (0..120).map(|i| (i - 123, 15 + (i as usize + 1) * 100)).collect()
+    }
+
+    const RAW_END: usize = 15 + 120 * 100;
+    const PROFILE: LatencyProfile = LatencyProfile { latency_ms: 1_000, jitter_ms: 200, burst_ms: 250 };
+
+    #[test]
+    fn nothing_lands_before_the_latency_has_passed() {
+        let schedule = burst_schedule(&frame_ends(), RAW_END, &PROFILE, 7);
+        assert!(schedule.first().unwrap().0 >= 1_000);
+    }
+
+    #[test]
+    fn frames_are_written_in_bursts() {
+        let schedule = burst_schedule(&frame_ends(), RAW_END, &PROFILE, 7);
+        assert!(schedule.len() < 20);
+        assert!(schedule.windows(2).all(|pair| pair[0].1 <= pair[1].1 && pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn the_last_write_covers_the_whole_raw_section() {
+        for profile in [PROFILE, LatencyProfile::default()] {
+            let schedule = burst_schedule(&frame_ends(), RAW_END, &profile, 7);
+            assert_eq!(schedule.last().unwrap().1, RAW_END);
+        }
+    }
+
+    #[test]
+    fn a_replay_without_frames_is_written_once_after_the_latency() {
+        assert_eq!(burst_schedule(&[], RAW_END, &PROFILE, 7), vec![(1_000, RAW_END)]);
+    }
+
+    #[test]
+    fn the_profile_is_off_until_something_is_set() {
+        assert!(!LatencyProfile::default().is_enabled());
+        assert!(LatencyProfile { burst_ms: 250, ..LatencyProfile::default() }.is_enabled());
+    }
+}
@@ -0,0 +1,341 @@
+use crate::audit_log::audited;
+use crate::config::*;
+use crate::replay::map_stage;
+use crate::types::{AllSetupsState, AppConfig};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use specta::Type;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// Stages legal when the event's ruleset doesn't list any, starters first.
+const DEFAULT_STAGE_IDS: [u16; 6] = [8, 2, 31, 32, 3, 28];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum StageAction {
+    Ban,
+    Pick,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StageStrike {
+    pub stage: String,
+    pub action: StageAction,
+    /// Who struck or picked, when the operator says
+    pub entrant_id: Option<u32>,
+    pub at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct GameStageStrikes {
+    pub game: u32,
+    /// In the order they were made
+    pub strikes: Vec<StageStrike>,
+    pub bans: Vec<String>,
+    pub pick: Option<String>,
+    /// Legal stages neither banned nor picked
+    pub remaining: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetStageStrikes {
+    pub set_id: u64,
+    /// Games with any strikes, first game first
+    pub games: Vec<GameStageStrikes>,
+    /// The legal stages, starters first
+    pub stages: Vec<String>,
+}
+
+/// Strikes by set id, then game number.
+type StrikeLog = BTreeMap<u64, BTreeMap<u32, Vec<StageStrike>>>;
+
+/// Loaded from disk on first use.
+static STAGE_STRIKES: Mutex<Option<StrikeLog>> = Mutex::new(None);
+
+fn stage_strikes_path() -> PathBuf {
+    repo_root().join("logs").join("stage_strikes.json")
+}
+
+fn load_stage_strikes() -> StrikeLog {
+    let path = stage_strikes_path();
+    let Ok(data) = fs::read_to_string(&path) else {
+        return StrikeLog::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|err| {
+        tracing::warn!("Ignoring unreadable {}: {}", path.display(), err);
+        StrikeLog::new()
+    })
+}
+
+fn save_stage_strikes(log: &StrikeLog) -> Result<(), String> {
+    let path = stage_strikes_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {e}", parent.display()))?;
+    }
+    let body = serde_json::to_vec_pretty(log).map_err(|e| format!("serialize stage strikes: {e}"))?;
+    write_file_atomic(&path, &body).map_err(|e| format!("write {}: {e}", path.display()))
+}
+
+/// Run `f` on the stored strikes and save them if it succeeds.
+fn with_stage_strikes<T>(f: impl FnOnce(&mut StrikeLog) -> Result<T, String>) -> Result<T, String> {
+    let mut guard = STAGE_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+    let log = guard.get_or_insert_with(load_stage_strikes);
+    let out = f(log)?;
+    save_stage_strikes(log)?;
+    Ok(out)
+}
+
+/// Move the stored strikes into `dir` and start the next event with none.
+pub fn archive_stage_strikes(dir: &Path) -> Result<(), String> {
+    let mut guard = STAGE_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+    archive_file(&stage_strikes_path(), dir)?;
+    *guard = Some(StrikeLog::new());
+    Ok(())
+}
+
+/// Undo `archive_stage_strikes`; the log is read back from disk on next use.
+pub fn restore_stage_strikes(dir: &Path) -> Result<(), String> {
+    let mut guard = STAGE_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+    restore_archived_file(&stage_strikes_path(), dir)?;
+    *guard = None;
+    Ok(())
+}
+
+/// The ruleset's stages, or the usual six when it has none.
+pub fn legal_stages(config: &AppConfig) -> Vec<String> {
+    let stages = config
+        .ruleset
+        .stages
+        .iter()
+        .map(|stage| stage.trim().to_string())
+        .filter(|stage| !stage.is_empty())
+        .collect::<Vec<_>>();
+    if !stages.is_empty() {
+        return stages;
+    }
+    DEFAULT_STAGE_IDS.iter().filter_map(|id| map_stage(*id)).map(str::to_string).collect()
+}
+
+fn game_view(game: u32, strikes: &[StageStrike], stages: &[String]) -> GameStageStrikes {
+    let bans = strikes
+        .iter()
+        .filter(|strike| strike.action == StageAction::Ban)
+        .map(|strike| strike.stage.clone())
+        .collect::<Vec<_>>();
+    let pick = strikes
+        .iter()
+        .find(|strike| strike.action == StageAction::Pick)
+        .map(|strike| strike.stage.clone());
+    let remaining = stages
+        .iter()
+        .filter(|stage| !bans.contains(*stage) && pick.as_ref() != Some(*stage))
+        .cloned()
+        .collect();
+    GameStageStrikes { game, strikes: strikes.to_vec(), bans, pick, remaining }
+}
+
+fn set_view(log: &StrikeLog, set_id: u64, stages: Vec<String>) -> SetStageStrikes {
+    let games = log
+        .get(&set_id)
+        .map(|games| games.iter().map(|(game, strikes)| game_view(*game, strikes, &stages)).collect())
+        .unwrap_or_default();
+    SetStageStrikes { set_id, games, stages }
+}
+
+/// Check a ban or pick against what's already happened in its game and
+/// add it. Stage names are matched case-insensitively and stored as the
+/// ruleset spells them.
+fn add_strike(
+    strikes: &mut Vec<StageStrike>,
+    stages: &[String],
+    game: u32,
+    stage: &str,
+    action: StageAction,
+    entrant_id: Option<u32>,
+    at_ms: u64,
+) -> Result<(), String> {
+    let stage = stages
+        .iter()
+        .find(|legal| legal.eq_ignore_ascii_case(stage.trim()))
+        .ok_or_else(|| format!("{} isn't a legal stage.", stage.trim()))?;
+    if let Some(picked) = strikes.iter().find(|strike| strike.action == StageAction::Pick) {
+        return Err(format!("Game {game} is already on {}.", picked.stage));
+    }
+    if strikes.iter().any(|strike| &strike.stage == stage) {
+        return Err(format!("{stage} is already struck for game {game}."));
+    }
+    strikes.push(StageStrike { stage: stage.clone(), action, entrant_id, at_ms });
+    Ok(())
+}
+
+/// Show the stage picked for the game being played, which is known before
+/// its replay is, over the stage read from the last replay.
+pub fn apply_stage_picks(state: &mut AllSetupsState) {
+    let mut guard = STAGE_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+    let log = guard.get_or_insert_with(load_stage_strikes);
+    if log.is_empty() {
+        return;
+    }
+    for setup in state.setups.iter_mut() {
+        let (Some(set_id), Some(game)) = (setup.meta.set_id, setup.meta.game_number) else {
+            continue;
+        };
+        let pick = log
+            .get(&set_id)
+            .and_then(|games| games.get(&game))
+            .and_then(|strikes| strikes.iter().find(|strike| strike.action == StageAction::Pick));
+        if let Some(pick) = pick {
+            setup.meta.stage = Some(pick.stage.clone());
+        }
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+#[specta::specta]
+pub fn stage_strikes(set_id: u64) -> Result<SetStageStrikes, String> {
+    let stages = legal_stages(&cached_config()?);
+    let mut guard = STAGE_STRIKES.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(set_view(guard.get_or_insert_with(load_stage_strikes), set_id, stages))
+}
+
+/// Ban or pick a stage for one game of a set. A game's pick ends its
+/// strikes.
+#[tauri::command]
+#[specta::specta]
+pub fn record_stage_strike(
+    set_id: u64,
+    game: u32,
+    stage: String,
+    action: StageAction,
+    entrant_id: Option<u32>,
+) -> Result<SetStageStrikes, String> {
+    let params = json!({
+        "setId": set_id,
+        "game": game,
+        "stage": stage,
+        "action": action,
+        "entrantId": entrant_id,
+    });
+    audited("recordStageStrike", params, || {
+        if game == 0 {
+            return Err("Games are numbered from 1.".to_string());
+        }
+        let stages = legal_stages(&cached_config()?);
+        with_stage_strikes(|log| {
+            let mut strikes = log.get(&set_id).and_then(|games| games.get(&game)).cloned().unwrap_or_default();
+            add_strike(&mut strikes, &stages, game, &stage, action, entrant_id, now_ms())?;
+            log.entry(set_id).or_default().insert(game, strikes);
+            Ok(set_view(log, set_id, stages))
+        })
+    })
+}
+
+/// Take back the last ban or pick of a game.
+#[tauri::command]
+#[specta::specta]
+pub fn undo_stage_strike(set_id: u64, game: u32) -> Result<SetStageStrikes, String> {
+    audited("undoStageStrike", json!({ "setId": set_id, "game": game }), || {
+        let stages = legal_stages(&cached_config()?);
+        with_stage_strikes(|log| {
+            let games = log.get_mut(&set_id).ok_or_else(|| format!("Set {set_id} has no stage strikes."))?;
+            let strikes = games.get_mut(&game).ok_or_else(|| format!("Game {game} has no stage strikes."))?;
+            strikes.pop();
+            if strikes.is_empty() {
+                games.remove(&game);
+            }
+            if games.is_empty() {
+                log.remove(&set_id);
+            }
+            Ok(set_view(log, set_id, stages))
+        })
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn clear_stage_strikes(set_id: u64) -> Result<SetStageStrikes, String> {
+    audited("clearStageStrikes", json!({ "setId": set_id }), || {
+        let stages = legal_stages(&cached_config()?);
+        with_stage_strikes(|log| {
+            log.remove(&set_id);
+            Ok(set_view(log, set_id, stages))
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strike(
+        strikes: &mut Vec<StageStrike>,
+        stages: &[String],
+        stage: &str,
+        action: StageAction,
+    ) -> Result<(), String> {
+        let at_ms = strikes.len() as u64 + 1;
+        add_strike(strikes, stages, 1, stage, action, None, at_ms)
+    }
+
+    #[test]
+    fn default_stages_are_the_usual_six_starters_first() {
+        let stages = legal_stages(&AppConfig::default());
+        assert_eq!(stages.len(), 6);
+        assert_eq!(stages[0], "Yoshi's Story");
+    }
+
+    #[test]
+    fn bans_keep_the_order_they_were_made() {
+        let stages = legal_stages(&AppConfig::default());
+        let mut strikes = Vec::new();
+        strike(&mut strikes, &stages, "final destination", StageAction::Ban).unwrap();
+        strike(&mut strikes, &stages, "Battlefield", StageAction::Ban).unwrap();
+        strike(&mut strikes, &stages, "Dream Land N64", StageAction::Ban).unwrap();
+
+        let view = game_view(1, &strikes, &stages);
+        assert_eq!(view.bans, vec!["Final Destination", "Battlefield", "Dream Land N64"]);
+        assert_eq!(view.strikes.iter().map(|s| s.at_ms).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(view.pick, None);
+    }
+
+    #[test]
+    fn strikes_must_be_legal_and_new_and_a_pick_ends_the_game() {
+        let stages = legal_stages(&AppConfig::default());
+        let mut strikes = Vec::new();
+        assert!(strike(&mut strikes, &stages, "Hyrule Temple", StageAction::Pick).is_err());
+        strike(&mut strikes, &stages, "Battlefield", StageAction::Ban).unwrap();
+        assert!(strike(&mut strikes, &stages, " battlefield ", StageAction::Pick).is_err());
+        strike(&mut strikes, &stages, " yoshi's story ", StageAction::Pick).unwrap();
+        assert_eq!(strikes[1].stage, "Yoshi's Story");
+        let err = strike(&mut strikes, &stages, "Pokemon Stadium", StageAction::Ban).unwrap_err();
+        assert_eq!(err, "Game 1 is already on Yoshi's Story.");
+        assert_eq!(strikes.len(), 2);
+    }
+
+    #[test]
+    fn remaining_stages_leave_out_bans_and_the_pick() {
+        let stages = legal_stages(&AppConfig::default());
+        let mut strikes = Vec::new();
+        strike(&mut strikes, &stages, "Battlefield", StageAction::Ban).unwrap();
+        strike(&mut strikes, &stages, "Final Destination", StageAction::Ban).unwrap();
+        assert_eq!(
+            game_view(1, &strikes, &stages).remaining,
+            vec!["Yoshi's Story", "Fountain of Dreams", "Pokemon Stadium", "Dream Land N64"]
+        );
+        strike(&mut strikes, &stages, "Yoshi's Story", StageAction::Pick).unwrap();
+        assert_eq!(
+            game_view(1, &strikes, &stages).remaining,
+            vec!["Fountain of Dreams", "Pokemon Stadium", "Dream Land N64"]
+        );
+    }
+}
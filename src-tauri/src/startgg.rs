@@ -1,8 +1,16 @@
+use crate::bracket_config::read_replay_map;
 use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::idle_mode::{is_idle, IDLE_POLL_INTERVAL_MS};
+use crate::ratings::{build_rating_table, seed_entrants_by_rating};
+use crate::sim_worker::replace_sim;
+use crate::startgg_webhook::{take_webhook_signal, webhooks_active, WEBHOOK_BACKUP_POLL_MS};
+use crate::network::{client_builder, offline_error, record_request_result};
 use crate::types::*;
 use crate::startgg_sim::{
-    StartggSim, StartggSimConfig, StartggSimEntrant, StartggSimEntrantConfig, StartggSimEventConfig,
-    StartggSimPhaseConfig, StartggSimSet, StartggSimSlot, StartggSimSimulationConfig, StartggSimState,
+    is_preview_set_id, preview_set_id, reconcile_preview_sets, StartggSim, StartggSimConfig, StartggSimEntrant,
+    StartggSimEntrantConfig, StartggSimEventConfig, StartggSimPhaseConfig, StartggSimSet, StartggSimSlot,
+    StartggSimSimulationConfig, StartggSimState,
 };
 use crate::test_mode::build_test_streams;
 use crate::replay::tag_from_code;
@@ -12,6 +20,7 @@ use std::{
     collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
     thread::sleep,
     time::{Duration, SystemTime},
 };
@@ -76,7 +85,10 @@ query EventEntrants($slug: String!, $page: Int!, $perPage: Int!) {
           gamerTag
           connectedAccounts
           player { gamerTag }
-          user { authorizations { type externalUsername } }
+          user {
+            location { country }
+            authorizations { type externalUsername }
+          }
         }
       }
     }
@@ -99,7 +111,10 @@ query EventEntrants($slug: String!, $page: Int!, $perPage: Int!) {
         participants {
           gamerTag
           player { gamerTag }
-          user { authorizations { type externalUsername } }
+          user {
+            location { country }
+            authorizations { type externalUsername }
+          }
         }
       }
     }
@@ -127,6 +142,9 @@ query EventSets($slug: String!, $page: Int!, $perPage: Int!) {
           phase { id name }
         }
         slots {
+          prereqId
+          prereqType
+          prereqPlacement
           entrant { id name }
           standing { stats { score { value label } } }
         }
@@ -136,6 +154,24 @@ query EventSets($slug: String!, $page: Int!, $perPage: Int!) {
 }
 "#;
 
+pub const STARTGG_MARK_SET_IN_PROGRESS_MUTATION: &str = r#"
+mutation MarkSetInProgress($setId: ID!) {
+  markSetInProgress(setId: $setId) {
+    id
+    state
+  }
+}
+"#;
+
+pub const STARTGG_REPORT_SET_MUTATION: &str = r#"
+mutation ReportBracketSet($setId: ID!, $winnerId: ID, $isDQ: Boolean, $gameData: [BracketSetGameDataInput]) {
+  reportBracketSet(setId: $setId, winnerId: $winnerId, isDQ: $isDQ, gameData: $gameData) {
+    id
+    state
+  }
+}
+"#;
+
 // ── Functions ──────────────────────────────────────────────────────────
 
 pub fn startgg_token_from_config(config: &AppConfig) -> Result<String, String> {
@@ -197,8 +233,13 @@ pub fn startgg_graphql_request<T: DeserializeOwned>(
   query: &str,
   variables: Value,
 ) -> Result<T, String> {
+  if config.offline_mode {
+    return Err(offline_error("Start.gg"));
+  }
   let token = startgg_token_from_config(config)?;
-  let client = reqwest::blocking::Client::new();
+  let client = client_builder()?
+    .build()
+    .map_err(|e| format!("build Start.gg client: {e}"))?;
   let request_log = {
     let vars = serde_json::to_string_pretty(&variables).unwrap_or_else(|_| variables.to_string());
     format!(
@@ -227,6 +268,7 @@ pub fn startgg_graphql_request<T: DeserializeOwned>(
       }
     }
   }
+  record_request_result(if resp.is_some() { Ok(()) } else { Err(last_send_err.as_str()) });
   let resp = resp.ok_or_else(|| last_send_err.clone())?;
   let status = resp.status();
   let body = resp.text().map_err(|e| {
@@ -258,6 +300,52 @@ pub fn startgg_graphql_request<T: DeserializeOwned>(
     .ok_or_else(|| "Start.gg response missing data.".to_string())
 }
 
+/// Mark a set as started on start.gg. Returns its state afterwards.
+pub fn mark_startgg_set_in_progress(config: &AppConfig, set_id: u64) -> Result<String, String> {
+  check_not_preview(set_id)?;
+  let data: StartggMarkSetInProgressData = startgg_graphql_request(
+    config,
+    STARTGG_MARK_SET_IN_PROGRESS_MUTATION,
+    json!({ "setId": set_id.to_string() }),
+  )?;
+  let set = data
+    .mark_set_in_progress
+    .ok_or_else(|| format!("Start.gg didn't return set {set_id}."))?;
+  Ok(map_startgg_set_state(set.state.as_ref()))
+}
+
+/// Report a finished set: its winner and, when known, the winner of each
+/// game in the order they were played. Returns the set's state afterwards.
+pub fn report_startgg_set(
+  config: &AppConfig,
+  set_id: u64,
+  winner_id: u32,
+  game_winner_ids: Option<&[u32]>,
+) -> Result<String, String> {
+  check_not_preview(set_id)?;
+  let mut variables = json!({
+    "setId": set_id.to_string(),
+    "winnerId": winner_id.to_string(),
+    "isDQ": false,
+  });
+  if let Some(game_winner_ids) = game_winner_ids {
+    variables["gameData"] = game_winner_ids
+      .iter()
+      .enumerate()
+      .map(|(idx, winner)| json!({ "gameNum": idx + 1, "winnerId": winner.to_string() }))
+      .collect();
+  }
+  let data: StartggReportSetData = startgg_graphql_request(config, STARTGG_REPORT_SET_MUTATION, variables)?;
+  // Sets the report completed downstream come back too; only ours matters.
+  let state = data
+    .report_bracket_set
+    .unwrap_or_default()
+    .into_iter()
+    .find(|set| set.id.as_ref().and_then(value_to_set_id).map(|(id, _)| id) == Some(set_id))
+    .map(|set| map_startgg_set_state(set.state.as_ref()));
+  Ok(state.unwrap_or_else(|| "completed".to_string()))
+}
+
 pub fn fetch_startgg_event_info(config: &AppConfig, slug: &str) -> Result<StartggEventInfoNode, String> {
   let data: StartggEventInfoData =
     startgg_graphql_request(config, STARTGG_EVENT_INFO_QUERY, json!({ "slug": slug }))?;
@@ -502,6 +590,25 @@ pub fn value_to_u64(value: &Value) -> Option<u64> {
   value_to_i64(value).and_then(|num| u64::try_from(num).ok())
 }
 
+/// A start.gg set id: numbers as they are, and the `preview_...` strings of
+/// sets whose phase hasn't started hashed by `preview_set_id`, with the
+/// string kept so it can be sent back.
+pub fn value_to_set_id(value: &Value) -> Option<(u64, Option<String>)> {
+  if let Some(id) = value_to_u64(value) {
+    return Some((id, None));
+  }
+  let raw = value.as_str().map(str::trim).filter(|raw| !raw.is_empty())?;
+  Some((preview_set_id(raw), Some(raw.to_string())))
+}
+
+/// start.gg only takes reports and starts for sets it has created.
+fn check_not_preview(set_id: u64) -> Result<(), String> {
+  if is_preview_set_id(set_id) {
+    return Err(format!("Set {set_id} is a preview; start.gg creates it when its phase starts."));
+  }
+  Ok(())
+}
+
 pub fn value_to_string(value: &Value) -> Option<String> {
   match value {
     Value::String(raw) => Some(raw.clone()),
@@ -512,13 +619,7 @@ pub fn value_to_string(value: &Value) -> Option<String> {
 
 pub fn parse_time_ms(value: Option<i64>) -> Option<u64> {
   let value = value?;
-  if value > 1_000_000_000_000 {
-    Some(value as u64)
-  } else if value > 0 {
-    Some((value as u64) * 1000)
-  } else {
-    None
-  }
+  (value > 0).then(|| crate::venue_time::epoch_ms(value) as u64)
 }
 
 pub fn map_startgg_set_state(value: Option<&Value>) -> String {
@@ -660,6 +761,41 @@ fn extract_slippi_from_connected_accounts(accounts: &Value) -> Option<String> {
   None
 }
 
+/// Where a live slot's entrant comes from, in the sim's terms: "winner" or
+/// "loser" of another set, or "empty" for a bye. Seeded slots have no source.
+fn live_slot_source(slot: &StartggSetSlotNode) -> (Option<String>, Option<u64>) {
+  match slot.prereq_type.as_deref() {
+    Some("set") => {
+      let source_type = match slot.prereq_placement {
+        Some(1) => "winner",
+        Some(_) => "loser",
+        None => return (None, None),
+      };
+      (
+        Some(source_type.to_string()),
+        slot.prereq_id.as_ref().and_then(value_to_set_id).map(|(id, _)| id),
+      )
+    }
+    Some("bye") => (Some("empty".to_string()), None),
+    _ => (None, None),
+  }
+}
+
+fn live_slot_source_label(slot: &StartggSimSlot, round_labels: &HashMap<u64, String>) -> Option<String> {
+  let source_type = slot.source_type.as_deref()?;
+  if source_type == "empty" {
+    return Some("TBD".to_string());
+  }
+  let label = slot
+    .source_set_id
+    .and_then(|id| round_labels.get(&id).cloned().or_else(|| Some(format!("Set {id}"))))?;
+  match source_type {
+    "winner" => Some(format!("Winner of {label}")),
+    "loser" => Some(format!("Loser of {label}")),
+    _ => None,
+  }
+}
+
 pub fn build_live_startgg_state(
   event: StartggEventInfoNode,
   entrants_raw: Vec<StartggEntrantNode>,
@@ -725,11 +861,11 @@ pub fn build_live_startgg_state(
 
   let mut sets = Vec::new();
   for (idx, set) in sets_raw.iter().enumerate() {
-    let id = set
+    let (id, startgg_id) = set
       .id
       .as_ref()
-      .and_then(value_to_u64)
-      .unwrap_or((idx + 1) as u64);
+      .and_then(value_to_set_id)
+      .unwrap_or(((idx + 1) as u64, None));
     let round = set.round.unwrap_or(0);
     let round_label = resolve_live_round_label(set.full_round_text.as_ref(), round);
     let state = map_startgg_set_state(set.state.as_ref());
@@ -804,6 +940,7 @@ pub fn build_live_startgg_state(
               result = Some("loss".to_string());
             }
 
+            let (source_type, source_set_id) = live_slot_source(slot);
             StartggSimSlot {
               entrant_id,
               entrant_name,
@@ -811,8 +948,8 @@ pub fn build_live_startgg_state(
               seed,
               score,
               result,
-              source_type: None,
-              source_set_id: None,
+              source_type,
+              source_set_id,
               source_label: None,
             }
           })
@@ -822,6 +959,8 @@ pub fn build_live_startgg_state(
 
     sets.push(StartggSimSet {
       id,
+      startgg_id,
+      preview_id: None,
       phase_id,
       phase_name,
       round,
@@ -836,6 +975,13 @@ pub fn build_live_startgg_state(
     });
   }
 
+  // Labels need every set's round label, so fill them in once all sets exist.
+  let round_labels: HashMap<u64, String> =
+    sets.iter().map(|set| (set.id, set.round_label.clone())).collect();
+  for slot in sets.iter_mut().flat_map(|set| set.slots.iter_mut()) {
+    slot.source_label = live_slot_source_label(slot, &round_labels);
+  }
+
   StartggSimState {
     event: StartggSimEventConfig {
       id: event_id,
@@ -848,6 +994,9 @@ pub fn build_live_startgg_state(
     started_at_ms: now_ms,
     now_ms,
     reference_tournament_link: event_link,
+    version: 0,
+    not_modified: false,
+    warming: false,
   }
 }
 
@@ -857,6 +1006,9 @@ pub fn fetch_live_startgg_state(
 ) -> Result<StartggSimState, String> {
   let event = fetch_startgg_event_info(config, event_slug)?;
   let entrants = fetch_startgg_entrants(config, event_slug)?;
+  if let Err(err) = crate::player_db::update_player_db_from_entrants(&entrants) {
+    tracing::warn!("Failed to update player DB: {}", err);
+  }
   let sets = fetch_startgg_sets(config, event_slug)?;
   let event_link = format!("https://start.gg/{}", event_slug.trim_start_matches('/'));
   Ok(build_live_startgg_state(
@@ -872,13 +1024,17 @@ pub fn maybe_refresh_live_startgg(
   live_state: &SharedLiveStartgg,
   force: bool,
 ) -> Option<StartggSimState> {
-  if config.test_mode {
+  if config.test_flags().fake_bracket {
     return None;
   }
   let link = config.startgg_link.trim();
   if link.is_empty() {
     return None;
   }
+  if config.offline_mode {
+    // Keep serving the last fetched bracket until the network is back.
+    return live_state.lock().unwrap_or_else(|e| e.into_inner()).state.clone();
+  }
   let (should_fetch, cached_state, cached_link, cached_slug, fetch_in_flight, last_fetch) = {
     let guard = live_state.lock().unwrap_or_else(|e| e.into_inner());
     (
@@ -942,7 +1098,10 @@ pub fn maybe_refresh_live_startgg(
   guard.startgg_link = Some(link.to_string());
   guard.event_slug = Some(resolved_slug.clone());
   match result {
-    Ok(state) => {
+    Ok(mut state) => {
+      if let Some(previous) = cached_state.as_ref() {
+        reconcile_preview_sets(previous, &mut state);
+      }
       guard.last_fetch = Some(SystemTime::now());
       guard.last_error = None;
       guard.state = Some(state.clone());
@@ -955,29 +1114,66 @@ pub fn maybe_refresh_live_startgg(
   }
 }
 
+/// Set by the shutdown routine so the poller stops hitting start.gg while
+/// the event is being wrapped up.
+static STARTGG_POLLING_SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_startgg_polling_suspended(suspended: bool) {
+  STARTGG_POLLING_SUSPENDED.store(suspended, Ordering::SeqCst);
+}
+
+/// How often the poller wakes to check for webhook signals.
+const STARTGG_WEBHOOK_CHECK_MS: u64 = 250;
+
 pub fn spawn_startgg_polling(
   live_state: SharedLiveStartgg,
   entrant_manager: Option<crate::types::SharedEntrantManager>,
 ) {
-  std::thread::spawn(move || loop {
-    let config = load_config_inner().unwrap_or_else(|_| AppConfig::default());
-    if config.test_mode || !config.startgg_polling {
-      sleep(Duration::from_millis(STARTGG_POLL_INTERVAL_MS));
-      continue;
-    }
-    if config.startgg_link.trim().is_empty() {
-      sleep(Duration::from_millis(STARTGG_POLL_INTERVAL_MS));
-      continue;
-    }
-    if let Some(state) = maybe_refresh_live_startgg(&config, &live_state, true) {
-      // Update entrant manager with new Start.gg state
-      if let Some(ref manager) = entrant_manager {
-        if let Ok(mut guard) = manager.lock() {
-          guard.update_from_startgg(&state);
+  std::thread::spawn(move || {
+    let mut last_poll: Option<std::time::Instant> = None;
+    loop {
+      sleep(Duration::from_millis(STARTGG_WEBHOOK_CHECK_MS));
+      let config = cached_config().unwrap_or_else(|_| AppConfig::default());
+      let paused = config.test_flags().fake_bracket
+        || config.offline_mode
+        || config.startgg_link.trim().is_empty()
+        || !feature_enabled(FeatureFlag::StartggPolling);
+      if paused || STARTGG_POLLING_SUSPENDED.load(Ordering::SeqCst) {
+        continue;
+      }
+      // A webhook refreshes right away. While they keep arriving, polling
+      // only runs as a slow safety net; once they go quiet it's back to the
+      // normal interval.
+      let webhook = config.startgg_webhook_enabled && take_webhook_signal(now_ms());
+      let interval_ms = if is_idle() {
+        IDLE_POLL_INTERVAL_MS
+      } else if config.startgg_webhook_enabled && webhooks_active(now_ms()) {
+        WEBHOOK_BACKUP_POLL_MS
+      } else {
+        STARTGG_POLL_INTERVAL_MS
+      };
+      let poll_due = config.startgg_polling
+        && match last_poll {
+          Some(at) => at.elapsed() >= Duration::from_millis(interval_ms),
+          None => true,
+        };
+      if !webhook && !poll_due {
+        continue;
+      }
+      last_poll = Some(std::time::Instant::now());
+      if webhook {
+        // Skip the poll-interval rate limit; the webhook says something changed.
+        live_state.lock().unwrap_or_else(|e| e.into_inner()).last_fetch = None;
+      }
+      if let Some(state) = maybe_refresh_live_startgg(&config, &live_state, true) {
+        // Update entrant manager with new Start.gg state
+        if let Some(ref manager) = entrant_manager {
+          if let Ok(mut guard) = manager.lock() {
+            guard.update_from_startgg(&state);
+          }
         }
       }
     }
-    sleep(Duration::from_millis(STARTGG_POLL_INTERVAL_MS));
   });
 }
 
@@ -1013,6 +1209,8 @@ pub fn build_default_startgg_sim_config() -> Result<StartggSimConfig, String> {
   if entrants.is_empty() {
     return Err("No entrants available to build Start.gg sim config.".to_string());
   }
+  // Seed from session ratings when there's history; otherwise folder order.
+  seed_entrants_by_rating(&mut entrants, &build_rating_table(None));
 
   Ok(StartggSimConfig {
     event: StartggSimEventConfig {
@@ -1059,99 +1257,84 @@ pub fn load_startgg_sim_config_from(path: &Path) -> Result<StartggSimConfig, Str
     .map_err(|e| format!("parse startgg sim config {}: {e}", path.display()))
 }
 
-pub fn init_startgg_sim(guard: &mut TestModeState, now: u64) -> Result<(), String> {
-  if guard.startgg_sim.is_none() {
-    let config_path = guard.startgg_config_path.clone();
-    let effective_path = config_path.clone().unwrap_or_else(startgg_sim_config_path);
-    let config = if let Some(ref path) = config_path {
-      load_startgg_sim_config_from(path)?
-    } else {
-      load_startgg_sim_config()?
-    };
-    let mut sim = StartggSim::new(config, now)?;
-
-    // Load persisted state if available
-    match sim.load_state(&effective_path) {
-      Ok(result) if result.loaded => {
-        tracing::info!(
-          "Loaded persisted bracket state from {} ({} sets restored, config {})",
-          StartggSim::persistence_path(&effective_path).display(),
-          result.sets_restored,
-          if result.config_matched { "matched" } else { "changed" }
-        );
-        guard.state_restored_from_persistence = true;
-        guard.state_config_matched = result.config_matched;
-        // Re-advance to propagate loaded state through the bracket
-        sim.state(now);
-      }
-      Ok(_) => {
-        guard.state_restored_from_persistence = false;
-        guard.state_config_matched = true;
-      }
-      Err(e) => {
-        tracing::warn!("Failed to load persisted bracket state: {}", e);
-        guard.state_restored_from_persistence = false;
-        guard.state_config_matched = true;
-      }
+/// A sim built from its config, with any persisted state loaded.
+pub struct LoadedStartggSim {
+  pub sim: StartggSim,
+  pub state_restored: bool,
+  pub config_matched: bool,
+}
+
+/// Build the sim for `config_path` (the default config if `None`) and
+/// restore its persisted state. Needs no lock, so it can run off the
+/// command path; large reference brackets take a while.
+pub fn load_startgg_sim(config_path: Option<&Path>, now: u64) -> Result<LoadedStartggSim, String> {
+  let effective_path = config_path.map(Path::to_path_buf).unwrap_or_else(startgg_sim_config_path);
+  let config = if let Some(path) = config_path {
+    load_startgg_sim_config_from(path)?
+  } else {
+    load_startgg_sim_config()?
+  };
+  let mut sim = StartggSim::new(config, now)?;
+
+  // Load persisted state if available
+  let (state_restored, config_matched) = match sim.load_state(&effective_path) {
+    Ok(result) if result.loaded => {
+      tracing::info!(
+        "Loaded persisted bracket state from {} ({} sets restored, config {})",
+        StartggSim::persistence_path(&effective_path).display(),
+        result.sets_restored,
+        if result.config_matched { "matched" } else { "changed" }
+      );
+      // Re-advance to propagate loaded state through the bracket
+      sim.state(now);
+      (true, result.config_matched)
+    }
+    Ok(_) => (false, true),
+    Err(e) => {
+      tracing::warn!("Failed to load persisted bracket state: {}", e);
+      (false, true)
     }
+  };
+  Ok(LoadedStartggSim { sim, state_restored, config_matched })
+}
+
+/// Put a loaded sim in place and publish it for polls.
+pub fn install_startgg_sim(guard: &mut TestModeState, loaded: LoadedStartggSim) {
+  guard.state_restored_from_persistence = loaded.state_restored;
+  guard.state_config_matched = loaded.config_matched;
+  replace_sim(guard, loaded.sim, now_ms());
+}
 
-    guard.startgg_sim = Some(sim);
+/// Load the sim in place if it isn't yet. Holds the caller's lock for the
+/// whole load; commands use `sim_worker` instead.
+pub fn init_startgg_sim(guard: &mut TestModeState, now: u64) -> Result<(), String> {
+  if guard.startgg_sim.is_none() {
+    let loaded = load_startgg_sim(guard.startgg_config_path.as_deref(), now)?;
+    install_startgg_sim(guard, loaded);
   }
   Ok(())
 }
 
+/// First readable replay of each set in the bracket config's replay map.
+/// An unreadable config is logged and leaves the map empty.
 pub fn build_bracket_replay_map(config_path: &Path) -> HashMap<u64, PathBuf> {
   let mut out = HashMap::new();
   if !config_path.is_file() {
     return out;
   }
-  let data = match fs::read_to_string(config_path) {
-    Ok(data) => data,
-    Err(_) => return out,
-  };
-  let value: Value = match serde_json::from_str(&data) {
-    Ok(value) => value,
-    Err(_) => return out,
-  };
-  let replay_map = match value.get("referenceReplayMap") {
-    Some(map) => map,
-    None => return out,
-  };
-  let base_dir = replay_map
-    .get("replaysDir")
-    .and_then(|v| v.as_str())
-    .map(resolve_repo_path);
-  let sets = match replay_map.get("sets").and_then(|sets| sets.as_array()) {
-    Some(sets) => sets,
-    None => return out,
+  let replay_map = match read_replay_map(config_path) {
+    Ok(Some(replay_map)) => replay_map,
+    Ok(None) => return out,
+    Err(err) => {
+      tracing::warn!("Test mode has no bracket replays: {}", err);
+      return out;
+    }
   };
-
-  for set in sets {
-    let id = set.get("id").and_then(|v| v.as_u64());
-    let replays = set.get("replays").and_then(|v| v.as_array());
-    let (Some(id), Some(replays)) = (id, replays) else {
-      continue;
-    };
-    for replay in replays {
-      let raw = replay.get("path").and_then(|v| v.as_str()).unwrap_or("").trim();
-      if raw.is_empty() {
-        continue;
-      }
-      let mut path = PathBuf::from(raw);
-      if !path.is_absolute() {
-        if let Some(base) = &base_dir {
-          path = base.join(&path);
-        } else {
-          path = resolve_repo_path(raw);
-        }
-      }
-      if path.is_file() {
-        out.entry(id).or_insert(path);
-        break;
-      }
+  for set in &replay_map.sets {
+    if let Some(path) = replay_map.replay_paths(set).into_iter().find(|path| path.is_file()) {
+      out.entry(set.id).or_insert(path);
     }
   }
-
   out
 }
 
@@ -1160,55 +1343,12 @@ pub fn read_bracket_set_replay_paths(config_path: &str, set_id: u64) -> Result<V
   if !resolved.is_file() {
     return Err(format!("Bracket config not found at {}", resolved.display()));
   }
-  let data = fs::read_to_string(&resolved)
-    .map_err(|e| format!("read bracket config {}: {e}", resolved.display()))?;
-  let value: Value = serde_json::from_str(&data)
-    .map_err(|e| format!("parse bracket config {}: {e}", resolved.display()))?;
-
-  let replay_map = value
-    .get("referenceReplayMap")
+  let replay_map = read_replay_map(&resolved)?
     .ok_or_else(|| "referenceReplayMap missing from bracket config.".to_string())?;
-  let base_dir = replay_map
-    .get("replaysDir")
-    .and_then(|v| v.as_str())
-    .map(resolve_repo_path);
-  let sets = replay_map
-    .get("sets")
-    .and_then(|sets| sets.as_array())
-    .ok_or_else(|| "referenceReplayMap sets missing from bracket config.".to_string())?;
-
-  let mut out: Vec<PathBuf> = Vec::new();
-  let mut seen: HashSet<PathBuf> = HashSet::new();
-
-  for set in sets {
-    let id = set.get("id").and_then(|v| v.as_u64());
-    if id != Some(set_id) {
-      continue;
-    }
-    let replays = match set.get("replays").and_then(|v| v.as_array()) {
-      Some(replays) => replays,
-      None => break,
-    };
-    for replay in replays {
-      let raw = replay.get("path").and_then(|v| v.as_str()).unwrap_or("").trim();
-      if raw.is_empty() {
-        continue;
-      }
-      let mut path = PathBuf::from(raw);
-      if !path.is_absolute() {
-        if let Some(base) = &base_dir {
-          path = base.join(&path);
-        } else {
-          path = resolve_repo_path(raw);
-        }
-      }
-      if seen.insert(path.clone()) {
-        out.push(path);
-      }
-    }
-    break;
-  }
-
+  let out = replay_map
+    .set(set_id)
+    .map(|set| replay_map.replay_paths(set))
+    .unwrap_or_default();
   if out.is_empty() {
     return Err(format!("No replay paths found for set {set_id}."));
   }
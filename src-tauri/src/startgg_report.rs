@@ -0,0 +1,431 @@
+use crate::audit_log::{audited, with_origin};
+use crate::config::*;
+use crate::feature_flags::{feature_enabled, require_feature};
+use crate::friendlies::{game_classifications, wins_needed, FriendlyReason, GameClassification};
+use crate::replay::{replay_winner_identity, set_slot_index_for_identity, sort_replay_paths_by_start_time, tag_from_code};
+use crate::report_lock::REPLAY_REPORTER;
+use crate::run_of_show::current_bracket_state;
+use crate::startgg::{mark_startgg_set_in_progress, maybe_refresh_live_startgg, report_startgg_set};
+use crate::startgg_sim::{StartggSimSet, StartggSimState};
+use crate::types::*;
+use serde::Serialize;
+use serde_json::json;
+use specta::Type;
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    thread::sleep,
+    time::Duration,
+};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// Emitted with a `SetResultProposal` when a set's replays show it's over.
+pub const RESULT_PROPOSED_EVENT: &str = "startgg-result-proposed";
+
+const MONITOR_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Results waiting for the operator to confirm, oldest first.
+static PROPOSALS: Mutex<Vec<SetResultProposal>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ProposedGame {
+    pub replay_path: String,
+    pub winner_slot: usize,
+}
+
+/// A set the replays say is finished, not yet reported to start.gg.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetResultProposal {
+    pub set_id: u64,
+    pub setup_id: u32,
+    pub round_label: String,
+    /// Entrant names in slot order
+    pub players: Vec<String>,
+    pub scores: [u8; 2],
+    pub winner_slot: usize,
+    /// Games in the order they were played
+    pub games: Vec<ProposedGame>,
+    pub detected_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggReportResult {
+    pub set_id: u64,
+    pub scores: [u8; 2],
+    pub winner_id: u32,
+    /// The set's state on start.gg after the report
+    pub state: String,
+}
+
+fn reporting_enabled(config: &AppConfig) -> bool {
+    config.startgg_reporting
+        && !config.offline_mode
+        && !config.test_flags().fake_bracket
+        && feature_enabled(FeatureFlag::AutoReport)
+}
+
+/// Count each game's winner; Some once a player has won enough games to
+/// take the set. `games` are in play order, with None for a game whose
+/// winner isn't either player.
+fn tally_set(set: &StartggSimSet, games: &[(String, Option<usize>)]) -> Option<([u8; 2], usize, Vec<ProposedGame>)> {
+    let needed = wins_needed(set.best_of);
+    let mut scores = [0u8; 2];
+    let mut counted = Vec::new();
+    for (replay_path, winner) in games {
+        let Some(slot) = winner.filter(|slot| *slot < 2) else {
+            continue;
+        };
+        scores[slot] = scores[slot].saturating_add(1);
+        counted.push(ProposedGame { replay_path: replay_path.clone(), winner_slot: slot });
+        if scores[slot] >= needed {
+            return Some((scores, slot, counted));
+        }
+    }
+    None
+}
+
+/// Winner slot of a finished game; None while it's still being played or
+/// when the winner isn't in the set.
+fn game_winner(set: &StartggSimSet, replay: &Path) -> Result<Option<usize>, String> {
+    let (code, tag) = replay_winner_identity(replay)?;
+    let tag = tag.or_else(|| code.as_deref().map(tag_from_code));
+    Ok(set_slot_index_for_identity(set, code.as_deref(), tag.as_deref()))
+}
+
+/// A game of its set. A game only called a friendly because start.gg
+/// hasn't started the set yet counts too: it's game one.
+fn counts_for_set(class: &GameClassification) -> bool {
+    !class.friendly || (!class.overridden && class.reasons == [FriendlyReason::SetNotStarted])
+}
+
+/// Games of bracket sets, by set id, with the setup they were played on.
+pub(crate) fn set_games() -> HashMap<u64, (u32, Vec<PathBuf>)> {
+    let mut games: HashMap<u64, (u32, Vec<PathBuf>)> = HashMap::new();
+    for class in game_classifications().into_iter().filter(counts_for_set) {
+        let Some(set_id) = class.set_id else {
+            continue;
+        };
+        let entry = games.entry(set_id).or_insert_with(|| (class.setup_id, Vec::new()));
+        entry.1.push(PathBuf::from(class.replay_path));
+    }
+    games
+}
+
+fn propose(proposal: SetResultProposal) {
+    let mut proposals = PROPOSALS.lock().unwrap_or_else(|e| e.into_inner());
+    proposals.retain(|existing| existing.set_id != proposal.set_id);
+    proposals.push(proposal);
+}
+
+fn check_sets(
+    app: &AppHandle,
+    config: &AppConfig,
+    bracket: &StartggSimState,
+    winners: &mut HashMap<String, Option<usize>>,
+    marked: &mut HashSet<u64>,
+) {
+    let proposed = PROPOSALS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|proposal| proposal.set_id)
+        .collect::<HashSet<_>>();
+    for (set_id, (setup_id, replays)) in set_games() {
+        let Some(set) = bracket.sets.iter().find(|set| set.id == set_id) else {
+            continue;
+        };
+        if set.state == "completed" || proposed.contains(&set_id) {
+            continue;
+        }
+        if config.startgg_auto_start_sets && set.state == "pending" && marked.insert(set_id) {
+            let result = with_origin("auto", || {
+                audited("markStartggSetInProgress", json!({ "setId": set_id }), || {
+                    mark_startgg_set_in_progress(config, set_id)
+                })
+            });
+            if let Err(err) = result {
+                tracing::warn!("Couldn't mark set {} in progress on start.gg: {}", set_id, err);
+            }
+        }
+        let games = sort_replay_paths_by_start_time(replays)
+            .into_iter()
+            .filter_map(|replay| {
+                let key = replay.to_string_lossy().to_string();
+                if let Some(winner) = winners.get(&key) {
+                    return Some((key, *winner));
+                }
+                // Err until the game ends and the replay has its placements.
+                let winner = game_winner(set, &replay).ok()?;
+                winners.insert(key.clone(), winner);
+                Some((key, winner))
+            })
+            .collect::<Vec<_>>();
+        let Some((scores, winner_slot, games)) = tally_set(set, &games) else {
+            continue;
+        };
+        let proposal = SetResultProposal {
+            set_id,
+            setup_id,
+            round_label: set.round_label.clone(),
+            players: set
+                .slots
+                .iter()
+                .map(|slot| slot.entrant_name.clone().unwrap_or_default())
+                .collect(),
+            scores,
+            winner_slot,
+            games,
+            detected_at_ms: now_ms(),
+        };
+        tracing::info!(
+            "Set {} ({}) looks finished at {}-{}; waiting for confirmation to report it",
+            set_id,
+            proposal.round_label,
+            scores[0],
+            scores[1]
+        );
+        let _ = app.emit(RESULT_PROPOSED_EVENT, &proposal);
+        propose(proposal);
+    }
+}
+
+/// Watch the games played on each setup while `startgg_reporting` is on and
+/// propose a set's result once someone has won enough games. Nothing is
+/// reported until the operator confirms with `report_startgg_set_result`;
+/// sets are only marked in progress as their first game starts with
+/// `startgg_auto_start_sets` on too.
+pub fn spawn_startgg_report_monitor(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut winners: HashMap<String, Option<usize>> = HashMap::new();
+        let mut marked: HashSet<u64> = HashSet::new();
+        loop {
+            sleep(MONITOR_INTERVAL);
+            let Ok(config) = cached_config() else {
+                continue;
+            };
+            if !reporting_enabled(&config) {
+                continue;
+            }
+            let test_state = app.state::<SharedTestState>().inner().clone();
+            let live_startgg = app.state::<SharedLiveStartgg>().inner().clone();
+            let Some(bracket) = current_bracket_state(&test_state, &live_startgg) else {
+                continue;
+            };
+            check_sets(&app, &config, &bracket, &mut winners, &mut marked);
+        }
+    });
+}
+
+/// Each game's winner entrant id in play order, when the detected games add
+/// up to `scores`. Scores entered by hand have no games behind them, so
+/// only the set's winner is reported for those.
+fn game_winner_ids(
+    scores: [u8; 2],
+    entrant_ids: [u32; 2],
+    proposal: Option<&SetResultProposal>,
+) -> Option<Vec<u32>> {
+    let proposal = proposal.filter(|proposal| proposal.scores == scores)?;
+    Some(proposal.games.iter().map(|game| entrant_ids[game.winner_slot]).collect())
+}
+
+/// Mark a set in progress on start.gg for `reporter`, e.g. a bracket runner
+/// calling it to a setup. Returns the set's state afterwards.
+pub fn start_startgg_set_inner(
+    set_id: u64,
+    reporter: &str,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<String, String> {
+    let config = cached_config()?;
+    if config.test_flags().fake_bracket {
+        return Err("The bracket is simulated; start the set from the bracket view.".to_string());
+    }
+    if !config.startgg_reporting {
+        return Err("Reporting to start.gg is off in settings.".to_string());
+    }
+    test_state.lock().map_err(|e| e.to_string())?.report_locks.check(set_id, reporter)?;
+    let state = mark_startgg_set_in_progress(&config, set_id)?;
+    test_state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .report_locks
+        .record(set_id, reporter, "markSetInProgress", None, now_ms());
+    live_startgg.lock().map_err(|e| e.to_string())?.last_fetch = None;
+    maybe_refresh_live_startgg(&config, live_startgg, true);
+    Ok(state)
+}
+
+/// Report a set's result to start.gg as `reporter`, holding its report lock
+/// like the sim's reporting does.
+pub fn report_startgg_set_result_inner(
+    set_id: u64,
+    scores: Option<[u8; 2]>,
+    reporter: &str,
+    test_state: &SharedTestState,
+    live_startgg: &SharedLiveStartgg,
+) -> Result<StartggReportResult, String> {
+    require_feature(FeatureFlag::AutoReport)?;
+    let config = cached_config()?;
+    if config.test_flags().fake_bracket {
+        return Err("The bracket is simulated; report the set from the bracket view.".to_string());
+    }
+    if !config.startgg_reporting {
+        return Err("Reporting to start.gg is off in settings.".to_string());
+    }
+    let proposal = PROPOSALS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .find(|proposal| proposal.set_id == set_id)
+        .cloned();
+    let set = live_startgg
+        .lock()
+        .map_err(|e| e.to_string())?
+        .state
+        .as_ref()
+        .and_then(|state| state.sets.iter().find(|set| set.id == set_id).cloned())
+        .ok_or_else(|| format!("Set {set_id} isn't in the start.gg bracket."))?;
+    let scores = scores
+        .or(proposal.as_ref().map(|proposal| proposal.scores))
+        .ok_or_else(|| format!("No result detected for set {set_id}; enter the scores to report."))?;
+    if scores[0] == scores[1] {
+        return Err(format!("A set can't end {}-{}.", scores[0], scores[1]));
+    }
+    let entrant_id = |idx: usize| {
+        set.slots
+            .get(idx)
+            .and_then(|slot| slot.entrant_id)
+            .ok_or_else(|| format!("Set {set_id} is missing a player."))
+    };
+    let entrant_ids = [entrant_id(0)?, entrant_id(1)?];
+    let winner_id = if scores[0] > scores[1] { entrant_ids[0] } else { entrant_ids[1] };
+
+    test_state.lock().map_err(|e| e.to_string())?.report_locks.check(set_id, reporter)?;
+    let games = game_winner_ids(scores, entrant_ids, proposal.as_ref());
+    let state = report_startgg_set(&config, set_id, winner_id, games.as_deref())?;
+    test_state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .report_locks
+        .record(set_id, reporter, "reportStartggSet", Some(scores), now_ms());
+    PROPOSALS
+        .lock()
+        .map_err(|e| e.to_string())?
+        .retain(|proposal| proposal.set_id != set_id);
+    tracing::info!("Reported set {} to start.gg: {}-{}", set_id, scores[0], scores[1]);
+
+    // Pick up the result and whatever it unlocked downstream right away.
+    live_startgg.lock().map_err(|e| e.to_string())?.last_fetch = None;
+    maybe_refresh_live_startgg(&config, live_startgg, true);
+    Ok(StartggReportResult { set_id, scores, winner_id, state })
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Finished sets waiting to be confirmed, oldest first.
+#[tauri::command]
+#[specta::specta]
+pub fn startgg_result_proposals() -> Vec<SetResultProposal> {
+    PROPOSALS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Confirm a set's result and report it to start.gg. `scores` overrides
+/// the detected result, or reports a set nothing was detected for.
+#[tauri::command]
+#[specta::specta]
+pub fn report_startgg_set_result(
+    set_id: u64,
+    scores: Option<[u8; 2]>,
+    test_state: State<'_, SharedTestState>,
+    live_startgg: State<'_, SharedLiveStartgg>,
+) -> Result<StartggReportResult, String> {
+    let params = json!({ "setId": set_id, "scores": scores });
+    audited("reportStartggSet", params, || {
+        report_startgg_set_result_inner(set_id, scores, REPLAY_REPORTER, &test_state, &live_startgg)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::startgg_sim::StartggSimSlot;
+
+    fn slot(id: u32) -> StartggSimSlot {
+        StartggSimSlot { entrant_id: Some(id), entrant_name: Some(format!("P{id}")), ..Default::default() }
+    }
+
+    fn set() -> StartggSimSet {
+        StartggSimSet {
+            id: 7,
+            phase_id: "p".to_string(),
+            phase_name: "Bracket".to_string(),
+            round: 1,
+            round_label: "Winners Round 1".to_string(),
+            best_of: 3,
+            state: "inProgress".to_string(),
+            started_at_ms: Some(1),
+            slots: vec![slot(10), slot(20)],
+            ..Default::default()
+        }
+    }
+
+    fn game(name: &str, winner: Option<usize>) -> (String, Option<usize>) {
+        (name.to_string(), winner)
+    }
+
+    fn replays(games: &[ProposedGame]) -> Vec<&str> {
+        games.iter().map(|g| g.replay_path.as_str()).collect()
+    }
+
+    /// P20 won 2-0.
+    fn proposal() -> SetResultProposal {
+        let (scores, winner, games) = tally_set(&set(), &[game("g1", Some(1)), game("g2", Some(1))]).unwrap();
+        SetResultProposal {
+            set_id: 7,
+            setup_id: 1,
+            round_label: "Winners Round 1".to_string(),
+            players: vec!["P10".to_string(), "P20".to_string()],
+            scores,
+            winner_slot: winner,
+            games,
+            detected_at_ms: 0,
+        }
+    }
+
+    #[test]
+    fn nothing_is_proposed_until_a_player_has_enough_wins() {
+        assert!(tally_set(&set(), &[game("g1", Some(1)), game("g2", Some(0))]).is_none());
+    }
+
+    #[test]
+    fn a_game_neither_player_won_does_not_count() {
+        let (scores, winner, games) =
+            tally_set(&set(), &[game("g1", Some(1)), game("g2", None), game("g3", Some(1))]).unwrap();
+        assert_eq!((scores, winner), ([0, 2], 1));
+        assert_eq!(replays(&games), vec!["g1", "g3"]);
+    }
+
+    #[test]
+    fn games_after_the_deciding_one_are_left_out() {
+        let (scores, _, games) =
+            tally_set(&set(), &[game("g1", Some(0)), game("g2", Some(0)), game("g3", Some(1))]).unwrap();
+        assert_eq!(scores, [2, 0]);
+        assert_eq!(replays(&games), vec!["g1", "g2"]);
+    }
+
+    #[test]
+    fn reported_game_winners_follow_the_proposal() {
+        assert_eq!(game_winner_ids([0, 2], [10, 20], Some(&proposal())), Some(vec![20, 20]));
+    }
+
+    #[test]
+    fn overridden_scores_report_no_games() {
+        // The games don't add up to them, so none are made up.
+        assert_eq!(game_winner_ids([1, 2], [10, 20], Some(&proposal())), None);
+        assert_eq!(game_winner_ids([3, 1], [10, 20], None), None);
+    }
+}
@@ -1,46 +1,112 @@
+use crate::audit_log::audited;
 use crate::config::*;
-use crate::types::{SharedTestState, TestModeState, BracketPersistenceStatus};
-use crate::startgg::{init_startgg_sim, load_startgg_sim_config_from};
-use crate::replay::{replay_winner_identity, set_slot_index_for_identity, tag_from_code, next_reference_step_scores};
-use crate::startgg_sim::{StartggSim, StartggSimState};
-use serde_json::Value;
+use crate::types::{
+    SharedTestState, TestModeState, BracketPersistenceStatus, PendingReport, ReportAuditEntry,
+    ScoreReconciliation, FeatureFlag,
+};
+use crate::feature_flags::require_feature;
+use crate::report_lock::{MANUAL_REPORTER, REPLAY_REPORTER};
+use crate::sim_worker::{publish, replace_sim, snapshot_or_warm, warm_up, SIM_WARMING};
+use crate::startgg::{load_startgg_sim_config_from, read_bracket_set_replay_paths};
+use crate::replay::{
+    replay_winner_identity, set_slot_index_for_identity, tag_from_code, next_reference_step_scores,
+    reconcile_set_scores, sort_replay_paths_by_start_time,
+};
+use crate::startgg_sim::{StartggSim, StartggSimConfig, StartggSimSet, StartggSimSetFilter, StartggSimState};
+use serde_json::{json, Value};
 use std::path::{Path, PathBuf};
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 
 // ── Helpers ─────────────────────────────────────────────────────────────
 
-/// Lock the mutex, initialize the sim if needed, then call `f` with `(&mut StartggSim, now_ms)`.
-fn with_sim<F, R>(test_state: &State<'_, SharedTestState>, f: F) -> Result<R, String>
+/// Fail with `SIM_WARMING` until the sim has loaded, starting the
+/// background load if it hasn't started.
+fn require_sim(test_state: &SharedTestState, guard: &TestModeState) -> Result<(), String> {
+    if guard.startgg_sim.is_none() {
+        warm_up(test_state, guard)?;
+        return Err(SIM_WARMING.to_string());
+    }
+    Ok(())
+}
+
+/// Lock the mutex, check the sim has loaded and run `f` on one set, then
+/// save and publish the sim. Refuses to run while another reporter holds
+/// the set's reporting lock, and once `f` succeeds records the action in the
+/// audit trail (taking the lock if it was free). Without an app handle
+/// nothing is emitted, so completed sets skip the score check.
+fn with_set_report<F, R>(
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+    set_id: u64,
+    reporter: &str,
+    action: &str,
+    f: F,
+) -> Result<R, String>
 where
     F: FnOnce(&mut StartggSim, u64) -> Result<R, String>,
 {
-    let now = now_ms();
-    let mut guard = test_state.lock().map_err(|e| e.to_string())?;
-    init_startgg_sim(&mut guard, now)?;
-    let sim = guard.startgg_sim.as_mut()
-        .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
-    f(sim, now)
+    let params = json!({ "setId": set_id, "reporter": reporter });
+    audited(action, params, || apply_set_report(app, test_state, set_id, reporter, action, f))
 }
 
-/// Lock the mutex, initialize the sim if needed, call `f`, then save state if successful.
-fn with_sim_save<F, R>(test_state: &State<'_, SharedTestState>, f: F) -> Result<R, String>
+fn apply_set_report<F, R>(
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+    set_id: u64,
+    reporter: &str,
+    action: &str,
+    f: F,
+) -> Result<R, String>
 where
     F: FnOnce(&mut StartggSim, u64) -> Result<R, String>,
 {
     let now = now_ms();
     let mut guard = test_state.lock().map_err(|e| e.to_string())?;
-    init_startgg_sim(&mut guard, now)?;
+    require_sim(test_state, &guard)?;
+    guard.report_locks.check(set_id, reporter)?;
     let config_path = guard.startgg_config_path.clone();
-    let sim = guard.startgg_sim.as_mut()
+    let TestModeState { startgg_sim, report_locks, .. } = &mut *guard;
+    let sim = startgg_sim.as_mut()
         .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
+    let was_completed = sim.set_snapshot(set_id).is_some_and(|set| set.state == "completed");
     let result = f(sim, now)?;
+    report_locks.record(set_id, reporter, action, sim.set_scores(set_id), now);
     save_sim_state(sim, config_path.as_deref());
+    publish(sim.state(now));
+    if let Some(app) = app.filter(|_| !was_completed) {
+        if let Some(set) = sim.set_snapshot(set_id).filter(|set| set.state == "completed") {
+            let effective_path = config_path.unwrap_or_else(startgg_sim_config_path);
+            spawn_score_check(app, set, effective_path);
+        }
+    }
     Ok(result)
 }
 
+/// Compare a just-completed set against its archived replays off the command
+/// path, emitting `score-discrepancy` when they disagree.
+fn spawn_score_check(app: &AppHandle, set: StartggSimSet, config_path: PathBuf) {
+    let app = app.clone();
+    std::thread::spawn(move || match reconcile_sim_set(&set, &config_path) {
+        Ok(result) if !result.matches => {
+            let _ = app.emit("score-discrepancy", &result);
+        }
+        Ok(_) => {}
+        Err(e) => tracing::debug!("Score check skipped for set {}: {}", set.id, e),
+    });
+}
+
+fn reconcile_sim_set(set: &StartggSimSet, config_path: &Path) -> Result<ScoreReconciliation, String> {
+    let replays = read_bracket_set_replay_paths(&config_path.to_string_lossy(), set.id)?;
+    if replays.is_empty() {
+        return Err(format!("No archived replays found for set {}.", set.id));
+    }
+    let replays = sort_replay_paths_by_start_time(replays);
+    Ok(reconcile_set_scores(set, &replays))
+}
+
 /// Lock the mutex, then call `f` with `(&mut TestModeState, now_ms)` — for reset
 /// commands that bypass init and create a new sim.
-fn with_test_state<F, R>(test_state: &State<'_, SharedTestState>, f: F) -> Result<R, String>
+fn with_test_state<F, R>(test_state: &SharedTestState, f: F) -> Result<R, String>
 where
     F: FnOnce(&mut TestModeState, u64) -> Result<R, String>,
 {
@@ -49,13 +115,29 @@ where
     f(&mut guard, now)
 }
 
-fn check_test_mode() -> Result<(), String> {
-    if !app_test_mode_enabled() {
-        return Err("Test mode is disabled in settings.".to_string());
+pub(crate) fn check_fake_bracket() -> Result<(), String> {
+    if !test_flags().fake_bracket {
+        return Err("The simulated bracket is disabled in settings.".to_string());
+    }
+    Ok(())
+}
+
+/// Reporting commands only need the reporting flag, so a rehearsal can
+/// practice reporting against the sim while overlays show the live event.
+fn check_fake_reporting() -> Result<(), String> {
+    if !test_flags().fake_reporting {
+        return Err("Simulated reporting is disabled in settings.".to_string());
     }
     Ok(())
 }
 
+fn reporter_name(reporter: Option<String>, default: &str) -> String {
+    reporter
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
 /// Save state to persistence file, using default config path if none set
 fn save_sim_state(sim: &StartggSim, config_path: Option<&Path>) {
     let effective_path = config_path
@@ -66,24 +148,69 @@ fn save_sim_state(sim: &StartggSim, config_path: Option<&Path>) {
     }
 }
 
+/// Replace the sim with a fresh one built from `config`, dropping the
+/// state persisted next to `config_path`, and publish it.
+pub(crate) fn rebuild_sim(
+    guard: &mut TestModeState,
+    config: StartggSimConfig,
+    config_path: &Path,
+    now: u64,
+) -> Result<StartggSimState, String> {
+    if let Err(e) = StartggSim::delete_state_file(config_path) {
+        tracing::warn!("Failed to delete bracket state file: {}", e);
+    }
+    // Clear persistence flags on reset
+    guard.state_restored_from_persistence = false;
+    guard.state_config_matched = true;
+    guard.report_locks.clear();
+    let sim = replace_sim(guard, StartggSim::new(config, now)?, now);
+    Ok(sim.state(now))
+}
+
 // ── Commands ────────────────────────────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_state(
     since_ms: Option<u64>,
+    filter: Option<StartggSimSetFilter>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<StartggSimState, String> {
-    check_test_mode()?;
-    with_sim(&test_state, |sim, now| Ok(sim.state_since(now, since_ms)))
+    startgg_sim_state_inner(since_ms, filter, &test_state)
+}
+
+pub fn startgg_sim_state_inner(
+    since_ms: Option<u64>,
+    filter: Option<StartggSimSetFilter>,
+    test_state: &SharedTestState,
+) -> Result<StartggSimState, String> {
+    check_fake_bracket()?;
+    let Some(snapshot) = snapshot_or_warm(test_state)? else {
+        return Ok(StartggSimState::warming(now_ms()));
+    };
+    let mut state = snapshot.since(since_ms);
+    if let Some(filter) = filter.as_ref() {
+        filter.apply(&mut state.sets);
+    }
+    Ok(state)
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_reset(
     config_path: Option<String>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<StartggSimState, String> {
-    check_test_mode()?;
-    with_test_state(&test_state, |guard, now| {
+    startgg_sim_reset_inner(config_path, &test_state)
+}
+
+pub fn startgg_sim_reset_inner(
+    config_path: Option<String>,
+    test_state: &SharedTestState,
+) -> Result<StartggSimState, String> {
+    check_fake_bracket()?;
+    let params = json!({ "configPath": config_path.clone() });
+    audited("resetBracket", params, || with_test_state(test_state, |guard, now| {
         let resolved_path = config_path
             .as_deref()
             .map(resolve_startgg_sim_config_path);
@@ -91,74 +218,131 @@ pub fn startgg_sim_reset(
             .or_else(|| guard.startgg_config_path.clone())
             .unwrap_or_else(startgg_sim_config_path);
         let config = load_startgg_sim_config_from(&effective_path)?;
-        // Delete persisted state file on reset
-        if let Err(e) = StartggSim::delete_state_file(&effective_path) {
-            tracing::warn!("Failed to delete bracket state file: {}", e);
-        }
         if resolved_path.is_some() {
             guard.startgg_config_path = resolved_path;
         }
-        // Clear persistence flags on reset
-        guard.state_restored_from_persistence = false;
-        guard.state_config_matched = true;
-        guard.startgg_sim = Some(StartggSim::new(config, now)?);
-        let sim = guard.startgg_sim.as_mut()
-            .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
-        Ok(sim.state(now))
-    })
+        rebuild_sim(guard, config, &effective_path, now)
+    }))
 }
 
 #[tauri::command]
-pub fn startgg_sim_advance_set(set_id: u64, test_state: State<'_, SharedTestState>) -> Result<StartggSimState, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+#[specta::specta]
+pub fn startgg_sim_advance_set(
+    set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
+    test_state: State<'_, SharedTestState>,
+) -> Result<StartggSimState, String> {
+    startgg_sim_advance_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_advance_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<StartggSimState, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "advanceSet", |sim, now| {
         sim.advance_set(set_id, now)?;
         Ok(sim.state(now))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_force_winner(
     set_id: u64,
     winner_slot: u8,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<StartggSimState, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_force_winner_inner(set_id, winner_slot, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_force_winner_inner(
+    set_id: u64,
+    winner_slot: u8,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<StartggSimState, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "forceWinner", |sim, now| {
         sim.force_winner(set_id, winner_slot as usize, now)?;
         Ok(sim.state(now))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_mark_dq(
     set_id: u64,
     dq_slot: u8,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<StartggSimState, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_mark_dq_inner(set_id, dq_slot, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_mark_dq_inner(
+    set_id: u64,
+    dq_slot: u8,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<StartggSimState, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "markDq", |sim, now| {
         sim.mark_dq(set_id, dq_slot as usize, now)?;
         Ok(sim.state(now))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_state(
     since_ms: Option<u64>,
+    filter: Option<StartggSimSetFilter>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim(&test_state, |sim, now| Ok(sim.raw_response(now, since_ms)))
+    startgg_sim_raw_state_inner(since_ms, filter, &test_state)
+}
+
+pub fn startgg_sim_raw_state_inner(
+    since_ms: Option<u64>,
+    filter: Option<StartggSimSetFilter>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_bracket()?;
+    let state = match snapshot_or_warm(test_state)? {
+        Some(snapshot) => snapshot.since(since_ms),
+        None => StartggSimState::warming(now_ms()),
+    };
+    Ok(state.to_raw(filter.as_ref()))
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_reset(
     config_path: Option<String>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_test_state(&test_state, |guard, now| {
+    startgg_sim_raw_reset_inner(config_path, &test_state)
+}
+
+pub fn startgg_sim_raw_reset_inner(
+    config_path: Option<String>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_bracket()?;
+    let params = json!({ "configPath": config_path.clone() });
+    audited("resetBracket", params, || with_test_state(test_state, |guard, now| {
         let resolved_path = config_path
             .as_deref()
             .map(resolve_startgg_sim_config_path);
@@ -176,60 +360,114 @@ pub fn startgg_sim_raw_reset(
         // Clear persistence flags on reset
         guard.state_restored_from_persistence = false;
         guard.state_config_matched = true;
-        guard.startgg_sim = Some(StartggSim::new(config, now)?);
-        let sim = guard.startgg_sim.as_mut()
-            .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
+        guard.report_locks.clear();
+        let sim = replace_sim(guard, StartggSim::new(config, now)?, now);
         Ok(sim.raw_response(now, None))
-    })
+    }))
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_advance_set(
     set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim(&test_state, |sim, now| {
+    startgg_sim_raw_advance_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_advance_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "advanceSet", |sim, now| {
         sim.advance_set(set_id, now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_start_set(
     set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_start_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_start_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "startSet", |sim, now| {
         sim.start_set_manual(set_id, now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_update_scores(
     set_id: u64,
     scores: Vec<u8>,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
+    startgg_sim_raw_update_scores_inner(set_id, scores, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_update_scores_inner(
+    set_id: u64,
+    scores: Vec<u8>,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
     if scores.len() != 2 {
         return Err("Scores must include exactly two values.".to_string());
     }
-    with_sim_save(&test_state, |sim, now| {
+    with_set_report(app, test_state, set_id, &reporter, "updateScores", |sim, now| {
         sim.update_set_scores_manual(set_id, [scores[0], scores[1]], now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_apply_replay_result(
     set_id: u64,
     replay_path: String,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
+    startgg_sim_raw_apply_replay_result_inner(set_id, replay_path, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_apply_replay_result_inner(
+    set_id: u64,
+    replay_path: String,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    require_feature(FeatureFlag::AutoReport)?;
+    let reporter = reporter_name(reporter, REPLAY_REPORTER);
     let replay_path = replay_path.trim().to_string();
     if replay_path.is_empty() {
         return Err("Replay path is empty.".to_string());
@@ -245,7 +483,7 @@ pub fn startgg_sim_raw_apply_replay_result(
     let (winner_code, winner_tag) = replay_winner_identity(&resolved)?;
     let winner_tag = winner_tag.or_else(|| winner_code.as_deref().map(tag_from_code));
 
-    with_sim_save(&test_state, |sim, now| {
+    with_set_report(app, test_state, set_id, &reporter, "applyReplayResult", |sim, now| {
         let state_snapshot = sim.state(now);
         let set = state_snapshot
             .sets
@@ -277,12 +515,25 @@ pub fn startgg_sim_raw_apply_replay_result(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_step_set(
     set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_step_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_step_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "stepSet", |sim, now| {
         let outcome = sim
             .reference_outcome_for_set(set_id)
             .ok_or_else(|| "No reference outcome found for this set.".to_string())?;
@@ -323,12 +574,25 @@ pub fn startgg_sim_raw_step_set(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_finalize_reference_set(
     set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_finalize_reference_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_finalize_reference_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "finalizeReferenceSet", |sim, now| {
         let outcome = sim
             .reference_outcome_for_set(set_id)
             .ok_or_else(|| "No reference outcome found for this set.".to_string())?;
@@ -342,80 +606,176 @@ pub fn startgg_sim_raw_finalize_reference_set(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_finish_set(
     set_id: u64,
     winner_slot: u8,
     scores: Vec<u8>,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
+    startgg_sim_raw_finish_set_inner(set_id, winner_slot, scores, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_finish_set_inner(
+    set_id: u64,
+    winner_slot: u8,
+    scores: Vec<u8>,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
     if scores.len() != 2 {
         return Err("Scores must include exactly two values.".to_string());
     }
-    with_sim_save(&test_state, |sim, now| {
+    with_set_report(app, test_state, set_id, &reporter, "finishSet", |sim, now| {
         sim.finish_set_manual(set_id, winner_slot as usize, [scores[0], scores[1]], now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_complete_bracket(
+    reporter: Option<String>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_complete_bracket_inner(reporter, &test_state)
+}
+
+pub fn startgg_sim_raw_complete_bracket_inner(
+    reporter: Option<String>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    let params = json!({ "reporter": reporter });
+    // Checking the locks and completing happen under one lock, so no other
+    // reporter can take a set in between; each set completed is recorded.
+    audited("completeBracket", params, || with_test_state(test_state, |guard, now| {
+        require_sim(test_state, guard)?;
+        guard.report_locks.check_all(&reporter)?;
+        let config_path = guard.startgg_config_path.clone();
+        let TestModeState { startgg_sim, report_locks, .. } = guard;
+        let sim = startgg_sim.as_mut()
+            .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
+        let open = sim
+            .state(now)
+            .sets
+            .into_iter()
+            .filter(|set| set.state != "completed")
+            .map(|set| set.id)
+            .collect::<Vec<_>>();
         if sim.has_reference_sets() {
             sim.complete_from_reference(now)?;
         } else {
             sim.complete_all_sets(now)?;
         }
+        for set_id in open {
+            if sim.set_snapshot(set_id).is_some_and(|set| set.state == "completed") {
+                report_locks.record(set_id, &reporter, "completeBracket", sim.set_scores(set_id), now);
+            }
+        }
+        save_sim_state(sim, config_path.as_deref());
+        publish(sim.state(now));
         Ok(sim.raw_response(now, None))
-    })
+    }))
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_force_winner(
     set_id: u64,
     winner_slot: u8,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_force_winner_inner(set_id, winner_slot, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_force_winner_inner(
+    set_id: u64,
+    winner_slot: u8,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "forceWinner", |sim, now| {
         sim.force_winner(set_id, winner_slot as usize, now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_mark_dq(
     set_id: u64,
     dq_slot: u8,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_mark_dq_inner(set_id, dq_slot, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_mark_dq_inner(
+    set_id: u64,
+    dq_slot: u8,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "markDq", |sim, now| {
         sim.mark_dq(set_id, dq_slot as usize, now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_raw_reset_set(
     set_id: u64,
+    reporter: Option<String>,
+    app: AppHandle,
     test_state: State<'_, SharedTestState>,
 ) -> Result<Value, String> {
-    check_test_mode()?;
-    with_sim_save(&test_state, |sim, now| {
+    startgg_sim_raw_reset_set_inner(set_id, reporter, Some(&app), &test_state)
+}
+
+pub fn startgg_sim_raw_reset_set_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    app: Option<&AppHandle>,
+    test_state: &SharedTestState,
+) -> Result<Value, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    with_set_report(app, test_state, set_id, &reporter, "resetSet", |sim, now| {
         sim.reset_set_and_dependents(set_id, now)?;
         Ok(sim.raw_response(now, None))
     })
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_clear_persisted_state(
     test_state: State<'_, SharedTestState>,
 ) -> Result<(), String> {
-    check_test_mode()?;
+    startgg_sim_clear_persisted_state_inner(&test_state)
+}
+
+pub fn startgg_sim_clear_persisted_state_inner(
+    test_state: &SharedTestState,
+) -> Result<(), String> {
+    check_fake_bracket()?;
     let guard = test_state.lock().map_err(|e| e.to_string())?;
     let effective_path = guard.startgg_config_path
         .clone()
@@ -425,10 +785,17 @@ pub fn startgg_sim_clear_persisted_state(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn startgg_sim_persistence_status(
     test_state: State<'_, SharedTestState>,
 ) -> Result<BracketPersistenceStatus, String> {
-    check_test_mode()?;
+    startgg_sim_persistence_status_inner(&test_state)
+}
+
+pub fn startgg_sim_persistence_status_inner(
+    test_state: &SharedTestState,
+) -> Result<BracketPersistenceStatus, String> {
+    check_fake_bracket()?;
     let guard = test_state.lock().map_err(|e| e.to_string())?;
     let effective_path = guard.startgg_config_path
         .clone()
@@ -441,3 +808,92 @@ pub fn startgg_sim_persistence_status(
         state_file_exists: state_file_path.exists(),
     })
 }
+
+#[tauri::command]
+#[specta::specta]
+pub fn pending_reports(
+    test_state: State<'_, SharedTestState>,
+) -> Result<Vec<PendingReport>, String> {
+    pending_reports_inner(&test_state)
+}
+
+pub fn pending_reports_inner(
+    test_state: &SharedTestState,
+) -> Result<Vec<PendingReport>, String> {
+    check_fake_reporting()?;
+    let state = snapshot_or_warm(test_state)?;
+    let guard = test_state.lock().map_err(|e| e.to_string())?;
+    Ok(guard.report_locks.pending(state.as_deref()))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn release_set_report_lock(
+    set_id: u64,
+    reporter: Option<String>,
+    force: Option<bool>,
+    test_state: State<'_, SharedTestState>,
+) -> Result<bool, String> {
+    release_set_report_lock_inner(set_id, reporter, force.unwrap_or(false), &test_state)
+}
+
+/// Release a set's reporting lock. Only its holder may, unless `force`
+/// takes it from whoever holds it.
+pub fn release_set_report_lock_inner(
+    set_id: u64,
+    reporter: Option<String>,
+    force: bool,
+    test_state: &SharedTestState,
+) -> Result<bool, String> {
+    check_fake_reporting()?;
+    let reporter = reporter_name(reporter, MANUAL_REPORTER);
+    let params = json!({ "setId": set_id, "reporter": reporter, "force": force });
+    audited("releaseReportLock", params, || {
+        let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+        guard.report_locks.release(set_id, &reporter, force, now_ms())
+    })
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn set_report_history(
+    set_id: Option<u64>,
+    test_state: State<'_, SharedTestState>,
+) -> Result<Vec<ReportAuditEntry>, String> {
+    set_report_history_inner(set_id, &test_state)
+}
+
+pub fn set_report_history_inner(
+    set_id: Option<u64>,
+    test_state: &SharedTestState,
+) -> Result<Vec<ReportAuditEntry>, String> {
+    check_fake_reporting()?;
+    let guard = test_state.lock().map_err(|e| e.to_string())?;
+    Ok(guard.report_locks.history(set_id))
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn reconcile_set(
+    set_id: u64,
+    app: AppHandle,
+    test_state: State<'_, SharedTestState>,
+) -> Result<ScoreReconciliation, String> {
+    check_fake_reporting()?;
+    let (set, config_path) = {
+        let guard = test_state.lock().map_err(|e| e.to_string())?;
+        require_sim(&test_state, &guard)?;
+        let config_path = guard.startgg_config_path
+            .clone()
+            .unwrap_or_else(startgg_sim_config_path);
+        let sim = guard.startgg_sim.as_ref()
+            .ok_or_else(|| "Start.gg sim failed to initialize.".to_string())?;
+        let set = sim.set_snapshot(set_id).ok_or_else(|| "Set not found.".to_string())?;
+        (set, config_path)
+    };
+    let result = reconcile_sim_set(&set, &config_path)?;
+    if !result.matches {
+        let _ = app.emit("score-discrepancy", &result);
+    }
+    Ok(result)
+}
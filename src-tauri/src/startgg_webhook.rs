@@ -0,0 +1,231 @@
+use crate::config::*;
+use axum::{
+    body::Bytes,
+    extract::Query,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Router,
+};
+use serde::Serialize;
+use serde_json::Value;
+use specta::Type;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+};
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+/// Webhooks count as healthy for this long after the last one; after that
+/// the poller goes back to its normal interval.
+pub const WEBHOOK_QUIET_MS: u64 = 5 * 60_000;
+/// Safety-net poll interval while webhooks are healthy.
+pub const WEBHOOK_BACKUP_POLL_MS: u64 = 120_000;
+/// Webhooks never refresh start.gg more often than this, however many
+/// arrive; a burst lands as one refresh when the interval is up.
+pub const WEBHOOK_MIN_REFRESH_MS: u64 = 5_000;
+
+const SECRET_HEADER: &str = "x-webhook-secret";
+
+static LAST_WEBHOOK_MS: AtomicU64 = AtomicU64::new(0);
+static WEBHOOK_SIGNAL: WebhookSignal = WebhookSignal::new();
+
+/// Set when a payload arrives; the poller takes it and refreshes, at most
+/// once per `WEBHOOK_MIN_REFRESH_MS`.
+struct WebhookSignal {
+    pending: AtomicBool,
+    last_refresh_ms: AtomicU64,
+}
+
+impl WebhookSignal {
+    const fn new() -> Self {
+        Self { pending: AtomicBool::new(false), last_refresh_ms: AtomicU64::new(0) }
+    }
+
+    fn raise(&self) {
+        self.pending.store(true, Ordering::SeqCst);
+    }
+
+    /// True when a webhook is waiting and the last webhook refresh was long
+    /// enough ago. A throttled signal stays pending for the next check.
+    fn take(&self, now_ms: u64) -> bool {
+        let last = self.last_refresh_ms.load(Ordering::SeqCst);
+        if last > 0 && now_ms.saturating_sub(last) < WEBHOOK_MIN_REFRESH_MS {
+            return false;
+        }
+        if !self.pending.swap(false, Ordering::SeqCst) {
+            return false;
+        }
+        self.last_refresh_ms.store(now_ms, Ordering::SeqCst);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggWebhookStatus {
+    pub enabled: bool,
+    pub bind_addr: String,
+    pub last_received_ms: Option<u64>,
+    /// A webhook arrived within the quiet window, so polling is slowed down
+    pub active: bool,
+}
+
+/// True when webhooks arrived since the last webhook refresh and another
+/// refresh is allowed.
+pub fn take_webhook_signal(now_ms: u64) -> bool {
+    WEBHOOK_SIGNAL.take(now_ms)
+}
+
+pub fn webhooks_active(now_ms: u64) -> bool {
+    let last = LAST_WEBHOOK_MS.load(Ordering::SeqCst);
+    last > 0 && now_ms.saturating_sub(last) < WEBHOOK_QUIET_MS
+}
+
+/// Set ids mentioned anywhere in a payload, for the log line. Start.gg and
+/// relays nest them differently, so this just walks the JSON.
+fn payload_set_ids(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let is_set_id = key == "setId" || (key == "id" && map.contains_key("slots"));
+                if is_set_id {
+                    match child {
+                        Value::String(id) => out.push(id.clone()),
+                        Value::Number(id) => out.push(id.to_string()),
+                        _ => {}
+                    }
+                }
+                payload_set_ids(child, out);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| payload_set_ids(item, out)),
+        _ => {}
+    }
+}
+
+/// Compare the configured secret with the one a request sent, in constant
+/// time so the response time doesn't give it away. No secret matches nothing.
+fn secret_matches(expected: &str, provided: &str) -> bool {
+    let expected = expected.trim();
+    !expected.is_empty() && bool::from(expected.as_bytes().ct_eq(provided.as_bytes()))
+}
+
+async fn receive_webhook(
+    headers: HeaderMap,
+    Query(query): Query<HashMap<String, String>>,
+    body: Bytes,
+) -> Response {
+    let config = cached_config().unwrap_or_default();
+    if !config.startgg_webhook_enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let provided = headers
+        .get(SECRET_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| query.get("secret").map(String::as_str))
+        .unwrap_or_default();
+    if !secret_matches(&config.startgg_webhook_secret, provided) {
+        warn!("Rejected start.gg webhook with a bad secret");
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    // Relays don't always send a JSON content type, so parse the body here.
+    let Ok(payload) = serde_json::from_slice::<Value>(&body) else {
+        return StatusCode::BAD_REQUEST.into_response();
+    };
+    let mut set_ids = Vec::new();
+    payload_set_ids(&payload, &mut set_ids);
+    if set_ids.is_empty() {
+        info!("Start.gg webhook received");
+    } else {
+        info!("Start.gg webhook received for set(s) {}", set_ids.join(", "));
+    }
+    LAST_WEBHOOK_MS.store(now_ms(), Ordering::SeqCst);
+    WEBHOOK_SIGNAL.raise();
+    StatusCode::ACCEPTED.into_response()
+}
+
+/// Listen for start.gg webhook POSTs, either straight from start.gg through
+/// a public URL or forwarded by a relay. Each one triggers a refresh through
+/// the same path as polling. Won't start without a secret: anyone who can
+/// reach the port could otherwise spend the start.gg rate limit.
+pub async fn start_webhook_server(addr: String) {
+    if cached_config().unwrap_or_default().startgg_webhook_secret.trim().is_empty() {
+        error!("Start.gg webhook receiver not started: set startggWebhookSecret first");
+        return;
+    }
+    let app = Router::new().route("/startgg/webhook", post(receive_webhook));
+    let listener = match TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Start.gg webhook server failed to bind {addr}: {e}");
+            return;
+        }
+    };
+    info!("Start.gg webhook receiver listening at http://{addr}/startgg/webhook");
+    if let Err(e) = axum::serve(listener, app).await {
+        error!("Start.gg webhook server error: {e}");
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+pub fn startgg_webhook_status() -> StartggWebhookStatus {
+    let config = cached_config().unwrap_or_default();
+    let last = LAST_WEBHOOK_MS.load(Ordering::SeqCst);
+    StartggWebhookStatus {
+        enabled: config.startgg_webhook_enabled,
+        bind_addr: config.startgg_webhook_bind,
+        last_received_ms: (last > 0).then_some(last),
+        active: webhooks_active(now_ms()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn finds_set_ids_in_nested_payloads() {
+        let payload = json!({
+            "event": "set.updated",
+            "data": { "set": { "id": 7001, "slots": [] } },
+            "batch": [{ "setId": "preview_1_2" }]
+        });
+        let mut ids = Vec::new();
+        payload_set_ids(&payload, &mut ids);
+        ids.sort();
+        assert_eq!(ids, vec!["7001".to_string(), "preview_1_2".to_string()]);
+    }
+
+    #[test]
+    fn secrets_must_match_exactly_and_be_set() {
+        assert!(secret_matches(" hunter2 ", "hunter2"));
+        assert!(!secret_matches("hunter2", "hunter3"));
+        assert!(!secret_matches("hunter2", "hunter"));
+        assert!(!secret_matches("hunter2", ""));
+        assert!(!secret_matches("", ""));
+        assert!(!secret_matches("  ", "  "));
+    }
+
+    #[test]
+    fn webhook_refreshes_are_throttled_and_bursts_collapse() {
+        let signal = WebhookSignal::new();
+        assert!(!signal.take(1_000));
+
+        signal.raise();
+        signal.raise();
+        assert!(signal.take(1_000));
+        assert!(!signal.take(1_250));
+
+        // Arrived inside the interval: held until it's up, then taken once.
+        signal.raise();
+        assert!(!signal.take(1_250));
+        assert!(!signal.take(1_000 + WEBHOOK_MIN_REFRESH_MS - 1));
+        assert!(signal.take(1_000 + WEBHOOK_MIN_REFRESH_MS));
+        assert!(!signal.take(1_000 + 3 * WEBHOOK_MIN_REFRESH_MS));
+    }
+}
@@ -0,0 +1,356 @@
+use crate::config::*;
+use crate::feature_flags::feature_enabled;
+use crate::dolphin::{launch_dolphin_for_setup_internal, slippi_launches_dolphin};
+use crate::slippi::{cdp_targets, launch_slippi_app, slippi_devtools_port};
+use crate::types::*;
+use serde_json::Value;
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    process::{Command, Stdio},
+    sync::atomic::{AtomicBool, Ordering},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter, Manager};
+use tungstenite::Message;
+
+const SLIPPI_DEVTOOLS_TIMEOUT: Duration = Duration::from_secs(45);
+const OBS_WEBSOCKET_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Set while an orchestration thread is running so a second click doesn't
+/// launch everything twice.
+static STARTUP_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Collects step results and mirrors each one to the frontend as a progress
+/// event (`startup-progress` or `shutdown-progress`).
+pub(crate) struct StartupProgress {
+    app: AppHandle,
+    event: &'static str,
+    pub steps: Vec<StartupStep>,
+}
+
+impl StartupProgress {
+    pub fn new(app: AppHandle, event: &'static str) -> Self {
+        StartupProgress { app, event, steps: Vec::new() }
+    }
+
+    pub fn emit(&mut self, step: &str, status: &str, message: Option<String>) {
+        let entry = StartupStep {
+            step: step.to_string(),
+            status: status.to_string(),
+            message,
+            at_ms: now_ms(),
+        };
+        let _ = self.app.emit(self.event, &entry);
+        if status != "running" {
+            self.steps.push(entry);
+        }
+    }
+
+    pub fn finish(&mut self, step: &str, result: Result<Option<String>, String>) {
+        match result {
+            Ok(message) => self.emit(step, "ok", message),
+            Err(err) => self.emit(step, "failed", Some(err)),
+        }
+    }
+}
+
+pub(crate) fn wait_until<F: FnMut() -> bool>(timeout: Duration, mut ready: F) -> bool {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if ready() {
+            return true;
+        }
+        if Instant::now() >= deadline {
+            return false;
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+// ── Slippi ──────────────────────────────────────────────────────────────
+
+fn devtools_ready(port: u16) -> bool {
+    cdp_targets(port).map(|targets| !targets.is_empty()).unwrap_or(false)
+}
+
+fn start_slippi(progress: &mut StartupProgress) {
+    let port = slippi_devtools_port();
+    if devtools_ready(port) {
+        progress.emit(
+            "slippi",
+            "skipped",
+            Some(format!("Slippi Launcher already running (DevTools port {port}).")),
+        );
+        return;
+    }
+    progress.emit("slippi", "running", Some("Launching Slippi Launcher.".to_string()));
+    let result = launch_slippi_app().and_then(|_| {
+        if wait_until(SLIPPI_DEVTOOLS_TIMEOUT, || devtools_ready(port)) {
+            Ok(Some(format!("DevTools port {port} is up.")))
+        } else {
+            Err(format!(
+                "Slippi Launcher did not open DevTools port {port} within {}s.",
+                SLIPPI_DEVTOOLS_TIMEOUT.as_secs()
+            ))
+        }
+    });
+    progress.finish("slippi", result);
+}
+
+// ── OBS ─────────────────────────────────────────────────────────────────
+
+/// Connect to obs-websocket and read its Hello message. Returns the server
+/// version when it answers.
+pub fn check_obs_websocket(url: &str) -> Result<String, String> {
+    let host = url
+        .trim()
+        .trim_start_matches("ws://")
+        .trim_start_matches("wss://")
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    let addr = host
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve {host}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("resolve {host}: no address"))?;
+    // Fail fast when nothing is listening instead of waiting on the
+    // websocket handshake.
+    TcpStream::connect_timeout(&addr, Duration::from_secs(2))
+        .map_err(|e| format!("connect {url}: {e}"))?;
+
+    let (mut socket, _) = tungstenite::connect(url).map_err(|e| format!("obs-websocket connect {url}: {e}"))?;
+    let hello = loop {
+        let msg = socket.read().map_err(|e| format!("obs-websocket read: {e}"))?;
+        if let Message::Text(txt) = msg {
+            break serde_json::from_str::<Value>(&txt).map_err(|e| format!("parse obs-websocket hello: {e}"))?;
+        }
+    };
+    let _ = socket.close(None);
+    if hello.get("op").and_then(|v| v.as_u64()) != Some(0) {
+        return Err(format!("{url} did not answer with an obs-websocket Hello."));
+    }
+    let version = hello
+        .get("d")
+        .and_then(|d| d.get("obsWebSocketVersion"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    Ok(version)
+}
+
+fn launch_obs(obs_path: &str) -> Result<(), String> {
+    let path = resolve_repo_path(obs_path);
+    let mut cmd = Command::new(&path);
+    cmd.stdin(Stdio::null());
+    cmd.stdout(Stdio::null());
+    cmd.stderr(Stdio::null());
+    if let Some(dir) = path.parent().filter(|dir| dir.is_dir()) {
+        cmd.current_dir(dir);
+    }
+    cmd.spawn().map_err(|e| format!("launch OBS: {e}"))?;
+    Ok(())
+}
+
+fn start_obs(progress: &mut StartupProgress, config: &AppConfig) {
+    if !feature_enabled(FeatureFlag::Obs) {
+        progress.emit("obs", "skipped", Some("OBS is disabled in feature flags.".to_string()));
+        return;
+    }
+    let url = config.obs_websocket_url.trim();
+    if url.is_empty() {
+        progress.emit("obs", "skipped", Some("No obs-websocket URL configured.".to_string()));
+        return;
+    }
+    progress.emit("obs", "running", Some(format!("Checking obs-websocket at {url}.")));
+    if let Ok(version) = check_obs_websocket(url) {
+        progress.emit("obs", "ok", Some(format!("obs-websocket {version} reachable at {url}.")));
+        return;
+    }
+
+    let obs_path = config.obs_path.trim();
+    if obs_path.is_empty() {
+        progress.emit(
+            "obs",
+            "failed",
+            Some(format!("obs-websocket not reachable at {url} and no OBS path is configured.")),
+        );
+        return;
+    }
+    progress.emit("obs", "running", Some("Launching OBS.".to_string()));
+    let result = launch_obs(obs_path).and_then(|_| {
+        let mut version = None;
+        let ready = wait_until(OBS_WEBSOCKET_TIMEOUT, || {
+            version = check_obs_websocket(url).ok();
+            version.is_some()
+        });
+        if ready {
+            Ok(Some(format!(
+                "obs-websocket {} reachable at {url}.",
+                version.unwrap_or_default()
+            )))
+        } else {
+            Err(format!(
+                "OBS started but obs-websocket did not answer at {url} within {}s.",
+                OBS_WEBSOCKET_TIMEOUT.as_secs()
+            ))
+        }
+    });
+    progress.finish("obs", result);
+}
+
+// ── Dolphin ─────────────────────────────────────────────────────────────
+
+fn start_dolphins(progress: &mut StartupProgress, store: &SharedSetupStore, setup_ids: Option<Vec<u32>>) {
+    if slippi_launches_dolphin() {
+        progress.emit(
+            "dolphin",
+            "skipped",
+            Some("Slippi Launcher starts Dolphin when a stream is watched.".to_string()),
+        );
+        return;
+    }
+    let (targets, running) = {
+        let guard = store.lock().unwrap_or_else(|e| e.into_inner());
+        let known = guard.setups.iter().map(|s| s.id).collect::<Vec<_>>();
+        let targets = setup_ids.unwrap_or_else(|| known.clone());
+        let running = targets
+            .iter()
+            .filter(|id| guard.processes.contains_key(id) || guard.process_pids.contains_key(id))
+            .copied()
+            .collect::<Vec<_>>();
+        let missing = targets.iter().filter(|id| !known.contains(id)).copied().collect::<Vec<_>>();
+        for id in missing {
+            progress.emit(&format!("dolphin-{id}"), "failed", Some("Setup not found.".to_string()));
+        }
+        let targets = targets.into_iter().filter(|id| known.contains(id)).collect::<Vec<_>>();
+        (targets, running)
+    };
+    if targets.is_empty() {
+        progress.emit("dolphin", "skipped", Some("No setups selected.".to_string()));
+        return;
+    }
+
+    for id in targets {
+        let step = format!("dolphin-{id}");
+        if running.contains(&id) {
+            progress.emit(&step, "skipped", Some(format!("Dolphin already running for setup {id}.")));
+            continue;
+        }
+        progress.emit(&step, "running", Some(format!("Launching Dolphin for setup {id}.")));
+        let result = launch_dolphin_for_setup_internal(id).and_then(|child| {
+            let mut guard = store.lock().map_err(|e| e.to_string())?;
+            guard.processes.insert(id, child);
+            Ok(None)
+        });
+        progress.finish(&step, result);
+    }
+}
+
+// ── Orchestration ───────────────────────────────────────────────────────
+
+fn run_startup(app: AppHandle, setup_ids: Option<Vec<u32>>) -> StartupReport {
+    let started_at_ms = now_ms();
+    let store = app.state::<SharedSetupStore>().inner().clone();
+    let mut progress = StartupProgress::new(app.clone(), "startup-progress");
+
+    let config = match cached_config() {
+        Ok(config) => config,
+        Err(err) => {
+            progress.emit("config", "failed", Some(err));
+            AppConfig::default()
+        }
+    };
+
+    // A previous end-of-day shutdown may have paused polling.
+    crate::startgg::set_startgg_polling_suspended(false);
+    start_slippi(&mut progress);
+    start_obs(&mut progress, &config);
+    start_dolphins(&mut progress, &store, setup_ids);
+
+    StartupReport {
+        ok: progress.steps.iter().all(|step| step.status != "failed"),
+        steps: progress.steps,
+        started_at_ms,
+        finished_at_ms: now_ms(),
+    }
+}
+
+/// Bring up the capture chain in order: Slippi Launcher (and its DevTools
+/// port), OBS (via obs-websocket), then Dolphin for the selected setups.
+/// Runs in the background; progress is reported through `startup-progress`
+/// events and the final report through `startup-complete`.
+#[tauri::command]
+#[specta::specta]
+pub fn orchestrate_startup(setup_ids: Option<Vec<u32>>, app: AppHandle) -> Result<(), String> {
+    if STARTUP_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("Startup is already in progress.".to_string());
+    }
+    std::thread::spawn(move || {
+        let report = run_startup(app.clone(), setup_ids);
+        STARTUP_RUNNING.store(false, Ordering::SeqCst);
+        if !report.ok {
+            tracing::warn!("Startup finished with failed steps");
+        }
+        let _ = app.emit("startup-complete", &report);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::{net::TcpListener, thread};
+
+    /// A websocket server that greets one client with `hello`.
+    fn greeting_server(hello: Value) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            let Ok((stream, _)) = listener.accept() else {
+                return;
+            };
+            let Ok(mut socket) = tungstenite::accept(stream) else {
+                return;
+            };
+            let _ = socket.send(Message::text(hello.to_string()));
+            while socket.read().is_ok() {}
+        });
+        url
+    }
+
+    #[test]
+    fn obs_websocket_answers_with_its_version() {
+        let url = greeting_server(json!({ "op": 0, "d": { "obsWebSocketVersion": "5.4.2", "rpcVersion": 1 } }));
+        assert_eq!(check_obs_websocket(&url).unwrap(), "5.4.2");
+    }
+
+    #[test]
+    fn a_websocket_that_is_not_obs_is_rejected() {
+        let url = greeting_server(json!({ "op": 5, "d": {} }));
+        let err = check_obs_websocket(&url).unwrap_err();
+        assert!(err.contains("did not answer with an obs-websocket Hello"), "{err}");
+    }
+
+    #[test]
+    fn obs_websocket_fails_fast_when_nothing_listens() {
+        let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+        let started = Instant::now();
+        assert!(check_obs_websocket(&format!("ws://{addr}")).is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn waiting_stops_once_ready_or_at_the_timeout() {
+        let mut polls = 0;
+        assert!(wait_until(Duration::from_secs(5), || {
+            polls += 1;
+            polls == 2
+        }));
+        assert_eq!(polls, 2);
+        assert!(!wait_until(Duration::ZERO, || false));
+    }
+}
@@ -0,0 +1,118 @@
+use crate::types::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+/// Longest delay a setup can be given.
+pub const MAX_STREAM_DELAY_SECS: u32 = 600;
+/// States kept per setup; overlays poll a few times a second, and only
+/// changes are kept.
+const MAX_BUFFERED: usize = 2_000;
+
+static BUFFERS: Mutex<Option<HashMap<u32, DelayBuffer>>> = Mutex::new(None);
+
+/// A setup's recent overlay states, oldest first, each with when it was
+/// first seen.
+#[derive(Default)]
+struct DelayBuffer {
+    states: VecDeque<(u64, String, OverlayState)>,
+}
+
+impl DelayBuffer {
+    fn push(&mut self, at_ms: u64, state: &OverlayState) {
+        let key = serde_json::to_string(state).unwrap_or_default();
+        if self.states.back().is_some_and(|(_, last, _)| *last == key) {
+            return;
+        }
+        self.states.push_back((at_ms, key, state.clone()));
+        while self.states.len() > MAX_BUFFERED {
+            self.states.pop_front();
+        }
+    }
+
+    /// The state viewers `delay_ms` behind see at `now_ms`; states older
+    /// than it are dropped. Until the buffer is `delay_ms` deep the oldest
+    /// state is held.
+    fn delayed(&mut self, now_ms: u64, delay_ms: u64) -> Option<OverlayState> {
+        let cutoff = now_ms.saturating_sub(delay_ms);
+        while self.states.get(1).is_some_and(|(at, _, _)| *at <= cutoff) {
+            self.states.pop_front();
+        }
+        self.states.front().map(|(_, _, state)| state.clone())
+    }
+}
+
+/// Record each setup's state and, unless `live`, swap in the state from
+/// its stream delay ago. Operator views pass `live` and stay real time.
+pub fn delay_overlays(payload: &mut AllSetupsState, setups: &[Setup], now_ms: u64, live: bool) {
+    let mut guard = BUFFERS.lock().unwrap_or_else(|e| e.into_inner());
+    let buffers = guard.get_or_insert_with(HashMap::new);
+    buffers.retain(|id, _| setups.iter().any(|setup| setup.id == *id && setup.stream_delay_secs > 0));
+    for setup in setups.iter().filter(|setup| setup.stream_delay_secs > 0) {
+        let idx = (setup.id as usize).checked_sub(1);
+        let Some(overlay) = idx.and_then(|idx| payload.setups.get_mut(idx)) else {
+            continue;
+        };
+        let buffer = buffers.entry(setup.id).or_default();
+        buffer.push(now_ms, overlay);
+        if live {
+            continue;
+        }
+        if let Some(delayed) = buffer.delayed(now_ms, u64::from(setup.stream_delay_secs) * 1000) {
+            *overlay = delayed;
+        }
+    }
+}
+
+/// Whether a set that finished at `completed_at_ms` has reached viewers of
+/// a stream `delay_secs` behind.
+pub fn revealed(completed_at_ms: Option<u64>, delay_secs: u32, now_ms: u64) -> bool {
+    !completed_at_ms.is_some_and(|at| at + u64::from(delay_secs) * 1000 > now_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::default_overlay_state;
+
+    fn scored(p1: u32) -> OverlayState {
+        let mut state = default_overlay_state(1);
+        state.p1.score = p1;
+        state
+    }
+
+    /// Scores 0 from 0 ms, 1 from 1 s and 2 from 4 s.
+    fn buffer() -> DelayBuffer {
+        let mut buffer = DelayBuffer::default();
+        buffer.push(0, &scored(0));
+        buffer.push(500, &scored(0));
+        buffer.push(1_000, &scored(1));
+        buffer.push(4_000, &scored(2));
+        buffer
+    }
+
+    #[test]
+    fn only_changes_are_buffered() {
+        let buffer = buffer();
+        assert_eq!(buffer.states.iter().map(|(at, _, _)| *at).collect::<Vec<_>>(), vec![0, 1_000, 4_000]);
+    }
+
+    #[test]
+    fn viewers_see_the_state_from_their_delay_ago() {
+        let mut buffer = buffer();
+        let delay = 3_000;
+        assert_eq!(buffer.delayed(2_000, delay).map(|s| s.p1.score), Some(0));
+        assert_eq!(buffer.delayed(4_500, delay).map(|s| s.p1.score), Some(1));
+        assert_eq!(buffer.states.len(), 2);
+        assert_eq!(buffer.delayed(7_000, delay).map(|s| s.p1.score), Some(2));
+    }
+
+    #[test]
+    fn results_are_revealed_once_the_delay_has_passed() {
+        assert!(!revealed(Some(10_000), 5, 14_999));
+        assert!(revealed(Some(10_000), 5, 15_000));
+        assert!(revealed(Some(10_000), 0, 10_000));
+        assert!(revealed(None, 5, 0));
+    }
+}
@@ -0,0 +1,147 @@
+use crate::config::normalize_slippi_code;
+use crate::types::{Setup, SlippiStream};
+
+/// Stream ids built from connect codes start with this, so they can't be
+/// mistaken for the launcher's DOM card ids.
+pub const CODES_ID_PREFIX: &str = "codes:";
+
+/// An id for a spectate card that survives launcher refreshes: the card's
+/// connect codes, normalized, sorted and joined. `None` without any code.
+pub fn stable_stream_id<S: AsRef<str>>(codes: &[S]) -> Option<String> {
+    let mut codes = codes
+        .iter()
+        .filter_map(|code| normalize_slippi_code(code.as_ref()))
+        .collect::<Vec<_>>();
+    if codes.is_empty() {
+        return None;
+    }
+    codes.sort();
+    codes.dedup();
+    Some(format!("{CODES_ID_PREFIX}{}", codes.join("|")))
+}
+
+/// The connect codes in a stable id; empty for anything else, such as a
+/// DOM card id stored before stable ids existed.
+pub fn stream_id_codes(id: &str) -> Vec<String> {
+    id.strip_prefix(CODES_ID_PREFIX)
+        .map(|codes| codes.split('|').filter(|code| !code.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The stable id for a stored stream, from its id if it already is one,
+/// otherwise from the codes it carries.
+fn stable_id_of(stream: &SlippiStream) -> Option<String> {
+    if stream.id.starts_with(CODES_ID_PREFIX) {
+        return Some(stream.id.clone());
+    }
+    let codes = [stream.p1_code.as_deref(), stream.p2_code.as_deref()];
+    stable_stream_id(&codes.into_iter().flatten().collect::<Vec<_>>())
+}
+
+/// Point assignments whose stream id no longer shows up in `streams` at
+/// the card they now belong to: the same connect codes, or, for ids from
+/// before stable ids, the same DOM card id. Returns the setups changed.
+pub fn migrate_assignments(setups: &mut [Setup], streams: &[SlippiStream]) -> Vec<u32> {
+    let mut migrated = Vec::new();
+    for setup in setups.iter_mut() {
+        let Some(assigned) = setup.assigned_stream.as_mut() else {
+            continue;
+        };
+        if streams.iter().any(|stream| stream.id == assigned.id) {
+            continue;
+        }
+        let stable_id = stable_id_of(assigned);
+        let found = streams
+            .iter()
+            .find(|stream| stable_id.as_deref() == Some(stream.id.as_str()))
+            .or_else(|| streams.iter().find(|stream| stream.card_id.as_deref() == Some(assigned.id.as_str())));
+        let Some(found) = found else {
+            continue;
+        };
+        tracing::info!("Setup {}: stream {} is now {}", setup.id, assigned.id, found.id);
+        // Keep the old id as the card to try if the codes can't be found.
+        if assigned.card_id.is_none() && !assigned.id.starts_with(CODES_ID_PREFIX) {
+            assigned.card_id = Some(assigned.id.clone());
+        }
+        assigned.id = found.id.clone();
+        if found.card_id.is_some() {
+            assigned.card_id = found.card_id.clone();
+        }
+        migrated.push(setup.id);
+    }
+    migrated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(id: &str, card_id: Option<&str>, p1_code: Option<&str>) -> SlippiStream {
+        SlippiStream {
+            id: id.to_string(),
+            p1_code: p1_code.map(str::to_string),
+            card_id: card_id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    fn setup(id: u32, stream: SlippiStream) -> Setup {
+        Setup { assigned_stream: Some(stream), ..Setup::new(id) }
+    }
+
+    fn streams() -> Vec<SlippiStream> {
+        vec![
+            stream("codes:AAA#1", Some("card-0"), Some("AAA#1")),
+            stream("codes:CCC#3", Some("card-1"), Some("CCC#3")),
+            stream("codes:DDD#4", Some("card-9"), Some("DDD#4")),
+        ]
+    }
+
+    #[test]
+    fn stable_ids_are_normalized_sorted_and_deduplicated() {
+        assert_eq!(
+            stable_stream_id(&["bbb#2", " AAA#1 ", "BBB#2"]).as_deref(),
+            Some("codes:AAA#1|BBB#2")
+        );
+        assert_eq!(stable_stream_id::<&str>(&[]), None);
+    }
+
+    #[test]
+    fn codes_are_read_back_only_from_stable_ids() {
+        assert_eq!(stream_id_codes("codes:AAA#1|BBB#2"), vec!["AAA#1", "BBB#2"]);
+        assert!(stream_id_codes("card-3").is_empty());
+    }
+
+    #[test]
+    fn an_old_card_id_migrates_to_the_card_that_had_it() {
+        let mut setups = vec![setup(1, stream("card-0", None, None))];
+        assert_eq!(migrate_assignments(&mut setups, &streams()), vec![1]);
+        let assigned = setups[0].assigned_stream.clone().unwrap();
+        assert_eq!(assigned.id, "codes:AAA#1");
+        assert_eq!(assigned.card_id.as_deref(), Some("card-0"));
+    }
+
+    #[test]
+    fn an_old_card_id_with_codes_migrates_to_the_card_showing_them() {
+        let mut setups = vec![setup(2, stream("card-7", None, Some("ccc#3")))];
+        assert_eq!(migrate_assignments(&mut setups, &streams()), vec![2]);
+        let assigned = setups[0].assigned_stream.clone().unwrap();
+        assert_eq!(assigned.id, "codes:CCC#3");
+        assert_eq!(assigned.card_id.as_deref(), Some("card-1"));
+    }
+
+    #[test]
+    fn stable_ids_still_listed_are_left_alone() {
+        // Its card moved, but the codes still match.
+        let mut setups = vec![setup(3, stream("codes:DDD#4", Some("card-2"), Some("DDD#4")))];
+        assert!(migrate_assignments(&mut setups, &streams()).is_empty());
+        assert_eq!(setups[0].assigned_stream.clone().unwrap().card_id.as_deref(), Some("card-2"));
+    }
+
+    #[test]
+    fn streams_that_are_gone_keep_their_assignment() {
+        let mut setups = vec![setup(4, stream("card-5", None, Some("EEE#5")))];
+        assert!(migrate_assignments(&mut setups, &streams()).is_empty());
+        assert_eq!(setups[0].assigned_stream.clone().unwrap().id, "card-5");
+    }
+}
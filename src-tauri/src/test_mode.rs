@@ -1,7 +1,9 @@
 use crate::config::*;
 use crate::types::*;
 use crate::replay::*;
+use crate::connect_codes::connect_codes_from_bytes;
 use crate::dolphin::stop_child_process;
+use crate::spoof_latency::{write_replay_with_latency, LatencyProfile};
 use crate::startgg::{init_startgg_sim, build_bracket_replay_map, read_bracket_set_replay_paths};
 use chrono::{DateTime, Local};
 use serde_json::{json, Value};
@@ -10,10 +12,10 @@ use std::{
     env,
     fs,
     io::{BufRead, BufReader},
-    path::PathBuf,
+    path::{Path, PathBuf},
     process::{Command, ChildStdout, ChildStderr, Stdio},
     thread::sleep,
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime},
 };
 use tauri::{Emitter, Manager, State};
 
@@ -34,6 +36,12 @@ pub fn replay_spoof_mode() -> ReplaySpoofMode {
         if matches!(normalized.as_str(), "stream" | "realtime" | "real-time") {
             return ReplaySpoofMode::Stream;
         }
+        if matches!(normalized.as_str(), "latency" | "bursty" | "spectate") {
+            return ReplaySpoofMode::Latency;
+        }
+    }
+    if LatencyProfile::from_env().is_enabled() {
+        return ReplaySpoofMode::Latency;
     }
     ReplaySpoofMode::Stream
 }
@@ -109,6 +117,10 @@ pub fn test_mode_streams() -> Result<Vec<SlippiStream>, String> {
                 is_playing: Some(false),
                 source: Some("mock".to_string()),
                 startgg_set: None,
+                p1_character: None,
+                card_id: None,
+                viewer_count: None,
+                broadcast_status: None,
             },
             SlippiStream {
                 id: "mock-2".to_string(),
@@ -122,6 +134,10 @@ pub fn test_mode_streams() -> Result<Vec<SlippiStream>, String> {
                 is_playing: Some(false),
                 source: Some("mock".to_string()),
                 startgg_set: None,
+                p1_character: None,
+                card_id: None,
+                viewer_count: None,
+                broadcast_status: None,
             },
             SlippiStream {
                 id: "mock-3".to_string(),
@@ -135,12 +151,72 @@ pub fn test_mode_streams() -> Result<Vec<SlippiStream>, String> {
                 is_playing: Some(false),
                 source: Some("mock".to_string()),
                 startgg_set: None,
+                p1_character: None,
+                card_id: None,
+                viewer_count: None,
+                broadcast_status: None,
             },
         ]);
     }
     build_test_streams().map(|items| items.into_iter().map(|item| item.stream).collect())
 }
 
+/// File in a test folder that pins the folder's player instead of
+/// inferring it from the replays.
+const TEST_PLAYER_PROFILE_FILE: &str = "player.json";
+
+fn load_test_player_profile(folder: &Path) -> Result<Option<TestPlayerProfile>, String> {
+    let path = folder.join(TEST_PLAYER_PROFILE_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let data = fs::read_to_string(&path).map_err(|e| format!("read player profile {}: {e}", path.display()))?;
+    let profile = serde_json::from_str::<TestPlayerProfile>(&data)
+        .map_err(|e| format!("parse player profile {}: {e}", path.display()))?;
+    Ok(Some(profile))
+}
+
+/// Order `primary`'s replays so consecutive games are against different
+/// opponents where possible: one game per opponent, then the next round.
+/// Replays `primary` isn't in are dropped unless none include them.
+fn cycle_replays_by_opponent(primary: &str, replays: &[PathBuf]) -> Vec<(PathBuf, Option<String>)> {
+    let primary_norm = normalize_slippi_code(primary);
+    let mut groups: Vec<(Option<String>, Vec<PathBuf>)> = Vec::new();
+    for replay in replays {
+        let codes = fs::read(replay).map(|bytes| connect_codes_from_bytes(&bytes).codes).unwrap_or_default();
+        let normalized = codes.iter().filter_map(|code| normalize_slippi_code(code)).collect::<Vec<_>>();
+        if primary_norm.is_some() && !normalized.iter().any(|code| Some(code) == primary_norm.as_ref()) {
+            continue;
+        }
+        let opponent = codes
+            .iter()
+            .find(|code| normalize_slippi_code(code) != primary_norm)
+            .cloned();
+        let key = opponent.as_deref().and_then(normalize_slippi_code);
+        match groups
+            .iter_mut()
+            .find(|(group, _)| group.as_deref().and_then(normalize_slippi_code) == key)
+        {
+            Some((_, paths)) => paths.push(replay.clone()),
+            None => groups.push((opponent, vec![replay.clone()])),
+        }
+    }
+    if groups.is_empty() {
+        return replays.iter().map(|replay| (replay.clone(), None)).collect();
+    }
+
+    let mut out = Vec::new();
+    let rounds = groups.iter().map(|(_, paths)| paths.len()).max().unwrap_or(0);
+    for round in 0..rounds {
+        for (opponent, paths) in &groups {
+            if let Some(path) = paths.get(round) {
+                out.push((path.clone(), opponent.clone()));
+            }
+        }
+    }
+    out
+}
+
 pub fn build_test_streams() -> Result<Vec<TestStreamSpec>, String> {
     let folders = load_test_folder_paths()?;
     let mut out = Vec::new();
@@ -151,22 +227,32 @@ pub fn build_test_streams() -> Result<Vec<TestStreamSpec>, String> {
             return Err(format!("No .slp files found in {}", folder.display()));
         }
 
-        let primary = most_common_connect_code(&replays)
-            .map_err(|e| format!("{e} (folder: {})", folder.display()))?;
-        let opponent = find_opponent_code(&primary, &replays);
+        let profile = load_test_player_profile(folder)?.unwrap_or_default();
+        let primary = match profile.code.as_deref().map(str::trim).filter(|code| !code.is_empty()) {
+            Some(code) => code.to_string(),
+            None => most_common_connect_code(&replays)
+                .map_err(|e| format!("{e} (folder: {})", folder.display()))?,
+        };
+        let cycle = cycle_replays_by_opponent(&primary, &replays);
+        let (replay_path, opponent) = cycle[0].clone();
         let folder_name = folder
             .file_name()
             .and_then(|s| s.to_str())
             .map(|s| s.to_string())
             .unwrap_or_else(|| format!("folder-{}", idx + 1));
 
-        let p1_tag = Some(tag_from_code(&primary));
+        let p1_tag = profile
+            .display_name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| tag_from_code(&primary));
         let p2_tag = opponent.as_ref().map(|code| tag_from_code(code));
-        let replay_path = replays[0].clone();
         let stream = SlippiStream {
             id: format!("test-{}", folder_name),
             window_title: Some("Test Mode".to_string()),
-            p1_tag,
+            p1_tag: Some(p1_tag),
             p2_tag,
             p1_code: Some(primary),
             p2_code: opponent,
@@ -175,11 +261,16 @@ pub fn build_test_streams() -> Result<Vec<TestStreamSpec>, String> {
             is_playing: Some(false),
             source: Some(format!("test:{}", folder_name)),
             startgg_set: None,
+            p1_character: profile.character.clone(),
+            card_id: None,
+            viewer_count: None,
+            broadcast_status: None,
         };
 
         out.push(TestStreamSpec {
             stream,
             replay_path,
+            replay_paths: cycle.into_iter().map(|(path, _)| path).collect(),
         });
     }
 
@@ -196,7 +287,7 @@ pub fn build_test_replay_lookup() -> HashMap<String, PathBuf> {
         Err(_) => return out,
     };
     for item in items {
-        let TestStreamSpec { stream, replay_path } = item;
+        let TestStreamSpec { stream, replay_path, .. } = item;
         if let Some(code) = stream.p1_code {
             let key = normalize_broadcast_key(&code);
             if !key.is_empty() {
@@ -212,6 +303,7 @@ pub fn build_test_replay_lookup() -> HashMap<String, PathBuf> {
 pub fn test_mode_broadcast_streams(guard: &mut TestModeState) -> Result<Vec<SlippiStream>, String> {
     if guard.broadcast_players.is_empty() {
         guard.spoof_replays.clear();
+        guard.spoof_replay_queues.clear();
         return Ok(Vec::new());
     }
 
@@ -307,6 +399,10 @@ pub fn test_mode_broadcast_streams(guard: &mut TestModeState) -> Result<Vec<Slip
             is_playing: Some(is_playing),
             source: Some("broadcast".to_string()),
             startgg_set: set.clone(),
+            p1_character: None,
+            card_id: None,
+            viewer_count: None,
+            broadcast_status: None,
         };
         streams.push(stream);
 
@@ -316,20 +412,26 @@ pub fn test_mode_broadcast_streams(guard: &mut TestModeState) -> Result<Vec<Slip
     }
 
     guard.spoof_replays = replay_lookup;
+    guard.spoof_replay_queues.clear();
     Ok(streams)
 }
 
 pub fn test_mode_streams_from_replays(guard: &mut TestModeState) -> Result<Vec<SlippiStream>, String> {
     let items = build_test_streams()?;
     let mut replay_map = HashMap::new();
+    let mut replay_queues = HashMap::new();
     let streams: Vec<SlippiStream> = items
         .into_iter()
         .map(|item| {
             replay_map.insert(item.stream.id.clone(), item.replay_path);
+            if item.replay_paths.len() > 1 {
+                replay_queues.insert(item.stream.id.clone(), item.replay_paths);
+            }
             item.stream
         })
         .collect();
     guard.spoof_replays = replay_map;
+    guard.spoof_replay_queues = replay_queues;
     Ok(streams)
 }
 
@@ -397,12 +499,17 @@ pub fn test_mode_bracket_streams(guard: &mut TestModeState) -> Result<Vec<Slippi
             is_playing: Some(is_playing),
             source: Some("test-bracket".to_string()),
             startgg_set: Some(set.clone()),
+            p1_character: None,
+            card_id: None,
+            viewer_count: None,
+            broadcast_status: None,
         });
         if let Some(path) = replay_path {
             replay_lookup.insert(stream_id, path);
         }
     }
     guard.spoof_replays = replay_lookup;
+    guard.spoof_replay_queues.clear();
     Ok(streams)
 }
 
@@ -502,10 +609,7 @@ fn spawn_stream_spoof(
     initial_replay_path: Option<PathBuf>,
 ) -> Result<usize, String> {
     let task_count = tasks.len();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
+    let now = now_ms();
     let tasks_dir = repo_root().join("airlock").join("tmp");
     fs::create_dir_all(&tasks_dir)
         .map_err(|e| format!("create tasks folder {}: {e}", tasks_dir.display()))?;
@@ -560,7 +664,8 @@ fn spawn_stream_spoof(
 }
 
 /// Spawn the copy loop on a background thread so it doesn't block the UI.
-/// For multi-replay sets: copies each replay with a gap between them.
+/// For multi-replay sets: copies each replay with a gap between them. With a
+/// latency profile each replay is written out at game pace instead.
 fn spawn_copy_spoof(
     app: &tauri::AppHandle,
     test_state: &State<'_, SharedTestState>,
@@ -568,6 +673,7 @@ fn spawn_copy_spoof(
     valid_paths: Vec<PathBuf>,
     spectate_dir: PathBuf,
     gap_ms: u64,
+    latency: Option<LatencyProfile>,
 ) -> Result<(), String> {
     {
         let mut guard = test_state.lock().map_err(|e| e.to_string())?;
@@ -615,18 +721,32 @@ fn spawn_copy_spoof(
                 "outputPath": output_path.to_string_lossy(),
             });
             let _ = app.emit("spoof-replay-progress", start_payload);
-            if let Err(e) = fs::copy(path, &output_path) {
-                let payload = json!({
-                    "type": "error",
-                    "setId": set_id,
-                    "message": format!(
-                        "copy replay {} -> {}: {e}",
-                        path.display(),
-                        output_path.display()
-                    ),
-                });
-                let _ = app.emit("spoof-replay-progress", payload);
-                break;
+            let written = match latency.as_ref() {
+                Some(profile) => write_replay_with_latency(path, &output_path, profile, || {
+                    shared
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .cancel_replay_sets
+                        .contains(&set_id)
+                }),
+                None => fs::copy(path, &output_path).map(|_| true).map_err(|e| e.to_string()),
+            };
+            match written {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    let payload = json!({
+                        "type": "error",
+                        "setId": set_id,
+                        "message": format!(
+                            "copy replay {} -> {}: {e}",
+                            path.display(),
+                            output_path.display()
+                        ),
+                    });
+                    let _ = app.emit("spoof-replay-progress", payload);
+                    break;
+                }
             }
             let event_type = if replay_index == replay_total {
                 "complete"
@@ -663,10 +783,10 @@ fn spawn_copy_spoof(
 
 /// Shared preamble: check test mode, load config, resolve and create spectate dir.
 fn spoof_preamble() -> Result<(PathBuf,), String> {
-    if !app_test_mode_enabled() {
-        return Err("Test mode is disabled in settings.".to_string());
+    if !test_flags().fake_replays {
+        return Err("Test replays are disabled in settings.".to_string());
     }
-    let config = load_config_inner()?;
+    let config = cached_config()?;
     let spectate_raw = config.spectate_folder_path.trim();
     if spectate_raw.is_empty() {
         return Err("Spectate folder path is not set in settings.".to_string());
@@ -680,11 +800,12 @@ fn spoof_preamble() -> Result<(PathBuf,), String> {
 // ── Tauri commands ──────────────────────────────────────────────────────
 
 #[tauri::command]
+#[specta::specta]
 pub fn spoof_live_games(test_state: State<'_, SharedTestState>) -> Result<Vec<SlippiStream>, String> {
-    if !app_test_mode_enabled() {
-        return Err("Test mode is disabled in settings.".to_string());
+    if !test_flags().fake_replays {
+        return Err("Test replays are disabled in settings.".to_string());
     }
-    let config = load_config_inner()?;
+    let config = cached_config()?;
     let spectate_raw = config.spectate_folder_path.trim();
     if spectate_raw.is_empty() {
         return Err("Spectate folder path is not set in settings.".to_string());
@@ -694,10 +815,7 @@ pub fn spoof_live_games(test_state: State<'_, SharedTestState>) -> Result<Vec<Sl
         .map_err(|e| format!("create spectate folder {}: {e}", spectate_dir.display()))?;
 
     let items = build_test_streams()?;
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
+    let now = now_ms();
     let tasks_dir = repo_root().join("airlock").join("tmp");
     fs::create_dir_all(&tasks_dir)
         .map_err(|e| format!("create tasks folder {}: {e}", tasks_dir.display()))?;
@@ -740,16 +858,21 @@ pub fn spoof_live_games(test_state: State<'_, SharedTestState>) -> Result<Vec<Sl
     cmd.spawn().map_err(|e| format!("start spoof script: {e}"))?;
 
     let mut replay_map = HashMap::new();
+    let mut replay_queues = HashMap::new();
     let streams: Vec<SlippiStream> = items
         .into_iter()
         .map(|item| {
             replay_map.insert(item.stream.id.clone(), item.replay_path.clone());
+            if item.replay_paths.len() > 1 {
+                replay_queues.insert(item.stream.id.clone(), item.replay_paths);
+            }
             item.stream
         })
         .collect();
     let mut guard = test_state.lock().map_err(|e| e.to_string())?;
     guard.spoof_streams = streams.clone();
     guard.spoof_replays = replay_map;
+    guard.spoof_replay_queues = replay_queues;
     if guard.broadcast_filter_enabled {
         return test_mode_broadcast_streams(&mut guard);
     }
@@ -757,6 +880,7 @@ pub fn spoof_live_games(test_state: State<'_, SharedTestState>) -> Result<Vec<Sl
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn spoof_bracket_set_replays(
     app_handle: tauri::AppHandle,
     config_path: String,
@@ -786,7 +910,9 @@ pub fn spoof_bracket_set_replays(
     let valid_paths = sort_replay_paths_by_start_time(valid_paths);
     let replay_total = valid_paths.len();
 
-    if replay_spoof_mode() == ReplaySpoofMode::Copy {
+    let mode = replay_spoof_mode();
+    if mode != ReplaySpoofMode::Stream {
+        let latency = (mode == ReplaySpoofMode::Latency).then(LatencyProfile::from_env);
         spawn_copy_spoof(
             &app_handle,
             &test_state,
@@ -794,6 +920,7 @@ pub fn spoof_bracket_set_replays(
             valid_paths,
             spectate_dir,
             replay_spoof_gap_ms(),
+            latency,
         )?;
         return Ok(SpoofReplayResult {
             started: replay_total,
@@ -832,6 +959,7 @@ pub fn spoof_bracket_set_replays(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn spoof_bracket_set_replay(
     app_handle: tauri::AppHandle,
     set_id: u64,
@@ -858,6 +986,23 @@ pub fn spoof_bracket_set_replay(
         return Err(format!("Replay not found at {}", resolved.display()));
     }
 
+    if replay_spoof_mode() == ReplaySpoofMode::Latency {
+        {
+            let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+            guard.active_replay_paths.insert(set_id, resolved.clone());
+        }
+        spawn_copy_spoof(
+            &app_handle,
+            &test_state,
+            set_id,
+            vec![resolved],
+            spectate_dir,
+            0,
+            Some(LatencyProfile::from_env()),
+        )?;
+        return Ok(SpoofReplayResult { started: 1, missing: 0 });
+    }
+
     if replay_spoof_mode() == ReplaySpoofMode::Copy {
         // Single-replay copy is fast enough to do inline (no sleep needed).
         {
@@ -923,6 +1068,7 @@ pub fn spoof_bracket_set_replay(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn cancel_spoof_bracket_set_replays(
     app_handle: tauri::AppHandle,
     set_id: Option<u64>,
@@ -967,10 +1113,18 @@ pub fn cancel_spoof_bracket_set_replays(
 }
 
 #[tauri::command]
+#[specta::specta]
 pub fn set_broadcast_players(
     players: Vec<BroadcastPlayerSelection>,
     test_state: State<'_, SharedTestState>,
 ) -> Result<(), String> {
+    let mut guard = test_state.lock().map_err(|e| e.to_string())?;
+    apply_broadcast_players(&mut guard, players);
+    Ok(())
+}
+
+/// Only show these players' broadcasts.
+pub fn apply_broadcast_players(guard: &mut TestModeState, players: Vec<BroadcastPlayerSelection>) {
     let mut codes = HashSet::new();
     let mut tags = HashSet::new();
     for player in &players {
@@ -984,10 +1138,68 @@ pub fn set_broadcast_players(
         }
     }
 
-    let mut guard = test_state.lock().map_err(|e| e.to_string())?;
     guard.broadcast_filter_enabled = true;
     guard.broadcast_players = players;
     guard.broadcast_codes = codes;
     guard.broadcast_tags = tags;
-    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_folder_profile_pins_its_player() {
+        let dir = scratch_dir("test-player-profile");
+        assert!(load_test_player_profile(&dir).unwrap().is_none());
+
+        let profile = r#"{ "name": "Mango", "slippiCode": "MANG#0", "character": "Falco" }"#;
+        fs::write(dir.join(TEST_PLAYER_PROFILE_FILE), profile).unwrap();
+        let profile = load_test_player_profile(&dir).unwrap().unwrap();
+        assert_eq!(profile.display_name.as_deref(), Some("Mango"));
+        assert_eq!(profile.code.as_deref(), Some("MANG#0"));
+        assert_eq!(profile.character.as_deref(), Some("Falco"));
+
+        fs::write(dir.join(TEST_PLAYER_PROFILE_FILE), "{ not json").unwrap();
+        assert!(load_test_player_profile(&dir).is_err());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn consecutive_test_games_face_different_opponents() {
+        let dir = scratch_dir("test-replay-cycle");
+        let replay = |name: &str, codes: &str| {
+            let path = dir.join(name);
+            fs::write(&path, codes).unwrap();
+            path
+        };
+        let replays = vec![
+            replay("1.slp", "MANG#001 ZAIN#908"),
+            replay("2.slp", "MANG#001 ZAIN#908"),
+            replay("3.slp", "MANG#001 HBOX#305"),
+            replay("4.slp", "ZAIN#908 HBOX#305"),
+        ];
+
+        assert_eq!(
+            cycle_replays_by_opponent("mang#001", &replays),
+            vec![
+                (replays[0].clone(), Some("ZAIN#908".to_string())),
+                (replays[2].clone(), Some("HBOX#305".to_string())),
+                (replays[1].clone(), Some("ZAIN#908".to_string())),
+            ]
+        );
+
+        // Nobody's replays to cycle: play them all as they are.
+        let all = cycle_replays_by_opponent("NONE#999", &replays);
+        assert_eq!(all.len(), 4);
+        assert!(all.iter().all(|(_, opponent)| opponent.is_none()));
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,71 @@
+//! Synthetic .slp files for tests that need a replay on disk.
+
+const VERSION: [u8; 4] = [0, 1, 0, 0];
+const GAME_START_SIZE: u16 = 0x140;
+const PRE_FRAME_SIZE: u16 = 0x3A;
+const POST_FRAME_SIZE: u16 = 0x21;
+const GAME_END_SIZE: u16 = 1;
+
+fn event(command: u8, size: u16, fill: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+    let mut payload = Vec::with_capacity(size as usize);
+    fill(&mut payload);
+    payload.resize(size as usize, 0);
+    let mut bytes = vec![command];
+    bytes.extend(payload);
+    bytes
+}
+
+/// A two-Fox v0.1.0 replay `frames` frames long with empty metadata.
+pub fn fixture_replay(frames: i32) -> Vec<u8> {
+    fixture_replay_with_metadata(frames, b"{}")
+}
+
+/// A two-Fox v0.1.0 replay `frames` frames long, written the way Slippi
+/// lays one out: event payload sizes, game start, a pre and post frame
+/// per port per frame, then game end. `metadata` is the UBJSON object
+/// stored after them.
+pub fn fixture_replay_with_metadata(frames: i32, metadata: &[u8]) -> Vec<u8> {
+    let mut raw = vec![0x35, 13];
+    let sizes = [(0x36, GAME_START_SIZE), (0x37, PRE_FRAME_SIZE), (0x38, POST_FRAME_SIZE), (0x39, GAME_END_SIZE)];
+    for (command, size) in sizes {
+        raw.push(command);
+        raw.extend(size.to_be_bytes());
+    }
+    raw.extend(event(0x36, GAME_START_SIZE, |payload| {
+        payload.extend(VERSION);
+        // Player blocks start 0x64 into the payload: external character,
+        // then player type (0 human, 3 empty).
+        payload.resize(0x140, 0);
+        for port in 0..4 {
+            let block = 0x64 + port * 0x24;
+            payload[block] = 2;
+            payload[block + 1] = if port < 2 { 0 } else { 3 };
+            payload[block + 2] = 4;
+        }
+    }));
+    for frame in -123..-123 + frames {
+        for port in 0..2u8 {
+            raw.extend(event(0x37, PRE_FRAME_SIZE, |payload| {
+                payload.extend(frame.to_be_bytes());
+                payload.push(port);
+            }));
+            raw.extend(event(0x38, POST_FRAME_SIZE, |payload| {
+                payload.extend(frame.to_be_bytes());
+                payload.extend([port, 0, 1]);
+                payload.extend(14u16.to_be_bytes());
+                for value in [port as f32 * 10.0, 0.0, 1.0, 12.5, 60.0] {
+                    payload.extend(value.to_be_bytes());
+                }
+                payload.extend([0, 0, 0, 4]);
+            }));
+        }
+    }
+    raw.extend(event(0x39, GAME_END_SIZE, |payload| payload.push(2)));
+
+    let mut bytes = b"{U\x03raw[$U#l".to_vec();
+    bytes.extend((raw.len() as i32).to_be_bytes());
+    bytes.extend(raw);
+    bytes.extend(b"U\x08metadata");
+    bytes.extend(metadata);
+    bytes.push(b'}');
+    bytes
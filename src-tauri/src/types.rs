@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use specta::Type;
 use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
@@ -8,6 +9,9 @@ use std::{
     time::SystemTime,
 };
 
+use crate::report_lock::ReportLockManager;
+pub use melee_stream_core::overlay::*;
+pub use melee_stream_core::replay::{LiveReplay, ParsedGameInfo, ParsedPlayerInfo, ParsedReplay};
 use crate::startgg_sim::{StartggSim, StartggSimSet, StartggSimState};
 
 // ── Constants ──────────────────────────────────────────────────────────
@@ -26,60 +30,186 @@ pub type SharedSetupStore = Arc<Mutex<SetupStore>>;
 pub type SharedTestState = Arc<Mutex<TestModeState>>;
 pub type SharedOverlayCache = Arc<Mutex<OverlayReplayCache>>;
 pub type SharedLiveStartgg = Arc<Mutex<LiveStartggState>>;
+pub type SharedRankCache = Arc<Mutex<crate::slippi_rank::RankCache>>;
+pub type SharedOverlayHistory = Arc<Mutex<crate::overlay_history::OverlayHistory>>;
 
 // ── App domain types ───────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct SetupStub {
     pub id: u8,
     pub name: String,
     pub note: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct Setup {
     pub id: u32,
     pub name: String,
     pub assigned_stream: Option<SlippiStream>,
+    #[serde(default)]
+    pub side_mapping: SideMapping,
+    #[serde(default)]
+    pub refresh_driver: OverlayRefreshDriver,
+    #[serde(default)]
+    pub bracket: SetupBracket,
+    #[serde(default)]
+    pub auto_stop: AutoStopPolicy,
+    /// Operator's label, e.g. "Stream Setup"; `name` when unset
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// Free-form note, e.g. its capture card or commentary desk
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Number on the physical station at the venue
+    #[serde(default)]
+    pub station_number: Option<u32>,
+    /// Broadcast delay of the setup's stream; public overlays show its
+    /// state this many seconds late so they don't spoil results
+    #[serde(default)]
+    pub stream_delay_secs: u32,
+    /// Capture card showing a console station, from the capture source
+    /// registry; None for a setup that spectates in Dolphin
+    #[serde(default)]
+    pub capture_source_id: Option<String>,
+}
+
+impl Setup {
+    /// An empty setup named after its id, with every setting at its default.
+    pub fn new(id: u32) -> Self {
+        Setup {
+            id,
+            name: format!("Setup {id}"),
+            assigned_stream: None,
+            side_mapping: SideMapping::default(),
+            refresh_driver: OverlayRefreshDriver::default(),
+            bracket: SetupBracket::default(),
+            auto_stop: AutoStopPolicy::default(),
+            display_name: None,
+            note: None,
+            station_number: None,
+            stream_delay_secs: 0,
+            capture_source_id: None,
+        }
+    }
+}
+
+/// How a setup's overlay decides which player is shown on the left.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SideMappingPolicy {
+    /// The stream's broadcaster on the left
+    #[default]
+    ConnectCode,
+    /// Lower in-game port on the left, fixed after the first game of a set
+    Port,
+    /// The pinned connect code on the pinned side
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SideMapping {
+    pub policy: SideMappingPolicy,
+    pub pinned_code: Option<String>,
+    /// "left" or "right"
+    pub pinned_side: Option<String>,
+}
+
+/// When a setup's overlay is rebuilt. Rebuilding parses the live replay, so
+/// slower drivers trade freshness for CPU on busy multi-setup machines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+pub enum OverlayRefreshDriver {
+    /// Every overlay poll
+    #[default]
+    Live,
+    /// When a replay in the spectate folder is written
+    FileWatcher,
+    /// At most once per `ms`
+    Interval { ms: u64 },
+    /// When a bracket set changes
+    BracketEvents,
+    /// Only on `force_overlay_refresh`
+    Manual,
+}
+
+/// Which bracket a setup's overlay finds its players' sets in, when a
+/// second bracket is linked to the main one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum SetupBracket {
+    #[default]
+    Main,
+    Linked,
+    /// Sets from both brackets
+    Either,
+}
+
+/// What a setup does once the set it's showing is over, so it doesn't go
+/// on spectating the players' friendlies.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoStopAction {
+    #[default]
+    Off,
+    /// Unassign the stream and close its Dolphin
+    ClearAssignment,
+    /// Keep the Dolphin but show the break screen until the next set
+    BreakScene,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoStopPolicy {
+    pub action: AutoStopAction,
+    /// Wait this long after the set completes, so the final game and the
+    /// result graphic stay up
+    pub grace_secs: u64,
+}
+
+impl Default for AutoStopPolicy {
+    fn default() -> Self {
+        AutoStopPolicy {
+            action: AutoStopAction::Off,
+            grace_secs: 30,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct AssignStreamResult {
     pub setups: Vec<Setup>,
     pub warning: Option<String>,
 }
 
+/// Payload of `setup-deleting-live`, sent when a setup that is on stream
+/// is deleted anyway, just before its Dolphin is closed.
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupDeleteWarning {
+    pub setup_id: u32,
+    pub stream_id: String,
+    pub label: String,
+}
+
 #[derive(Default)]
 pub struct SetupStore {
     pub setups: Vec<Setup>,
     pub processes: HashMap<u32, Child>,
     pub process_pids: HashMap<u32, u32>,
+    pub playback_sessions: HashMap<u32, crate::playback::PlaybackSession>,
 }
 
 impl SetupStore {
     pub fn bootstrap_from_existing() -> Self {
         SetupStore {
-            setups: vec![
-                Setup {
-                    id: 1,
-                    name: "Setup 1".to_string(),
-                    assigned_stream: None,
-                },
-                Setup {
-                    id: 2,
-                    name: "Setup 2".to_string(),
-                    assigned_stream: None,
-                },
-                Setup {
-                    id: 3,
-                    name: "Setup 3".to_string(),
-                    assigned_stream: None,
-                },
-            ],
+            setups: (1..=3).map(Setup::new).collect(),
             processes: HashMap::new(),
             process_pids: HashMap::new(),
+            playback_sessions: HashMap::new(),
         }
     }
 }
@@ -87,6 +217,8 @@ impl SetupStore {
 pub struct TestModeState {
     pub spoof_streams: Vec<SlippiStream>,
     pub spoof_replays: HashMap<String, PathBuf>,
+    /// Replays to cycle through per test stream, when it has more than one
+    pub spoof_replay_queues: HashMap<String, Vec<PathBuf>>,
     pub startgg_sim: Option<StartggSim>,
     pub startgg_config_path: Option<PathBuf>,
     pub state_restored_from_persistence: bool,
@@ -99,6 +231,7 @@ pub struct TestModeState {
     pub active_replay_paths: HashMap<u64, PathBuf>,
     pub active_replay_children: HashMap<u64, Child>,
     pub cancel_replay_sets: HashSet<u64>,
+    pub report_locks: ReportLockManager,
 }
 
 impl Default for TestModeState {
@@ -106,6 +239,7 @@ impl Default for TestModeState {
         Self {
             spoof_streams: Vec::new(),
             spoof_replays: HashMap::new(),
+            spoof_replay_queues: HashMap::new(),
             startgg_sim: None,
             startgg_config_path: None,
             state_restored_from_persistence: false,
@@ -118,6 +252,7 @@ impl Default for TestModeState {
             active_replay_paths: HashMap::new(),
             active_replay_children: HashMap::new(),
             cancel_replay_sets: HashSet::new(),
+            report_locks: ReportLockManager::new(),
         }
     }
 }
@@ -138,14 +273,17 @@ pub struct OverlayServerState {
     pub test_state: SharedTestState,
     pub live_startgg: SharedLiveStartgg,
     pub replay_cache: SharedOverlayCache,
+    pub resources_dir: PathBuf,
+    pub rank_cache: SharedRankCache,
+    pub history: SharedOverlayHistory,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct SetupsPayload {
     pub setups: Vec<SetupStub>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct SlippiStream {
     pub id: String,
@@ -159,9 +297,30 @@ pub struct SlippiStream {
     pub is_playing: Option<bool>,
     pub source: Option<String>,
     pub startgg_set: Option<StartggSimSet>,
+    /// Character from a test folder's player.json, shown until a replay
+    /// says otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub p1_character: Option<String>,
+    /// The launcher's DOM id for the card, which changes between refreshes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card_id: Option<String>,
+    /// Viewers the launcher shows for the broadcast
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub viewer_count: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub broadcast_status: Option<BroadcastStatus>,
+}
+
+/// Whether a spectate card's broadcast is still going, as the launcher
+/// labels it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum BroadcastStatus {
+    Live,
+    Ended,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct BroadcastPlayerSelection {
     pub id: u32,
@@ -169,7 +328,7 @@ pub struct BroadcastPlayerSelection {
     pub slippi_code: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct SlippiWindowInfo {
     pub id: u32,
@@ -179,23 +338,34 @@ pub struct SlippiWindowInfo {
     pub width: u32,
     pub height: u32,
     pub screen: u32,
+    /// Windowing backend that found it, "x11" or "wayland". Wayland windows
+    /// may have no geometry (all zeros) and can't be captured for OCR.
+    pub backend: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct BracketConfigInfo {
     pub name: String,
     pub path: String,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct SpoofReplayResult {
     pub started: usize,
     pub missing: usize,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetPackReport {
+    pub root: String,
+    pub checked: usize,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct BracketPersistenceStatus {
     pub state_restored: bool,
@@ -204,90 +374,222 @@ pub struct BracketPersistenceStatus {
     pub state_file_exists: bool,
 }
 
-// ── Overlay types ──────────────────────────────────────────────────────
+// ── Set reporting types ────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
-pub struct PlayerState {
-    pub side: String,
-    pub port: Option<u8>,
-    pub tag: String,
-    pub sponsor: Option<String>,
-    pub handle: Option<String>,
-    pub character: String,
-    pub character_color: String,
-    pub score: u32,
-    pub country_code: Option<String>,
+pub struct SetReportLock {
+    pub set_id: u64,
+    pub holder: String,
+    pub acquired_at_ms: u64,
+    pub last_action: String,
+    pub last_action_at_ms: u64,
+    pub reported_scores: Option<[u8; 2]>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
-pub struct CommentaryState {
-    pub name: String,
-    pub handle: Option<String>,
-    pub active: Option<bool>,
+pub struct ReportAuditEntry {
+    pub set_id: u64,
+    pub reporter: String,
+    pub action: String,
+    pub scores: Option<[u8; 2]>,
+    pub at_ms: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
-pub struct MatchMeta {
-    pub tournament: Option<String>,
-    pub round: String,
-    pub best_of: u8,
-    pub game_number: Option<u32>,
-    pub stage: Option<String>,
-    pub notes: Option<String>,
+pub struct PendingReport {
+    pub lock: SetReportLock,
+    pub set_state: Option<String>,
+    pub bracket_scores: Option<[u8; 2]>,
+    pub history: Vec<ReportAuditEntry>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Type)]
 #[serde(rename_all = "camelCase")]
-pub struct OverlayState {
-    pub p1: PlayerState,
-    pub p2: PlayerState,
-    pub meta: MatchMeta,
-    pub commentators: Vec<CommentaryState>,
+pub struct ScoreReconciliation {
+    pub set_id: u64,
+    pub reported_scores: [u8; 2],
+    pub replay_scores: [u8; 2],
+    pub games_counted: usize,
+    pub unmatched_replays: Vec<String>,
+    pub matches: bool,
+    pub proposed_scores: Option<[u8; 2]>,
+    pub proposed_winner_slot: Option<usize>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct AllSetupsState {
-    pub setups: Vec<OverlayState>,
+#[derive(Debug, Default)]
+pub struct OverlayReplayCache {
+    /// Spectate folder the entries below were indexed from
+    pub indexed_dir: Option<PathBuf>,
+    pub last_scan: Option<SystemTime>,
+    pub replay_mtimes: HashMap<String, SystemTime>,
+    pub replay_codes: HashMap<String, Vec<String>>,
+    /// How each replay's codes were extracted, keyed like `replay_codes`
+    pub replay_code_sources: HashMap<String, crate::connect_codes::CodeSource>,
+    pub code_index: HashMap<String, String>,
+    pub parsed: HashMap<String, ParsedReplay>,
+    /// Port policy: per setup, the set id and connect code that took the
+    /// left side in the set's first game
+    pub port_side_locks: HashMap<u32, (Option<u64>, String)>,
+    /// Last overlay built per setup, served until its refresh driver fires
+    pub refresh: HashMap<u32, crate::overlay_refresh::OverlayRefreshEntry>,
+    /// Frames decoded so far from the replays overlays are showing
+    pub live: HashMap<String, LiveReplay>,
 }
 
-// ── Replay parsing types ───────────────────────────────────────────────
+// ── Config types ───────────────────────────────────────────────────────
 
-#[derive(Debug, Clone)]
-pub struct ParsedPlayerInfo {
-    pub port: u8,
-    pub tag: Option<String>,
-    pub code: Option<String>,
-    pub character: Option<String>,
-    pub color: Option<String>,
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TestModeFlags {
+    /// Use the start.gg simulator instead of the live event
+    pub fake_bracket: bool,
+    /// Mock Slippi Launcher streams; assigned streams launch playback
+    pub fake_streams: bool,
+    /// Serve test replays from the spoof map instead of indexing the
+    /// spectate folder
+    pub fake_replays: bool,
+    /// Allow set reports against the simulated bracket
+    pub fake_reporting: bool,
+    /// Made-up ping and packet loss on overlay players, for building
+    /// connection widgets before a real source exists
+    pub fake_connection: bool,
+}
+
+impl TestModeFlags {
+    pub fn all() -> Self {
+        TestModeFlags {
+            fake_bracket: true,
+            fake_streams: true,
+            fake_replays: true,
+            fake_reporting: true,
+            fake_connection: true,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.fake_bracket || self.fake_streams || self.fake_replays || self.fake_reporting || self.fake_connection
+    }
 }
 
-#[derive(Debug, Clone)]
-pub struct ParsedGameInfo {
-    pub players: Vec<ParsedPlayerInfo>,
+/// A subsystem an operator can switch off mid-event without a restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum FeatureFlag {
+    Obs,
+    OverlayServer,
+    StartggPolling,
+    AutoReport,
+    AutoSpectate,
+    AutoStop,
+    LauncherWatchdog,
+    BroadcastMonitor,
+}
+
+/// Which live set gets a free setup first when there aren't enough to go
+/// around.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoAssignPriority {
+    /// Grand finals, then the latest rounds
+    #[default]
+    RoundImportance,
+    /// The set with the best-seeded player
+    Seed,
+    /// Whichever set started first
+    StartedFirst,
+}
+
+/// The rules an event is played under.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventRuleset {
+    /// Best-of shown for sets the bracket doesn't give one for
+    pub best_of: u8,
+    /// Legal stages, starters first
+    pub stages: Vec<String>,
 }
 
-#[derive(Debug, Clone)]
-pub struct ParsedReplay {
-    pub info: ParsedGameInfo,
-    pub modified: SystemTime,
+impl Default for EventRuleset {
+    fn default() -> Self {
+        Self {
+            best_of: 3,
+            stages: Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Default)]
-pub struct OverlayReplayCache {
-    pub last_scan: Option<SystemTime>,
-    pub replay_mtimes: HashMap<String, SystemTime>,
-    pub replay_codes: HashMap<String, Vec<String>>,
-    pub code_index: HashMap<String, String>,
-    pub parsed: HashMap<String, ParsedReplay>,
+/// Where finished sets' replays are uploaded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum ReplayUploadBackend {
+    #[default]
+    Off,
+    /// Any S3-compatible bucket: AWS, R2, B2, MinIO
+    S3,
+    WebDav,
+    /// A folder, e.g. a mounted network share
+    LocalShare,
 }
 
-// ── Config types ───────────────────────────────────────────────────────
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplayUploadConfig {
+    pub backend: ReplayUploadBackend,
+    /// S3: the bucket URL, e.g. "https://s3.us-east-1.amazonaws.com/my-bucket";
+    /// WebDAV: the collection URL; local share: the folder
+    pub target: String,
+    /// Folder each set's replays go in; `{event}` and `{set}` are filled in
+    pub path_pattern: String,
+    /// Where the uploads can be downloaded from, when that isn't `target`
+    pub public_base_url: String,
+    /// S3 only
+    pub region: String,
+    /// S3 access key id, or the WebDAV user
+    pub access_key: String,
+    /// S3 secret key, or the WebDAV password
+    pub secret_key: String,
+}
+
+impl Default for ReplayUploadConfig {
+    fn default() -> Self {
+        Self {
+            backend: ReplayUploadBackend::Off,
+            target: String::new(),
+            path_pattern: "{event}/set-{set}".to_string(),
+            public_base_url: String::new(),
+            region: "us-east-1".to_string(),
+            access_key: String::new(),
+            secret_key: String::new(),
+        }
+    }
+}
+
+/// What a remote API token may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteRole {
+    /// Everything the remote API offers
+    Operator,
+    /// Bracket only: call sets, start DQ timers and report scores; no
+    /// setups, Dolphin or OBS
+    BracketRunner,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A device allowed to use the remote API, e.g. a bracket runner's laptop.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteToken {
+    /// Shows up in the audit log as "remote:<name>"
+    pub name: String,
+    /// Hex sha256 of the secret; the secret itself is never stored.
+    pub token_hash: String,
+    pub role: RemoteRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase", default)]
 pub struct AppConfig {
     pub dolphin_path: String,
@@ -297,10 +599,94 @@ pub struct AppConfig {
     pub startgg_link: String,
     pub startgg_token: String,
     pub startgg_polling: bool,
+    /// Detect finished sets from replays and offer to report them to
+    /// start.gg; the token must be allowed to report for the event
+    pub startgg_reporting: bool,
+    /// Mark a set in progress on start.gg when its first game is detected,
+    /// without waiting for anyone to confirm; off, sets are only started
+    /// when called or reported
+    pub startgg_auto_start_sets: bool,
     pub auto_stream: bool,
+    /// Put the streams of sets that go live on free setups from the
+    /// backend, without the app window open
+    pub auto_assign_enabled: bool,
+    pub auto_assign_priority: AutoAssignPriority,
+    /// Legacy all-in-one switch; when on, every `test_flags` flag is on
     pub test_mode: bool,
+    /// Which subsystems are simulated, for hybrid rehearsals (e.g. real
+    /// Dolphins and OBS against a fake bracket)
+    pub test_flags: TestModeFlags,
     pub test_bracket_path: String,
     pub auto_complete_bracket: bool,
+    pub asset_pack_path: String,
+    pub ruleset: EventRuleset,
+    pub show_player_ranks: bool,
+    pub obs_path: String,
+    pub obs_websocket_url: String,
+    /// Empty when obs-websocket authentication is off
+    pub obs_websocket_password: String,
+    /// Switch OBS to a setup's scene when its set starts
+    pub obs_switch_on_live: bool,
+    /// Fill the Launcher login form from stored credentials when it logs out
+    pub slippi_auto_login: bool,
+    /// List and watch streams straight from Slippi's spectate server
+    /// instead of through the Launcher's UI
+    pub slippi_native_spectate: bool,
+    pub slippi_ws_url: String,
+    /// Proxy for outbound requests, e.g. "http://proxy.venue.lan:3128"
+    pub network_proxy_url: String,
+    /// Comma-separated hosts that skip the proxy
+    pub network_proxy_bypass: String,
+    /// Pause start.gg and other internet traffic and serve cached state
+    pub offline_mode: bool,
+    /// Accept start.gg webhook POSTs so set changes land without waiting
+    /// for the next poll
+    pub startgg_webhook_enabled: bool,
+    /// Address the webhook receiver binds to. Loopback by default, for a
+    /// relay or tunnel on this machine; use 0.0.0.0 to take them from the LAN
+    pub startgg_webhook_bind: String,
+    /// Shared secret expected in `X-Webhook-Secret` or `?secret=`; the
+    /// receiver doesn't start without one
+    pub startgg_webhook_secret: String,
+    /// Playback Dolphin launch tuning, keyed by setup id
+    pub playback_tuning: HashMap<u32, PlaybackTuning>,
+    /// Alert when a playback Dolphin stays under 58 fps for this long
+    pub playback_fps_alert_secs: u64,
+    /// Relaunch a setup's Dolphin when it crashes
+    pub dolphin_auto_relaunch: bool,
+    /// Put a result lower-third up when a set finishes
+    pub result_card_enabled: bool,
+    pub result_card_secs: u64,
+    /// Setups whose sets trigger the card; every setup when empty
+    pub result_card_setups: Vec<u32>,
+    /// Upload each finished set's replays so they can be shared
+    pub replay_upload: ReplayUploadConfig,
+    /// IANA zone of the venue, e.g. "America/New_York"; empty uses this
+    /// machine's zone
+    pub venue_timezone: String,
+    /// Subsystems switched off; everything else runs
+    pub disabled_features: Vec<FeatureFlag>,
+    /// Serve the remote API so staff can run the bracket from their own
+    /// devices
+    pub remote_api_enabled: bool,
+    /// Address the remote API binds to. Loopback by default; 0.0.0.0 opens
+    /// it to staff devices on the venue network
+    pub remote_api_bind: String,
+    pub remote_tokens: Vec<RemoteToken>,
+    /// Port of a standalone overlay WebSocket (`ws://127.0.0.1:<port>/ws`);
+    /// 0 leaves only the main overlay server's `/ws`
+    pub overlay_ws_port: u16,
+}
+
+impl AppConfig {
+    /// The flags in effect, with the legacy `test_mode` switch applied.
+    pub fn test_flags(&self) -> TestModeFlags {
+        if self.test_mode {
+            TestModeFlags::all()
+        } else {
+            self.test_flags
+        }
+    }
 }
 
 impl Default for AppConfig {
@@ -313,14 +699,123 @@ impl Default for AppConfig {
             startgg_link: String::new(),
             startgg_token: String::new(),
             startgg_polling: false,
+            startgg_reporting: false,
+            startgg_auto_start_sets: false,
             auto_stream: true,
+            auto_assign_enabled: false,
+            auto_assign_priority: AutoAssignPriority::default(),
             test_mode: false,
+            test_flags: TestModeFlags::default(),
             test_bracket_path: "test_brackets/test_bracket_2.json".to_string(),
             auto_complete_bracket: true,
+            asset_pack_path: String::new(),
+            ruleset: EventRuleset::default(),
+            show_player_ranks: false,
+            obs_path: String::new(),
+            obs_websocket_url: "ws://127.0.0.1:4455".to_string(),
+            obs_websocket_password: String::new(),
+            obs_switch_on_live: false,
+            slippi_auto_login: false,
+            slippi_native_spectate: false,
+            slippi_ws_url: "wss://slippi-broadcast.herokuapp.com/".to_string(),
+            network_proxy_url: String::new(),
+            network_proxy_bypass: String::new(),
+            offline_mode: false,
+            startgg_webhook_enabled: false,
+            startgg_webhook_bind: "127.0.0.1:17895".to_string(),
+            startgg_webhook_secret: String::new(),
+            playback_tuning: HashMap::new(),
+            playback_fps_alert_secs: 5,
+            dolphin_auto_relaunch: false,
+            result_card_enabled: false,
+            result_card_secs: 15,
+            result_card_setups: Vec::new(),
+            replay_upload: ReplayUploadConfig::default(),
+            venue_timezone: String::new(),
+            disabled_features: Vec::new(),
+            remote_api_enabled: false,
+            remote_api_bind: "127.0.0.1:17896".to_string(),
+            remote_tokens: Vec::new(),
+            overlay_ws_port: 17894,
         }
     }
 }
 
+// ── Startup types ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStep {
+    pub step: String,
+    /// "running", "ok", "skipped" or "failed"
+    pub status: String,
+    pub message: Option<String>,
+    pub at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupReport {
+    pub ok: bool,
+    pub steps: Vec<StartupStep>,
+    pub started_at_ms: u64,
+    pub finished_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct ShutdownReport {
+    pub safe_to_power_down: bool,
+    pub steps: Vec<StartupStep>,
+    pub event_report_path: Option<String>,
+    pub started_at_ms: u64,
+    pub finished_at_ms: u64,
+}
+
+// ── Playback types ─────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaybackBufferStatus {
+    pub setup_id: u32,
+    pub replay_path: String,
+    /// "waiting", "buffering", "live", "stalled" or "ended"
+    pub state: String,
+    pub bytes: u64,
+    pub latest_frame: Option<i32>,
+    /// Estimated frame Dolphin is showing, assuming 60fps playback that
+    /// pauses whenever it catches up to the file.
+    pub playback_frame: Option<i32>,
+    pub buffered_frames: i32,
+    pub game_ended: bool,
+    pub last_growth_ms: Option<u64>,
+    pub updated_at_ms: u64,
+}
+
+/// Dolphin settings that trade accuracy for steadier playback on a loaded
+/// host. Unset fields leave Dolphin's own value alone.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PlaybackTuning {
+    /// Dolphin `[DSP] Backend`, e.g. "Pulse", "ALSA", "Cubeb" or "No audio output"
+    pub audio_backend: Option<String>,
+    /// Dolphin `[Core] CPUThread`; turning it off trades speed for sync
+    pub dual_core: Option<bool>,
+    /// Dolphin `[Core] EmulationSpeed`; 1.0 caps at full speed
+    pub emulation_speed: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Type)]
+#[serde(rename_all = "camelCase")]
+pub struct FramePacingStatus {
+    pub setup_id: u32,
+    pub fps: f32,
+    /// When fps first dropped below the threshold in the current dip
+    pub below_since_ms: Option<u64>,
+    pub alerting: bool,
+    pub updated_at_ms: u64,
+}
+
 // ── Dolphin types ──────────────────────────────────────────────────────
 
 #[derive(Debug)]
@@ -331,7 +826,7 @@ pub struct DolphinConfig {
 
 // ── CDP types ──────────────────────────────────────────────────────────
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Type)]
 pub struct CdpTarget {
     pub title: Option<String>,
     #[serde(rename = "type")]
@@ -346,12 +841,29 @@ pub struct CdpTarget {
 pub struct TestStreamSpec {
     pub stream: SlippiStream,
     pub replay_path: PathBuf,
+    /// Every replay of the folder's player, alternating between opponents;
+    /// starts with `replay_path`
+    pub replay_paths: Vec<PathBuf>,
+}
+
+/// Optional `player.json` in a test folder, overriding what is inferred
+/// from the replays.
+#[derive(Debug, Clone, Default, Deserialize, Type)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TestPlayerProfile {
+    #[serde(alias = "name")]
+    pub display_name: Option<String>,
+    #[serde(alias = "slippiCode")]
+    pub code: Option<String>,
+    pub character: Option<String>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ReplaySpoofMode {
     Stream,
     Copy,
+    /// Written from Rust with spectate-like latency, jitter and bursts
+    Latency,
 }
 
 // ── Start.gg link parsing ──────────────────────────────────────────────
@@ -364,7 +876,7 @@ pub struct StartggLinkInfo {
 
 // ── Start.gg live snapshot ─────────────────────────────────────────────
 
-#[derive(Serialize)]
+#[derive(Serialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggLiveSnapshot {
     pub state: Option<StartggSimState>,
@@ -482,6 +994,25 @@ pub struct StartggEntrantConnection {
     pub page_info: Option<StartggPageInfo>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggSetMutationNode {
+    pub id: Option<Value>,
+    pub state: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggMarkSetInProgressData {
+    pub mark_set_in_progress: Option<StartggSetMutationNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggReportSetData {
+    pub report_bracket_set: Option<Vec<StartggSetMutationNode>>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StartggSetsData {
@@ -542,6 +1073,14 @@ pub struct StartggPlayerNode {
 #[serde(rename_all = "camelCase")]
 pub struct StartggUserNode {
     pub authorizations: Option<Vec<StartggAuthorizationNode>>,
+    #[serde(default)]
+    pub location: Option<StartggLocationNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartggLocationNode {
+    pub country: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -578,6 +1117,9 @@ pub struct StartggPhaseGroupNode {
 pub struct StartggSetSlotNode {
     pub entrant: Option<StartggEntrantStub>,
     pub standing: Option<StartggStandingNode>,
+    pub prereq_id: Option<Value>,
+    pub prereq_type: Option<String>,
+    pub prereq_placement: Option<u32>,
 }
 
 #[derive(Deserialize)]
@@ -608,7 +1150,7 @@ pub struct StartggScoreNode {
 
 // ── Unified Entrant types ───────────────────────────────────────────────
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Type)]
 #[serde(rename_all = "camelCase")]
 pub enum EntrantBracketState {
     Active,
@@ -622,7 +1164,7 @@ impl Default for EntrantBracketState {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct LiveGameInfo {
     pub stage: Option<String>,
@@ -635,7 +1177,7 @@ pub struct LiveGameInfo {
     pub scores: Option<[u8; 2]>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, Type)]
 #[serde(rename_all = "camelCase")]
 pub struct UnifiedEntrant {
     // Start.gg data (primary source)
@@ -0,0 +1,100 @@
+use crate::config::*;
+use chrono::{DateTime, FixedOffset, Local, Offset, SecondsFormat, TimeZone, Utc};
+use chrono_tz::Tz;
+pub use melee_stream_core::overlay::TimeZoneInfo;
+pub use melee_stream_core::replay::normalize_timestamp_ms as epoch_ms;
+
+/// `venue_timezone` from the config, if it names a known zone.
+pub fn venue_tz() -> Option<Tz> {
+    let config = cached_config().ok()?;
+    let name = config.venue_timezone.trim();
+    if name.is_empty() {
+        return None;
+    }
+    match name.parse::<Tz>() {
+        Ok(tz) => Some(tz),
+        Err(_) => {
+            tracing::debug!("Unknown venue timezone {:?}; using the machine's zone", name);
+            None
+        }
+    }
+}
+
+fn in_zone<Z: TimeZone>(utc: DateTime<Utc>, zone: &Z) -> DateTime<FixedOffset> {
+    utc.with_timezone(zone).fixed_offset()
+}
+
+/// `ms` (epoch milliseconds) at the venue.
+pub fn venue_datetime(ms: i64) -> Option<DateTime<FixedOffset>> {
+    let utc = DateTime::<Utc>::from_timestamp_millis(ms)?;
+    Some(match venue_tz() {
+        Some(tz) => in_zone(utc, &tz),
+        None => in_zone(utc, &Local),
+    })
+}
+
+pub fn venue_now() -> DateTime<FixedOffset> {
+    venue_datetime(Utc::now().timestamp_millis()).unwrap_or_else(|| Local::now().fixed_offset())
+}
+
+/// RFC 3339 with the venue's offset, e.g. "2024-06-01T14:05:00-04:00".
+pub fn venue_rfc3339(ms: u64) -> Option<String> {
+    venue_datetime(ms as i64).map(|dt| dt.to_rfc3339_opts(SecondsFormat::Secs, false))
+}
+
+pub fn timezone_info(at_ms: u64) -> TimeZoneInfo {
+    let utc = DateTime::<Utc>::from_timestamp_millis(at_ms as i64).unwrap_or_else(Utc::now);
+    let configured = cached_config().is_ok_and(|config| !config.venue_timezone.trim().is_empty());
+    match venue_tz() {
+        Some(tz) => {
+            let local = utc.with_timezone(&tz);
+            TimeZoneInfo {
+                name: tz.name().to_string(),
+                utc_offset_minutes: local.offset().fix().local_minus_utc() / 60,
+                abbreviation: local.format("%Z").to_string(),
+                valid: true,
+            }
+        }
+        None => {
+            let local = utc.with_timezone(&Local);
+            TimeZoneInfo {
+                name: "Local".to_string(),
+                utc_offset_minutes: local.offset().fix().local_minus_utc() / 60,
+                abbreviation: local.format("%:z").to_string(),
+                valid: !configured,
+            }
+        }
+    }
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// The venue timezone as of now.
+#[tauri::command]
+#[specta::specta]
+pub fn venue_timezone() -> TimeZoneInfo {
+    timezone_info(now_ms())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_are_read_as_milliseconds() {
+        assert_eq!(epoch_ms(1_717_250_700), 1_717_250_700_000);
+        assert_eq!(epoch_ms(0), 0);
+    }
+
+    #[test]
+    fn milliseconds_are_kept() {
+        assert_eq!(epoch_ms(1_717_250_700_000), 1_717_250_700_000);
+    }
+
+    #[test]
+    fn times_are_shown_in_the_venue_zone() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let utc = DateTime::<Utc>::from_timestamp_millis(1_717_250_700_000).unwrap();
+        assert_eq!(in_zone(utc, &tz).to_rfc3339(), "2024-06-01T10:05:00-04:00");
+    }
+}
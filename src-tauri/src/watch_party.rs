@@ -0,0 +1,98 @@
+use crate::audit_log::audited;
+use crate::auto_stop::leave_break;
+use crate::config::normalize_slippi_code;
+use crate::idle_mode::exit_idle;
+use crate::mirror::end_mirror;
+use crate::replay::tag_from_code;
+use crate::slippi::assign_stream;
+use crate::stream_identity::stable_stream_id;
+use crate::types::*;
+use serde_json::json;
+use tauri::State;
+
+/// `SlippiStream::source` for a stream followed by connect code. Such a
+/// stream has no spectate card; the launcher is told to connect to the
+/// code instead of clicking Watch, and everything after that (Dolphin,
+/// overlay, scoring) is the same as for a card.
+pub const WATCH_PARTY_SOURCE: &str = "watch party";
+
+/// Normalize a connect code like `abcd#123`, or say what's wrong with it.
+pub(crate) fn parse_connect_code(raw: &str) -> Result<String, String> {
+    let code = normalize_slippi_code(raw).ok_or_else(|| "Enter the broadcaster's connect code.".to_string())?;
+    let valid = code.split_once('#').is_some_and(|(tag, number)| {
+        !tag.is_empty()
+            && tag.len() <= 7
+            && tag.chars().all(|c| c.is_ascii_alphanumeric())
+            && !number.is_empty()
+            && number.len() <= 7
+            && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if !valid {
+        return Err(format!("{code} isn't a connect code; they look like ABCD#123."));
+    }
+    Ok(code)
+}
+
+/// The stream a setup is assigned when following `code` remotely.
+fn watch_party_stream(code: &str) -> Result<SlippiStream, String> {
+    let code = parse_connect_code(code)?;
+    let id = stable_stream_id(&[&code]).ok_or_else(|| format!("No stream id for {code}."))?;
+    Ok(SlippiStream {
+        id,
+        p1_tag: Some(tag_from_code(&code)),
+        p1_code: Some(code),
+        source: Some(WATCH_PARTY_SOURCE.to_string()),
+        ..Default::default()
+    })
+}
+
+// ── Commands ────────────────────────────────────────────────────────────
+
+/// Assign `setup_id` a remote broadcaster by connect code, for players
+/// streaming from home who never show up in the spectate list.
+#[tauri::command]
+#[specta::specta]
+pub fn assign_watch_party(
+    setup_id: u32,
+    code: String,
+    launch: Option<bool>,
+    store: State<'_, SharedSetupStore>,
+    test_state: State<'_, SharedTestState>,
+) -> Result<AssignStreamResult, String> {
+    let params = json!({ "setupId": setup_id, "code": code, "launch": launch });
+    audited("assignWatchParty", params, || {
+        let stream = watch_party_stream(&code)?;
+        let result = assign_stream(setup_id, stream, launch, &store, &test_state)?;
+        exit_idle(&format!("watch party assigned to setup {setup_id}"));
+        leave_break(setup_id);
+        end_mirror(setup_id);
+        Ok(result)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_are_trimmed_and_uppercased() {
+        assert_eq!(parse_connect_code(" abcd#123 ").unwrap(), "ABCD#123");
+    }
+
+    #[test]
+    fn anything_not_shaped_like_a_code_is_refused() {
+        assert_eq!(parse_connect_code("").unwrap_err(), "Enter the broadcaster's connect code.");
+        assert!(parse_connect_code("ABCD").is_err());
+        assert!(parse_connect_code("ABCD#12x").is_err());
+        assert!(parse_connect_code("#123").is_err());
+    }
+
+    #[test]
+    fn the_stream_is_keyed_and_tagged_by_the_code() {
+        let stream = watch_party_stream(" abcd#123 ").unwrap();
+        assert_eq!(stream.id, "codes:ABCD#123");
+        assert_eq!(stream.p1_code.as_deref(), Some("ABCD#123"));
+        assert_eq!(stream.p1_tag.as_deref(), Some("ABCD"));
+        assert_eq!(stream.source.as_deref(), Some(WATCH_PARTY_SOURCE));
+    }
+}
@@ -0,0 +1,359 @@
+use crate::slippi::{read_window_title, read_wm_class, slippi_x11_connect};
+use std::{collections::HashMap, env};
+use wayland_client::{
+    event_created_child,
+    globals::{registry_queue_init, GlobalListContents},
+    protocol::wl_registry::{self, WlRegistry},
+    Connection, Dispatch, Proxy, QueueHandle,
+};
+use wayland_protocols_plasma::plasma_window_management::client::{
+    org_kde_plasma_window::{self, OrgKdePlasmaWindow},
+    org_kde_plasma_window_management::{self, OrgKdePlasmaWindowManagement},
+};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+use x11rb::{connection::Connection as _, protocol::xproto::ConnectionExt};
+
+pub const X11_BACKEND: &str = "x11";
+pub const WAYLAND_BACKEND: &str = "wayland";
+
+/// Windows this small are usually helpers (tooltips, splash screens) and
+/// only count when nothing bigger matches.
+const MIN_WINDOW_SIDE: u32 = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WindowGeometry {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A top-level window as a backend sees it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopLevel {
+    /// X11 window id, or the Wayland protocol object id
+    pub id: u32,
+    pub title: String,
+    /// WM_CLASS parts on X11, the app id on Wayland
+    pub app_ids: Vec<String>,
+    /// None when the compositor doesn't share it (wlroots doesn't)
+    pub geometry: Option<WindowGeometry>,
+    pub screen: u32,
+}
+
+/// Lists top-level windows for one windowing system.
+pub trait WindowBackend {
+    fn name(&self) -> &'static str;
+    fn toplevels(&self) -> Result<Vec<TopLevel>, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    X11,
+    Wayland,
+}
+
+/// `XDG_SESSION_TYPE` decides; sessions that don't set it (or say "tty")
+/// count as Wayland when a Wayland display is around.
+fn session_type_from(xdg_session_type: Option<&str>, wayland_display: bool) -> SessionType {
+    match xdg_session_type.map(str::to_ascii_lowercase).as_deref() {
+        Some("wayland") => SessionType::Wayland,
+        Some("x11") => SessionType::X11,
+        _ if wayland_display => SessionType::Wayland,
+        _ => SessionType::X11,
+    }
+}
+
+pub fn session_type() -> SessionType {
+    let xdg = env::var("XDG_SESSION_TYPE").ok();
+    session_type_from(xdg.as_deref(), env::var_os("WAYLAND_DISPLAY").is_some())
+}
+
+/// Backends to ask, in order. Wayland sessions fall back to X11 because
+/// Electron apps like the Slippi Launcher usually run under XWayland,
+/// where X11 still knows their geometry.
+pub fn session_backends() -> Vec<Box<dyn WindowBackend>> {
+    match session_type() {
+        SessionType::Wayland => vec![Box::new(WaylandBackend), Box::new(X11Backend)],
+        SessionType::X11 => vec![Box::new(X11Backend)],
+    }
+}
+
+/// The biggest window, skipping tiny ones unless nothing else matched.
+fn pick_window(candidates: Vec<TopLevel>) -> Option<TopLevel> {
+    let mut best: Option<(TopLevel, u64)> = None;
+    for window in candidates {
+        let area = window.geometry.map_or(0, |g| u64::from(g.width) * u64::from(g.height));
+        let tiny = window.geometry.is_some_and(|g| g.width < MIN_WINDOW_SIDE || g.height < MIN_WINDOW_SIDE);
+        if tiny && best.is_some() {
+            continue;
+        }
+        match &best {
+            Some((_, best_area)) if area <= *best_area => {}
+            _ => best = Some((window, area)),
+        }
+    }
+    best.map(|(window, _)| window)
+}
+
+/// Find the best window matching `is_match` with the session's backends,
+/// along with the name of the backend that found it. A match with geometry
+/// wins over one without; Err only when every backend failed.
+pub fn find_toplevel(is_match: impl Fn(&TopLevel) -> bool) -> Result<Option<(TopLevel, &'static str)>, String> {
+    let backends = session_backends();
+    let mut without_geometry = None;
+    let mut errors = Vec::new();
+    for backend in &backends {
+        let windows = match backend.toplevels() {
+            Ok(windows) => windows,
+            Err(err) => {
+                errors.push(format!("{}: {err}", backend.name()));
+                continue;
+            }
+        };
+        match pick_window(windows.into_iter().filter(|window| is_match(window)).collect()) {
+            Some(window) if window.geometry.is_some() => return Ok(Some((window, backend.name()))),
+            Some(window) => {
+                without_geometry.get_or_insert((window, backend.name()));
+            }
+            None => {}
+        }
+    }
+    if without_geometry.is_none() && errors.len() == backends.len() {
+        return Err(errors.join("; "));
+    }
+    Ok(without_geometry)
+}
+
+// ── X11 ─────────────────────────────────────────────────────────────────
+
+pub struct X11Backend;
+
+impl WindowBackend for X11Backend {
+    fn name(&self) -> &'static str {
+        X11_BACKEND
+    }
+
+    fn toplevels(&self) -> Result<Vec<TopLevel>, String> {
+        let (conn, screen_num) = slippi_x11_connect()?;
+        let root = conn.setup().roots[screen_num].root;
+        let tree = conn
+            .query_tree(root)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let windows = tree
+            .children
+            .into_iter()
+            .map(|win| TopLevel {
+                id: win,
+                title: read_window_title(&conn, win).unwrap_or_default(),
+                app_ids: read_wm_class(&conn, win).unwrap_or_default(),
+                // A window closed mid-scan just has no geometry.
+                geometry: conn
+                    .get_geometry(win)
+                    .ok()
+                    .and_then(|cookie| cookie.reply().ok())
+                    .map(|geo| WindowGeometry {
+                        x: geo.x.into(),
+                        y: geo.y.into(),
+                        width: geo.width.into(),
+                        height: geo.height.into(),
+                    }),
+                screen: screen_num as u32,
+            })
+            .collect();
+        Ok(windows)
+    }
+}
+
+// ── Wayland ─────────────────────────────────────────────────────────────
+
+/// Reads the window list through KDE's Plasma window management protocol
+/// (titles and geometry) or wlr-foreign-toplevel on wlroots compositors
+/// like sway and Hyprland (titles only). KWin only offers the Plasma
+/// protocol to apps whose desktop file lists it in
+/// `X-KDE-Wayland-Interfaces`; without it, this falls through to XWayland.
+pub struct WaylandBackend;
+
+#[derive(Default)]
+struct WaylandToplevels {
+    windows: HashMap<u32, TopLevel>,
+}
+
+impl WaylandToplevels {
+    fn window(&mut self, id: u32) -> &mut TopLevel {
+        self.windows.entry(id).or_insert_with(|| TopLevel { id, ..Default::default() })
+    }
+}
+
+impl WindowBackend for WaylandBackend {
+    fn name(&self) -> &'static str {
+        WAYLAND_BACKEND
+    }
+
+    fn toplevels(&self) -> Result<Vec<TopLevel>, String> {
+        let conn = Connection::connect_to_env().map_err(|e| format!("connect to Wayland: {e}"))?;
+        let (globals, mut queue) =
+            registry_queue_init::<WaylandToplevels>(&conn).map_err(|e| format!("read Wayland globals: {e}"))?;
+        let qh = queue.handle();
+        let plasma_version = OrgKdePlasmaWindowManagement::interface().version;
+        let wlr_version = ZwlrForeignToplevelManagerV1::interface().version;
+        let bound = globals
+            .bind::<OrgKdePlasmaWindowManagement, _, _>(&qh, 1..=plasma_version, ())
+            .map(|_| ())
+            .or_else(|_| globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(&qh, 1..=wlr_version, ()).map(|_| ()));
+        if bound.is_err() {
+            return Err("the compositor doesn't share its window list.".to_string());
+        }
+        let mut state = WaylandToplevels::default();
+        // The first roundtrip announces the windows, the second brings in
+        // their titles and geometry.
+        for _ in 0..2 {
+            queue.roundtrip(&mut state).map_err(|e| format!("read Wayland windows: {e}"))?;
+        }
+        Ok(state.windows.into_values().collect())
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for WaylandToplevels {
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<OrgKdePlasmaWindowManagement, ()> for WaylandToplevels {
+    fn event(
+        _: &mut Self,
+        manager: &OrgKdePlasmaWindowManagement,
+        event: org_kde_plasma_window_management::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            org_kde_plasma_window_management::Event::WindowWithUuid { uuid, .. } => {
+                manager.get_window_by_uuid(uuid, qh, ());
+            }
+            // Older KWin only announces numeric ids.
+            org_kde_plasma_window_management::Event::Window { id } if manager.version() < 12 => {
+                manager.get_window(id, qh, ());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<OrgKdePlasmaWindow, ()> for WaylandToplevels {
+    fn event(
+        state: &mut Self,
+        window: &OrgKdePlasmaWindow,
+        event: org_kde_plasma_window::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = window.id().protocol_id();
+        match event {
+            org_kde_plasma_window::Event::TitleChanged { title } => state.window(id).title = title,
+            org_kde_plasma_window::Event::AppIdChanged { app_id } => state.window(id).app_ids = vec![app_id],
+            org_kde_plasma_window::Event::Geometry { x, y, width, height } => {
+                state.window(id).geometry = Some(WindowGeometry { x, y, width, height });
+            }
+            org_kde_plasma_window::Event::Unmapped => {
+                state.windows.remove(&id);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for WaylandToplevels {
+    fn event(
+        _: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        _: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // Handles arrive through `event_created_child`; their own events
+        // carry everything we need.
+    }
+
+    event_created_child!(WaylandToplevels, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for WaylandToplevels {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let id = handle.id().protocol_id();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::Title { title } => state.window(id).title = title,
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => state.window(id).app_ids = vec![app_id],
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                state.windows.remove(&id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(id: u32, size: Option<(u32, u32)>) -> TopLevel {
+        TopLevel {
+            id,
+            geometry: size.map(|(width, height)| WindowGeometry { x: 0, y: 0, width, height }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn xdg_session_type_decides_the_session() {
+        assert_eq!(session_type_from(Some("wayland"), false), SessionType::Wayland);
+        assert_eq!(session_type_from(Some("X11"), true), SessionType::X11);
+    }
+
+    #[test]
+    fn wayland_display_decides_when_xdg_session_type_does_not() {
+        assert_eq!(session_type_from(Some("tty"), true), SessionType::Wayland);
+        assert_eq!(session_type_from(None, false), SessionType::X11);
+    }
+
+    #[test]
+    fn the_biggest_window_is_picked() {
+        let picked = pick_window(vec![window(1, Some((100, 50))), window(2, Some((1280, 720))), window(3, Some((800, 600)))]);
+        assert_eq!(picked.map(|w| w.id), Some(2));
+        assert_eq!(pick_window(Vec::new()), None);
+    }
+
+    #[test]
+    fn a_tiny_window_is_picked_only_when_it_is_all_there_is() {
+        assert_eq!(pick_window(vec![window(1, Some((100, 50)))]).map(|w| w.id), Some(1));
+    }
+
+    #[test]
+    fn windows_without_geometry_still_match() {
+        assert_eq!(pick_window(vec![window(4, None)]).map(|w| w.id), Some(4));
+    }
+}
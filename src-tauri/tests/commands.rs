@@ -0,0 +1,269 @@
+//! Command handlers driven end to end against a scratch repo root, with the
+//! simulated bracket standing in for start.gg. Each test builds its own
+//! setup store and test state the way the app does at startup.
+
+use new_melee_stream_tool_lib::audit_log::read_audit_log;
+use new_melee_stream_tool_lib::config::{cached_config, config_path, reload_config, save_config_inner};
+use new_melee_stream_tool_lib::audit_log::AuditQuery;
+use new_melee_stream_tool_lib::overlay_history::OverlayHistory;
+use new_melee_stream_tool_lib::replay::build_overlay_for_setup;
+use new_melee_stream_tool_lib::setup_meta::{load_saved_setups, rename_setup_inner};
+use new_melee_stream_tool_lib::slippi::{assign_stream, clear_assignment};
+use new_melee_stream_tool_lib::startgg_sim::{StartggSim, StartggSimState};
+use new_melee_stream_tool_lib::startgg_sim_commands::{
+    release_set_report_lock_inner, startgg_sim_force_winner_inner, startgg_sim_raw_start_set_inner,
+    startgg_sim_raw_update_scores_inner, startgg_sim_reset_inner, startgg_sim_state_inner,
+};
+use new_melee_stream_tool_lib::types::*;
+use new_melee_stream_tool_lib::{
+    create_setup_slot, delete_setup_inner, pin_player_side_inner, set_overlay_refresh_driver_inner,
+    set_setup_auto_stop_inner, set_setup_bracket_inner, set_setup_stream_delay_inner,
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    env, fs,
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, OnceLock},
+};
+
+/// Config and bracket are process-wide, so tests share one scratch root
+/// and take turns.
+fn scratch_root() -> (PathBuf, MutexGuard<'static, ()>) {
+    static ROOT: OnceLock<PathBuf> = OnceLock::new();
+    static TURN: Mutex<()> = Mutex::new(());
+    let root = ROOT.get_or_init(|| {
+        let root = env::temp_dir().join(format!("to-command-tests-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("test_brackets")).unwrap();
+        let config = json!({ "testFlags": { "fakeBracket": true, "fakeReporting": true } });
+        fs::write(root.join("config.json"), config.to_string()).unwrap();
+        let entrants = (1..=4)
+            .map(|i| json!({ "id": i, "name": format!("Player {i}"), "slippiCode": format!("P{i}#000"), "seed": i }))
+            .collect::<Vec<_>>();
+        let bracket = json!({
+            "event": { "id": "harness", "name": "Harness", "slug": "harness" },
+            "phases": [{ "id": "phase-1", "name": "Bracket", "bestOf": 3 }],
+            "entrants": entrants,
+            "simulation": { "manualMode": true },
+            "referenceTournamentLink": null,
+        });
+        fs::write(root.join("test_brackets").join("test_bracket_2.json"), bracket.to_string()).unwrap();
+        env::set_var("APP_REPO_ROOT", &root);
+        env::remove_var("STARTGG_SIM_CONFIG_PATH");
+        root
+    });
+    (root.clone(), TURN.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+fn setup_store() -> SharedSetupStore {
+    Arc::new(Mutex::new(SetupStore::bootstrap_from_existing()))
+}
+
+fn test_state() -> SharedTestState {
+    Arc::new(Mutex::new(TestModeState::default()))
+}
+
+fn stream(code: &str) -> SlippiStream {
+    SlippiStream {
+        id: format!("codes:{code}"),
+        p1_tag: Some("Harness".to_string()),
+        p1_code: Some(code.to_string()),
+        ..Default::default()
+    }
+}
+
+/// A first-round set with both entrants in it.
+fn callable_set(state: &StartggSimState) -> u64 {
+    state
+        .sets
+        .iter()
+        .find(|set| set.state == "pending" && set.slots.iter().all(|slot| slot.entrant_id.is_some()))
+        .map(|set| set.id)
+        .expect("bracket has a callable set")
+}
+
+fn audited_actions(action: &str) -> usize {
+    let query = AuditQuery { action: Some(action.to_string()), ..Default::default() };
+    read_audit_log(&query).unwrap().len()
+}
+
+#[test]
+fn setups_are_created_edited_and_deleted() {
+    let (_root, _turn) = scratch_root();
+    let store = setup_store();
+    let history = Arc::new(Mutex::new(OverlayHistory::new()));
+
+    let created = create_setup_slot(&store).unwrap();
+    assert_eq!(created.id, 4);
+    let saved = load_saved_setups().unwrap();
+    assert!(saved.iter().any(|setup| setup.id == 4));
+
+    let renamed = rename_setup_inner(4, "  Stream ".to_string(), &store).unwrap();
+    assert_eq!(renamed.display_name.as_deref(), Some("Stream"));
+    assert!(pin_player_side_inner(4, "P1#000", "middle", &store).is_err());
+    let pinned = pin_player_side_inner(4, "p1#000", "Left", &store).unwrap();
+    assert_eq!(pinned.side_mapping.pinned_side.as_deref(), Some("left"));
+    assert!(set_overlay_refresh_driver_inner(4, OverlayRefreshDriver::Interval { ms: 1 }, &store).is_err());
+    let linked = set_setup_bracket_inner(4, SetupBracket::Linked, &store).unwrap();
+    assert_eq!(linked.bracket, SetupBracket::Linked);
+    assert!(set_setup_bracket_inner(9, SetupBracket::Main, &store).is_err());
+
+    // A setup on stream says so before it's deleted with force.
+    let result = assign_stream(4, stream("P1#000"), Some(false), &store, &test_state()).unwrap();
+    assert!(result.warning.is_none());
+    let mut warned = None;
+    delete_setup_inner(4, true, &store, &history, |warning| warned = Some(warning.clone())).unwrap();
+    assert_eq!(warned.map(|warning| warning.setup_id), Some(4));
+    assert!(!store.lock().unwrap().setups.iter().any(|setup| setup.id == 4));
+    assert!(!load_saved_setups().unwrap().iter().any(|setup| setup.id == 4));
+
+    assert!(assign_stream(3, stream("P2#000"), Some(false), &store, &test_state()).is_ok());
+    let cleared = clear_assignment(3, Some(false), &store).unwrap();
+    assert!(cleared.assigned_stream.is_none());
+}
+
+#[test]
+fn a_setup_on_stream_is_kept_without_force() {
+    let (_root, _turn) = scratch_root();
+    let store = setup_store();
+    let history = Arc::new(Mutex::new(OverlayHistory::new()));
+    assign_stream(2, stream("P3#000"), Some(false), &store, &test_state()).unwrap();
+
+    let mut warned = None;
+    let err = delete_setup_inner(2, false, &store, &history, |warning| warned = Some(warning.clone())).unwrap_err();
+    assert!(err.contains("on stream (Harness)"), "{err}");
+    assert!(warned.is_none());
+    let guard = store.lock().unwrap();
+    let setup = guard.setups.iter().find(|setup| setup.id == 2).expect("setup 2 is kept");
+    assert_eq!(setup.assigned_stream.as_ref().map(|stream| stream.id.as_str()), Some("codes:P3#000"));
+    drop(guard);
+    assert!(load_saved_setups().unwrap().iter().any(|setup| setup.id == 2));
+    clear_assignment(2, Some(false), &store).unwrap();
+}
+
+fn assigned_id(store: &SharedSetupStore, setup_id: u32) -> Option<String> {
+    let guard = store.lock().unwrap();
+    let setup = guard.setups.iter().find(|setup| setup.id == setup_id)?;
+    setup.assigned_stream.as_ref().map(|stream| stream.id.clone())
+}
+
+#[test]
+fn a_stream_is_on_one_setup_at_a_time() {
+    let (_root, _turn) = scratch_root();
+    let store = setup_store();
+    let test_state = test_state();
+
+    assign_stream(1, stream("P4#000"), Some(false), &store, &test_state).unwrap();
+    assign_stream(2, stream("P4#000"), Some(false), &store, &test_state).unwrap();
+    assert_eq!(assigned_id(&store, 1), None);
+    assert_eq!(assigned_id(&store, 2).as_deref(), Some("codes:P4#000"));
+
+    // Taking a stream from a setup that's showing another swaps them.
+    assign_stream(1, stream("P2#000"), Some(false), &store, &test_state).unwrap();
+    let result = assign_stream(1, stream("P4#000"), Some(false), &store, &test_state).unwrap();
+    assert_eq!(assigned_id(&store, 1).as_deref(), Some("codes:P4#000"));
+    assert_eq!(assigned_id(&store, 2).as_deref(), Some("codes:P2#000"));
+    assert!(result.setups.iter().any(|setup| setup.id == 2));
+
+    assert!(assign_stream(9, stream("P1#000"), Some(false), &store, &test_state).is_err());
+    clear_assignment(1, Some(false), &store).unwrap();
+    clear_assignment(2, Some(false), &store).unwrap();
+    assert_eq!(assigned_id(&store, 2), None);
+}
+
+#[test]
+fn a_pinned_character_is_shown_before_any_replay() {
+    let (_root, _turn) = scratch_root();
+    let store = setup_store();
+    let pinned = SlippiStream { p1_character: Some(" Falco ".to_string()), ..stream("P1#000") };
+    assign_stream(3, pinned, Some(false), &store, &test_state()).unwrap();
+
+    let setup = store.lock().unwrap().setups.iter().find(|setup| setup.id == 3).cloned();
+    let config = cached_config().unwrap();
+    let mut cache = OverlayReplayCache::default();
+    let overlay = build_overlay_for_setup(3, setup.as_ref(), None, None, &config, &HashMap::new(), &mut cache);
+    assert_eq!(overlay.p1.character, "Falco");
+    assert_eq!(overlay.p1.connect_code.as_deref(), Some("P1#000"));
+    clear_assignment(3, Some(false), &store).unwrap();
+}
+
+#[test]
+fn setup_settings_are_saved_with_the_setup() {
+    let (_root, _turn) = scratch_root();
+    let store = setup_store();
+    let policy = AutoStopPolicy { action: AutoStopAction::ClearAssignment, grace_secs: 10 };
+
+    assert_eq!(set_setup_auto_stop_inner(1, policy, &store).unwrap().auto_stop, policy);
+    assert_eq!(set_setup_stream_delay_inner(1, 20, &store).unwrap().stream_delay_secs, 20);
+    assert!(set_setup_stream_delay_inner(1, u32::MAX, &store).is_err());
+    assert!(set_setup_auto_stop_inner(9, policy, &store).is_err());
+    let saved = load_saved_setups().unwrap();
+    let setup = saved.iter().find(|setup| setup.id == 1).unwrap();
+    assert_eq!(setup.auto_stop, policy);
+    assert_eq!(setup.stream_delay_secs, 20);
+
+    set_setup_auto_stop_inner(1, AutoStopPolicy::default(), &store).unwrap();
+    set_setup_stream_delay_inner(1, 0, &store).unwrap();
+}
+
+#[test]
+fn sets_are_reported_against_the_sim() {
+    let (root, _turn) = scratch_root();
+    let test_state = test_state();
+
+    let state = startgg_sim_reset_inner(None, &test_state).unwrap();
+    let set_id = callable_set(&state);
+    let resets = audited_actions("resetBracket");
+    assert!(resets >= 1);
+
+    startgg_sim_raw_start_set_inner(set_id, None, None, &test_state).unwrap();
+    assert!(startgg_sim_raw_update_scores_inner(set_id, vec![1], None, None, &test_state).is_err());
+    startgg_sim_raw_update_scores_inner(set_id, vec![1, 0], None, None, &test_state).unwrap();
+
+    // The manual reporter holds the set now; a remote one is turned away
+    // until the lock is released.
+    let remote = Some("remote:runner".to_string());
+    let err = startgg_sim_raw_update_scores_inner(set_id, vec![2, 0], remote.clone(), None, &test_state).unwrap_err();
+    assert!(err.contains("locked"), "{err}");
+    assert!(release_set_report_lock_inner(set_id, remote.clone(), false, &test_state).is_err());
+    assert!(release_set_report_lock_inner(set_id, None, false, &test_state).unwrap());
+    startgg_sim_force_winner_inner(set_id, 0, remote, None, &test_state).unwrap();
+
+    let state = startgg_sim_state_inner(None, None, &test_state).unwrap();
+    let set = state.sets.iter().find(|set| set.id == set_id).unwrap();
+    assert_eq!(set.state, "completed");
+    assert_eq!(set.slots[0].entrant_id, set.winner_id);
+    assert!(audited_actions("updateScores") >= 1);
+    assert!(audited_actions("forceWinner") >= 1);
+    let bracket_path = root.join("test_brackets").join("test_bracket_2.json");
+    assert!(StartggSim::persistence_path(&bracket_path).exists());
+
+    // A reset starts the bracket over.
+    let state = startgg_sim_reset_inner(None, &test_state).unwrap();
+    assert!(state.sets.iter().all(|set| set.state != "completed"));
+    assert_eq!(audited_actions("resetBracket"), resets + 1);
+}
+
+#[test]
+fn the_config_cache_follows_saves_and_reloads() {
+    let (_root, _turn) = scratch_root();
+    let before = reload_config().unwrap();
+    assert!(before.test_flags().fake_bracket);
+
+    let mut next = before.clone();
+    next.offline_mode = !before.offline_mode;
+    save_config_inner(next).unwrap();
+    assert_eq!(cached_config().unwrap().offline_mode, !before.offline_mode);
+    let on_disk: serde_json::Value = serde_json::from_str(&fs::read_to_string(config_path()).unwrap()).unwrap();
+    assert_eq!(on_disk["offlineMode"], json!(!before.offline_mode));
+
+    // An edit made outside the app shows up on reload, not before.
+    let mut edited = on_disk;
+    edited["offlineMode"] = json!(before.offline_mode);
+    fs::write(config_path(), edited.to_string()).unwrap();
+    assert_eq!(cached_config().unwrap().offline_mode, !before.offline_mode);
+    assert_eq!(reload_config().unwrap().offline_mode, before.offline_mode);
+    assert_eq!(cached_config().unwrap().offline_mode, before.offline_mode);
+    assert!(cached_config().unwrap().test_flags().fake_bracket);
+}
@@ -198,6 +198,7 @@ export default function App() {
           bracketDropTarget={bracketHook.bracketDropTarget}
           recentDropSetId={bracketHook.recentDropSetId}
           replaySet={bracketHook.replaySet}
+          setCodes={bracketHook.setCodes}
           replayStreamUpdate={bracketHook.replayStreamUpdate}
           replayStreamStartedAt={bracketHook.replayStreamStartedAt}
           broadcastEntrants={bracketHook.broadcastEntrants}
@@ -29,6 +29,8 @@ type BracketViewProps = {
   bracketDropTarget: number | null;
   recentDropSetId: number | null;
   replaySet: Set<number>;
+  /** Quick-assign code for each ready or running set, by set id. */
+  setCodes: Record<number, number>;
   replayStreamUpdate: ReplayStreamUpdate | null;
   replayStreamStartedAt: number | null;
   broadcastEntrants: StartggSimEntrant[];
@@ -73,6 +75,7 @@ export default function BracketView({
   bracketDropTarget,
   recentDropSetId,
   replaySet,
+  setCodes,
   replayStreamUpdate,
   replayStreamStartedAt,
   broadcastEntrants,
@@ -205,6 +208,11 @@ export default function BracketView({
             <span className="state-pill" data-state={stateData}>
               {stateLabel}
             </span>
+            {setCodes[set.id] !== undefined && (
+              <span className="set-code-pill" title="Quick-assign code">
+                #{setCodes[set.id]}
+              </span>
+            )}
             {hasReplay && (
               <button
                 type="button"
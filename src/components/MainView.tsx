@@ -255,7 +255,7 @@ export default function MainView({
                   <div className={`setup-main ${assigned?.isPlaying && !isOffline && !isNonTourney ? "playing" : isMatchActive ? "match-active" : ""}`}>
                     <div className="setup-header">
                       <div className="setup-name">
-                        {s.name}
+                        {s.displayName || s.name}
                         {isMatchActive && (
                           <span className="setup-match-indicator" title="Match active" />
                         )}
@@ -377,7 +377,7 @@ export default function MainView({
                       <option value="">Assign to setup…</option>
                       {setups.map((setup) => (
                         <option key={setup.id} value={setup.id}>
-                          {setup.name}
+                          {setup.displayName || setup.name}
                         </option>
                       ))}
                     </select>
@@ -67,7 +67,7 @@ export default function SetupDetailsModal({
         {/* Header */}
         <div className="modal-header">
           <div className="setup-modal-title">
-            <h2>{setupDetails.name}</h2>
+            <h2>{setupDetails.displayName || setupDetails.name}</h2>
             <span className={`setup-status-pill ${statusClass}`}>{status}</span>
           </div>
           <button className="icon-button" onClick={closeSetupDetails} aria-label="Close">
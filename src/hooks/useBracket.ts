@@ -15,6 +15,7 @@ import type {
   AppConfig,
   BroadcastPlayerSelection,
   ReplayStreamUpdate,
+  SetCode,
   SpoofReplayResult,
   StartggSimState,
 } from "../types/overlay";
@@ -174,6 +175,7 @@ export function useBracket(deps: UseBracketDeps): UseBracketReturn {
   const [replayStreamStartedAt, setReplayStreamStartedAt] = useState<number | null>(null);
   const [broadcastSelections, setBroadcastSelections] = useState<Record<number, boolean>>({});
   const [isRefreshing, setIsRefreshing] = useState<boolean>(false);
+  const [setCodes, setSetCodes] = useState<Record<number, number>>({});
 
   // ── Refs ──────────────────────────────────────────────────────────────
 
@@ -883,6 +885,22 @@ export function useBracket(deps: UseBracketDeps): UseBracketReturn {
     })();
   }, [isBracketView]);
 
+  // Refresh quick-assign set codes whenever the bracket changes
+  useEffect(() => {
+    if (!isBracketView || !bracketState) {
+      return;
+    }
+    invoke<SetCode[]>("set_codes")
+      .then((codes) => {
+        const next: Record<number, number> = {};
+        for (const entry of codes) {
+          next[entry.setId] = entry.code;
+        }
+        setSetCodes(next);
+      })
+      .catch(() => setSetCodes({}));
+  }, [isBracketView, bracketState]);
+
   // Load replay sets when bracket view or path changes
   useEffect(() => {
     if (!isBracketView) {
@@ -1170,6 +1188,7 @@ export function useBracket(deps: UseBracketDeps): UseBracketReturn {
     broadcastEntrants,
     broadcastActiveCount,
     replaySet,
+    setCodes,
     isRefreshing,
     setBracketStatus,
     applyNormalizedState,
@@ -1,9 +1,11 @@
 import { useState, useRef, useEffect, useMemo, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen, type UnlistenFn } from "@tauri-apps/api/event";
 import { open as openDialog } from "@tauri-apps/plugin-dialog";
 import type {
   AppConfig,
   BracketConfigInfo,
+  ConfigChange,
   StartggSimState,
   StartggLiveSnapshot,
 } from "../types/overlay";
@@ -64,10 +66,58 @@ export function useConfig(
     startggLink: "",
     startggToken: "",
     startggPolling: false,
+    startggReporting: false,
+    startggAutoStartSets: false,
     autoStream: true,
+    autoAssignEnabled: false,
+    autoAssignPriority: "roundImportance",
     testMode: false,
+    testFlags: {
+      fakeBracket: false,
+      fakeStreams: false,
+      fakeReplays: false,
+      fakeReporting: false,
+      fakeConnection: false,
+    },
     testBracketPath: DEFAULT_TEST_BRACKET_PATH,
     autoCompleteBracket: true,
+    assetPackPath: "",
+    ruleset: { bestOf: 3, stages: [] },
+    showPlayerRanks: false,
+    obsPath: "",
+    obsWebsocketUrl: "ws://127.0.0.1:4455",
+    obsWebsocketPassword: "",
+    obsSwitchOnLive: false,
+    slippiAutoLogin: false,
+    slippiNativeSpectate: false,
+    slippiWsUrl: "wss://slippi-broadcast.herokuapp.com/",
+    networkProxyUrl: "",
+    networkProxyBypass: "",
+    offlineMode: false,
+    startggWebhookEnabled: false,
+    startggWebhookBind: "127.0.0.1:17895",
+    startggWebhookSecret: "",
+    playbackTuning: {},
+    playbackFpsAlertSecs: 5,
+    dolphinAutoRelaunch: false,
+    resultCardEnabled: false,
+    resultCardSecs: 15,
+    resultCardSetups: [],
+    replayUpload: {
+      backend: "off",
+      target: "",
+      pathPattern: "{event}/set-{set}",
+      publicBaseUrl: "",
+      region: "us-east-1",
+      accessKey: "",
+      secretKey: "",
+    },
+    venueTimezone: "",
+    disabledFeatures: [],
+    remoteApiEnabled: false,
+    remoteApiBind: "127.0.0.1:17896",
+    remoteTokens: [],
+    overlayWsPort: 17894,
   });
   const [settingsOpen, setSettingsOpen] = useState(false);
   const [configStatus, setConfigStatus] = useState("");
@@ -133,6 +183,8 @@ export function useConfig(
         startggLink: res.startggLink ?? "",
         startggToken: res.startggToken ?? "",
         startggPolling: res.startggPolling ?? false,
+        startggReporting: res.startggReporting ?? false,
+        startggAutoStartSets: res.startggAutoStartSets ?? false,
         autoStream: res.autoStream ?? true,
         testMode: res.testMode ?? false,
         autoCompleteBracket: res.autoCompleteBracket ?? true,
@@ -283,6 +335,27 @@ export function useConfig(
     await resetBracketStateRef.current?.(normalized);
   }
 
+  // Keep the local copy in step with config changes made elsewhere (remote
+  // API, feature flags, new events), so a later save can't write back a
+  // stale config.
+  useEffect(() => {
+    let unlisten: UnlistenFn | null = null;
+
+    listen<ConfigChange>("config-changed", (event) => {
+      setConfig(event.payload.config);
+    })
+      .then((fn) => {
+        unlisten = fn;
+      })
+      .catch(() => {
+        unlisten = null;
+      });
+
+    return () => {
+      if (unlisten) unlisten();
+    };
+  }, []);
+
   // Auto-focus start.gg inputs
   useEffect(() => {
     if (!settingsOpen || !pendingStartggFocus) {
@@ -56,7 +56,7 @@ export function useSetups(isBracketView: boolean): UseSetupsReturn {
 
   const setupOverlayUrl = useMemo(() => {
     if (!setupDetails) return "";
-    return `http://127.0.0.1:17890/?setup=${setupDetails.id}`;
+    return `http://127.0.0.1:17890/?setup=${setupDetails.id}&live=1`;
   }, [setupDetails]);
 
   function clearSetupStatusTimer() {
@@ -162,9 +162,16 @@ export function useSetups(isBracketView: boolean): UseSetupsReturn {
   }
 
   async function removeSetup(id: number) {
+    const setup = setups.find((s) => s.id === id);
+    let force = false;
+    if (setup?.assignedStream) {
+      const label = setup.assignedStream.p1Tag || setup.assignedStream.p1Code || setup.assignedStream.id;
+      force = window.confirm(`Setup ${id} is on stream (${label}). Delete it and close its Dolphin anyway?`);
+      if (!force) return;
+    }
     setPersistentSetupStatus(`Deleting setup ${id}…`);
     try {
-      await invoke("delete_setup", { id });
+      await invoke("delete_setup", { id, force });
       setSetups((prev) => {
         const next = prev.filter((s) => s.id !== id);
         next.sort((a, b) => a.id - b.id);
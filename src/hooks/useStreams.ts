@@ -21,6 +21,9 @@ import {
   findSetForStream,
 } from "../tournamentUtils";
 
+/** Prefix of backend errors from window features this session can't offer. */
+const UNSUPPORTED_SESSION = "Unsupported on this session";
+
 export type UseStreamsReturn = {
   streams: SlippiStream[];
   setStreams: React.Dispatch<React.SetStateAction<SlippiStream[]>>;
@@ -173,10 +176,16 @@ export function useStreams(deps: {
       } catch (e) {
         const msg = e instanceof Error ? e.message : typeof e === "string" ? e : JSON.stringify(e);
         setWindowInfo(null);
-        setWindowStatus(`Window search failed: ${msg}`);
-        setStreams([]);
-        setStreamsStatus("Could not find Slippi Launcher. Launch it, then refresh.");
-        return;
+        if (msg.startsWith(UNSUPPORTED_SESSION)) {
+          // Headless or Wayland-only: there's no window to find, but the
+          // launcher can still be scanned over DevTools.
+          setWindowStatus(msg);
+        } else {
+          setWindowStatus(`Window search failed: ${msg}`);
+          setStreams([]);
+          setStreamsStatus("Could not find Slippi Launcher. Launch it, then refresh.");
+          return;
+        }
       }
       setStreamsStatus("Scanning for streams…");
       try {
@@ -343,7 +352,8 @@ export function useStreams(deps: {
   }
 
   async function applyAutoStreamAssignments() {
-    if (isBracketView || !config.autoStream) return;
+    // The backend engine assigns streams itself when it's on.
+    if (isBracketView || !config.autoStream || config.autoAssignEnabled) return;
     if (autoStreamInFlight.current) return;
     if (!currentStartggState) return;
     autoStreamInFlight.current = true;
@@ -55,6 +55,7 @@ type StartggRawSet = {
   completedAt?: number | null;
   updatedAt?: number | null;
   winnerId?: number | string | null;
+  previewId?: number | null;
   phaseGroup?: { phase?: { id?: string | number; name?: string | null } };
   slots?: StartggRawSlot[];
 };
@@ -69,8 +70,22 @@ type StartggRawSlot = {
   sourceType?: string | null;
   sourceSetId?: number | string | null;
   sourceLabel?: string | null;
+  prereqId?: number | string | null;
+  prereqType?: string | null;
+  prereqPlacement?: number | null;
 };
 
+// Live start.gg responses carry prereq fields instead of the sim's source fields.
+function prereqSourceType(slot: StartggRawSlot): string | null {
+  if (slot.prereqType === "bye") {
+    return "empty";
+  }
+  if (slot.prereqType === "set" && slot.prereqPlacement != null) {
+    return slot.prereqPlacement === 1 ? "winner" : "loser";
+  }
+  return null;
+}
+
 function normalizeNumber(value: unknown): number | null {
   if (typeof value === "number") {
     return Number.isFinite(value) ? value : null;
@@ -82,6 +97,27 @@ function normalizeNumber(value: unknown): number | null {
   return null;
 }
 
+// Mirrors preview_set_id in the sim: start.gg's non-numeric "preview_..."
+// set ids hashed (FNV-1a) into a range real ids never reach.
+const PREVIEW_SET_ID_BASE = 2 ** 52;
+
+function previewSetId(raw: string): number {
+  let hash = 0xcbf29ce484222325n;
+  for (const byte of new TextEncoder().encode(raw)) {
+    hash = ((hash ^ BigInt(byte)) * 0x100000001b3n) & 0xffffffffffffffffn;
+  }
+  return PREVIEW_SET_ID_BASE + Number(hash & BigInt(PREVIEW_SET_ID_BASE - 1));
+}
+
+function normalizeSetId(value: unknown): number | null {
+  const num = normalizeNumber(value);
+  if (num !== null) {
+    return num;
+  }
+  const raw = typeof value === "string" ? value.trim() : "";
+  return raw ? previewSetId(raw) : null;
+}
+
 function normalizeTimestampMs(value: unknown): number | null {
   const num = normalizeNumber(value);
   if (num === null) {
@@ -245,7 +281,8 @@ export function normalizeStartggResponse(raw: StartggRawResponse): StartggSimSta
   const entrantsById = new Map(entrants.map((entrant) => [entrant.id, entrant]));
   const setsNodes = event.sets?.nodes ?? [];
   const sets = setsNodes.map((set, index) => {
-    const id = normalizeNumber(set.id) ?? index + 1;
+    const id = normalizeSetId(set.id) ?? index + 1;
+    const startggId = typeof set.id === "string" && normalizeNumber(set.id) === null ? set.id.trim() || null : null;
     const phaseId = String(set.phaseGroup?.phase?.id ?? phases[0]?.id ?? "phase-1");
     const phaseName = set.phaseGroup?.phase?.name ?? phaseLookup.get(phaseId)?.name ?? "Bracket";
     const round = normalizeNumber(set.round) ?? 0;
@@ -257,8 +294,11 @@ export function normalizeStartggResponse(raw: StartggRawResponse): StartggSimSta
         (entrantId !== null ? entrantsById.get(entrantId) : undefined) ?? null;
       const score = normalizeNumber(slot.standing?.stats?.score?.value);
       const label = slot.standing?.stats?.score?.label ?? null;
-      const sourceSetId = normalizeNumber(slot.sourceSetId);
-      const sourceType = slot.sourceType ?? null;
+      const prereqType = prereqSourceType(slot);
+      const sourceSetId =
+        normalizeSetId(slot.sourceSetId) ??
+        (prereqType === "winner" || prereqType === "loser" ? normalizeSetId(slot.prereqId) : null);
+      const sourceType = slot.sourceType ?? prereqType;
       const sourceLabel = slot.sourceLabel ?? null;
       let result: string | null = null;
       if (label && label.toLowerCase().includes("dq")) {
@@ -282,6 +322,8 @@ export function normalizeStartggResponse(raw: StartggRawResponse): StartggSimSta
     });
     return {
       id,
+      startggId,
+      previewId: normalizeNumber(set.previewId),
       phaseId,
       phaseName,
       round,
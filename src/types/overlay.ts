@@ -1,7 +1,46 @@
+export type SideMappingPolicy = "connectCode" | "port" | "manual";
+
+export type SideMapping = {
+  policy: SideMappingPolicy;
+  pinnedCode?: string | null;
+  pinnedSide?: "left" | "right" | null;
+};
+
+export type OverlayRefreshDriver =
+  | { kind: "live" }
+  | { kind: "fileWatcher" }
+  | { kind: "interval"; ms: number }
+  | { kind: "bracketEvents" }
+  | { kind: "manual" };
+
+export type SetupBracket = "main" | "linked" | "either";
+
+export type AutoStopAction = "off" | "clearAssignment" | "breakScene";
+
+export type AutoStopPolicy = {
+  action: AutoStopAction;
+  graceSecs: number;
+};
+
 export type Setup = {
   id: number;
   name: string;
   assignedStream?: SlippiStream | null;
+  sideMapping?: SideMapping;
+  refreshDriver?: OverlayRefreshDriver;
+  bracket?: SetupBracket;
+  autoStop?: AutoStopPolicy;
+  displayName?: string | null;
+  note?: string | null;
+  stationNumber?: number | null;
+  streamDelaySecs?: number;
+  captureSourceId?: string | null;
+};
+
+export type SetupMetadata = {
+  displayName?: string | null;
+  note?: string | null;
+  stationNumber?: number | null;
 };
 
 export type AssignStreamResult = {
@@ -21,8 +60,15 @@ export type SlippiStream = {
   isPlaying?: boolean | null;
   source?: string | null;
   startggSet?: StartggSimSet | null;
+  p1Character?: string | null;
+  // Launcher DOM id; `id` is built from connect codes when the card has any
+  cardId?: string | null;
+  viewerCount?: number | null;
+  broadcastStatus?: BroadcastStatus | null;
 };
 
+export type BroadcastStatus = "live" | "ended";
+
 export type SlippiWindowInfo = {
   id: number;
   title?: string | null;
@@ -31,6 +77,15 @@ export type SlippiWindowInfo = {
   width: number;
   height: number;
   screen?: number;
+  backend: "x11" | "wayland";
+};
+
+export type TestModeFlags = {
+  fakeBracket: boolean;
+  fakeStreams: boolean;
+  fakeReplays: boolean;
+  fakeReporting: boolean;
+  fakeConnection: boolean;
 };
 
 export type AppConfig = {
@@ -41,10 +96,199 @@ export type AppConfig = {
   startggLink: string;
   startggToken: string;
   startggPolling: boolean;
+  startggReporting: boolean;
+  startggAutoStartSets: boolean;
   autoStream: boolean;
+  autoAssignEnabled: boolean;
+  autoAssignPriority: AutoAssignPriority;
   testMode: boolean;
+  testFlags: TestModeFlags;
   testBracketPath: string;
   autoCompleteBracket: boolean;
+  assetPackPath: string;
+  ruleset: EventRuleset;
+  showPlayerRanks: boolean;
+  obsPath: string;
+  obsWebsocketUrl: string;
+  obsWebsocketPassword: string;
+  obsSwitchOnLive: boolean;
+  slippiAutoLogin: boolean;
+  slippiNativeSpectate: boolean;
+  slippiWsUrl: string;
+  networkProxyUrl: string;
+  networkProxyBypass: string;
+  offlineMode: boolean;
+  startggWebhookEnabled: boolean;
+  startggWebhookBind: string;
+  startggWebhookSecret: string;
+  playbackTuning: Record<string, PlaybackTuning>;
+  playbackFpsAlertSecs: number;
+  dolphinAutoRelaunch: boolean;
+  resultCardEnabled: boolean;
+  resultCardSecs: number;
+  resultCardSetups: number[];
+  replayUpload: ReplayUploadConfig;
+  venueTimezone: string;
+  disabledFeatures: FeatureFlag[];
+  remoteApiEnabled: boolean;
+  remoteApiBind: string;
+  remoteTokens: RemoteToken[];
+  overlayWsPort: number;
+};
+
+export type AutoAssignPriority = "roundImportance" | "seed" | "startedFirst";
+
+export type ProposedGame = {
+  replayPath: string;
+  winnerSlot: number;
+};
+
+export type SetResultProposal = {
+  setId: number;
+  setupId: number;
+  roundLabel: string;
+  players: string[];
+  scores: [number, number];
+  winnerSlot: number;
+  games: ProposedGame[];
+  detectedAtMs: number;
+};
+
+export type StartggReportResult = {
+  setId: number;
+  scores: [number, number];
+  winnerId: number;
+  state: string;
+};
+
+export type EventRuleset = {
+  bestOf: number;
+  stages: string[];
+};
+
+export type EventTemplate = {
+  name: string;
+  assetPackPath: string;
+  ruleset: EventRuleset;
+  setupCount: number;
+  broadcastPlayers: BroadcastPlayerSelection[];
+  archiveFolderPattern: string;
+};
+
+export type CurrentEvent = {
+  template: string;
+  startggLink: string;
+  archiveDir: string;
+  startedAtMs: number;
+};
+
+export type NewEventReport = {
+  event: CurrentEvent;
+  config: AppConfig;
+  setups: Setup[];
+  archivedTo: string;
+  archivedReplays: number;
+  eventReportPath: string | null;
+};
+
+export type AutoAssignEvent = {
+  setupId: number;
+  setId: number;
+  streamId: string;
+  round: string;
+  atMs: number;
+};
+
+export type ReplayUploadBackend = "off" | "s3" | "webDav" | "localShare";
+
+export type ReplayUploadConfig = {
+  backend: ReplayUploadBackend;
+  target: string;
+  pathPattern: string;
+  publicBaseUrl: string;
+  region: string;
+  accessKey: string;
+  secretKey: string;
+};
+
+export type UploadedReplay = {
+  path: string;
+  url: string;
+  bytes: number;
+  sha256: string;
+};
+
+export type SetReplayManifest = {
+  setId: number;
+  setupId?: number | null;
+  roundLabel: string;
+  backend: ReplayUploadBackend;
+  files: UploadedReplay[];
+  uploadedAtMs: number;
+};
+
+export type RemoteRole = "operator" | "bracketRunner";
+
+export type RemoteToken = {
+  name: string;
+  tokenHash: string;
+  role: RemoteRole;
+};
+
+export type NewRemoteToken = {
+  name: string;
+  role: RemoteRole;
+  token: string;
+};
+
+export type RemoteTokenInfo = {
+  name: string;
+  role: RemoteRole;
+};
+
+export type RemoteApiStatus = {
+  enabled: boolean;
+  bindAddr: string;
+  tokens: RemoteTokenInfo[];
+};
+
+export type FeatureFlag =
+  | "obs"
+  | "overlayServer"
+  | "startggPolling"
+  | "autoReport"
+  | "autoSpectate"
+  | "autoStop"
+  | "launcherWatchdog"
+  | "broadcastMonitor";
+
+export type FeatureFlagStatus = {
+  flag: FeatureFlag;
+  label: string;
+  description: string;
+  enabled: boolean;
+};
+
+export type ConfigChange = {
+  origin: string;
+  changed: string[];
+  steps: string[];
+  config: AppConfig;
+  atMs: number;
+};
+
+export type PlaybackTuning = {
+  audioBackend?: string | null;
+  dualCore?: boolean | null;
+  emulationSpeed?: number | null;
+};
+
+export type FramePacingStatus = {
+  setupId: number;
+  fps: number;
+  belowSinceMs?: number | null;
+  alerting: boolean;
+  updatedAtMs: number;
 };
 
 export type StartggSimEvent = {
@@ -86,6 +330,8 @@ export type StartggSimSlot = {
 
 export type StartggSimSet = {
   id: number;
+  startggId?: string | null;
+  previewId?: number | null;
   phaseId: string;
   phaseName: string;
   round: number;
@@ -107,6 +353,8 @@ export type StartggSimState = {
   startedAtMs: number;
   nowMs: number;
   eventLink?: string | null;
+  // Set while the simulated bracket is still loading; the rest is empty
+  warming?: boolean;
 };
 
 export type StartggLiveSnapshot = {
@@ -182,3 +430,538 @@ export type SetupWithSeed = {
   highestSeed?: number | null;
   isAvailable: boolean;
 };
+
+export type ScheduleItem = {
+  id: string;
+  label: string;
+  scheduledMs: number;
+  roundMatch?: string | null;
+  actualMs?: number | null;
+};
+
+export type RunOfShow = {
+  items: ScheduleItem[];
+  behindThresholdMin: number;
+};
+
+export type ScheduleEntry = {
+  id: string;
+  label: string;
+  scheduledMs: number;
+  estimatedMs: number;
+  actualMs?: number | null;
+  status: "upcoming" | "late" | "started";
+  scheduledLocal?: string | null;
+  estimatedLocal?: string | null;
+  actualLocal?: string | null;
+};
+
+export type TimeZoneInfo = {
+  name: string;
+  utcOffsetMinutes: number;
+  abbreviation: string;
+  valid: boolean;
+};
+
+export type ScheduleTimeline = {
+  items: ScheduleEntry[];
+  behindMs: number;
+  behind: boolean;
+  nowMs: number;
+  timezone: TimeZoneInfo;
+};
+
+export type AppMode = "full" | "overlayOnly" | "bracketOnly";
+
+export type DisplaySupport = {
+  session: string;
+  x11: boolean;
+  windowList: boolean;
+  reason: string | null;
+};
+
+export type Capabilities = {
+  mode: AppMode;
+  dolphin: boolean;
+  slippiLauncher: boolean;
+  spectateFolder: boolean;
+  display: DisplaySupport;
+  issues: string[];
+  checkedAtMs: number;
+};
+
+export type SessionSummary = {
+  atMs: number;
+  windowStartMs: number;
+  setsCompleted: number;
+  setsCompletedInWindow: number;
+  setsRemaining: number;
+  avgSetLengthMs?: number | null;
+  waitingSets: number;
+  longestQueueWaitMs?: number | null;
+  setupsInUse: number;
+  setupCount: number;
+  setupUtilization?: number | null;
+};
+
+export type CodeSource = "gameStart" | "byteScan";
+
+export type ReplayCodes = {
+  codes: string[];
+  source: CodeSource;
+};
+
+export type ExhibitionPlayer = {
+  name: string;
+  slippiCode?: string | null;
+};
+
+export type ExhibitionSet = {
+  id: number;
+  label: string;
+  bestOf: number;
+  players: [ExhibitionPlayer, ExhibitionPlayer];
+  scores: [number, number];
+  state: "pending" | "inProgress" | "completed";
+  setupId?: number | null;
+  winnerSlot?: number | null;
+  createdAtMs: number;
+  startedAtMs?: number | null;
+  completedAtMs?: number | null;
+};
+
+export type StageAction = "ban" | "pick";
+
+export type StageStrike = {
+  stage: string;
+  action: StageAction;
+  entrantId?: number | null;
+  atMs: number;
+};
+
+export type GameStageStrikes = {
+  game: number;
+  strikes: StageStrike[];
+  bans: string[];
+  pick?: string | null;
+  remaining: string[];
+};
+
+export type SetStageStrikes = {
+  setId: number;
+  games: GameStageStrikes[];
+  stages: string[];
+};
+
+export type FriendlyReason = "setNotStarted" | "setAlreadyDecided" | "playersMismatch";
+
+export type GameClassification = {
+  setupId: number;
+  replayPath: string;
+  setId?: number | null;
+  friendly: boolean;
+  reasons: FriendlyReason[];
+  overridden: boolean;
+  detectedAtMs: number;
+};
+
+export type PlayerGameWins = {
+  connectCode?: string | null;
+  tag?: string | null;
+  wins: number;
+};
+
+export type SetupScore = {
+  setupId: number;
+  streamId?: string | null;
+  players: PlayerGameWins[];
+  games: string[];
+  updatedAtMs: number;
+};
+
+export type BracketLink = {
+  simConfigPath?: string | null;
+  eventSlug?: string | null;
+  feedPhaseId?: string | null;
+  fedAtMs?: number | null;
+};
+
+export type LinkedEntrant = {
+  id: number;
+  name: string;
+  slippiCode: string;
+  seed?: number | null;
+};
+
+export type BracketLinkStatus = {
+  link?: BracketLink | null;
+  linkedEvent?: StartggSimEvent | null;
+  eliminated: LinkedEntrant[];
+  pendingEntrants: LinkedEntrant[];
+  feedReady: boolean;
+};
+
+export type SubsystemMode = "running" | "slowed" | "paused";
+
+export type SubsystemState = {
+  name: string;
+  mode: SubsystemMode;
+  detail?: string | null;
+};
+
+export type SubsystemStatus = {
+  idle: boolean;
+  idleSinceMs?: number | null;
+  lastExitReason?: string | null;
+  subsystems: SubsystemState[];
+};
+
+export type MirrorLink = {
+  primaryId: number;
+  mirrorId: number;
+  createdAtMs: number;
+};
+
+export type MirrorStopped = {
+  primaryId: number;
+  mirrorId: number;
+  reason: string;
+};
+
+export type SeedSwap = {
+  entrantA: number;
+  entrantB: number;
+};
+
+export type SeedChange = {
+  entrantId: number;
+  name: string;
+  fromSeed: number;
+  toSeed: number;
+};
+
+export type SeedMatchup = {
+  highSeed: number;
+  lowSeed: number;
+  high?: StartggSimEntrant | null;
+  low?: StartggSimEntrant | null;
+};
+
+export type ReseedPreview = {
+  live: boolean;
+  swaps: SeedSwap[];
+  entrants: StartggSimEntrant[];
+  changes: SeedChange[];
+  matchups: SeedMatchup[];
+  started: boolean;
+};
+
+export type ReseedReport = {
+  preview: ReseedPreview;
+  text: string;
+};
+
+export type DuplicateReason = "sameConnectCode" | "sameTag" | "similarTag";
+
+export type AuditedEntrant = {
+  id: number;
+  name: string;
+  seed: number;
+  slippiCode?: string | null;
+};
+
+export type EntrantCluster = {
+  entrants: AuditedEntrant[];
+  reasons: DuplicateReason[];
+};
+
+export type EntrantAuditReport = {
+  entrantCount: number;
+  clusters: EntrantCluster[];
+  missingCodes: AuditedEntrant[];
+  invalidCodes: AuditedEntrant[];
+  started: boolean;
+  text: string;
+  generatedAtMs: number;
+};
+
+export type JobState = "running" | "done" | "failed" | "cancelled";
+
+export type JobProgress = {
+  id: string;
+  kind: string;
+  label: string;
+  percent?: number | null;
+  message?: string | null;
+  cancellable: boolean;
+  state: JobState;
+  startedAtMs: number;
+  updatedAtMs: number;
+};
+
+export type FunFact = {
+  kind: "mostPickedStage" | "commonMatchup" | "fastestSweep";
+  title: string;
+  detail: string;
+};
+
+export type SetupDeleteWarning = {
+  setupId: number;
+  streamId: string;
+  label: string;
+};
+
+export type LauncherAlertStage = "crashed" | "recovered" | "recoveryFailed";
+
+export type LauncherAlert = {
+  stage: LauncherAlertStage;
+  setupIds: number[];
+  message: string;
+  atMs: number;
+};
+
+export type SetupProcessState = "exited" | "crashed" | "relaunched" | "relaunchFailed";
+
+export type SetupProcessStatus = {
+  setupId: number;
+  state: SetupProcessState;
+  exitCode?: number | null;
+  signal?: number | null;
+  message: string;
+  atMs: number;
+};
+
+export type ObsStatus = {
+  url: string;
+  obsVersion: string;
+  websocketVersion: string;
+  programScene: string;
+};
+
+export type ObsSceneList = {
+  programScene: string;
+  scenes: string[];
+};
+
+export type ObsSetupBinding = {
+  setupId: number;
+  scene: string;
+  source: string;
+  createdScene: boolean;
+  renamedFrom?: string | null;
+  createdSource: boolean;
+};
+
+export type CaptureSourceKind = "v4l2" | "obs";
+
+export type CaptureSource = {
+  id: string;
+  label: string;
+  kind: CaptureSourceKind;
+  device: string;
+};
+
+export type CommentaryState = {
+  name: string;
+  handle?: string | null;
+  active?: boolean | null;
+  twitter?: string | null;
+  pronouns?: string | null;
+};
+
+export type CommentatorBoard = {
+  global: CommentaryState[];
+  setups: Record<number, CommentaryState[]>;
+};
+
+export type SetCode = {
+  code: number;
+  setId: number;
+  roundLabel: string;
+  p1?: string | null;
+  p2?: string | null;
+  state: string;
+};
+
+export type QuickAssignResult = {
+  set: SetCode;
+  setups: Setup[];
+  warning?: string | null;
+};
+
+export type StreamAvailability = "playing" | "listed" | "missing" | "noCode";
+
+export type PlayerReadiness = {
+  entrantId?: number | null;
+  name?: string | null;
+  connectCode?: string | null;
+  availability: StreamAvailability;
+  streamId?: string | null;
+};
+
+export type SetReadyStatus = {
+  setId: number;
+  roundLabel: string;
+  players: PlayerReadiness[];
+  streamReady: boolean;
+  checkedAtMs: number;
+};
+
+export type PromptRequest = {
+  text: string;
+  kind?: string | null;
+  priority?: number | null;
+  ttlMs?: number | null;
+  setupId?: number | null;
+  meter?: number | null;
+};
+
+export type CrowdPrompt = {
+  id: number;
+  text: string;
+  kind?: string | null;
+  priority: number;
+  setupId?: number | null;
+  meter?: number | null;
+  ttlMs: number;
+  queuedAtMs: number;
+  shownAtMs?: number | null;
+  expiresAtMs?: number | null;
+};
+
+export type CrowdPromptState = {
+  current?: CrowdPrompt | null;
+  queued: CrowdPrompt[];
+};
+
+export type ForecastPlayer = {
+  entrantId: number;
+  name: string;
+  reach: number;
+  win: number;
+};
+
+export type SetForecast = {
+  setId: number;
+  roundLabel: string;
+  phaseName: string;
+  state: string;
+  etaMs: number;
+  endMs: number;
+  players: ForecastPlayer[];
+};
+
+export type BracketForecast = {
+  eventName: string;
+  sets: SetForecast[];
+  basis: "ratings" | "seeds";
+  generatedAtMs: number;
+};
+
+export type BroadcastEndReason = "ended" | "gone";
+
+export type BroadcastEnded = {
+  setupId: number;
+  streamId: string;
+  label: string;
+  reason: BroadcastEndReason;
+  lastViewerCount?: number | null;
+  atMs: number;
+};
+
+export type AutoCheck =
+  | "isoVerified"
+  | "obsConnected"
+  | "overlayReachable"
+  | "startggToken"
+  | "spectateFolderWritable";
+
+export type ChecklistItem = {
+  id: string;
+  label: string;
+  check?: AutoCheck | null;
+  hint?: string | null;
+};
+
+export type Checklist = {
+  items: ChecklistItem[];
+};
+
+export type CheckStatus = "pass" | "fail" | "manual";
+
+export type ChecklistResult = {
+  id: string;
+  label: string;
+  status: CheckStatus;
+  automated: boolean;
+  message?: string | null;
+  hint?: string | null;
+};
+
+export type ChecklistRun = {
+  items: ChecklistResult[];
+  passed: number;
+  failed: number;
+  manual: number;
+  ranAtMs: number;
+};
+
+export type CountdownRequest = {
+  label: string;
+  durationSecs?: number | null;
+  endsAtMs?: number | null;
+  setupId?: number | null;
+  setId?: number | null;
+};
+
+export type CountdownUpdate = {
+  label?: string | null;
+  durationSecs?: number | null;
+  endsAtMs?: number | null;
+  addSecs?: number | null;
+};
+
+export type Countdown = {
+  id: number;
+  label: string;
+  setupId?: number | null;
+  setId?: number | null;
+  endsAtMs: number;
+  remainingMs: number;
+  finished: boolean;
+};
+
+export type CountdownBoardState = {
+  countdowns: Countdown[];
+  nowMs: number;
+};
+
+export type SetResultPlayer = {
+  tag: string;
+  connectCode?: string | null;
+  score: number;
+  characters: string[];
+};
+
+export type SetResultSnapshot = {
+  setupId: number;
+  setId: number;
+  tournament?: string | null;
+  round: string;
+  bestOf: number;
+  p1: SetResultPlayer;
+  p2: SetResultPlayer;
+  winner?: string | null;
+  startedAtMs?: number | null;
+  completedAtMs?: number | null;
+  startedAtLocal?: string | null;
+  completedAtLocal?: string | null;
+  durationMs?: number | null;
+  replayUrls: string[];
+};
+
+export type ResultCardState = {
+  card?: SetResultSnapshot | null;
+  shownAtMs: number;
+  hideAtMs: number;
+};